@@ -0,0 +1,118 @@
+//! Benchmarks for the planning logic behind stacks and restacks, and for status
+//!
+//! Run them with `cargo xtask bench`, which can also save a baseline and compare against it.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sage::git::{stack, status};
+
+/// Branch counts the stack benchmarks run against
+const SIZES: &[usize] = &[100, 1_000, 5_000];
+/// Branches in each stack of the generated repository
+const STACK_DEPTH: usize = 10;
+/// Files in the repository status is benchmarked against
+const STATUS_FILES: usize = 2_000;
+
+/// `git config --get-regexp` output for `count` branches, in stacks of STACK_DEPTH on main,
+/// with every third branch getting a sibling so stacks fork too
+fn relations_output(count: usize) -> String {
+    let mut output = String::new();
+    for index in 0..count {
+        let parent = if index % STACK_DEPTH == 0 {
+            "main".to_string()
+        } else if index % 3 == 0 {
+            format!("b{}", index - 2)
+        } else {
+            format!("b{}", index - 1)
+        };
+        output.push_str(&format!("branch.b{}.sage-parent {}\n", index, parent));
+    }
+    output
+}
+
+fn bench_stack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stack");
+    for &count in SIZES {
+        let output = relations_output(count);
+        let relations = stack::parse_relations(&output);
+        // The top of the last stack walks all the way down before collecting
+        let top = format!("b{}", count - 1);
+
+        group.bench_with_input(BenchmarkId::new("parse_relations", count), &output, |b, output| {
+            b.iter(|| stack::parse_relations(black_box(output)))
+        });
+        group.bench_with_input(BenchmarkId::new("build_stack", count), &relations, |b, relations| {
+            b.iter(|| stack::build_stack(black_box(&top), black_box(relations), "main"))
+        });
+    }
+    group.finish();
+}
+
+fn bench_restack_plan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("restack_plan");
+    for &count in SIZES {
+        let relations = stack::parse_relations(&relations_output(count));
+        // Restacking from trunk plans every branch
+        group.bench_with_input(BenchmarkId::new("descendants", count), &relations, |b, relations| {
+            b.iter(|| stack::collect_descendants(black_box("main"), black_box(relations)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_status(c: &mut Criterion) {
+    let dir = status_repo();
+    std::env::set_current_dir(&dir).expect("Failed to enter the benchmark repository");
+
+    let mut group = c.benchmark_group("status");
+    group.bench_function("status", |b| b.iter(|| status::status().expect("status failed")));
+    group.bench_function("lightweight_status", |b| {
+        b.iter(|| status::lightweight_status().expect("lightweight status failed"))
+    });
+    group.finish();
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Create a repository of STATUS_FILES committed files, with some of them modified, staged
+/// or deleted and as many untracked again
+fn status_repo() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sage-bench-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).expect("Failed to create the benchmark repository");
+
+    git(&dir, &["init", "--quiet", "--initial-branch", "main"]);
+    for index in 0..STATUS_FILES {
+        write(&dir, &format!("src/file{}.txt", index), "original\n");
+    }
+    git(&dir, &["add", "."]);
+    git(&dir, &["-c", "user.name=bench", "-c", "user.email=bench@example.com", "commit", "--quiet", "-m", "files"]);
+
+    for index in 0..STATUS_FILES {
+        let path = format!("src/file{}.txt", index);
+        match index % 10 {
+            0 | 2 => write(&dir, &path, "modified\n"),
+            1 => std::fs::remove_file(dir.join(&path)).expect("Failed to delete a file"),
+            _ => {}
+        }
+        write(&dir, &format!("untracked{}.txt", index), "new\n");
+    }
+    git(&dir, &["add", "src/file2.txt", "untracked0.txt"]);
+
+    dir
+}
+
+fn write(dir: &Path, path: &str, contents: &str) {
+    std::fs::write(dir.join(path), contents).expect("Failed to write a file");
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git").args(args).current_dir(dir).status().expect("Failed to run git");
+    assert!(status.success(), "git {} failed", args.join(" "));
+}
+
+criterion_group!(benches, bench_stack, bench_restack_plan, bench_status);
+criterion_main!(benches);
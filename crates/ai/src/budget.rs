@@ -0,0 +1,36 @@
+//! Keeping prompts within what a model accepts
+//!
+//! Sizes are counted in characters, which overestimates tokens for English and code, so a prompt
+//! that fits here fits the model.
+
+/// The most a single prompt may hold
+pub const MAX_TOKENS: usize = 1_048_576;
+
+/// Marks text that was cut short to fit
+const TRUNCATED: &str = "\n[diff truncated]";
+
+/// room returns how much of the budget is left once `prompt` (without the text it's built
+/// around) is sent
+pub fn room(prompt: &str) -> usize {
+    MAX_TOKENS.saturating_sub(prompt.chars().count())
+}
+
+/// fit cuts `text` down to `room` characters, marking where it was cut
+pub fn fit(text: &str, room: usize) -> String {
+    match text.char_indices().nth(room) {
+        Some((end, _)) => format!("{}{}", &text[..end], TRUNCATED),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("short", 10), "short");
+        assert_eq!(fit("ünïcode", 3), "ünï\n[diff truncated]");
+        assert_eq!(room(&"x".repeat(MAX_TOKENS + 1)), 0);
+    }
+}
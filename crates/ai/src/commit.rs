@@ -0,0 +1,50 @@
+use anyhow::Result;
+use crate::{budget, prompts, Provider};
+
+/// generate writes a commit message for `diff`
+pub async fn generate(diff: &str) -> Result<String> {
+    generate_with(&*crate::default_provider()?, diff).await
+}
+
+/// generate_with writes a commit message for `diff` with a given provider
+pub async fn generate_with(provider: &dyn Provider, diff: &str) -> Result<String> {
+    let diff = budget::fit(diff, budget::room(&prompts::commit_message_prompt("")));
+    let res = provider.complete(&prompts::commit_message_prompt(&diff)).await?;
+    Ok(tidy(&res))
+}
+
+/// tidy cleans up a commit message the AI wrote
+pub fn tidy(message: &str) -> String {
+    // Remove surrounding backticks if present
+    let message = message.trim();
+    if message.starts_with("```") && message.ends_with("```") {
+        message.trim_start_matches("```").trim_end_matches("```").trim().to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BoxFuture;
+
+    /// Answers every prompt the same way
+    struct Canned(&'static str);
+
+    impl Provider for Canned {
+        fn name(&self) -> &str {
+            "canned"
+        }
+
+        fn complete<'a>(&'a self, _prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+            Box::pin(async move { Ok(self.0.to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_strips_code_fences() {
+        let message = generate_with(&Canned("```\nfeat: add answer\n```"), "diff").await.unwrap();
+        assert_eq!(message, "feat: add answer");
+    }
+}
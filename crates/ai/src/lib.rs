@@ -0,0 +1,24 @@
+//! AI for sage: providers, the prompts sage sends them and keeping those prompts within budget
+//!
+//! Anything that wants to ask a model something goes through a [`Provider`]. The trait is object
+//! safe, so callers can hold a `Box<dyn Provider>` and tests can swap in their own. [`ask`] uses
+//! the provider configured in the environment.
+
+pub mod budget;
+pub mod commit;
+pub mod prompts;
+pub mod provider;
+
+use anyhow::Result;
+
+pub use provider::{BoxFuture, OpenAi, Provider};
+
+/// default_provider returns the provider to use, configured from the environment
+pub fn default_provider() -> Result<Box<dyn Provider>> {
+    Ok(Box::new(OpenAi::from_env()?))
+}
+
+/// Asks the AI with a prompt
+pub async fn ask(prompt: &str) -> Result<String> {
+    default_provider()?.complete(prompt).await
+}
@@ -0,0 +1,185 @@
+//! Prompts used for AI-powered features
+
+/// Prompt for generating commit messages
+pub fn commit_message_prompt(diff: &str) -> String {
+    let prefix = r#"
+    You are a helpful git commit message generator. Your task is to analyze the following code changes and generate a clear, meaningful commit message that follows the Conventional Commits specification.
+
+Guidelines:
+1. Use one of these types:
+   - feat: A new feature
+   - fix: A bug fix
+   - docs: Documentation changes
+   - style: Code style changes (formatting, missing semi-colons, etc)
+   - refactor: Code changes that neither fix a bug nor add a feature
+   - test: Adding or modifying tests
+   - ci: Changes to CI/CD configuration and scripts
+   - chore: Changes to build process or auxiliary tools
+
+2. Format: <type>: <description>
+   Examples:
+   - feat: add user authentication system
+   - fix: resolve null pointer in data processing
+   - ci: update GitHub Actions workflow
+
+3. Analyze the diff carefully:
+   - Look for function/method additions or modifications
+   - Identify bug fixes from error handling changes
+   - Note any test additions or modifications
+   - Consider impact on existing functionality
+   - Changes in .github/workflows/ directory should use 'ci' type
+   - Changes to CI/CD pipeline configurations should use 'ci' type
+
+4. Keep the message:
+   - Concise but informative (ideally under 72 characters)
+   - Focused on WHAT changed and WHY
+   - In imperative mood ("add" not "added")
+   - Without unnecessary technical details
+
+Code changes to analyze:
+    "#;
+
+    let static_footer = "Respond with ONLY the commit message, no additional text or formatting.";
+    
+    format!("{prefix}{diff}{static_footer}")
+}
+
+/// Prompt for generating pull request descriptions
+pub fn pr_description_prompt(title: &str, commit_log: &str) -> String {
+    format!(
+        r#"You are writing a GitHub pull request description for a change with the title: "{}".
+        
+        Here's information about the commits in this PR:
+        ```
+        {}
+        ```
+        
+        Follow these guidelines for an effective PR description:
+        
+        1. Start with a brief summary of what this PR achieves (1-2 sentences).
+        2. Explain the problem this PR solves and why it's important.
+        3. Highlight key changes or new features introduced.
+        4. If applicable, mention any testing performed or areas that would benefit from extra review.
+        5. If there are any breaking changes, dependencies, or deployment considerations, note them.
+        
+        Format your description professionally, using proper Markdown formatting with headers and lists where appropriate.
+        Be concise yet thorough - aim for clarity and completeness.
+        
+        Your response should ONLY include the PR description text, no additional explanations or comments."#,
+        title,
+        commit_log
+    )
+}
+
+/// Prompt for generating the cover letter of an emailed patch series
+pub fn cover_letter_prompt(commit_log: &str, diffstat: &str) -> String {
+    format!(
+        r#"You are writing the cover letter for a patch series that will be sent to a project mailing list.
+
+        Commits in the series, oldest first:
+        ```
+        {}
+        ```
+
+        Diffstat for the whole series:
+        ```
+        {}
+        ```
+
+        Follow these guidelines:
+
+        1. The first line is the subject of the series: short, imperative, no trailing period, no "[PATCH]" prefix.
+        2. Leave one blank line after the subject.
+        3. Then write plain-text paragraphs (no Markdown) explaining the motivation for the series and how the patches fit together.
+        4. Wrap lines at 72 characters, as is customary on mailing lists.
+
+        Your response should ONLY include the subject line and the body, no additional explanations or comments."#,
+        commit_log,
+        diffstat
+    )
+}
+
+/// Prompt for reviewing the changes on a branch before a pull request is opened
+pub fn review_prompt(commit_log: &str, diff: &str) -> String {
+    format!(
+        r#"You are an experienced engineer reviewing a branch before its pull request is opened.
+
+        Commits on the branch:
+        ```
+        {}
+        ```
+
+        Changes on the branch:
+        ```diff
+        {}
+        ```
+
+        Follow these guidelines:
+
+        1. Point out bugs, missed edge cases, error handling gaps and security problems first.
+        2. Then mention anything that would confuse a reviewer: unclear names, missing tests, leftover debugging code.
+        3. Refer to files and lines from the diff, and keep each point short.
+        4. Skip style nits a formatter or linter would catch, and don't praise the change.
+        5. If there is nothing worth raising, say so in one sentence.
+
+        Your response should ONLY include the review as a Markdown list, no additional explanations or comments."#,
+        commit_log,
+        diff
+    )
+}
+
+/// Prompt for explaining a commit, a range of commits or something sage did to the branches
+pub fn explain_prompt(facts: &str, diff: &str) -> String {
+    format!(
+        r#"You are an experienced engineer explaining a change to a teammate who hasn't seen it.
+
+        What is known about it:
+        ```
+        {}
+        ```
+
+        The changes themselves:
+        ```diff
+        {}
+        ```
+
+        Follow these guidelines:
+
+        1. Start with one or two sentences in plain English saying what changed.
+        2. Then say why it might matter: behaviour that changes, who or what it affects, and any risk worth checking.
+        3. Only use what is shown above; if the reason for the change isn't clear from it, say so instead of guessing.
+        4. Keep it short, with no headings, and don't repeat the facts back line by line.
+
+        Your response should ONLY include the explanation, no additional comments."#,
+        facts,
+        diff
+    )
+}
+
+/// Prompt for turning a request in plain English into sage commands
+pub fn intent_prompt(intent: &str, commands: &str) -> String {
+    format!(
+        r#"You translate what a developer asks for into commands for sage, a git CLI.
+
+        The sage commands available:
+        ```
+        {}
+        ```
+
+        The developer asked:
+        ```
+        {}
+        ```
+
+        Follow these guidelines:
+
+        1. Reply with the sage commands to run, in order, one per line, each starting with `sage `.
+        2. Only use the commands listed above with the flags they take; never use git or any other program, pipes, redirects or `&&`.
+        3. Quote an argument with double quotes when it contains spaces.
+        4. Use as few commands as it takes. If the request can't be done with these commands, reply with the single line `none`.
+
+        Your response should ONLY include the commands, no numbering, code fences or explanations."#,
+        commands,
+        intent
+    )
+}
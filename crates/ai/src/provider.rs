@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Context, Result};
+use openai_api_rs::v1::{api::OpenAIClient, chat_completion::{self, ChatCompletionRequest}};
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+
+/// A boxed future, so [`Provider`] stays usable as a trait object
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Something that can answer a prompt
+pub trait Provider: Send + Sync {
+    /// name identifies the provider in messages, e.g. `openai`
+    fn name(&self) -> &str;
+
+    /// complete returns the whole answer to `prompt`
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>>;
+
+    /// stream passes the answer to `on_chunk` piece by piece as it arrives, then returns all of
+    /// it. Providers that can't stream hand over the whole answer as a single piece.
+    fn stream<'a>(&'a self, prompt: &'a str, on_chunk: &'a mut (dyn FnMut(&str) + Send)) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let answer = self.complete(prompt).await?;
+            on_chunk(&answer);
+            Ok(answer)
+        })
+    }
+}
+
+/// OpenAI's chat completions
+pub struct OpenAi {
+    api_key: String,
+    model: String,
+}
+
+impl OpenAi {
+    /// Model used unless another is given
+    pub const DEFAULT_MODEL: &'static str = "o4-mini";
+
+    pub fn new(api_key: impl Into<String>, model: impl Into<String>) -> OpenAi {
+        OpenAi { api_key: api_key.into(), model: model.into() }
+    }
+
+    /// from_env reads the API key from OPENAI_API_KEY
+    pub fn from_env() -> Result<OpenAi> {
+        let api_key = env::var("OPENAI_API_KEY").context("Failed to get OPENAI_API_KEY environment variable")?;
+        Ok(OpenAi::new(api_key, OpenAi::DEFAULT_MODEL))
+    }
+}
+
+impl Provider for OpenAi {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let mut client = OpenAIClient::builder()
+                .with_api_key(&self.api_key)
+                .build()
+                .map_err(|e| anyhow!("Failed to build OpenAI client: {}", e))?;
+
+            let req = ChatCompletionRequest::new(
+                self.model.clone(),
+                vec![chat_completion::ChatCompletionMessage {
+                    role: chat_completion::MessageRole::user,
+                    content: chat_completion::Content::Text(String::from(prompt)),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                }],
+            );
+
+            let result = client.chat_completion(req).await.context("Failed to get chat completion")?;
+
+            // Ensure we have choices
+            if result.choices.is_empty() {
+                return Err(anyhow!("No choices returned from API"));
+            }
+
+            match &result.choices[0].message.content {
+                Some(content) => Ok(content.to_string()),
+                None => Err(anyhow!("No content in the response message")),
+            }
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use inquire::Password;
+use std::io::{self, IsTerminal, Read};
+use std::time::{Duration, Instant};
+
+use crate::{gh::auth::{self, DevicePoll, Source}, ui::{self, accessible::Mark, theme, ColorizeExt}};
+
+/// login checks a personal access token and saves it in the keychain.
+/// The token is read from stdin when `with_token` is set, and prompted for otherwise.
+pub async fn login(with_token: bool) -> Result<()> {
+    let token = if with_token || !io::stdin().is_terminal() {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        Password::new("GitHub token:")
+            .without_confirmation()
+            .with_help_message("Create one at https://github.com/settings/tokens with the repo and read:org scopes")
+            .prompt()?
+    };
+
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(anyhow!("No token given"));
+    }
+
+    save(token).await
+}
+
+/// login_web signs in through GitHub's device flow in the browser and saves the resulting token
+pub async fn login_web() -> Result<()> {
+    let client_id = auth::client_id()?;
+    let code = auth::request_device_code(&client_id).await?;
+
+    println!("First copy your one-time code: {}", code.user_code.sage().bold());
+    println!("Then approve sage at {}", code.verification_uri.url());
+    if let Err(e) = ui::open_in_browser(&code.verification_uri) {
+        println!("{} Could not open the browser: {}", "WARNING:".yellow(), e);
+    }
+    println!("{}", "Waiting for you to finish in the browser...".gray());
+
+    let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = code.interval.max(1);
+    loop {
+        if Instant::now() >= deadline {
+            return Err(anyhow!("The login code expired. Run 'sage auth login --web' again"));
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        match auth::poll_device_token(&client_id, &code.device_code).await? {
+            DevicePoll::Token(token) => return save(&token).await,
+            DevicePoll::Pending => {}
+            DevicePoll::SlowDown(seconds) => interval = seconds.max(interval + 5),
+        }
+    }
+}
+
+/// save verifies a token and stores it in the keychain
+pub async fn save(token: &str) -> Result<()> {
+    let account = auth::verify(token).await?;
+    auth::store(token)?;
+
+    println!("✨ Logged in to GitHub as {}", account.login.sage());
+    println!("   Token {} saved to the keychain", auth::redact(token).gray());
+    warn_if_shadowed();
+
+    Ok(())
+}
+
+/// status shows where sage looks for a token, which one it uses, and who it belongs to
+pub async fn status() -> Result<()> {
+    let sources = auth::sources();
+    let mut active = None;
+
+    println!("GitHub token sources, in order:");
+    for source in &sources {
+        match source.lookup() {
+            Some(token) => {
+                let in_use = active.is_none();
+                println!(
+                    "  {} {:<12} {}{}",
+                    theme::styled(Mark::Passing),
+                    source.to_string(),
+                    auth::redact(token.trim()).gray(),
+                    if in_use { " (in use)".sage().to_string() } else { String::new() }
+                );
+                if in_use {
+                    active = Some(token);
+                }
+            }
+            None => println!("  {} {:<12} {}", "-".gray(), source.to_string(), "no token".gray()),
+        }
+    }
+
+    let Some(token) = active else {
+        println!("\n{} No GitHub token found. Run 'sage auth login' to add one", "WARNING:".yellow());
+        return Ok(());
+    };
+
+    let account = auth::verify(token.trim()).await?;
+    println!("\nLogged in as {}", account.login.sage());
+    if !account.scopes.is_empty() {
+        println!("Scopes: {}", account.scopes.join(", "));
+        if !account.scopes.iter().any(|scope| scope == "repo") {
+            println!(
+                "{} The token is missing the repo scope, so private repositories and pull request changes won't work",
+                "WARNING:".yellow()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// logout removes the token saved in the keychain
+pub fn logout() -> Result<()> {
+    if !auth::forget()? {
+        println!("No GitHub token is saved in the keychain");
+        return Ok(());
+    }
+
+    println!("✨ Removed the GitHub token from the keychain");
+    if let Some(source) = auth::sources().into_iter().find(|source| source.lookup().is_some()) {
+        println!("   Sage will now use the token from the {}", source);
+    }
+
+    Ok(())
+}
+
+/// Let the user know when a token earlier in the lookup order will win over the keychain
+fn warn_if_shadowed() {
+    let earlier = auth::sources()
+        .into_iter()
+        .take_while(|source| *source != Source::Keychain)
+        .find(|source| source.lookup().is_some());
+
+    if let Some(source) = earlier {
+        println!(
+            "{} A token from the {} is used before the keychain. Change the order with 'sage config set auth.sources'",
+            "WARNING:".yellow(),
+            source
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use inquire::Confirm;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{app::crash, config::{self, features::{self, Feature}}, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// Where to file the report
+const ISSUES_URL: &str = "https://github.com/crazywolf132/sage-rs/issues/new";
+
+/// bug_report bundles the environment and the latest crash reports into a single archive to
+/// attach to an issue, once the user has seen what goes in it
+pub fn bug_report(output: Option<&Path>, yes: bool) -> Result<()> {
+    let environment = crash::redact(&format!("{}\n{}", crash::environment(), settings()));
+    // The latest panic, and the last failed command
+    let reports = crash::reports()?;
+    let latest_panic = reports.iter().find(|path| !path.ends_with(crash::LAST_ERROR_FILE));
+    let last_error = reports.iter().find(|path| path.ends_with(crash::LAST_ERROR_FILE));
+    let included = latest_panic.into_iter().chain(last_error).collect::<Vec<_>>();
+
+    println!("The bug report will contain:");
+    println!("  {} {}", accessible::mark(Mark::Bullet).sage(), "environment.txt".sage());
+    for line in environment.lines() {
+        println!("      {}", line.gray());
+    }
+    for report in &included {
+        println!("  {} {}", accessible::mark(Mark::Bullet).sage(), report.display().to_string().sage());
+    }
+    if included.is_empty() {
+        println!("  {}", "No crash reports have been saved".gray());
+    }
+    println!("Tokens, passwords in URLs and your home directory have been removed. Look the files over before sharing them.");
+
+    if !yes && !Confirm::new("Create the bug report?").with_default(true).prompt()? {
+        return Ok(());
+    }
+
+    let output = match output {
+        Some(output) => output.to_path_buf(),
+        None => PathBuf::from(format!("sage-bug-report-{}.tar.gz", Local::now().format("%Y%m%d-%H%M%S"))),
+    };
+
+    let dir = std::env::temp_dir().join(format!("sage-bug-report-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let result = bundle(&dir, &environment, &included, &output);
+    let _ = fs::remove_dir_all(&dir);
+    result?;
+
+    println!("✨ Wrote {}", output.display().to_string().sage());
+    println!("Attach it to an issue at {}", ISSUES_URL.url());
+    Ok(())
+}
+
+/// Describe sage's own settings: which config keys are set (not their values) and features on
+fn settings() -> String {
+    let keys = config::load().map(|values| values.into_keys().collect::<Vec<_>>().join(", ")).unwrap_or_default();
+    let enabled = Feature::ALL
+        .iter()
+        .filter(|feature| features::enabled(**feature))
+        .map(|feature| feature.name())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "in a repository: {}\nconfig keys set: {}\nexperimental features on: {}",
+        git::repo::is_repo().unwrap_or(false),
+        if keys.is_empty() { "none" } else { &keys },
+        if enabled.is_empty() { "none" } else { &enabled }
+    )
+}
+
+fn bundle(dir: &Path, environment: &str, reports: &[&PathBuf], output: &Path) -> Result<()> {
+    fs::write(dir.join("environment.txt"), environment)?;
+    for report in reports {
+        // Reports are redacted when written; again here in case they were edited since
+        let contents = crash::redact(&fs::read_to_string(report)?);
+        fs::write(dir.join(report.file_name().unwrap_or_default()), contents)?;
+    }
+
+    let output_path = std::env::current_dir()?.join(output);
+    let result = Command::new("tar")
+        .arg("-czf")
+        .arg(&output_path)
+        .arg("-C")
+        .arg(dir)
+        .arg(".")
+        .output()?;
+    if !result.status.success() {
+        return Err(anyhow!("Failed to create {}: {}", output.display(), String::from_utf8_lossy(&result.stderr)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use colored::Colorize;
+use inquire::Confirm;
+
+use crate::{config, errors, git, ledger::checkpoint::{self, Checkpoint}, ui::ColorizeExt};
+
+/// create takes a checkpoint of every branch and the working tree
+pub fn create(label: &str) -> Result<Checkpoint> {
+    let now = Utc::now();
+    let head = git::branch::current().ok().filter(|branch| branch != "HEAD");
+
+    let checkpoint = Checkpoint {
+        id: now.timestamp_millis(),
+        created_at: now,
+        label: label.to_string(),
+        head,
+        branches: git::checkpoint::branch_tips()?.into_iter().collect(),
+        parents: git::stack::relations()?.into_iter().collect(),
+        worktree: git::checkpoint::stash_create()?,
+    };
+    checkpoint::add(checkpoint.clone())?;
+    Ok(checkpoint)
+}
+
+/// auto takes a checkpoint before a risky operation, unless checkpoint.auto is off. A failure
+/// only warns, so it never stops the operation itself.
+pub fn auto(label: &str) {
+    if !config::get_bool("checkpoint.auto", true) {
+        return;
+    }
+    if let Err(e) = create(label) {
+        println!("{} Could not take a checkpoint: {}", "WARNING:".yellow(), e);
+    }
+}
+
+/// checkpoint is `sage checkpoint`: take a checkpoint now, or list them
+pub fn checkpoint(message: Option<&str>, list: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    if list {
+        return list_checkpoints();
+    }
+
+    let checkpoint = create(message.unwrap_or("manual"))?;
+    println!(
+        "✨ Saved checkpoint {} of {} branches{}",
+        checkpoint.id.to_string().sage(),
+        checkpoint.branches.len(),
+        if checkpoint.worktree.is_some() { " and your uncommitted changes" } else { "" }
+    );
+    println!("Return to it with {}", format!("sage rollback --to {}", checkpoint.id).sage());
+    Ok(())
+}
+
+fn list_checkpoints() -> Result<()> {
+    let checkpoints = checkpoint::load()?;
+    if checkpoints.is_empty() {
+        println!("No checkpoints yet. Take one with {}", "sage checkpoint".sage());
+        return Ok(());
+    }
+
+    for checkpoint in checkpoints.iter().rev() {
+        println!(
+            "{}  {}  {}{}{}",
+            checkpoint.id.to_string().yellow(),
+            format_time(checkpoint.created_at).gray(),
+            checkpoint.label,
+            checkpoint.head.as_ref().map(|head| format!(" on {}", head)).unwrap_or_default(),
+            if checkpoint.worktree.is_some() { " (with uncommitted changes)".gray().to_string() } else { String::new() }
+        );
+    }
+    Ok(())
+}
+
+/// rollback returns every branch, the checked out branch and the working tree to a checkpoint:
+/// the one with id `to`, the latest taken at or before the time `to`, or the latest of all.
+/// A checkpoint of the current state is taken first, so the rollback can itself be rolled back.
+pub fn rollback(to: Option<&str>, yes: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+    if let Some(operation) = git::repo::in_progress()? {
+        return Err(anyhow!("A {} is in progress. Finish or abort it first", operation));
+    }
+
+    let checkpoints = checkpoint::load()?;
+    let target = match to {
+        Some(spec) => match checkpoints.iter().find(|checkpoint| checkpoint.id.to_string() == spec.trim()) {
+            Some(checkpoint) => checkpoint,
+            None => {
+                let time = parse_time(spec, Local::now())?;
+                checkpoint::at_or_before(&checkpoints, time)
+                    .ok_or_else(|| anyhow!("No checkpoint was taken at or before {}", format_time(time)))?
+            }
+        },
+        None => checkpoints.last().ok_or_else(|| anyhow!("No checkpoints yet"))?,
+    };
+
+    println!(
+        "Rolling back to {} ({}, {})",
+        target.id.to_string().sage(),
+        target.label,
+        format_time(target.created_at).gray()
+    );
+    let current = git::checkpoint::branch_tips()?;
+    for (branch, oid) in &target.branches {
+        match current.iter().find(|(name, _)| name == branch) {
+            Some((_, tip)) if tip == oid => {}
+            Some(_) => println!("  {} moves back to {}", branch.yellow(), &oid[..7.min(oid.len())]),
+            None => println!("  {} is recreated at {}", branch.yellow(), &oid[..7.min(oid.len())]),
+        }
+    }
+    for (branch, _) in current.iter().filter(|(name, _)| !target.branches.contains_key(name)) {
+        println!("  {} didn't exist yet and is left alone", branch.gray());
+    }
+    if target.worktree.is_some() {
+        println!("  Uncommitted changes from then are restored");
+    }
+
+    if !yes && !Confirm::new("Roll back? Your current state is saved first").with_default(false).prompt()? {
+        return Ok(());
+    }
+
+    let safety = create("before rollback")?;
+
+    git::checkpoint::reset_hard()?;
+    git::checkpoint::detach()?;
+    for (branch, oid) in &target.branches {
+        git::checkpoint::update_ref(&format!("refs/heads/{}", branch), oid)?;
+    }
+    for (branch, parent) in &target.parents {
+        git::stack::set_parent(branch, parent)?;
+    }
+    if let Some(head) = &target.head {
+        git::branch::switch(head, false)?;
+    }
+    if let Some(worktree) = &target.worktree {
+        git::checkpoint::stash_apply(worktree)?;
+    }
+
+    println!("✨ Rolled back to {}", target.id.to_string().sage());
+    println!("Changed your mind? {}", format!("sage rollback --to {}", safety.id).sage());
+    Ok(())
+}
+
+/// parse_time understands `30m`, `2h`, `1d` or `1w` (optionally followed by `ago`), a time today
+/// like `14:30`, a local date and time like `2024-05-01 14:30`, a date like `2024-05-01` (at
+/// midnight), or RFC 3339
+pub fn parse_time(spec: &str, now: DateTime<Local>) -> Result<DateTime<Utc>> {
+    let spec = spec.trim();
+    let relative = spec.strip_suffix("ago").unwrap_or(spec).trim();
+
+    let unit = relative.chars().last().filter(|unit| "mhdw".contains(*unit));
+    let amount = unit.and_then(|_| relative[..relative.len() - 1].trim().parse::<i64>().ok());
+    if let (Some(unit), Some(amount)) = (unit, amount) {
+        let duration = match unit {
+            'm' => Duration::minutes(amount),
+            'h' => Duration::hours(amount),
+            'd' => Duration::days(amount),
+            _ => Duration::weeks(amount),
+        };
+        return Ok((now - duration).with_timezone(&Utc));
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let local = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(spec, format).ok())
+        .or_else(|| {
+            ["%H:%M:%S", "%H:%M"]
+                .iter()
+                .find_map(|format| NaiveTime::parse_from_str(spec, format).ok())
+                .map(|time| now.date_naive().and_time(time))
+        })
+        .or_else(|| NaiveDate::parse_from_str(spec, "%Y-%m-%d").ok().map(|date| date.and_time(NaiveTime::MIN)));
+
+    local
+        .and_then(|local| Local.from_local_datetime(&local).earliest())
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Can't read '{}' as a time. Try 30m, 2h, 14:30, 2024-05-01 or 2024-05-01 14:30", spec))
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        let now = Local.with_ymd_and_hms(2024, 5, 1, 15, 0, 0).unwrap();
+        let local = |h, m| Local.with_ymd_and_hms(2024, 5, 1, h, m, 0).unwrap().with_timezone(&Utc);
+
+        assert_eq!(parse_time("30m", now).unwrap(), local(14, 30));
+        assert_eq!(parse_time("2h ago", now).unwrap(), local(13, 0));
+        assert_eq!(parse_time("14:05", now).unwrap(), local(14, 5));
+        assert_eq!(parse_time("2024-05-01 09:15", now).unwrap(), local(9, 15));
+        assert_eq!(parse_time("2024-05-01", now).unwrap(), local(0, 0));
+        assert_eq!(
+            parse_time("2024-05-01T12:00:00Z", now).unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+        );
+        assert!(parse_time("yesterday", now).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+use crate::{errors, gh, gh::checks::CheckRun, git, tui::{self, select::Select}, ui::{accessible::{self, Mark}, pager, theme, ColorizeExt}};
+
+/// How often to poll a running job in --follow mode
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The branch and commit CI should be looked up for: the PR's head when given, else the current branch
+async fn target(pr: Option<u64>) -> Result<(String, String)> {
+    if let Some(number) = pr {
+        let (owner, repo) = git::repo::owner_repo()?;
+        let pull = gh::pulls::get_pull_request(&owner, &repo, number).await?;
+        return Ok((pull.head.ref_field, pull.head.sha));
+    }
+
+    let branch = git::branch::current()?;
+
+    // CI runs against what was pushed, not local commits
+    let remote = format!("origin/{}", branch);
+    let sha = if git::repo::rev_exists(&remote) {
+        git::repo::rev_parse(&remote)?
+    } else {
+        git::repo::rev_parse("HEAD")?
+    };
+
+    Ok((branch, sha))
+}
+
+/// rerun starts the GitHub Actions runs for the current branch (or a PR) again
+pub async fn rerun(failed_only: bool, pr: Option<u64>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let (owner, repo) = git::repo::owner_repo()?;
+    let (branch, sha) = target(pr).await?;
+    let runs = gh::actions::runs_for_commit(&owner, &repo, &branch, &sha).await?;
+
+    if runs.is_empty() {
+        println!("No workflow runs found for {} ({})", branch.yellow(), &sha[..7.min(sha.len())]);
+        return Ok(());
+    }
+
+    let mut rerun_count = 0;
+    for run in &runs {
+        let name = run.name.clone().unwrap_or_else(|| run.id.to_string());
+
+        // Runs still in progress can't be re-run, and passing ones only when asked for everything
+        if !run.is_completed() {
+            println!("  {} {} {}", "○".gray(), name, "(still running)".gray());
+            continue;
+        }
+        if failed_only && !run.is_failed() {
+            continue;
+        }
+
+        gh::actions::rerun(&owner, &repo, run.id, failed_only).await?;
+        rerun_count += 1;
+        println!("  {} {} {}", "↻".sage(), name, run.html_url.url());
+    }
+
+    if rerun_count == 0 {
+        println!("Nothing to re-run{}", if failed_only { ", no failed runs" } else { "" });
+    } else {
+        println!("✨ Re-running {} workflow run(s)", rerun_count);
+    }
+
+    Ok(())
+}
+
+/// run triggers a workflow_dispatch workflow, prompting for inputs the workflow declares
+pub async fn run(workflow: &str, git_ref: Option<String>, fields: &[String]) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let (owner, repo) = git::repo::owner_repo()?;
+    let git_ref = match git_ref {
+        Some(git_ref) => git_ref,
+        None => git::branch::current()?,
+    };
+
+    let mut provided = BTreeMap::new();
+    for field in fields {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid input '{}', expected key=value", field))?;
+        provided.insert(key.to_string(), value.to_string());
+    }
+
+    let file = workflow_file(workflow)?;
+    let inputs = match &file {
+        Some(path) => gh::actions::parse_dispatch_inputs(&fs::read_to_string(path)?),
+        None => Vec::new(),
+    };
+    let values = tui::workflow::prompt_inputs(&inputs, &provided)?;
+
+    // The API accepts the workflow's file name as its id
+    let id = file
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| workflow.to_string());
+
+    gh::actions::dispatch(&owner, &repo, &id, &git_ref, &values).await?;
+    println!("✨ Triggered {} on {}", id.sage(), git_ref.yellow());
+
+    Ok(())
+}
+
+pub struct LogsOptions {
+    /// Name (or part of the name) of the check to show
+    pub check: Option<String>,
+    /// Use the head of this pull request instead of the current branch
+    pub pr: Option<u64>,
+    /// Keep polling until the job finishes
+    pub follow: bool,
+    /// Keep the timestamp GitHub puts in front of every line
+    pub timestamps: bool,
+}
+
+/// logs shows the log of one of the checks for the current branch
+pub async fn logs(opts: &LogsOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let (owner, repo) = git::repo::owner_repo()?;
+    let (branch, sha) = target(opts.pr).await?;
+    let runs: Vec<CheckRun> = gh::checks::check_runs(&owner, &repo, &sha)
+        .await?
+        .into_iter()
+        .filter(|run| run.is_actions_job())
+        .collect();
+
+    if runs.is_empty() {
+        println!("No GitHub Actions checks found for {}", branch.yellow());
+        return Ok(());
+    }
+
+    let run = pick_check(runs, opts.check.as_deref())?;
+    let name = run.name.clone();
+
+    if !opts.follow {
+        let log = gh::checks::job_logs(&owner, &repo, run.id).await?;
+        return pager::page(&format_log(&log, opts.timestamps));
+    }
+
+    // Print whatever is new on every poll until the job is done
+    let mut printed = 0;
+    loop {
+        let current = gh::checks::check_run(&owner, &repo, run.id).await?;
+        let finished = current.status == "completed";
+
+        match gh::checks::job_logs(&owner, &repo, run.id).await {
+            Ok(log) => {
+                let lines: Vec<String> = format_log(&log, opts.timestamps).lines().map(str::to_string).collect();
+                for line in lines.iter().skip(printed) {
+                    println!("{}", line);
+                }
+                printed = printed.max(lines.len());
+            }
+            // GitHub only serves logs for some jobs once they finish
+            Err(e) if finished => return Err(e),
+            Err(_) => {}
+        }
+
+        if finished {
+            let conclusion = current.conclusion.unwrap_or_default();
+            let label = if conclusion == "success" { conclusion.green() } else { conclusion.red() };
+            println!("\n{} finished: {}", name.sage(), label);
+            return Ok(());
+        }
+
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+    }
+}
+
+/// Pick the check matching `filter`, asking when there's no single match
+fn pick_check(runs: Vec<CheckRun>, filter: Option<&str>) -> Result<CheckRun> {
+    let candidates: Vec<CheckRun> = match filter {
+        Some(filter) => {
+            let filter = filter.to_lowercase();
+            let matching: Vec<CheckRun> = runs
+                .iter()
+                .filter(|run| run.name.to_lowercase().contains(&filter))
+                .cloned()
+                .collect();
+
+            if let Some(exact) = matching.iter().find(|run| run.name.to_lowercase() == filter) {
+                return Ok(exact.clone());
+            }
+            if matching.is_empty() {
+                return Err(anyhow!("No check matching '{}'", filter));
+            }
+            matching
+        }
+        None => runs,
+    };
+
+    if let [only] = candidates.as_slice() {
+        return Ok(only.clone());
+    }
+
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|run| {
+            let state = match (run.status.as_str(), run.conclusion.as_deref()) {
+                ("completed", Some("success")) => theme::styled(Mark::Passing),
+                ("completed", Some("skipped")) | ("completed", Some("neutral")) => theme::styled(Mark::Skipped),
+                ("completed", _) => theme::styled(Mark::Failing),
+                _ => theme::styled(Mark::Pending),
+            };
+            format!("{} {}", state, run.name)
+        })
+        .collect();
+
+    let index = if accessible::enabled() {
+        accessible::select("Which check?", &labels, None)?
+    } else {
+        Select::new("Which check?", &labels).prompt()?
+    };
+    Ok(candidates[index].clone())
+}
+
+/// Drop the timestamp GitHub prefixes every log line with, keeping ANSI colors intact
+fn format_log(log: &str, timestamps: bool) -> String {
+    if timestamps {
+        return log.to_string();
+    }
+
+    log.lines()
+        .map(|line| match line.split_once(' ') {
+            Some((stamp, rest)) if is_timestamp(stamp) => rest,
+            _ => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+/// Matches the `2024-05-01T12:00:00.1234567Z` stamps in Actions logs
+fn is_timestamp(value: &str) -> bool {
+    let value = value.trim_start_matches('\u{feff}');
+    value.len() >= 20
+        && value.ends_with('Z')
+        && value.as_bytes().get(4) == Some(&b'-')
+        && value.as_bytes().get(10) == Some(&b'T')
+}
+
+/// Find a workflow in .github/workflows by file name, with or without its extension
+fn workflow_file(workflow: &str) -> Result<Option<std::path::PathBuf>> {
+    let dir = git::repo::toplevel()?.join(".github").join("workflows");
+    let candidates = [workflow.to_string(), format!("{}.yml", workflow), format!("{}.yaml", workflow)];
+
+    Ok(candidates
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_log_strips_timestamps() {
+        let log = "\u{feff}2024-05-01T12:00:00.1234567Z ##[group]Run cargo fmt\n2024-05-01T12:00:01.0000000Z \u{1b}[31merror\u{1b}[0m: diff\nplain line";
+        assert_eq!(
+            format_log(log, false),
+            "##[group]Run cargo fmt\n\u{1b}[31merror\u{1b}[0m: diff\nplain line\n"
+        );
+        assert_eq!(format_log(log, true), log);
+    }
+}
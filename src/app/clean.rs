@@ -1,9 +1,17 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use octocrab::models::IssueState;
-use crate::{git, errors, gh::pulls};
+use crate::{app::{checkpoint, gc::human_size, guard::glob_match, ignore::{self, IgnoreAddOptions}, reviewers::parse_picks, undo}, config, git, errors, gh::graphql::{self, PrState}, policy, ui::{self, accessible}};
 use colored::Colorize;
+use serde::Serialize;
+use inquire::Confirm;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
 
-pub async fn clean() -> Result<()> {
+const DAY: i64 = 24 * 60 * 60;
+
+pub async fn clean(yes: bool) -> Result<()> {
     // Check to ensure we are in a repo first.
     if !git::repo::is_repo()? {
         return Err(errors::GitError::NotARepository.into());
@@ -21,16 +29,16 @@ pub async fn clean() -> Result<()> {
         println!("  {}", branch.blue());
     }
 
-    // Ask for confirmation
-    println!("\nDo you want to delete these branches? [y/N]");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
-    
-    if !input.trim().eq_ignore_ascii_case("y") {
+    if !yes && !confirm("Do you want to delete these branches?")? {
         println!("Operation cancelled.");
         return Ok(());
     }
 
+    checkpoint::auto("before clean");
+
+    // Keep a record so sage undo can bring them back
+    let id = undo::record_deletion("clean", &cleanable_branches)?;
+
     // Delete the branches
     for branch in cleanable_branches {
         // Try to delete remote first if it exists
@@ -52,9 +60,483 @@ pub async fn clean() -> Result<()> {
         }
     }
 
+    undo::finish_deletion(id)?;
+    println!("Changed your mind? {} brings them back", ui::sage("sage undo"));
+
+    Ok(())
+}
+
+/// An untracked file or directory, and how much space it takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UntrackedPath {
+    path: String,
+    ignored: bool,
+    size: u64,
+}
+
+impl UntrackedPath {
+    fn label(&self) -> String {
+        let ignored = if self.ignored { ", ignored" } else { "" };
+        format!("{} {}", self.path, ui::gray(&format!("({}{})", human_size(self.size), ignored)))
+    }
+}
+
+/// untracked is `sage clean --untracked`: pick which untracked files, and with `ignored` ignored
+/// ones too, to keep, optionally adding them to .gitignore, and delete the rest. Without a
+/// terminal to pick in, or with `yes`, it offers to delete them all. Git repositories of their own
+/// inside the working tree are listed but never deleted.
+pub fn untracked(ignored: bool, yes: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let root = git::repo::toplevel()?;
+    let mut paths = git::files::untracked_paths(false)?
+        .into_iter()
+        .map(|path| (path, false))
+        .collect::<Vec<_>>();
+    if ignored {
+        paths.extend(git::files::untracked_paths(true)?.into_iter().map(|path| (path, true)));
+    }
+    paths.sort();
+
+    // A repository inside the working tree has history of its own, which deleting would lose
+    let (repositories, paths): (Vec<_>, Vec<_>) = paths.into_iter().partition(|(path, _)| contains_repository(&root.join(path)));
+    for (path, _) in &repositories {
+        println!("{} Leaving {} alone, it holds a git repository of its own", "WARNING:".yellow(), path);
+    }
+    let paths = paths
+        .into_iter()
+        .map(|(path, ignored)| UntrackedPath { size: disk_size(&root.join(&path)), path, ignored })
+        .collect::<Vec<_>>();
+
+    if paths.is_empty() {
+        println!("No untracked files. Everything is tidy.");
+        return Ok(());
+    }
+
+    let labels = paths.iter().map(UntrackedPath::label).collect::<Vec<_>>();
+    let keep = if yes || !std::io::stdin().is_terminal() {
+        Vec::new()
+    } else if accessible::enabled() {
+        println!("Untracked files:");
+        for (index, label) in labels.iter().enumerate() {
+            println!("  {}. {}", index + 1, label);
+        }
+        let answer = inquire::Text::new("Keep which (numbers separated by commas, the rest are deleted)?").prompt()?;
+        parse_picks(&answer, paths.len()).ok_or_else(|| anyhow!("{} is not a list of the numbers shown", answer))?
+    } else {
+        inquire::MultiSelect::new("Keep which? The rest are deleted", labels)
+            .raw_prompt()?
+            .into_iter()
+            .map(|option| option.index)
+            .collect()
+    };
+
+    let (kept, delete): (Vec<_>, Vec<_>) = paths.into_iter().enumerate().partition(|(index, _)| keep.contains(index));
+    let kept = kept.into_iter().map(|(_, path)| path).collect::<Vec<_>>();
+    let delete = delete.into_iter().map(|(_, path)| path).collect::<Vec<_>>();
+
+    let unignored = kept.iter().filter(|path| !path.ignored).map(|path| format!("/{}", path.path)).collect::<Vec<_>>();
+    if !unignored.is_empty() && Confirm::new("Add the files you kept to .gitignore?").with_default(false).prompt()? {
+        ignore::add(&IgnoreAddOptions { patterns: unignored, section: None, untrack: false })?;
+    }
+    if delete.is_empty() {
+        println!("Nothing to delete");
+        return Ok(());
+    }
+
+    let total = delete.iter().map(|path| path.size).sum::<u64>();
+    println!("\nThese will be deleted, {} in all:", human_size(total));
+    for path in &delete {
+        println!("  {}", path.label());
+    }
+    if !yes && !confirm("Delete them? sage undo can't bring untracked files back")? {
+        println!("Operation cancelled.");
+        return Ok(());
+    }
+
+    for path in &delete {
+        let full = root.join(&path.path);
+        let removed = if full.is_dir() { fs::remove_dir_all(&full) } else { fs::remove_file(&full) };
+        if let Err(e) = removed {
+            println!("{} Failed to delete '{}': {}", "WARNING:".yellow(), path.path, e);
+        }
+    }
+    println!("✨ Deleted {} path(s), freeing {}", delete.len(), human_size(total));
+    Ok(())
+}
+
+/// contains_repository returns if a directory is a git repository or has one anywhere inside it
+fn contains_repository(path: &Path) -> bool {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return false;
+    };
+    if !metadata.is_dir() {
+        return false;
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().any(|entry| entry.file_name() == ".git" || contains_repository(&entry.path())))
+        .unwrap_or(false)
+}
+
+/// How much space a file, or everything under a directory, takes
+fn disk_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| disk_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// keep marks branches for sage clean to leave alone, or with `keep` false takes the mark off
+pub fn keep(branches: &[String], keep: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    for branch in branches {
+        if !git::branch::exists(branch) {
+            return Err(anyhow!("There's no branch called {}", branch));
+        }
+        git::stack::set_keep(branch, keep)?;
+        if keep {
+            println!("✨ sage clean will leave {} alone", branch.blue());
+        } else {
+            println!("✨ sage clean may clean {} again", branch.blue());
+        }
+    }
+    Ok(())
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    println!("\n{} [y/N]", question);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Which branches `sage clean --policy` removes, from the clean.* settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanPolicy {
+    /// Merged branches go once their last commit is this many days old
+    pub merged_days: i64,
+    /// Branches without an open pull request go once their last commit is this many days old,
+    /// merged or not
+    pub stale_days: Option<i64>,
+}
+
+/// A branch as the policy sees it
+#[derive(Debug, Clone)]
+struct Candidate {
+    name: String,
+    committed_at: i64,
+    merged: bool,
+    /// None when pull requests couldn't be looked up
+    open_pr: Option<bool>,
+}
+
+impl CleanPolicy {
+    /// load reads the policy from config, failing on a setting that isn't a number of days
+    pub fn load() -> Result<CleanPolicy> {
+        let days = |key: &str| -> Result<Option<i64>> {
+            config::get(key)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<u32>()
+                        .map(i64::from)
+                        .map_err(|_| anyhow!("{} must be a number of days, not '{}'", key, value))
+                })
+                .transpose()
+        };
+        Ok(CleanPolicy { merged_days: days("clean.merged_days")?.unwrap_or(30), stale_days: days("clean.stale_days")? })
+    }
+
+    /// reason explains why the policy removes a branch, None to keep it
+    fn reason(&self, branch: &Candidate, now: i64) -> Option<String> {
+        let age = (now - branch.committed_at).max(0) / DAY;
+        if branch.merged && age >= self.merged_days {
+            return Some(format!("merged, last commit {} days ago", age));
+        }
+        // Without knowing about pull requests, a branch could have an open one
+        match (self.stale_days, branch.open_pr) {
+            (Some(stale_days), Some(false)) if age >= stale_days => {
+                Some(format!("no open pull request, last commit {} days ago", age))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Branches sage clean leaves alone whatever else it finds: those matching clean.protect, younger
+/// than clean.min_age_days, or marked with `sage clean --keep`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Protections {
+    /// Globs like `release/*`. Patterns without a `/` only match branches without one.
+    pub patterns: Vec<String>,
+    pub min_age_days: Option<i64>,
+    /// Branches marked with `sage clean --keep`
+    pub kept: HashSet<String>,
+}
+
+impl Protections {
+    /// load reads the protections from config and the branch marks
+    pub fn load() -> Result<Protections> {
+        let patterns = config::get("clean.protect")
+            .map(|value| value.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+        let min_age_days = config::get("clean.min_age_days")
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<u32>()
+                    .map(i64::from)
+                    .map_err(|_| anyhow!("clean.min_age_days must be a number of days, not '{}'", value))
+            })
+            .transpose()?;
+        Ok(Protections { patterns, min_age_days, kept: git::stack::kept()? })
+    }
+
+    /// reason explains why a branch last committed to at `committed_at` is left alone, None when
+    /// it isn't protected
+    fn reason(&self, name: &str, committed_at: i64, now: i64) -> Option<String> {
+        if self.kept.contains(name) {
+            return Some("marked with sage clean --keep".to_string());
+        }
+        let pattern = self.patterns.iter().find(|pattern| {
+            (pattern.contains('/') || !name.contains('/')) && glob_match(pattern, name)
+        });
+        if let Some(pattern) = pattern {
+            return Some(format!("matches clean.protect {}", pattern));
+        }
+        let age = (now - committed_at).max(0) / DAY;
+        match self.min_age_days {
+            Some(min_age_days) if age < min_age_days => {
+                Some(format!("last commit {} days ago, under clean.min_age_days", age))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What `sage clean --policy --report json` prints
+#[derive(Debug, Serialize)]
+struct Report {
+    dry_run: bool,
+    archive: bool,
+    branches: Vec<ReportBranch>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportBranch {
+    name: String,
+    reason: String,
+    action: &'static str,
+    done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archived_as: Option<String>,
+    deleted_remote: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// policy_clean is `sage clean --policy`: archive or delete the branches the clean policy
+/// picks. With `json`, nothing but the report is printed to stdout, and without `yes` nothing
+/// is changed, so it can run unattended from a scheduled job.
+pub async fn policy_clean(archive: bool, yes: bool, json: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let clean_policy = CleanPolicy::load()?;
+    let archive = archive || config::get_bool("clean.archive", false);
+    let warn = |message: String| {
+        if json {
+            eprintln!("{} {}", "WARNING:".yellow(), message);
+        } else {
+            println!("{} {}", "WARNING:".yellow(), message);
+        }
+    };
+
+    git::repo::fetch_remote()?;
+    let candidates = policy_candidates(&clean_policy, &warn).await?;
+    let now = chrono::Utc::now().timestamp();
+    let selected = candidates
+        .iter()
+        .filter_map(|branch| clean_policy.reason(branch, now).map(|reason| (branch, reason)))
+        .collect::<Vec<_>>();
+
+    let action = if archive { "archive" } else { "delete" };
+    let mut report = Report {
+        dry_run: json && !yes,
+        archive,
+        branches: selected
+            .iter()
+            .map(|(branch, reason)| ReportBranch {
+                name: branch.name.clone(),
+                reason: reason.clone(),
+                action,
+                done: false,
+                archived_as: None,
+                deleted_remote: false,
+                error: None,
+            })
+            .collect(),
+    };
+
+    if !json {
+        if selected.is_empty() {
+            println!("No branches match the clean policy. Everything is tidy.");
+            return Ok(());
+        }
+        println!("\nThe clean policy would {} these branches:", action);
+        for (branch, reason) in &selected {
+            println!("  {} {}", branch.name.blue(), ui::gray(&format!("({})", reason)));
+        }
+        if !yes && !confirm(&format!("Do you want to {} these branches?", action))? {
+            println!("Operation cancelled.");
+            return Ok(());
+        }
+    }
+    if selected.is_empty() || report.dry_run {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    // Like checkpoint::auto, but warning where the report won't be spoiled
+    let taken = config::get_bool("checkpoint.auto", true).then(|| checkpoint::create("before clean"));
+    if let Some(Err(e)) = taken {
+        warn(format!("Could not take a checkpoint: {}", e));
+    }
+
+    // Keep a record so sage undo can bring them back, archived or not
+    let names = selected.iter().map(|(branch, _)| branch.name.clone()).collect::<Vec<_>>();
+    let id = undo::record_deletion("clean", &names)?;
+
+    for ((branch, _), entry) in selected.iter().zip(report.branches.iter_mut()) {
+        if archive {
+            match git::gc::archive(&branch.name) {
+                Ok(archived_as) => entry.archived_as = Some(archived_as),
+                Err(e) => {
+                    warn(format!("Failed to archive '{}': {}", branch.name, e));
+                    entry.error = Some(e.to_string());
+                    continue;
+                }
+            }
+        }
+
+        // Only merged work is safe to take off the remote as well
+        if branch.merged && git::branch::exists(&format!("origin/{}", branch.name)) {
+            match git::branch::delete_remote(&branch.name) {
+                Ok(()) => entry.deleted_remote = true,
+                Err(e) => warn(format!("Failed to delete remote branch '{}': {}", branch.name, e)),
+            }
+        }
+
+        match git::branch::delete_local(&branch.name) {
+            Ok(()) => {
+                entry.done = true;
+                if !json {
+                    match &entry.archived_as {
+                        Some(archived_as) => println!("Archived {} as {}", branch.name.blue(), archived_as),
+                        None => println!("Deleted local branch: {}", branch.name.blue()),
+                    }
+                }
+            }
+            Err(e) => {
+                warn(format!("Failed to delete local branch '{}': {}", branch.name, e));
+                entry.error = Some(e.to_string());
+            }
+        }
+    }
+
+    undo::finish_deletion(id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Changed your mind? {} brings them back", ui::sage("sage undo"));
+    }
+
     Ok(())
 }
 
+/// Every branch the policy may remove: never the current or default branch, nor one the org
+/// policy protects
+async fn policy_candidates(clean_policy: &CleanPolicy, warn: &impl Fn(String)) -> Result<Vec<Candidate>> {
+    let default_branch = git::repo::default_branch()?;
+    let current_branch = git::branch::current()?;
+    let org_policy = policy::load()?;
+    let merged = git::list::merged()?.into_iter().collect::<HashSet<_>>();
+    let protections = Protections::load()?;
+    let now = chrono::Utc::now().timestamp();
+
+    let refs = git::index::refs()?
+        .into_iter()
+        .filter(|branch| branch.name != default_branch && branch.name != current_branch)
+        .filter(|branch| !org_policy.as_ref().is_some_and(|org_policy| org_policy.is_protected(&branch.name)))
+        .filter(|branch| protections.reason(&branch.name, branch.committed_at, now).is_none())
+        .collect::<Vec<_>>();
+
+    // Pull requests only matter for stale branches
+    let pull_requests = if clean_policy.stale_days.is_some() && !refs.is_empty() {
+        let names = refs.iter().map(|branch| branch.name.clone()).collect::<Vec<_>>();
+        match graphql::pull_requests_by_branch(&names).await {
+            Ok(pull_requests) => Some(pull_requests),
+            Err(e) => {
+                warn(format!("Could not look up pull requests, so only merged branches are cleaned: {}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let target = merge_target(&default_branch);
+    let pr_merged = |name: &str| {
+        pull_requests
+            .as_ref()
+            .is_some_and(|pull_requests| pull_requests.get(name).is_some_and(|pr| pr.state == PrState::Merged))
+    };
+    Ok(refs
+        .into_iter()
+        // A branch nothing was committed to only looks merged or stale because of the commit it
+        // was started from, however old that is, so it's left alone unless its pull request merged
+        .filter(|branch| pr_merged(&branch.name) || git::branch::has_own_commits(&branch.name))
+        .map(|branch| Candidate {
+            merged: merged.contains(&branch.name) || pr_merged(&branch.name) || squash_merged(&branch.name, &target),
+            open_pr: pull_requests
+                .as_ref()
+                .map(|pull_requests| pull_requests.get(&branch.name).is_some_and(|pr| pr.state == PrState::Open)),
+            name: branch.name,
+            committed_at: branch.committed_at,
+        })
+        .collect())
+}
+
+/// Where merged branches end up: the default branch on origin, which is ahead of the local one
+/// after merging on GitHub
+fn merge_target(default_branch: &str) -> String {
+    if git::repo::remote_branch_exists(default_branch) {
+        format!("origin/{}", default_branch)
+    } else {
+        default_branch.to_string()
+    }
+}
+
+/// squash_merged returns if a branch was merged as different commits, taking a branch that
+/// can't be compared as unmerged
+fn squash_merged(branch: &str, target: &str) -> bool {
+    git::list::squash_merged(branch, target).unwrap_or(false)
+}
+
 // Core logic for determining if a branch should be cleaned
 fn should_clean_branch(
     branch_info: &git::branch::BranchInfo,
@@ -105,10 +587,18 @@ async fn find_cleanable_branches() -> Result<Vec<String>> {
 
     // Get detailed branch information including tracking info
     let branch_infos = git::branch::list_with_info()?;
-    let merged_branches: Vec<String> = git::list::merged()?
+    let mut merged_branches: Vec<String> = git::list::merged()?
         .into_iter()
         .filter(|branch| *branch != default_branch && *branch != current_branch)
         .collect();
+    let target = merge_target(&default_branch);
+
+    // Look up the pull requests for every branch in one go
+    let names = branch_infos.iter().map(|info| info.name.clone()).collect::<Vec<_>>();
+    let pull_requests = graphql::pull_requests_by_branch(&names).await.unwrap_or_else(|e| {
+        println!("{} Could not look up pull requests: {}", "WARNING:".yellow(), e);
+        HashMap::new()
+    });
 
     let mut cleanable_branches = Vec::new();
 
@@ -117,12 +607,23 @@ async fn find_cleanable_branches() -> Result<Vec<String>> {
         let branch_name = &branch_info.name;
 
         // Get PR state if it exists
-        let (pr_state, pr_merged) = if let Ok(Some(pr)) = pulls::get_by_branch(branch_name).await {
-            (pr.state.clone(), pr.merged_at.is_some())
-        } else {
-            (None, false)
+        let (pr_state, pr_merged) = match pull_requests.get(branch_name).map(|pr| pr.state) {
+            Some(PrState::Open) => (Some(IssueState::Open), false),
+            Some(PrState::Closed) => (Some(IssueState::Closed), false),
+            Some(PrState::Merged) => (Some(IssueState::Closed), true),
+            None => (None, false),
         };
 
+        // Squash and rebase merges leave no merge commit, so look for the branch's changes
+        if !pr_merged
+            && *branch_name != current_branch
+            && *branch_name != default_branch
+            && !merged_branches.contains(branch_name)
+            && squash_merged(branch_name, &target)
+        {
+            merged_branches.push(branch_name.clone());
+        }
+
         // Check if upstream exists (if branch has one)
         let upstream_exists = if let Some(upstream) = &branch_info.upstream {
             git::branch::exists(upstream)
@@ -143,6 +644,20 @@ async fn find_cleanable_branches() -> Result<Vec<String>> {
         }
     }
 
+    let protections = Protections::load()?;
+    let now = chrono::Utc::now().timestamp();
+    let committed_at = git::index::refs()?
+        .into_iter()
+        .map(|branch| (branch.name, branch.committed_at))
+        .collect::<HashMap<_, _>>();
+    cleanable_branches.retain(|branch| {
+        let reason = protections.reason(branch, committed_at.get(branch).copied().unwrap_or(now), now);
+        if let Some(reason) = &reason {
+            println!("Keeping {} {}", branch.blue(), ui::gray(&format!("({})", reason)));
+        }
+        reason.is_none()
+    });
+
     Ok(cleanable_branches)
 }
 
@@ -150,6 +665,27 @@ async fn find_cleanable_branches() -> Result<Vec<String>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_protections() {
+        let protections = Protections {
+            patterns: vec!["release/*".to_string(), "wip".to_string()],
+            min_age_days: Some(3),
+            kept: HashSet::from(["pinned".to_string()]),
+        };
+        let now = 100 * DAY;
+        let old = now - 10 * DAY;
+
+        assert_eq!(protections.reason("pinned", old, now).as_deref(), Some("marked with sage clean --keep"));
+        assert_eq!(protections.reason("release/1.x", old, now).as_deref(), Some("matches clean.protect release/*"));
+        assert!(protections.reason("wip", old, now).is_some());
+        assert_eq!(protections.reason("feature/wip", old, now), None);
+        assert_eq!(
+            protections.reason("fresh", now - DAY, now).as_deref(),
+            Some("last commit 1 days ago, under clean.min_age_days")
+        );
+        assert_eq!(protections.reason("feature/done", old, now), None);
+    }
+
     // Helper function to create a test branch info
     fn create_branch_info(name: &str, upstream: Option<&str>, is_current: bool) -> git::branch::BranchInfo {
         git::branch::BranchInfo {
@@ -266,6 +802,31 @@ mod tests {
         assert!(result, "Should clean branch with merged PR");
     }
 
+    #[test]
+    fn test_clean_policy_reason() {
+        let now = 100 * DAY;
+        let branch = |merged, open_pr, days_ago| Candidate {
+            name: "feature".to_string(),
+            committed_at: now - days_ago * DAY,
+            merged,
+            open_pr,
+        };
+        let clean_policy = CleanPolicy { merged_days: 30, stale_days: Some(60) };
+
+        assert_eq!(clean_policy.reason(&branch(true, None, 45), now).as_deref(), Some("merged, last commit 45 days ago"));
+        assert_eq!(clean_policy.reason(&branch(true, None, 10), now), None);
+        assert_eq!(
+            clean_policy.reason(&branch(false, Some(false), 90), now).as_deref(),
+            Some("no open pull request, last commit 90 days ago")
+        );
+        assert_eq!(clean_policy.reason(&branch(false, Some(true), 90), now), None);
+        assert_eq!(clean_policy.reason(&branch(false, None, 90), now), None);
+        assert_eq!(clean_policy.reason(&branch(false, Some(false), 30), now), None);
+
+        let merged_only = CleanPolicy { merged_days: 30, stale_days: None };
+        assert_eq!(merged_only.reason(&branch(false, Some(false), 90), now), None);
+    }
+
     #[test]
     fn test_should_not_clean_active_branch() {
         let branch_info = create_branch_info("feature/active", Some("origin/feature/active"), false);
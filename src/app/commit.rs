@@ -1,19 +1,33 @@
-use anyhow::Result;
-use crate::{ai, errors, git};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use colored::Colorize;
+use crate::{ai, app::{checkpoint::parse_time, context, dco, prompts::{self, Inputs, Prompt}, guard::{self, Mode}, hooks, identity, lfs, messages, policy, vars::Vars, wip}, config, errors, git};
 use inquire::Confirm;
 
+/// Message for `--retry-empty` commits when commit.empty_message isn't set
+const DEFAULT_EMPTY_MESSAGE: &str = "chore: trigger ci [skip changelog]";
+
+/// How far ahead of the clock a commit date can be before it looks like clock skew
+const CLOCK_SKEW_TOLERANCE_MINUTES: i64 = 60;
+
 #[derive(Default)]
 pub struct CommitOptions {
     /// The message to commit with
     pub message: String,
     /// Whether to allow empty commits or not
     pub empty: bool,
+    /// Create an empty commit with the commit.empty_message template, e.g. to rerun CI
+    pub retry_empty: bool,
     /// Push to remote after committing
     pub push: bool,
     /// Use AI to generate commit message
     pub ai: bool,
     /// Skip confirmation when using AI-generated commit message
     pub auto_confirm: bool,
+    /// Commit guard rules to let through
+    pub allow: Vec<String>,
+    /// Date the commit for both author and committer, e.g. when importing older work
+    pub date: Option<String>,
 }
 
 pub async fn commit(opts: &CommitOptions) -> Result<()> {
@@ -22,25 +36,49 @@ pub async fn commit(opts: &CommitOptions) -> Result<()> {
         return Err(errors::GitError::NotARepository.into());
     }
 
+    let date = opts.date.as_deref().map(|spec| parse_time(spec, Local::now())).transpose()?;
+
     // We are here, so obviously we are within a repo.
     // Next thing to workout is if there are files staged or not. If there is, we will commit them,
     // if not we will commit all of them.
 
     let status = git::status::status()?;
+    let empty = opts.empty || opts.retry_empty;
 
-    if !status.is_dirty() && !opts.empty {
+    if !status.is_dirty() && !empty {
         return Err(errors::GitError::NoChanges.into());
     }
 
-    if !status.has_staged_changes() {
+    if opts.retry_empty && status.has_staged_changes() {
+        return Err(anyhow!("You have staged changes. Commit them with a message of their own"));
+    }
+
+    if empty {
+        // Empty commits only take what's already staged, so check changes to tracked files weren't
+        // meant to go in too. New untracked files are often deliberately left out.
+        let unstaged = status.unstaged_modified.len() + status.unstaged_deleted.len();
+        if !status.has_staged_changes() && unstaged > 0 {
+            check_forgot_to_stage(unstaged)?;
+        }
+    } else if !status.has_staged_changes() {
         // We will stage all changes then.
         git::repo::stage_all()?;
     }
 
+    // Let formatters in the repository's hooks fix files before the message is written from them.
+    // Once they've passed here, git doesn't need to run them again.
+    let hooks_ran = git::status::status()?.has_staged_changes() && hooks::run_before_commit()?;
+
     // Get the commit message - either from AI or user input
-    let message = if opts.ai {
+    let vars = || Vars::for_branch(&git::branch::current().unwrap_or_default());
+    let message = if opts.retry_empty {
+        let template = config::get("commit.empty_message").unwrap_or_else(|| DEFAULT_EMPTY_MESSAGE.to_string());
+        empty_message(&template, &vars())?
+    } else if opts.ai {
         println!("✨ AI mode activated. Generating commit message...");
-        let generated_message = ai::commit::generate().await?;
+        let diff = git::repo::diff()?;
+        let prompt = prompts::render(Prompt::Commit, Inputs { diff: &diff, ..Inputs::default() })?;
+        let generated_message = ai::commit::tidy(&prompts::ask("commit message", &prompt).await?);
         
         // If not auto-confirming, ask for user approval
         if !opts.auto_confirm {
@@ -59,15 +97,146 @@ pub async fn commit(opts: &CommitOptions) -> Result<()> {
         // If not using AI, use the provided message
         opts.message.clone()
     };
+    // Keep it before anything can stop the commit, so it can be reused with --reuse
+    if !opts.retry_empty && let Err(e) = messages::record(&message, opts.ai) {
+        eprintln!("{} Could not keep the commit message: {}", "WARNING:".yellow(), e);
+    }
+
+    // Empty commits have a template of their own
+    let message = match config::get("commit.template") {
+        Some(template) if !opts.retry_empty => vars().render(&template, &[("message", &message)])?,
+        _ => message,
+    };
 
     // We will now create the commit.
-    git::commit::commit(&message, opts.empty)?;
+    policy::check_commit(&git::branch::current()?, &message)?;
+    identity::check_before_commit()?;
+    lfs::check_before_commit()?;
+    guard::check_before_commit(&opts.allow)?;
+    check_clock(date)?;
+    let message = if hooks_ran { hooks::commit_msg(&message)? } else { message };
+    let date = date.map(|date| date.to_rfc3339());
+    git::commit::commit_at(&message, empty, dco::signoff_enabled(), date.as_deref(), hooks_ran)?;
+    guard::after_commit(&message)?;
+    context::after_commit();
 
     if opts.push {
         let current_branch = git::branch::current()?;
+        dco::verify_outgoing(&current_branch)?;
+        wip::check_before_push(&current_branch, false)?;
+        guard::check_before_push(&current_branch, false)?;
         git::branch::push(&current_branch, false)?;
         println!("Pushed changes to remote");
     }
 
     Ok(())
 }
+
+/// Stop (or warn about) an empty commit made while there are unstaged changes, which usually
+/// means they were meant to be staged. Controlled by commit.empty_guard.
+fn check_forgot_to_stage(unstaged: usize) -> Result<()> {
+    let mode = config::get("commit.empty_guard")
+        .and_then(|value| Mode::parse(&value))
+        .unwrap_or(Mode::Block);
+
+    match mode {
+        Mode::Off => Ok(()),
+        Mode::Warn => {
+            println!(
+                "{} Nothing is staged, so this commit is empty even though {} tracked files have changes",
+                "WARNING:".yellow(),
+                unstaged
+            );
+            Ok(())
+        }
+        Mode::Block => Err(anyhow!(
+            "Nothing is staged, so this commit would be empty even though {} tracked files have changes. \
+             Stage them first, or set commit.empty_guard to warn or off to allow it",
+            unstaged
+        )),
+    }
+}
+
+/// Stop (or warn about) a commit whose date looks wrong: in the future, before the commit it goes
+/// on top of, or made while the last commit is dated ahead of the clock. Changelogs and stats sort
+/// by date, so these end up in the wrong place. Controlled by commit.clock_guard.
+fn check_clock(date: Option<DateTime<Utc>>) -> Result<()> {
+    let mode = config::get("commit.clock_guard")
+        .and_then(|value| Mode::parse(&value))
+        .unwrap_or(Mode::Warn);
+    if mode == Mode::Off {
+        return Ok(());
+    }
+
+    let parent = git::commit::head_date()?.and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single());
+    let Some(problem) = clock_problem(date, parent, Utc::now()) else {
+        return Ok(());
+    };
+
+    match mode {
+        Mode::Block => Err(anyhow!("{}. Set commit.clock_guard to warn or off to allow it", problem)),
+        _ => {
+            println!("{} {}", "WARNING:".yellow(), problem);
+            Ok(())
+        }
+    }
+}
+
+/// Describe what looks wrong about committing at `date` (now when None) on top of a commit dated
+/// `parent`
+fn clock_problem(date: Option<DateTime<Utc>>, parent: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<String> {
+    let tolerance = Duration::minutes(CLOCK_SKEW_TOLERANCE_MINUTES);
+    let format = |time: DateTime<Utc>| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+
+    match (date, parent) {
+        (Some(date), _) if date > now + tolerance => {
+            Some(format!("The commit date {} is in the future", format(date)))
+        }
+        (Some(date), Some(parent)) if date < parent => Some(format!(
+            "The commit date {} is before the commit it goes on top of ({}), so it will look out of order",
+            format(date),
+            format(parent)
+        )),
+        (None, Some(parent)) if parent > now + tolerance => Some(format!(
+            "The last commit is dated {}, ahead of your clock ({}). Your system clock may be wrong",
+            format(parent),
+            format(now)
+        )),
+        _ => None,
+    }
+}
+
+/// Fill in the variables of an empty commit message template. Templates from before variables
+/// were added wrote the branch as `{branch}`, which still works.
+fn empty_message(template: &str, vars: &Vars) -> Result<String> {
+    if template.contains("{{") {
+        vars.render(template, &[])
+    } else {
+        Ok(template.replace("{branch}", &vars.branch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_message() {
+        let vars = Vars { branch: "feature".to_string(), ticket: Some("123".to_string()), ..Vars::default() };
+        assert_eq!(empty_message(DEFAULT_EMPTY_MESSAGE, &vars).unwrap(), "chore: trigger ci [skip changelog]");
+        assert_eq!(empty_message("ci: rerun {branch} [skip ci-changelog]", &vars).unwrap(), "ci: rerun feature [skip ci-changelog]");
+        assert_eq!(empty_message("ci: rerun #{{ticket}} on {{branch}}", &vars).unwrap(), "ci: rerun #123 on feature");
+    }
+
+    #[test]
+    fn test_clock_problem() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let hours = |h| Some(now + Duration::hours(h));
+
+        assert_eq!(clock_problem(None, hours(-1), now), None);
+        assert_eq!(clock_problem(hours(-48), hours(-72), now), None);
+        assert!(clock_problem(hours(3), hours(-1), now).unwrap().contains("in the future"));
+        assert!(clock_problem(hours(-48), hours(-1), now).unwrap().contains("before the commit"));
+        assert!(clock_problem(None, hours(5), now).unwrap().contains("clock may be wrong"));
+    }
+}
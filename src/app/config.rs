@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crate::{config::{self, Scope}, ui, ui::ColorizeExt};
+
+/// get prints the effective value for a key
+pub fn get(key: &str) -> Result<()> {
+    match config::get(key) {
+        Some(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        None => Err(anyhow!("{} is not set", key)),
+    }
+}
+
+/// set stores a value, warning about keys sage doesn't know about
+pub fn set(key: &str, value: &str, scope: Scope) -> Result<()> {
+    if !config::is_known(key) {
+        println!("{} {} is not a setting sage knows about", "WARNING:".yellow(), key.yellow());
+    }
+
+    config::set(key, value, scope)?;
+    println!("Set {} = {}", key.sage(), value);
+    Ok(())
+}
+
+/// unset removes a value from the given scope
+pub fn unset(key: &str, scope: Scope) -> Result<()> {
+    if !config::unset(key, scope)? {
+        return Err(anyhow!("{} is not set", key));
+    }
+
+    println!("Unset {}", key.sage());
+    Ok(())
+}
+
+/// list prints every configured value along with the settings sage supports
+pub fn list() -> Result<()> {
+    let policy = crate::policy::config_defaults();
+    let global = config::read(Scope::Global)?;
+    let local = if crate::git::repo::is_repo().unwrap_or(false) {
+        config::read(Scope::Local)?
+    } else {
+        Default::default()
+    };
+
+    println!("{}", "Configured values:".sage().bold());
+    if policy.is_empty() && global.is_empty() && local.is_empty() {
+        println!("  {}", "Nothing configured yet".gray());
+    }
+    for (key, value) in &policy {
+        if !global.contains_key(key) && !local.contains_key(key) {
+            println!("  {} = {} {}", key.yellow(), value, "(policy)".gray());
+        }
+    }
+    for (key, value) in &global {
+        // Local values shadow global ones
+        if !local.contains_key(key) {
+            println!("  {} = {} {}", key.yellow(), value, "(global)".gray());
+        }
+    }
+    for (key, value) in &local {
+        println!("  {} = {} {}", key.yellow(), value, "(local)".gray());
+    }
+
+    println!();
+    println!("{}", "Available settings:".sage().bold());
+    // Descriptions wrap under themselves rather than back under the key
+    let room = ui::text::terminal_width().map(|columns| columns.saturating_sub(27).max(20));
+    for (key, description) in config::KNOWN_KEYS {
+        let lines = match room {
+            Some(room) => ui::text::wrap(description, room),
+            None => vec![description.to_string()],
+        };
+        for (index, line) in lines.iter().enumerate() {
+            let key = if index == 0 { *key } else { "" };
+            println!("  {} {}", ui::text::pad(key, 24), line.gray());
+        }
+    }
+
+    Ok(())
+}
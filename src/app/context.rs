@@ -0,0 +1,322 @@
+//! A local index of the repository, so the AI sees the files related to a change
+//!
+//! `sage ai index` summarizes every file at HEAD into `.git/sage_index/index.json`: a few lines
+//! of what the file declares, and the identifiers it uses most as a small local embedding. Once
+//! the index exists, `sage commit` keeps it up to date, reading only the files whose contents
+//! changed, and the prompts for commit messages, pull requests, reviews and explanations get the
+//! summaries of the files closest to the diff. Nothing leaves the machine to build it, and files
+//! matching `ai.exclude_paths` are never indexed.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{app::{guard::glob_match, redact}, config, errors, git, ui::ColorizeExt};
+
+/// Files bigger than this are left out, as they're rarely written by hand
+const MAX_FILE_BYTES: u64 = 256 * 1024;
+/// Identifiers kept per file
+const MAX_TERMS: usize = 64;
+/// Declaration lines kept per file
+const MAX_DECLARATIONS: usize = 12;
+/// Related files handed to the AI
+const MAX_RELATED: usize = 5;
+/// Files read from git at once while indexing
+const BATCH: usize = 500;
+
+/// Lines that start these are worth a place in a summary
+const DECLARATIONS: &[&str] = &[
+    "pub fn ", "fn ", "pub async fn ", "async fn ", "pub struct ", "struct ", "pub enum ", "enum ", "pub trait ", "trait ",
+    "impl ", "pub mod ", "class ", "def ", "func ", "function ", "export ", "interface ", "type ", "module ",
+];
+/// Words too common in code to say anything about a file
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "let", "mut", "pub", "use", "self", "return", "this", "that", "with", "from", "true", "false",
+    "none", "some", "string", "str", "new", "def", "func", "function", "const", "var", "else", "null", "import", "crate",
+];
+
+/// What's known about one file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    /// The blob the entry was made from, to tell when the file changed
+    id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    summary: Vec<String>,
+    /// How often the file uses each of its most common identifiers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    terms: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    files: BTreeMap<String, Entry>,
+}
+
+/// What a refresh changed
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Refresh {
+    pub indexed: usize,
+    pub removed: usize,
+    pub total: usize,
+}
+
+/// path returns where the index for the current repository is kept
+fn path() -> Result<PathBuf> {
+    Ok(git::repo::git_dir()?.join("sage_index").join("index.json"))
+}
+
+/// exists says whether the repository has an index, which is what turns it on
+pub fn exists() -> bool {
+    path().is_ok_and(|path| path.exists())
+}
+
+fn load() -> Result<Index> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Index::default());
+    }
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse index {}", path.display()))
+}
+
+fn save(index: &Index) -> Result<()> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(index)?)?;
+    Ok(())
+}
+
+/// refresh brings the index up to date with HEAD, reading only the files that changed since
+pub fn refresh() -> Result<Refresh> {
+    let mut index = load()?;
+    let excludes = redact::excludes(config::get("ai.exclude_paths"));
+    let files = git::files::tree_files("HEAD")?
+        .into_iter()
+        .filter(|file| file.size <= MAX_FILE_BYTES && !excludes.iter().any(|pattern| glob_match(pattern, &file.path)))
+        .collect::<Vec<_>>();
+
+    let present = files.iter().map(|file| file.path.as_str()).collect::<HashSet<_>>();
+    let before = index.files.len();
+    index.files.retain(|path, _| present.contains(path.as_str()));
+    let removed = before - index.files.len();
+
+    let changed = files
+        .iter()
+        .filter(|file| index.files.get(&file.path).is_none_or(|entry| entry.id != file.id))
+        .collect::<Vec<_>>();
+    for batch in changed.chunks(BATCH) {
+        let ids = batch.iter().map(|file| file.id.clone()).collect::<Vec<_>>();
+        for (file, contents) in batch.iter().zip(git::files::read_blobs(&ids)?) {
+            index.files.insert(file.path.clone(), summarize(&file.path, &file.id, &contents));
+        }
+    }
+
+    save(&index)?;
+    Ok(Refresh { indexed: changed.len(), removed, total: index.files.len() })
+}
+
+/// Summarize a file: its opening comment and declarations, and the identifiers it uses most.
+/// Binary files get an empty entry, so they aren't read again until they change.
+fn summarize(path: &str, id: &str, contents: &[u8]) -> Entry {
+    let mut entry = Entry { id: id.to_string(), ..Entry::default() };
+    if contents.contains(&0) {
+        return entry;
+    }
+    let text = String::from_utf8_lossy(contents);
+    let clip = |line: &str| line.chars().take(100).collect::<String>();
+
+    let comment = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty() || line.starts_with("#!"))
+        .take_while(|line| ["//", "#", "/*", "*", "\"\"\"", "--"].iter().any(|marker| line.starts_with(marker)))
+        .map(|line| line.trim_start_matches(['/', '#', '*', '"', '-', '!']).trim())
+        .find(|line| !line.is_empty());
+    entry.summary.extend(comment.map(clip));
+
+    let declarations = text
+        .lines()
+        .filter(|line| line.len() - line.trim_start().len() <= 4)
+        .map(|line| line.trim().trim_end_matches('{').trim_end())
+        .filter(|line| DECLARATIONS.iter().any(|declaration| line.starts_with(declaration)))
+        .take(MAX_DECLARATIONS)
+        .map(clip);
+    entry.summary.extend(declarations);
+
+    let mut terms = count_terms(&text);
+    // The file's own name says a lot about it
+    for part in path.split(['/', '.', '_', '-']).filter(|part| part.len() >= 3) {
+        *terms.entry(part.to_lowercase()).or_default() += 2;
+    }
+    let mut ranked = terms.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(MAX_TERMS);
+    entry.terms = ranked.into_iter().collect();
+    entry
+}
+
+/// Count the identifiers in `text`, split into their words, e.g. `parseTree` and `parse_tree`
+/// both count as `parse` and `tree`
+fn count_terms(text: &str) -> BTreeMap<String, u32> {
+    let mut terms = BTreeMap::new();
+    for identifier in text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) {
+        let mut word = String::new();
+        let mut words = Vec::new();
+        let mut previous = '_';
+        for c in identifier.chars() {
+            if c == '_' || (c.is_ascii_uppercase() && previous.is_ascii_lowercase()) {
+                words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+            }
+            if c != '_' {
+                word.push(c.to_ascii_lowercase());
+            }
+            previous = c;
+        }
+        words.extend((!word.is_empty()).then_some(word));
+
+        for word in words {
+            if word.len() >= 3 && !word.starts_with(|c: char| c.is_ascii_digit()) && !STOP_WORDS.contains(&word.as_str()) {
+                *terms.entry(word).or_insert(0) += 1;
+            }
+        }
+    }
+    terms
+}
+
+/// related returns the summaries of the files closest to what `diff` changes, ready to put in
+/// a prompt, or nothing when the repository has no index
+pub fn related(diff: &str) -> Result<String> {
+    if diff.trim().is_empty() || !exists() {
+        return Ok(String::new());
+    }
+    let index = load()?;
+    let changed = diff
+        .lines()
+        .filter_map(|line| line.strip_prefix("diff --git a/")?.rsplit_once(" b/").map(|(_, path)| path.trim()))
+        .collect::<HashSet<_>>();
+
+    Ok(closest(&index, &changed, diff)
+        .into_iter()
+        .map(|(path, entry)| format!("{}\n{}", path, entry.summary.iter().map(|line| format!("  {}\n", line)).collect::<String>()))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// The files most like the changed ones and the diff, by cosine similarity of their identifiers
+/// weighted by how rare each is across the repository
+fn closest<'a>(index: &'a Index, changed: &HashSet<&str>, diff: &str) -> Vec<(&'a String, &'a Entry)> {
+    let mut query = count_terms(diff);
+    for entry in changed.iter().filter_map(|path| index.files.get(*path)) {
+        for (term, count) in &entry.terms {
+            *query.entry(term.clone()).or_insert(0) += count;
+        }
+    }
+
+    let mut documents = BTreeMap::<&str, u32>::new();
+    for entry in index.files.values() {
+        for term in entry.terms.keys() {
+            *documents.entry(term).or_insert(0) += 1;
+        }
+    }
+    let files = index.files.len() as f64;
+    let weight = |term: &str, count: u32| {
+        let rarity = (files / documents.get(term).copied().unwrap_or(1) as f64).ln();
+        count as f64 * rarity
+    };
+    let norm = |terms: &BTreeMap<String, u32>| terms.iter().map(|(term, &count)| weight(term, count).powi(2)).sum::<f64>().sqrt();
+    let query_norm = norm(&query);
+
+    let mut scored = index
+        .files
+        .iter()
+        .filter(|(path, entry)| !changed.contains(path.as_str()) && !entry.summary.is_empty())
+        .filter_map(|(path, entry)| {
+            let dot = entry
+                .terms
+                .iter()
+                .filter_map(|(term, &count)| query.get(term).map(|&asked| weight(term, count) * weight(term, asked)))
+                .sum::<f64>();
+            let score = dot / (norm(&entry.terms) * query_norm);
+            (score > 0.0).then_some((score, path, entry))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored.into_iter().take(MAX_RELATED).map(|(_, path, entry)| (path, entry)).collect()
+}
+
+/// index is `sage ai index`: build or refresh the index, or remove it with `clear`
+pub fn index(clear: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    if clear {
+        let path = path()?;
+        match path.parent().filter(|_| path.exists()) {
+            Some(dir) => {
+                fs::remove_dir_all(dir)?;
+                println!("✨ Removed the index. The AI no longer gets related files as context");
+            }
+            None => println!("{}", "There's no index to remove".gray()),
+        }
+        return Ok(());
+    }
+
+    let refresh = refresh()?;
+    println!(
+        "✨ Indexed {} {} ({} removed), {} in all",
+        refresh.indexed,
+        if refresh.indexed == 1 { "file" } else { "files" },
+        refresh.removed,
+        refresh.total
+    );
+    println!("   {}", "sage commit keeps it up to date from now on".gray());
+    Ok(())
+}
+
+/// after_commit refreshes the index, if there is one, once a commit is made. The commit is
+/// already done, so a failure only warns.
+pub fn after_commit() {
+    if exists() && let Err(e) = refresh() {
+        println!("{} Could not refresh the index for the AI: {}", "WARNING:".yellow(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let source = b"//! Parsing trees\nuse std::fs;\n\npub fn parseTree(input: &str) -> Tree {\n    parse_tree_node(input)\n}\n";
+        let entry = summarize("src/tree.rs", "abc", source);
+        assert_eq!(entry.summary, vec!["Parsing trees", "pub fn parseTree(input: &str) -> Tree"]);
+        assert_eq!(entry.terms.get("parse"), Some(&2));
+        assert_eq!(entry.terms.get("tree"), Some(&5));
+        assert!(!entry.terms.contains_key("use"));
+
+        assert_eq!(summarize("logo.png", "def", b"\x89PNG\x00"), Entry { id: "def".to_string(), ..Entry::default() });
+    }
+
+    #[test]
+    fn test_closest() {
+        let mut index = Index::default();
+        for (path, source) in [
+            ("src/tree.rs", "pub fn parse_tree() {}"),
+            ("src/tree_walk.rs", "pub fn walk_tree(tree: Tree) { visit_node(tree) }"),
+            ("src/http.rs", "pub fn fetch_url(url: Url) { request(url) }"),
+        ] {
+            index.files.insert(path.to_string(), summarize(path, "id", source.as_bytes()));
+        }
+
+        let changed = HashSet::from(["src/tree.rs"]);
+        let related = closest(&index, &changed, "+    let node = parse_tree();");
+        assert_eq!(related.into_iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>(), vec!["src/tree_walk.rs"]);
+    }
+}
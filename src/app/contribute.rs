@@ -0,0 +1,135 @@
+//! `sage contribute`: set up to work on someone else's repository in one go
+//!
+//! When you can't push to the repository it's forked into your account (or your existing fork is
+//! used), the fork is cloned as origin with the original added as upstream, and a branch is
+//! started from upstream's default branch and recorded on the stack, ready for
+//! `sage sync --upstream`.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::env;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{
+    app::{start, sync::UPSTREAM_REMOTE},
+    gh::{self, repos::Repository},
+    git,
+    ui::{accessible::{self, Mark}, ColorizeExt},
+};
+
+/// How many times to try cloning a fork before giving up on GitHub finishing it
+const CLONE_ATTEMPTS: u32 = 10;
+
+/// contribute forks `target` (owner/repo or a GitHub URL) when needed, clones it into a directory
+/// named after it and starts `branch` there
+pub async fn contribute(target: &str, branch: &str, ssh: bool) -> Result<()> {
+    let (owner, repo) = parse_target(target)?;
+    if Path::new(&repo).exists() {
+        return Err(anyhow!("Directory '{}' already exists", repo));
+    }
+
+    let upstream = gh::repos::get(&owner, &repo).await?;
+    let login = gh::repos::viewer().await?;
+    let can_push = upstream.owner.login.eq_ignore_ascii_case(&login)
+        || upstream.permissions.as_ref().is_some_and(|permissions| permissions.push);
+
+    let origin = if can_push {
+        println!("You can push to {}, so there's no need for a fork", upstream.full_name.sage());
+        upstream.clone()
+    } else {
+        println!("Forking {} into {}...", upstream.full_name.sage(), login.sage());
+        gh::repos::fork(&owner, &repo).await?
+    };
+
+    // A fork renamed to avoid a clash is cloned under its own name
+    let dir = Path::new(&origin.name);
+    if dir.exists() {
+        return Err(anyhow!("Directory '{}' already exists", origin.name));
+    }
+    println!("Cloning {}...", origin.full_name.sage());
+    clone(&origin, ssh, dir).await?;
+    env::set_current_dir(dir)?;
+
+    if !can_push {
+        git::remote::add(UPSTREAM_REMOTE, &clone_url(&upstream, ssh))?;
+        git::remote::fetch_branch(UPSTREAM_REMOTE, &upstream.default_branch)?;
+
+        // A fork made a while ago can be behind the repository it came from
+        let upstream_ref = format!("{}/{}", UPSTREAM_REMOTE, upstream.default_branch);
+        if git::branch::fast_forward(&upstream_ref).is_err() {
+            println!(
+                "{} Your fork's {} has commits {} doesn't, so it was left as it is",
+                "WARNING:".yellow(),
+                upstream.default_branch,
+                upstream_ref
+            );
+        }
+    }
+
+    let default_branch = git::repo::default_branch()?;
+    let branch = start::start(branch, None)?;
+    git::stack::set_parent(&branch, &default_branch)?;
+
+    println!("✨ Ready to contribute to {} in {}, on {}", upstream.full_name.sage(), origin.name.sage(), branch.sage());
+    let bullet = accessible::mark(Mark::Bullet).sage();
+    println!("  {} origin: {}", bullet, origin.full_name);
+    if !can_push {
+        println!("  {} {}: {}", bullet, UPSTREAM_REMOTE, upstream.full_name);
+        println!("Keep up with {} with {}", UPSTREAM_REMOTE, "sage sync --upstream".sage());
+    }
+    Ok(())
+}
+
+/// Clone `repository` into `dir`. GitHub makes a new fork in the background, and until it's done
+/// the fork can be looked up but not cloned, so cloning is tried again for a while.
+async fn clone(repository: &Repository, ssh: bool, dir: &Path) -> Result<()> {
+    let url = clone_url(repository, ssh);
+    for attempt in 1.. {
+        match git::repo::clone_into(&url, dir) {
+            Ok(()) => break,
+            Err(_) if attempt < CLONE_ATTEMPTS => tokio::time::sleep(Duration::from_secs(2)).await,
+            Err(e) => return Err(anyhow!("Could not clone {}, GitHub may not have finished making it: {}", repository.full_name, e)),
+        }
+    }
+    Ok(())
+}
+
+fn clone_url(repository: &Repository, ssh: bool) -> String {
+    if ssh { repository.ssh_url.clone() } else { repository.clone_url.clone() }
+}
+
+/// The owner and name of the repository to contribute to, from owner/repo or a clone URL
+fn parse_target(target: &str) -> Result<(String, String)> {
+    let target = target.trim();
+    if target.contains("://") || target.contains('@') {
+        return git::repo::parse_remote_url(target)
+            .map(|(_, owner, repo)| (owner, repo))
+            .ok_or_else(|| anyhow!("Could not work out the repository from {}", target));
+    }
+
+    match target.trim_end_matches(".git").split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Ok((owner.to_string(), repo.to_string()))
+        }
+        _ => Err(anyhow!("Please provide the repository as {}", "owner/repo".sage())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        let parsed = |target| parse_target(target).ok();
+        let expected = Some(("octocat".to_string(), "Hello-World".to_string()));
+        assert_eq!(parsed("octocat/Hello-World"), expected);
+        assert_eq!(parsed("octocat/Hello-World.git"), expected);
+        assert_eq!(parsed("https://github.com/octocat/Hello-World"), expected);
+        assert_eq!(parsed("git@github.com:octocat/Hello-World.git"), expected);
+        assert_eq!(parsed("Hello-World"), None);
+        assert_eq!(parsed("octocat/"), None);
+        assert_eq!(parsed("a/b/c"), None);
+    }
+}
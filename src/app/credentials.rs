@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use inquire::Confirm;
+use std::io::{self, IsTerminal};
+
+use crate::{config, git::{self, remote::{AccessProblem, AgentStatus, Protocol}}, ui::ColorizeExt};
+
+/// check_remote makes sure we can authenticate against `remote` before pushing to it.
+/// When we can't, it explains why for the remote's protocol, and offers to switch the remote
+/// between SSH and HTTPS (or does so straight away when `switch_protocol` is set).
+pub fn check_remote(remote: &str, switch_protocol: bool) -> Result<()> {
+    if !config::get_bool("push.probe", true) && !switch_protocol {
+        return Ok(());
+    }
+
+    let url = git::repo::remote_url(remote)?.ok_or_else(|| anyhow!("No remote named {}", remote))?;
+    let protocol = git::remote::protocol(&url);
+    if protocol == Protocol::Other {
+        return Ok(());
+    }
+
+    let Some((problem, stderr)) = git::remote::probe(remote)? else {
+        return Ok(());
+    };
+
+    let host = git::repo::parse_remote_url(&url).map(|(host, _, _)| host).unwrap_or_default();
+    println!("{} Sage can't access {} ({})", "WARNING:".yellow(), remote.yellow(), url.gray());
+    for line in advice(problem, protocol, &host, git::remote::ssh_agent()) {
+        println!("  {}", line);
+    }
+
+    // Offer the other protocol when the problem is with credentials for this one
+    let switched = match (problem, protocol) {
+        (AccessProblem::SshKey | AccessProblem::HostKey, Protocol::Ssh) => git::remote::to_https_url(&url),
+        (AccessProblem::HttpsCredentials, Protocol::Https) => git::remote::to_ssh_url(&url),
+        _ => None,
+    };
+
+    if let Some(switched) = switched {
+        let confirmed = switch_protocol
+            || (io::stdin().is_terminal()
+                && Confirm::new(&format!("Switch {} to {}?", remote, switched))
+                    .with_default(false)
+                    .prompt()?);
+
+        if confirmed {
+            git::remote::set_url(remote, &switched)?;
+            if git::remote::probe(remote)?.is_none() {
+                println!("✨ Switched {} to {}", remote, switched.sage());
+                return Ok(());
+            }
+
+            // Don't leave the remote on a URL that doesn't work either
+            git::remote::set_url(remote, &url)?;
+            println!("{} {} didn't work either, so {} was left unchanged", "WARNING:".yellow(), switched, remote);
+        }
+    }
+
+    Err(anyhow!("Cannot access {}: {}", remote, stderr.lines().next().unwrap_or_default()))
+}
+
+/// Explain how to fix an access problem
+fn advice(problem: AccessProblem, protocol: Protocol, host: &str, agent: AgentStatus) -> Vec<String> {
+    match problem {
+        AccessProblem::SshKey => {
+            let mut lines = vec![format!("{} didn't accept any of your SSH keys.", host)];
+            lines.push(match agent {
+                AgentStatus::Missing => "No ssh-agent is running (SSH_AUTH_SOCK isn't set). Start one and 'ssh-add' your key, \
+                    or on a remote machine connect with agent forwarding ('ssh -A')."
+                    .to_string(),
+                AgentStatus::Unreachable => "SSH_AUTH_SOCK is set but the agent isn't answering; a forwarded agent may have \
+                    gone away. Reconnect, or start a new agent."
+                    .to_string(),
+                AgentStatus::Empty => "Your ssh-agent has no keys loaded. Add one with 'ssh-add'.".to_string(),
+                AgentStatus::Ready => format!("Make sure the public key is added to your account on {}.", host),
+            });
+            lines
+        }
+        AccessProblem::HostKey => vec![
+            format!("The SSH host key for {} isn't known or has changed.", host),
+            format!("Check it against the host's published fingerprints, then run 'ssh -T git@{}' to accept it.", host),
+        ],
+        AccessProblem::HttpsCredentials => vec![
+            format!("Git has no working HTTPS credentials for {}.", host),
+            "Set up a credential helper (for GitHub, 'gh auth setup-git'), or enter a personal access token as the password."
+                .to_string(),
+        ],
+        AccessProblem::NotFound => vec![
+            "The repository doesn't exist, or your account can't see it.".to_string(),
+            match protocol {
+                Protocol::Ssh => "Check the remote URL, and that the SSH key belongs to an account with access.".to_string(),
+                _ => "Check the remote URL, and that your credentials belong to an account with access.".to_string(),
+            },
+        ],
+        AccessProblem::Network => vec![format!("{} couldn't be reached. Check your network connection or proxy.", host)],
+        AccessProblem::Unknown => vec!["Run 'git ls-remote' against the remote to see the full error.".to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advice_mentions_agent_forwarding_without_an_agent() {
+        let lines = advice(AccessProblem::SshKey, Protocol::Ssh, "github.com", AgentStatus::Missing);
+        assert_eq!(lines[0], "github.com didn't accept any of your SSH keys.");
+        assert!(lines[1].contains("ssh -A"));
+    }
+}
@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crate::{config, errors, git, ui::{actions::Annotation, ColorizeExt}};
+
+/// Config key that turns on DCO enforcement
+pub const SIGNOFF_KEY: &str = "commit.signoff";
+
+/// signoff_enabled returns if sage should sign off commits and enforce it on push
+pub fn signoff_enabled() -> bool {
+    config::get_bool(SIGNOFF_KEY, false)
+}
+
+/// Find the commit outgoing commits are measured from: the upstream if there is one,
+/// otherwise the default branch
+pub(crate) fn outgoing_base(branch: &str) -> Result<String> {
+    // The upstream may have been deleted on the remote, so make sure it still resolves
+    if let Some(upstream) = git::branch::upstream(branch)?.filter(|upstream| git::repo::rev_exists(upstream)) {
+        return Ok(upstream);
+    }
+
+    let default_branch = git::repo::default_branch()?;
+    if git::repo::remote_branch_exists(&default_branch) {
+        return Ok(format!("origin/{}", default_branch));
+    }
+    Ok(default_branch)
+}
+
+/// verify_outgoing fails when DCO enforcement is on and a commit about to be pushed isn't signed off
+pub fn verify_outgoing(branch: &str) -> Result<()> {
+    if !signoff_enabled() {
+        return Ok(());
+    }
+
+    let base = outgoing_base(branch)?;
+    let missing = git::commit::missing_signoff(&format!("{}..{}", base, branch))?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    println!("{}", "These commits are missing a Signed-off-by trailer:".red().bold());
+    for commit in &missing {
+        println!("  {} {} {}", commit.hash.bright_yellow(), commit.subject, format!("({})", commit.author).gray());
+        let message = format!("{} {} is missing a Signed-off-by trailer. Run sage fix-dco to add it", commit.hash, commit.subject);
+        Annotation { title: "DCO", message: &message, ..Annotation::default() }.emit();
+    }
+    println!("\nRun {} to add them.", "sage fix-dco".sage());
+
+    Err(anyhow!("{} commit(s) are missing Signed-off-by", missing.len()))
+}
+
+/// fix_dco adds Signed-off-by trailers to every commit on the current branch that lacks one
+pub fn fix_dco() -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let parent = match git::stack::parent(&current_branch)? {
+        Some(parent) => parent,
+        None => git::repo::default_branch()?,
+    };
+    let base = git::repo::merge_base(&parent, "HEAD")?;
+
+    let missing = git::commit::missing_signoff(&format!("{}..HEAD", base))?;
+    if missing.is_empty() {
+        println!("✨ All commits on {} are already signed off!", current_branch.sage());
+        return Ok(());
+    }
+
+    if !git::status::is_clean()? {
+        return Err(anyhow!("Working tree has uncommitted changes. Commit or stash them before rewriting history"));
+    }
+
+    println!("Adding Signed-off-by to {} commit(s)...", missing.len());
+    git::commit::signoff_since(&base)?;
+
+    println!("✨ Signed off all commits on {}!", current_branch.sage());
+    println!("History was rewritten, so push with {} to update the remote.", "sage push --force".yellow());
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crate::{errors, gh::pulls, git, ui::{pager::{self, Pager}, ColorizeExt}};
+use std::fmt::Write;
+
+/// What the current branch should be compared against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffTarget {
+    /// Uncommitted changes against HEAD
+    #[default]
+    WorkingTree,
+    /// The stack parent of the current branch (falls back to the default branch)
+    Parent,
+    /// The merge-base with the default branch
+    Default,
+    /// The base branch of the pull request for the current branch
+    PullRequest,
+}
+
+#[derive(Default)]
+pub struct DiffOptions {
+    /// What to compare against
+    pub target: DiffTarget,
+    /// Print the raw patch without any styling
+    pub patch: bool,
+    /// Only show a diffstat summary
+    pub stat: bool,
+}
+
+pub async fn diff(opts: &DiffOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+
+    let base = match opts.target {
+        DiffTarget::WorkingTree => None,
+        DiffTarget::Parent => {
+            let parent = match git::stack::parent(&current_branch)? {
+                Some(parent) => parent,
+                None => git::repo::default_branch()?,
+            };
+            Some(parent)
+        }
+        DiffTarget::Default => Some(remote_or_local(&git::repo::default_branch()?)),
+        DiffTarget::PullRequest => {
+            let pull_request = pulls::get_by_branch(&current_branch)
+                .await?
+                .ok_or_else(|| anyhow!("No pull request associated with the current branch '{}'", current_branch))?;
+
+            // Make sure we compare against the latest state of the PR base
+            let base_branch = pull_request.base.ref_field;
+            git::repo::fetch_branch(&base_branch)?;
+            Some(format!("origin/{}", base_branch))
+        }
+    };
+
+    let range = match &base {
+        Some(base) => format!("{}...HEAD", base),
+        None => String::new(),
+    };
+
+    let output = git::repo::diff_range(&range, opts.stat)?;
+
+    // Raw patches are meant for piping, so we skip all decoration
+    if opts.patch {
+        return pager::page(&output);
+    }
+
+    let mut out = Pager::new();
+    match &base {
+        Some(base) => {
+            let merge_base = git::repo::merge_base(base, "HEAD")?;
+            writeln!(
+                out,
+                "{} {} {} {} ({} {})",
+                "Diff:".sage().bold(),
+                current_branch.yellow(),
+                "vs".gray(),
+                base.yellow(),
+                "merge-base".gray(),
+                merge_base.chars().take(7).collect::<String>().bright_yellow()
+            )?;
+        }
+        None => writeln!(out, "{} {}", "Diff:".sage().bold(), "working tree vs HEAD".yellow())?,
+    }
+    writeln!(out)?;
+
+    if output.trim().is_empty() {
+        writeln!(out, "{}", "No differences found".bright_green())?;
+        return out.finish();
+    }
+
+    for line in output.lines() {
+        writeln!(out, "{}", render_line(line))?;
+    }
+
+    out.finish()
+}
+
+/// Prefer the remote-tracking branch so we compare against what's actually upstream
+fn remote_or_local(branch: &str) -> String {
+    if git::repo::remote_branch_exists(branch) {
+        format!("origin/{}", branch)
+    } else {
+        branch.to_string()
+    }
+}
+
+/// Colorize a single line of unified diff output
+fn render_line(line: &str) -> String {
+    if line.starts_with("diff --git")
+        || line.starts_with("index ")
+        || line.starts_with("+++")
+        || line.starts_with("---")
+    {
+        line.bold().to_string()
+    } else if line.starts_with("@@") {
+        line.cyan().to_string()
+    } else if line.starts_with('+') {
+        line.green().to_string()
+    } else if line.starts_with('-') {
+        line.red().to_string()
+    } else {
+        line.to_string()
+    }
+}
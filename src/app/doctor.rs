@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+
+use crate::{config::{self, Scope}, git, plugin, policy, ui::{accessible::Mark, theme, ColorizeExt}};
+
+/// One thing doctor looked at, and what's wrong with it if anything
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub problem: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>) -> Check {
+        Check { name: name.into(), problem: None }
+    }
+
+    fn fail(name: impl Into<String>, problem: impl Into<String>) -> Check {
+        Check { name: name.into(), problem: Some(problem.into()) }
+    }
+}
+
+/// doctor checks this machine and repository follow the org policy: that the bundle synced is
+/// the configured one, its plugins are installed as it ships them with valid signatures and its
+/// settings aren't overridden
+pub fn doctor() -> Result<()> {
+    let Some(source) = config::get("policy.source") else {
+        println!("No org policy is configured (policy.source), so there's nothing to check");
+        return Ok(());
+    };
+
+    let checks = policy_checks(&source)?;
+    for check in &checks {
+        match &check.problem {
+            None => println!("{} {}", theme::styled(Mark::Passing), check.name),
+            Some(problem) => println!("{} {}: {}", theme::styled(Mark::Failing), check.name, problem),
+        }
+    }
+
+    let problems = checks.iter().filter(|check| check.problem.is_some()).count();
+    if problems > 0 {
+        return Err(anyhow!(
+            "{} {} with the org policy. Run {} to fix what it can",
+            problems,
+            if problems == 1 { "problem" } else { "problems" },
+            "sage policy sync"
+        ));
+    }
+    println!("✨ Everything follows the org policy");
+    Ok(())
+}
+
+fn policy_checks(source: &str) -> Result<Vec<Check>> {
+    let (Some(policy), Some(installed)) = (policy::load()?, policy::installed()?) else {
+        return Ok(vec![Check::fail("policy bundle", "not synced yet")]);
+    };
+
+    let mut checks = Vec::new();
+    let synced = format!("synced {}", installed.synced_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"));
+    if installed.source == source {
+        checks.push(Check::pass(format!("{} policy {}", policy.name, synced.gray())));
+    } else {
+        checks.push(Check::fail(
+            format!("{} policy", policy.name),
+            format!("{} from {}, but policy.source is now {}", synced, installed.source, source),
+        ));
+    }
+
+    let plugins = plugin::discover()?;
+    for name in &policy.plugins {
+        let check = format!("plugin {}", name);
+        let bundled = policy::bundled_plugin(name)?;
+        let Some(plugin) = plugins.iter().find(|plugin| &plugin.manifest.name == name) else {
+            checks.push(Check::fail(check, "not installed"));
+            continue;
+        };
+        if plugin.manifest.version != bundled.version {
+            checks.push(Check::fail(
+                check,
+                format!("{} is installed, the policy ships {}", version(&plugin.manifest.version), version(&bundled.version)),
+            ));
+        } else if plugin.manifest.hooks != bundled.hooks {
+            checks.push(Check::fail(check, "runs on different hooks than the policy ships it with"));
+        } else if let Err(e) = plugin::verify(&plugin.dir) {
+            checks.push(Check::fail(check, e.to_string()));
+        } else {
+            checks.push(Check::pass(check));
+        }
+    }
+
+    let global = config::read(Scope::Global)?;
+    let local = if git::repo::is_repo().unwrap_or(false) { config::read(Scope::Local)? } else { Default::default() };
+    for (key, expected) in &policy.config {
+        let overridden = [("repository", &local), ("global", &global)]
+            .into_iter()
+            .find_map(|(scope, values)| values.get(key).map(|value| (scope, value)));
+        match overridden {
+            Some((scope, value)) if value != expected => checks.push(Check::fail(
+                key.clone(),
+                format!("{} in the {} config, the policy sets {}", value, scope, expected),
+            )),
+            _ => checks.push(Check::pass(format!("{} = {}", key, expected))),
+        }
+    }
+
+    Ok(checks)
+}
+
+fn version(version: &str) -> String {
+    if version.is_empty() { "an unversioned copy".to_string() } else { format!("version {}", version) }
+}
@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use colored::Colorize;
+
+use crate::{app::{prompts::{self, Inputs, Prompt}, snapshot::{self, RefChange}}, errors, git, ledger, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// What to explain
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    /// A range of commits git understands, e.g. `main..feature`
+    Commits(String),
+    /// An operation in the ledger, by id
+    Operation(u64),
+}
+
+/// What's known about a change, shown as it is offline and handed to the AI otherwise
+#[derive(Debug, Default)]
+struct Facts {
+    title: String,
+    lines: Vec<String>,
+    diff: String,
+}
+
+impl Facts {
+    fn as_text(&self) -> String {
+        std::iter::once(self.title.as_str()).chain(self.lines.iter().map(String::as_str)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Work out what to explain: `op:12` is operation 12, a range like `main..feature` is the
+/// commits in it, and anything else is a single commit
+fn parse_target(target: &str) -> Result<Target> {
+    if let Some(id) = target.strip_prefix("op:") {
+        return id
+            .parse()
+            .map(Target::Operation)
+            .map_err(|_| anyhow!("'{}' isn't an operation id. See {}", id, "sage snapshot list"));
+    }
+    if target.contains("..") {
+        return Ok(Target::Commits(target.to_string()));
+    }
+    Ok(Target::Commits(format!("{}^!", target)))
+}
+
+/// explain describes a commit, range or sage operation: the facts first, then the AI's plain
+/// English explanation of what changed and why it might matter, unless `offline`
+pub async fn explain(target: &str, offline: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let facts = match parse_target(target)? {
+        Target::Commits(range) => commit_facts(&range)?,
+        Target::Operation(id) => operation_facts(id)?,
+    };
+
+    println!("{}", facts.title.sage().bold());
+    let bullet = accessible::mark(Mark::Bullet).sage();
+    for line in &facts.lines {
+        println!("  {} {}", bullet, line);
+    }
+
+    if offline {
+        return Ok(());
+    }
+    if facts.diff.trim().is_empty() && facts.lines.is_empty() {
+        println!("\n{}", "Nothing changed, so there's nothing to explain".gray());
+        return Ok(());
+    }
+
+    let prompt = prompts::render(Prompt::Explain, Inputs { facts: &facts.as_text(), diff: &facts.diff, ..Inputs::default() })?;
+    // The facts stand on their own, so an AI that can't be reached only warns
+    match prompts::ask("explain", &prompt).await {
+        Ok(explanation) => println!("\n{}", explanation.trim()),
+        Err(e) => println!("\n{} Could not ask the AI, showing the facts only: {}", "WARNING:".yellow(), e),
+    }
+    Ok(())
+}
+
+/// The commits in a range, what they touch and their diff
+fn commit_facts(range: &str) -> Result<Facts> {
+    let log = git::repo::log_range(range).map_err(|e| anyhow!("Can't explain '{}': {}", range.trim_end_matches("^!"), e))?;
+    let commits = log.lines().filter(|line| !line.is_empty()).collect::<Vec<_>>();
+    let title = match commits.as_slice() {
+        [commit] => format!("Commit {}", commit),
+        _ => format!("{} commits in {}", commits.len(), range),
+    };
+
+    let mut lines = if commits.len() > 1 { commits.iter().map(|commit| commit.to_string()).collect() } else { Vec::new() };
+    let stat = git::repo::diff_range(range, true)?;
+    lines.extend(stat.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()));
+
+    Ok(Facts { title, lines, diff: git::repo::diff_range(range, false)? })
+}
+
+/// What an operation in the ledger did to the branches
+fn operation_facts(id: u64) -> Result<Facts> {
+    let entries = ledger::load()?;
+    let entry = entries
+        .iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| anyhow!("No operation {} in the ledger. See {}", id, "sage snapshot list"))?;
+
+    let title = format!(
+        "Operation #{}: {} on {}, started {} ({})",
+        entry.id,
+        entry.operation.name(),
+        entry.branch,
+        entry.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+        format!("{:?}", entry.status).to_lowercase()
+    );
+    let mut facts = Facts { title, ..Facts::default() };
+
+    for step in &entry.steps {
+        let error = step.error.as_ref().map(|error| format!(": {}", error.trim())).unwrap_or_default();
+        facts.lines.push(format!("{} {}{}", step.branch, format!("{:?}", step.outcome).to_lowercase(), error));
+    }
+    if let ledger::Operation::Hooks { event, plugins } = &entry.operation {
+        for run in plugins {
+            let said = run.message.as_deref().or(run.error.as_deref()).unwrap_or("nothing");
+            facts.lines.push(format!("{} on {}: {}", run.plugin, event, said));
+        }
+    }
+
+    if entry.refs_before.is_empty() || entry.refs_after.is_empty() {
+        facts.lines.push("No snapshot of the branches before and after it was kept".to_string());
+        return Ok(facts);
+    }
+    for change in snapshot::compare(&entry.refs_before, &entry.refs_after) {
+        match change {
+            RefChange::Created { branch, tip } => facts.lines.push(format!("{} created at {}", branch, git::short_oid(&tip))),
+            RefChange::Deleted { branch, tip } => facts.lines.push(format!("{} deleted, was at {}", branch, git::short_oid(&tip))),
+            RefChange::Moved { branch, from, to } => {
+                facts.lines.push(format!("{} moved from {} to {}", branch, git::short_oid(&from), git::short_oid(&to)));
+                // Commits an operation dropped may have been collected since
+                let range = format!("{}..{}", from, to);
+                if let (Ok(added), Ok(removed)) = (git::repo::log_range(&range), git::repo::log_range(&format!("{}..{}", to, from))) {
+                    facts.lines.extend(removed.lines().map(|commit| format!("{} lost {}", branch, commit)));
+                    facts.lines.extend(added.lines().map(|commit| format!("{} gained {}", branch, commit)));
+                }
+                if let Ok(diff) = git::repo::diff_range(&range, false) {
+                    facts.diff.push_str(&format!("# {}\n{}\n", branch, diff));
+                }
+            }
+        }
+    }
+    Ok(facts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(parse_target("op:12").unwrap(), Target::Operation(12));
+        assert!(parse_target("op:last").is_err());
+        assert_eq!(parse_target("main..feature").unwrap(), Target::Commits("main..feature".to_string()));
+        assert_eq!(parse_target("HEAD~2").unwrap(), Target::Commits("HEAD~2^!".to_string()));
+    }
+}
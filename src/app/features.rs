@@ -0,0 +1,36 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{config::{self, features::{self, Feature, Source}, Scope}, ui::ColorizeExt};
+
+/// list prints every experimental feature, whether it's on and why
+pub fn list() -> Result<()> {
+    for feature in Feature::ALL {
+        let (enabled, source) = features::state(*feature);
+        let state = if enabled { "on".green() } else { "off".normal() };
+        let source = match source {
+            Source::Env => format!("({})", features::UNSTABLE_ENV),
+            Source::Config => format!("(features.{})", feature.name()),
+            Source::Default => "(default)".to_string(),
+        };
+        println!("  {:<12} {:<4} {} {}", feature.name().sage(), state, feature.description(), source.gray());
+    }
+
+    for name in features::unknown_unstable() {
+        println!("{} {} lists {}, which isn't a feature", "WARNING:".yellow(), features::UNSTABLE_ENV, name);
+    }
+
+    Ok(())
+}
+
+/// set turns a feature on or off for every repository
+pub fn set(name: &str, enabled: bool) -> Result<()> {
+    let feature = Feature::from_name(name).ok_or_else(|| {
+        let names = Feature::ALL.iter().map(|feature| feature.name()).collect::<Vec<_>>().join(", ");
+        anyhow!("There is no feature called {}. Features: {}", name, names)
+    })?;
+
+    config::set(&format!("features.{}", feature.name()), &enabled.to_string(), Scope::Global)?;
+    println!("✨ Turned {} {}", feature.name().sage(), if enabled { "on" } else { "off" });
+    Ok(())
+}
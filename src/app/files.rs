@@ -0,0 +1,92 @@
+use anyhow::Result;
+use colored::Colorize;
+use inquire::Confirm;
+use crate::{app::{dco, restack}, errors, git, ledger, ui::ColorizeExt};
+
+/// mv renames a path, staging the rename so history follows the file
+pub fn mv(source: &str, destination: &str) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let affected = touched_in_descendants(&[source.to_string()])?;
+    let staged_elsewhere = git::files::staged_except(&[source.to_string(), destination.to_string()])?;
+
+    git::files::move_path(source, destination)?;
+    println!("✨ Moved {} to {}", source.yellow(), destination.sage());
+
+    offer_propagation(&affected, &staged_elsewhere, &format!("Move {} to {}", source, destination))
+}
+
+/// rm deletes paths, staging the deletion
+pub fn rm(paths: &[String], cached: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let affected = touched_in_descendants(paths)?;
+    let staged_elsewhere = git::files::staged_except(paths)?;
+
+    git::files::remove_paths(paths, cached)?;
+    for path in paths {
+        println!("✨ Removed {}{}", path.yellow(), if cached { " (kept on disk)" } else { "" });
+    }
+
+    offer_propagation(&affected, &staged_elsewhere, &format!("Remove {}", paths.join(", ")))
+}
+
+/// Branches stacked on the current one that modify any of `paths`, with the files they touch
+fn touched_in_descendants(paths: &[String]) -> Result<Vec<(String, Vec<String>)>> {
+    let current_branch = git::branch::current()?;
+
+    let mut affected = Vec::new();
+    for child in git::stack::descendants(&current_branch)? {
+        // Only the changes the child made since it forked from this branch matter
+        let files = git::files::changed_files(&format!("{}...{}", current_branch, child), paths)?;
+        if !files.is_empty() {
+            affected.push((child, files));
+        }
+    }
+
+    Ok(affected)
+}
+
+/// Warn about upcoming restack conflicts and offer to commit and restack right away. The commit
+/// would take everything staged with it, so it's only offered when nothing else is.
+fn offer_propagation(affected: &[(String, Vec<String>)], staged_elsewhere: &[String], message: &str) -> Result<()> {
+    if affected.is_empty() {
+        return Ok(());
+    }
+
+    println!("\n{} Branches stacked on this one also change these files:", "WARNING:".yellow());
+    for (branch, files) in affected {
+        println!("  {} {}", branch.yellow(), files.join(", ").gray());
+    }
+    println!("They will need to pick up this change when they are restacked, which may conflict.");
+
+    if !staged_elsewhere.is_empty() {
+        println!(
+            "Other changes are staged too ({}), so commit this yourself and run {}",
+            staged_elsewhere.join(", ").gray(),
+            "sage restack".sage()
+        );
+        return Ok(());
+    }
+
+    if !Confirm::new(&format!("Commit \"{}\" and restack them now?", message))
+        .with_default(true)
+        .prompt()?
+    {
+        return Ok(());
+    }
+
+    git::commit::commit(message, false, dco::signoff_enabled())?;
+    let steps = restack::restack_descendants(&git::branch::current()?)?;
+    let restacked = steps.iter().filter(|step| step.outcome == ledger::Outcome::Done).count();
+    restack::print_summary(&steps);
+    println!("✨ Restacked {} branch(es)", restacked);
+
+    Ok(())
+}
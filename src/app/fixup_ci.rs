@@ -0,0 +1,96 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::time::Duration;
+use crate::{app::{dco, guard, policy}, errors, gh, git, ui::ColorizeExt};
+
+/// Message used for the fix commit unless one is given
+pub const DEFAULT_MESSAGE: &str = "style: fix ci";
+
+/// How long to wait for GitHub to register check suites for the pushed commit
+const SUITE_WAIT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct FixupCiOptions {
+    /// Commit message, defaults to `style: fix ci`
+    pub message: Option<String>,
+    /// Create a `fixup!` commit for HEAD instead
+    pub fixup: bool,
+    /// Commit guard rules to let through
+    pub allow: Vec<String>,
+}
+
+/// fixup_ci commits every change, pushes, and makes sure CI runs again for the new commit
+pub async fn fixup_ci(opts: &FixupCiOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    if !git::status::status()?.is_dirty() {
+        return Err(errors::GitError::NoChanges.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let (owner, repo) = git::repo::owner_repo()?;
+
+    // Knowing what was failing is nice, but not worth stopping over
+    let previous_head = git::repo::rev_parse("HEAD")?;
+    match gh::checks::check_runs(&owner, &repo, &previous_head).await {
+        Ok(runs) => {
+            let failed: Vec<String> = runs.into_iter().filter(|run| run.is_failed()).map(|run| run.name).collect();
+            if !failed.is_empty() {
+                println!("Failing checks: {}", failed.join(", ").red());
+            }
+        }
+        Err(e) => println!("{} Could not load checks: {}", "WARNING:".yellow(), e),
+    }
+
+    git::repo::stage_all()?;
+    guard::check_before_commit(&opts.allow)?;
+    if opts.fixup {
+        git::commit::fixup("HEAD", dco::signoff_enabled())?;
+    } else {
+        let message = opts.message.as_deref().unwrap_or(DEFAULT_MESSAGE);
+        policy::check_commit(&current_branch, message)?;
+        git::commit::commit(message, false, dco::signoff_enabled())?;
+    }
+
+    dco::verify_outgoing(&current_branch)?;
+    guard::check_before_push(&current_branch, false)?;
+    git::branch::push(&current_branch, false)?;
+    println!("✨ Pushed fix to {}", current_branch.sage());
+
+    let head = git::repo::rev_parse("HEAD")?;
+    let rerequested = rerequest_idle_suites(&owner, &repo, &head).await?;
+    if rerequested.is_empty() {
+        println!("Checks will run on the new commit. Follow along with {}", "sage pr status".yellow());
+    } else {
+        println!("Re-requested checks from {}", rerequested.join(", ").sage());
+    }
+
+    Ok(())
+}
+
+/// GitHub creates check suites for every app when a commit is pushed, but apps that don't
+/// trigger on push leave theirs queued with no runs. Re-request those so the checks actually run.
+async fn rerequest_idle_suites(owner: &str, repo: &str, sha: &str) -> Result<Vec<String>> {
+    let start = std::time::Instant::now();
+    let mut suites = Vec::new();
+    while suites.is_empty() && start.elapsed() < SUITE_WAIT {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        suites = gh::checks::check_suites(owner, repo, sha).await?;
+    }
+
+    let mut rerequested = Vec::new();
+    for suite in suites {
+        let idle = suite.status.as_deref() == Some("queued") && suite.latest_check_runs_count == 0;
+        if !idle {
+            continue;
+        }
+
+        gh::checks::rerequest_check_suite(owner, repo, suite.id).await?;
+        rerequested.push(suite.app.map(|app| app.name).unwrap_or_else(|| suite.id.to_string()));
+    }
+
+    Ok(rerequested)
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use chrono::Utc;
+use colored::Colorize;
+
+use crate::{config, errors, git, ui::ColorizeExt};
+
+/// Days a trashed branch tip is kept before gc deletes it
+const DEFAULT_TRASH_DAYS: i64 = 30;
+/// Loose objects past which git's own auto gc would kick in
+const LOOSE_OBJECT_LIMIT: u64 = 6700;
+/// Packs past which fetches start to slow down
+const PACK_LIMIT: u64 = 50;
+
+/// gc reports on the health of the repository, then cleans it up unless `dry_run` is set:
+/// trashed branch tips past retention and stale remote-tracking branches are deleted, and
+/// objects are packed with `git gc`
+pub fn gc(dry_run: bool, aggressive: bool, top: usize) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let before = git::gc::object_stats()?;
+    println!("{}", "Repository size".bold());
+    println!("  Total:         {}", human_size(before.total_size() * 1024).sage());
+    println!("  Loose objects: {} ({})", before.loose_count, human_size(before.loose_size * 1024));
+    println!("  Packs:         {} holding {} objects ({})", before.packs, before.in_pack, human_size(before.pack_size * 1024));
+    if before.garbage_size > 0 {
+        println!("  Garbage:       {}", human_size(before.garbage_size * 1024).yellow());
+    }
+    for advice in advise(&before) {
+        println!("  {} {}", "→".sage(), advice);
+    }
+
+    // Stale remote-tracking branches, per remote
+    let mut stale = Vec::new();
+    for remote in git::remote::list()? {
+        match git::gc::stale_tracking_refs(&remote.name) {
+            Ok(refs) if !refs.is_empty() => stale.push((remote.name, refs)),
+            Ok(_) => {}
+            Err(e) => println!("{} Skipping {}: {}", "WARNING:".yellow(), remote.name, e.to_string().trim()),
+        }
+    }
+    println!("\n{}", "Stale remote-tracking branches".bold());
+    if stale.is_empty() {
+        println!("  {}", "none".gray());
+    }
+    for (_, refs) in &stale {
+        for name in refs {
+            println!("  {}", name.gray());
+        }
+    }
+
+    if top > 0 {
+        println!("\n{}", "Largest files in history".bold());
+        for blob in git::gc::largest_blobs(top)? {
+            println!("  {:>10}  {} {}", human_size(blob.size), blob.path, blob.id[..10.min(blob.id.len())].gray());
+        }
+    }
+
+    let retention_days = config::get("gc.trash_days")
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_TRASH_DAYS);
+    let cutoff = Utc::now().timestamp() - retention_days * 24 * 60 * 60;
+    let expired = git::gc::trash_refs()?
+        .into_iter()
+        .filter(|trashed| trashed.trashed_at < cutoff)
+        .collect::<Vec<_>>();
+    if !expired.is_empty() {
+        println!("\n{} ({} days)", "Trashed branches past retention".bold(), retention_days);
+        for trashed in &expired {
+            println!("  {}", trashed.name.trim_start_matches(git::gc::TRASH_PREFIX).gray());
+        }
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    println!();
+    for trashed in &expired {
+        git::gc::delete_ref(&trashed.name)?;
+    }
+    for (remote, _) in &stale {
+        git::remote::prune(remote)?;
+    }
+
+    println!("Packing objects{}...", if aggressive { " (aggressive, this can take a while)" } else { "" });
+    git::gc::gc(aggressive, "2.weeks.ago")?;
+    if git::gc::object_stats()?.packs > 1 {
+        git::gc::repack()?;
+    }
+
+    let after = git::gc::object_stats()?;
+    println!(
+        "✨ Repository is {} (was {}), removed {} trashed branches and {} stale remote-tracking branches",
+        human_size(after.total_size() * 1024).sage(),
+        human_size(before.total_size() * 1024),
+        expired.len(),
+        stale.iter().map(|(_, refs)| refs.len()).sum::<usize>()
+    );
+
+    Ok(())
+}
+
+/// Explain what in the object stats is worth acting on
+fn advise(stats: &git::gc::ObjectStats) -> Vec<String> {
+    let mut advice = Vec::new();
+    if stats.loose_count > LOOSE_OBJECT_LIMIT {
+        advice.push(format!("{} loose objects; packing them will save space and speed git up", stats.loose_count));
+    }
+    if stats.packs > PACK_LIMIT {
+        advice.push(format!("{} packs; repacking into one will speed up fetches", stats.packs));
+    }
+    if stats.prune_packable > 0 {
+        advice.push(format!("{} loose objects are already packed and can be removed", stats.prune_packable));
+    }
+    if stats.garbage_size > 0 {
+        advice.push("Git found files in the object directory it doesn't recognise".to_string());
+    }
+    advice
+}
+
+/// Format a size in bytes
+pub(crate) fn human_size(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    const GIB: u64 = 1024 * MIB;
+    match bytes {
+        0..KIB => format!("{} B", bytes),
+        KIB..MIB => format!("{:.1} KiB", bytes as f64 / KIB as f64),
+        MIB..GIB => format!("{:.1} MiB", bytes as f64 / MIB as f64),
+        _ => format!("{:.2} GiB", bytes as f64 / GIB as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_size_and_advice() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536 * 1024), "1.5 MiB");
+        assert_eq!(human_size(3 << 30), "3.00 GiB");
+
+        let stats = git::gc::ObjectStats { loose_count: 7000, packs: 2, ..Default::default() };
+        let advice = advise(&stats);
+        assert_eq!(advice.len(), 1);
+        assert!(advice[0].starts_with("7000 loose objects"));
+    }
+}
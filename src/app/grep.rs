@@ -0,0 +1,123 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashSet;
+use crate::{errors, git, git::grep::GrepMatch, ui::ColorizeExt};
+
+#[derive(Default)]
+pub struct GrepOptions {
+    /// The pattern to search for
+    pub pattern: String,
+    /// Match case-insensitively
+    pub ignore_case: bool,
+    /// Limit the search to these paths
+    pub paths: Vec<String>,
+}
+
+/// grep searches the working tree and the tip of every branch in the current stack,
+/// highlighting matches each branch introduced on top of its parent
+pub fn grep(opts: &GrepOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let stack = git::stack::stack(&current_branch)?;
+    let search = |rev: Option<&str>| git::grep::grep(&opts.pattern, rev, opts.ignore_case, &opts.paths);
+
+    let base_matches = if git::repo::rev_exists(&stack.base) { search(Some(&stack.base))? } else { Vec::new() };
+
+    let mut total = 0;
+    let mut introduced_in: Option<String> = None;
+
+    for branch in &stack.branches {
+        let parent = git::stack::parent(branch)?.unwrap_or_else(|| stack.base.clone());
+        let parent_matches = if parent == stack.base { base_matches.clone() } else { search(Some(&parent))? };
+        let matches = search(Some(branch))?;
+
+        let new = new_matches(&parent_matches, &matches);
+        if !new.is_empty() && introduced_in.is_none() && base_matches.is_empty() {
+            introduced_in = Some(branch.clone());
+        }
+
+        total += matches.len();
+        print_group(branch, &parent, &matches, &new);
+    }
+
+    // Uncommitted changes are shown against the branch they sit on
+    let working_tree = search(None)?;
+    let committed = search(Some("HEAD"))?;
+    let new = new_matches(&committed, &working_tree);
+    if !new.is_empty() {
+        print_group(&format!("working tree ({})", current_branch), &current_branch, &working_tree, &new);
+    }
+
+    if total == 0 && working_tree.is_empty() {
+        println!("No matches for {} in the stack", opts.pattern.yellow());
+        return Ok(());
+    }
+
+    if let Some(branch) = introduced_in {
+        println!("First introduced in {}", branch.sage().bold());
+    } else if !base_matches.is_empty() {
+        println!("Already present in {} ({} matches)", stack.base.sage(), base_matches.len());
+    }
+
+    Ok(())
+}
+
+fn print_group(label: &str, parent: &str, matches: &[GrepMatch], new: &HashSet<(String, String)>) {
+    if matches.is_empty() {
+        return;
+    }
+
+    let summary = if new.is_empty() {
+        format!("({} matches)", matches.len())
+    } else {
+        format!("({} matches, {} new on top of {})", matches.len(), new.len(), parent)
+    };
+    println!("{} {}", label.yellow().bold(), summary.gray());
+
+    for found in matches {
+        let is_new = new.contains(&(found.path.clone(), found.text.clone()));
+        println!(
+            "  {} {}:{}: {}",
+            if is_new { "+".green() } else { " ".normal() },
+            found.path.sage(),
+            found.line.to_string().gray(),
+            found.text.trim()
+        );
+    }
+    println!();
+}
+
+/// Matches in `matches` that don't appear in `parent`, ignoring line numbers since
+/// unrelated edits shift them around
+fn new_matches(parent: &[GrepMatch], matches: &[GrepMatch]) -> HashSet<(String, String)> {
+    let parent: HashSet<(&str, &str)> = parent.iter().map(|m| (m.path.as_str(), m.text.as_str())).collect();
+
+    matches
+        .iter()
+        .filter(|m| !parent.contains(&(m.path.as_str(), m.text.as_str())))
+        .map(|m| (m.path.clone(), m.text.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(path: &str, line: usize, text: &str) -> GrepMatch {
+        GrepMatch { path: path.to_string(), line, text: text.to_string() }
+    }
+
+    #[test]
+    fn test_new_matches_ignores_line_shifts() {
+        let parent = vec![found("a.rs", 1, "fn helper()"), found("b.rs", 4, "helper();")];
+        let branch = vec![found("a.rs", 10, "fn helper()"), found("c.rs", 2, "helper();")];
+
+        let new = new_matches(&parent, &branch);
+        assert_eq!(new.len(), 1);
+        assert!(new.contains(&("c.rs".to_string(), "helper();".to_string())));
+    }
+}
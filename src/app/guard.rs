@@ -0,0 +1,337 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::Serialize;
+use crate::{app::policy, config, git, plugin::{self, Verdict}, ui::actions::{Annotation, Level}};
+
+/// Lockfiles and the manifest that is expected to change alongside them
+const LOCKFILES: &[(&str, &[&str])] = &[
+    ("Cargo.lock", &["Cargo.toml"]),
+    ("package-lock.json", &["package.json"]),
+    ("yarn.lock", &["package.json"]),
+    ("pnpm-lock.yaml", &["package.json"]),
+    ("Gemfile.lock", &["Gemfile"]),
+    ("poetry.lock", &["pyproject.toml"]),
+    ("Pipfile.lock", &["Pipfile"]),
+    ("composer.lock", &["composer.json"]),
+    ("go.sum", &["go.mod"]),
+];
+
+const DEFAULT_BINARY_MAX_KB: u64 = 512;
+
+/// A rule checked against the files in a commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rule {
+    /// Binary files over `guard.binary_max_kb`
+    Binary,
+    /// Lockfile changes without a matching manifest change
+    Lockfile,
+    /// Files matching `guard.generated_paths`
+    Generated,
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::Binary => "binary",
+            Rule::Lockfile => "lockfile",
+            Rule::Generated => "generated",
+        }
+    }
+
+    fn mode(&self) -> Mode {
+        let default = match self {
+            Rule::Binary | Rule::Lockfile => Mode::Warn,
+            Rule::Generated => Mode::Block,
+        };
+
+        config::get(&format!("guard.{}", self.as_str()))
+            .and_then(|value| Mode::parse(&value))
+            .unwrap_or(default)
+    }
+}
+
+/// What happens when a rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Off,
+    Warn,
+    Block,
+}
+
+impl Mode {
+    pub(crate) fn parse(value: &str) -> Option<Mode> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "false" => Some(Mode::Off),
+            "warn" => Some(Mode::Warn),
+            "block" | "true" => Some(Mode::Block),
+            _ => None,
+        }
+    }
+}
+
+/// A file that tripped a rule
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: Rule,
+    pub mode: Mode,
+    pub path: String,
+    pub message: String,
+    /// Set when the rule was overridden with --allow
+    pub allowed: bool,
+}
+
+/// The data plugins receive on the `pre-commit` hook
+#[derive(Debug, Serialize)]
+struct CommitEvent<'a> {
+    branch: &'a str,
+    files: &'a [String],
+    findings: &'a [Finding],
+}
+
+/// check_before_commit applies the commit guard rules to the staged files and lets plugins veto
+/// the commit. `allow` lists rule names (or `all`) to let through.
+pub fn check_before_commit(allow: &[String]) -> Result<()> {
+    let files = git::files::staged_files()?;
+    let is_allowed = |rule: Rule| allow.iter().any(|name| name == "all" || name == rule.as_str());
+
+    let mut findings = Vec::new();
+    for rule in [Rule::Binary, Rule::Lockfile, Rule::Generated] {
+        let mode = rule.mode();
+        if mode == Mode::Off {
+            continue;
+        }
+
+        for (path, message) in check_rule(rule, &files)? {
+            findings.push(Finding { rule, mode, path, message, allowed: is_allowed(rule) });
+        }
+    }
+
+    let mut blocked = Vec::new();
+    for finding in findings.iter().filter(|finding| !finding.allowed) {
+        let label = match finding.mode {
+            Mode::Block => "BLOCKED:".red(),
+            _ => "WARNING:".yellow(),
+        };
+        println!("{} {} {}", label, finding.path.yellow(), finding.message);
+        let level = if finding.mode == Mode::Block { Level::Error } else { Level::Warning };
+        let title = format!("Commit guard: {}", finding.rule.as_str());
+        Annotation { level, title: &title, message: &finding.message, file: Some(&finding.path), line: None }.emit();
+        if finding.mode == Mode::Block {
+            blocked.push(finding.rule.as_str());
+        }
+    }
+
+    let branch = git::branch::current()?;
+    let replies = plugin::run_hook("pre-commit", &CommitEvent { branch: &branch, files: &files, findings: &findings })?;
+
+    let blocking_plugins = print_replies(replies);
+    if !blocking_plugins.is_empty() {
+        return Err(anyhow!("Commit blocked by plugin(s): {}", blocking_plugins.join(", ")));
+    }
+
+    if !blocked.is_empty() {
+        blocked.dedup();
+        return Err(anyhow!(
+            "Commit blocked. Re-run with {} to commit anyway",
+            blocked
+                .iter()
+                .map(|rule| format!("--allow {}", rule))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Show what plugins said, returning the ones that blocked
+fn print_replies(replies: Vec<(String, plugin::Reply)>) -> Vec<String> {
+    let mut blocking_plugins = Vec::new();
+    for (name, reply) in replies {
+        let message = reply.message.unwrap_or_default();
+        match reply.verdict {
+            Verdict::Allow => {}
+            Verdict::Warn => println!("{} [{}] {}", "WARNING:".yellow(), name, message),
+            Verdict::Block => {
+                println!("{} [{}] {}", "BLOCKED:".red(), name, message);
+                blocking_plugins.push(name);
+            }
+        }
+    }
+    blocking_plugins
+}
+
+/// The data plugins receive on the `post-commit` hook
+#[derive(Debug, Serialize)]
+struct CommittedEvent<'a> {
+    branch: &'a str,
+    commit: &'a str,
+    message: &'a str,
+}
+
+/// after_commit tells plugins about the commit just made. The commit is already there, so a
+/// plugin can only warn about it.
+pub fn after_commit(message: &str) -> Result<()> {
+    let branch = git::branch::current()?;
+    let commit = git::repo::rev_parse("HEAD")?;
+    let replies = plugin::run_hook("post-commit", &CommittedEvent { branch: &branch, commit: &commit, message })?;
+    for name in print_replies(replies) {
+        println!("{} [{}] post-commit hooks can't block a commit that's already made", "WARNING:".yellow(), name);
+    }
+    Ok(())
+}
+
+/// The data plugins receive on the `pre-push` hook
+#[derive(Debug, Serialize)]
+struct PushEvent<'a> {
+    branch: &'a str,
+    remote: &'a str,
+    force: bool,
+}
+
+/// check_before_push stops pushing a branch the org policy protects, and lets plugins veto
+/// pushing `branch`
+pub fn check_before_push(branch: &str, force: bool) -> Result<()> {
+    policy::check_push(branch)?;
+    let replies = plugin::run_hook("pre-push", &PushEvent { branch, remote: "origin", force })?;
+    let blocking_plugins = print_replies(replies);
+    if !blocking_plugins.is_empty() {
+        return Err(anyhow!(
+            "Push of {} blocked by plugin(s): {}. See {}",
+            branch,
+            blocking_plugins.join(", "),
+            "sage hooks last"
+        ));
+    }
+    Ok(())
+}
+
+/// Returns (path, message) for every staged file that matches `rule`
+fn check_rule(rule: Rule, files: &[String]) -> Result<Vec<(String, String)>> {
+    match rule {
+        Rule::Binary => {
+            let max_kb = config::get("guard.binary_max_kb")
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(DEFAULT_BINARY_MAX_KB);
+
+            let mut matches = Vec::new();
+            for path in git::files::staged_binaries()? {
+                let size_kb = git::files::staged_size(&path)? / 1024;
+                if size_kb > max_kb {
+                    matches.push((path, format!("is a {} KB binary (limit {} KB)", size_kb, max_kb)));
+                }
+            }
+            Ok(matches)
+        }
+        Rule::Lockfile => Ok(lockfiles_without_manifest(files)
+            .into_iter()
+            .map(|(path, manifest)| (path, format!("changed without {}", manifest)))
+            .collect()),
+        Rule::Generated => {
+            let patterns = config::get("guard.generated_paths").unwrap_or_default();
+            let patterns: Vec<&str> = patterns.split(',').map(str::trim).filter(|p| !p.is_empty()).collect();
+
+            Ok(files
+                .iter()
+                .filter_map(|path| {
+                    let pattern = patterns.iter().find(|pattern| glob_match(pattern, path))?;
+                    Some((path.clone(), format!("is a generated file ({})", pattern)))
+                })
+                .collect())
+        }
+    }
+}
+
+/// Lockfiles in `files` whose manifest in the same directory didn't change, with that manifest's name
+fn lockfiles_without_manifest(files: &[String]) -> Vec<(String, String)> {
+    let split = |path: &str| match path.rsplit_once('/') {
+        Some((dir, name)) => (dir.to_string(), name.to_string()),
+        None => (String::new(), path.to_string()),
+    };
+
+    files
+        .iter()
+        .filter_map(|path| {
+            let (dir, name) = split(path);
+            let (_, manifests) = LOCKFILES.iter().find(|(lockfile, _)| *lockfile == name)?;
+
+            let manifest_changed = files.iter().any(|other| {
+                let (other_dir, other_name) = split(other);
+                other_dir == dir && manifests.contains(&other_name.as_str())
+            });
+
+            (!manifest_changed).then(|| (path.clone(), manifests.join(" or ")))
+        })
+        .collect()
+}
+
+/// Match a path against a gitignore-style glob: `*` and `?` stay within a path segment, `**`
+/// crosses them, and patterns without a `/` match the file name in any directory
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return glob_match_from(pattern.as_bytes(), name.as_bytes());
+    }
+
+    glob_match_from(pattern.trim_start_matches('/').as_bytes(), path.as_bytes())
+}
+
+fn glob_match_from(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            // `**/` may also match no directories at all
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=path.len()).any(|start| glob_match_from(rest, &path[start..]))
+        }
+        Some(b'*') => {
+            let segment_end = path.iter().position(|&c| c == b'/').unwrap_or(path.len());
+            (0..=segment_end).any(|start| glob_match_from(&pattern[1..], &path[start..]))
+        }
+        Some(b'?') => !path.is_empty() && path[0] != b'/' && glob_match_from(&pattern[1..], &path[1..]),
+        Some(&c) => path.first() == Some(&c) && glob_match_from(&pattern[1..], &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_lockfile_without_manifest() {
+        let files = paths(&["Cargo.lock", "web/yarn.lock", "web/package.json", "api/go.sum"]);
+        assert_eq!(
+            lockfiles_without_manifest(&files),
+            vec![
+                ("Cargo.lock".to_string(), "Cargo.toml".to_string()),
+                ("api/go.sum".to_string(), "go.mod".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.pb.go", "api/v1/service.pb.go"));
+        assert!(glob_match("dist/**", "dist/js/app.min.js"));
+        assert!(glob_match("**/generated/*.rs", "generated/schema.rs"));
+        assert!(glob_match("src/**/gen_*.rs", "src/a/b/gen_types.rs"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+
+        assert!(!glob_match("dist/*", "dist/js/app.js"));
+        assert!(!glob_match("src/*.rs", "lib/src/main.rs"));
+        assert!(!glob_match("*.pb.go", "service.go"));
+    }
+
+    #[test]
+    fn test_mode_parse() {
+        assert_eq!(Mode::parse("Block"), Some(Mode::Block));
+        assert_eq!(Mode::parse("off"), Some(Mode::Off));
+        assert_eq!(Mode::parse("sometimes"), None);
+    }
+}
@@ -1,24 +1,42 @@
-use crate::{git, ui::ColorizeExt};
+use crate::{git, t, ui::{accessible::{self, Mark}, pager::Pager, template::Template, ColorizeExt}};
 use anyhow::Result;
 use colored::Colorize;
+use serde_json::json;
+use std::fmt::Write;
 
 /// history will show the history of commits
-pub fn history() -> Result<()> {
+pub fn history(format: Option<&Template>) -> Result<()> {
     // Get the commits
     let mut commits = git::list::commits()?;
     let current_branch = git::branch::current()?;
 
     // Reverse the commits so that the latest commits are at the bottom
     commits.reverse();
+    let mut out = Pager::new();
 
-    println!(
+    if let Some(template) = format {
+        for commit in &commits {
+            let record = json!({
+                "hash": commit.hash,
+                "author": commit.author,
+                "date": commit.date,
+                "message": commit.message,
+                "branch": current_branch,
+            });
+            writeln!(out, "{}", template.render(&record))?;
+        }
+        return out.finish();
+    }
+
+    writeln!(
+        out,
         "{} {}",
-        "Branch History:".sage().bold(),
+        t!("history.header").sage().bold(),
         current_branch.yellow()
-    );
+    )?;
     if commits.is_empty() {
-        println!("{}", "No commits found".bright_green());
-        return Ok(());
+        writeln!(out, "{}", t!("history.empty").bright_green())?;
+        return out.finish();
     }
 
     // Group commits by date
@@ -28,24 +46,25 @@ pub fn history() -> Result<()> {
         // If we encounter a new date, print it
         if commit.date != current_date {
             current_date = commit.date.clone();
-            println!();
-            println!("{} {}", "Date:".bright_blue(), current_date.bold());
+            writeln!(out)?;
+            writeln!(out, "{} {}", t!("history.date").bright_blue(), current_date.bold())?;
         }
 
         // Print commit info in the desired format
-        println!(
+        writeln!(
+            out,
             " {} {} {} @{}",
-            "●".sage(),
+            accessible::mark(Mark::Bullet).sage(),
             commit.hash.bright_yellow(),
-            "by".gray(),
+            t!("history.by").gray(),
             commit.author
-        );
+        )?;
 
         // Print the commit message indented
         if !commit.message.is_empty() {
-            println!("   {}", commit.message);
+            writeln!(out, "   {}", commit.message)?;
         }
     }
 
-    Ok(())
+    out.finish()
 }
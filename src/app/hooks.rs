@@ -0,0 +1,201 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use colored::Colorize;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{config, errors, git, ledger, plugin::Verdict, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// A hook framework a repository can be set up with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    /// The Python pre-commit framework, configured in .pre-commit-config.yaml
+    PreCommit,
+    /// husky, with hook scripts in .husky/
+    Husky,
+}
+
+impl Framework {
+    const ALL: [Framework; 2] = [Framework::PreCommit, Framework::Husky];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Framework::PreCommit => "pre-commit",
+            Framework::Husky => "husky",
+        }
+    }
+
+    /// Config key that turns running this framework on or off
+    fn config_key(&self) -> &'static str {
+        match self {
+            Framework::PreCommit => "hooks.pre_commit",
+            Framework::Husky => "hooks.husky",
+        }
+    }
+
+    /// Whether the repository at `root` is set up with this framework
+    fn detect(&self, root: &Path) -> bool {
+        match self {
+            Framework::PreCommit => root.join(".pre-commit-config.yaml").is_file(),
+            Framework::Husky => root.join(".husky").join("pre-commit").is_file(),
+        }
+    }
+
+    /// Run the framework's pre-commit hooks against the staged files, returning if they passed
+    fn run(&self, root: &Path) -> Result<bool> {
+        let mut cmd = match self {
+            Framework::PreCommit => {
+                let mut cmd = Command::new("pre-commit");
+                cmd.args(["run", "--hook-stage", "pre-commit"]);
+                cmd
+            }
+            Framework::Husky => {
+                // Husky runs hooks with sh -e and the project's node binaries on the PATH
+                let mut cmd = Command::new("sh");
+                cmd.args(["-e", ".husky/pre-commit"]);
+                let mut paths = vec![root.join("node_modules").join(".bin")];
+                paths.extend(env::split_paths(&env::var_os("PATH").unwrap_or_default()));
+                cmd.env("PATH", env::join_paths(paths)?);
+                cmd
+            }
+        };
+
+        let status = cmd.current_dir(root).status().map_err(|e| match self {
+            Framework::PreCommit => anyhow!(
+                "Could not run pre-commit ({}). Install it with 'pip install pre-commit', or set hooks.pre_commit to false",
+                e
+            ),
+            Framework::Husky => anyhow!("Could not run the husky pre-commit hook: {}", e),
+        })?;
+        Ok(status.success())
+    }
+}
+
+/// frameworks returns the hook frameworks the repository uses that aren't turned off
+pub fn frameworks() -> Result<Vec<Framework>> {
+    let root = git::repo::toplevel()?;
+    Ok(Framework::ALL
+        .into_iter()
+        .filter(|framework| framework.detect(&root) && config::get_bool(framework.config_key(), true))
+        .collect())
+}
+
+/// run_before_commit runs the repository's pre-commit and husky hooks on the staged files before
+/// sage builds a commit message from them, so formatter fixes end up in the commit and in the diff
+/// the message describes. Files the hooks fix are staged again and the hooks rerun once to check.
+/// When they still fail, the index is put back the way it was, with the fixes left unstaged.
+///
+/// Returns whether any ran, in which case the commit can skip git's pre-commit hook rather than
+/// run them all again, see [`commit_msg`].
+pub fn run_before_commit() -> Result<bool> {
+    let root = git::repo::toplevel()?;
+    let frameworks = frameworks()?;
+    for framework in &frameworks {
+        println!("Running {} hooks...", framework.name().sage());
+        let staged = git::files::staged_files()?;
+        // Staging a whole file would take these files' unstaged changes along with the fixes
+        let partly_staged = git::files::unstaged_files(&staged)?;
+        let index = git::files::write_tree()?;
+        if framework.run(&root)? {
+            continue;
+        }
+
+        let fixed = git::files::unstaged_files(&staged)?;
+        if fixed.is_empty() {
+            return Err(anyhow!("{} hooks failed", framework.name()));
+        }
+        let mixed = fixed.iter().filter(|file| partly_staged.contains(file)).cloned().collect::<Vec<_>>();
+        if !mixed.is_empty() {
+            return Err(anyhow!(
+                "{} hooks changed {}, which also have unstaged changes. Stage the fixes you want and commit again",
+                framework.name(),
+                mixed.join(", ")
+            ));
+        }
+
+        println!("Staging files fixed by the hooks:");
+        for file in &fixed {
+            println!("  {}", file.gray());
+        }
+        git::files::stage_paths(&fixed)?;
+
+        if !framework.run(&root)? {
+            git::files::read_tree(&index)?;
+            return Err(anyhow!("{} hooks failed after staging their fixes", framework.name()));
+        }
+    }
+
+    Ok(!frameworks.is_empty())
+}
+
+/// commit_msg runs git's commit-msg hook on `message` and returns the message as the hook left
+/// it. Skipping git's hooks with --no-verify skips this one too, so a commit that does that
+/// because sage already ran the pre-commit hooks runs it here instead.
+pub fn commit_msg(message: &str) -> Result<String> {
+    let path = git::repo::git_dir()?.join("COMMIT_EDITMSG");
+    fs::write(&path, message)?;
+
+    let status = git::command()
+        .args(["hook", "run", "--ignore-missing", "commit-msg", "--"])
+        .arg(&path)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!("The commit-msg hook rejected the commit message"));
+    }
+
+    Ok(fs::read_to_string(&path)?.trim_end().to_string())
+}
+
+/// last shows the report of the latest plugin hook run, of `event` when given: which plugins ran,
+/// how long each took and what they replied
+pub fn last(event: Option<&str>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let Some(entry) = ledger::last_hooks(event)? else {
+        match event {
+            Some(event) => println!("No plugins have run on {} yet", event),
+            None => println!("No plugins have run on any hook yet"),
+        }
+        return Ok(());
+    };
+    let ledger::Operation::Hooks { event, plugins } = &entry.operation else {
+        return Ok(());
+    };
+
+    let outcome = match entry.status {
+        ledger::Status::Failed => "blocked".red(),
+        _ => "passed".green(),
+    };
+    println!(
+        "{} on {} {} {}",
+        event.sage(),
+        entry.branch.yellow(),
+        entry.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string().gray(),
+        outcome
+    );
+
+    for run in plugins {
+        let verdict = match run.verdict {
+            Some(Verdict::Allow) => "allow".green(),
+            Some(Verdict::Warn) => "warn".yellow(),
+            Some(Verdict::Block) => "block".red(),
+            None => "failed".red(),
+        };
+        println!(
+            "  {} {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            run.plugin,
+            verdict,
+            format!("({} ms)", run.duration_ms).gray()
+        );
+        if let Some(message) = run.message.as_ref().or(run.error.as_ref()) {
+            println!("      {}", message);
+        }
+    }
+    Ok(())
+}
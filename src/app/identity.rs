@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crate::{config::{self, Scope}, errors, git, ui::ColorizeExt};
+
+/// A named commit identity (e.g. "work" or "personal")
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub user_name: String,
+    pub email: String,
+    pub signing_key: Option<String>,
+    /// Remote owners (users or orgs) this identity should be used for
+    pub orgs: Vec<String>,
+}
+
+/// profiles returns every identity profile in the effective configuration
+pub fn profiles() -> Result<Vec<Profile>> {
+    let mut profiles: Vec<Profile> = Vec::new();
+
+    for (key, value) in config::load()? {
+        let Some(rest) = key.strip_prefix("identity.") else { continue };
+        let Some((name, field)) = rest.rsplit_once('.') else { continue };
+
+        let index = match profiles.iter().position(|profile| profile.name == name) {
+            Some(index) => index,
+            None => {
+                profiles.push(Profile { name: name.to_string(), ..Default::default() });
+                profiles.len() - 1
+            }
+        };
+
+        let profile = &mut profiles[index];
+        match field {
+            "name" => profile.user_name = value,
+            "email" => profile.email = value,
+            "signingkey" => profile.signing_key = Some(value),
+            "orgs" => profile.orgs = value.split(',').map(|org| org.trim().to_string()).filter(|org| !org.is_empty()).collect(),
+            _ => {}
+        }
+    }
+
+    Ok(profiles)
+}
+
+fn find(name: &str) -> Result<Profile> {
+    profiles()?
+        .into_iter()
+        .find(|profile| profile.name == name)
+        .ok_or_else(|| anyhow!("No identity profile named {}", name))
+}
+
+/// expected_profile returns the profile the current repository should commit with, if any:
+/// the explicitly selected one, or the first profile whose orgs match the origin owner
+pub fn expected_profile() -> Result<Option<Profile>> {
+    if let Some(name) = config::get("identity.profile") {
+        return Ok(Some(find(&name)?));
+    }
+
+    let owner = match git::remote::primary_url().ok().and_then(|url| git::repo::parse_remote_url(&url)) {
+        Some((_, owner, _)) => owner,
+        None => return Ok(None),
+    };
+
+    Ok(profiles()?
+        .into_iter()
+        .find(|profile| profile.orgs.iter().any(|org| org.eq_ignore_ascii_case(&owner))))
+}
+
+/// check_before_commit warns when the configured git email doesn't match the expected identity
+pub fn check_before_commit() -> Result<()> {
+    let Some(profile) = expected_profile()? else { return Ok(()) };
+
+    let email = git::repo::get_config("user.email")?.unwrap_or_default();
+    if email.eq_ignore_ascii_case(&profile.email) {
+        return Ok(());
+    }
+
+    println!(
+        "{} Committing as {} but this repository expects the {} identity ({})",
+        "WARNING:".yellow(),
+        if email.is_empty() { "<unset>".to_string() } else { email },
+        profile.name.sage(),
+        profile.email
+    );
+    println!("Run {} to switch.", format!("sage identity use {}", profile.name).yellow());
+
+    Ok(())
+}
+
+/// add stores a new identity profile in the global config
+pub fn add(profile: &Profile) -> Result<()> {
+    let prefix = format!("identity.{}", profile.name);
+    config::set(&format!("{}.name", prefix), &profile.user_name, Scope::Global)?;
+    config::set(&format!("{}.email", prefix), &profile.email, Scope::Global)?;
+    if let Some(key) = &profile.signing_key {
+        config::set(&format!("{}.signingkey", prefix), key, Scope::Global)?;
+    }
+    if !profile.orgs.is_empty() {
+        config::set(&format!("{}.orgs", prefix), &profile.orgs.join(","), Scope::Global)?;
+    }
+
+    println!("Added identity profile {}", profile.name.sage());
+    Ok(())
+}
+
+/// remove deletes an identity profile from the global config
+pub fn remove(name: &str) -> Result<()> {
+    find(name)?;
+    for field in ["name", "email", "signingkey", "orgs"] {
+        config::unset(&format!("identity.{}.{}", name, field), Scope::Global)?;
+    }
+
+    println!("Removed identity profile {}", name.sage());
+    Ok(())
+}
+
+/// list prints every profile, marking the one expected for the current repository
+pub fn list() -> Result<()> {
+    let profiles = profiles()?;
+    if profiles.is_empty() {
+        println!("No identity profiles yet. Add one with {}", "sage identity add".yellow());
+        return Ok(());
+    }
+
+    let expected = if git::repo::is_repo().unwrap_or(false) {
+        expected_profile()?.map(|profile| profile.name)
+    } else {
+        None
+    };
+
+    for profile in profiles {
+        let marker = if expected.as_deref() == Some(profile.name.as_str()) { "*" } else { " " };
+        println!("{} {} {} <{}>", marker.green(), profile.name.sage().bold(), profile.user_name, profile.email);
+        if let Some(key) = &profile.signing_key {
+            println!("    {} {}", "signing key:".gray(), key);
+        }
+        if !profile.orgs.is_empty() {
+            println!("    {} {}", "used for:".gray(), profile.orgs.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+/// use_profile applies a profile to the current repository's git config and remembers the choice
+pub fn use_profile(name: &str) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let profile = find(name)?;
+    git::repo::set_config("user.name", &profile.user_name)?;
+    git::repo::set_config("user.email", &profile.email)?;
+    match &profile.signing_key {
+        Some(key) => {
+            git::repo::set_config("user.signingkey", key)?;
+            git::repo::set_config("commit.gpgsign", "true")?;
+        }
+        // Don't keep signing with the key of the profile used before
+        None => {
+            git::repo::unset_config("user.signingkey")?;
+            git::repo::unset_config("commit.gpgsign")?;
+        }
+    }
+    config::set("identity.profile", &profile.name, Scope::Local)?;
+
+    println!("Now committing as {} <{}> in this repository", profile.user_name.sage(), profile.email);
+    Ok(())
+}
+
+/// check reports which identity the current repository expects and whether git is using it
+pub fn check() -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let email = git::repo::get_config("user.email")?.unwrap_or_default();
+    match expected_profile()? {
+        Some(profile) if email.eq_ignore_ascii_case(&profile.email) => {
+            println!("✨ Committing as the {} identity <{}>", profile.name.sage(), profile.email);
+        }
+        Some(_) => check_before_commit()?,
+        None => println!("No identity profile applies to this repository, committing as <{}>", email),
+    }
+
+    Ok(())
+}
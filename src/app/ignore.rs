@@ -0,0 +1,193 @@
+use anyhow::Result;
+use colored::Colorize;
+use inquire::Confirm;
+use std::fs;
+use crate::{errors, git, ui::ColorizeExt};
+
+pub struct IgnoreAddOptions {
+    /// Patterns to append to .gitignore
+    pub patterns: Vec<String>,
+    /// Group the patterns under a `# <section>` comment
+    pub section: Option<String>,
+    /// Untrack already-committed files matching the new patterns without asking
+    pub untrack: bool,
+}
+
+/// add appends patterns to the repository's .gitignore and offers to untrack files they now match
+pub fn add(opts: &IgnoreAddOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let path = git::repo::toplevel()?.join(".gitignore");
+    let contents = if path.exists() { fs::read_to_string(&path)? } else { String::new() };
+
+    let (updated, added) = add_patterns(&contents, opts.section.as_deref(), &opts.patterns);
+    if added.is_empty() {
+        println!("All patterns are already in .gitignore");
+        return Ok(());
+    }
+
+    // Anything tracked and ignored before this change isn't our concern
+    let already_ignored = git::ignore::tracked_ignored()?;
+    fs::write(&path, updated)?;
+
+    for pattern in &added {
+        println!("  {} {}", "+".green(), pattern);
+    }
+    println!("✨ Added {} pattern(s) to .gitignore", added.len());
+
+    let newly_ignored = git::ignore::tracked_ignored()?
+        .into_iter()
+        .filter(|file| !already_ignored.contains(file))
+        .collect::<Vec<_>>();
+
+    if newly_ignored.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} committed file(s) match the new patterns but are still tracked:",
+        "WARNING:".yellow(),
+        newly_ignored.len()
+    );
+    for file in &newly_ignored {
+        println!("  {}", file.gray());
+    }
+
+    let untrack = opts.untrack
+        || Confirm::new("Stop tracking these files? They will stay on disk")
+            .with_default(false)
+            .prompt()?;
+
+    if untrack {
+        git::ignore::untrack(&newly_ignored)?;
+        println!(
+            "Untracked {} file(s). Run {} to record the removal",
+            newly_ignored.len(),
+            "sage commit".sage()
+        );
+    }
+
+    Ok(())
+}
+
+/// check explains which ignore rule applies to each path
+pub fn check(paths: &[String]) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let matches = git::ignore::check_ignore(paths)?;
+    let tracked_ignored = git::ignore::tracked_ignored()?;
+
+    for path in paths {
+        let Some(rule) = matches.iter().find(|rule| &rule.path == path) else {
+            println!("{} {}", path.yellow(), "is not ignored".gray());
+            continue;
+        };
+
+        let source = format!("{}:{}", rule.source, rule.line);
+        if rule.is_negated() {
+            println!("{} is re-included by {} {}", path.yellow(), rule.pattern.sage(), source.gray());
+            continue;
+        }
+
+        println!("{} is ignored by {} {}", path.yellow(), rule.pattern.sage(), source.gray());
+        if tracked_ignored.contains(path) {
+            println!(
+                "  {} the file is already committed, so the pattern has no effect until it is untracked",
+                "note:".gray()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// add_patterns returns the new .gitignore contents and the patterns that were actually added
+fn add_patterns(contents: &str, section: Option<&str>, patterns: &[String]) -> (String, Vec<String>) {
+    let mut lines: Vec<String> = contents.lines().map(|line| line.to_string()).collect();
+
+    let mut added: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim();
+        if pattern.is_empty()
+            || lines.iter().any(|line| line.trim() == pattern)
+            || added.iter().any(|existing| existing == pattern)
+        {
+            continue;
+        }
+        added.push(pattern.to_string());
+    }
+
+    if added.is_empty() {
+        return (contents.to_string(), added);
+    }
+
+    let header = section.map(|section| format!("# {}", section.trim()));
+    let header_index = header
+        .as_ref()
+        .and_then(|header| lines.iter().position(|line| line.trim() == header));
+
+    match header_index {
+        // Extend the existing section, which runs until the next blank line or comment
+        Some(index) => {
+            let end = lines[index + 1..]
+                .iter()
+                .position(|line| line.trim().is_empty() || line.starts_with('#'))
+                .map(|offset| index + 1 + offset)
+                .unwrap_or(lines.len());
+            lines.splice(end..end, added.iter().cloned());
+        }
+        None => {
+            if let Some(header) = header {
+                if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(header);
+            }
+            lines.extend(added.iter().cloned());
+        }
+    }
+
+    (format!("{}\n", lines.join("\n")), added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_add_patterns_deduplicates() {
+        let (updated, added) = add_patterns("target\n*.log\n", None, &patterns(&["*.log", ".env", ".env"]));
+        assert_eq!(added, vec![".env"]);
+        assert_eq!(updated, "target\n*.log\n.env\n");
+    }
+
+    #[test]
+    fn test_add_patterns_nothing_new() {
+        let (updated, added) = add_patterns("target", None, &patterns(&["target"]));
+        assert!(added.is_empty());
+        assert_eq!(updated, "target");
+    }
+
+    #[test]
+    fn test_add_patterns_new_section() {
+        let (updated, _) = add_patterns("target\n", Some("Editors"), &patterns(&[".idea/", ".vscode/"]));
+        assert_eq!(updated, "target\n\n# Editors\n.idea/\n.vscode/\n");
+    }
+
+    #[test]
+    fn test_add_patterns_existing_section() {
+        let contents = "# Editors\n.idea/\n\n# Build\ntarget\n";
+        let (updated, _) = add_patterns(contents, Some("Editors"), &patterns(&[".vscode/"]));
+        assert_eq!(updated, "# Editors\n.idea/\n.vscode/\n\n# Build\ntarget\n");
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use colored::ColoredString;
+use std::io::IsTerminal;
+use crate::{app::{lock, pull_checkout}, cli, config, gh, gh::search::IssueItem, git, tui::select::Select, ui, ui::accessible::{self, Mark}, ui::theme, ui::ColorizeExt};
+
+/// Why a pull request is in the inbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    Review,
+    Assigned,
+    Both,
+}
+
+/// The narrowest a title is cut down to, however little room the terminal has
+const MIN_TITLE_WIDTH: usize = 16;
+
+struct Entry {
+    item: IssueItem,
+    reason: Reason,
+    additions: u64,
+    deletions: u64,
+    ci: ColoredString,
+}
+
+pub struct InboxOptions {
+    /// Only include these repositories (owner/repo), overrides inbox.repos
+    pub repos: Vec<String>,
+    /// Only include these orgs or users, overrides inbox.orgs
+    pub orgs: Vec<String>,
+    /// Print the list without prompting for an action
+    pub no_interactive: bool,
+}
+
+/// inbox lists open pull requests waiting on a review from, or assigned to, the current user
+pub async fn inbox(opts: &InboxOptions) -> Result<()> {
+    let scope = scope_qualifiers(
+        &or_config(&opts.repos, "inbox.repos"),
+        &or_config(&opts.orgs, "inbox.orgs"),
+    );
+
+    let base = format!("is:pr is:open archived:false {}", scope);
+    let review = gh::search::search_issues(&format!("{} review-requested:@me", base)).await?;
+    let assigned = gh::search::search_issues(&format!("{} assignee:@me", base)).await?;
+
+    let mut items: Vec<(IssueItem, Reason)> = review.into_iter().map(|item| (item, Reason::Review)).collect();
+    for item in assigned {
+        match items.iter_mut().find(|(existing, _)| existing.html_url == item.html_url) {
+            Some((_, reason)) => *reason = Reason::Both,
+            None => items.push((item, Reason::Assigned)),
+        }
+    }
+
+    if items.is_empty() {
+        println!("✨ Inbox zero, nothing is waiting on you");
+        return Ok(());
+    }
+
+    let mut entries = Vec::with_capacity(items.len());
+    for (item, reason) in items {
+        entries.push(load_entry(item, reason).await?);
+    }
+
+    // Oldest first, those have been waiting the longest
+    entries.sort_by_key(|entry| entry.item.created_at);
+
+    let columns = ui::text::terminal_width();
+    let rows: Vec<String> = entries.iter().map(|entry| format_row(entry, columns)).collect();
+    if opts.no_interactive || !std::io::stdout().is_terminal() {
+        for row in &rows {
+            println!("{}", row);
+        }
+        return Ok(());
+    }
+
+    let index = if accessible::enabled() {
+        accessible::select("Pull requests waiting on you:", &rows, None)?
+    } else {
+        Select::new("Pull requests waiting on you:", &rows).with_page_size(15).prompt()?
+    };
+    act_on(&entries[index]).await
+}
+
+/// Look up the size and CI state of a pull request
+async fn load_entry(item: IssueItem, reason: Reason) -> Result<Entry> {
+    let (owner, repo) = item.owner_repo().unwrap_or_default();
+    let pull = gh::pulls::get_pull_request(&owner, &repo, item.number).await?;
+
+    // CI state is a nice to have, don't fail the whole inbox over it
+    let ci = match gh::checks::check_runs(&owner, &repo, &pull.head.sha).await {
+        Ok(runs) if runs.is_empty() => "-".gray(),
+        Ok(runs) if runs.iter().any(|run| run.is_failed()) => theme::styled(Mark::Failing),
+        Ok(runs) if runs.iter().any(|run| run.status != "completed") => theme::styled(Mark::Pending),
+        Ok(_) => theme::styled(Mark::Passing),
+        Err(_) => "?".gray(),
+    };
+
+    Ok(Entry {
+        item,
+        reason,
+        additions: pull.additions.unwrap_or_default(),
+        deletions: pull.deletions.unwrap_or_default(),
+        ci,
+    })
+}
+
+/// format_row lays an entry out on one line, cutting the title short so the row fits in
+/// `columns` when given
+fn format_row(entry: &Entry, columns: Option<usize>) -> String {
+    let (owner, repo) = entry.item.owner_repo().unwrap_or_default();
+    let reason = match entry.reason {
+        Reason::Review => "review",
+        Reason::Assigned => "assigned",
+        Reason::Both => "review+assigned",
+    };
+
+    let age = format_age(Utc::now() - entry.item.created_at);
+    let reference = ui::text::pad(&format!("{}/{}#{}", owner, repo, entry.item.number), 28);
+    let size = format!("+{} -{}", entry.additions, entry.deletions);
+    let author = format!("@{}", entry.item.user.login);
+    let reason = format!("[{}]", reason);
+
+    let title = match columns {
+        Some(columns) => {
+            let rest = [&*entry.ci, &format!("{:>4}", age), &reference, &size, &author, &reason]
+                .iter()
+                .map(|part| ui::text::width(part) + 1)
+                .sum::<usize>();
+            ui::text::ellipsize(&entry.item.title, columns.saturating_sub(rest).max(MIN_TITLE_WIDTH))
+        }
+        None => entry.item.title.clone(),
+    };
+
+    format!("{} {:>4} {} {} {} {} {}", entry.ci, age, reference, title, size.gray(), author.gray(), reason.sage())
+}
+
+async fn act_on(entry: &Entry) -> Result<()> {
+    let (owner, repo) = entry.item.owner_repo().unwrap_or_default();
+
+    // Checking out only makes sense when we're inside a clone of that repository, and changes it,
+    // so it's not offered with SAGE_READONLY set
+    let in_repo = !cli::read_only()
+        && git::repo::is_repo().unwrap_or(false)
+        && git::repo::owner_repo().map(|current| current == (owner.clone(), repo.clone())).unwrap_or(false);
+
+    let open = "Open in browser";
+    let checkout = "Check out locally";
+    let mut actions = vec![open];
+    if in_repo {
+        actions.push(checkout);
+    }
+    actions.push("Cancel");
+
+    let labels = actions.iter().map(|action| action.to_string()).collect::<Vec<_>>();
+    let action = if accessible::enabled() {
+        actions[accessible::select("What now?", &labels, None)?]
+    } else {
+        actions[Select::new("What now?", &labels).prompt()?]
+    };
+    match action {
+        action if action == open => ui::open_in_browser(&entry.item.html_url),
+        action if action == checkout => {
+            // The inbox itself only looks, so it takes the lock just for this
+            let _lock = lock::acquire("inbox", false).await?;
+            pull_checkout::pull_checkout(entry.item.number, None).await
+        }
+        _ => Ok(()),
+    }
+}
+
+fn or_config(values: &[String], key: &str) -> Vec<String> {
+    if !values.is_empty() {
+        return values.to_vec();
+    }
+
+    config::get(key)
+        .map(|value| value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Build the search qualifiers limiting the inbox to the configured repositories and orgs
+fn scope_qualifiers(repos: &[String], orgs: &[String]) -> String {
+    repos
+        .iter()
+        .map(|repo| format!("repo:{}", repo))
+        .chain(orgs.iter().map(|org| format!("org:{}", org)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Compact age like `45m`, `6h`, `3d` or `5w`
+fn format_age(age: Duration) -> String {
+    if age.num_hours() < 1 {
+        format!("{}m", age.num_minutes().max(0))
+    } else if age.num_days() < 1 {
+        format!("{}h", age.num_hours())
+    } else if age.num_weeks() < 2 {
+        format!("{}d", age.num_days())
+    } else {
+        format!("{}w", age.num_weeks())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::minutes(12)), "12m");
+        assert_eq!(format_age(Duration::hours(5)), "5h");
+        assert_eq!(format_age(Duration::days(9)), "9d");
+        assert_eq!(format_age(Duration::days(30)), "4w");
+    }
+
+    #[test]
+    fn test_scope_qualifiers() {
+        let repos = vec!["acme/api".to_string()];
+        let orgs = vec!["acme".to_string(), "acme-labs".to_string()];
+        assert_eq!(scope_qualifiers(&repos, &orgs), "repo:acme/api org:acme org:acme-labs");
+        assert_eq!(scope_qualifiers(&[], &[]), "");
+    }
+}
@@ -0,0 +1,126 @@
+//! Turning a request in plain English into sage commands
+//!
+//! `sage do "get my branch up to date and open a draft PR"` asks the AI which sage commands would
+//! do that. Its answer is only ever read as sage commands: each line is split into arguments
+//! here and parsed like a command line by the caller, which refuses anything that isn't a sage
+//! command. Nothing goes through a shell, and nothing runs until the plan has been shown and
+//! confirmed.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{ai, app::prompts, ui::ColorizeExt};
+
+/// A proposed sage command, its arguments without the leading `sage`
+pub type Step = Vec<String>;
+
+/// plan asks the AI which sage commands do what `intent` asks, out of `commands`
+pub async fn plan(intent: &str, commands: &str) -> Result<Vec<Step>> {
+    let answer = prompts::ask("do", &ai::prompts::intent_prompt(intent, commands)).await?;
+    parse_plan(answer.trim())
+}
+
+/// Read the AI's answer into steps, refusing the whole plan if any line isn't a sage command
+fn parse_plan(answer: &str) -> Result<Vec<Step>> {
+    let lines = answer
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("```"))
+        .collect::<Vec<_>>();
+    if lines.is_empty() || lines.iter().any(|line| line.eq_ignore_ascii_case("none")) {
+        return Err(anyhow!("sage has no commands for that. Try asking in other words, or see {}", "sage --help"));
+    }
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let line = line.strip_prefix("$ ").unwrap_or(line);
+            match split_args(line)?.split_first() {
+                Some((program, args)) if program == "sage" && !args.is_empty() => Ok(args.to_vec()),
+                _ => Err(anyhow!("The plan included '{}', which isn't a sage command, so none of it was run", line)),
+            }
+        })
+        .collect()
+}
+
+/// Split a command line into arguments like a shell would for quoting, without anything else a
+/// shell does: no variables, globs, pipes or command separators
+fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let quoted = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') if c == '"' => quoted.extend(chars.next()),
+                        Some(other) => quoted.push(other),
+                        None => return Err(anyhow!("'{}' has an unmatched quote", line)),
+                    }
+                }
+            }
+            '\\' => current.get_or_insert_with(String::new).extend(chars.next()),
+            c if c.is_whitespace() => args.extend(current.take()),
+            ';' | '|' | '&' | '<' | '>' | '`' | '$' => {
+                return Err(anyhow!("'{}' uses shell syntax, which sage do never runs", line));
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// describe writes a step back out as a command line
+pub fn describe(step: &Step) -> String {
+    let quote = |arg: &String| {
+        if arg.is_empty() || arg.contains(char::is_whitespace) { format!("\"{}\"", arg.replace('"', "\\\"")) } else { arg.clone() }
+    };
+    std::iter::once("sage".to_string()).chain(step.iter().map(quote)).collect::<Vec<_>>().join(" ")
+}
+
+/// confirm shows the plan and asks before running it
+pub fn confirm(steps: &[Step]) -> Result<bool> {
+    println!("{}", "sage do would run:".sage().bold());
+    for (number, step) in steps.iter().enumerate() {
+        println!("  {}. {}", number + 1, describe(step));
+    }
+
+    println!("\nRun these commands? [y/N]");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// announce says which step is starting
+pub fn announce(number: usize, total: usize, step: &Step) {
+    println!("\n{} {}", format!("[{}/{}]", number, total).gray(), describe(step).bold());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args(r#"sage pr create --title "Add login" --draft"#).unwrap(), vec!["sage", "pr", "create", "--title", "Add login", "--draft"]);
+        assert_eq!(split_args(r#"sage commit 'a b' "say \"hi\"""#).unwrap(), vec!["sage", "commit", "a b", "say \"hi\""]);
+        assert!(split_args("sage sync; rm -rf /").is_err());
+        assert!(split_args("sage commit $(whoami)").is_err());
+        assert!(split_args(r#"sage commit "open"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        let plan = parse_plan("```\nsage sync\n$ sage pr create --draft --title \"WIP login\"\n```").unwrap();
+        assert_eq!(plan, vec![vec!["sync".to_string()], vec!["pr", "create", "--draft", "--title", "WIP login"].into_iter().map(String::from).collect()]);
+        assert_eq!(describe(&plan[1]), "sage pr create --draft --title \"WIP login\"");
+
+        assert!(parse_plan("sage sync\ngit push --force").is_err());
+        assert!(parse_plan("none").is_err());
+        assert!(parse_plan("sage").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::{git::{self, repo::InProgress}, ledger, t, ui::ColorizeExt};
+
+/// Number of operations currently able to stop at a safe point
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+/// Set by the first Ctrl-C while an operation is active
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// install replaces the default Ctrl-C behaviour. Outside of a guarded operation sage exits
+/// straight away as before. During one, the first Ctrl-C asks it to stop at the next safe point
+/// and a second one exits immediately.
+pub fn install() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if ACTIVE.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst) {
+                process::exit(130);
+            }
+            eprintln!("\n{} {}", t!("warning").yellow(), t!("interrupt.stopping"));
+        }
+    });
+}
+
+/// Marks a long operation as running until dropped
+pub struct Guard(());
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// guard makes Ctrl-C stop at the next checkpoint rather than killing sage mid-operation
+pub fn guard() -> Guard {
+    ACTIVE.fetch_add(1, Ordering::SeqCst);
+    Guard(())
+}
+
+/// interrupted returns if Ctrl-C was pressed during a guarded operation
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// checkpoint fails if Ctrl-C was pressed, so the operation stops between steps
+pub fn checkpoint() -> Result<()> {
+    if interrupted() {
+        return Err(anyhow!("Interrupted"));
+    }
+    Ok(())
+}
+
+/// recovery_commands lists the git commands that put the repository back how the user left it
+pub fn recovery_commands(branch: &str, wip: bool) -> Vec<String> {
+    let mut commands = Vec::new();
+    match git::repo::in_progress().ok().flatten() {
+        Some(InProgress::Rebase) => commands.push("git rebase --abort".to_string()),
+        Some(InProgress::Merge) => commands.push("git merge --abort".to_string()),
+        Some(InProgress::CherryPick) => commands.push("git cherry-pick --abort".to_string()),
+        Some(InProgress::Revert) => commands.push("git revert --abort".to_string()),
+        None => {}
+    }
+
+    if git::branch::current().map_or(true, |current| current != branch) {
+        commands.push(format!("git switch {}", branch));
+    }
+    if wip {
+        // Undo the temporary commit sync made for uncommitted changes
+        commands.push("git reset --soft HEAD~1".to_string());
+    }
+
+    commands
+}
+
+/// stop records an interrupted operation in the ledger and tells the user how to resume it or
+/// back out by hand
+pub fn stop(id: u64, branch: &str, wip: bool) -> Result<()> {
+    let recovery = recovery_commands(branch, wip);
+    ledger::update(id, |entry| {
+        entry.status = ledger::Status::Interrupted;
+        entry.recovery = recovery.clone();
+    })?;
+
+    println!("\n{} {}", t!("warning").yellow(), t!("interrupt.stopped"));
+    println!("{}", t!("interrupt.resume", command = "sage continue".sage()));
+    if !recovery.is_empty() {
+        println!("{}", t!("interrupt.manual"));
+        for command in &recovery {
+            println!("  {}", command.gray());
+        }
+    }
+
+    Ok(())
+}
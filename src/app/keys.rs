@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::IsTerminal;
+
+use crate::{tui::keys::{self, Action, Keymap}, ui::ColorizeExt};
+
+/// list prints every action with the keys bound to it
+pub fn list() -> Result<()> {
+    let keymap = Keymap::load()?;
+
+    for action in Action::ALL {
+        let source = if keymap.is_configured(action) {
+            format!("(keys.{})", action.name())
+        } else {
+            "(default)".to_string()
+        };
+        println!(
+            "  {:<10} {:<18} {} {}",
+            action.name().sage(),
+            keymap.keys(action).join(", "),
+            action.description(),
+            source.gray()
+        );
+    }
+
+    for (key, actions) in keymap.conflicts() {
+        let names = actions.iter().map(|action| action.name()).collect::<Vec<_>>().join(" and ");
+        println!("{} {} is bound to both {}", "WARNING:".yellow(), key, names);
+    }
+
+    Ok(())
+}
+
+/// test reads key presses and shows what each one is bound to, until Ctrl-C
+pub fn test() -> Result<()> {
+    let keymap = Keymap::load()?;
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow!("sage keys test needs an interactive terminal"));
+    }
+
+    println!("Press keys to see what they do. Ctrl-C to stop.");
+    terminal::enable_raw_mode()?;
+    let result = read_keys(&keymap);
+    terminal::disable_raw_mode()?;
+    result
+}
+
+/// Echo key presses until Ctrl-C. The terminal is in raw mode, so lines end with \r\n.
+fn read_keys(keymap: &Keymap) -> Result<()> {
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            print!("\r\n");
+            return Ok(());
+        }
+
+        let name = keys::key_name(&key).unwrap_or_else(|| format!("{:?}", key.code));
+        match keymap.action(&key) {
+            Some(action) => print!("  {:<12} {}\r\n", name, action.name().sage()),
+            None => print!("  {:<12} {}\r\n", name, "not bound".gray()),
+        }
+    }
+}
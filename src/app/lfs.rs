@@ -0,0 +1,84 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use inquire::Confirm;
+use crate::{errors, git, ui::ColorizeExt};
+
+const INSTALL_HINT: &str = "Install it from https://git-lfs.com and run 'git lfs install'";
+
+/// check_before_commit warns when staged files belong in LFS but git-lfs isn't installed,
+/// which would commit them as regular blobs
+pub fn check_before_commit() -> Result<()> {
+    if !git::lfs::uses_lfs()? || git::lfs::is_installed() {
+        return Ok(());
+    }
+
+    let files = git::lfs::tracked_by_lfs(&git::files::staged_files()?)?;
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "{} These files are configured for Git LFS, but git-lfs is not installed.",
+        "WARNING:".yellow()
+    );
+    println!("They will be committed as regular files:");
+    for file in &files {
+        println!("  {}", file.gray());
+    }
+    println!("{}", INSTALL_HINT.gray());
+
+    Ok(())
+}
+
+/// print_status adds LFS details to `sage status` for repositories using it
+pub fn print_status() -> Result<()> {
+    if !git::lfs::uses_lfs()? {
+        return Ok(());
+    }
+
+    if !git::lfs::is_installed() {
+        println!("{} This repository uses Git LFS, but git-lfs is not installed", "WARNING:".yellow());
+        return Ok(());
+    }
+
+    println!("{} {} files stored in LFS", "LFS:".sage(), git::lfs::object_count()?);
+    Ok(())
+}
+
+/// prune removes old local LFS objects after showing what would be deleted
+pub fn prune(dry_run: bool, yes: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    if !git::lfs::is_installed() {
+        return Err(anyhow!("git-lfs is not installed. {}", INSTALL_HINT));
+    }
+
+    if !git::lfs::uses_lfs()? {
+        println!("This repository doesn't use Git LFS, nothing to prune");
+        return Ok(());
+    }
+
+    // Objects only referenced by unpushed commits would be lost for good
+    if git::branch::needs_push()? {
+        return Err(anyhow!(
+            "The current branch has unpushed commits. Push them before pruning LFS objects"
+        ));
+    }
+
+    println!("{}", git::lfs::prune(true)?.trim());
+    if dry_run {
+        return Ok(());
+    }
+
+    if !yes && !Confirm::new("Delete these local LFS objects?").with_default(false).prompt()? {
+        return Ok(());
+    }
+
+    println!("{}", git::lfs::prune(false)?.trim());
+    println!("✨ Pruned local LFS objects");
+
+    Ok(())
+}
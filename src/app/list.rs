@@ -1,69 +1,324 @@
 use anyhow::Result;
-use crate::{errors, git};
+use crate::{app::{clean::CleanPolicy, note::describe_note}, errors, git::{self, branch::BranchInfo}, gh::graphql::{self, PrState, PrSummary}, t, ui::{accessible::{self, Mark}, template::Template}};
 use colored::Colorize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 
-pub fn list() -> Result<()> {
+/// Which page of branches to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub limit: usize,
+    /// Counted from 1
+    pub number: usize,
+}
+
+impl Page {
+    /// range returns which of `total` branches are on the page
+    pub fn range(&self, total: usize) -> std::ops::Range<usize> {
+        let start = self.limit.saturating_mul(self.number.saturating_sub(1)).min(total);
+        start..start.saturating_add(self.limit).min(total)
+    }
+}
+
+/// Days without commits after which a branch counts as stale, unless clean.stale_days says
+/// otherwise
+pub const DEFAULT_STALE_DAYS: i64 = 30;
+
+/// Branches with commits in the last week are fresh
+const FRESH_DAYS: i64 = 7;
+
+/// How long a branch has gone without commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Staleness {
+    Fresh,
+    Aging,
+    Stale,
+}
+
+impl Staleness {
+    fn of(age_days: i64, stale_days: i64) -> Staleness {
+        if age_days >= stale_days {
+            Staleness::Stale
+        } else if age_days >= FRESH_DAYS.min(stale_days) {
+            Staleness::Aging
+        } else {
+            Staleness::Fresh
+        }
+    }
+}
+
+/// How old a branch is and when it forked from the default branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Age {
+    days: i64,
+    /// None for the default branch itself, or a branch with no history in common with it
+    forked_days: Option<i64>,
+    staleness: Staleness,
+}
+
+impl Age {
+    /// of works out a branch's age, with the default branch's name and tip to tell when it
+    /// forked
+    pub(crate) fn of(
+        index: &mut git::index::Index,
+        branch: &git::index::Ref,
+        (default_branch, default_tip): (&str, Option<&str>),
+        now: i64,
+        stale_days: i64,
+    ) -> Result<Age> {
+        let forked_at = match default_tip {
+            Some(default_tip) if branch.name != default_branch => index.forked_at(branch, default_tip)?,
+            _ => None,
+        };
+        Ok(Age {
+            days: branch.age_days(now),
+            forked_days: forked_at.map(|at| (now - at).max(0) / (24 * 60 * 60)),
+            staleness: Staleness::of(branch.age_days(now), stale_days),
+        })
+    }
+}
+
+/// stale_days returns after how many days without commits a branch is stale: the same
+/// clean.stale_days `sage clean --policy` goes by, or DEFAULT_STALE_DAYS
+pub fn stale_days() -> Result<i64> {
+    Ok(CleanPolicy::load()?.stale_days.unwrap_or(DEFAULT_STALE_DAYS))
+}
+
+/// list shows the branches, or with `stale` only those without commits in that many days
+pub async fn list(show_prs: bool, format: Option<&Template>, page: Option<Page>, stale: Option<i64>) -> Result<()> {
     // Check to ensure we are in a repo first.
     if !git::repo::is_repo()? {
         return Err(errors::GitError::NotARepository.into());
     }
 
-    println!("Branches:");
-    // Getting all the branches with detailed information
-    let branches = git::branch::list_with_info()?;
-    
+    if format.is_none() {
+        println!("{}", t!("list.header"));
+    }
+    // Listing the branches is cheap; counting how far each is from its upstream isn't, so only
+    // the page shown is counted, and each branch is printed as soon as it has been
+    let current = git::branch::current()?;
+    let now = chrono::Utc::now().timestamp();
+    let refs = git::index::refs()?;
+    let all = refs
+        .iter()
+        .filter(|branch| stale.is_none_or(|stale| branch.age_days(now) >= stale))
+        .collect::<Vec<_>>();
+    let stale_days = stale.map(Ok).unwrap_or_else(stale_days)?;
+    let default_branch = git::repo::default_branch()?;
+    let default_tip = refs.iter().find(|branch| branch.name == default_branch).map(|branch| branch.tip.clone());
+    let range = page.map(|page| page.range(all.len())).unwrap_or(0..all.len());
+    let branches = &all[range.clone()];
+
+    // Pull requests are a nice-to-have here, so listing still works offline or without a token
+    let pull_requests = if show_prs {
+        let names = branches.iter().map(|branch| branch.name.clone()).collect::<Vec<_>>();
+        graphql::pull_requests_by_branch(&names).await.unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+
+    let mut index = git::index::Index::load();
     for branch in branches {
-        let mut output = String::new();
-        
-        // Mark current branch with an asterisk
-        if branch.is_current {
-            output.push_str("* ");
+        let age = Age::of(&mut index, branch, (&default_branch, default_tip.as_deref()), now, stale_days)?;
+        let branch = index.info(branch, &current)?;
+        if let Some(template) = format {
+            let record = json!({
+                "name": branch.name,
+                "current": branch.is_current,
+                "upstream": branch.upstream,
+                "ahead": branch.ahead_count,
+                "behind": branch.behind_count,
+                "age_days": age.days,
+                "forked_days": age.forked_days,
+                "stale": age.staleness == Staleness::Stale,
+                "pr": pull_requests.get(&branch.name).map(pr_record),
+                "note": git::stack::note(&branch.name)?,
+            });
+            println!("{}", template.render(&record));
         } else {
-            output.push_str("  ");
+            print_branch(branch, age, &pull_requests)?;
         }
-        
-        // Add branch name
-        output.push_str(&branch.name);
-        
-        // Add tracking information if available
-        if let Some(upstream) = branch.upstream {
-            output.push_str(&format!(" -> {}", upstream));
-            
-            // Add ahead/behind information with arrows
-            if branch.ahead_count > 0 || branch.behind_count > 0 {
-                let mut counts = Vec::new();
-                
-                if branch.ahead_count > 0 {
-                    counts.push(format!("↑{}", branch.ahead_count));
-                }
-                
-                if branch.behind_count > 0 {
-                    counts.push(format!("↓{}", branch.behind_count));
-                }
-                
-                if !counts.is_empty() {
-                    output.push_str(&format!(" [{}]", counts.join(", ")));
-                }
-            }
-        }
-        
-        // Colorize differently based on status
-        if branch.is_current {
-            println!("{}", output.green());
-        } else if branch.ahead_count > 0 && branch.behind_count > 0 {
-            // Diverged branches - yellow
-            println!("{}", output.yellow());
-        } else if branch.ahead_count > 0 {
-            // Branches ahead - cyan
-            println!("{}", output.cyan());
-        } else if branch.behind_count > 0 {
-            // Branches behind - magenta
-            println!("{}", output.magenta());
-        } else {
-            // Regular branches - blue
-            println!("{}", output.blue());
+    }
+    // Only a full listing knows which branches are gone
+    let _ = index.save((page.is_none() && stale.is_none()).then_some(refs.as_slice()));
+
+    if let (Some(page), None) = (page, format) {
+        print_page_footer(page, range, all.len());
+    }
+    Ok(())
+}
+
+fn print_branch(branch: BranchInfo, age: Age, pull_requests: &HashMap<String, PrSummary>) -> Result<()> {
+    let pr = pull_requests.get(&branch.name).map(|pr| format!(" {}", describe_pr(pr)));
+    let mut pr = pr.unwrap_or_default();
+    if let Some(note) = git::stack::note(&branch.name)? {
+        pr.push_str(&format!(" {}", describe_note(&note)));
+    }
+
+    let mut output = String::new();
+
+    // Mark current branch with an asterisk, or say so in accessible mode
+    if branch.is_current {
+        output.push_str(if accessible::enabled() { "(current) " } else { "* " });
+    } else if !accessible::enabled() {
+        output.push_str("  ");
+    }
+
+    // Add branch name
+    output.push_str(&branch.name);
+
+    // Add tracking information if available
+    if let Some(upstream) = branch.upstream {
+        output.push_str(&format!(" -> {}", upstream));
+
+        // Add ahead/behind information with arrows
+        if branch.ahead_count > 0 || branch.behind_count > 0 {
+            output.push_str(&format!(" [{}]", accessible::ahead_behind(branch.ahead_count, branch.behind_count)));
         }
     }
 
+    let age = describe_age(age);
+
+    // Colorize differently based on status
+    if branch.is_current {
+        println!("{}{}{}", output.green(), age, pr.dimmed());
+    } else if branch.ahead_count > 0 && branch.behind_count > 0 {
+        // Diverged branches - yellow
+        println!("{}{}{}", output.yellow(), age, pr.dimmed());
+    } else if branch.ahead_count > 0 {
+        // Branches ahead - cyan
+        println!("{}{}{}", output.cyan(), age, pr.dimmed());
+    } else if branch.behind_count > 0 {
+        // Branches behind - magenta
+        println!("{}{}{}", output.magenta(), age, pr.dimmed());
+    } else {
+        // Regular branches - blue
+        println!("{}{}{}", output.blue(), age, pr.dimmed());
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The age of a branch, colored from green to red as it goes stale
+pub(crate) fn describe_age(age: Age) -> String {
+    let mut text = format!(" {} old", days(age.days));
+    if let Some(forked_days) = age.forked_days {
+        text.push_str(&format!(", forked {} ago", days(forked_days)));
+    }
+    match age.staleness {
+        Staleness::Fresh => text.green().to_string(),
+        Staleness::Aging => text.yellow().to_string(),
+        Staleness::Stale if accessible::enabled() => format!("{} (stale)", text),
+        Staleness::Stale => text.red().to_string(),
+    }
+}
+
+/// A number of days, in weeks or months once there are enough of them
+pub fn days(days: i64) -> String {
+    if days < 1 {
+        "<1d".to_string()
+    } else if days < 14 {
+        format!("{}d", days)
+    } else if days < 60 {
+        format!("{}w", days / 7)
+    } else {
+        format!("{}mo", days / 30)
+    }
+}
+
+fn print_page_footer(page: Page, range: std::ops::Range<usize>, total: usize) {
+    if range.is_empty() {
+        println!("{}", format!("No branches on page {}, there are {} in all", page.number, total).dimmed());
+        return;
+    }
+    let mut footer = format!("Branches {}-{} of {}", range.start + 1, range.end, total);
+    if range.end < total {
+        footer.push_str(&format!(", next: sage list --limit {} --page {}", page.limit, page.number + 1));
+    }
+    println!("{}", footer.dimmed());
+}
+
+pub fn describe_pr(pr: &PrSummary) -> String {
+    let state = match pr.state {
+        PrState::Open if pr.is_draft => "draft",
+        PrState::Open => "open",
+        PrState::Closed => "closed",
+        PrState::Merged => "merged",
+    };
+
+    let mut summary = format!("#{} {}", pr.number, state);
+    if pr.state == PrState::Open {
+        let checks = match pr.checks.as_deref() {
+            Some("SUCCESS") => Some(Mark::Passing),
+            Some("FAILURE") | Some("ERROR") => Some(Mark::Failing),
+            Some(_) => Some(Mark::Pending),
+            None => None,
+        };
+        if let Some(checks) = checks {
+            summary.push_str(&format!(" {}", accessible::mark(checks)));
+        }
+        if pr.is_conflicting() {
+            summary.push_str(" conflicts");
+        }
+    }
+
+    summary
+}
+
+/// pr_record describes a pull request for `--format` templates
+pub fn pr_record(pr: &PrSummary) -> Value {
+    json!({
+        "number": pr.number,
+        "state": match pr.state {
+            PrState::Open => "open",
+            PrState::Closed => "closed",
+            PrState::Merged => "merged",
+        },
+        "draft": pr.is_draft,
+        "url": pr.url,
+        "base": pr.base,
+        "checks": pr.checks.as_ref().map(|checks| checks.to_lowercase()),
+        "conflicting": pr.is_conflicting(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr(state: PrState, is_draft: bool, checks: Option<&str>, mergeable: &str) -> PrSummary {
+        PrSummary {
+            number: 12,
+            state,
+            is_draft,
+            url: String::new(),
+            base: "main".to_string(),
+            mergeable: mergeable.to_string(),
+            checks: checks.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_describe_pr() {
+        assert_eq!(describe_pr(&pr(PrState::Open, false, Some("SUCCESS"), "MERGEABLE")), "#12 open ✓");
+        assert_eq!(describe_pr(&pr(PrState::Open, true, Some("PENDING"), "CONFLICTING")), "#12 draft ● conflicts");
+        assert_eq!(describe_pr(&pr(PrState::Merged, false, Some("FAILURE"), "UNKNOWN")), "#12 merged");
+    }
+
+    #[test]
+    fn test_staleness() {
+        assert_eq!(Staleness::of(2, 30), Staleness::Fresh);
+        assert_eq!(Staleness::of(7, 30), Staleness::Aging);
+        assert_eq!(Staleness::of(30, 30), Staleness::Stale);
+        assert_eq!(Staleness::of(3, 3), Staleness::Stale);
+        assert_eq!(Staleness::of(2, 3), Staleness::Fresh);
+        assert_eq!([0, 5, 20, 90].map(days), ["<1d", "5d", "2w", "3mo"]);
+    }
+
+    #[test]
+    fn test_page_range() {
+        assert_eq!(Page { limit: 50, number: 1 }.range(120), 0..50);
+        assert_eq!(Page { limit: 50, number: 3 }.range(120), 100..120);
+        assert_eq!(Page { limit: 50, number: 4 }.range(120), 120..120);
+        assert_eq!(Page { limit: 50, number: 0 }.range(120), 0..50);
+    }
+}
@@ -0,0 +1,202 @@
+//! Keeping two sage processes from changing a repository at the same time
+//!
+//! Every command that changes something takes `.git/sage.lock` first. It records which sage
+//! holds it, so a second one can say what it's waiting on, and so a lock left behind by a sage
+//! that was killed can be recognised and cleared.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::Duration;
+
+use crate::{git, ui::ColorizeExt};
+
+/// File name of the lock, inside the .git directory
+const LOCK_FILE: &str = "sage.lock";
+/// How often a waiting sage checks whether the lock is free
+const POLL: Duration = Duration::from_millis(200);
+/// How long a lock that doesn't say who holds it is given before it counts as abandoned
+const UNREADABLE_GRACE: Duration = Duration::from_secs(5);
+
+/// Who holds the lock
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Holder {
+    pid: u32,
+    /// The sage command that took it, e.g. `sync`
+    command: String,
+    started_at: DateTime<Utc>,
+}
+
+/// Holds the lock until dropped
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// path returns where the lock lives for the current repository
+pub fn path() -> Result<PathBuf> {
+    Ok(git::repo::git_dir()?.join(LOCK_FILE))
+}
+
+/// acquire takes the lock for `command`. When another sage holds it, this fails saying which,
+/// unless `wait` is set, in which case it waits for that sage to finish.
+pub async fn acquire(command: &str, wait: bool) -> Result<Lock> {
+    let path = path()?;
+    let holder = Holder { pid: process::id(), command: command.to_string(), started_at: Utc::now() };
+    let mut announced = false;
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(serde_json::to_string(&holder)?.as_bytes())?;
+                return Ok(Lock { path });
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(anyhow!("Failed to create {}: {}", path.display(), e)),
+        }
+
+        let Some(other) = read(&path) else {
+            // Either still being written by the other sage, or left empty when it was killed
+            // right after creating it
+            if is_abandoned(&path) {
+                remove_stale(&path, None);
+            }
+            tokio::time::sleep(POLL).await;
+            continue;
+        };
+
+        if !is_running(other.pid) {
+            eprintln!(
+                "{} Removed a lock left by sage {} (pid {}), which is no longer running",
+                "WARNING:".yellow(),
+                other.command,
+                other.pid
+            );
+            remove_stale(&path, Some(&other));
+            continue;
+        }
+
+        if !wait {
+            return Err(anyhow!(
+                "Another sage is changing this repository: sage {} (pid {}), started {}.\n\
+                 Run again with --wait to wait for it, or delete {} if it isn't running",
+                other.command,
+                other.pid,
+                other.started_at.with_timezone(&chrono::Local).format("%H:%M:%S"),
+                path.display()
+            ));
+        }
+
+        if !announced {
+            eprintln!("Waiting for {} (pid {}) to finish...", format!("sage {}", other.command).sage(), other.pid);
+            announced = true;
+        }
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+/// is_abandoned returns if a file has gone unchanged for longer than a sage needs to write it
+fn is_abandoned(path: &Path) -> bool {
+    let age = fs::metadata(path).and_then(|meta| meta.modified()).ok().and_then(|at| at.elapsed().ok());
+    age.is_some_and(|age| age > UNREADABLE_GRACE)
+}
+
+/// remove_stale removes the lock found held by `stale`, or unreadable when that's None. Two sages
+/// can find the same stale lock, and the first to remove it may take the lock before the second
+/// gets to it, so removing is done under a second lock file, and only once the lock is checked to
+/// still be the stale one.
+fn remove_stale(path: &Path, stale: Option<&Holder>) {
+    let takeover = path.with_file_name(format!("{}.takeover", LOCK_FILE));
+    match OpenOptions::new().write(true).create_new(true).open(&takeover) {
+        Ok(_) => {}
+        Err(_) => {
+            // Another sage is removing it, unless that one was killed part way
+            if is_abandoned(&takeover) {
+                let _ = fs::remove_file(&takeover);
+            }
+            return;
+        }
+    }
+
+    let unchanged = match stale {
+        Some(stale) => read(path).as_ref() == Some(stale),
+        None => read(path).is_none() && is_abandoned(path),
+    };
+    if unchanged {
+        let _ = fs::remove_file(path);
+    }
+    let _ = fs::remove_file(&takeover);
+}
+
+/// Read the lock's holder, or None when the file is gone or still being written
+fn read(path: &Path) -> Option<Holder> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// is_running returns if a process with `pid` exists
+fn is_running(pid: u32) -> bool {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("tasklist");
+        command.args(["/FI", &format!("PID eq {}", pid), "/NH"]);
+        command
+    } else {
+        let mut command = Command::new("kill");
+        command.args(["-0", &pid.to_string()]);
+        command
+    };
+
+    match command.stderr(Stdio::null()).output() {
+        Ok(output) if cfg!(windows) => String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()),
+        Ok(output) => output.status.success(),
+        // Can't tell, so treat it as running rather than take the lock from under it
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_is_running() {
+        assert!(is_running(process::id()));
+
+        let mut child = Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!is_running(pid));
+    }
+
+    #[test]
+    fn test_remove_stale_leaves_a_new_lock() {
+        let dir = std::env::temp_dir().join(format!("sage-lock-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOCK_FILE);
+        let holder = |pid| Holder { pid, command: "sync".to_string(), started_at: Utc::now() };
+        let stale = holder(1);
+
+        // Taken by another sage since it was found stale
+        fs::write(&path, serde_json::to_string(&holder(2)).unwrap()).unwrap();
+        remove_stale(&path, Some(&stale));
+        assert!(path.exists());
+
+        fs::write(&path, serde_json::to_string(&stale).unwrap()).unwrap();
+        remove_stale(&path, Some(&stale));
+        assert!(!path.exists());
+        assert!(!dir.join(format!("{}.takeover", LOCK_FILE)).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
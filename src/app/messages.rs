@@ -0,0 +1,132 @@
+//! Recent commit messages, to reuse with `sage commit --reuse`
+//!
+//! Every message sage commit is about to use, typed or written by the AI, is kept in
+//! `.git/sage/messages.json` before the commit is made, so one lost to a failed hook or guard
+//! can be picked up again. The newest come first, and a message used again moves back to the
+//! top.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use crate::{git, ui::{self, accessible}};
+
+/// How many messages are kept
+const MAX_MESSAGES: usize = 30;
+
+/// A commit message sage commit used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    pub message: String,
+    /// Whether the AI wrote it
+    #[serde(default)]
+    pub ai: bool,
+    pub used_at: DateTime<Utc>,
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(git::repo::git_dir()?.join("sage").join("messages.json"))
+}
+
+/// load returns the messages kept for this repository, newest first
+pub fn load() -> Result<Vec<Message>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// record keeps a message sage commit is about to use
+pub fn record(message: &str, ai: bool) -> Result<()> {
+    let mut messages = load()?;
+    remember(&mut messages, Message { message: message.trim().to_string(), ai, used_at: Utc::now() });
+
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&messages)?)?;
+    Ok(())
+}
+
+/// Put a message at the top, dropping an earlier copy of it and the oldest past the limit
+fn remember(messages: &mut Vec<Message>, message: Message) {
+    if message.message.is_empty() {
+        return;
+    }
+    messages.retain(|kept| kept.message != message.message);
+    messages.insert(0, message);
+    messages.truncate(MAX_MESSAGES);
+}
+
+/// pick asks which recent message to reuse, then lets it be edited before committing
+pub fn pick() -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow!("--reuse asks which message to use, so it needs a terminal. Pass the message instead"));
+    }
+    let messages = load()?;
+    if messages.is_empty() {
+        return Err(anyhow!("No commit messages to reuse yet. They're kept from the next sage commit on"));
+    }
+
+    let labels = messages.iter().map(label).collect::<Vec<_>>();
+    let index = if accessible::enabled() {
+        accessible::select("Reuse which message?", &labels, Some(0))?
+    } else {
+        inquire::Select::new("Reuse which message?", labels).raw_prompt()?.index
+    };
+    let message = &messages[index].message;
+
+    // Editing a message takes an editor, which a screen reader may not follow
+    if accessible::enabled() {
+        return Ok(message.clone());
+    }
+    let edited = inquire::Editor::new("Edit the message:")
+        .with_predefined_text(message)
+        .with_help_message("Save it as it is to use it unchanged")
+        .prompt()?;
+    let edited = edited.trim();
+    if edited.is_empty() {
+        return Err(anyhow!("Not committing, the message is empty"));
+    }
+    Ok(edited.to_string())
+}
+
+/// The message's subject, with when it was used and whether the AI wrote it
+fn label(message: &Message) -> String {
+    let subject = message.message.lines().next().unwrap_or_default();
+    let more = if message.message.lines().count() > 1 { " …" } else { "" };
+    let when = message.used_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+    let ai = if message.ai { ", AI" } else { "" };
+    format!("{}{} {}", subject, more, ui::gray(&format!("({}{})", when, ai)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember() {
+        let message = |text: &str| Message { message: text.to_string(), ai: false, used_at: Utc::now() };
+        let mut messages = Vec::new();
+        remember(&mut messages, message("chore: bump deps"));
+        remember(&mut messages, message("fix: typo"));
+        remember(&mut messages, message(""));
+        remember(&mut messages, message("chore: bump deps"));
+        assert_eq!(
+            messages.iter().map(|kept| kept.message.as_str()).collect::<Vec<_>>(),
+            ["chore: bump deps", "fix: typo"]
+        );
+
+        for n in 0..MAX_MESSAGES {
+            remember(&mut messages, message(&format!("fix: {}", n)));
+        }
+        assert_eq!(messages.len(), MAX_MESSAGES);
+        assert_eq!(messages[0].message, format!("fix: {}", MAX_MESSAGES - 1));
+    }
+}
@@ -9,4 +9,63 @@ pub mod status;
 pub mod switch;
 pub mod sync;
 pub mod clean;
-pub mod history;
\ No newline at end of file
+pub mod history;
+pub mod diff;
+pub mod stack;
+pub mod send_email;
+pub mod config;
+pub mod dco;
+pub mod identity;
+pub mod ignore;
+pub mod restack;
+pub mod files;
+pub mod grep;
+pub mod lfs;
+pub mod guard;
+pub mod fixup_ci;
+pub mod ci;
+pub mod inbox;
+pub mod watch;
+pub mod open;
+pub mod auth;
+pub mod credentials;
+pub mod remote;
+pub mod interrupt;
+pub mod resume;
+pub mod gc;
+pub mod purge;
+pub mod hooks;
+pub mod keys;
+pub mod undo;
+pub mod checkpoint;
+pub mod session;
+pub mod note;
+pub mod todos;
+pub mod features;
+pub mod review;
+pub mod crash;
+pub mod bug_report;
+pub mod lock;
+pub mod pull_comment;
+pub mod pull_edit;
+pub mod pull_size;
+pub mod owners;
+pub mod reviewers;
+pub mod vars;
+pub mod snapshot;
+pub mod policy;
+pub mod doctor;
+pub mod plugin;
+pub mod profile;
+pub mod stats;
+pub mod explain;
+pub mod intent;
+pub mod prompts;
+pub mod redact;
+pub mod context;
+pub mod verify;
+pub mod messages;
+pub mod wip;
+pub mod contribute;
+pub mod rename;
+pub mod visualize;
\ No newline at end of file
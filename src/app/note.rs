@@ -0,0 +1,51 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{errors, git, ui::ColorizeExt};
+
+/// note shows, sets or clears the note on `branch`, or the current branch when none is given
+pub fn note(branch: Option<&str>, text: Option<&str>, clear: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let branch = match branch {
+        Some(branch) => branch.to_string(),
+        None => git::branch::current()?,
+    };
+    if !git::branch::exists(&branch) {
+        return Err(anyhow!("Branch {} does not exist", branch));
+    }
+
+    if clear {
+        git::stack::clear_note(&branch)?;
+        println!("✨ Cleared the note on {}", branch.sage());
+        return Ok(());
+    }
+
+    match text.map(str::trim) {
+        Some("") => Err(anyhow!("The note is empty. Use --clear to remove it")),
+        Some(text) => {
+            git::stack::set_note(&branch, text)?;
+            println!("✨ Noted on {}: {}", branch.sage(), text);
+            Ok(())
+        }
+        None => {
+            match git::stack::note(&branch)? {
+                Some(note) => println!("{}", note),
+                None => println!(
+                    "{} has no note. Add one with {}",
+                    branch,
+                    "sage note 'waiting on infra team'".sage()
+                ),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// describe_note renders a branch note to follow the branch in `sage list` and `sage stack status`
+pub fn describe_note(note: &str) -> String {
+    format!("— {}", note).italic().to_string()
+}
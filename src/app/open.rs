@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crate::{errors, forge::{Kind, Remote}, gh, git, ui, ui::ColorizeExt};
+
+/// open opens the web page for a target: `repo`, `pr`, `ci`, a `path[:line[-end]]`, or by
+/// default the current branch's pull request
+pub async fn open(target: Option<&str>, print: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let remote = Remote::origin()?;
+    let branch = git::branch::current()?;
+
+    let url = match target {
+        Some("repo") => remote.repo_url(),
+        Some("ci") => remote.ci_url(&branch),
+        Some("pr") | None => pull_request_url(&remote, &branch, target.is_some()).await?,
+        Some(location) => file_url(&remote, location)?,
+    };
+
+    if print {
+        println!("{}", url);
+        return Ok(());
+    }
+
+    println!("Opening {}", url.url());
+    ui::open_in_browser(&url)
+}
+
+/// The current branch's pull request, falling back to the branch itself when there is none
+async fn pull_request_url(remote: &Remote, branch: &str, required: bool) -> Result<String> {
+    if remote.kind == Kind::GitLab {
+        return Ok(format!("{}/-/merge_requests?source_branch={}", remote.repo_url(), branch));
+    }
+
+    if let Some(pull) = gh::pulls::get_by_branch(branch).await? {
+        return Ok(remote.pull_request_url(pull.number));
+    }
+
+    if required {
+        return Err(anyhow!("No pull request found for {}", branch));
+    }
+
+    println!("{}", format!("No pull request for {}, opening the branch instead", branch).gray());
+    Ok(remote.branch_url(branch))
+}
+
+/// A file (and optionally lines) at the current commit
+fn file_url(remote: &Remote, location: &str) -> Result<String> {
+    let (path, lines) = parse_location(location);
+    let path = normalize(&format!("{}{}", git::repo::show_prefix()?, path))
+        .ok_or_else(|| anyhow!("{} is outside the repository", path))?;
+
+    let commit = git::repo::rev_parse("HEAD")?;
+    if !git::repo::is_pushed(&commit)? {
+        println!(
+            "{} HEAD has not been pushed, the link will only work once it is",
+            "WARNING:".yellow()
+        );
+    }
+
+    Ok(remote.file_url(&commit, &path, lines))
+}
+
+/// Split `src/lib.rs:42` or `src/lib.rs:10-20` into the path and line range
+fn parse_location(location: &str) -> (&str, Option<(usize, Option<usize>)>) {
+    let Some((path, lines)) = location.rsplit_once(':') else {
+        return (location, None);
+    };
+
+    let parsed = match lines.split_once('-') {
+        Some((start, end)) => start.parse().ok().zip(end.parse().ok()).map(|(start, end)| (start, Some(end))),
+        None => lines.parse().ok().map(|start| (start, None)),
+    };
+
+    match parsed {
+        Some(lines) => (path, Some(lines)),
+        None => (location, None),
+    }
+}
+
+/// Resolve `.` and `..` in a repository-relative path
+fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(parse_location("src/lib.rs"), ("src/lib.rs", None));
+        assert_eq!(parse_location("src/lib.rs:42"), ("src/lib.rs", Some((42, None))));
+        assert_eq!(parse_location("src/lib.rs:10-20"), ("src/lib.rs", Some((10, Some(20)))));
+        assert_eq!(parse_location("weird:name"), ("weird:name", None));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("src/app/../lib.rs").as_deref(), Some("src/lib.rs"));
+        assert_eq!(normalize("./README.md").as_deref(), Some("README.md"));
+        assert_eq!(normalize("../outside"), None);
+    }
+}
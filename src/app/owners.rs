@@ -0,0 +1,214 @@
+//! Reading CODEOWNERS the way GitHub does
+//!
+//! The file is looked for in `.github/`, the repository root and `docs/`, first one found wins.
+//! Each line is a gitignore-style pattern followed by owners (`@user`, `@org/team` or an email),
+//! and the last matching line decides who owns a path. A line without owners leaves the paths it
+//! matches unowned.
+//!
+//! `sage status` and `sage pr create` use it to show whose review the changed files will need.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::fs;
+
+use crate::{app::guard::glob_match, config, git, ui::ColorizeExt};
+
+/// Where GitHub looks for a CODEOWNERS file, in order
+const LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+/// How many owners changes can need before they're flagged as slow to review
+const DEFAULT_MAX_OWNERS: usize = 3;
+
+/// A single CODEOWNERS line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub pattern: String,
+    pub owners: Vec<String>,
+}
+
+impl Rule {
+    /// matches returns if the rule's pattern covers `path`. A pattern naming a directory covers
+    /// everything inside it.
+    pub fn matches(&self, path: &str) -> bool {
+        let pattern = self.pattern.trim_end_matches('/');
+        // Like gitignore, only a slash at the start or in the middle anchors a pattern
+        let pattern = if pattern.contains('/') { pattern.to_string() } else { format!("**/{}", pattern) };
+        glob_match(&pattern, path) || glob_match(&format!("{}/**", pattern), path)
+    }
+}
+
+/// CodeOwners is a parsed CODEOWNERS file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeOwners {
+    pub rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    pub fn parse(contents: &str) -> CodeOwners {
+        let rules = contents
+            .lines()
+            .map(|line| line.split_once(" #").map_or(line, |(rule, _)| rule).trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?.to_string();
+                Some(Rule { pattern, owners: fields.map(|owner| owner.to_string()).collect() })
+            })
+            .collect();
+        CodeOwners { rules }
+    }
+
+    /// owners_of returns who owns `path`, nobody when no rule with owners matches it last
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        self.rules.iter().rev().find(|rule| rule.matches(path)).map_or(&[], |rule| &rule.owners)
+    }
+}
+
+/// load reads the repository's CODEOWNERS file from the working tree, or None when there isn't one
+pub fn load() -> Result<Option<CodeOwners>> {
+    let root = git::repo::toplevel()?;
+    Ok(LOCATIONS
+        .iter()
+        .find_map(|location| fs::read_to_string(root.join(location)).ok())
+        .map(|contents| CodeOwners::parse(&contents)))
+}
+
+/// Who owns a set of changed files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ownership {
+    /// Each owner with how many of the files they own, most first
+    pub owners: Vec<(String, usize)>,
+    /// Files nobody owns
+    pub unowned: usize,
+}
+
+impl CodeOwners {
+    /// ownership adds up who owns `paths`
+    pub fn ownership(&self, paths: &[String]) -> Ownership {
+        let mut ownership = Ownership::default();
+        for path in paths {
+            let owners = self.owners_of(path);
+            if owners.is_empty() {
+                ownership.unowned += 1;
+            }
+            for owner in owners {
+                match ownership.owners.iter_mut().find(|(known, _)| known == owner) {
+                    Some((_, count)) => *count += 1,
+                    None => ownership.owners.push((owner.clone(), 1)),
+                }
+            }
+        }
+        ownership.owners.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ownership
+    }
+}
+
+/// print_summary shows whose review `paths` will need, warning when that's more owners than
+/// pr.max_owners. Nothing is shown without a CODEOWNERS file.
+pub fn print_summary(paths: &[String]) -> Result<()> {
+    let Some(codeowners) = load()? else {
+        return Ok(());
+    };
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let ownership = codeowners.ownership(paths);
+    let owners = ownership
+        .owners
+        .iter()
+        .map(|(owner, files)| format!("{} {}", owner, format!("({})", plural(*files)).gray()))
+        .collect::<Vec<_>>();
+    if owners.is_empty() {
+        println!("{} {}", "Owners:".sage(), "none of the changed files are owned".gray());
+    } else {
+        println!("{} {}", "Owners:".sage(), owners.join(", "));
+    }
+    if ownership.unowned > 0 && !owners.is_empty() {
+        println!("  {}", format!("{} without an owner", plural(ownership.unowned)).gray());
+    }
+
+    let max_owners = config::get("pr.max_owners")
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_OWNERS);
+    if ownership.owners.len() > max_owners {
+        println!(
+            "{} These changes need review from {} owners, which is likely to be slow. Consider splitting them up",
+            "WARNING:".yellow(),
+            ownership.owners.len()
+        );
+    }
+    Ok(())
+}
+
+/// print_status shows whose review the current branch's changes will need, committed or not,
+/// compared with the branch's parent
+pub fn print_status() -> Result<()> {
+    let branch = git::branch::current()?;
+    let base = match git::stack::parent(&branch)? {
+        Some(parent) => parent,
+        None => git::repo::default_branch()?,
+    };
+    // On the base itself only uncommitted changes are the user's
+    let since = if branch == base { "HEAD".to_string() } else { git::repo::merge_base(&base, "HEAD")? };
+    let paths = git::files::numstat(&since)?.into_iter().map(|change| change.path).collect::<Vec<_>>();
+    print_summary(&paths)
+}
+
+fn plural(files: usize) -> String {
+    if files == 1 { "1 file".to_string() } else { format!("{} files", files) }
+}
+
+/// is_team returns if an owner is an `@org/team` rather than a single person
+pub fn is_team(owner: &str) -> bool {
+    owner.starts_with('@') && owner.contains('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owners_of() {
+        let owners = CodeOwners::parse(
+            "# Everything else\n\
+             *       @acme/core\n\
+             *.md    @writer  # docs anywhere\n\
+             /api/   @acme/backend @dana\n\
+             docs/   @acme/docs\n\
+             /api/generated/\n",
+        );
+
+        assert_eq!(owners.owners_of("src/main.rs"), ["@acme/core"]);
+        assert_eq!(owners.owners_of("README.md"), ["@writer"]);
+        assert_eq!(owners.owners_of("api/handlers/users.go"), ["@acme/backend", "@dana"]);
+        // Unanchored directories match at any depth, anchored ones only at the root
+        assert_eq!(owners.owners_of("web/docs/intro.txt"), ["@acme/docs"]);
+        assert_eq!(owners.owners_of("web/api/client.ts"), ["@acme/core"]);
+        // A rule without owners unowns what it matches
+        assert!(owners.owners_of("api/generated/types.go").is_empty());
+    }
+
+    #[test]
+    fn test_ownership() {
+        let owners = CodeOwners::parse("/api/ @acme/backend @dana\n/web/ @acme/frontend\n");
+        let paths = ["api/a.go", "api/b.go", "web/app.ts", "README.md"].map(str::to_string);
+        assert_eq!(
+            owners.ownership(&paths),
+            Ownership {
+                owners: vec![
+                    ("@acme/backend".to_string(), 2),
+                    ("@dana".to_string(), 2),
+                    ("@acme/frontend".to_string(), 1),
+                ],
+                unowned: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_is_team() {
+        assert!(is_team("@acme/core"));
+        assert!(!is_team("@dana"));
+        assert!(!is_team("dana@example.com"));
+    }
+}
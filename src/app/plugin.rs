@@ -0,0 +1,20 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+use crate::{plugin, ui::ColorizeExt};
+
+/// install is `sage plugin install`: check the plugin in `path` against its publisher's
+/// signature and install it, without needing network access
+pub fn install(path: &Path) -> Result<()> {
+    if !path.is_dir() {
+        return Err(anyhow!("{} isn't a plugin directory", path.display()));
+    }
+
+    let (manifest, signed_by) = plugin::install(path)?;
+    let version = if manifest.version.is_empty() { String::new() } else { format!(" {}", manifest.version) };
+    match signed_by {
+        Some(key) => println!("✨ Installed plugin {}{}, signed by key {}", manifest.name.sage(), version, key),
+        None => println!("✨ Installed plugin {}{} (unsigned)", manifest.name.sage(), version),
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{config, plugin::signature, policy, ui::{accessible::{self, Mark}, actions::Annotation, ColorizeExt}};
+
+/// sync is `sage policy sync`: fetch the org policy bundle from policy.source and install it
+/// with its plugins
+pub fn sync() -> Result<()> {
+    let source = config::get("policy.source").ok_or_else(|| {
+        anyhow!("No org policy to sync. Set policy.source to the policy bundle's git repository or .tar.gz URL")
+    })?;
+    let sha256 = config::get("policy.sha256");
+    let keys = signature::trusted_keys(&config::get("policy.trusted_keys").unwrap_or_default())?;
+    if keys.is_empty() && policy::is_tarball(&source) && sha256.is_none() {
+        println!(
+            "{} Neither policy.trusted_keys nor policy.sha256 is set, so the bundle can't be checked before it's installed",
+            "WARNING:".yellow()
+        );
+    }
+
+    println!("Syncing the org policy from {}", source.sage());
+    let (policy, installed) = policy::sync(&source, sha256.as_deref(), &keys)?;
+
+    let version = if policy.version.is_empty() { String::new() } else { format!(" {}", policy.version) };
+    println!("✨ Installed the {}{} policy", policy.name.sage(), version);
+    let bullet = accessible::mark(Mark::Bullet).sage();
+    if let Some(key) = &installed.signed_by {
+        println!("  {} signed with key {}", bullet, key);
+    }
+    for name in &policy.plugins {
+        println!("  {} plugin {}", bullet, name);
+    }
+    if !policy.protected_branches.is_empty() {
+        println!("  {} protected branches: {}", bullet, policy.protected_branches.join(", "));
+    }
+    if policy.commits.conventional {
+        println!("  {} conventional commit messages", bullet);
+    }
+    if !policy.config.is_empty() {
+        println!("  {} {} settings", bullet, policy.config.len());
+    }
+    println!("Check everything follows it with {}", "sage doctor".sage());
+    Ok(())
+}
+
+/// check_commit stops a commit the org policy doesn't allow: one on a protected branch, or with
+/// a message that breaks its commit rules
+pub fn check_commit(branch: &str, message: &str) -> Result<()> {
+    let Some(policy) = policy::load()? else {
+        return Ok(());
+    };
+    if policy.is_protected(branch) {
+        return Err(anyhow!(
+            "{} is protected by the {} policy. Commit on a branch of its own instead, e.g. {}",
+            branch,
+            policy.name,
+            "sage start <name>"
+        ));
+    }
+    if let Some(problem) = policy.check_message(message) {
+        let title = format!("{} policy: commit message", policy.name);
+        Annotation { title: &title, message: &problem, ..Annotation::default() }.emit();
+        return Err(anyhow!("The {} policy doesn't allow this commit: {}", policy.name, problem));
+    }
+    Ok(())
+}
+
+/// check_push stops pushing a branch the org policy protects
+pub fn check_push(branch: &str) -> Result<()> {
+    match policy::load()? {
+        Some(policy) if policy.is_protected(branch) => Err(anyhow!(
+            "{} is protected by the {} policy, push a branch of its own and open a pull request instead",
+            branch,
+            policy.name
+        )),
+        _ => Ok(()),
+    }
+}
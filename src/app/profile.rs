@@ -0,0 +1,69 @@
+//! `--profile` and `sage profile`: run a command and show where its time went
+
+use anyhow::Result;
+use chrono::Local;
+use colored::Colorize;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{git, profile, ui::ColorizeExt};
+
+/// How many of the slowest timings the breakdown lists
+const SLOWEST: usize = 5;
+
+/// measure runs `command` with profiling on, then prints the breakdown to stderr (so it never
+/// mixes with the command's own output) and saves the trace. The command's result is passed on.
+pub async fn measure<F: Future<Output = Result<()>>>(name: &str, command: F) -> Result<()> {
+    profile::start();
+    let result = command.await;
+    let Some((total, timings)) = profile::finish() else {
+        return result;
+    };
+
+    eprintln!();
+    eprintln!("Profile of {}: {}", format!("sage {}", name).sage(), millis(total).bold());
+    for (phase, spent, count) in profile::breakdown(&timings) {
+        let calls = match count {
+            0 => String::new(),
+            1 => "1 call".to_string(),
+            count => format!("{} calls", count),
+        };
+        eprintln!("  {:<10} {:>8}  {}", phase.name(), millis(spent), calls.gray());
+    }
+
+    let mut slowest = timings.iter().collect::<Vec<_>>();
+    slowest.sort_by_key(|timing| std::cmp::Reverse(timing.duration));
+    if !slowest.is_empty() {
+        eprintln!("Slowest:");
+    }
+    for timing in slowest.into_iter().take(SLOWEST) {
+        eprintln!("  {:>8}  {} {}", millis(timing.duration), timing.name, format!("({})", timing.phase.name()).gray());
+    }
+
+    // A trace that can't be saved mustn't turn a command that worked into a failure
+    match save_trace(name, total, &timings) {
+        Ok(path) => eprintln!("Trace saved to {} (open it in chrome://tracing or ui.perfetto.dev)", path.display()),
+        Err(e) => eprintln!("{} Failed to save the trace: {}", "WARNING:".yellow(), e),
+    }
+    result
+}
+
+fn save_trace(name: &str, total: Duration, timings: &[profile::Timing]) -> Result<PathBuf> {
+    // Traces belong with the repository they were taken in when there is one
+    let dir = match git::repo::git_dir() {
+        Ok(git_dir) => git_dir.join("sage").join("profiles"),
+        Err(_) => std::env::temp_dir().join("sage-profiles"),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let command = name.split_whitespace().next().unwrap_or("sage");
+    let path = dir.join(format!("{}-{}.json", Local::now().format("%Y%m%d-%H%M%S"), command));
+    fs::write(&path, profile::chrome_trace(&format!("sage {}", name), total, timings)?)?;
+    Ok(path)
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,189 @@
+//! The prompts sage sends the AI, which a repository can override
+//!
+//! Every prompt has a built-in version. A repository replaces one by committing a Markdown file
+//! under `.sage/prompts/`, e.g. `.sage/prompts/commit.md`, written in the same `{{...}}` template
+//! language as commit.template (see [`crate::app::vars`]). Besides `{{ticket}}`, `{{branch}}` and
+//! the other template variables, a prompt can use:
+//!
+//! - `{{diff}}`: the changes, cut short to fit what the model accepts
+//! - `{{commits}}`: the commits involved, one per line
+//! - `{{conventions}}`: the org policy's rules for commit messages, empty without any
+//! - `{{title}}`: the pull request's title, for `pr`
+//! - `{{facts}}`: what's known about the change, for `explain`
+//! - `{{context}}`: summaries of the files related to the diff, from the index `sage ai index`
+//!   builds (see [`crate::app::context`]), empty without one
+
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{ai::{self, budget}, app::{context, redact, vars::Vars}, config, git, policy, profile::{self, Phase}, ui::ColorizeExt};
+
+/// A prompt a repository can override
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    /// Writing a commit message, or a pull request's title, from a diff
+    Commit,
+    /// Writing a pull request's description from its title and commits
+    Pr,
+    /// Reviewing a branch
+    Review,
+    /// Explaining a change with sage explain
+    Explain,
+}
+
+impl Prompt {
+    pub const ALL: [Prompt; 4] = [Prompt::Commit, Prompt::Pr, Prompt::Review, Prompt::Explain];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Prompt::Commit => "commit",
+            Prompt::Pr => "pr",
+            Prompt::Review => "review",
+            Prompt::Explain => "explain",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Prompt> {
+        Prompt::ALL.into_iter().find(|prompt| prompt.name() == name.trim().trim_end_matches(".md"))
+    }
+}
+
+/// What a prompt is about; each prompt uses the parts it needs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Inputs<'a> {
+    pub diff: &'a str,
+    pub commits: &'a str,
+    pub title: &'a str,
+    pub facts: &'a str,
+    /// Filled in by [`render`] from the repository's index
+    pub context: &'a str,
+}
+
+/// path returns where a repository overrides a prompt
+fn path(prompt: Prompt) -> Result<PathBuf> {
+    Ok(git::repo::toplevel()?.join(".sage").join("prompts").join(format!("{}.md", prompt.name())))
+}
+
+/// The repository's override of a prompt, if it has one
+fn load(prompt: Prompt) -> Result<Option<(PathBuf, String)>> {
+    // Outside a repository there's nothing to override the built-ins
+    let Ok(path) = path(prompt) else {
+        return Ok(None);
+    };
+    match fs::read_to_string(&path) {
+        Ok(template) => Ok(Some((path, template))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// render returns the prompt to send: the repository's override filled in when there is one,
+/// the built-in otherwise
+pub fn render(prompt: Prompt, inputs: Inputs) -> Result<String> {
+    let related = context::related(inputs.diff)?;
+    let inputs = Inputs { context: &related, ..inputs };
+    match load(prompt)? {
+        Some((path, template)) => {
+            let vars = Vars::for_branch(&git::branch::current().unwrap_or_default());
+            fill(&template, inputs, &vars, &conventions()).map_err(|e| anyhow!("Failed to render {}: {}", path.display(), e))
+        }
+        None => Ok(built_in(prompt, inputs)),
+    }
+}
+
+/// ask sends `prompt` to the AI, timed as `name`, once the changes to files in `ai.exclude_paths`,
+/// secrets and email addresses are taken out of it. Nothing is sent while `ai.enabled` is false.
+pub async fn ask(name: &str, prompt: &str) -> Result<String> {
+    if !config::get_bool("ai.enabled", true) {
+        return Err(anyhow!("AI is turned off here (ai.enabled is false), so nothing was sent"));
+    }
+
+    let redacted = redact::redact(prompt, &redact::excludes(config::get("ai.exclude_paths")));
+    if let Some(summary) = redacted.summary() {
+        println!("{}", format!("{} before asking the AI", summary).gray());
+    }
+    profile::time(Phase::Ai, name, ai::ask(&redacted.text)).await
+}
+
+/// Fill in an override, cutting the diff short so the whole prompt fits
+fn fill(template: &str, inputs: Inputs, vars: &Vars, conventions: &str) -> Result<String> {
+    let render = |diff: &str| {
+        vars.render(
+            template,
+            &[
+                ("diff", diff),
+                ("commits", inputs.commits),
+                ("title", inputs.title),
+                ("facts", inputs.facts),
+                ("conventions", conventions),
+                ("context", inputs.context),
+            ],
+        )
+    };
+    let room = budget::room(&render("")?);
+    render(&budget::fit(inputs.diff, room))
+}
+
+fn built_in(prompt: Prompt, inputs: Inputs) -> String {
+    let related = match inputs.context {
+        "" => String::new(),
+        context => format!("\n\nFiles in this repository related to the change, for context:\n\n{}", context),
+    };
+    let fit = |build: &dyn Fn(&str) -> String| {
+        let room = budget::room(&format!("{}{}", build(""), related));
+        format!("{}{}", build(&budget::fit(inputs.diff, room)), related)
+    };
+    match prompt {
+        Prompt::Commit => fit(&|diff| ai::prompts::commit_message_prompt(diff)),
+        Prompt::Pr => fit(&|_| ai::prompts::pr_description_prompt(inputs.title, inputs.commits)),
+        Prompt::Review => fit(&|diff| ai::prompts::review_prompt(inputs.commits, diff)),
+        Prompt::Explain => fit(&|diff| ai::prompts::explain_prompt(inputs.facts, diff)),
+    }
+}
+
+/// The org policy's commit rules in words, empty without a policy or rules
+fn conventions() -> String {
+    policy::load().ok().flatten().and_then(|policy| policy.commits.describe()).unwrap_or_default()
+}
+
+/// show prints the prompt sage uses for `name`, with the variables left as `{{...}}`
+/// placeholders. Where it comes from goes to stderr, so the prompt can be saved as an override.
+pub fn show(name: &str) -> Result<()> {
+    let prompt = Prompt::from_name(name).ok_or_else(|| {
+        let names = Prompt::ALL.map(Prompt::name).join(", ");
+        anyhow!("There's no prompt called '{}'. The prompts are {}", name, names)
+    })?;
+
+    match load(prompt)? {
+        Some((path, template)) => {
+            eprintln!("{} {}\n", "Source:".sage(), path.display());
+            println!("{}", template.trim_end());
+        }
+        None => {
+            let placeholders = Inputs { diff: "{{diff}}", commits: "{{commits}}", title: "{{title}}", facts: "{{facts}}", context: "{{context}}" };
+            eprintln!("{} built in (override it with .sage/prompts/{}.md)\n", "Source:".sage(), prompt.name());
+            println!("{}", built_in(prompt, placeholders).trim_end());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        let vars = Vars { ticket: Some("ABC-1".to_string()), branch: "feature/ABC-1-login".to_string(), ..Vars::default() };
+        let inputs = Inputs { diff: "+login()", commits: "abc123 add login", ..Inputs::default() };
+        let template = "Ticket {{ticket}}. {{conventions}}\n{{commits}}\n{{diff}}";
+
+        assert_eq!(
+            fill(template, inputs, &vars, "Use feat or fix").unwrap(),
+            "Ticket ABC-1. Use feat or fix\nabc123 add login\n+login()"
+        );
+        assert_eq!(Prompt::from_name("review.md"), Some(Prompt::Review));
+        assert_eq!(Prompt::from_name("other"), None);
+    }
+}
@@ -1,7 +1,6 @@
 use anyhow::{anyhow, Result};
 use crate::{errors, gh::pulls, git};
 use colored::Colorize;
-use std::process::Command;
 
 pub async fn pull_checkout(pr_number: u64, branch_name: Option<String>) -> Result<()> {
     // Check to ensure we are in a repo first.
@@ -32,21 +31,48 @@ pub async fn pull_checkout(pr_number: u64, branch_name: Option<String>) -> Resul
         }
     };
 
-    // Determine branch name (use provided or from PR head reference)
-    let branch_name = match &branch_name {
-        Some(name) => name.clone(),
-        None => pull_request.head.ref_field.clone(), // Use the remote branch name
+    let origin_url = git::repo::remote_url("origin")?.unwrap_or_default();
+    let head_repo = pull_request.head.repo.as_ref().and_then(|repo| {
+        let owner = repo.owner.as_ref()?.login.clone();
+        let url = repo.clone_url.as_ref().map(|url| url.to_string()).unwrap_or_default();
+        Some(HeadRepo { owner, name: repo.name.clone(), url })
+    });
+    let head = locate_head((&owner, &repo_name), head_repo.as_ref(), &git::remote::list()?, &origin_url);
+    let head_ref = pull_request.head.ref_field.clone();
+
+    // Determine branch name (use provided or from PR head reference). A fork's branch is often
+    // called main too, so it's named after its owner when the name's taken by another branch.
+    // Not owner/branch, which reads like the fork's remote-tracking branch.
+    let branch_name = match (&branch_name, &head_repo) {
+        (Some(name), _) => name.clone(),
+        (None, Some(fork)) if head.remote().is_some() && taken(&head_ref, &head) => {
+            format!("{}-{}", fork.owner, head_ref)
+        }
+        (None, _) => head_ref.clone(), // Use the remote branch name
     };
 
     // Check if the branch already exists locally
     let branch_exists = git::branch::exists(&branch_name);
+
+    // A fork's branch is fetched from the fork, so it can be pulled and pushed to. When that
+    // fails, e.g. the fork is private, the pull request's own ref still has its commits.
+    let fork_fetched = match head.remote() {
+        Some(remote) if !branch_exists => match fetch_from_fork(&head, remote, &head_ref) {
+            Ok(()) => true,
+            Err(e) => {
+                println!("{} {}", "Warning: Couldn't fetch the branch from the fork, using the pull request's ref instead:".yellow(), e);
+                false
+            }
+        },
+        _ => false,
+    };
     
     if branch_exists {
         // Branch exists - switch to it and update
         println!("Branch {} already exists locally, switching to it...", branch_name.blue());
         
         // Use git command directly for more reliable checkout
-        let checkout_result = Command::new("git")
+        let checkout_result = git::command()
             .arg("checkout")
             .arg(&branch_name)
             .status()?;
@@ -61,7 +87,7 @@ pub async fn pull_checkout(pr_number: u64, branch_name: Option<String>) -> Resul
             println!("Pulling latest changes from remote...");
             
             // Pull directly with git command
-            let pull_result = Command::new("git")
+            let pull_result = git::command()
                 .arg("pull")
                 .arg("--ff-only")
                 .status()?;
@@ -74,13 +100,21 @@ pub async fn pull_checkout(pr_number: u64, branch_name: Option<String>) -> Resul
         }
 
         println!("Switched to branch: {}", branch_name.blue());
+    } else if let Some(remote) = head.remote().filter(|_| fork_fetched) {
+        let upstream = format!("{}/{}", remote, head_ref);
+        git::branch::create_at(&branch_name, &upstream)?;
+        git::branch::track(&branch_name, &upstream)?;
+        git::branch::switch(&branch_name, false)?;
+
+        println!("Successfully checked out pull request #{} to branch: {}", pr_number, branch_name.blue());
+        println!("Pull request is from: {}", upstream.yellow());
     } else {
         // Branch doesn't exist - fetch PR and create branch
         println!("Fetching and checking out pull request #{}...", pr_number);
         
         // Method 1: First try to fetch and checkout directly with one command
         // This is the most reliable way to get all the PR changes
-        let checkout_pr_result = Command::new("git")
+        let checkout_pr_result = git::command()
             .arg("fetch")
             .arg("origin")
             .arg(format!("pull/{}/head:{}", pr_number, branch_name))
@@ -91,7 +125,7 @@ pub async fn pull_checkout(pr_number: u64, branch_name: Option<String>) -> Resul
         }
         
         // Now checkout the branch
-        let checkout_result = Command::new("git")
+        let checkout_result = git::command()
             .arg("checkout")
             .arg(&branch_name)
             .status()?;
@@ -100,21 +134,138 @@ pub async fn pull_checkout(pr_number: u64, branch_name: Option<String>) -> Resul
             return Err(anyhow!("Failed to checkout branch: {}", branch_name));
         }
         
-        // Set the upstream tracking branch
-        let upstream_branch = &pull_request.head.ref_field;
-        let set_upstream_result = Command::new("git")
-            .arg("branch")
-            .arg("--set-upstream-to")
-            .arg(format!("origin/{}", upstream_branch))
-            .status()?;
-            
-        if !set_upstream_result.success() {
-            println!("{}", "Warning: Failed to set upstream branch. Remote tracking not configured.".yellow());
+        if head == Head::Gone {
+            // Without the fork there's nothing to track, only the pull request's own ref
+            println!("{}", "Warning: The fork this pull request came from was deleted, so the branch has no upstream.".yellow());
+        } else if head.remote().is_some() {
+            println!("{}", "Warning: The branch has no upstream, since the fork couldn't be fetched.".yellow());
+        } else {
+            // Set the upstream tracking branch
+            let set_upstream_result = git::command()
+                .arg("branch")
+                .arg("--set-upstream-to")
+                .arg(format!("origin/{}", head_ref))
+                .status()?;
+                
+            if !set_upstream_result.success() {
+                println!("{}", "Warning: Failed to set upstream branch. Remote tracking not configured.".yellow());
+            }
         }
 
         println!("Successfully checked out pull request #{} to branch: {}", pr_number, branch_name.blue());
-        println!("Pull request is from: {}", upstream_branch.yellow());
+        println!("Pull request is from: {}", head_ref.yellow());
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Fetch a pull request's branch from the fork it's in, adding a remote for the fork when there
+/// isn't one. A remote added here is removed again when the fetch fails.
+fn fetch_from_fork(head: &Head, remote: &str, branch: &str) -> Result<()> {
+    if let Head::NewRemote { name, url } = head {
+        println!("Adding remote {} for {}...", name.blue(), url);
+        git::remote::add_fork(name, url)?;
+    }
+    println!("Fetching {} from {}...", branch, remote);
+    let fetched = git::remote::fetch_branch(remote, branch);
+    if fetched.is_err() && matches!(head, Head::NewRemote { .. }) {
+        let _ = git::remote::remove(remote);
+    }
+    fetched
+}
+
+/// The repository a pull request's head branch is in
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HeadRepo {
+    owner: String,
+    name: String,
+    /// Its clone URL, for when origin's URL can't be reused
+    url: String,
+}
+
+/// Where to fetch a pull request's head branch from
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Head {
+    /// This repository, through origin
+    Origin,
+    /// A fork that a remote already points at
+    Remote(String),
+    /// A fork without a remote yet, to be added with this name and URL
+    NewRemote { name: String, url: String },
+    /// A fork that's been deleted, so only the pull request's own ref is left
+    Gone,
+}
+
+impl Head {
+    /// The remote to fetch a fork's branch from
+    fn remote(&self) -> Option<&str> {
+        match self {
+            Head::Remote(name) | Head::NewRemote { name, .. } => Some(name),
+            Head::Origin | Head::Gone => None,
+        }
+    }
+}
+
+/// Work out where a pull request's head branch is, given the repository it was opened against.
+/// A fork gets a remote named after its owner, reached the same way as origin.
+fn locate_head(base: (&str, &str), head_repo: Option<&HeadRepo>, remotes: &[git::remote::RemoteInfo], origin_url: &str) -> Head {
+    let Some(fork) = head_repo else {
+        return Head::Gone;
+    };
+    let same = |owner: &str, name: &str| owner.eq_ignore_ascii_case(&fork.owner) && name.eq_ignore_ascii_case(&fork.name);
+    if same(base.0, base.1) {
+        return Head::Origin;
+    }
+
+    let points_at_fork = |url: &str| git::repo::parse_remote_url(url).is_some_and(|(_, owner, name)| same(&owner, &name));
+    if let Some(remote) = remotes.iter().find(|remote| points_at_fork(&remote.url)) {
+        return Head::Remote(remote.name.clone());
+    }
+
+    let url = match (git::repo::parse_remote_url(origin_url), git::remote::protocol(origin_url)) {
+        (Some((host, _, _)), git::remote::Protocol::Ssh) => format!("git@{}:{}/{}.git", host, fork.owner, fork.name),
+        (Some((host, _, _)), git::remote::Protocol::Https) => format!("https://{}/{}/{}.git", host, fork.owner, fork.name),
+        _ => fork.url.clone(),
+    };
+    let taken = |name: &str| remotes.iter().any(|remote| remote.name == name);
+    let name = if taken(&fork.owner) { format!("{}-fork", fork.owner) } else { fork.owner.clone() };
+    Head::NewRemote { name, url }
+}
+
+/// Whether a local branch called `name` exists that isn't already tracking the fork's branch
+fn taken(name: &str, head: &Head) -> bool {
+    let fork_branch = head.remote().map(|remote| format!("{}/{}", remote, name));
+    git::branch::exists(name) && git::branch::upstream(name).ok().flatten() != fork_branch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(name: &str, url: &str) -> git::remote::RemoteInfo {
+        git::remote::RemoteInfo { name: name.to_string(), url: url.to_string() }
+    }
+
+    #[test]
+    fn test_locate_head() {
+        let fork = HeadRepo { owner: "octocat".to_string(), name: "sage".to_string(), url: "https://github.com/octocat/sage.git".to_string() };
+        let origin = "git@github.com:crazywolf132/sage.git";
+        let remotes = vec![remote("origin", origin)];
+
+        assert_eq!(locate_head(("crazywolf132", "sage"), None, &remotes, origin), Head::Gone);
+        assert_eq!(locate_head(("octocat", "Sage"), Some(&fork), &remotes, origin), Head::Origin);
+        assert_eq!(
+            locate_head(("crazywolf132", "sage"), Some(&fork), &remotes, origin),
+            Head::NewRemote { name: "octocat".to_string(), url: "git@github.com:octocat/sage.git".to_string() }
+        );
+
+        let remotes = vec![remote("origin", origin), remote("cat", "https://github.com/octocat/sage")];
+        assert_eq!(locate_head(("crazywolf132", "sage"), Some(&fork), &remotes, origin), Head::Remote("cat".to_string()));
+
+        let remotes = vec![remote("octocat", "https://github.com/octocat/other.git")];
+        assert_eq!(
+            locate_head(("crazywolf132", "sage"), Some(&fork), &remotes, "/srv/sage.git"),
+            Head::NewRemote { name: "octocat-fork".to_string(), url: fork.url.clone() }
+        );
+    }
+}
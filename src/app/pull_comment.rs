@@ -0,0 +1,41 @@
+use anyhow::{anyhow, Result};
+use std::io::{IsTerminal, Read};
+use crate::{errors, gh::pulls, git, ui::{accessible, ColorizeExt}};
+
+/// pull_comment posts a comment on a pull request, the current branch's unless `pr_number` is
+/// given. The message comes from `message`, from stdin when that's `-` or piped, or otherwise
+/// from $EDITOR.
+pub async fn pull_comment(pr_number: Option<u64>, message: Option<String>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let (owner, repo) = git::repo::owner_repo()?;
+    let number = pulls::resolve_number(&owner, &repo, pr_number).await?;
+
+    let message = match message.as_deref() {
+        Some("-") => read_stdin()?,
+        Some(message) => message.to_string(),
+        None if !std::io::stdin().is_terminal() => read_stdin()?,
+        // The editor prompt redraws the screen, so ask on one line in accessible mode
+        None if accessible::enabled() => inquire::Text::new(&format!("Comment on #{}:", number)).prompt()?,
+        None => inquire::Editor::new(&format!("Comment on #{}:", number)).prompt()?,
+    };
+    if message.trim().is_empty() {
+        return Err(anyhow!("Not commenting, the message is empty"));
+    }
+
+    let url = pulls::comment(&owner, &repo, number, message.trim_end()).await?;
+    println!("✨ Commented on #{}", number);
+    if !url.is_empty() {
+        println!("{}", url.url());
+    }
+    Ok(())
+}
+
+fn read_stdin() -> Result<String> {
+    let mut message = String::new();
+    std::io::stdin().read_to_string(&mut message)?;
+    Ok(message)
+}
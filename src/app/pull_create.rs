@@ -1,5 +1,19 @@
-use crate::{gh::pulls, git, tui, ai};
+use crate::{app::{owners, prompts::{self, Inputs, Prompt}, pull_size, reviewers, todos, vars::Vars}, config, gh::pulls, git, tui, ai};
 use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+/// Extras for the PR beyond its title and body
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Additions {
+    /// The branch's note from `sage note`, at the end of the description
+    pub note: bool,
+    /// The TODOs the branch adds, as a task list at the end of the description
+    pub todos: bool,
+    /// Reviewers picked from sage's suggestions
+    pub reviewers: bool,
+    /// Request the top suggestions without asking which
+    pub reviewers_unasked: bool,
+}
 
 pub async fn pull_create(
     title: Option<String>,
@@ -7,9 +21,12 @@ pub async fn pull_create(
     base_branch: Option<String>,
     head_branch: Option<String>,
     draft: bool,
-    interactive: bool,
     use_ai: bool,
+    additions: Additions,
 ) -> Result<()> {
+    // Use interactive mode if any required fields are missing and AI is not enabled
+    let interactive = (title.is_none() || body.is_none()) && !use_ai;
+
     let (owner, repo) = git::repo::owner_repo()?;
     let head_branch = head_branch.unwrap_or(git::branch::current()?);
 
@@ -30,7 +47,9 @@ pub async fn pull_create(
         println!("Using AI to generate PR title and body...");
         
         // Get the diff and use AI to generate a commit message
-        let commit_message = ai::commit::generate().await?;
+        let diff = git::repo::diff()?;
+        let prompt = prompts::render(Prompt::Commit, Inputs { diff: &diff, ..Inputs::default() })?;
+        let commit_message = ai::commit::tidy(&prompts::ask("pull request title", &prompt).await?);
         
         // The first line of the commit message becomes the title
         let parts: Vec<&str> = commit_message.trim().splitn(2, '\n').collect();
@@ -43,8 +62,8 @@ pub async fn pull_create(
             // If no multiline commit message, generate a more detailed PR description
             // Use commit log instead of diff for PR description
             let commit_log = git::repo::commit_log()?;
-            let prompt = ai::prompts::pr_description_prompt(&ai_title, &commit_log);
-            ai::ask(&prompt).await?
+            let prompt = prompts::render(Prompt::Pr, Inputs { title: &ai_title, commits: &commit_log, ..Inputs::default() })?;
+            prompts::ask("pull request description", &prompt).await?
         };
         
         println!("AI generated title: {}", ai_title);
@@ -58,9 +77,37 @@ pub async fn pull_create(
         (title, body, draft)
     };
 
+    let vars = Vars::for_branch(&head_branch);
+    let title = match (config::get("pr.title_template"), title) {
+        (Some(template), Some(title)) => Some(vars.render(&template, &[("title", &title)])?),
+        (_, title) => title,
+    };
+    let body = match config::get("pr.body_template") {
+        Some(template) => Some(vars.render(&template, &[("body", body.as_deref().unwrap_or(""))])?),
+        None => body,
+    };
+
     // Default to "main" for base branch if not provided
     let base_branch = base_branch.or(Some("main".to_string()));
 
+    // Neither size nor new TODOs ever stop the PR from being opened, they're only worth a second look
+    pull_size::check(base_branch.as_deref().unwrap_or("main"), &head_branch);
+    let changed = git::files::changed_files(&format!("{}...{}", base_branch.as_deref().unwrap_or("main"), head_branch), &[]);
+    let _ = owners::print_summary(&changed.unwrap_or_default());
+    let added_todos = todos::added(base_branch.as_deref().unwrap_or("main"), &head_branch).unwrap_or_default();
+    todos::warn(&added_todos);
+
+    let note = if additions.note { git::stack::note(&head_branch)? } else { None };
+    let body = match note {
+        Some(note) => Some(with_note(body.as_deref().unwrap_or(""), &note)),
+        None => body,
+    };
+    let body = if additions.todos && !added_todos.is_empty() {
+        Some(with_section(body.as_deref().unwrap_or(""), &format!("### TODOs\n\n{}", todos::task_list(&added_todos))))
+    } else {
+        body
+    };
+
     match pulls::create_pull_request(
         &owner,
         &repo,
@@ -75,8 +122,44 @@ pub async fn pull_create(
         Ok(pr) => {
             println!("Pull request created successfully!");
             println!("Pull request URL: {}", pr.html_url.unwrap());
+            if additions.reviewers {
+                let author = pr.user.as_ref().map(|user| user.login.as_str());
+                // The pull request is there either way, so a failed request doesn't fail the command
+                let base = base_branch.as_deref().unwrap_or("main");
+                if let Err(e) = reviewers::request(&owner, &repo, pr.number, base, &head_branch, author, additions.reviewers_unasked).await {
+                    println!("{} Could not request reviewers: {}", "WARNING:".yellow(), e);
+                }
+            }
             Ok(())
         }
         Err(e) => Err(anyhow!("Failed to create pull request: {:?}", e)),
     }
 }
+
+/// with_note appends a branch note to a PR description as a quoted note
+fn with_note(body: &str, note: &str) -> String {
+    with_section(body, &format!("> **Note:** {}", note))
+}
+
+/// with_section appends a section to a PR description, separated by a blank line
+fn with_section(body: &str, section: &str) -> String {
+    if body.trim().is_empty() {
+        section.to_string()
+    } else {
+        format!("{}\n\n{}", body.trim_end(), section)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_note() {
+        assert_eq!(
+            with_note("Adds retries\n", "waiting on infra team"),
+            "Adds retries\n\n> **Note:** waiting on infra team"
+        );
+        assert_eq!(with_note("", "blocked"), "> **Note:** blocked");
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::io::{IsTerminal, Read};
+use crate::{errors, gh::pulls, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// What to change on a pull request
+#[derive(Debug, Default)]
+pub struct EditOptions {
+    pub title: Option<String>,
+    /// The new body, or `-` to read it from stdin
+    pub body: Option<String>,
+    pub add_labels: Vec<String>,
+    pub remove_labels: Vec<String>,
+}
+
+impl EditOptions {
+    fn is_empty(&self) -> bool {
+        self.title.is_none() && self.body.is_none() && self.add_labels.is_empty() && self.remove_labels.is_empty()
+    }
+}
+
+/// pull_edit changes the title, body or labels of a pull request, the current branch's unless
+/// `pr_number` is given. Without any changes it opens the title and body in $EDITOR.
+pub async fn pull_edit(pr_number: Option<u64>, mut opts: EditOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let (owner, repo) = git::repo::owner_repo()?;
+    let number = pulls::resolve_number(&owner, &repo, pr_number).await?;
+
+    if opts.is_empty() {
+        if accessible::enabled() || !std::io::stdin().is_terminal() {
+            return Err(anyhow!("Nothing to change, pass --title, --body, --add-label or --remove-label"));
+        }
+        let current = pulls::get_pull_request(&owner, &repo, number).await?;
+        let title = current.title.unwrap_or_default();
+        let body = current.body.unwrap_or_default();
+        let edited = inquire::Editor::new(&format!("Edit #{}:", number))
+            .with_predefined_text(&format!("{}\n\n{}", title, body))
+            .with_help_message("The first line is the title, everything after the blank line the body")
+            .prompt()?;
+
+        let (new_title, new_body) = parse_edited(&edited);
+        if new_title.is_empty() {
+            return Err(anyhow!("Not editing, the title is empty"));
+        }
+        if new_title == title.trim() && new_body == body.trim() {
+            println!("Nothing changed");
+            return Ok(());
+        }
+        opts.title = Some(new_title);
+        opts.body = Some(new_body);
+    }
+
+    if opts.body.as_deref() == Some("-") {
+        let mut body = String::new();
+        std::io::stdin().read_to_string(&mut body)?;
+        opts.body = Some(body.trim_end().to_string());
+    }
+
+    if opts.title.is_some() || opts.body.is_some() {
+        pulls::update_pull_request(&owner, &repo, number, opts.title.as_deref(), opts.body.as_deref()).await?;
+    }
+    if !opts.add_labels.is_empty() {
+        pulls::add_labels(&owner, &repo, number, &opts.add_labels).await?;
+    }
+    for label in &opts.remove_labels {
+        pulls::remove_label(&owner, &repo, number, label).await?;
+    }
+
+    println!("✨ Updated #{}", number);
+    let bullet = accessible::mark(Mark::Bullet).sage();
+    if let Some(title) = &opts.title {
+        println!("  {} title: {}", bullet, title);
+    }
+    if opts.body.is_some() {
+        println!("  {} body", bullet);
+    }
+    for label in &opts.add_labels {
+        println!("  {} +{}", bullet, label.green());
+    }
+    for label in &opts.remove_labels {
+        println!("  {} -{}", bullet, label.red());
+    }
+    Ok(())
+}
+
+/// parse_edited splits edited text into a title (its first line) and a body (the rest)
+fn parse_edited(text: &str) -> (String, String) {
+    let text = text.trim_start();
+    match text.split_once('\n') {
+        Some((title, body)) => (title.trim().to_string(), body.trim().to_string()),
+        None => (text.trim().to_string(), String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_edited() {
+        assert_eq!(
+            parse_edited("Add rate limiting\n\nLimits each client.\n\nSecond paragraph.\n"),
+            ("Add rate limiting".to_string(), "Limits each client.\n\nSecond paragraph.".to_string())
+        );
+        assert_eq!(parse_edited("\n  Only a title  "), ("Only a title".to_string(), String::new()));
+        assert_eq!(parse_edited(""), (String::new(), String::new()));
+    }
+}
@@ -0,0 +1,410 @@
+//! Measuring how big a branch's pull request would be, and how to split it when it's too big
+//!
+//! Changes are grouped into areas by where they live: the top-level directory, or the package
+//! directory inside a monorepo folder like `crates/` or `packages/`. A branch spread over several
+//! areas is split one area per branch. A branch confined to a single area is split along its
+//! commits instead.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+use crate::{config, errors, git::{self, files::FileChange}, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// Folders whose subdirectories are each a package of their own
+const PACKAGE_ROOTS: &[&str] = &["apps", "crates", "libs", "packages", "services"];
+
+/// Sizes above which a pull request counts as too big to review comfortably
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Lines added plus lines removed
+    pub max_lines: usize,
+    pub max_files: usize,
+    /// Separate areas the changes are spread over
+    pub max_areas: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Thresholds {
+        Thresholds { max_lines: 400, max_files: 25, max_areas: 3 }
+    }
+}
+
+impl Thresholds {
+    /// load reads the thresholds from pr.max_lines, pr.max_files and pr.max_areas
+    pub fn load() -> Thresholds {
+        let default = Thresholds::default();
+        let get = |key: &str, default: usize| config::get(key).and_then(|value| value.trim().parse().ok()).unwrap_or(default);
+        Thresholds {
+            max_lines: get("pr.max_lines", default.max_lines),
+            max_files: get("pr.max_files", default.max_files),
+            max_areas: get("pr.max_areas", default.max_areas),
+        }
+    }
+}
+
+/// The changes in one area
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Area {
+    pub name: String,
+    pub paths: Vec<String>,
+    pub lines: usize,
+}
+
+/// How big a set of changes is
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Size {
+    pub files: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Largest first
+    pub areas: Vec<Area>,
+}
+
+impl Size {
+    /// measure adds up changes and groups them by area
+    pub fn measure(changes: &[FileChange]) -> Size {
+        let mut areas: BTreeMap<String, Area> = BTreeMap::new();
+        for change in changes {
+            let name = area_of(&change.path);
+            let area = areas.entry(name.clone()).or_insert_with(|| Area { name, paths: Vec::new(), lines: 0 });
+            area.paths.push(change.path.clone());
+            area.lines += change.added + change.removed;
+        }
+
+        let mut areas = areas.into_values().collect::<Vec<_>>();
+        areas.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+
+        Size {
+            files: changes.len(),
+            added: changes.iter().map(|change| change.added).sum(),
+            removed: changes.iter().map(|change| change.removed).sum(),
+            areas,
+        }
+    }
+
+    pub fn lines(&self) -> usize {
+        self.added + self.removed
+    }
+
+    /// exceeded says which thresholds these changes are over, if any
+    pub fn exceeded(&self, thresholds: &Thresholds) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.lines() > thresholds.max_lines {
+            reasons.push(format!("{} lines changed (limit {})", self.lines(), thresholds.max_lines));
+        }
+        if self.files > thresholds.max_files {
+            reasons.push(format!("{} files changed (limit {})", self.files, thresholds.max_files));
+        }
+        if self.areas.len() > thresholds.max_areas {
+            reasons.push(format!("spread over {} areas (limit {})", self.areas.len(), thresholds.max_areas));
+        }
+        reasons
+    }
+}
+
+/// One branch of a suggested split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// What the part holds, e.g. an area or the first commit's subject
+    pub name: String,
+    pub paths: Vec<String>,
+    pub lines: usize,
+}
+
+/// area_of returns the area a path belongs to
+pub fn area_of(path: &str) -> String {
+    let parts = path.split('/').collect::<Vec<_>>();
+    match parts.as_slice() {
+        [_] => ".".to_string(),
+        [root, package, _, ..] if PACKAGE_ROOTS.contains(root) => format!("{}/{}", root, package),
+        [root, ..] => root.to_string(),
+        [] => ".".to_string(),
+    }
+}
+
+/// suggest_split proposes parts to split changes into, in the order they should be stacked, or
+/// nothing when they can't be usefully split. `commits` are the branch's commits, oldest first,
+/// with the files each touches.
+pub fn suggest_split(size: &Size, changes: &[FileChange], commits: &[(String, Vec<String>)], thresholds: &Thresholds) -> Vec<Part> {
+    let lines_of = |path: &str| {
+        changes.iter().find(|change| change.path == path).map_or(0, |change| change.added + change.removed)
+    };
+    // Where each changed file is first touched, so earlier work ends up lower in the stack
+    let first_touched = |path: &str| commits.iter().position(|(_, paths)| paths.iter().any(|p| p == path)).unwrap_or(usize::MAX);
+
+    let mut parts = if size.areas.len() > 1 {
+        let mut areas = size.areas.clone();
+        areas.sort_by_key(|area| area.paths.iter().map(|path| first_touched(path)).min());
+        areas.into_iter().map(|area| Part { name: area.name, paths: area.paths, lines: area.lines }).collect::<Vec<_>>()
+    } else {
+        // A single area is split along its commits, each part filling up to the line limit. A
+        // file goes with the first part that touches it, as files can't be split.
+        let mut parts: Vec<Part> = Vec::new();
+        let mut taken: Vec<&str> = Vec::new();
+        for (subject, paths) in commits {
+            let paths = paths
+                .iter()
+                .filter(|path| !taken.contains(&path.as_str()) && changes.iter().any(|change| &change.path == *path))
+                .collect::<Vec<_>>();
+            if paths.is_empty() {
+                continue;
+            }
+            let lines = paths.iter().map(|path| lines_of(path)).sum::<usize>();
+            let start_new = parts.last().is_none_or(|part| part.lines > 0 && part.lines + lines > thresholds.max_lines);
+            if start_new {
+                parts.push(Part { name: subject.clone(), paths: Vec::new(), lines: 0 });
+            }
+            let part = parts.last_mut().expect("a part was just pushed");
+            part.lines += lines;
+            for path in paths {
+                part.paths.push(path.clone());
+                taken.push(path);
+            }
+        }
+        parts
+    };
+
+    // Anything no commit mentions (shouldn't happen, but the range and log can disagree) rides
+    // along with the last part
+    let missing = changes
+        .iter()
+        .filter(|change| !parts.iter().any(|part| part.paths.contains(&change.path)))
+        .collect::<Vec<_>>();
+    if let Some(last) = parts.last_mut() {
+        for change in missing {
+            last.paths.push(change.path.clone());
+            last.lines += change.added + change.removed;
+        }
+    }
+
+    if parts.len() < 2 {
+        return Vec::new();
+    }
+    parts
+}
+
+/// Everything needed to report on a branch's size
+struct Analysis {
+    base: String,
+    head: String,
+    changes: Vec<FileChange>,
+    size: Size,
+    parts: Vec<Part>,
+    reasons: Vec<String>,
+}
+
+fn analyze(base: &str, head: &str) -> Result<Analysis> {
+    let range = format!("{}...{}", base, head);
+    let changes = git::files::numstat(&range)?;
+    let commits = git::files::commit_paths(&format!("{}..{}", base, head))?;
+    let thresholds = Thresholds::load();
+    let size = Size::measure(&changes);
+    let reasons = size.exceeded(&thresholds);
+    let parts = if reasons.is_empty() { Vec::new() } else { suggest_split(&size, &changes, &commits, &thresholds) };
+    Ok(Analysis { base: base.to_string(), head: head.to_string(), changes, size, parts, reasons })
+}
+
+/// pull_size reports how big a pull request for `head` (default the current branch) against
+/// `base` would be, suggesting how to split it when it's over the limits. With `plan`, the
+/// suggested split is scaffolded as a stack of new branches.
+pub fn pull_size(base: Option<String>, head: Option<String>, plan: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let head = match head {
+        Some(head) => head,
+        None => git::branch::current()?,
+    };
+    let base = match base {
+        Some(base) => base,
+        None => git::stack::parent(&head)?.unwrap_or(git::repo::default_branch()?),
+    };
+    let analysis = analyze(&base, &head)?;
+
+    println!(
+        "{} against {}: {} files, {} {} across {} areas",
+        head.sage(),
+        base.sage(),
+        analysis.size.files,
+        format!("+{}", analysis.size.added).green(),
+        format!("-{}", analysis.size.removed).red(),
+        analysis.size.areas.len()
+    );
+    for area in &analysis.size.areas {
+        println!(
+            "  {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            area.name,
+            format!("({} files, {} lines)", area.paths.len(), area.lines).gray()
+        );
+    }
+
+    if analysis.reasons.is_empty() {
+        println!("\n✨ Small enough to review in one go");
+        return Ok(());
+    }
+
+    println!();
+    warn(&analysis);
+
+    if plan {
+        if analysis.parts.is_empty() {
+            return Err(anyhow!("There's no split to scaffold"));
+        }
+        scaffold(&analysis)?;
+    }
+    Ok(())
+}
+
+/// check warns when a pull request for `head` against `base` would be over the limits, without
+/// ever stopping it from being opened
+pub fn check(base: &str, head: &str) {
+    match analyze(base, head) {
+        Ok(analysis) if !analysis.reasons.is_empty() => {
+            warn(&analysis);
+            if !analysis.parts.is_empty() {
+                println!("  Run {} to create these as a stack\n", format!("sage pr size --plan --base {}", base).sage());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn warn(analysis: &Analysis) {
+    println!("{} This pull request is large: {}", "WARNING:".yellow(), analysis.reasons.join(", "));
+    if analysis.parts.is_empty() {
+        return;
+    }
+
+    println!("  It could be split into {} stacked pull requests:", analysis.parts.len());
+    for (index, part) in analysis.parts.iter().enumerate() {
+        println!(
+            "  {}. {} {}",
+            index + 1,
+            part.name,
+            format!("({} files, {} lines)", part.paths.len(), part.lines).gray()
+        );
+    }
+}
+
+/// Create a branch per part, each on top of the last, holding the part's files as they are on the
+/// head branch. The head branch itself is left alone.
+fn scaffold(analysis: &Analysis) -> Result<()> {
+    if !git::status::is_clean()? {
+        return Err(anyhow!("Working tree has uncommitted changes. Commit or stash them before splitting"));
+    }
+
+    let names = (1..=analysis.parts.len()).map(|n| format!("{}-{}", analysis.head, n)).collect::<Vec<_>>();
+    if let Some(existing) = names.iter().find(|name| git::branch::exists(name)) {
+        return Err(anyhow!("Branch {} already exists", existing.yellow()));
+    }
+    let deleted = git::files::deleted_files(&format!("{}...{}", analysis.base, analysis.head))?;
+    let original_branch = git::branch::current()?;
+
+    // The sizes were measured from where the head branch forked, so the split starts there too,
+    // not from wherever the base has got to since
+    let mut start = git::repo::merge_base(&analysis.base, &analysis.head)?;
+
+    println!("\nScaffolding the split:");
+    let mut parent = analysis.base.clone();
+    for (name, part) in names.iter().zip(&analysis.parts) {
+        git::branch::create_at(name, &start)?;
+        git::branch::switch(name, false)?;
+
+        let (removed, kept): (Vec<String>, Vec<String>) = part.paths.iter().cloned().partition(|path| deleted.contains(path));
+        if !kept.is_empty() {
+            git::files::checkout_paths(&analysis.head, &kept)?;
+        }
+        if !removed.is_empty() {
+            git::files::remove_paths(&removed, false)?;
+        }
+        git::commit::commit(&format!("{} (split from {})", part.name, analysis.head), false, false)?;
+        git::stack::set_parent(name, &parent)?;
+
+        println!(
+            "  {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            name.yellow(),
+            format!("({} on {})", part.name, parent).gray()
+        );
+        parent = name.clone();
+        start = name.clone();
+    }
+    git::branch::switch(&original_branch, false)?;
+
+    // The last part holds every changed file, so they should all match the head branch
+    let paths = analysis.changes.iter().map(|change| change.path.clone()).collect::<Vec<_>>();
+    let leftover = git::files::changed_files(&format!("{}..{}", parent, analysis.head), &paths)?;
+    if !leftover.is_empty() {
+        println!(
+            "{} {} differs from {} in {} files, check the split before opening pull requests",
+            "WARNING:".yellow(),
+            parent,
+            analysis.head,
+            leftover.len()
+        );
+    }
+
+    println!("\n✨ Split {} into {} stacked branches, {} is unchanged", analysis.head, names.len(), analysis.head);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(path: &str, lines: usize) -> FileChange {
+        FileChange { path: path.to_string(), added: lines, removed: 0, binary: false }
+    }
+
+    #[test]
+    fn test_area_of() {
+        assert_eq!(area_of("README.md"), ".");
+        assert_eq!(area_of("src/cli/pr.rs"), "src");
+        assert_eq!(area_of("crates/sage-ai/src/lib.rs"), "crates/sage-ai");
+        assert_eq!(area_of("crates/README.md"), "crates");
+    }
+
+    #[test]
+    fn test_measure_and_exceeded() {
+        let changes = [change("src/a.rs", 300), change("src/b.rs", 50), change("docs/x.md", 100)];
+        let size = Size::measure(&changes);
+        assert_eq!(size.lines(), 450);
+        assert_eq!(size.areas.iter().map(|area| area.name.as_str()).collect::<Vec<_>>(), ["src", "docs"]);
+        assert_eq!(size.exceeded(&Thresholds::default()), ["450 lines changed (limit 400)"]);
+        assert!(size.exceeded(&Thresholds { max_lines: 500, ..Thresholds::default() }).is_empty());
+    }
+
+    #[test]
+    fn test_split_by_area_in_commit_order() {
+        let changes = [change("src/a.rs", 300), change("docs/x.md", 200)];
+        let commits = [
+            ("Document it".to_string(), vec!["docs/x.md".to_string()]),
+            ("Build it".to_string(), vec!["src/a.rs".to_string()]),
+        ];
+        let parts = suggest_split(&Size::measure(&changes), &changes, &commits, &Thresholds::default());
+        assert_eq!(parts.iter().map(|part| part.name.as_str()).collect::<Vec<_>>(), ["docs", "src"]);
+    }
+
+    #[test]
+    fn test_split_single_area_by_commits() {
+        let changes = [change("src/a.rs", 300), change("src/b.rs", 200), change("src/c.rs", 50)];
+        let commits = [
+            ("First".to_string(), vec!["src/a.rs".to_string()]),
+            ("Second".to_string(), vec!["src/b.rs".to_string(), "src/a.rs".to_string()]),
+            ("Third".to_string(), vec!["src/c.rs".to_string()]),
+        ];
+        let parts = suggest_split(&Size::measure(&changes), &changes, &commits, &Thresholds::default());
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].paths, ["src/a.rs"]);
+        assert_eq!(parts[1].paths, ["src/b.rs", "src/c.rs"]);
+        assert_eq!(parts[1].lines, 250);
+
+        // One commit can't be split
+        let commits = [("Everything".to_string(), changes.iter().map(|change| change.path.clone()).collect())];
+        assert!(suggest_split(&Size::measure(&changes), &changes, &commits, &Thresholds::default()).is_empty());
+    }
+}
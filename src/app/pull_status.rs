@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use crate::{errors, gh::pulls, git, ui::ColorizeExt};
+use crate::{errors, gh::pulls, git, ui::{accessible::Mark, theme, ColorizeExt}};
 use colored::Colorize;
 
 pub async fn pull_status(pr_number: Option<u64>) -> Result<()> {
@@ -86,16 +86,16 @@ pub async fn pull_status(pr_number: Option<u64>) -> Result<()> {
                     
                     // Format the check status with color based on conclusion
                     let status_display = match conclusion {
-                        Some("success") => format!("{}", "✓".green()),
-                        Some("failure") => format!("{}", "✗".red()),
-                        Some("cancelled") => format!("{}", "○".yellow()),
-                        Some("skipped") => format!("{}", "-".bright_black()),
+                        Some("success") => theme::styled(Mark::Passing).to_string(),
+                        Some("failure") => theme::styled(Mark::Failing).to_string(),
+                        Some("cancelled") => theme::styled(Mark::Cancelled).to_string(),
+                        Some("skipped") => theme::styled(Mark::Skipped).to_string(),
                         Some(other) => format!("{}", other.yellow()),
                         None => {
                             if status == "completed" {
                                 format!("{}", "?".yellow())
                             } else {
-                                format!("{}", "…".bright_black())
+                                theme::styled(Mark::Pending).to_string()
                             }
                         }
                     };
@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use inquire::{Confirm, Text};
+use std::fs;
+
+use crate::{app::guard, errors, gh, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// Options for `sage purge-file`
+#[derive(Debug, Default)]
+pub struct PurgeOptions {
+    /// Also rewrite history shared with the default branch
+    pub shared: bool,
+    /// Force push the rewritten branches that have an upstream
+    pub push: bool,
+    /// Replace open pull requests for rewritten branches with new ones
+    pub recreate_prs: bool,
+    /// Skip confirmation prompts (except for shared history)
+    pub yes: bool,
+}
+
+/// purge_file removes a file from every commit of the current stack, for secrets or large files
+/// that were committed by accident. History shared with the default branch is only rewritten
+/// with `shared` and a typed confirmation.
+pub async fn purge_file(path: &str, opts: PurgeOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    if let Some(operation) = git::repo::in_progress()? {
+        return Err(anyhow!("A {} is in progress. Finish or abort it first", operation));
+    }
+    let status = git::status::status()?;
+    if status.has_changes() || status.has_staged_changes() {
+        return Err(anyhow!("You have uncommitted changes. Commit or stash them before rewriting history"));
+    }
+
+    let current = git::branch::current()?;
+    let stack = git::stack::stack(&current)?;
+    let branches = if stack.branches.is_empty() { vec![current.clone()] } else { stack.branches };
+    let in_shared_history = git::purge::touches(&stack.base, path)?;
+
+    // Work out which branches actually carry the file
+    let (affected, exclude) = if in_shared_history {
+        if !opts.shared {
+            println!(
+                "{} {} is in the history of {}, which other people have too.",
+                "WARNING:".yellow(),
+                path.yellow(),
+                stack.base.sage()
+            );
+            println!("Rewriting it changes every commit since it was added, for everyone.");
+            return Err(anyhow!("Run again with --shared to rewrite {} and every local branch", stack.base));
+        }
+        (git::branch::list()?, None)
+    } else {
+        let mut affected = Vec::new();
+        for branch in &branches {
+            if git::purge::touches(&format!("{}..{}", stack.base, branch), path)? {
+                affected.push(branch.clone());
+            }
+        }
+        (affected, Some(stack.base.as_str()))
+    };
+
+    if affected.is_empty() {
+        println!("No commit on {} touches {}", branches.join(", "), path);
+        return Ok(());
+    }
+
+    println!("{} will be removed from every commit of:", path.yellow());
+    for branch in &affected {
+        let revs = match exclude {
+            Some(base) => format!("{}..{}", base, branch),
+            None => branch.clone(),
+        };
+        println!("  {} {}", branch.sage(), format!("({} commits)", git::purge::commit_count(&revs)?).gray());
+    }
+
+    if in_shared_history {
+        let answer = Text::new(&format!("This rewrites shared history. Type {} to confirm:", stack.base)).prompt()?;
+        if answer.trim() != stack.base {
+            return Ok(());
+        }
+    } else if !opts.yes && !Confirm::new("Rewrite these branches?").with_default(false).prompt()? {
+        return Ok(());
+    }
+
+    // No checkpoint here: it would keep the purged file reachable for good. The old tips are kept
+    // until sage gc instead, along with the file itself on disk.
+    for branch in &affected {
+        git::gc::trash(branch)?;
+    }
+    let on_disk = git::repo::toplevel()?.join(path);
+    let contents = fs::read(&on_disk).ok();
+
+    println!("Rewriting history...");
+    git::purge::remove_path(&affected, exclude, path)?;
+    if let Some(contents) = contents {
+        fs::write(&on_disk, contents)?;
+    }
+    println!("✨ Removed {} from {} branches", path.sage(), affected.len());
+
+    // Branches that were never pushed have nothing to clean up remotely
+    let mut pushed = Vec::new();
+    for branch in &affected {
+        if git::branch::upstream(branch)?.is_some() {
+            pushed.push(branch.clone());
+        }
+    }
+
+    if !pushed.is_empty() {
+        let push = opts.push
+            || (!opts.yes
+                && Confirm::new(&format!("Force push {}?", pushed.join(", ")))
+                    .with_default(false)
+                    .prompt()?);
+        if push {
+            // Asked up front, so a plugin can't stop the push with only some of the branches pushed
+            for branch in &pushed {
+                guard::check_before_push(branch, true)?;
+            }
+            for branch in &pushed {
+                git::branch::push(branch, false)?;
+                println!("  {} {}", accessible::mark(Mark::Pushed).sage(), branch);
+            }
+            if opts.recreate_prs {
+                recreate_prs(&pushed, path).await?;
+            }
+        } else {
+            println!("Push them later with {}", "git push --force-with-lease".sage());
+        }
+    }
+
+    println!("\n{}", "Next steps".bold());
+    println!("  - If {} held a secret, rotate it now. Anyone who fetched the old commits still has it.", path);
+    println!("  - Add it to .gitignore with {} so it isn't committed again.", format!("sage ignore add {}", path).sage());
+    println!("  - Old branch tips are kept until {} removes them.", "sage gc".sage());
+    if !pushed.is_empty() {
+        println!("  - GitHub may still serve the old commits by SHA; contact GitHub support to have them purged.");
+    }
+
+    Ok(())
+}
+
+/// Close the open pull request of each branch and open an identical one, so the old commits
+/// aren't linked from the new pull request's timeline
+async fn recreate_prs(branches: &[String], path: &str) -> Result<()> {
+    let (owner, repo) = git::repo::owner_repo()?;
+    let prs = gh::graphql::pull_requests_by_branch(branches).await?;
+
+    for branch in branches {
+        let Some(summary) = prs.get(branch).filter(|pr| pr.state == gh::graphql::PrState::Open) else {
+            continue;
+        };
+        let old = gh::pulls::get_pull_request(&owner, &repo, summary.number).await?;
+
+        gh::pulls::close_pull_request(&owner, &repo, old.number).await?;
+        let new = gh::pulls::create_pull_request(
+            &owner,
+            &repo,
+            old.title.as_deref().unwrap_or(branch),
+            branch,
+            &old.base.ref_field,
+            old.body.as_deref().unwrap_or_default(),
+            old.draft.unwrap_or(false),
+        )
+        .await?;
+        gh::pulls::comment(
+            &owner,
+            &repo,
+            old.number,
+            &format!("Closed after rewriting history to remove `{}`. Continued in #{}.", path, new.number),
+        )
+        .await?;
+
+        println!("  #{} replaced by {}", old.number, format!("#{}", new.number).sage());
+    }
+
+    Ok(())
+}
@@ -1,8 +1,8 @@
-use anyhow::Result;
-use crate::{errors, git};
+use anyhow::{anyhow, Result};
+use crate::{app::{credentials, dco, guard, wip}, errors, git, ui::progress::MultiProgress};
 use colored::Colorize;
 
-pub fn push(force: bool) -> Result<()> {
+pub fn push(force: bool, switch_protocol: bool, allow_wip: bool) -> Result<()> {
 
     // Check to ensure we are in a repo first.
     if !git::repo::is_repo()? {
@@ -13,10 +13,70 @@ pub fn push(force: bool) -> Result<()> {
     // Getting the current branch name
     let current_branch = git::branch::current()?;
 
+    // Make sure every outgoing commit is signed off when DCO is enforced
+    dco::verify_outgoing(&current_branch)?;
+    wip::check_before_push(&current_branch, allow_wip)?;
+    guard::check_before_push(&current_branch, force)?;
+
+    // Catch missing SSH keys or HTTPS credentials before git fails with raw stderr
+    credentials::check_remote("origin", switch_protocol)?;
+
     // Pushing the branch to remote
     git::branch::push(&current_branch, force)?;
 
     println!("Successfully pushed branch: {}", current_branch.blue());
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// push_stack pushes every branch in the current stack at once, showing a line for each
+pub async fn push_stack(force: bool, switch_protocol: bool, allow_wip: bool) -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let branches = git::stack::stack(&current_branch)?.branches;
+
+    // Check everything up front so nothing is pushed when any of it would be refused
+    for branch in &branches {
+        dco::verify_outgoing(branch)?;
+        wip::check_before_push(branch, allow_wip)?;
+        guard::check_before_push(branch, force)?;
+    }
+    credentials::check_remote("origin", switch_protocol)?;
+
+    let progress = MultiProgress::new();
+    let pushes = branches
+        .iter()
+        .map(|branch| {
+            let task = progress.add(branch.clone());
+            let branch = branch.clone();
+            tokio::task::spawn_blocking(move || {
+                let result = git::branch::push_untracked(&branch, force);
+                task.finish(&result);
+                result
+            })
+        })
+        .collect::<Vec<_>>();
+    progress.wait().await;
+
+    let mut failed = Vec::new();
+    for (branch, push) in branches.iter().zip(pushes) {
+        match push.await? {
+            // Upstreams are set one at a time, git can't write its config from several processes
+            Ok(()) => git::branch::track(branch, &format!("origin/{}", branch))?,
+            Err(e) => failed.push((branch, e)),
+        }
+    }
+
+    if !failed.is_empty() {
+        for (branch, e) in &failed {
+            eprintln!("\n{} {}:\n{}", "Failed to push".red(), branch.yellow(), e);
+        }
+        return Err(anyhow!("Failed to push {} of {} branches", failed.len(), branches.len()));
+    }
+
+    println!("Successfully pushed {} branches", branches.len().to_string().blue());
+    Ok(())
+}
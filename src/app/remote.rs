@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{errors, git::{self, remote::Protocol}, ui, ui::ColorizeExt};
+
+/// list prints every remote with its URL and protocol, marking the primary one
+pub fn list() -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let remotes = git::remote::list()?;
+    if remotes.is_empty() {
+        println!("No remotes configured. Add one with 'sage remote add <name> <url>'");
+        return Ok(());
+    }
+
+    let primary = git::remote::primary()?;
+    let width = remotes.iter().map(|remote| ui::text::width(&remote.name)).max().unwrap_or(0);
+    for remote in remotes {
+        let marker = if remote.name == primary { "*".sage() } else { " ".normal() };
+        let protocol = match git::remote::protocol(&remote.url) {
+            Protocol::Https => "https",
+            Protocol::Ssh => "ssh",
+            Protocol::Other => "local",
+        };
+        println!(
+            "{} {}  {} {}",
+            marker,
+            ui::text::pad(&remote.name, width),
+            remote.url,
+            format!("({})", protocol).gray()
+        );
+    }
+
+    Ok(())
+}
+
+/// add validates a URL and adds it as a new remote, optionally making it the primary remote
+pub fn add(name: &str, url: &str, primary: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    git::remote::validate_url(url)?;
+    if git::repo::remote_url(name)?.is_some() {
+        return Err(anyhow!("Remote {} already exists. Use 'sage remote set-url' to change it", name));
+    }
+
+    git::remote::add(name, url)?;
+    if primary {
+        git::remote::set_primary(name)?;
+    }
+    println!("✨ Added remote {} ({})", name.sage(), url);
+    warn_if_unreachable(name)?;
+
+    Ok(())
+}
+
+/// set_url validates a URL and points an existing remote at it
+pub fn set_url(name: &str, url: &str) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    git::remote::validate_url(url)?;
+    if git::repo::remote_url(name)?.is_none() {
+        return Err(anyhow!("No remote named {}", name));
+    }
+
+    git::remote::set_url(name, url)?;
+    println!("✨ {} now points at {}", name.sage(), url);
+    warn_if_unreachable(name)?;
+
+    Ok(())
+}
+
+/// prune removes remote-tracking branches that were deleted on the remote, for one remote or all
+pub fn prune(name: Option<&str>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let names = match name {
+        Some(name) => vec![name.to_string()],
+        None => git::remote::list()?.into_iter().map(|remote| remote.name).collect(),
+    };
+
+    for name in &names {
+        let report = match git::remote::prune(name) {
+            Ok(report) => report,
+            // One unreachable remote shouldn't stop the others from being pruned
+            Err(e) if names.len() > 1 => {
+                println!("{} {}", "WARNING:".yellow(), e.to_string().trim());
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let pruned = report
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("* [pruned] "))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        if pruned.is_empty() {
+            println!("{} {}", name.sage(), "nothing to prune".gray());
+            continue;
+        }
+
+        println!("{}", name.sage());
+        for branch in pruned {
+            println!("  {} {}", "-".red(), branch);
+        }
+    }
+
+    Ok(())
+}
+
+/// rewrite moves every SSH or HTTPS remote to the given protocol
+pub fn rewrite(to: Protocol) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let mut changed = 0;
+    for remote in git::remote::list()? {
+        let current = git::remote::protocol(&remote.url);
+        if current == to || current == Protocol::Other {
+            continue;
+        }
+
+        let rewritten = match to {
+            Protocol::Ssh => git::remote::to_ssh_url(&remote.url),
+            _ => git::remote::to_https_url(&remote.url),
+        };
+        let Some(rewritten) = rewritten else {
+            println!("{} Could not rewrite {} ({})", "WARNING:".yellow(), remote.name, remote.url);
+            continue;
+        };
+
+        git::remote::set_url(&remote.name, &rewritten)?;
+        println!("  {} {} {} {}", remote.name.sage(), remote.url.gray(), "→".gray(), rewritten);
+        changed += 1;
+    }
+
+    if changed == 0 {
+        println!("All remotes already use that protocol");
+    } else {
+        println!("\n✨ Rewrote {} remote{}", changed, if changed == 1 { "" } else { "s" });
+    }
+
+    Ok(())
+}
+
+/// Let the user know straight away when a new URL can't be reached with their credentials
+fn warn_if_unreachable(name: &str) -> Result<()> {
+    if let Some((_, stderr)) = git::remote::probe(name)? {
+        println!(
+            "{} Could not access {}: {}",
+            "WARNING:".yellow(),
+            name,
+            stderr.lines().next().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
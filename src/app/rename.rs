@@ -0,0 +1,106 @@
+//! `sage rename`: rename the current branch everywhere it's known
+//!
+//! Besides the local branch, the branch on origin is renamed (through GitHub's API when it can,
+//! so open pull requests follow it), the upstream is pointed at the new name and branches stacked
+//! on it get the new name as their parent.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{
+    app::checkpoint,
+    errors,
+    forge::{self, Kind},
+    gh, git,
+    ui::{accessible::{self, Mark}, ColorizeExt},
+};
+
+/// rename renames the current branch to `new_name`, locally, on origin and in the stack
+pub async fn rename(new_name: &str) -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let branch = git::branch::current()?;
+    if branch == new_name {
+        return Err(anyhow!("The branch is already called {}", new_name));
+    }
+    if branch == git::repo::default_branch()? {
+        return Err(anyhow!("Not renaming the default branch, {}. Do that in the repository's settings", branch));
+    }
+    if git::branch::exists(new_name) {
+        return Err(anyhow!("There's already a branch called {}", new_name));
+    }
+
+    let pushed = git::repo::remote_branch_exists(&branch);
+    if pushed && git::repo::remote_branch_exists(new_name) {
+        return Err(anyhow!("origin already has a branch called {}", new_name));
+    }
+
+    checkpoint::auto("before rename");
+    git::branch::rename(&branch, new_name)?;
+    println!("✨ Renamed {} to {}", branch.yellow(), new_name.sage());
+
+    // The branch's own parent moves with its config, its children still name it
+    for child in git::stack::children(&branch)? {
+        git::stack::set_parent(&child, new_name)?;
+        println!("  {} {} {}", accessible::mark(Mark::Bullet).sage(), child.yellow(), format!("(now stacked on {})", new_name).gray());
+    }
+
+    if pushed {
+        rename_remote(&branch, new_name).await?;
+    }
+    Ok(())
+}
+
+/// Rename the branch on origin. GitHub renames it in place and moves its pull requests along;
+/// anywhere else the new name is pushed and the old one deleted, unless a pull request still
+/// needs it.
+async fn rename_remote(branch: &str, new_name: &str) -> Result<()> {
+    let github = forge::Remote::origin().ok().filter(|remote| remote.kind == Kind::GitHub);
+    let on_github = github.is_some();
+    if let Some(remote) = github {
+        match gh::repos::rename_branch(&remote.owner, &remote.repo, branch, new_name).await {
+            Ok(()) => {
+                git::remote::fetch_branch("origin", new_name)?;
+                git::remote::prune("origin")?;
+                git::branch::set_upstream(new_name)?;
+                println!("✨ Renamed it on origin too, along with its pull requests");
+                return Ok(());
+            }
+            Err(e) => println!("{} Couldn't rename it on GitHub ({}), pushing it under the new name instead", "WARNING:".yellow(), e),
+        }
+    }
+
+    git::branch::push(new_name, false)?;
+    println!("✨ Pushed {} to origin", new_name.sage());
+
+    // Deleting a pull request's branch would close it, so on GitHub it's only deleted once
+    // GitHub says there isn't one
+    let lookup = if on_github { gh::pulls::get_by_branch(branch).await } else { Ok(None) };
+    match lookup {
+        Ok(None) => {}
+        Ok(Some(pull_request)) => {
+            println!(
+                "{} Left {} on origin, pull request #{} still uses it. Close it and open a new one from {}",
+                "WARNING:".yellow(),
+                branch,
+                pull_request.number,
+                new_name
+            );
+            return Ok(());
+        }
+        Err(e) => {
+            println!(
+                "{} Left {} on origin, couldn't check whether a pull request uses it ({}). Delete it once you have",
+                "WARNING:".yellow(),
+                branch,
+                e
+            );
+            return Ok(());
+        }
+    }
+    git::branch::delete_remote(branch)?;
+    println!("  {} {}", accessible::mark(Mark::Bullet).sage(), format!("deleted {} from origin", branch).gray());
+    Ok(())
+}
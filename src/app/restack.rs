@@ -0,0 +1,315 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crate::{app::{checkpoint, interrupt}, errors, events, git, ledger, ui::{self, accessible::{self, Mark}, ColorizeExt}};
+
+/// restack brings every branch in the current stack back on top of its parent. Only the branches
+/// that have fallen behind, and the ones stacked on those, are rebased; with `dry_run` they're
+/// listed instead. Conflicts stop it, for `sage continue` to finish.
+pub fn restack(dry_run: bool) -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let original_branch = git::branch::current()?;
+    let stack = git::stack::stack(&original_branch)?;
+    let relations = git::stack::relations()?;
+    let parent_of = |branch: &str| relations.iter().find(|(child, _)| child == branch).map(|(_, parent)| parent.clone());
+    let plan = plan(&stack.branches, parent_of, git::repo::is_ancestor);
+
+    if plan.is_empty() {
+        println!("✨ Every branch on {} is already on top of its parent", stack.base.sage());
+        return Ok(());
+    }
+
+    println!("{} {} branch(es):", if dry_run { "Would restack" } else { "Restacking" }, plan.len());
+    if dry_run {
+        for (branch, parent) in &plan {
+            println!("  {} {} {}", accessible::mark(Mark::Bullet).sage(), branch.yellow(), format!("(onto {})", parent).gray());
+        }
+        return Ok(());
+    }
+
+    let branches = plan.into_iter().map(|(branch, _)| branch).collect::<Vec<_>>();
+    let steps = restack_branches(&original_branch, &stack.base, &branches)?;
+    let restacked = steps.iter().filter(|step| step.outcome == ledger::Outcome::Done).count();
+    print_summary(&steps);
+    println!("✨ Restacked {} branch(es)", restacked);
+    Ok(())
+}
+
+/// The branches of a stack that need rebasing, with the parent each goes onto, parents first.
+/// A branch needs it when its parent has moved on without it, or its parent is being rebased.
+/// Branches without a parent, like the bottom of a stack root, stay where they are.
+fn plan(
+    branches: &[String],
+    parent_of: impl Fn(&str) -> Option<String>,
+    contains: impl Fn(&str, &str) -> bool,
+) -> Vec<(String, String)> {
+    let mut plan: Vec<(String, String)> = Vec::new();
+    for branch in branches {
+        let Some(parent) = parent_of(branch) else {
+            continue;
+        };
+        if plan.iter().any(|(planned, _)| *planned == parent) || !contains(&parent, branch) {
+            plan.push((branch.clone(), parent));
+        }
+    }
+    plan
+}
+
+/// restack_descendants rebases every branch stacked on `branch` onto its (possibly updated) parent,
+/// returning to the original branch when done
+pub fn restack_descendants(branch: &str) -> Result<Vec<ledger::Step>> {
+    let original_branch = git::branch::current()?;
+    let descendants = git::stack::descendants(branch)?;
+    restack_branches(&original_branch, branch, &descendants)
+}
+
+/// restack_branches rebases `branches` in order onto their parents, falling back to `root` for
+/// branches without one. Progress is kept in the ledger, so when it stops on conflicts or Ctrl-C
+/// `sage continue` can restack whatever is left. Branches already on top of their parent are
+/// skipped, so running it again is safe, and a branch or parent that has gone missing stops it
+/// before anything else is rebased. What happened to each branch is recorded in the ledger entry
+/// and returned.
+pub fn restack_branches(original_branch: &str, root: &str, branches: &[String]) -> Result<Vec<ledger::Step>> {
+    if !branches.is_empty() {
+        // Rebasing needs a clean working tree, so say so before touching any branch
+        if git::repo::has_tracked_changes()? {
+            return Err(anyhow!("Commit or stash your changes before restacking"));
+        }
+        checkpoint::auto("before restack");
+    }
+    let _guard = interrupt::guard();
+    let id = ledger::begin(
+        original_branch,
+        ledger::Operation::Restack { root: root.to_string(), remaining: branches.to_vec() },
+    )?;
+
+    let mut steps = Vec::new();
+    let mut record = |step: ledger::Step| -> Result<()> {
+        ledger::record(id, step.clone())?;
+        steps.push(step);
+        Ok(())
+    };
+
+    for (index, child) in branches.iter().enumerate() {
+        let remaining = branches[index..].to_vec();
+        ledger::update(id, |entry| {
+            entry.operation = ledger::Operation::Restack { root: root.to_string(), remaining: remaining.clone() };
+        })?;
+
+        if interrupt::interrupted() {
+            interrupt::stop(id, original_branch, false)?;
+            return Err(anyhow!("Restack interrupted before {}", child));
+        }
+
+        let parent = git::stack::parent(child)?.unwrap_or_else(|| root.to_string());
+
+        // Checked per branch, since a resumed restack may find the repository has changed since
+        if let Some(missing) = [child.as_str(), parent.as_str()].into_iter().find(|branch| !git::branch::exists(branch)) {
+            ledger::set_status(id, ledger::Status::Failed)?;
+            return Err(anyhow!("Can't restack {} onto {}: {} no longer exists", child, parent, missing));
+        }
+
+        // Nothing to do when it's already on top of its parent, e.g. restacked before an interruption
+        let before = git::repo::rev_parse(child).ok();
+        if git::repo::is_ancestor(&parent, child) {
+            record(step(child, ledger::Outcome::Skipped, before, None, None))?;
+            println!("  {} {} {}", accessible::mark(Mark::Bullet).sage(), child.yellow(), format!("(already on {})", parent).gray());
+            continue;
+        }
+
+        if let Err(e) = git::stack::rebase(child, &parent) {
+            if interrupt::interrupted() {
+                // The rebase was cut short rather than stopped on conflicts, so start it over on resume
+                if git::repo::in_progress()? == Some(git::repo::InProgress::Rebase) {
+                    git::branch::abort_rebase()?;
+                }
+                interrupt::stop(id, original_branch, false)?;
+                return Err(anyhow!("Restack interrupted at {}", child));
+            }
+            record(step(child, ledger::Outcome::Failed, before, None, Some(e.to_string())))?;
+
+            // The caller never gets the steps back from here, so show how far it got
+            print_summary(&steps);
+
+            let conflicts = git::branch::conflicting_files().unwrap_or_default();
+            if conflicts.is_empty() {
+                ledger::set_status(id, ledger::Status::Failed)?;
+                return Err(e);
+            }
+
+            events::emit(events::Event::Conflict { operation: "restack", branch: Some(child), onto: &parent, files: &conflicts });
+            println!("{} Conflicts while restacking {} onto {}:", "WARNING:".yellow(), child.yellow(), parent.sage());
+            for file in &conflicts {
+                println!("  {}", file.red());
+            }
+            print_rebase_progress();
+
+            // The conflicted branch finishes with the rebase, so only the ones after it are left
+            let remaining = branches[index + 1..].to_vec();
+            let recovery = interrupt::recovery_commands(original_branch, false);
+            ledger::update(id, |entry| {
+                entry.operation = ledger::Operation::Restack { root: root.to_string(), remaining };
+                entry.status = ledger::Status::Interrupted;
+                entry.recovery = recovery;
+            })?;
+
+            println!("\nResolve them, then run {}", "sage continue".sage());
+            println!("To back out instead, run {}", "git rebase --abort".gray());
+
+            return Err(anyhow!("Restack stopped at {}", child));
+        }
+
+        record(step(child, ledger::Outcome::Done, before, git::repo::rev_parse(child).ok(), None))?;
+        println!("  {} {} {}", accessible::mark(Mark::Bullet).sage(), child.yellow(), format!("(restacked onto {})", parent).gray());
+    }
+
+    git::branch::switch(original_branch, false)?;
+    ledger::update(id, |entry| {
+        entry.operation = ledger::Operation::Restack { root: root.to_string(), remaining: Vec::new() };
+        entry.status = ledger::Status::Done;
+    })?;
+    Ok(steps)
+}
+
+/// report_conflicts tells anything reading `--events` that `operation` stopped on conflicts while
+/// bringing in `onto`. Nothing is reported when it stopped for another reason.
+pub(crate) fn report_conflicts(operation: &str, onto: &str) {
+    if !events::is_enabled() {
+        return;
+    }
+    let files = git::branch::conflicting_files().unwrap_or_default();
+    if files.is_empty() {
+        return;
+    }
+    let progress = git::rebase::progress().ok().flatten();
+    let branch = progress.as_ref().and_then(|progress| progress.branch.as_deref());
+    events::emit(events::Event::Conflict { operation, branch, onto, files: &files });
+}
+
+/// How many of the commits still to apply are listed
+const MAX_REMAINING: usize = 10;
+
+/// print_rebase_progress says which commit a stopped rebase is on, as in "commit 3 of 7", and
+/// what it has left to apply. Nothing is printed when it can't be worked out.
+pub(crate) fn print_rebase_progress() {
+    let Ok(Some(progress)) = git::rebase::progress() else {
+        return;
+    };
+
+    let position = format!("{} of {}", progress.current, progress.total);
+    match &progress.commit {
+        Some(commit) => println!(
+            "\nStopped at commit {}: {} {} {}",
+            position,
+            commit.hash.bright_yellow(),
+            commit.subject,
+            format!("({})", commit.author).gray()
+        ),
+        None => println!("\nStopped at commit {}", position),
+    }
+    if progress.remaining.is_empty() {
+        return;
+    }
+
+    println!("Still to apply:");
+    for commit in progress.remaining.iter().take(MAX_REMAINING) {
+        println!(
+            "  {} {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            commit.hash.bright_yellow(),
+            commit.subject,
+            format!("({})", commit.author).gray()
+        );
+    }
+    if progress.remaining.len() > MAX_REMAINING {
+        println!("  {}", format!("…and {} more", progress.remaining.len() - MAX_REMAINING).gray());
+    }
+}
+
+fn step(branch: &str, outcome: ledger::Outcome, before: Option<String>, after: Option<String>, error: Option<String>) -> ledger::Step {
+    ledger::Step { branch: branch.to_string(), outcome, before, after, error }
+}
+
+/// print_summary shows what a restack did to each branch as a table, with what git said under
+/// the ones that failed
+pub fn print_summary(steps: &[ledger::Step]) {
+    if steps.is_empty() {
+        return;
+    }
+    println!();
+    for (index, row) in summary(steps).into_iter().enumerate() {
+        let Some(step) = index.checked_sub(1).map(|index| &steps[index]) else {
+            println!("  {}", row.gray());
+            continue;
+        };
+        match step.outcome {
+            ledger::Outcome::Done => println!("  {}", row),
+            ledger::Outcome::Skipped => println!("  {}", row.gray()),
+            ledger::Outcome::Failed => println!("  {}", row.red()),
+        }
+        if let Some(error) = &step.error {
+            for line in error.trim().lines() {
+                println!("    {}", line.gray());
+            }
+        }
+    }
+    println!();
+}
+
+/// The summary table's rows, the header first, with the columns lined up
+fn summary(steps: &[ledger::Step]) -> Vec<String> {
+    let short = |oid: &Option<String>| oid.as_deref().map(|oid| git::short_oid(oid).to_string()).unwrap_or_else(|| "-".to_string());
+    let mut rows = vec![["Branch".to_string(), "Result".to_string(), "Before".to_string(), "After".to_string()]];
+    rows.extend(steps.iter().map(|step| {
+        [step.branch.clone(), format!("{:?}", step.outcome).to_lowercase(), short(&step.before), short(&step.after)]
+    }));
+
+    let widths = (0..4).map(|column| rows.iter().map(|row| ui::text::width(&row[column])).max().unwrap_or(0)).collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            let cells = row.iter().zip(&widths).map(|(cell, width)| ui::text::pad(cell, *width)).collect::<Vec<_>>();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        // main <- api <- ui <- docs, with api behind main
+        let branches = ["api", "ui", "docs"].map(String::from);
+        let parents = [("api", "main"), ("ui", "api"), ("docs", "ui")];
+        let parent_of = |branch: &str| parents.iter().find(|(child, _)| *child == branch).map(|(_, parent)| parent.to_string());
+
+        let planned = plan(&branches, parent_of, |parent, _| parent != "main");
+        let names = planned.iter().map(|(branch, parent)| (branch.as_str(), parent.as_str())).collect::<Vec<_>>();
+        assert_eq!(names, [("api", "main"), ("ui", "api"), ("docs", "ui")]);
+
+        // Only docs is behind ui, so api and ui stay put
+        let planned = plan(&branches, parent_of, |parent, _| parent != "ui");
+        assert_eq!(planned, [("docs".to_string(), "ui".to_string())]);
+
+        assert!(plan(&branches, parent_of, |_, _| true).is_empty());
+    }
+
+    #[test]
+    fn test_summary() {
+        let steps = [
+            step("api", ledger::Outcome::Done, Some("1a2b3c4d5e".to_string()), Some("5d6e7f8a9b".to_string()), None),
+            step("feature/ui", ledger::Outcome::Failed, Some("9f8e7d6c5b".to_string()), None, Some("conflict".to_string())),
+        ];
+        assert_eq!(
+            summary(&steps),
+            [
+                "Branch      Result  Before   After",
+                "api         done    1a2b3c4  5d6e7f8",
+                "feature/ui  failed  9f8e7d6  -",
+            ]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{app::{restack, sync}, errors, git::{self, repo::InProgress}, ledger, t, ui::ColorizeExt};
+
+/// resume picks up the last sync or restack that was interrupted or stopped on conflicts
+pub fn resume() -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let Some(entry) = ledger::last_interrupted()? else {
+        return Err(anyhow!("Nothing to continue"));
+    };
+
+    println!(
+        "{}",
+        t!(
+            "resume.continuing",
+            operation = entry.operation.name(),
+            branch = entry.branch.sage(),
+            time = entry.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string().gray()
+        )
+    );
+
+    // Finish whatever git stopped in the middle of first
+    match git::repo::in_progress()? {
+        Some(InProgress::Rebase) => {
+            let conflicts = git::branch::conflicting_files()?;
+            if !conflicts.is_empty() {
+                println!("{} {}", t!("warning").yellow(), t!("resume.resolve_first"));
+                for file in &conflicts {
+                    println!("  {}", file.red());
+                }
+                restack::print_rebase_progress();
+                return Err(anyhow!("Conflicts are still unresolved"));
+            }
+            git::branch::continue_rebase()?;
+        }
+        Some(other) => {
+            return Err(anyhow!("A {} is in progress. Finish or abort it, then run sage continue again", other));
+        }
+        None => {}
+    }
+
+    match &entry.operation {
+        ledger::Operation::Sync { wip } => {
+            if git::branch::current()? != entry.branch {
+                git::branch::switch(&entry.branch, false)?;
+            }
+            // Sync parks changes again when it reruns, so put the ones it parked back first
+            if *wip && git::commit::head_is_wip()? {
+                git::commit::pop_wip_commit()?;
+            }
+            ledger::set_status(entry.id, ledger::Status::Done)?;
+            sync::sync()
+        }
+        ledger::Operation::Restack { root, remaining } => {
+            ledger::set_status(entry.id, ledger::Status::Done)?;
+            let steps = restack::restack_branches(&entry.branch, root, remaining)?;
+            restack::print_summary(&steps);
+            println!("{}", t!("resume.restacked"));
+            Ok(())
+        }
+        // Neither deleting branches nor running hooks is left part way
+        ledger::Operation::DeleteBranches { .. } | ledger::Operation::Hooks { .. } => Err(anyhow!("Nothing to continue")),
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::{anyhow, Result};
+
+use crate::{app::prompts::{self, Inputs, Prompt}, config::features::{self, Feature}, errors, git, ui::ColorizeExt};
+
+/// review asks the AI to review what the current branch adds on top of its parent
+pub async fn review() -> Result<()> {
+    features::require(Feature::AiReview)?;
+
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let branch = git::branch::current()?;
+    let parent = match git::stack::parent(&branch)? {
+        Some(parent) => parent,
+        None => git::repo::default_branch()?,
+    };
+
+    let range = format!("{}...{}", parent, branch);
+    let diff = git::repo::diff_range(&range, false)?;
+    if diff.trim().is_empty() {
+        return Err(anyhow!("{} has no changes on top of {} to review", branch, parent));
+    }
+
+    println!("Reviewing {} against {}...", branch.sage(), parent.sage());
+    let commit_log = git::repo::log_range(&format!("{}..{}", parent, branch))?;
+    let prompt = prompts::render(Prompt::Review, Inputs { diff: &diff, commits: &commit_log, ..Inputs::default() })?;
+    println!("\n{}", prompts::ask("review", &prompt).await?.trim());
+
+    Ok(())
+}
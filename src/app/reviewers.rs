@@ -0,0 +1,293 @@
+//! Suggesting who should review a pull request
+//!
+//! Candidates come from three places: CODEOWNERS for the files the branch touches, who wrote
+//! the lines of those files as they are on the base (git blame), and who committed to them
+//! recently. Owning a file counts most, then recent commits, then blamed lines. Commit emails are
+//! turned into GitHub logins through the API, and people without an account are left out, as
+//! they can't be asked to review.
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+
+use crate::{app::owners, gh::pulls, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// How many of the most changed files are blamed, as blame is slow on large files
+const MAX_BLAMED_FILES: usize = 20;
+/// How many recent commits to the touched files are looked at
+const RECENT_COMMITS: usize = 100;
+/// How many emails are looked up on GitHub, best first
+const MAX_LOOKUPS: usize = 8;
+/// How many suggestions are shown
+const MAX_SHOWN: usize = 8;
+/// How many of the top suggestions are picked unless the user chooses otherwise
+const DEFAULT_PICKS: usize = 2;
+
+/// Someone, or a team, who could review the changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Candidate {
+    /// `@login`, `@org/team`, or an email not yet matched to a login
+    pub name: String,
+    /// Changed files CODEOWNERS gives them
+    pub owned: usize,
+    /// Lines of the changed files they last touched
+    pub lines: usize,
+    /// Recent commits of theirs to the changed files
+    pub commits: usize,
+    /// One of their commits, to find their login by
+    commit: Option<String>,
+}
+
+impl Candidate {
+    fn named(name: &str) -> Candidate {
+        Candidate { name: name.to_string(), ..Candidate::default() }
+    }
+
+    /// score orders candidates: owning files counts most, then recent commits, then blamed lines
+    pub fn score(&self) -> (usize, usize, usize) {
+        (self.owned, self.commits, self.lines)
+    }
+
+    /// describe says why they're suggested, e.g. `owns 2 files, 3 recent commits`
+    pub fn describe(&self) -> String {
+        let mut reasons = Vec::new();
+        if self.owned > 0 {
+            reasons.push(format!("owns {} {}", self.owned, plural(self.owned, "file")));
+        }
+        if self.commits > 0 {
+            reasons.push(format!("{} recent {}", self.commits, plural(self.commits, "commit")));
+        }
+        if self.lines > 0 {
+            reasons.push(format!("wrote {} {}", self.lines, plural(self.lines, "line")));
+        }
+        reasons.join(", ")
+    }
+
+    fn merge(&mut self, other: &Candidate) {
+        self.owned += other.owned;
+        self.lines += other.lines;
+        self.commits += other.commits;
+        if self.commit.is_none() {
+            self.commit = other.commit.clone();
+        }
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 { word.to_string() } else { format!("{}s", word) }
+}
+
+/// gather collects candidates for `paths` from CODEOWNERS, blame and recent commits, keyed by
+/// owner or email
+pub fn gather(
+    codeowners: Option<&owners::CodeOwners>,
+    paths: &[String],
+    blame: &[(String, String, usize)],
+    recent: &[(String, String)],
+) -> Vec<Candidate> {
+    let mut candidates: BTreeMap<String, Candidate> = BTreeMap::new();
+
+    if let Some(codeowners) = codeowners {
+        for path in paths {
+            for owner in codeowners.owners_of(path) {
+                candidates.entry(owner.clone()).or_insert_with(|| Candidate::named(owner)).owned += 1;
+            }
+        }
+    }
+    for (email, commit, lines) in blame {
+        let candidate = candidates.entry(email.clone()).or_insert_with(|| Candidate::named(email));
+        candidate.lines += lines;
+        candidate.commit.get_or_insert_with(|| commit.clone());
+    }
+    for (commit, email) in recent {
+        let candidate = candidates.entry(email.clone()).or_insert_with(|| Candidate::named(email));
+        candidate.commits += 1;
+        candidate.commit.get_or_insert_with(|| commit.clone());
+    }
+
+    rank(candidates.into_values().collect())
+}
+
+/// rank orders candidates best first
+fn rank(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| b.score().cmp(&a.score()).then_with(|| a.name.cmp(&b.name)));
+    candidates
+}
+
+/// noreply_login reads the login out of a GitHub noreply address, e.g.
+/// `1234+dana@users.noreply.github.com`
+pub fn noreply_login(email: &str) -> Option<String> {
+    let user = email.strip_suffix("@users.noreply.github.com")?;
+    let login = user.split_once('+').map_or(user, |(_, login)| login);
+    (!login.is_empty()).then(|| login.to_string())
+}
+
+/// suggest ranks who could review the changes `head` makes on top of `base`, leaving out
+/// `author` and the local git user
+pub async fn suggest(owner: &str, repo: &str, base: &str, head: &str, author: Option<&str>) -> Result<Vec<Candidate>> {
+    let merge_base = git::repo::merge_base(base, head)?;
+    let mut changes = git::files::numstat(&format!("{}..{}", merge_base, head))?;
+    let paths = changes.iter().map(|change| change.path.clone()).collect::<Vec<_>>();
+
+    changes.sort_by_key(|change| std::cmp::Reverse(change.added + change.removed));
+    let mut blame = Vec::new();
+    for change in changes.iter().filter(|change| !change.binary).take(MAX_BLAMED_FILES) {
+        // New files have nothing to blame
+        blame.extend(git::files::blame_authors(&merge_base, &change.path).unwrap_or_default());
+    }
+    let recent = if paths.is_empty() { Vec::new() } else { git::files::recent_authors(&merge_base, &paths, RECENT_COMMITS)? };
+
+    let me = git::repo::get_config("user.email")?.unwrap_or_default();
+    let codeowners = owners::load()?;
+    let gathered = gather(codeowners.as_ref(), &paths, &blame, &recent);
+
+    // People found by email become @login, merging with what CODEOWNERS says about them
+    let mut resolved: Vec<Candidate> = Vec::new();
+    let mut lookups = 0;
+    for mut candidate in gathered.into_iter().filter(|candidate| candidate.name != me) {
+        if !candidate.name.starts_with('@') {
+            let login = match noreply_login(&candidate.name) {
+                Some(login) => Some(login),
+                None if lookups < MAX_LOOKUPS => {
+                    lookups += 1;
+                    match &candidate.commit {
+                        Some(commit) => pulls::commit_author(owner, repo, commit).await.unwrap_or_default(),
+                        None => None,
+                    }
+                }
+                None => None,
+            };
+            let Some(login) = login else { continue };
+            candidate.name = format!("@{}", login);
+        }
+
+        match resolved.iter_mut().find(|known| known.name.eq_ignore_ascii_case(&candidate.name)) {
+            Some(known) => known.merge(&candidate),
+            None => resolved.push(candidate),
+        }
+    }
+
+    let author = author.map(|author| format!("@{}", author));
+    Ok(rank(resolved.into_iter().filter(|candidate| Some(&candidate.name) != author.as_ref()).collect()))
+}
+
+/// request suggests reviewers for pull request `pr_number`, lets the user pick from them (the
+/// top few unless they change it) and requests reviews from whoever was picked. `unasked` picks
+/// the top few without asking; otherwise, without a terminal, the suggestions are only shown.
+pub async fn request(
+    owner: &str,
+    repo: &str,
+    pr_number: u64,
+    base: &str,
+    head: &str,
+    author: Option<&str>,
+    unasked: bool,
+) -> Result<()> {
+    let mut candidates = suggest(owner, repo, base, head, author).await?;
+    candidates.truncate(MAX_SHOWN);
+    if candidates.is_empty() {
+        println!("No reviewers to suggest for these changes");
+        return Ok(());
+    }
+
+    let labels = candidates
+        .iter()
+        .map(|candidate| format!("{} {}", candidate.name, format!("({})", candidate.describe()).gray()))
+        .collect::<Vec<_>>();
+    let defaults = (0..candidates.len().min(DEFAULT_PICKS)).collect::<Vec<_>>();
+
+    let picked = if unasked || !std::io::stdin().is_terminal() {
+        println!("Suggested reviewers:");
+        for label in &labels {
+            println!("  {} {}", accessible::mark(Mark::Bullet).sage(), label);
+        }
+        // Nobody to confirm with, so only show who could be asked
+        if !unasked {
+            println!("No reviews requested without a terminal to confirm them. Pass --yes to request them anyway");
+            return Ok(());
+        }
+        defaults
+    } else if accessible::enabled() {
+        println!("Suggested reviewers:");
+        for (index, label) in labels.iter().enumerate() {
+            println!("  {}. {}", index + 1, label);
+        }
+        let default = defaults.iter().map(|index| (index + 1).to_string()).collect::<Vec<_>>().join(",");
+        let answer = inquire::Text::new("Request reviews from (numbers separated by commas):")
+            .with_default(&default)
+            .prompt()?;
+        parse_picks(&answer, candidates.len()).ok_or_else(|| anyhow!("{} is not a list of the numbers shown", answer))?
+    } else {
+        inquire::MultiSelect::new("Request reviews from:", labels)
+            .with_default(&defaults)
+            .raw_prompt()?
+            .into_iter()
+            .map(|option| option.index)
+            .collect()
+    };
+
+    let picked = picked.into_iter().map(|index| candidates[index].name.clone()).collect::<Vec<_>>();
+    if picked.is_empty() {
+        println!("No reviewers requested");
+        return Ok(());
+    }
+
+    let (teams, people): (Vec<&String>, Vec<&String>) = picked.iter().partition(|name| owners::is_team(name));
+    // The API wants logins without the @ and team slugs without the org
+    let people = people.iter().map(|name| name.trim_start_matches('@').to_string()).collect::<Vec<_>>();
+    let teams = teams
+        .iter()
+        .filter_map(|name| name.split_once('/').map(|(_, slug)| slug.to_string()))
+        .collect::<Vec<_>>();
+    pulls::request_reviewers(owner, repo, pr_number, &people, &teams).await?;
+
+    println!("✨ Requested reviews from {}", picked.join(", ").sage());
+    Ok(())
+}
+
+/// parse_picks reads a comma separated list of 1-based choices, None when any isn't one
+pub(crate) fn parse_picks(answer: &str, count: usize) -> Option<Vec<usize>> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<usize>().ok().filter(|number| (1..=count).contains(number)).map(|number| number - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gather_ranks_owners_first() {
+        let codeowners = owners::CodeOwners::parse("/api/ @acme/backend\n");
+        let paths = vec!["api/users.go".to_string(), "web/app.ts".to_string()];
+        let blame = vec![
+            ("sam@example.com".to_string(), "a1".to_string(), 120),
+            ("dana@example.com".to_string(), "b2".to_string(), 30),
+        ];
+        let recent = vec![("b2".to_string(), "dana@example.com".to_string()); 3];
+
+        let ranked = gather(Some(&codeowners), &paths, &blame, &recent);
+        let names = ranked.iter().map(|candidate| candidate.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["@acme/backend", "dana@example.com", "sam@example.com"]);
+        assert_eq!(ranked[0].describe(), "owns 1 file");
+        assert_eq!(ranked[1].describe(), "3 recent commits, wrote 30 lines");
+    }
+
+    #[test]
+    fn test_noreply_login() {
+        assert_eq!(noreply_login("1234+dana@users.noreply.github.com").as_deref(), Some("dana"));
+        assert_eq!(noreply_login("dana@users.noreply.github.com").as_deref(), Some("dana"));
+        assert_eq!(noreply_login("dana@example.com"), None);
+    }
+
+    #[test]
+    fn test_parse_picks() {
+        assert_eq!(parse_picks("1, 3", 3), Some(vec![0, 2]));
+        assert_eq!(parse_picks("", 3), Some(vec![]));
+        assert_eq!(parse_picks("4", 3), None);
+        assert_eq!(parse_picks("two", 3), None);
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::fs;
+use crate::{ai, app::prompts, errors, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// Placeholders git format-patch leaves in a generated cover letter
+const SUBJECT_PLACEHOLDER: &str = "*** SUBJECT HERE ***";
+const BLURB_PLACEHOLDER: &str = "*** BLURB HERE ***";
+
+#[derive(Default)]
+pub struct SendEmailOptions {
+    /// Recipients of the series
+    pub to: Vec<String>,
+    /// Carbon-copy recipients
+    pub cc: Vec<String>,
+    /// Send every commit in the stack instead of just the current branch
+    pub stack: bool,
+    /// Use AI to write the cover letter
+    pub ai: bool,
+    /// Skip the cover letter entirely
+    pub no_cover_letter: bool,
+    /// Pass --dry-run to git send-email
+    pub dry_run: bool,
+}
+
+pub async fn send_email(opts: &SendEmailOptions) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+
+    // Work out where the series starts
+    let base = if opts.stack {
+        git::stack::stack(&current_branch)?.base
+    } else {
+        match git::stack::parent(&current_branch)? {
+            Some(parent) => parent,
+            None => git::repo::default_branch()?,
+        }
+    };
+    let range = format!("{}..HEAD", base);
+
+    let commit_log = git::repo::log_range(&range)?;
+    let commit_count = commit_log.lines().count();
+    if commit_count == 0 {
+        return Err(anyhow!("No commits to send between {} and {}", base, current_branch));
+    }
+
+    // A cover letter only makes sense when there's more than one patch
+    let cover_letter = !opts.no_cover_letter && commit_count > 1;
+
+    // The patches are only needed for the duration of the send, the directory goes when this returns
+    let out_dir = tempfile::Builder::new().prefix("sage-send-email-").tempdir()?;
+    let files = git::patch::format_patch(&range, out_dir.path(), cover_letter)?;
+
+    println!(
+        "{} {} {}",
+        "Patch series:".sage().bold(),
+        format!("{} patches", commit_count).yellow(),
+        format!("({})", range).gray()
+    );
+    for line in commit_log.lines() {
+        println!("  {} {}", accessible::mark(Mark::Bullet).sage(), line);
+    }
+    println!();
+
+    if cover_letter {
+        let (subject, blurb) = if opts.ai {
+            println!("✨ AI mode activated. Writing cover letter...");
+            let diffstat = git::repo::diff_range(&range, true)?;
+            let letter = prompts::ask("cover letter", &ai::prompts::cover_letter_prompt(&commit_log, &diffstat)).await?;
+
+            // The first line of the response becomes the subject
+            let parts: Vec<&str> = letter.trim().splitn(2, '\n').collect();
+            let subject = parts[0].trim().to_string();
+            let blurb = parts.get(1).map(|body| body.trim().to_string()).unwrap_or_default();
+            (subject, blurb)
+        } else {
+            let subject = inquire::Text::new("Cover letter subject: ").prompt()?;
+            let blurb = inquire::Editor::new("Cover letter body: ").prompt()?;
+            (subject, blurb)
+        };
+
+        // format-patch always writes the cover letter first
+        let cover_path = &files[0];
+        let contents = fs::read_to_string(cover_path)?
+            .replace(SUBJECT_PLACEHOLDER, &subject)
+            .replace(BLURB_PLACEHOLDER, &blurb);
+        fs::write(cover_path, contents)?;
+    }
+
+    git::patch::send_email(&files, &opts.to, &opts.cc, opts.dry_run)?;
+
+    if !opts.dry_run {
+        println!("✨ Sent {} patches!", commit_count);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use inquire::Confirm;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::{errors, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// Remote a session is saved to when no file is given
+const SESSION_REMOTE: &str = "origin";
+
+/// Where you were in a stack, to pick up on another machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub saved_at: DateTime<Utc>,
+    /// The branch checked out
+    pub head: String,
+    /// The trunk the stack is based on
+    pub base: String,
+    /// Branches of the stack, parents first
+    pub branches: Vec<SessionBranch>,
+    /// Stashes sage made, newest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stashes: Vec<String>,
+    /// Uncommitted changes to tracked files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionBranch {
+    pub name: String,
+    pub tip: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// save writes the current branch, its stack, sage's stashes and uncommitted changes to `file`.
+/// Without one it's pushed to the remote when `push` is set or that's confirmed, and otherwise
+/// written to a file in the .git directory.
+pub fn save(file: Option<&Path>, push: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let head = git::branch::current()?;
+    let stack = git::stack::stack(&head)?;
+    let names = if stack.branches.contains(&head) { stack.branches } else { vec![head.clone()] };
+
+    let mut branches = Vec::with_capacity(names.len());
+    for name in names {
+        let tip = git::repo::rev_parse(&format!("refs/heads/{}", name))?;
+        // Commits that only exist here can't be restored anywhere else
+        if !git::repo::is_pushed(&tip)? {
+            println!("{} {} has commits that aren't pushed; push it before restoring elsewhere", "WARNING:".yellow(), name);
+        }
+        branches.push(SessionBranch {
+            parent: git::stack::parent(&name)?,
+            upstream: git::branch::upstream(&name)?,
+            note: git::stack::note(&name)?,
+            name,
+            tip,
+        });
+    }
+
+    let mut stashes = Vec::new();
+    for stash in git::stash::list()?.into_iter().filter(git::stash::Stash::is_sage) {
+        stashes.push(git::stash::patch(&stash.name)?);
+    }
+    let changes = Some(git::patch::working_tree_diff()?).filter(|patch| !patch.is_empty());
+
+    let session = Session { saved_at: Utc::now(), head, base: stack.base, branches, stashes, changes };
+    let contents = serde_json::to_string_pretty(&session)?;
+
+    // Anyone who can read the repository can read a pushed session, changes and all
+    let push = file.is_none()
+        && (push
+            || (std::io::stdin().is_terminal()
+                && Confirm::new(&format!(
+                    "Push your session, including uncommitted changes, to {} where anyone with access to the repository can see it?",
+                    SESSION_REMOTE
+                ))
+                .with_default(false)
+                .prompt()?));
+
+    if push {
+        let reference = git::session::session_ref()?;
+        // Nothing there yet is fine, and anything else shows up when pushing
+        let _ = git::session::fetch(SESSION_REMOTE, &reference);
+        git::session::store(&reference, &contents, &format!("sage session on {}", session.head))?;
+        git::session::push(SESSION_REMOTE, &reference).context("Failed to push your session. Was one saved from another machine since? Restore it first")?;
+        println!("✨ Saved your session to {} on {}", reference.sage(), SESSION_REMOTE);
+        println!("Pick it up with {}", "sage session restore".sage());
+    } else {
+        let file = match file {
+            Some(file) => file.to_path_buf(),
+            None => default_file()?,
+        };
+        fs::write(&file, contents)?;
+        println!("✨ Saved your session to {}", file.display().to_string().sage());
+        println!("Pick it up with {}", format!("sage session restore --file {}", file.display()).sage());
+    }
+    if session.changes.is_some() {
+        println!("Untracked files aren't included; commit or copy them separately.");
+    }
+
+    Ok(())
+}
+
+/// Where a session is saved when it isn't pushed and no file is given
+fn default_file() -> Result<PathBuf> {
+    let dir = git::repo::git_dir()?.join("sage");
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join("session.json"))
+}
+
+/// restore recreates a saved session: branches are created or fast-forwarded, their stack
+/// placement and upstreams restored, the saved branch checked out and stashes and uncommitted
+/// changes put back
+pub fn restore(file: Option<&Path>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let status = git::status::status()?;
+    if status.has_changes() || status.has_staged_changes() {
+        return Err(anyhow!("You have uncommitted changes. Commit or stash them before restoring a session"));
+    }
+
+    let contents = match file {
+        Some(file) => fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?,
+        None => {
+            let reference = git::session::session_ref()?;
+            git::session::fetch(SESSION_REMOTE, &reference)?;
+            git::session::read(&reference)?
+        }
+    };
+    let session: Session = serde_json::from_str(&contents).context("Failed to parse the saved session")?;
+
+    println!(
+        "Restoring your session on {} from {}",
+        session.head.sage(),
+        session.saved_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string().gray()
+    );
+    if let Err(e) = git::repo::fetch_remote() {
+        println!("{} {}; branches may be out of date", "WARNING:".yellow(), e);
+    }
+
+    for branch in &session.branches {
+        restore_branch(branch)?;
+    }
+    if git::branch::exists(&session.head) {
+        git::branch::switch(&session.head, false)?;
+    }
+
+    // Oldest first, so they end up stacked in the same order
+    for patch in session.stashes.iter().rev() {
+        git::patch::apply(patch)?;
+        git::stash::push_message(git::stash::SAGE_STASH_MESSAGE)?;
+    }
+    if let Some(changes) = &session.changes {
+        git::patch::apply(changes)?;
+        println!("Your uncommitted changes are back");
+    }
+
+    println!("✨ Picked up where you left off on {}", session.head.sage());
+    Ok(())
+}
+
+/// Bring a branch to its saved tip where that's safe, and restore its place in the stack
+fn restore_branch(branch: &SessionBranch) -> Result<()> {
+    let short = &branch.tip[..7.min(branch.tip.len())];
+    let bullet = accessible::mark(Mark::Bullet).sage();
+    let have_tip = git::repo::rev_exists(&branch.tip);
+
+    if git::branch::exists(&branch.name) {
+        let local = git::repo::rev_parse(&format!("refs/heads/{}", branch.name))?;
+        if local == branch.tip {
+            println!("  {} {} {}", bullet, branch.name, "up to date".gray());
+        } else if have_tip && git::repo::is_ancestor(&local, &branch.tip) {
+            // The checked-out branch has to take its working tree along with it
+            if git::branch::current().is_ok_and(|current| current == branch.name) {
+                if !git::status::is_clean()? {
+                    return Err(anyhow!("{} has uncommitted changes. Commit or stash them before restoring a session", branch.name));
+                }
+                git::branch::fast_forward(&branch.tip)?;
+            } else {
+                git::branch::fast_forward_branch(&branch.name, &branch.tip)?;
+            }
+            println!("  {} {} {}", bullet, branch.name.yellow(), format!("fast-forwarded to {}", short).gray());
+        } else {
+            println!("{} {} has changed here too, leaving it as it is", "WARNING:".yellow(), branch.name);
+        }
+    } else if have_tip {
+        git::branch::create_at(&branch.name, &branch.tip)?;
+        println!("  {} {} {}", bullet, branch.name.yellow(), format!("created at {}", short).gray());
+    } else {
+        println!("{} {} was never pushed, so it can't be restored here", "WARNING:".yellow(), branch.name);
+        return Ok(());
+    }
+
+    if let Some(parent) = &branch.parent {
+        git::stack::set_parent(&branch.name, parent)?;
+    }
+    if let Some(note) = &branch.note {
+        git::stack::set_note(&branch.name, note)?;
+    }
+    if let Some(upstream) = branch.upstream.as_deref().filter(|upstream| git::repo::rev_exists(upstream)) {
+        git::branch::track(&branch.name, upstream)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Comparing the branch snapshots the ledger keeps for each operation
+//!
+//! `sage snapshot diff 12` shows what operation 12 did: the branches it created, deleted and
+//! moved, with the commits each moved branch gained and lost. Given two ids it covers everything
+//! from the start of the first to the end of the second, including whatever happened in between.
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+use crate::{errors, git, ledger, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// What happened to one branch between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefChange {
+    Created { branch: String, tip: String },
+    Deleted { branch: String, tip: String },
+    Moved { branch: String, from: String, to: String },
+}
+
+/// compare lists the branches that differ between two snapshots, by branch name
+pub fn compare(before: &BTreeMap<String, String>, after: &BTreeMap<String, String>) -> Vec<RefChange> {
+    let mut changes = Vec::new();
+    for (branch, from) in before {
+        match after.get(branch) {
+            None => changes.push(RefChange::Deleted { branch: branch.clone(), tip: from.clone() }),
+            Some(to) if to != from => {
+                changes.push(RefChange::Moved { branch: branch.clone(), from: from.clone(), to: to.clone() })
+            }
+            Some(_) => {}
+        }
+    }
+    for (branch, tip) in after {
+        if !before.contains_key(branch) {
+            changes.push(RefChange::Created { branch: branch.clone(), tip: tip.clone() });
+        }
+    }
+    changes.sort_by(|a, b| name(a).cmp(name(b)));
+    changes
+}
+
+fn name(change: &RefChange) -> &str {
+    match change {
+        RefChange::Created { branch, .. } | RefChange::Deleted { branch, .. } | RefChange::Moved { branch, .. } => branch,
+    }
+}
+
+/// list shows the operations in the ledger, newest first, with the ids `diff` takes
+pub fn list() -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let entries = ledger::load()?;
+    if entries.is_empty() {
+        println!("No operations recorded yet");
+        return Ok(());
+    }
+
+    // Hooks don't move branches, see sage hooks last for them
+    for entry in entries.iter().rev().filter(|entry| !matches!(entry.operation, ledger::Operation::Hooks { .. })) {
+        println!(
+            "{}  {}  {} on {} {}{}",
+            entry.id.to_string().yellow(),
+            entry.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string().gray(),
+            entry.operation.name(),
+            entry.branch.sage(),
+            format!("({})", status(entry.status)).gray(),
+            if entry.refs_before.is_empty() { " (no snapshot)".gray().to_string() } else { String::new() }
+        );
+    }
+    Ok(())
+}
+
+fn status(status: ledger::Status) -> &'static str {
+    match status {
+        ledger::Status::Running => "running",
+        ledger::Status::Interrupted => "interrupted",
+        ledger::Status::Done => "done",
+        ledger::Status::Failed => "failed",
+        ledger::Status::Undone => "undone",
+    }
+}
+
+/// diff shows how the branches changed from the start of operation `from` to the end of
+/// operation `to`, or the end of `from` itself
+pub fn diff(from: u64, to: Option<u64>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let entries = ledger::load()?;
+    let find = |id: u64| {
+        entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| anyhow!("No operation {} in the ledger. See {}", id, "sage snapshot list"))
+    };
+    let first = find(from)?;
+    let last = find(to.unwrap_or(from))?;
+    if last.id < first.id {
+        return Err(anyhow!("Operation {} came before {}; put the earlier one first", last.id, first.id));
+    }
+    if first.refs_before.is_empty() {
+        return Err(anyhow!("Operation {} was recorded before sage kept snapshots of branches", first.id));
+    }
+    if last.refs_after.is_empty() {
+        return Err(anyhow!("Operation {} hasn't finished, so there's nothing to compare it with yet", last.id));
+    }
+
+    if first.id == last.id {
+        println!("{} {} on {}", format!("#{}", first.id).yellow(), first.operation.name(), first.branch.sage());
+    } else {
+        println!(
+            "From the start of {} {} to the end of {} {}",
+            format!("#{}", first.id).yellow(),
+            first.operation.name(),
+            format!("#{}", last.id).yellow(),
+            last.operation.name()
+        );
+    }
+
+    let changes = compare(&first.refs_before, &last.refs_after);
+    if changes.is_empty() {
+        println!("No branches changed");
+        return Ok(());
+    }
+
+    let bullet = accessible::mark(Mark::Bullet).sage();
+    for change in &changes {
+        match change {
+            RefChange::Created { branch, tip } => {
+                println!("  {} {} {}", bullet, branch.green(), format!("created at {}", git::short_oid(tip)).gray());
+            }
+            RefChange::Deleted { branch, tip } => {
+                println!("  {} {} {}", bullet, branch.red(), format!("deleted, was at {}", git::short_oid(tip)).gray());
+            }
+            RefChange::Moved { branch, from, to } => {
+                println!("  {} {} {}", bullet, branch.yellow(), format!("{} -> {}", git::short_oid(from), git::short_oid(to)).gray());
+                print_commits(from, to);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Show the commits a moved branch gained and lost. The old ones may have been collected by
+/// gc since, in which case there's nothing left to show.
+fn print_commits(from: &str, to: &str) {
+    let (Ok(added), Ok(removed)) =
+        (git::repo::log_range(&format!("{}..{}", from, to)), git::repo::log_range(&format!("{}..{}", to, from)))
+    else {
+        println!("      {}", "commits are no longer available".gray());
+        return;
+    };
+    for line in removed.lines() {
+        println!("      {} {}", "-".red(), line);
+    }
+    for line in added.lines() {
+        println!("      {} {}", "+".green(), line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let snapshot = |refs: &[(&str, &str)]| {
+            refs.iter().map(|(name, oid)| (name.to_string(), oid.to_string())).collect::<BTreeMap<_, _>>()
+        };
+        let before = snapshot(&[("main", "a1"), ("feature", "b2"), ("old", "c3")]);
+        let after = snapshot(&[("main", "a1"), ("feature", "d4"), ("new", "e5")]);
+
+        assert_eq!(
+            compare(&before, &after),
+            vec![
+                RefChange::Moved { branch: "feature".to_string(), from: "b2".to_string(), to: "d4".to_string() },
+                RefChange::Created { branch: "new".to_string(), tip: "e5".to_string() },
+                RefChange::Deleted { branch: "old".to_string(), tip: "c3".to_string() },
+            ]
+        );
+        assert!(compare(&before, &before).is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::{app::{list::{describe_pr, pr_record}, note::describe_note}, errors, gh::graphql, git, ui::{accessible::{self, Mark}, actions::{self, Annotation, Level}, template::Template, ColorizeExt}};
+
+/// Name of the metadata file written alongside exported patches
+const MANIFEST_FILE: &str = "stack.json";
+
+/// Describes an exported stack so it can be rebuilt in another clone
+#[derive(Debug, Serialize, Deserialize)]
+struct StackManifest {
+    /// Version of sage that produced the export
+    sage_version: String,
+    /// The branch the bottom of the stack is based on
+    base: String,
+    /// Branches in the order they need to be re-created
+    branches: Vec<ExportedBranch>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedBranch {
+    name: String,
+    parent: String,
+    /// Patch files relative to the export directory, in apply order
+    patches: Vec<String>,
+}
+
+/// status shows every branch in the current stack with its pull request and check state, as a
+/// tree, through a template, or with `json` as a single JSON document
+pub async fn status(format: Option<&Template>, json: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let stack = git::stack::stack(&current_branch)?;
+
+    for cycle in git::stack::cycles(&git::stack::relations()?) {
+        eprintln!(
+            "{} The recorded stack parents go round in a loop: {} -> {}",
+            "WARNING:".yellow(),
+            cycle.join(" -> "),
+            cycle[0]
+        );
+        eprintln!("  Break it with {}\n", format!("git config --unset branch.{}.sage-parent", cycle[0]).sage());
+    }
+
+    // A single request covers the whole stack; without GitHub we still show the shape
+    let pull_requests = graphql::pull_requests_by_branch(&stack.branches).await.unwrap_or_else(|e| {
+        eprintln!("{} Could not look up pull requests: {}\n", "WARNING:".yellow(), e);
+        HashMap::new()
+    });
+
+    if format.is_none() && !json {
+        println!("Stack on {}", stack.base.sage());
+    }
+
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    let mut records = Vec::new();
+    let mut summary = format!("### Stack on {}\n\n| Branch | Parent | Pull request | Needs restack |\n| --- | --- | --- | --- |\n", stack.base);
+    for branch in &stack.branches {
+        let parent = git::stack::parent(branch)?.unwrap_or_else(|| stack.base.clone());
+        let depth = depths.get(&parent).map_or(0, |depth| depth + 1);
+        depths.insert(branch.clone(), depth);
+
+        let name = if *branch == current_branch {
+            branch.green().bold()
+        } else {
+            branch.yellow()
+        };
+
+        // The branch is behind its parent when the parent's tip isn't in its history
+        let behind_parent = !git::repo::is_ancestor(&parent, branch);
+        let note = git::stack::note(branch)?;
+        let (ahead, behind) = git::repo::ahead_behind(&parent, branch).unwrap_or_default();
+
+        let pr = pull_requests.get(branch).map(describe_pr).unwrap_or_default();
+        summary.push_str(&format!("| {} | {} | {} | {} |\n", branch, parent, pr, if behind_parent { "yes" } else { "no" }));
+        if behind_parent {
+            let message = format!("{} is behind {}. Run sage restack to bring it up to date", branch, parent);
+            Annotation { level: Level::Warning, title: "Needs restack", message: &message, ..Annotation::default() }.emit();
+        }
+
+        let record = serde_json::json!({
+            "name": branch,
+            "parent": parent,
+            "base": stack.base,
+            "depth": depth,
+            "current": *branch == current_branch,
+            "ahead": ahead,
+            "behind": behind,
+            "needs_restack": behind_parent,
+            "pr": pull_requests.get(branch).map(pr_record),
+            "note": note,
+        });
+        if json {
+            records.push(record);
+            continue;
+        }
+        if let Some(template) = format {
+            println!("{}", template.render(&record));
+            continue;
+        }
+
+        let mut line = format!("{}{} {}", "  ".repeat(depth + 1), accessible::mark(Mark::Bullet).sage(), name);
+        let counts = accessible::ahead_behind(ahead, behind);
+        if !counts.is_empty() {
+            line.push_str(&format!(" {}", counts.gray()));
+        }
+        if behind_parent {
+            line.push_str(&format!(" {}", "(needs restack)".yellow()));
+        }
+        if let Some(pr) = pull_requests.get(branch) {
+            line.push_str(&format!(" {}", describe_pr(pr).gray()));
+        }
+        if let Some(note) = &note {
+            line.push_str(&format!(" {}", describe_note(note)));
+        }
+        println!("{}", line);
+    }
+
+    actions::summary(&summary)?;
+    if json {
+        let document = serde_json::json!({ "base": stack.base, "current": current_branch, "branches": records });
+        println!("{}", serde_json::to_string_pretty(&document)?);
+    }
+    Ok(())
+}
+
+/// export writes the stack containing the current branch as an ordered patch series
+pub fn export(output: Option<PathBuf>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let stack = git::stack::stack(&current_branch)?;
+
+    let out_dir = output
+        .unwrap_or_else(|| PathBuf::from(format!("sage-stack-{}", current_branch.replace('/', "-"))));
+    if out_dir.exists() {
+        return Err(anyhow!("Output directory '{}' already exists", out_dir.display()));
+    }
+    fs::create_dir_all(&out_dir)?;
+
+    let mut manifest = StackManifest {
+        sage_version: env!("CARGO_PKG_VERSION").to_string(),
+        base: stack.base.clone(),
+        branches: Vec::with_capacity(stack.branches.len()),
+    };
+
+    for (index, branch) in stack.branches.iter().enumerate() {
+        let parent = git::stack::parent(branch)?.unwrap_or_else(|| stack.base.clone());
+
+        // Every branch gets its own numbered directory so the boundaries survive the export
+        let branch_dir = format!("{:02}-{}", index + 1, branch.replace('/', "-"));
+        let files = git::patch::format_patch(&format!("{}..{}", parent, branch), &out_dir.join(&branch_dir), false)?;
+
+        let patches = files
+            .iter()
+            .filter_map(|file| Path::new(file).file_name())
+            .map(|name| format!("{}/{}", branch_dir, name.to_string_lossy()))
+            .collect::<Vec<_>>();
+
+        println!(
+            "  {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            branch.yellow(),
+            format!("({} patches on {})", patches.len(), parent).gray()
+        );
+
+        manifest.branches.push(ExportedBranch {
+            name: branch.clone(),
+            parent,
+            patches,
+        });
+    }
+
+    fs::write(out_dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)?;
+
+    println!(
+        "\nExported {} branches to {}",
+        manifest.branches.len(),
+        out_dir.display().to_string().sage()
+    );
+
+    Ok(())
+}
+
+/// apply re-creates the branches of an exported stack in the current repository
+pub fn apply(dir: &Path) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let contents = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .with_context(|| format!("No {} found in {}", MANIFEST_FILE, dir.display()))?;
+    let manifest: StackManifest = serde_json::from_str(&contents)
+        .context("Failed to parse stack manifest")?;
+
+    if !git::status::is_clean()? {
+        return Err(anyhow!("Working tree has uncommitted changes. Commit or stash them before applying a stack"));
+    }
+
+    // Validate everything up front so we never leave a half-applied stack behind
+    let mut available = Vec::new();
+    for branch in &manifest.branches {
+        if git::branch::exists(&branch.name) {
+            return Err(anyhow!("Branch {} already exists", branch.name.yellow()));
+        }
+        if !available.contains(&branch.parent) && !git::branch::exists(&branch.parent) {
+            return Err(anyhow!("Parent branch {} does not exist locally", branch.parent.yellow()));
+        }
+        available.push(branch.name.clone());
+    }
+
+    let original_branch = git::branch::current()?;
+
+    // A patch that doesn't apply takes every branch made so far with it
+    let mut created = Vec::new();
+    if let Err(e) = apply_branches(dir, &manifest, &mut created) {
+        let _ = git::patch::abort_mailbox();
+        roll_back(&original_branch, &created);
+        return Err(e);
+    }
+
+    git::branch::switch(&original_branch, false)?;
+
+    println!("\nApplied {} branches on top of {}", manifest.branches.len(), manifest.base.sage());
+
+    Ok(())
+}
+
+/// apply_branches creates each branch in the manifest from its patches, adding its name to
+/// `created` as soon as it exists
+fn apply_branches(dir: &Path, manifest: &StackManifest, created: &mut Vec<String>) -> Result<()> {
+    for branch in &manifest.branches {
+        git::branch::switch(&branch.parent, false)?;
+        git::branch::switch(&branch.name, true)?;
+        created.push(branch.name.clone());
+
+        let files = branch
+            .patches
+            .iter()
+            .map(|patch| dir.join(patch).to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        // Branches without any commits of their own only need to exist
+        if !files.is_empty() {
+            git::patch::apply_mailbox(&files)
+                .map_err(|e| anyhow!("Failed to apply patches for {}: {}", branch.name, e))?;
+        }
+
+        git::stack::set_parent(&branch.name, &branch.parent)?;
+        println!(
+            "  {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            branch.name.yellow(),
+            format!("({} patches on {})", files.len(), branch.parent).gray()
+        );
+    }
+    Ok(())
+}
+
+/// roll_back goes back to `original` and deletes the branches a failed apply created, warning
+/// about any it can't
+fn roll_back(original: &str, created: &[String]) {
+    if let Err(e) = git::branch::switch(original, false) {
+        println!("{} Could not switch back to {}: {}", "WARNING:".yellow(), original, e);
+        return;
+    }
+    for branch in created.iter().rev() {
+        match git::branch::delete_local(branch) {
+            Ok(()) => println!("  {} {}", "Removed".gray(), branch.yellow()),
+            Err(e) => println!("{} Could not remove {}: {}", "WARNING:".yellow(), branch, e),
+        }
+    }
+}
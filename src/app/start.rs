@@ -1,25 +1,107 @@
-use crate::{errors, git};
-use anyhow::Result;
+use crate::{app::{dco, vars::Vars}, config, errors, git};
+use anyhow::{anyhow, Result};
+use std::fs;
 
-pub fn start(name: &str) -> Result<()> {
+/// start creates a branch from the default branch, or from `parent` to stack it on top, returning
+/// its name after branch.template is applied
+pub fn start(name: &str, parent: Option<&str>) -> Result<String> {
     // Check to ensure we are in a repo first.
     if !git::repo::is_repo()? {
         return Err(errors::GitError::NotARepository.into());
     }
 
+    let name = match config::get("branch.template") {
+        Some(template) => branch_vars(name, parent).render(&template, &[("name", name)])?,
+        None => name.to_string(),
+    };
+    let name = name.as_str();
+
     // Get the default branch (usually main or master)
-    // If we can't determine it, default to "main"
-    let default_branch = git::repo::default_branch().unwrap_or("main".to_string());
+    let default_branch = git::repo::default_branch()?;
 
     // Fetching the remote
     git::repo::fetch_remote()?;
 
-    // Pull latest changes for the default branch
-    git::repo::pull(&default_branch, true)?;
+    match parent {
+        Some(parent) => {
+            // Stacked branches start from their parent rather than the default branch
+            git::branch::switch(parent, false)?;
+        }
+        None => {
+            // Pull latest changes for the default branch
+            git::repo::pull(&default_branch, true)?;
+        }
+    }
 
     // Create a new branch if it doesn't exist
     git::branch::switch(name, true)?;
     git::branch::set_upstream(name)?;
 
-    Ok(())
+    // Remember where this branch was started from so stack-aware commands can find it
+    if let Some(parent) = parent {
+        git::stack::set_parent(name, parent)?;
+    }
+
+    Ok(name.to_string())
+}
+
+/// start_orphan creates a branch with no history, e.g. for gh-pages or docs, with a first commit
+/// that's empty or, with `readme`, adds a README. It's recorded as a stack root, so branches can
+/// be stacked on it. Returns its name after branch.template is applied.
+pub fn start_orphan(name: &str, readme: bool) -> Result<String> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let name = match config::get("branch.template") {
+        Some(template) => branch_vars(name, None).render(&template, &[("name", name)])?,
+        None => name.to_string(),
+    };
+    if git::branch::exists(&name) {
+        return Err(anyhow!("There's already a branch called {}", name));
+    }
+    // Starting over empties the working tree, which would lose them
+    if git::repo::has_tracked_changes()? {
+        return Err(anyhow!("Commit or stash your changes before starting an orphan branch"));
+    }
+
+    // A detached HEAD is gone back to by its commit
+    let previous = match git::branch::current()? {
+        branch if branch == "HEAD" => git::repo::rev_parse("HEAD")?,
+        branch => branch,
+    };
+    git::branch::switch_orphan(&name)?;
+    if let Err(e) = first_commit(&name, readme) {
+        git::branch::leave_orphan(&name, &previous)?;
+        return Err(e);
+    }
+
+    Ok(name)
+}
+
+/// Make the first commit on a new orphan branch and record it as a stack root
+fn first_commit(name: &str, readme: bool) -> Result<()> {
+    if readme {
+        // Tracked files are gone by now, so anything left is untracked and not ours to replace
+        let path = git::repo::toplevel()?.join("README.md");
+        if path.exists() {
+            return Err(anyhow!("There's already a README.md in the working tree, so --readme would overwrite it"));
+        }
+        fs::write(&path, format!("# {}\n", name))?;
+        git::files::stage_paths(&[path.to_string_lossy().to_string()])?;
+    }
+    git::commit::commit(&format!("chore: start {}", name), !readme, dco::signoff_enabled())?;
+    git::stack::set_root(name)
+}
+
+/// The variables for naming a new branch. A branch stacked on another carries on its parent's
+/// ticket and sits just above it.
+fn branch_vars(name: &str, parent: Option<&str>) -> Vars {
+    match parent {
+        Some(parent) if git::stack::parent(parent).ok().flatten().is_some() => {
+            let vars = Vars::for_branch(parent);
+            Vars { stack_position: vars.stack_position + 1, stack_size: vars.stack_size + 1, ..vars }
+        }
+        _ => Vars::for_branch(name),
+    }
 }
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use std::collections::HashMap;
+
+use crate::{app::{checkpoint::parse_time, reviewers::noreply_login}, errors, gh::search, git};
+
+/// How a report is written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Csv,
+}
+
+/// One author's contributions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Row {
+    author: String,
+    /// The GitHub login, when it's known
+    login: Option<String>,
+    commits: usize,
+    merged_prs: usize,
+}
+
+/// Who to leave out of a report
+#[derive(Debug, Clone, Default)]
+struct Exclusions {
+    bots: bool,
+    /// Names, emails or logins, matched ignoring case
+    authors: Vec<String>,
+}
+
+impl Exclusions {
+    fn excludes(&self, names: &[&str]) -> bool {
+        names.iter().any(|name| {
+            (self.bots && is_bot(name)) || self.authors.iter().any(|author| author.eq_ignore_ascii_case(name))
+        })
+    }
+}
+
+/// team is `sage stats team`: commits on the default branch and merged pull requests by author
+/// over a time window, as a table for a retrospective
+pub async fn team(since: &str, until: Option<&str>, format: Format, exclude_bots: bool, exclude: &[String]) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let now = Local::now();
+    let since = parse_time(since, now)?;
+    let until = until.map(|until| parse_time(until, now)).transpose()?;
+
+    let authors = git::list::authors(&git::repo::default_branch()?, since, until)?;
+    // The report still works from local history alone, so a missing remote or token only warns
+    let merged_by = match merged_pull_requests(since, until.unwrap_or_else(Utc::now)).await {
+        Ok(merged_by) => Some(merged_by),
+        Err(e) => {
+            eprintln!("{} Could not count merged pull requests, showing commits only: {}", "WARNING:".yellow(), e);
+            None
+        }
+    };
+
+    let exclusions = Exclusions { bots: exclude_bots, authors: exclude.to_vec() };
+    let rows = tally(&authors, merged_by.as_deref(), &exclusions);
+    let window = (since.with_timezone(&Local), until.unwrap_or_else(Utc::now).with_timezone(&Local));
+    let with_prs = merged_by.is_some();
+    print!(
+        "{}",
+        match format {
+            Format::Markdown => markdown(&rows, window, with_prs),
+            Format::Csv => csv(&rows, with_prs),
+        }
+    );
+    Ok(())
+}
+
+/// The authors of the pull requests merged in a time window, one entry per pull request
+async fn merged_pull_requests(since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<String>> {
+    let (owner, repo) = git::repo::owner_repo()?;
+    let format = "%Y-%m-%dT%H:%M:%SZ";
+    let query = format!("repo:{}/{} is:pr is:merged merged:{}..{}", owner, repo, since.format(format), until.format(format));
+    let items = search::search_all_issues(&query).await?;
+    Ok(items.into_iter().map(|item| item.user.login).collect())
+}
+
+/// Count commits and merged pull requests by author, busiest first. Commits are counted by
+/// author name; a pull request goes to the author whose noreply email or name matches its login.
+fn tally(authors: &[git::list::Author], merged_by: Option<&[String]>, exclusions: &Exclusions) -> Vec<Row> {
+    let mut rows: Vec<Row> = Vec::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+
+    for author in authors {
+        let login = noreply_login(&author.email);
+        if exclusions.excludes(&[&author.name, &author.email, login.as_deref().unwrap_or_default()]) {
+            continue;
+        }
+        let index = *by_name.entry(author.name.clone()).or_insert_with(|| {
+            rows.push(Row { author: author.name.clone(), ..Row::default() });
+            rows.len() - 1
+        });
+        let row = &mut rows[index];
+        row.commits += 1;
+        if row.login.is_none() {
+            row.login = login;
+        }
+    }
+
+    for login in merged_by.unwrap_or_default() {
+        if exclusions.excludes(&[login]) {
+            continue;
+        }
+        let matches = |row: &Row| match &row.login {
+            Some(row_login) => row_login.eq_ignore_ascii_case(login),
+            None => row.author.eq_ignore_ascii_case(login),
+        };
+        match rows.iter_mut().find(|row| matches(row)) {
+            Some(row) => row.merged_prs += 1,
+            None => rows.push(Row { author: login.clone(), login: Some(login.clone()), commits: 0, merged_prs: 1 }),
+        }
+    }
+
+    rows.sort_by(|a, b| {
+        (b.commits, b.merged_prs).cmp(&(a.commits, a.merged_prs)).then_with(|| a.author.to_lowercase().cmp(&b.author.to_lowercase()))
+    });
+    rows
+}
+
+/// Bot accounts sign as `name[bot]`, in their name, login and email
+fn is_bot(name: &str) -> bool {
+    name.split('@').next().unwrap_or_default().to_lowercase().ends_with("[bot]")
+}
+
+fn markdown(rows: &[Row], (since, until): (DateTime<Local>, DateTime<Local>), with_prs: bool) -> String {
+    let escape = |text: &str| text.replace('|', "\\|");
+    let mut out = format!("### Contributions from {} to {}\n\n", since.format("%Y-%m-%d"), until.format("%Y-%m-%d"));
+    if rows.is_empty() {
+        out.push_str("No contributions in this window.\n");
+        return out;
+    }
+
+    if with_prs {
+        out.push_str("| Author | Commits | PRs merged |\n| --- | ---: | ---: |\n");
+    } else {
+        out.push_str("| Author | Commits |\n| --- | ---: |\n");
+    }
+    for row in rows {
+        out.push_str(&format!("| {} | {} |", escape(&row.author), row.commits));
+        if with_prs {
+            out.push_str(&format!(" {} |", row.merged_prs));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn csv(rows: &[Row], with_prs: bool) -> String {
+    let quote = |text: &str| {
+        if text.contains([',', '"', '\n']) { format!("\"{}\"", text.replace('"', "\"\"")) } else { text.to_string() }
+    };
+    let mut out = if with_prs { "author,commits,merged_prs\n" } else { "author,commits\n" }.to_string();
+    for row in rows {
+        out.push_str(&format!("{},{}", quote(&row.author), row.commits));
+        if with_prs {
+            out.push_str(&format!(",{}", row.merged_prs));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn author(name: &str, email: &str) -> git::list::Author {
+        git::list::Author { name: name.to_string(), email: email.to_string() }
+    }
+
+    #[test]
+    fn test_tally() {
+        let authors = [
+            author("Ada", "ada@example.com"),
+            author("Grace", "123+grace@users.noreply.github.com"),
+            author("Ada", "ada@example.com"),
+            author("dependabot[bot]", "49699333+dependabot[bot]@users.noreply.github.com"),
+        ];
+        let merged_by = ["grace".to_string(), "ada".to_string(), "linus".to_string(), "renovate[bot]".to_string()];
+
+        let rows = tally(&authors, Some(&merged_by), &Exclusions { bots: true, authors: vec![] });
+        let summary = rows.iter().map(|row| (row.author.as_str(), row.commits, row.merged_prs)).collect::<Vec<_>>();
+        assert_eq!(summary, vec![("Ada", 2, 1), ("Grace", 1, 1), ("linus", 0, 1)]);
+
+        let rows = tally(&authors, None, &Exclusions { bots: false, authors: vec!["ADA@example.com".to_string()] });
+        let summary = rows.iter().map(|row| (row.author.as_str(), row.commits)).collect::<Vec<_>>();
+        assert_eq!(summary, vec![("dependabot[bot]", 1), ("Grace", 1)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let rows = vec![Row { author: "Ada, Countess".to_string(), login: None, commits: 2, merged_prs: 1 }];
+        let since = "2026-10-02T12:00:00Z".parse::<DateTime<Utc>>().unwrap().with_timezone(&Local);
+
+        assert_eq!(csv(&rows, true), "author,commits,merged_prs\n\"Ada, Countess\",2,1\n");
+        assert_eq!(csv(&rows, false), "author,commits\n\"Ada, Countess\",2\n");
+        assert!(markdown(&rows, (since, since), true).ends_with("| Author | Commits | PRs merged |\n| --- | ---: | ---: |\n| Ada, Countess | 2 | 1 |\n"));
+    }
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
-use crate::{errors, git};
+use serde_json::{json, Value};
+use crate::{app::{lfs, list::{self, Age}, owners}, errors, git::{self, status::{GitStatus, StatusSymbols}}, profile::{self, Phase}, ui::{accessible, template::Template, theme::{self, Theme}, ColorizeExt}};
 
-pub fn status() -> Result<()> {
+pub fn status(format: Option<&Template>) -> Result<()> {
 
     // Check to ensure we are in a repo first.
     if !git::repo::is_repo()? {
@@ -10,7 +11,163 @@ pub fn status() -> Result<()> {
 
     // // Get the full status
     let status = git::status::status()?;
-    println!("{}", status);
+    if let Some(template) = format {
+        println!("{}", template.render(&record(&status)));
+        return Ok(());
+    }
+
+    {
+        let _timing = profile::span(Phase::Render, "status");
+        println!("{}", status);
+    }
+    lfs::print_status()?;
+    // Like the owners, the branch's age is a nicety
+    let _ = print_age(&status.current_branch);
+    // Knowing who'll review is a nicety, so it never stops status from showing
+    let _ = owners::print_status();
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Describe the status for `--format` templates
+fn record(status: &GitStatus) -> Value {
+    let renamed = |pairs: &[(String, String)]| pairs.iter().map(|(_, to)| to.clone()).collect::<Vec<_>>();
+    let staged = [
+        &status.staged_added[..],
+        &status.staged_modified,
+        &status.staged_deleted,
+        &renamed(&status.staged_renamed),
+        &renamed(&status.staged_copied),
+        &status.staged_modified_unstaged_modified,
+        &status.staged_added_unstaged_modified,
+        &status.staged_added_unstaged_deleted,
+        &status.staged_deleted_unstaged_modified,
+        &status.staged_renamed_unstaged_modified,
+        &status.staged_copied_unstaged_modified,
+    ]
+    .concat();
+    let unstaged = [
+        &status.unstaged_modified[..],
+        &status.unstaged_deleted,
+        &status.unstaged_added,
+        &status.staged_modified_unstaged_modified,
+        &status.staged_added_unstaged_modified,
+        &status.staged_added_unstaged_deleted,
+        &status.staged_deleted_unstaged_modified,
+        &status.staged_renamed_unstaged_modified,
+        &status.staged_copied_unstaged_modified,
+    ]
+    .concat();
+
+    json!({
+        "branch": status.current_branch,
+        "upstream": status.upstream_branch,
+        "ahead": status.ahead_count,
+        "behind": status.behind_count,
+        "stash": status.has_stash,
+        "staged": staged,
+        "unstaged": unstaged,
+        "untracked": status.untracked,
+        "clean": status.is_clean(),
+    })
+}
+
+/// Print how long ago the current branch was last committed to and forked from the default
+/// branch
+fn print_age(current: &str) -> Result<()> {
+    let refs = git::index::refs()?;
+    let Some(branch) = refs.iter().find(|branch| branch.name == current) else {
+        return Ok(());
+    };
+    let default_branch = git::repo::default_branch()?;
+    let default_tip = refs.iter().find(|branch| branch.name == default_branch).map(|branch| branch.tip.as_str());
+
+    let mut index = git::index::Index::load();
+    let age = Age::of(&mut index, branch, (&default_branch, default_tip), chrono::Utc::now().timestamp(), list::stale_days()?)?;
+    let _ = index.save(None);
+    println!("{}{}", "Age:".sage(), list::describe_age(age));
+    Ok(())
+}
+
+/// The file status letters `sage status` prints, with what they mean
+fn file_symbols(symbols: &StatusSymbols) -> Vec<(String, &'static str)> {
+    let both = |staged: &str, unstaged: &str| format!("{}{}", staged, unstaged);
+    vec![
+        (symbols.added.to_string(), "added, staged for commit"),
+        (symbols.modified.to_string(), "modified"),
+        (symbols.deleted.to_string(), "deleted"),
+        (symbols.renamed.to_string(), "renamed"),
+        (symbols.copied.to_string(), "copied"),
+        (symbols.untracked.to_string(), "untracked"),
+        (symbols.ignored.to_string(), "ignored"),
+        (both(symbols.modified, symbols.modified), "modified in the index and again in the working tree"),
+        (both(symbols.added, symbols.modified), "added to the index, then modified in the working tree"),
+        (both(symbols.added, symbols.deleted), "added to the index, then deleted in the working tree"),
+        (both(symbols.deleted, symbols.modified), "deleted in the index, still in the working tree with changes"),
+        (both(symbols.renamed, symbols.modified), "renamed in the index, then modified in the working tree"),
+        (both(symbols.copied, symbols.modified), "copied in the index, then modified in the working tree"),
+    ]
+}
+
+/// What each mark stands for
+fn meaning(mark: accessible::Mark) -> &'static str {
+    use accessible::Mark;
+    match mark {
+        Mark::Passing => "checks passed, or a step succeeded",
+        Mark::Failing => "checks failed, or a step went wrong",
+        Mark::Pending => "checks still running",
+        Mark::Skipped => "a check was skipped",
+        Mark::Cancelled => "a check was cancelled",
+        Mark::Pushed => "pushed to origin",
+        Mark::Bullet => "an item in a list",
+    }
+}
+
+/// legend prints every symbol sage's output uses, as the current `ui.theme` shows them
+pub fn legend() {
+    for line in legend_lines(theme::current()) {
+        println!("{}", line);
+    }
+}
+
+fn legend_lines(theme: Theme) -> Vec<String> {
+    let mut lines = vec!["File status:".sage().to_string()];
+    lines.extend(file_symbols(&StatusSymbols::default()).iter().map(|(symbol, meaning)| format!("  {:<3} {}", symbol, meaning)));
+
+    lines.push(String::new());
+    lines.push("Branch:".sage().to_string());
+    lines.push(format!("  {:<3} {}", "+n", "n files staged"));
+    lines.push(format!("  {:<3} {}", "!n", "n files with unstaged changes"));
+    lines.push(format!("  {:<3} {}", "?n", "n untracked files"));
+    lines.push(format!("  {:<3} {}", "↑n", "n commits ahead of the upstream"));
+    lines.push(format!("  {:<3} {}", "↓n", "n commits behind the upstream"));
+    lines.push(format!("  {:<3} {}", "$", "stashed changes exist"));
+
+    lines.push(String::new());
+    lines.push(format!("{} {}", "Checks and steps:".sage(), format!("(ui.theme {})", theme.name()).gray()));
+    for mark in theme::MARKS {
+        // Accessible output shows the words, so there's only the meaning to give
+        let shown = if accessible::enabled() { mark.label() } else { theme.glyph(mark) };
+        let padded = format!("{:<3}", shown);
+        lines.push(format!("  {} {}", theme.paint(mark, &padded), meaning(mark)));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legend_lists_the_theme_glyphs() {
+        let lines = legend_lines(Theme::ColorBlind);
+        let has = |glyph: &str, meaning: &str| lines.iter().any(|line| line.contains(glyph) && line.ends_with(meaning));
+        assert!(has("✔", "checks passed, or a step succeeded"));
+        assert!(has("✘", "checks failed, or a step went wrong"));
+        assert!(has("?", "untracked"));
+        assert!(has("AD", "added to the index, then deleted in the working tree"));
+        assert!(has("+n", "n files staged"));
+        assert!(has("?n", "n untracked files"));
+        assert!(lines.iter().any(|line| line.contains("(ui.theme colorblind)")));
+    }
+}
@@ -1,11 +1,105 @@
-use crate::{errors, git};
+use crate::{app::{checkpoint, guard, interrupt, restack, wip}, config, errors, events, git, ledger, t};
 use anyhow::{anyhow, Result};
 use crate::ui::ColorizeExt;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Minutes after a fetch before sync --if-stale looks at the remote again
+const DEFAULT_STALE_MINUTES: u64 = 60;
+
+/// The remote for the canonical repository when origin is a fork
+pub(crate) const UPSTREAM_REMOTE: &str = "upstream";
+
+/// How sync brings the default branch's changes into a branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    /// Rebase, falling back to a merge when the rebase runs into conflicts
+    Auto,
+    Rebase,
+    Merge,
+    /// Only move a branch that hasn't got commits of its own
+    FfOnly,
+}
+
+impl Strategy {
+    fn parse(value: &str) -> Option<Strategy> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Some(Strategy::Auto),
+            "rebase" => Some(Strategy::Rebase),
+            "merge" => Some(Strategy::Merge),
+            "ff-only" | "ff_only" => Some(Strategy::FfOnly),
+            _ => None,
+        }
+    }
+
+    /// The strategy git's own pull.rebase and pull.ff ask for, with the setting that decided it
+    fn from_git(pull_rebase: Option<&str>, pull_ff: Option<&str>) -> Option<(Strategy, String)> {
+        let pull_rebase = pull_rebase.map(|value| value.trim().to_lowercase());
+        let pull_ff = pull_ff.map(|value| value.trim().to_lowercase());
+
+        // Anything but false rebases, e.g. merges or interactive
+        if let Some(value) = pull_rebase.as_deref().filter(|value| config::parse_bool(value) != Some(false)) {
+            return Some((Strategy::Rebase, format!("pull.rebase = {}", value)));
+        }
+        if pull_ff.as_deref() == Some("only") {
+            return Some((Strategy::FfOnly, "pull.ff = only".to_string()));
+        }
+        pull_rebase.map(|value| (Strategy::Merge, format!("pull.rebase = {}", value)))
+    }
+}
+
+/// Whether sync pushes a branch that's ahead of its upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushMode {
+    Auto,
+    Never,
+    Ask,
+}
+
+impl PushMode {
+    fn parse(value: &str) -> Option<PushMode> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" | "true" => Some(PushMode::Auto),
+            "never" | "false" => Some(PushMode::Never),
+            "ask" => Some(PushMode::Ask),
+            _ => None,
+        }
+    }
+}
+
+/// The strategy to sync with and the setting that picked it: sync.strategy, or git's pull.rebase
+/// and pull.ff when that isn't set, or sage's own judgement when neither is
+fn strategy() -> Result<(Strategy, Option<String>)> {
+    if let Some(value) = config::get("sync.strategy") {
+        let strategy = Strategy::parse(&value)
+            .ok_or_else(|| anyhow!("sync.strategy must be auto, rebase, merge or ff-only, not '{}'", value.trim()))?;
+        return Ok((strategy, Some(format!("sync.strategy = {}", value.trim()))));
+    }
+
+    let pull_rebase = git::repo::get_config("pull.rebase")?;
+    let pull_ff = git::repo::get_config("pull.ff")?;
+    Ok(match Strategy::from_git(pull_rebase.as_deref(), pull_ff.as_deref()) {
+        Some((strategy, setting)) => (strategy, Some(setting)),
+        None => (Strategy::Auto, None),
+    })
+}
+
+/// sync.push: auto by default
+fn push_mode() -> Result<PushMode> {
+    match config::get("sync.push") {
+        Some(value) => PushMode::parse(&value)
+            .ok_or_else(|| anyhow!("sync.push must be auto, never or ask, not '{}'", value.trim())),
+        None => Ok(PushMode::Auto),
+    }
+}
 
 /// Sync the current branch with its upstream/parent branch
 /// 
 /// This is a smart sync that:
-/// 1. Detects the best sync strategy based on branch state
+/// 1. Detects the best sync strategy based on branch state, unless sync.strategy (or git's
+///    pull.rebase) picks one
 /// 2. Tries to minimize conflicts by analyzing changes
 /// 3. Handles everything automatically without user intervention
 /// 4. Recovers gracefully from errors when possible
@@ -15,39 +109,268 @@ pub fn sync() -> Result<()> {
         return Err(errors::GitError::NotARepository.into());
     }
 
-    // Get current branch and default branch
     let current_branch = git::branch::current()?;
+    checkpoint::auto("before sync");
+
+    // Record the run so Ctrl-C or a failure part way through can be resumed with sage continue
+    let _guard = interrupt::guard();
+    let id = ledger::begin(&current_branch, ledger::Operation::Sync { wip: false })?;
+
+    match sync_branch(id, &current_branch) {
+        Ok(()) => ledger::set_status(id, ledger::Status::Done),
+        Err(e) if interrupt::interrupted() => {
+            let wip = ledger::load()?
+                .into_iter()
+                .find(|entry| entry.id == id)
+                .is_some_and(|entry| entry.operation == ledger::Operation::Sync { wip: true });
+            interrupt::stop(id, &current_branch, wip)?;
+            Err(e)
+        }
+        Err(e) => {
+            ledger::set_status(id, ledger::Status::Failed)?;
+            Err(e)
+        }
+    }
+}
+
+/// sync_if_stale syncs only when there may be something to sync: the branch is behind the default
+/// branch as last fetched or its stack parent, or, once the last look at the remote is older than
+/// sync.stale_minutes, the remote's default branch, the branch's own upstream or its parent's
+/// upstream has moved. Looking at the remote is a cheap ls-remote, so it's quick enough to run
+/// whenever a terminal opens. When the remote can't be reached or git is in the middle of
+/// something, it does nothing.
+pub fn sync_if_stale() -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+    if let Some(operation) = git::repo::in_progress()? {
+        println!("{}", t!("sync.skip_in_progress", operation = operation));
+        return Ok(());
+    }
+
+    let current_branch = git::branch::current()?;
+    let default_branch = git::repo::default_branch()?;
+    let remote_ref = format!("origin/{}", default_branch);
+    let parent = git::stack::parent(&current_branch)?.filter(|parent| git::branch::exists(parent));
+
+    // Behind what's already here, so there's something to sync whatever the remote says
+    let behind = [Some(remote_ref.clone()), parent.clone()]
+        .into_iter()
+        .flatten()
+        .filter(|rev| git::repo::rev_exists(rev))
+        .any(|rev| !git::repo::is_ancestor(&rev, "HEAD"));
+    if behind {
+        return sync();
+    }
+
+    let stale_minutes = config::get("sync.stale_minutes").and_then(|value| value.trim().parse().ok()).unwrap_or(DEFAULT_STALE_MINUTES);
+    let checked_minutes_ago = [git::repo::last_fetch()?, last_check()?]
+        .into_iter()
+        .flatten()
+        .max()
+        .and_then(|checked| checked.elapsed().ok())
+        .map(|elapsed| elapsed.as_secs() / 60);
+
+    if checked_minutes_ago.is_none_or(|minutes| minutes >= stale_minutes) {
+        for upstream in watched(&current_branch, &default_branch, parent.as_deref())? {
+            let Some((remote, branch)) = upstream.split_once('/') else {
+                continue;
+            };
+            match git::remote::tip(remote, branch) {
+                Ok(tip) if tip != git::repo::rev_parse(&upstream).ok() => return sync(),
+                Ok(_) => {}
+                Err(_) => {
+                    println!("{}", t!("sync.skip_unreachable"));
+                    return Ok(());
+                }
+            }
+        }
+        // Nothing moved, so there's no need to look again for another sync.stale_minutes
+        record_check()?;
+        println!("{}", t!("sync.skip_up_to_date_unfetched", branch = remote_ref.sage()));
+        return Ok(());
+    }
+
+    match checked_minutes_ago {
+        Some(minutes) => println!("{}", t!("sync.skip_up_to_date", branch = remote_ref.sage(), minutes = minutes)),
+        None => println!("{}", t!("sync.skip_up_to_date_unfetched", branch = remote_ref.sage())),
+    }
+    Ok(())
+}
+
+/// The remote-tracking branches a sync of `branch` brings in: the default branch's, and the
+/// upstreams of the branch and of its stack parent
+fn watched(branch: &str, default_branch: &str, parent: Option<&str>) -> Result<Vec<String>> {
+    let mut watched = vec![format!("origin/{}", default_branch)];
+    for branch in [Some(branch), parent].into_iter().flatten() {
+        if let Some(upstream) = git::branch::upstream(branch)?
+            && !watched.contains(&upstream)
+        {
+            watched.push(upstream);
+        }
+    }
+    Ok(watched)
+}
+
+/// Where sync --if-stale notes when it last found nothing had moved on the remote
+fn last_check_path() -> Result<PathBuf> {
+    Ok(git::repo::git_dir()?.join("sage").join("last-check"))
+}
+
+/// When sync --if-stale last found nothing had moved, None when it never has
+fn last_check() -> Result<Option<SystemTime>> {
+    Ok(fs::metadata(last_check_path()?).and_then(|metadata| metadata.modified()).ok())
+}
+
+fn record_check() -> Result<()> {
+    let path = last_check_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, "")?;
+    Ok(())
+}
+
+/// Rebase onto `branch`, showing where the rebase stopped when it runs into conflicts
+fn rebase_onto(branch: &str) -> Result<()> {
+    let phase = events::phase(format!("rebase onto {}", branch));
+    git::branch::rebase(branch).inspect_err(|_| {
+        restack::report_conflicts("sync", branch);
+        restack::print_rebase_progress();
+    })?;
+    phase.done();
+    Ok(())
+}
+
+/// Whether to push a branch that's ahead, going by sync.push. Asking needs a terminal, so
+/// without one nothing is pushed.
+fn should_push(branch: &str) -> Result<bool> {
+    match push_mode()? {
+        PushMode::Auto => Ok(true),
+        PushMode::Never => Ok(false),
+        PushMode::Ask if !std::io::stdin().is_terminal() => Ok(false),
+        PushMode::Ask => Ok(inquire::Confirm::new(&format!("Push {} to origin?", branch)).with_default(true).prompt()?),
+    }
+}
+
+/// sync_upstream keeps a fork up to date, for when origin is your fork and upstream the canonical
+/// repository: the default branch is fast-forwarded to upstream's and pushed to origin, then the
+/// current branch is brought up to date with it using sync.strategy (rebasing by default).
+pub fn sync_upstream() -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+    if git::repo::remote_url(UPSTREAM_REMOTE)?.is_none() {
+        return Err(anyhow!(
+            "There's no upstream remote. Add the repository you forked with 'sage remote add upstream <url>'"
+        ));
+    }
+
+    let default_branch = git::repo::default_branch()?;
+    let current_branch = git::branch::current()?;
+    let upstream_ref = format!("{}/{}", UPSTREAM_REMOTE, default_branch);
+    checkpoint::auto("before sync --upstream");
+
+    println!("{}", t!("sync.fetching_upstream", branch = upstream_ref.sage()));
+    git::remote::fetch_branch(UPSTREAM_REMOTE, &default_branch)?;
+    interrupt::checkpoint()?;
+
+    // Only ever fast-forward the default branch, so commits made on it by mistake aren't lost
+    println!("{}", t!("sync.updating_fork", branch = default_branch.sage()));
+    if current_branch == default_branch {
+        git::branch::fast_forward(&upstream_ref)?;
+    } else {
+        git::branch::fast_forward_branch(&default_branch, &upstream_ref)?;
+    }
+    git::remote::push_branch("origin", &default_branch)?;
+    interrupt::checkpoint()?;
+
+    if current_branch == default_branch || git::repo::is_ancestor(&default_branch, "HEAD") {
+        println!("{}", t!("sync.done", branch = current_branch.sage()));
+        return Ok(());
+    }
+
+    let (strategy, setting) = strategy()?;
+    let setting = setting.unwrap_or_default();
+    match strategy {
+        Strategy::Auto => {
+            println!("{}", t!("sync.behind", branch = default_branch.sage()));
+            rebase_onto(&default_branch)?;
+        }
+        Strategy::Rebase => {
+            println!("{}", t!("sync.rebasing", branch = default_branch.sage(), setting = setting));
+            rebase_onto(&default_branch)?;
+        }
+        Strategy::Merge => {
+            println!("{}", t!("sync.merging", branch = default_branch.sage(), setting = setting));
+            let phase = events::phase(format!("merge {}", default_branch));
+            git::branch::merge(&default_branch).inspect_err(|_| restack::report_conflicts("sync", &default_branch))?;
+            phase.done();
+        }
+        Strategy::FfOnly => {
+            println!("{}", t!("sync.fast_forwarding", branch = default_branch.sage(), setting = setting));
+            git::branch::fast_forward(&default_branch)?;
+        }
+    }
+
+    println!("{}", t!("sync.done", branch = current_branch.sage()));
+    // A rebase rewrote commits origin already has
+    let rewritten = matches!(strategy, Strategy::Auto | Strategy::Rebase);
+    if rewritten && git::branch::upstream(&current_branch)?.is_some() {
+        println!("{}", t!("sync.push_rewritten", command = "sage push --force".sage()));
+    }
+    Ok(())
+}
+
+/// Record whether uncommitted changes are currently parked in a WIP commit
+fn set_wip(id: u64, wip: bool) -> Result<()> {
+    ledger::update(id, |entry| entry.operation = ledger::Operation::Sync { wip })
+}
+
+fn sync_branch(id: u64, current_branch: &str) -> Result<()> {
     let default_branch = git::repo::default_branch()?;
 
     // Get initial status
     let status = git::status::status()?;
 
     // Fetch latest changes from remote to get an up-to-date picture
-    println!("Fetching remote changes...");
+    println!("{}", t!("sync.fetching"));
+    let phase = events::phase("fetch");
     git::repo::fetch_remote()?;
+    phase.done();
+    interrupt::checkpoint()?;
 
     // If we're on the default branch, just pull and we're done
+    let (strategy, setting) = strategy()?;
+    let setting = setting.unwrap_or_default();
+
     if current_branch == default_branch {
-        println!("On default branch, pulling latest changes...");
-        git::repo::pull(&default_branch, true)?;
-        println!("✨ Successfully updated default branch!");
+        println!("{}", t!("sync.pulling_default"));
+        match strategy {
+            Strategy::Auto | Strategy::FfOnly => git::repo::pull(&default_branch, true)?,
+            Strategy::Merge => git::repo::pull(&default_branch, false)?,
+            Strategy::Rebase => rebase_onto(&format!("origin/{}", default_branch))?,
+        }
+        println!("{}", t!("sync.default_updated"));
         return Ok(());
     }
 
     // We're on a feature branch - let's be smart about how we sync
-    println!("Analyzing branch state...");
+    println!("{}", t!("sync.analyzing"));
 
     // First update the default branch without switching to it
     // This gives us the latest state to work with
     git::repo::fetch_branch(&default_branch)?;
+    interrupt::checkpoint()?;
 
     // Check if there are any local changes that aren't pushed
     let has_local_changes = status.has_changes() || status.has_staged_changes();
 
     // If we have local changes, commit them as a WIP
     if has_local_changes {
-        println!("Creating temporary commit for local changes...");
+        println!("{}", t!("sync.wip"));
         git::commit::create_wip_commit()?;
+        set_wip(id, true)?;
     }
 
     // Determine the best sync strategy based on branch state
@@ -55,47 +378,117 @@ pub fn sync() -> Result<()> {
     let behind = status.behind_count > 0;
     let ahead = status.ahead_count > 0;
 
-    if diverged {
-        // Branch has diverged - try to rebase but fall back to merge if needed
-        println!("Branch has diverged from {}...", default_branch.sage());
-        
-        // Try rebase first
-        if let Err(_) = git::branch::rebase(&default_branch) {
-            println!("Rebase encountered conflicts, falling back to merge...");
-            // Abort the failed rebase
-            git::branch::abort_rebase()?;
-            
-            // Try merge instead
-            if let Err(_) = git::branch::merge(&default_branch) {
-                // Both rebase and merge failed - need manual intervention
-                println!("\n⚠️  Could not automatically sync branch:");
-                println!("1. Your branch has diverged significantly from {}", default_branch.sage());
-                println!("2. Both rebase and merge resulted in conflicts");
-                println!("\nRecommended actions:");
-                println!("1. Manually merge {} into your branch", default_branch.sage());
-                println!("2. Resolve the conflicts");
-                println!("3. Run sage sync again");
-                return Err(anyhow!("Could not automatically sync diverged branch"));
+    match strategy {
+        Strategy::Auto if diverged => {
+            // Branch has diverged - try to rebase but fall back to merge if needed
+            println!("{}", t!("sync.diverged", branch = default_branch.sage()));
+
+            // Try rebase first
+            if let Err(e) = git::branch::rebase(&default_branch) {
+                // Leave the rebase where it stopped so it can be continued
+                if interrupt::interrupted() {
+                    return Err(e);
+                }
+                restack::report_conflicts("sync", &default_branch);
+                println!("{}", t!("sync.fallback_merge"));
+                // Abort the failed rebase
+                git::branch::abort_rebase()?;
+
+                // Try merge instead
+                if let Err(_) = git::branch::merge(&default_branch) {
+                    // Both rebase and merge failed - need manual intervention
+                    println!("{}", t!("sync.failed"));
+                    println!("{}", t!("sync.failed_diverged", branch = default_branch.sage()));
+                    println!("{}", t!("sync.failed_conflicts"));
+                    println!("{}", t!("sync.recommended"));
+                    println!("{}", t!("sync.recommended_merge", branch = default_branch.sage()));
+                    println!("{}", t!("sync.recommended_resolve"));
+                    println!("{}", t!("sync.recommended_rerun"));
+                    return Err(anyhow!("Could not automatically sync diverged branch"));
+                }
+            }
+        }
+        Strategy::Auto if behind => {
+            // We're just behind - do a rebase
+            println!("{}", t!("sync.behind", branch = default_branch.sage()));
+            rebase_onto(&default_branch)?;
+        }
+        // The configured strategy is used as it is, stopping at conflicts rather than trying another
+        Strategy::Rebase if behind => {
+            println!("{}", t!("sync.rebasing", branch = default_branch.sage(), setting = setting));
+            rebase_onto(&default_branch)?;
+        }
+        Strategy::Merge if behind => {
+            println!("{}", t!("sync.merging", branch = default_branch.sage(), setting = setting));
+            git::branch::merge(&default_branch)?;
+        }
+        Strategy::FfOnly if diverged => {
+            return Err(anyhow!(
+                "Can't fast-forward, the branch has commits {} doesn't ({}). Set sync.strategy to rebase or merge to bring them together",
+                default_branch,
+                setting
+            ));
+        }
+        Strategy::FfOnly if behind => {
+            println!("{}", t!("sync.fast_forwarding", branch = default_branch.sage(), setting = setting));
+            git::branch::fast_forward(&default_branch)?;
+        }
+        _ if ahead && !has_local_changes => {
+            // We're ahead with clean commits - try to push
+            interrupt::checkpoint()?;
+            if !wip::outgoing(current_branch)?.is_empty() {
+                println!("{}", t!("sync.not_pushing_wip", command = "sage unwip".sage()));
+            } else if should_push(current_branch)? {
+                println!("{}", t!("sync.pushing"));
+                let phase = events::phase("push");
+                guard::check_before_push(current_branch, false)?;
+                git::branch::push(current_branch, false)?;
+                phase.done();
+            } else {
+                println!("{}", t!("sync.not_pushing", command = "sage push".sage()));
             }
         }
-    } else if behind {
-        // We're just behind - do a rebase
-        println!("Branch is behind {}, updating...", default_branch.sage());
-        git::branch::rebase(&default_branch)?;
-    } else if ahead && !has_local_changes {
-        // We're ahead with clean commits - try to push
-        println!("Pushing commits to remote...");
-        git::branch::push(&current_branch, false)?;
+        _ => {}
     }
 
     // If we created a WIP commit, handle it now
     if has_local_changes {
         // Pop the WIP commit but keep the changes
-        println!("Restoring uncommitted changes...");
+        println!("{}", t!("sync.restoring"));
         git::commit::pop_wip_commit()?;
+        set_wip(id, false)?;
     }
 
-    println!("✨ Successfully synced branch {}!", current_branch.sage());
+    println!("{}", t!("sync.done", branch = current_branch.sage()));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategy_from_git() {
+        let from_git = |rebase, ff| Strategy::from_git(rebase, ff).map(|(strategy, _)| strategy);
+        assert_eq!(from_git(None, None), None);
+        assert_eq!(from_git(Some("true"), None), Some(Strategy::Rebase));
+        assert_eq!(from_git(Some("merges"), Some("only")), Some(Strategy::Rebase));
+        assert_eq!(from_git(Some("false"), None), Some(Strategy::Merge));
+        assert_eq!(from_git(Some("false"), Some("only")), Some(Strategy::FfOnly));
+        assert_eq!(from_git(None, Some("only")), Some(Strategy::FfOnly));
+        assert_eq!(from_git(None, Some("true")), None);
+        assert_eq!(
+            Strategy::from_git(Some("True"), None),
+            Some((Strategy::Rebase, "pull.rebase = true".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Strategy::parse(" FF-only"), Some(Strategy::FfOnly));
+        assert_eq!(Strategy::parse("squash"), None);
+        assert_eq!(PushMode::parse("ask"), Some(PushMode::Ask));
+        assert_eq!(PushMode::parse("sometimes"), None);
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{errors, git, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// Markers that flag unfinished work
+const MARKERS: &[&str] = &["TODO", "FIXME"];
+
+/// A TODO or FIXME on a line added by a branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Todo {
+    pub path: String,
+    /// Line number in the branch's version of the file
+    pub line: usize,
+    pub marker: String,
+    /// The line, trimmed
+    pub text: String,
+}
+
+/// todos lists the TODO and FIXME markers every branch of the current stack adds, grouped by
+/// branch and file, or as a markdown task list when `markdown` is set
+pub fn todos(markdown: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let stack = git::stack::stack(&current_branch)?;
+    let branches = if stack.branches.contains(&current_branch) { stack.branches } else { vec![current_branch] };
+
+    let mut found = Vec::new();
+    for branch in branches {
+        let parent = git::stack::parent(&branch)?.unwrap_or_else(|| stack.base.clone());
+        let todos = added(&parent, &branch)?;
+        if !todos.is_empty() {
+            found.push((branch, todos));
+        }
+    }
+
+    if found.is_empty() {
+        println!("✨ No new TODOs in this stack");
+        return Ok(());
+    }
+
+    if markdown {
+        let headed = found.len() > 1;
+        for (branch, todos) in &found {
+            if headed {
+                println!("**{}**\n", branch);
+            }
+            println!("{}\n", task_list(todos));
+        }
+        return Ok(());
+    }
+
+    for (branch, todos) in &found {
+        println!("{} {}", branch.sage(), format!("({})", count(todos.len())).gray());
+        let mut path = None;
+        for todo in todos {
+            if path != Some(&todo.path) {
+                println!("  {}", todo.path);
+                path = Some(&todo.path);
+            }
+            println!(
+                "    {} {} {}",
+                accessible::mark(Mark::Bullet).sage(),
+                format!("{}:", todo.line).gray(),
+                todo.text
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// added returns the TODOs on lines `branch` adds on top of `parent`
+pub fn added(parent: &str, branch: &str) -> Result<Vec<Todo>> {
+    Ok(parse_diff(&git::repo::diff_range(&format!("{}...{}", parent, branch), false)?))
+}
+
+/// warn prints the TODOs a branch adds before its pull request is opened
+pub fn warn(todos: &[Todo]) {
+    if todos.is_empty() {
+        return;
+    }
+
+    println!("{} This branch adds {}:", "WARNING:".yellow(), count(todos.len()));
+    for todo in todos {
+        println!("  {} {}:{} {}", accessible::mark(Mark::Bullet).sage(), todo.path, todo.line, todo.text);
+    }
+}
+
+/// task_list renders TODOs as markdown checkboxes, e.g. for a PR description
+pub fn task_list(todos: &[Todo]) -> String {
+    todos
+        .iter()
+        .map(|todo| format!("- [ ] `{}:{}` {}", todo.path, todo.line, todo.text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn count(n: usize) -> String {
+    if n == 1 { "1 TODO".to_string() } else { format!("{} TODOs", n) }
+}
+
+/// Find markers on the added lines of a unified diff, with their line numbers in the new file
+pub(crate) fn parse_diff(diff: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut path: Option<String> = None;
+    let mut line = 0;
+    // Between `diff --git` and the first hunk, where `+++` names the file rather than adding a line
+    let mut in_header = false;
+
+    for diff_line in diff.lines() {
+        if diff_line.starts_with("diff ") {
+            in_header = true;
+        } else if in_header {
+            if let Some(new_path) = diff_line.strip_prefix("+++ ") {
+                // Deleted files show up as /dev/null and have nothing added
+                path = new_path.strip_prefix("b/").map(str::to_string);
+            } else if let Some(hunk) = diff_line.strip_prefix("@@ ") {
+                line = hunk_start(hunk).unwrap_or(0);
+                in_header = false;
+            }
+        } else if let Some(hunk) = diff_line.strip_prefix("@@ ") {
+            line = hunk_start(hunk).unwrap_or(0);
+        } else if let Some(added) = diff_line.strip_prefix('+') {
+            if let (Some(path), Some(marker)) = (&path, marker(added)) {
+                todos.push(Todo { path: path.clone(), line, marker: marker.to_string(), text: added.trim().to_string() });
+            }
+            line = line.saturating_add(1);
+        } else if diff_line.starts_with(' ') {
+            line = line.saturating_add(1);
+        }
+    }
+
+    todos
+}
+
+/// Read the first new-file line number from `-a,b +c,d @@`
+fn hunk_start(hunk: &str) -> Option<usize> {
+    let new = hunk.split_whitespace().find_map(|range| range.strip_prefix('+'))?;
+    new.split(',').next()?.parse().ok()
+}
+
+/// The marker on a line, if any. Markers have to stand on their own, so `TODOS` or `todo_list`
+/// don't count.
+fn marker(line: &str) -> Option<&'static str> {
+    MARKERS.iter().copied().find(|marker| {
+        line.match_indices(marker).any(|(start, _)| {
+            let before = line[..start].chars().next_back();
+            let after = line[start + marker.len()..].chars().next();
+            !before.is_some_and(|c| c.is_alphanumeric() || c == '_') && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diff_finds_added_markers() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,3 +10,6 @@ fn main() {
+     let a = 1;
+-    // TODO: old
++    // TODO: handle errors
++    let todos = vec![];
+     call(a);
++++ not a header // TODO
++    // FIXME(sam) this leaks
+diff --git a/old.rs b/old.rs
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-// TODO gone
+";
+        let todos = parse_diff(diff);
+        assert_eq!(todos.len(), 3);
+        assert_eq!((todos[0].path.as_str(), todos[0].line, todos[0].marker.as_str()), ("src/lib.rs", 11, "TODO"));
+        assert_eq!(todos[0].text, "// TODO: handle errors");
+        assert_eq!((todos[1].path.as_str(), todos[1].line), ("src/lib.rs", 14));
+        assert_eq!((todos[2].line, todos[2].marker.as_str()), (15, "FIXME"));
+    }
+
+    #[test]
+    fn test_task_list() {
+        let todo = Todo { path: "a.rs".to_string(), line: 3, marker: "TODO".to_string(), text: "// TODO: retry".to_string() };
+        assert_eq!(task_list(&[todo]), "- [ ] `a.rs:3` // TODO: retry");
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{errors, git, ledger, ui::{accessible::{self, Mark}, ColorizeExt}};
+
+/// record_deletion notes the tips, stack parents and upstreams of branches about to be deleted,
+/// and keeps each tip under the trash refs so gc doesn't collect it before it can be undone
+pub fn record_deletion(command: &str, branches: &[String]) -> Result<u64> {
+    let mut deleted = Vec::with_capacity(branches.len());
+    for name in branches {
+        deleted.push(capture(name)?);
+    }
+
+    ledger::begin(
+        &git::branch::current()?,
+        ledger::Operation::DeleteBranches { command: command.to_string(), branches: deleted },
+    )
+}
+
+/// Note down a branch before it's deleted, keeping its tip under the trash refs
+fn capture(name: &str) -> Result<ledger::DeletedBranch> {
+    let branch = ledger::DeletedBranch {
+        name: name.to_string(),
+        tip: git::repo::rev_parse(&format!("refs/heads/{}", name))?,
+        parent: git::stack::parent(name)?,
+        upstream: git::branch::upstream(name)?,
+        note: git::stack::note(name)?,
+    };
+    git::gc::trash(name)?;
+    Ok(branch)
+}
+
+/// finish_deletion marks a recorded deletion as done, dropping branches that weren't deleted
+/// after all so undo leaves them alone
+pub fn finish_deletion(id: u64) -> Result<()> {
+    let remaining = git::branch::list()?;
+    ledger::update(id, |entry| {
+        if let ledger::Operation::DeleteBranches { branches, .. } = &mut entry.operation {
+            branches.retain(|branch| !remaining.contains(&branch.name));
+        }
+        entry.status = ledger::Status::Done;
+    })
+}
+
+/// undo recreates the branches removed by the last command that deleted any, back in their
+/// place in the stack
+pub fn undo() -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let Some(entry) = ledger::last_undoable()? else {
+        return Err(anyhow!("Nothing to undo"));
+    };
+    let ledger::Operation::DeleteBranches { command, branches } = &entry.operation else {
+        return Err(anyhow!("Nothing to undo"));
+    };
+
+    println!(
+        "Undoing sage {} from {}",
+        command.sage(),
+        entry.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string().gray()
+    );
+
+    let mut restored = 0;
+    for branch in branches {
+        if git::branch::exists(&branch.name) {
+            println!("{} {} has been created again since, leaving it alone", "WARNING:".yellow(), branch.name);
+            continue;
+        }
+
+        git::branch::create_at(&branch.name, &branch.tip)?;
+        if let Some(parent) = &branch.parent {
+            git::stack::set_parent(&branch.name, parent)?;
+        }
+        if let Some(note) = &branch.note {
+            git::stack::set_note(&branch.name, note)?;
+        }
+        println!(
+            "  {} {} {}",
+            accessible::mark(Mark::Bullet).sage(),
+            branch.name.yellow(),
+            format!("at {}", &branch.tip[..7.min(branch.tip.len())]).gray()
+        );
+
+        match &branch.upstream {
+            Some(upstream) if git::repo::rev_exists(upstream) => git::branch::track(&branch.name, upstream)?,
+            Some(upstream) => println!(
+                "    {} was deleted too; push the branch again with {}",
+                upstream,
+                "sage push".sage()
+            ),
+            None => {}
+        }
+        restored += 1;
+    }
+
+    ledger::update(entry.id, |entry| {
+        entry.status = ledger::Status::Undone;
+        entry.undone_at = Some(chrono::Utc::now());
+    })?;
+    println!("✨ Restored {} branches", restored);
+
+    Ok(())
+}
+
+/// redo deletes the branches the last undo brought back again. Unless `force` is set it refuses
+/// when any of them has moved or gone since, as deleting it would lose more than undo restored.
+pub fn redo(force: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let Some(entry) = ledger::last_undone()? else {
+        return Err(anyhow!("Nothing to redo"));
+    };
+    let ledger::Operation::DeleteBranches { command, branches } = &entry.operation else {
+        return Err(anyhow!("Nothing to redo"));
+    };
+
+    let current = git::branch::current()?;
+    let problems = redo_problems(branches, &current);
+    if !problems.is_empty() {
+        println!("{} The repository has changed since sage undo:", "WARNING:".yellow());
+        for problem in &problems {
+            println!("  - {}", problem);
+        }
+        if !force {
+            return Err(anyhow!("Redoing sage {} isn't safe. Run sage redo --force to delete the branches that are left anyway", command));
+        }
+    }
+
+    println!("Redoing sage {}", command.sage());
+    let mut deleted = Vec::new();
+    for branch in branches {
+        if branch.name == current || !git::branch::exists(&branch.name) {
+            continue;
+        }
+        deleted.push(capture(&branch.name)?);
+        git::branch::delete_local(&branch.name)?;
+        println!("  {} {}", accessible::mark(Mark::Bullet).sage(), branch.name.yellow());
+    }
+
+    let count = deleted.len();
+    ledger::update(entry.id, |entry| {
+        // Record the tips actually deleted, so undoing again restores those
+        if let ledger::Operation::DeleteBranches { branches, .. } = &mut entry.operation {
+            *branches = deleted;
+        }
+        entry.status = ledger::Status::Done;
+        entry.undone_at = None;
+    })?;
+    println!("✨ Deleted {} branches again. {} brings them back", count, "sage undo".sage());
+
+    Ok(())
+}
+
+/// Explain what stops the deletion being replayed exactly as it was undone
+fn redo_problems(branches: &[ledger::DeletedBranch], current: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    for branch in branches {
+        match git::repo::rev_parse(&format!("refs/heads/{}", branch.name)) {
+            Err(_) => problems.push(format!("{} no longer exists", branch.name)),
+            Ok(tip) if tip != branch.tip => problems.push(format!(
+                "{} has moved since it was restored (from {} to {})",
+                branch.name,
+                &branch.tip[..7.min(branch.tip.len())],
+                &tip[..7.min(tip.len())]
+            )),
+            Ok(_) if branch.name == current => {
+                problems.push(format!("{} is checked out, switch away from it first", branch.name))
+            }
+            Ok(_) => {}
+        }
+    }
+    problems
+}
@@ -0,0 +1,151 @@
+//! Variables about the repository for commit, pull request and branch name templates
+//!
+//! Templates use the same `{{...}}` language as `--format` (see [`crate::ui::template`]), with:
+//!
+//! - `{{ticket}}`: the issue key in the branch name, e.g. `ABC-123` from `feature/ABC-123-login`,
+//!   or the number from `fix/123-login`
+//! - `{{branch}}`: the branch
+//! - `{{stack_position}}` and `{{stack_size}}`: where the branch sits in its stack, counting from 1
+//! - `{{owner}}` and `{{repo}}`: the GitHub repository
+//!
+//! Each use adds its own, like `{{message}}` for commit.template. Variables that can't be worked
+//! out (no remote, no ticket in the branch) are empty, so `{{#if ticket}}` can leave them out.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+use crate::{git, ui::template::Template};
+
+/// Repository metadata available to templates
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vars {
+    pub ticket: Option<String>,
+    pub branch: String,
+    pub stack_position: usize,
+    pub stack_size: usize,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl Vars {
+    /// for_branch works out the variables for `branch`, leaving out whatever can't be found
+    pub fn for_branch(branch: &str) -> Vars {
+        let (owner, repo) = git::repo::owner_repo().unwrap_or_default();
+        let relations = git::stack::relations().unwrap_or_default();
+        let stack_size = match git::stack::stack(branch) {
+            Ok(stack) if relations.iter().any(|(child, _)| child == branch) => stack.branches.len(),
+            _ => 1,
+        };
+
+        Vars {
+            ticket: ticket(branch),
+            branch: branch.to_string(),
+            stack_position: position(branch, &relations),
+            stack_size,
+            owner,
+            repo,
+        }
+    }
+
+    /// render fills in `template` with these variables and `extra` ones, e.g. `("message", ...)`
+    pub fn render(&self, template: &str, extra: &[(&str, &str)]) -> Result<String> {
+        let mut record = Map::new();
+        record.insert("ticket".to_string(), json!(self.ticket.clone().unwrap_or_default()));
+        record.insert("branch".to_string(), json!(self.branch));
+        record.insert("stack_position".to_string(), json!(self.stack_position));
+        record.insert("stack_size".to_string(), json!(self.stack_size));
+        record.insert("owner".to_string(), json!(self.owner));
+        record.insert("repo".to_string(), json!(self.repo));
+        for (name, value) in extra {
+            record.insert(name.to_string(), json!(value));
+        }
+
+        Ok(Template::parse(template)?.render(&Value::Object(record)))
+    }
+}
+
+/// position counts how far up its stack a branch is: 1 for a branch on the trunk, 2 for one on
+/// top of that and so on. Branches without a recorded parent count as 1.
+fn position(branch: &str, relations: &[(String, String)]) -> usize {
+    let parent_of = |name: &str| relations.iter().find(|(child, _)| child == name).map(|(_, parent)| parent.as_str());
+    let mut position = 1;
+    let mut current = branch;
+    // Bounded by the number of relations, in case they form a cycle
+    while let Some(parent) = parent_of(current).filter(|parent| parent_of(parent).is_some()) {
+        if position > relations.len() {
+            break;
+        }
+        position += 1;
+        current = parent;
+    }
+    position
+}
+
+/// ticket finds the issue a branch is for in its name: a tracker key like `ABC-123`, or else a
+/// number standing on its own like the `123` in `fix/123-login`
+pub fn ticket(branch: &str) -> Option<String> {
+    let key = branch.split(['/', '_']).find_map(|segment| {
+        // Keys start a segment or follow a dash, e.g. `ABC-123-login` or `fix-ABC-123`
+        let parts = segment.split('-').collect::<Vec<_>>();
+        parts.windows(2).find_map(|pair| {
+            let (project, number) = (pair[0], pair[1]);
+            let is_project = project.len() >= 2
+                && project.starts_with(|c: char| c.is_ascii_uppercase())
+                && project.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+            let is_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+            (is_project && is_number).then(|| format!("{}-{}", project, number))
+        })
+    });
+
+    key.or_else(|| {
+        branch
+            .split(['/', '-', '_'])
+            .find(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+            .map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticket() {
+        assert_eq!(ticket("feature/ABC-123-login").as_deref(), Some("ABC-123"));
+        assert_eq!(ticket("fix-OPS2-7").as_deref(), Some("OPS2-7"));
+        assert_eq!(ticket("fix/123-login").as_deref(), Some("123"));
+        assert_eq!(ticket("bugfix/issue-123").as_deref(), Some("123"));
+        assert_eq!(ticket("feature/oauth2-login"), None);
+        assert_eq!(ticket("main"), None);
+    }
+
+    #[test]
+    fn test_position() {
+        let relations = [("feature", "main"), ("child", "feature"), ("grandchild", "child"), ("a", "b"), ("b", "a")]
+            .map(|(child, parent)| (child.to_string(), parent.to_string()));
+        assert_eq!(position("feature", &relations), 1);
+        assert_eq!(position("grandchild", &relations), 3);
+        assert_eq!(position("untracked", &relations), 1);
+        assert!(position("a", &relations) <= relations.len() + 1);
+    }
+
+    #[test]
+    fn test_render() {
+        let vars = Vars {
+            ticket: Some("ABC-123".to_string()),
+            branch: "feature/ABC-123-login".to_string(),
+            stack_position: 2,
+            stack_size: 3,
+            owner: "acme".to_string(),
+            repo: "api".to_string(),
+        };
+        assert_eq!(
+            vars.render("{{ticket}}: {{message}} ({{stack_position}}/{{stack_size}})", &[("message", "Add login")]).unwrap(),
+            "ABC-123: Add login (2/3)"
+        );
+        assert_eq!(vars.render("{{owner}}/{{repo}}@{{branch}}", &[]).unwrap(), "acme/api@feature/ABC-123-login");
+
+        let vars = Vars { ticket: None, ..vars };
+        assert_eq!(vars.render("{{#if ticket}}{{ticket}}: {{/if}}{{message}}", &[("message", "Add login")]).unwrap(), "Add login");
+    }
+}
@@ -0,0 +1,334 @@
+//! `sage verify`: the checks a pull request should pass, in one command for CI
+//!
+//! It looks at the commits between the base and HEAD, and each check reports problems rather
+//! than stopping at the first, so one run shows everything to fix. `verify.checks` picks the
+//! checks to run.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+
+use crate::{
+    app::{dco, redact},
+    config, errors, git,
+    policy::{self, Commits, Policy},
+    ui::{accessible::{self, Mark}, actions::{self, Annotation}, theme, ColorizeExt},
+};
+
+/// Every check, in the order they run
+const CHECKS: &[&str] = &["commits", "merges", "stack", "dco", "secrets"];
+
+/// What `sage verify --report json` prints
+#[derive(Debug, Serialize)]
+struct Report {
+    base: String,
+    passed: bool,
+    checks: Vec<CheckResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckResult {
+    name: &'static str,
+    passed: bool,
+    problems: Vec<Problem>,
+}
+
+/// Something a check found, on a commit or a line of a file when it's about one
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct Problem {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+}
+
+/// A line a diff adds, with the file and line number it ends up at
+#[derive(Debug, PartialEq, Eq)]
+struct AddedLine<'a> {
+    file: &'a str,
+    line: usize,
+    text: &'a str,
+}
+
+/// verify runs the checks on the commits between `base` and HEAD, failing when any finds a
+/// problem. With `json`, nothing but the report is printed to stdout.
+pub fn verify(base: Option<String>, json: bool) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let base = match base {
+        Some(base) => base,
+        None => default_base()?,
+    };
+    let merge_base = git::repo::merge_base(&base, "HEAD")?;
+    let range = format!("{}..HEAD", merge_base);
+
+    let checks = enabled_checks()?;
+    let mut report = Report { base: base.clone(), passed: true, checks: Vec::new() };
+    for name in checks {
+        let problems = match name {
+            "commits" => check_commits(&range)?,
+            "merges" => check_merges(&range)?,
+            "stack" => check_stack()?,
+            "dco" => check_dco(&range)?,
+            _ => check_secrets(&range)?,
+        };
+        report.passed &= problems.is_empty();
+        report.checks.push(CheckResult { name, passed: problems.is_empty(), problems });
+    }
+
+    // Annotations would spoil the JSON, which has everything they do
+    for check in report.checks.iter().filter(|_| !json) {
+        for problem in &check.problems {
+            let message = match &problem.commit {
+                Some(commit) => format!("{} {}", commit, problem.message),
+                None => problem.message.clone(),
+            };
+            let title = format!("sage verify: {}", check.name);
+            Annotation { title: &title, message: &message, file: problem.file.as_deref(), line: problem.line, ..Annotation::default() }
+                .emit();
+        }
+    }
+    actions::summary(&summary(&report))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    let failed = report.checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        return Err(anyhow!("{} of {} checks failed", failed, report.checks.len()));
+    }
+    if !json {
+        println!("✨ Everything since {} passes", base.sage());
+    }
+    Ok(())
+}
+
+/// The base to check from: the pull request's base branch in GitHub Actions, otherwise the
+/// branch's stack parent or the default branch, on origin when it's there
+fn default_base() -> Result<String> {
+    let branch = match env::var("GITHUB_BASE_REF").ok().filter(|base| !base.is_empty()) {
+        Some(base) => base,
+        None => {
+            let current = git::branch::current()?;
+            match git::stack::parent(&current)? {
+                Some(parent) => parent,
+                None => git::repo::default_branch()?,
+            }
+        }
+    };
+
+    if git::repo::remote_branch_exists(&branch) && !git::branch::exists(&branch) {
+        return Ok(format!("origin/{}", branch));
+    }
+    if git::branch::exists(&branch) {
+        return Ok(branch);
+    }
+    Err(anyhow!("Can't find {} to check from. Fetch it, or pass the base with --base", branch))
+}
+
+/// The checks to run, from `verify.checks`: all of them by default, leaving out dco unless
+/// commit.signoff is on
+fn enabled_checks() -> Result<Vec<&'static str>> {
+    let Some(setting) = config::get("verify.checks") else {
+        let signoff = dco::signoff_enabled();
+        return Ok(CHECKS.iter().copied().filter(|check| *check != "dco" || signoff).collect());
+    };
+
+    let names = setting.split(',').map(str::trim).filter(|name| !name.is_empty()).collect::<Vec<_>>();
+    if let Some(unknown) = names.iter().find(|name| !CHECKS.contains(name)) {
+        return Err(anyhow!("verify.checks has an unknown check '{}' (use any of {})", unknown, CHECKS.join(", ")));
+    }
+    Ok(CHECKS.iter().copied().filter(|check| names.contains(check)).collect())
+}
+
+/// Commit messages follow Conventional Commits, with the org policy's types when it has them
+fn check_commits(range: &str) -> Result<Vec<Problem>> {
+    let commits = match policy::load()? {
+        Some(policy) if policy.commits.conventional => policy.commits,
+        _ => Commits { conventional: true, types: Vec::new() },
+    };
+    let rules = Policy { commits, ..Policy::default() };
+
+    Ok(git::commit::range_commits(range)?
+        .into_iter()
+        // Merge commits are the merges check's to report
+        .filter(|commit| commit.parents < 2)
+        .filter_map(|commit| {
+            rules.check_message(&commit.message).map(|message| Problem {
+                message,
+                commit: Some(commit.hash),
+                ..Problem::default()
+            })
+        })
+        .collect())
+}
+
+/// The branch has no merge commits, so it can be rebased and reviewed commit by commit
+fn check_merges(range: &str) -> Result<Vec<Problem>> {
+    Ok(git::commit::range_commits(range)?
+        .into_iter()
+        .filter(|commit| commit.parents > 1)
+        .map(|commit| Problem {
+            message: format!("'{}' is a merge commit. Rebase onto the base instead, e.g. with sage sync", commit.subject),
+            commit: Some(commit.hash),
+            ..Problem::default()
+        })
+        .collect())
+}
+
+/// The recorded stack parents don't loop, and every parent still exists
+fn check_stack() -> Result<Vec<Problem>> {
+    let relations = git::stack::relations()?;
+    let mut problems = git::stack::cycles(&relations)
+        .into_iter()
+        .map(|cycle| Problem {
+            message: format!("The stack parents go round in a loop: {} -> {}", cycle.join(" -> "), cycle[0]),
+            ..Problem::default()
+        })
+        .collect::<Vec<_>>();
+
+    for (child, parent) in &relations {
+        if git::branch::exists(child) && !git::branch::exists(parent) && !git::repo::remote_branch_exists(parent) {
+            problems.push(Problem {
+                message: format!("{}'s stack parent {} doesn't exist any more. Run sage sync on it to restack", child, parent),
+                ..Problem::default()
+            });
+        }
+    }
+    Ok(problems)
+}
+
+/// Every commit is signed off by its author
+fn check_dco(range: &str) -> Result<Vec<Problem>> {
+    Ok(git::commit::missing_signoff(range)?
+        .into_iter()
+        .map(|commit| Problem {
+            message: format!("'{}' is missing a Signed-off-by trailer. Run sage fix-dco to add it", commit.subject),
+            commit: Some(commit.hash),
+            ..Problem::default()
+        })
+        .collect())
+}
+
+/// No line the branch adds looks like a secret, going by what's redacted before asking the AI
+fn check_secrets(range: &str) -> Result<Vec<Problem>> {
+    let diff = git::repo::diff_range(range, false)?;
+    Ok(added_lines(&diff)
+        .into_iter()
+        .filter(|added| redact::redact(added.text, &[]).secrets > 0)
+        .map(|added| Problem {
+            message: "This line looks like it adds a secret. Move it out of the repository and rotate it".to_string(),
+            file: Some(added.file.to_string()),
+            line: Some(added.line),
+            ..Problem::default()
+        })
+        .collect())
+}
+
+/// The lines a diff adds, numbered by the hunk headers
+fn added_lines(diff: &str) -> Vec<AddedLine<'_>> {
+    let mut added = Vec::new();
+    let mut file = None;
+    let mut line = 0;
+
+    for text in diff.lines() {
+        if let Some(path) = text.strip_prefix("+++ ") {
+            file = path.strip_prefix("b/");
+        } else if let Some(hunk) = text.strip_prefix("@@ ") {
+            // @@ -old,count +new,count @@
+            line = hunk
+                .split_whitespace()
+                .find_map(|range| range.strip_prefix('+'))
+                .and_then(|range| range.split(',').next()?.parse().ok())
+                .unwrap_or(0);
+        } else if let (Some(path), Some(text)) = (file, text.strip_prefix('+')) {
+            added.push(AddedLine { file: path, line, text });
+            line += 1;
+        } else if text.starts_with(' ') {
+            line += 1;
+        }
+    }
+    added
+}
+
+fn print_report(report: &Report) {
+    println!("Checking everything since {}", report.base.sage());
+    for check in &report.checks {
+        if check.passed {
+            println!("{} {}", theme::styled(Mark::Passing), check.name);
+            continue;
+        }
+        println!("{} {}", theme::styled(Mark::Failing), check.name);
+        for problem in &check.problems {
+            let place = match (&problem.commit, &problem.file, problem.line) {
+                (Some(commit), _, _) => format!("{} ", commit.bright_yellow()),
+                (None, Some(file), Some(line)) => format!("{} ", format!("{}:{}", file, line).bright_yellow()),
+                _ => String::new(),
+            };
+            println!("  {} {}{}", accessible::mark(Mark::Bullet).sage(), place, problem.message);
+        }
+    }
+}
+
+/// The job summary: a row per check, with the problems under the table
+fn summary(report: &Report) -> String {
+    let mut summary = format!("### sage verify since {}\n\n| Check | Result |\n| --- | --- |\n", report.base);
+    for check in &report.checks {
+        let result = if check.passed { "passed".to_string() } else { format!("{} problem(s)", check.problems.len()) };
+        summary.push_str(&format!("| {} | {} |\n", check.name, result));
+    }
+    for check in report.checks.iter().filter(|check| !check.passed) {
+        summary.push_str(&format!("\n**{}**\n\n", check.name));
+        for problem in &check.problems {
+            let place = match (&problem.commit, &problem.file, problem.line) {
+                (Some(commit), _, _) => format!("`{}` ", commit),
+                (None, Some(file), Some(line)) => format!("`{}:{}` ", file, line),
+                _ => String::new(),
+            };
+            summary.push_str(&format!("- {}{}\n", place, problem.message));
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_added_lines() {
+        let diff = "diff --git a/src/db.rs b/src/db.rs\n\
+                    --- a/src/db.rs\n\
+                    +++ b/src/db.rs\n\
+                    @@ -10,3 +10,4 @@ fn connect() {\n\
+                    \x20    let url = env();\n\
+                    -    let user = \"admin\";\n\
+                    +    let user = \"root\";\n\
+                    +    let password = \"hunter2hunter2\";\n\
+                    \x20}\n\
+                    diff --git a/old.txt b/old.txt\n\
+                    deleted file mode 100644\n\
+                    --- a/old.txt\n\
+                    +++ /dev/null\n\
+                    @@ -1 +0,0 @@\n\
+                    -gone\n";
+
+        assert_eq!(
+            added_lines(diff),
+            vec![
+                AddedLine { file: "src/db.rs", line: 11, text: "    let user = \"root\";" },
+                AddedLine { file: "src/db.rs", line: 12, text: "    let password = \"hunter2hunter2\";" },
+            ]
+        );
+    }
+}
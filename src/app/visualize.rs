@@ -0,0 +1,283 @@
+//! `sage stack visualize`: draw the current stack as a picture
+//!
+//! The stack is laid out as a tree, a row per branch, and drawn as SVG with each branch's pull
+//! request and checks next to it. `--web` wraps the drawing in a standalone HTML page with a table
+//! of the same details. It has no scripts or links to other files, so it can be attached to a
+//! design doc or sent to reviewers who don't use sage.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{
+    app::list::describe_pr,
+    errors,
+    gh::graphql::{self, PrState, PrSummary},
+    git,
+    ui::ColorizeExt,
+};
+
+/// Height of a row, and how far each level of the tree is indented, in pixels
+const ROW: usize = 36;
+const INDENT: usize = 28;
+/// Roughly how wide a character of the labels is, to size the drawing
+const CHAR_WIDTH: usize = 8;
+
+/// A branch as it's drawn
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub parent: String,
+    /// How far up the stack it is, with 0 for the bottom branch
+    pub depth: usize,
+    pub current: bool,
+    pub needs_restack: bool,
+    pub pr: Option<PrSummary>,
+}
+
+/// visualize writes the current stack as SVG, to `output` or stdout, or with `web` as an HTML page
+pub async fn visualize(web: bool, output: Option<PathBuf>) -> Result<()> {
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let current_branch = git::branch::current()?;
+    let stack = git::stack::stack(&current_branch)?;
+
+    // Without GitHub the picture still shows the shape of the stack
+    let pull_requests = graphql::pull_requests_by_branch(&stack.branches).await.unwrap_or_else(|e| {
+        eprintln!("{} Could not look up pull requests: {}", "WARNING:".yellow(), e);
+        HashMap::new()
+    });
+
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    let mut nodes = Vec::with_capacity(stack.branches.len());
+    for branch in &stack.branches {
+        let parent = git::stack::parent(branch)?.unwrap_or_else(|| stack.base.clone());
+        let depth = depths.get(&parent).map_or(0, |depth| depth + 1);
+        depths.insert(branch.clone(), depth);
+        nodes.push(Node {
+            name: branch.clone(),
+            needs_restack: !git::repo::is_ancestor(&parent, branch),
+            parent,
+            depth,
+            current: *branch == current_branch,
+            pr: pull_requests.get(branch).cloned(),
+        });
+    }
+
+    if !web {
+        let svg = svg(&stack.base, &nodes);
+        match output {
+            Some(path) => {
+                fs::write(&path, svg)?;
+                println!("✨ Drew the stack in {}", path.display().to_string().sage());
+            }
+            None => print!("{}", svg),
+        }
+        return Ok(());
+    }
+
+    let path = output.unwrap_or_else(|| PathBuf::from(format!("sage-stack-{}.html", current_branch.replace('/', "-"))));
+    let generated = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+    fs::write(&path, page(&stack.base, &nodes, &generated))?;
+    println!("✨ Wrote the stack to {}", path.display().to_string().sage());
+    println!("Open it in a browser, or attach it to a doc or review");
+    Ok(())
+}
+
+/// svg draws the stack on `base` as a tree, with the base at the top
+pub fn svg(base: &str, nodes: &[Node]) -> String {
+    let label = |node: &Node| match &node.pr {
+        Some(pr) => format!("{}  {}", node.name, describe_pr(pr)),
+        None => node.name.clone(),
+    };
+    let widest = nodes
+        .iter()
+        .map(|node| (node.depth + 1) * INDENT + label(node).chars().count() * CHAR_WIDTH)
+        .chain([base.chars().count() * CHAR_WIDTH])
+        .max()
+        .unwrap_or_default();
+    let width = widest + 3 * INDENT;
+    let height = (nodes.len() + 1) * ROW;
+
+    // Where each branch's dot goes, with the base in the first row
+    let at = |row: usize, depth: usize| (INDENT / 2 + depth * INDENT, ROW / 2 + row * ROW);
+    let mut places: HashMap<&str, (usize, usize)> = HashMap::new();
+    places.insert(base, at(0, 0));
+    for (row, node) in nodes.iter().enumerate() {
+        places.insert(&node.name, at(row + 1, node.depth + 1));
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="ui-monospace, SFMono-Regular, Menlo, monospace" font-size="13">"#
+    );
+    let _ = writeln!(out, "  <title>Stack on {}</title>", escape(base));
+
+    // Lines first, so the dots sit on top of them
+    for node in nodes {
+        let (x, y) = places[node.name.as_str()];
+        let (px, py) = places.get(node.parent.as_str()).copied().unwrap_or(places[base]);
+        let dashes = if node.needs_restack { r#" stroke-dasharray="4 3""# } else { "" };
+        let _ = writeln!(out, r##"  <path d="M{px} {py} V{y} H{x}" fill="none" stroke="#8c959f" stroke-width="1.5"{dashes}/>"##);
+    }
+
+    let (x, y) = places[base];
+    let _ = writeln!(out, r##"  <circle cx="{x}" cy="{y}" r="6" fill="#8EA58C"/>"##);
+    let _ = writeln!(out, r##"  <text x="{}" y="{}" fill="#57606a">{}</text>"##, x + 14, y + 4, escape(base));
+
+    for node in nodes {
+        let (x, y) = places[node.name.as_str()];
+        let stroke = if node.current { r##" stroke="#24292f" stroke-width="2""## } else { "" };
+        let _ = writeln!(out, r#"  <circle cx="{x}" cy="{y}" r="6" fill="{}"{stroke}/>"#, check_color(node.pr.as_ref()));
+
+        let weight = if node.current { r#" font-weight="bold""# } else { "" };
+        let mut text = format!(r##"<text x="{}" y="{}" fill="#24292f"{weight}>{}"##, x + 14, y + 4, escape(&label(node)));
+        if node.needs_restack {
+            text.push_str(r##"<tspan fill="#9a6700">  (needs restack)</tspan>"##);
+        }
+        text.push_str("</text>");
+        match &node.pr {
+            Some(pr) if !pr.url.is_empty() => {
+                let _ = writeln!(out, r#"  <a href="{}">{}</a>"#, escape(&pr.url), text);
+            }
+            _ => {
+                let _ = writeln!(out, "  {}", text);
+            }
+        }
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// page wraps the drawing of the stack in an HTML page, with a table of the branches under it
+pub fn page(base: &str, nodes: &[Node], generated: &str) -> String {
+    let mut rows = String::new();
+    for node in nodes {
+        let pr = match &node.pr {
+            Some(pr) => format!(r#"<a href="{}">#{}</a> {}"#, escape(&pr.url), pr.number, state(pr)),
+            None => "none".to_string(),
+        };
+        let checks = node.pr.as_ref().and_then(|pr| pr.checks.as_deref()).unwrap_or("-").to_lowercase();
+        let _ = writeln!(
+            rows,
+            "      <tr><td>{}{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            "&nbsp;&nbsp;".repeat(node.depth),
+            escape(&node.name),
+            escape(&node.parent),
+            pr,
+            escape(&checks),
+            if node.needs_restack { "yes" } else { "no" }
+        );
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Stack on {base}</title>
+  <style>
+    body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; color: #24292f; margin: 2rem; }}
+    .drawing {{ overflow-x: auto; margin: 1.5rem 0; }}
+    table {{ border-collapse: collapse; }}
+    th, td {{ border: 1px solid #d0d7de; padding: 0.4rem 0.8rem; text-align: left; font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.9rem; }}
+    th {{ background: #f6f8fa; }}
+    footer {{ color: #57606a; font-size: 0.8rem; margin-top: 1.5rem; }}
+  </style>
+</head>
+<body>
+  <h1>Stack on {base}</h1>
+  <div class="drawing">
+{svg}  </div>
+  <table>
+    <thead>
+      <tr><th>Branch</th><th>Parent</th><th>Pull request</th><th>Checks</th><th>Needs restack</th></tr>
+    </thead>
+    <tbody>
+{rows}    </tbody>
+  </table>
+  <footer>Exported by sage {version} on {generated}</footer>
+</body>
+</html>
+"#,
+        base = escape(base),
+        svg = svg(base, nodes),
+        rows = rows,
+        version = env!("CARGO_PKG_VERSION"),
+        generated = escape(generated),
+    )
+}
+
+/// The dot's color: the pull request's checks, or grey without any
+fn check_color(pr: Option<&PrSummary>) -> &'static str {
+    match pr.and_then(|pr| pr.checks.as_deref()) {
+        Some("SUCCESS") => "#1f883d",
+        Some("FAILURE") | Some("ERROR") => "#cf222e",
+        Some(_) => "#bf8700",
+        None => "#afb8c1",
+    }
+}
+
+fn state(pr: &PrSummary) -> &'static str {
+    match pr.state {
+        PrState::Open if pr.is_draft => "draft",
+        PrState::Open => "open",
+        PrState::Closed => "closed",
+        PrState::Merged => "merged",
+    }
+}
+
+/// Escape text for HTML and SVG
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, parent: &str, depth: usize, pr: Option<PrSummary>) -> Node {
+        Node { name: name.to_string(), parent: parent.to_string(), depth, current: false, needs_restack: false, pr }
+    }
+
+    #[test]
+    fn test_page() {
+        let pr = PrSummary {
+            number: 7,
+            state: PrState::Open,
+            is_draft: false,
+            url: "https://github.com/o/r/pull/7".to_string(),
+            base: "main".to_string(),
+            mergeable: "MERGEABLE".to_string(),
+            checks: Some("FAILURE".to_string()),
+        };
+        let mut ui = node("feature/<ui>", "api", 1, None);
+        ui.needs_restack = true;
+        let nodes = [node("api", "main", 0, Some(pr)), ui];
+
+        let svg = svg("main", &nodes);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"<a href="https://github.com/o/r/pull/7">"#));
+        assert!(svg.contains("feature/&lt;ui&gt;"));
+        assert!(svg.contains("stroke-dasharray"));
+        assert!(svg.contains("#cf222e"));
+
+        let page = page("main", &nodes, "2026-10-16 09:30");
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<title>Stack on main</title>"));
+        assert!(page.contains(r#"<a href="https://github.com/o/r/pull/7">#7</a> open"#));
+        assert!(page.contains("<td>failure</td>"));
+        assert!(!page.contains("<script"));
+    }
+}
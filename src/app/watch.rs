@@ -0,0 +1,171 @@
+use anyhow::Result;
+use chrono::Local;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::{config, gh, ui, ui::ColorizeExt};
+
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// What we know about one of the user's pull requests at a point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Snapshot {
+    title: String,
+    url: String,
+    /// (review id, reviewer, state)
+    reviews: Vec<(u64, String, String)>,
+    failed_checks: Vec<String>,
+    conflicted: bool,
+}
+
+pub struct WatchOptions {
+    /// Seconds between polls, overrides watch.interval
+    pub interval: Option<u64>,
+    /// Also show desktop notifications, on top of watch.desktop
+    pub desktop: bool,
+}
+
+/// watch polls the user's open pull requests and announces reviews, failing checks and conflicts
+pub async fn watch(opts: &WatchOptions) -> Result<()> {
+    let interval = opts
+        .interval
+        .or_else(|| config::get("watch.interval").and_then(|value| value.trim().parse().ok()))
+        .unwrap_or(DEFAULT_INTERVAL_SECS)
+        .max(10);
+    let desktop = opts.desktop || config::get_bool("watch.desktop", false);
+
+    println!(
+        "👀 Watching your open pull requests every {}s{}. Press Ctrl-C to stop.",
+        interval,
+        if desktop { " with desktop notifications" } else { "" }
+    );
+
+    // The first poll only records the current state, so we don't replay old news
+    let mut known = poll().await?;
+    println!("{}", format!("Tracking {} pull request(s)", known.len()).gray());
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        let current = match poll().await {
+            Ok(current) => current,
+            Err(e) => {
+                println!("{} {}", "WARNING:".yellow(), e);
+                continue;
+            }
+        };
+
+        for (key, snapshot) in &current {
+            let Some(previous) = known.get(key) else { continue };
+            for message in changes(previous, snapshot) {
+                announce(key, snapshot, &message, desktop);
+            }
+        }
+
+        known = current;
+    }
+}
+
+async fn poll() -> Result<HashMap<String, Snapshot>> {
+    let mut snapshots = HashMap::new();
+
+    for item in gh::search::search_issues("is:pr is:open author:@me archived:false").await? {
+        let Some((owner, repo)) = item.owner_repo() else { continue };
+        let pull = gh::pulls::get_pull_request(&owner, &repo, item.number).await?;
+        let reviews = gh::pulls::list_reviews(&owner, &repo, item.number).await?;
+        let runs = gh::checks::check_runs(&owner, &repo, &pull.head.sha).await?;
+
+        snapshots.insert(
+            format!("{}/{}#{}", owner, repo, item.number),
+            Snapshot {
+                title: item.title.clone(),
+                url: item.html_url.clone(),
+                reviews: reviews
+                    .into_iter()
+                    .map(|review| (review.id, review.user.map(|user| user.login).unwrap_or_default(), review.state))
+                    .collect(),
+                failed_checks: runs.into_iter().filter(|run| run.is_failed()).map(|run| run.name).collect(),
+                conflicted: pull.mergeable == Some(false),
+            },
+        );
+    }
+
+    Ok(snapshots)
+}
+
+/// Describe what changed between two snapshots of the same pull request
+fn changes(previous: &Snapshot, current: &Snapshot) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    for (id, reviewer, state) in &current.reviews {
+        if previous.reviews.iter().any(|(known, _, _)| known == id) {
+            continue;
+        }
+        let verb = match state.as_str() {
+            "APPROVED" => "approved",
+            "CHANGES_REQUESTED" => "requested changes",
+            _ => "reviewed",
+        };
+        messages.push(format!("{} {}", reviewer, verb));
+    }
+
+    let newly_failed: Vec<&str> = current
+        .failed_checks
+        .iter()
+        .filter(|check| !previous.failed_checks.contains(check))
+        .map(String::as_str)
+        .collect();
+    if !newly_failed.is_empty() {
+        messages.push(format!("checks failed: {}", newly_failed.join(", ")));
+    }
+
+    if current.conflicted && !previous.conflicted {
+        messages.push("has merge conflicts".to_string());
+    }
+
+    messages
+}
+
+fn announce(key: &str, snapshot: &Snapshot, message: &str, desktop: bool) {
+    println!(
+        "{} {} {} {}\n  {}",
+        format!("[{}]", Local::now().format("%H:%M")).gray(),
+        key.sage().bold(),
+        message.yellow(),
+        snapshot.title,
+        snapshot.url.url()
+    );
+
+    if desktop {
+        ui::notify(&format!("{} {}", key, message), &snapshot.title);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let previous = Snapshot {
+            reviews: vec![(1, "alice".to_string(), "COMMENTED".to_string())],
+            failed_checks: vec!["lint".to_string()],
+            ..Default::default()
+        };
+        let current = Snapshot {
+            reviews: vec![
+                (1, "alice".to_string(), "COMMENTED".to_string()),
+                (2, "bob".to_string(), "APPROVED".to_string()),
+            ],
+            failed_checks: vec!["lint".to_string(), "test".to_string()],
+            conflicted: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            changes(&previous, &current),
+            vec!["bob approved", "checks failed: test", "has merge conflicts"]
+        );
+        assert!(changes(&current, &current).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::{app::dco, config, errors, git, ui::ColorizeExt};
+
+/// Message of a `sage wip` commit without one of its own
+const DEFAULT_MESSAGE: &str = "work in progress";
+
+/// wip commits everything, untracked files included, as a `wip:` commit to pick up later with
+/// `sage unwip`. Commit hooks are skipped unless wip.skip_hooks is false.
+pub fn wip(message: Option<&str>) -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    if git::status::is_clean()? {
+        return Err(errors::GitError::NoChanges.into());
+    }
+
+    let message = format!("wip: {}", message.map(str::trim).filter(|m| !m.is_empty()).unwrap_or(DEFAULT_MESSAGE));
+    git::commit::commit_all(&message, config::get_bool("wip.skip_hooks", true))?;
+
+    println!("✨ Saved everything as {}", message.sage());
+    println!("Pick it back up with {}. It won't be pushed until then", "sage unwip".sage());
+    Ok(())
+}
+
+/// unwip takes the `wip:` commits at the tip of the branch off again, leaving their changes in
+/// the working tree
+pub fn unwip() -> Result<()> {
+    // Check to ensure we are in a repo first.
+    if !git::repo::is_repo()? {
+        return Err(errors::GitError::NotARepository.into());
+    }
+
+    let (count, has_parent) = git::commit::wip_at_tip("HEAD")?;
+    if count == 0 {
+        return Err(anyhow!("The last commit isn't a WIP commit, so there's nothing to unwip"));
+    }
+    // Without a parent there's nothing to reset to
+    if !has_parent {
+        return Err(anyhow!("Every commit on this branch is a WIP commit, so there's nothing to go back to"));
+    }
+    // Taking back commits others may have pulled rewrites history they already have
+    if git::repo::is_pushed(&format!("HEAD~{}", count - 1))? {
+        println!(
+            "{} These WIP commits were already pushed. Pushing the branch again will need {}",
+            "WARNING:".yellow(),
+            "sage push --force".yellow()
+        );
+    }
+
+    git::commit::uncommit(count)?;
+    println!("✨ Put {} WIP commit(s) back into the working tree", count);
+    Ok(())
+}
+
+/// check_before_push stops pushing WIP commits, unless `allow` says to
+pub fn check_before_push(branch: &str, allow: bool) -> Result<()> {
+    if allow {
+        return Ok(());
+    }
+
+    let outgoing = outgoing(branch)?;
+    if outgoing.is_empty() {
+        return Ok(());
+    }
+
+    println!("{}", "These commits are still work in progress:".red().bold());
+    for (hash, subject) in &outgoing {
+        println!("  {} {}", hash.bright_yellow(), subject);
+    }
+    println!("\nRun {} to carry on with them, or push with {} anyway.", "sage unwip".sage(), "--allow-wip".yellow());
+    Err(anyhow!("{} has {} WIP commit(s) to push", branch, outgoing.len()))
+}
+
+/// outgoing lists the WIP commits on `branch` that would be pushed
+pub fn outgoing(branch: &str) -> Result<Vec<(String, String)>> {
+    let base = dco::outgoing_base(branch)?;
+    Ok(git::commit::subjects(&format!("{}..{}", base, branch))?
+        .into_iter()
+        .filter(|(_, subject)| git::commit::is_wip(subject))
+        .collect())
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app;
+
+/// See how sage talks to the AI
+#[derive(Parser, Debug)]
+pub struct AiArgs {
+    #[clap(subcommand)]
+    pub command: AiCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AiCommands {
+    /// The prompts sage sends the AI
+    Prompts(AiPromptsArgs),
+    /// Build or refresh the local index that gives the AI related files as context
+    Index(AiIndexArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct AiIndexArgs {
+    /// Remove the index instead, so the AI no longer gets related files
+    #[clap(long)]
+    pub clear: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct AiPromptsArgs {
+    #[clap(subcommand)]
+    pub command: AiPromptsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AiPromptsCommands {
+    /// Show the prompt sage uses, this repository's override or the built-in
+    Show(AiPromptsShowArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct AiPromptsShowArgs {
+    /// Which prompt: commit, pr, review or explain
+    pub name: String,
+}
+
+impl Run for AiArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            AiCommands::Prompts(AiPromptsArgs { command: AiPromptsCommands::Show(args) }) => app::prompts::show(&args.name),
+            AiCommands::Index(args) => app::context::index(args.clear),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app;
+
+/// Manage the GitHub token sage uses
+#[derive(Parser, Debug)]
+#[clap(after_help = "Sage looks for a token in the order set by auth.sources (default env,keychain,gh):
+the SAGE_GITHUB_TOKEN or GITHUB_TOKEN environment variables, the OS keychain, then the gh CLI.")]
+pub struct AuthArgs {
+    #[clap(subcommand)]
+    pub command: AuthCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommands {
+    /// Save a GitHub token in the OS keychain
+    Login(AuthLoginArgs),
+    /// Show which token sage uses and who it belongs to
+    Status,
+    /// Remove the GitHub token from the OS keychain
+    Logout,
+}
+
+#[derive(Parser, Debug)]
+pub struct AuthLoginArgs {
+    /// Read the token from stdin instead of prompting for it
+    #[clap(long, conflicts_with = "web")]
+    pub with_token: bool,
+
+    /// Log in through the browser instead of pasting a token
+    #[clap(long)]
+    pub web: bool,
+}
+
+impl Run for AuthArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            AuthCommands::Login(args) if args.web => app::auth::login_web().await,
+            AuthCommands::Login(args) => app::auth::login(args.with_token).await,
+            AuthCommands::Status => app::auth::status().await,
+            AuthCommands::Logout => app::auth::logout(),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct BugReportArgs {
+    /// Where to write the archive (defaults to sage-bug-report-<time>.tar.gz here)
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Create it without asking
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+impl Run for BugReportArgs {
+    async fn run(&self) -> Result<()> {
+        app::bug_report::bug_report(self.output.as_deref(), self.yes)
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct CheckpointArgs {
+    /// What the checkpoint is for, shown when listing them
+    #[clap(short, long)]
+    pub message: Option<String>,
+
+    /// List checkpoints instead of taking one
+    #[clap(long, conflicts_with = "message")]
+    pub list: bool,
+}
+
+impl Run for CheckpointArgs {
+    async fn run(&self) -> Result<()> {
+        app::checkpoint::checkpoint(self.message.as_deref(), self.list)
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct RollbackArgs {
+    /// Checkpoint id, or a time to go back to: 30m, 2h, 14:30, 2024-05-01 14:30
+    #[clap(long)]
+    pub to: Option<String>,
+
+    /// Don't ask for confirmation
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+impl Run for RollbackArgs {
+    async fn run(&self) -> Result<()> {
+        app::checkpoint::rollback(self.to.as_deref(), self.yes)
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app::{self, ci::LogsOptions};
+
+/// Work with GitHub Actions for the current branch
+#[derive(Parser, Debug)]
+pub struct CiArgs {
+    #[clap(subcommand)]
+    pub command: CiCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CiCommands {
+    /// Re-run the workflow runs for the current branch
+    #[clap(long_about = "Re-runs the latest run of every workflow for the pushed head of the current branch,
+or of a pull request with --pr. Runs that are still in progress are skipped.
+
+EXAMPLES:
+  sage ci rerun
+  sage ci rerun --failed-only
+  sage ci rerun --pr 42")]
+    Rerun(CiRerunArgs),
+
+    /// Trigger a workflow with a workflow_dispatch trigger
+    #[clap(long_about = "Triggers a workflow through its workflow_dispatch trigger. When the workflow file is
+found in .github/workflows, sage prompts for each of its inputs that wasn't given with -f.
+
+EXAMPLES:
+  sage ci run deploy
+  sage ci run deploy.yml -f environment=staging
+  sage ci run release --ref main")]
+    Run(CiRunArgs),
+
+    /// Show the log of a check for the current branch
+    #[clap(long_about = "Lists the GitHub Actions checks for the pushed head of the current branch (or a pull
+request with --pr), lets you pick one, and shows its log with colors preserved. Pass part of a
+check's name to skip the picker. With --follow, sage keeps polling until the job finishes.
+
+EXAMPLES:
+  sage ci logs
+  sage ci logs clippy
+  sage ci logs test --follow")]
+    Logs(CiLogsArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct CiRerunArgs {
+    /// Only re-run failed jobs of failed runs
+    #[clap(long)]
+    pub failed_only: bool,
+
+    /// Use the head of this pull request instead of the current branch
+    #[clap(long)]
+    pub pr: Option<u64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct CiRunArgs {
+    /// Workflow file name, with or without the .yml extension
+    pub workflow: String,
+
+    /// Branch or tag to run the workflow on (defaults to the current branch)
+    #[clap(long = "ref")]
+    pub git_ref: Option<String>,
+
+    /// Workflow input as key=value, can be repeated
+    #[clap(short = 'f', long = "field")]
+    pub fields: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct CiLogsArgs {
+    /// Name, or part of the name, of the check
+    pub check: Option<String>,
+
+    /// Use the head of this pull request instead of the current branch
+    #[clap(long)]
+    pub pr: Option<u64>,
+
+    /// Keep streaming new output until the job finishes
+    #[clap(short, long)]
+    pub follow: bool,
+
+    /// Keep the timestamp at the start of every line
+    #[clap(long)]
+    pub timestamps: bool,
+}
+
+impl Run for CiArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            CiCommands::Rerun(args) => app::ci::rerun(args.failed_only, args.pr).await,
+            CiCommands::Run(args) => app::ci::run(&args.workflow, args.git_ref.clone(), &args.fields).await,
+            CiCommands::Logs(args) => app::ci::logs(&LogsOptions {
+                check: args.check.clone(),
+                pr: args.pr,
+                follow: args.follow,
+                timestamps: args.timestamps,
+            })
+            .await,
+        }
+    }
+}
@@ -1,15 +1,63 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 use crate::app;
 
 use super::Run;
 
 #[derive(Parser, Debug)]
-pub struct CleanArgs {}
+pub struct CleanArgs {
+    /// Clean the branches the clean policy picks (see clean.merged_days and clean.stale_days)
+    #[clap(long)]
+    pub policy: bool,
+
+    /// Keep the branches under refs/sage/archive/ instead of deleting them outright
+    #[clap(long, requires = "policy")]
+    pub archive: bool,
+
+    /// Don't ask before cleaning. With --untracked there's no picker either, so every untracked
+    /// file is deleted
+    #[clap(short, long)]
+    pub yes: bool,
+
+    /// Pick untracked files to keep and delete the rest, instead of cleaning branches
+    #[clap(long, conflicts_with_all = ["policy", "keep", "unkeep"])]
+    pub untracked: bool,
+
+    /// With --untracked, offer ignored files too, like build output
+    #[clap(long, requires = "untracked")]
+    pub ignored: bool,
+
+    /// Mark branches for sage clean to leave alone, instead of cleaning
+    #[clap(long, num_args = 1.., value_name = "BRANCH", conflicts_with_all = ["policy", "unkeep"])]
+    pub keep: Vec<String>,
+
+    /// Take the mark from branches marked with --keep
+    #[clap(long, num_args = 1.., value_name = "BRANCH", conflicts_with = "policy")]
+    pub unkeep: Vec<String>,
+
+    /// Print a report instead of the usual output. Without --yes nothing is changed.
+    #[clap(long, value_enum, requires = "policy")]
+    pub report: Option<ReportFormat>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Json,
+}
 
 impl Run for CleanArgs {
     async fn run(&self) -> Result<()> {
-        app::clean::clean().await
+        if !self.keep.is_empty() || !self.unkeep.is_empty() {
+            return app::clean::keep(if self.keep.is_empty() { &self.unkeep } else { &self.keep }, !self.keep.is_empty());
+        }
+        if self.untracked {
+            return app::clean::untracked(self.ignored, self.yes);
+        }
+        if self.policy {
+            let json = matches!(self.report, Some(ReportFormat::Json));
+            return app::clean::policy_clean(self.archive, self.yes, json).await;
+        }
+        app::clean::clean(self.yes).await
     }
-}
\ No newline at end of file
+}
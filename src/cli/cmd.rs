@@ -1,19 +1,112 @@
+use crate::cli::ai;
+use crate::cli::auth;
+use crate::cli::bug_report;
+use crate::cli::checkpoint;
+use crate::cli::ci;
 use crate::cli::clean;
 use crate::cli::clone;
 use crate::cli::commit;
 use crate::cli::completion;
+use crate::cli::config;
+use crate::cli::diff;
+use crate::cli::doctor;
+use crate::cli::explain;
+use crate::cli::features;
+use crate::cli::fix_dco;
+use crate::cli::fixup_ci;
+use crate::cli::gc;
+use crate::cli::grep;
 use crate::cli::history;
+use crate::cli::hooks;
+use crate::cli::identity;
+use crate::cli::ignore;
+use crate::cli::inbox;
+use crate::cli::intent;
+use crate::cli::keys;
+use crate::cli::lfs;
 use crate::cli::list;
+use crate::cli::mv;
+use crate::cli::note;
+use crate::cli::open;
+use crate::cli::plugin;
+use crate::cli::policy;
+use crate::cli::profile;
 use crate::cli::pr;
+use crate::cli::purge;
+use crate::cli::redo;
 use crate::cli::push;
+use crate::cli::remote;
+use crate::cli::resume;
+use crate::cli::review;
+use crate::cli::rm;
+use crate::cli::self_update;
+use crate::cli::send_email;
+use crate::cli::session;
+use crate::cli::snapshot;
+use crate::cli::stack;
 use crate::cli::start;
+use crate::cli::stats;
+use crate::cli::verify;
+use crate::cli::wip;
+use crate::cli::unwip;
+use crate::cli::contribute;
+use crate::cli::rename;
+use crate::cli::restack;
 use crate::cli::status;
 use crate::cli::switch;
 use crate::cli::sync;
+use crate::cli::todos;
+use crate::cli::undo;
+use crate::cli::watch;
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
+/// sage's command line: a command, and the options every command takes
 #[derive(Parser, Debug)]
+pub struct Cli {
+    /// Wait for another sage changing this repository to finish, rather than failing
+    #[clap(long, global = true, long_help = "Every command that changes something takes a lock on
+    the repository (.git/sage.lock) so two sage processes can't interleave. When another sage
+    holds it, sage stops and says which one; with --wait it waits for it to finish instead.")]
+    pub wait: bool,
+
+    /// Time the command and show where the time went
+    // Its own id, so it doesn't clash with the <PROFILE> that sage identity takes
+    #[clap(long = "profile", id = "timing_profile", global = true, long_help = "Records how long the command spends in git, network requests, AI and
+    rendering its output, then prints a breakdown with the slowest calls to stderr. The whole run is
+    saved as a trace under .git/sage/profiles for chrome://tracing or ui.perfetto.dev.")]
+    pub profile: bool,
+
+    /// Print long output straight to the terminal instead of through a pager
+    #[clap(long, global = true, long_help = "History, diffs and CI logs longer than the screen are shown through a
+    pager, picked like git picks one: GIT_PAGER, core.pager, PAGER, then less. --no-pager prints
+    them straight to the terminal instead, as does setting the pager to cat.")]
+    pub no_pager: bool,
+
+    /// Write progress events for tools that wrap sage
+    #[clap(long, global = true, value_name = "FORMAT", long_help = "Writes a JSON object per line as the command runs: command_started and
+    command_finished, phase_started and phase_finished for each step of the work, action_applied
+    for every branch changed and conflict when a rebase stops on conflicts. Events go to
+    --events-fd, and everything else sage prints goes to stderr.")]
+    pub events: Option<EventFormat>,
+
+    /// File descriptor to write --events to
+    #[clap(long, global = true, value_name = "FD", default_value_t = 1, requires = "events", long_help = "File descriptor to write
+    --events to, stdout unless set. Open it for sage first, e.g. 'sage sync --events ndjson
+    --events-fd 3 3>events.ndjson'.")]
+    pub events_fd: i32,
+
+    #[clap(subcommand)]
+    pub cmd: Cmd,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum EventFormat {
+    /// Newline-delimited JSON
+    Ndjson,
+}
+
+#[derive(Subcommand, Debug)]
 pub enum Cmd {
     /// Start a new feature branch
     #[clap(
@@ -32,9 +125,14 @@ from the remote repository. This command performs several git operations automat
 This workflow ensures your new branch starts from the latest version of the default branch,
 preventing future merge conflicts and keeping your feature branch up-to-date.
 
+With --orphan the branch starts with no history at all instead, for things like gh-pages or
+docs: an empty first commit (or a README with --readme), recorded as the root of a stack of its
+own, so 'sage start --parent' can build on it.
+
 EXAMPLES:
   sage start new-feature
-  sage start bugfix/issue-123 --parent release/v2.0"
+  sage start bugfix/issue-123 --parent release/v2.0
+  sage start gh-pages --orphan --readme"
     )]
     Start(start::StartArgs),
 
@@ -54,13 +152,22 @@ When used with the --ai flag, it analyzes your changes and generates a descripti
 commit message following the Conventional Commits specification, which helps maintain
 a standardized commit history.
 
+Repositories using the pre-commit framework or husky have their pre-commit hooks run
+before the message is written, and files the hooks fix are staged, so the message
+describes what is actually committed. Turn this off with hooks.pre_commit or hooks.husky.
+
 The --empty flag allows creating commits with no changes, which can be useful for
-triggering CI/CD pipelines or marking specific points in history.
+triggering CI/CD pipelines or marking specific points in history. --retry-empty does the
+same with the message from commit.empty_message. Empty commits are stopped when nothing is
+staged but you have other changes, since you probably forgot to stage them; set
+commit.empty_guard to warn or off to change that.
 
 EXAMPLES:
   sage commit \"fix: resolve login issue\"
   sage commit \"update documentation\" --push
   sage commit \"empty commit for CI trigger\" --empty
+  sage commit --retry-empty --push
+  sage commit --reuse
   sage commit \"initial commit\" --ai"
     )]
     Commit(commit::Commit),
@@ -102,9 +209,15 @@ This command gives you a complete picture of your repository's state in a well-f
 easy-to-read display that helps you understand exactly what changes exist and where they are
 in the git workflow (staged, unstaged, or untracked).
 
+--legend explains every symbol sage prints, as the current theme shows them. Set ui.theme to
+colorblind for check marks that differ in shape, colored blue and orange rather than green and
+red.
+
 EXAMPLES:
   sage status
-  sage s"
+  sage s
+  sage status --legend
+  sage config set ui.theme colorblind"
     )]
     Status(status::StatusArgs),
 
@@ -127,10 +240,22 @@ When used with the --force flag, it performs a force push, which can overwrite r
 history. This should be used with caution, but is useful in specific scenarios like
 updating a feature branch after rebasing.
 
+Before pushing, sage checks it can authenticate with origin (set push.probe to false to skip
+this). When it can't, it explains whether the SSH key, ssh-agent (including agent forwarding)
+or HTTPS credentials are the problem, and offers to switch origin to the other protocol.
+
+With --stack, every branch in the current stack is pushed at the same time, with a line per
+branch showing whether it went through and how long it took.
+
+WIP commits (made with 'sage wip', or any starting with 'wip:') aren't pushed: run 'sage unwip'
+first, or pass --allow-wip to push them anyway.
+
 EXAMPLES:
-  sage push              # Push current branch to remote
-  sage push --force      # Force push current branch to remote
-  sage p                 # Using the alias"
+  sage push                    # Push current branch to remote
+  sage push --force            # Force push current branch to remote
+  sage push --stack            # Push every branch in the stack at once
+  sage push --switch-protocol  # Move origin between SSH and HTTPS if its credentials fail
+  sage p                       # Using the alias"
     )]
     Push(push::PushArgs),
 
@@ -174,13 +299,31 @@ EXAMPLES:
    - Branches behind remote: magenta
    - Diverged branches (both ahead and behind): yellow
    - Other branches: blue
+7. With --prs, shows the pull request for each branch, with its state and check results,
+   fetched for all branches in a single GitHub request (set list.prs to always show them, and
+   --no-prs to skip them then)
+8. Shows how long ago each branch was last committed to and forked from the default branch,
+   green for the last week, yellow after that and red once stale
 
 This command provides a quick overview of all your branches and their synchronization status
 with remote branches, helping you understand which branches need attention (pushing, pulling,
 or resolving divergence).
 
+Branches are printed as they're worked out. In repositories with thousands of branches, --limit
+and --page show one page at a time, and only that page's branches are looked at. Ahead and
+behind counts are kept in .git/sage/branch-index.json and only counted again for branches that
+moved since, so later listings are quicker.
+
+--stale lists only the branches without commits in a number of days, by default the
+clean.stale_days that 'sage clean --policy' goes by (30 when it's not set).
+
 EXAMPLES:
   sage list
+  sage list --prs
+  sage list --stale
+  sage list --stale 90
+  sage list --limit 20
+  sage list --page 2
   sage l"
     )]
     List(list::ListArgs),
@@ -230,7 +373,9 @@ EXAMPLES:
 
 1. Checkout pull requests locally for review and testing
 2. View detailed information about pull requests including status, description, and CI checks
-3. Seamlessly integrate GitHub workflows into your local development process
+3. Comment on pull requests and edit their title, description and labels
+4. Check how big a pull request would be and split it into a stack when it's too big
+5. Seamlessly integrate GitHub workflows into your local development process
 
 The PR commands automatically handle authentication with GitHub using either a SAGE_GITHUB_TOKEN
 environment variable or the GitHub CLI if installed. They provide a streamlined interface for
@@ -240,7 +385,10 @@ EXAMPLES:
   sage pr checkout 123                  # Checkout PR #123 to a local branch
   sage pr checkout 123 feature/test     # Checkout PR #123 to a specific branch name
   sage pr status                        # Show status of PR associated with current branch
-  sage pr status 456                    # Show status of PR #456"
+  sage pr status 456                    # Show status of PR #456
+  sage pr comment \"Ready for review\"    # Comment on the current branch's PR
+  sage pr edit --add-label bug          # Label the current branch's PR
+  sage pr size --plan                   # Split a large branch into stacked branches"
     )]
     Pr(pr::PrArgs),
 
@@ -261,19 +409,991 @@ This command performs several git operations automatically:
 8. Restores any stashed changes
 9. Pushes your updated branch to the remote
 
+sync.strategy picks how the default branch is brought in instead: rebase, merge or ff-only (which
+stops when the branch has commits of its own). A configured strategy isn't swapped for another
+when it runs into conflicts. Without sync.strategy, git's pull.rebase and pull.ff are followed
+when they're set. sync.push is auto, never or ask, for whether a branch that's ahead is pushed.
+
 This workflow ensures your branch stays up-to-date with the latest changes from the default branch,
 reducing the likelihood of complex merge conflicts later. It's particularly useful for long-lived
 feature branches that need to incorporate ongoing changes from the main codebase.
 
+With --if-stale it only syncs when there may be something to sync: the branch is behind the
+default branch as fetched or its stack parent, or the last look at the remote is older than
+sync.stale_minutes (60 by default) and the default branch, the branch's upstream or its parent's
+upstream has moved there since. Looking is a quick ls-remote, and nothing happens when the remote
+can't be reached, so it's safe to run whenever a terminal opens.
+
+--upstream is for contributing from a fork, with origin your fork and an upstream remote for
+the repository you forked: the default branch is fast-forwarded to upstream's and pushed to
+origin, then your branch is rebased onto it (or brought up to date as sync.strategy says).
+
 EXAMPLES:
-  sage sync"
+  sage sync
+  sage sync --if-stale
+  sage sync --upstream"
     )]
     Sync(sync::SyncArgs),
 
     /// Cleans up all dead branches
+    #[clap(
+        long_about = "Deletes the branches that are done with: merged into the default branch, with a closed or
+merged pull request, or whose upstream is gone. The current and default branches are never
+touched, and 'sage undo' brings back what was deleted.
+
+With --policy, the clean policy picks the branches instead:
+  - clean.merged_days: merged branches whose last commit is at least this many days old (default 30)
+  - clean.stale_days: branches without an open pull request and no commits in this many days
+    (default never)
+Branches the org policy protects are left alone, and so are branches nothing was ever committed
+to, unless their pull request was merged. With --archive (or clean.archive set), each
+branch is kept under refs/sage/archive/ rather than deleted for good.
+
+For a scheduled job, --report json prints only a JSON report of what was picked and done. It's
+a dry run unless --yes is given too.
+
+Either way, some branches are always left alone:
+  - clean.protect: comma-separated globs like release/*,wip/*
+  - clean.min_age_days: branches with a commit in the last this many days
+  - branches marked with 'sage clean --keep <branch>' (take the mark off with --unkeep)
+
+--untracked tidies the working tree instead: it lists the untracked files, with their sizes, for
+you to pick the ones to keep (and add them to .gitignore if you like) and deletes the rest.
+--ignored offers ignored files, like build output, as well. With --yes nothing is asked, not even
+which to keep, so every one of them is deleted. Directories holding a git repository of their own
+are never deleted. Deleted files can't be brought back.
+
+EXAMPLES:
+  sage clean
+  sage clean --untracked --ignored
+  sage config set --local clean.protect 'release/*,wip/*'
+  sage clean --keep experiment
+  sage clean --policy --archive
+  sage config set clean.stale_days 90
+  sage clean --policy --report json
+  sage clean --policy --yes --report json"
+    )]
     Clean(clean::CleanArgs),
 
     /// History of commits
     #[clap(alias = "h")]
     History(history::History),
+
+    /// Show changes against the stack parent, default branch, or PR base
+    #[clap(
+        alias = "d",
+        long_about = "Shows a diff that understands sage concepts. By default it shows your uncommitted changes,
+but it can also compare your branch against:
+
+1. Its stack parent (--vs-parent), the branch it was started from with 'sage start --parent'
+2. Its merge-base with the default branch (--vs-default)
+3. The base branch of its pull request on GitHub (--pr)
+
+Branch comparisons use the merge-base, so only the changes introduced on your branch are shown.
+Use --patch to print a plain patch that can be piped into other tools.
+
+EXAMPLES:
+  sage diff                       # Uncommitted changes
+  sage diff --vs-default          # Everything this branch introduces
+  sage diff --vs-parent --stat    # Summary of changes on top of the parent branch
+  sage diff --pr --patch > pr.patch"
+    )]
+    Diff(diff::DiffArgs),
+
+    /// Work with stacks of dependent branches
+    #[clap(
+        long_about = "Commands for managing stacks of branches that build on top of each other.
+Branches become part of a stack when they are started with 'sage start --parent <branch>'.
+
+EXAMPLES:
+  sage stack                       # Show the stack with PR and check status (or sage stack status)
+  sage stack --json                # The same as JSON, for scripts
+  sage stack export                # Export the current stack as a patch series
+  sage stack apply ./sage-stack-x  # Re-create an exported stack in this clone"
+    )]
+    Stack(stack::StackArgs),
+
+    /// Send the current branch or stack to a mailing list as patches
+    #[clap(
+        long_about = "Formats the commits on your branch as a patch series and sends them with 'git send-email'.
+This command:
+
+1. Works out the series from the branch's stack parent (or the default branch)
+2. Formats every commit as a patch, adding a cover letter when there's more than one
+3. Fills in the cover letter, either by prompting you or with AI (--ai)
+4. Hands the series to 'git send-email' using your sendemail.* configuration
+
+With --stack the series covers the whole stack below the current branch, not just its own commits.
+
+EXAMPLES:
+  sage send-email --to list@example.org
+  sage send-email --to list@example.org --cc maintainer@example.org --ai
+  sage send-email --stack --to list@example.org --dry-run"
+    )]
+    SendEmail(send_email::SendEmailArgs),
+
+    /// Read and change sage settings
+    #[clap(
+        long_about = "Reads and writes sage settings. Settings are global by default; use --local to store
+a value for the current repository only, which takes precedence over the global value.
+
+EXAMPLES:
+  sage config list
+  sage config get commit.signoff
+  sage config set commit.signoff true
+  sage config set --local commit.signoff false
+  sage config unset commit.signoff"
+    )]
+    Config(config::ConfigArgs),
+
+    /// Add missing Signed-off-by trailers to the commits on this branch
+    #[clap(
+        long_about = "Rewrites the commits on the current branch so every one of them carries a Signed-off-by
+trailer, as required by projects that use the Developer Certificate of Origin (DCO).
+
+Only commits since the branch diverged from its parent (or the default branch) are rewritten.
+When commit.signoff is enabled, sage also signs off its own commits and refuses to push
+commits that are missing the trailer.
+
+EXAMPLES:
+  sage fix-dco
+  sage push --force"
+    )]
+    FixDco(fix_dco::FixDcoArgs),
+
+    /// Manage commit identity profiles
+    #[clap(
+        long_about = "Manages named commit identities (name, email and optional signing key), such as one
+for work and one for personal projects.
+
+A profile is used for a repository when it has been selected there with 'sage identity use',
+or when the origin remote belongs to one of the profile's orgs. 'sage commit' warns when the
+configured git email does not match the identity the repository expects.
+
+EXAMPLES:
+  sage identity add work --name \"Jane Doe\" --email jane@corp.com --orgs corp,corp-labs
+  sage identity add personal --name \"Jane Doe\" --email jane@hey.com --signing-key ABCD1234
+  sage identity use work
+  sage identity list
+  sage identity check"
+    )]
+    Identity(identity::IdentityArgs),
+
+    /// Add ignore patterns and explain why files are ignored
+    #[clap(
+        long_about = "Manages .gitignore without hand-editing it: add patterns (deduplicated and optionally
+grouped into sections), untrack committed files that a new pattern should have covered, and
+find out which rule is responsible for ignoring a path.
+
+EXAMPLES:
+  sage ignore add '*.log' --section Logs
+  sage ignore add .env --untrack
+  sage ignore check build/output.txt"
+    )]
+    Ignore(ignore::IgnoreArgs),
+
+    /// Move or rename a file, keeping its history
+    #[clap(
+        long_about = "Moves or renames a file or directory and stages the rename, so git tracks it as a
+rename rather than a delete and an add.
+
+When branches stacked on top of the current one also modify the file, sage warns that
+restacking them will need to carry the rename, and offers to commit it and restack them
+straight away.
+
+EXAMPLES:
+  sage mv src/util.rs src/helpers.rs
+  sage mv docs documentation"
+    )]
+    Mv(mv::MvArgs),
+
+    /// Remove files and stage the deletion
+    #[clap(
+        long_about = "Deletes files or directories and stages the deletion. With --cached the files are only
+untracked and stay on disk.
+
+When branches stacked on top of the current one also modify the files, sage warns that
+restacking them will conflict, and offers to commit the removal and restack them straight away.
+
+EXAMPLES:
+  sage rm old_script.sh
+  sage rm --cached config/local.toml"
+    )]
+    Rm(rm::RmArgs),
+
+    /// Search every branch in the current stack
+    #[clap(
+        long_about = "Searches for a pattern in the tip of every branch in the current stack, plus any
+uncommitted changes, and groups the results by branch.
+
+Matches a branch adds on top of its parent are marked with '+', so you can see where in the
+stack a symbol was introduced or changed.
+
+EXAMPLES:
+  sage grep parse_config
+  sage grep -i 'todo' src/
+  sage grep 'fn \\w+_handler'"
+    )]
+    Grep(grep::GrepArgs),
+
+    /// Git LFS helpers
+    #[clap(
+        long_about = "Helpers for repositories that store large files with Git LFS.
+
+Sage also warns when committing files that .gitattributes routes to LFS while git-lfs is
+not installed, and shows the number of LFS files in 'sage status'.
+
+EXAMPLES:
+  sage lfs prune --dry-run
+  sage lfs prune"
+    )]
+    Lfs(lfs::LfsArgs),
+
+    /// Commit and push a quick CI fix in one step
+    #[clap(
+        long_about = "For when CI fails on something trivial like formatting: stages every change, commits it
+as 'style: fix ci' (or a fixup! commit with --fixup), pushes, and makes sure the checks run
+again on the new commit by re-requesting any that GitHub queued without starting.
+
+EXAMPLES:
+  cargo fmt && sage fixup-ci
+  sage fixup-ci -m \"chore: update snapshots\"
+  sage fixup-ci --fixup"
+    )]
+    FixupCi(fixup_ci::FixupCiArgs),
+
+    /// Re-run, trigger and read the logs of GitHub Actions workflows
+    #[clap(
+        long_about = "Works with the GitHub Actions runs for the current branch without opening a browser.
+
+EXAMPLES:
+  sage ci rerun --failed-only
+  sage ci run deploy -f environment=staging
+  sage ci logs --follow"
+    )]
+    Ci(ci::CiArgs),
+
+    /// List pull requests waiting on your review or assigned to you
+    #[clap(
+        long_about = "Lists open pull requests where you are a requested reviewer or an assignee, oldest first,
+with their age, CI state and size. Pick one to open it in the browser, or check it out when
+you are inside a clone of its repository.
+
+By default every repository you can see is searched. Limit it with --repo/--org, or set
+inbox.repos and inbox.orgs (comma-separated) with 'sage config set'.
+
+EXAMPLES:
+  sage inbox
+  sage inbox --org acme
+  sage config set inbox.repos acme/api,acme/web"
+    )]
+    Inbox(inbox::InboxArgs),
+
+    /// Watch your pull requests and get notified about changes
+    #[clap(
+        long_about = "Keeps running and polls your open pull requests, printing a banner (and optionally a
+desktop notification) when a review arrives, a check fails, or a merge conflict appears.
+
+The poll interval and desktop notifications can be set with the watch.interval and
+watch.desktop settings.
+
+EXAMPLES:
+  sage watch
+  sage watch --desktop --interval 120"
+    )]
+    Watch(watch::WatchArgs),
+
+    /// Open the repository, pull request, CI or a file in the browser
+    #[clap(
+        alias = "o",
+        long_about = "Opens the right web page for what you're working on. Without a target it opens the
+current branch's pull request (or the branch when there is none).
+
+Targets:
+  repo                 The repository home page
+  pr                   The current branch's pull request
+  ci                   CI runs for the current branch
+  <path>[:line[-end]]  A file at the current commit, optionally highlighting lines
+
+Works with GitHub and GitLab remotes.
+
+EXAMPLES:
+  sage open
+  sage open ci
+  sage open src/lib.rs:42
+  sage open src/main.rs:10-20 --print"
+    )]
+    Open(open::OpenArgs),
+
+    /// Log in to GitHub and manage the saved token
+    #[clap(
+        long_about = "Saves a GitHub personal access token in the OS keychain (macOS Keychain, Windows Credential
+Manager or the Secret Service on Linux) so it doesn't have to live in an environment variable.
+
+With --web, sage logs in through GitHub's device flow instead: it shows a one-time code,
+opens github.com/login/device, and saves the token once you approve it (requesting the repo
+and read:org scopes). This needs auth.client_id set to a GitHub OAuth app with device flow
+enabled, unless your build of sage comes with one.
+
+The token is checked against GitHub before it is saved. 'sage auth status' shows every place
+sage looks for a token, which one is in use, and the account and scopes it has. Tokens are
+always shown redacted.
+
+EXAMPLES:
+  sage auth login
+  sage auth login --web
+  echo $TOKEN | sage auth login --with-token
+  sage auth status
+  sage auth logout
+  sage config set auth.sources keychain,gh,env   # Prefer the keychain over environment variables"
+    )]
+    Auth(auth::AuthArgs),
+
+    /// Manage remotes with URL checks
+    #[clap(
+        long_about = "Wraps 'git remote' with some safety checks. New URLs must be valid SSH, HTTPS or local
+URLs (and name an owner and repository on a forge), and sage checks they can be reached with
+your credentials straight away.
+
+The primary remote is the one sage uses to find the repository on GitHub or GitLab. It is
+detected once (origin, then upstream, then the first remote) and remembered; 'sage remote
+add --primary' changes it.
+
+'sage remote rewrite' switches every remote between SSH and HTTPS URLs at once.
+
+EXAMPLES:
+  sage remote list
+  sage remote add upstream git@github.com:octo/sage.git --primary
+  sage remote set-url origin https://github.com/me/sage.git
+  sage remote prune
+  sage remote rewrite ssh"
+    )]
+    Remote(remote::RemoteArgs),
+
+    /// Resume an interrupted sync or restack
+    #[clap(
+        long_about = "Picks up the last sync or restack that stopped part way, either because you pressed Ctrl-C
+or because a rebase hit conflicts.
+
+Pressing Ctrl-C during a sync or restack lets sage stop at the next safe point instead of
+leaving your branch mid-rebase. It records what it was doing and prints the git commands to
+back out by hand. Press Ctrl-C a second time to quit immediately.
+
+If a rebase stopped on conflicts, resolve and stage them first; sage continue finishes the
+rebase and carries on with the remaining branches.
+
+EXAMPLES:
+  sage continue"
+    )]
+    Continue(resume::ContinueArgs),
+
+    /// Report on repository size and clean it up
+    #[clap(
+        long_about = "Shows how big the repository is, how many loose objects and packs it has, which
+remote-tracking branches no longer exist on their remote, and the largest files anywhere in
+history, with advice on what is worth cleaning up.
+
+Unless --dry-run is given, it then deletes trashed branch tips older than gc.trash_days
+(default 30), prunes the stale remote-tracking branches, and packs objects with git gc,
+repacking into a single pack with a bitmap if more than one is left.
+
+EXAMPLES:
+  sage gc --dry-run
+  sage gc
+  sage gc --aggressive --top 20"
+    )]
+    Gc(gc::GcArgs),
+
+    /// Remove an accidentally committed file from history
+    #[clap(
+        long_about = "Rewrites the commits of the current stack so a file (a leaked secret, a huge binary) was
+never committed, and keeps it on disk. Commits left empty are dropped, and the old branch tips
+are kept until 'sage gc' removes them.
+
+Only commits on top of the default branch are rewritten. If the file is in the default
+branch's history too, --shared is required, you must type the branch name to confirm, and
+every local branch is rewritten.
+
+Rewritten branches that were pushed can be force pushed (with --force-with-lease). With
+--recreate-prs their open pull requests are closed and opened again, so the old commits are
+no longer linked from the pull request.
+
+If the file held a secret, rotate it: anyone who fetched the old commits still has it.
+
+EXAMPLES:
+  sage purge-file config/.env
+  sage purge-file assets/video.mp4 --push --recreate-prs
+  sage purge-file secrets.json --shared"
+    )]
+    PurgeFile(purge::PurgeFileArgs),
+
+    /// List and test keybindings
+    #[clap(
+        long_about = "Lists the keys bound to each action in sage's interactive screens, and whether they
+come from the defaults or the config. Keys bound to more than one action are flagged.
+
+Change a binding with 'sage config set keys.<action> <keys>', a comma-separated list of keys:
+a single character (q, +, ö), a name (enter, esc, up, down, pageup, space, f1) or either with
+ctrl- or alt- in front. Useful when the defaults clash with your keyboard layout.
+
+'sage keys test' shows which action each key you press is bound to, so you can check what
+your layout actually sends.
+
+EXAMPLES:
+  sage keys
+  sage config set keys.quit x,esc
+  sage config set keys.down down,j
+  sage keys test"
+    )]
+    Keys(keys::KeysArgs),
+
+    /// Bring back branches deleted by sage clean
+    #[clap(
+        long_about = "Recreates the branches removed by the last sage command that deleted any, such as
+'sage clean', at the commits they pointed at. Their place in the stack and their upstream are
+restored too; if the remote branch was deleted as well, push the branch again to recreate it.
+
+Deleted branch tips are kept until 'sage gc' removes them (gc.trash_days, default 30), so undo
+works until then. Branches that have been created again since are left alone.
+
+EXAMPLES:
+  sage clean
+  sage undo"
+    )]
+    Undo(undo::UndoArgs),
+
+    /// Replay what the last sage undo reverted
+    #[clap(
+        long_about = "Deletes the branches the last 'sage undo' brought back again.
+
+Before doing anything it checks every branch still points at the commit undo restored it to.
+If one has moved, been deleted or is checked out, redo explains why and stops, since deleting
+it now could lose work done after the undo. --force deletes the branches that are left anyway;
+their tips are kept, so 'sage undo' can still bring them back.
+
+EXAMPLES:
+  sage undo
+  sage redo
+  sage redo --force"
+    )]
+    Redo(redo::RedoArgs),
+
+    /// Save a snapshot of every branch and your uncommitted changes
+    #[clap(
+        long_about = "Takes a checkpoint: where every local branch points, their place in the stack, the branch
+you're on and any uncommitted changes to tracked files. Nothing in the working tree changes.
+Untracked files aren't included.
+
+Checkpoints are also taken automatically before sync, restack and clean (turn this
+off with checkpoint.auto=false). The latest 50 are kept. List them with --list, and go back to
+one with 'sage rollback'.
+
+EXAMPLES:
+  sage checkpoint
+  sage checkpoint -m 'before trying the big refactor'
+  sage checkpoint --list"
+    )]
+    Checkpoint(checkpoint::CheckpointArgs),
+
+    /// Return branches and the working tree to a checkpoint
+    #[clap(
+        long_about = "Puts every branch back where it was at a checkpoint, recreating any deleted since, checks
+out the branch you were on and restores the uncommitted changes saved with it. Branches created
+after the checkpoint are left alone.
+
+--to takes a checkpoint id from 'sage checkpoint --list', or a time, in which case the latest
+checkpoint taken at or before it is used: 30m or 2h ago, 14:30 today, or 2024-05-01 14:30.
+Without --to the latest checkpoint is used.
+
+Your current state is checkpointed first, so a rollback can be rolled back too. Uncommitted
+changes to tracked files are replaced; untracked files are left as they are.
+
+EXAMPLES:
+  sage rollback --to 30m
+  sage rollback --to 14:30
+  sage rollback --to 1714572600000"
+    )]
+    Rollback(checkpoint::RollbackArgs),
+
+    /// Show what sync, restack and other operations did to your branches
+    #[clap(
+        long_about = "sage records where every local branch points when a sync, restack or branch deletion starts
+and when it stops. 'sage snapshot list' shows those operations with their ids.
+
+'sage snapshot diff <id>' lists the branches the operation created, deleted and moved, with the
+commits each moved branch gained and lost. Given a second id, it compares the start of the
+first operation with the end of the second, covering anything done in between too.
+
+Commits lost along the way can still be shown until git garbage collects them.
+
+EXAMPLES:
+  sage snapshot list
+  sage snapshot diff 12
+  sage snapshot diff 12 15"
+    )]
+    Snapshot(snapshot::SnapshotArgs),
+
+    /// Show which plugins ran on the last hook and what they said
+    #[clap(
+        long_about = "Plugins can run on pre-commit and post-commit around 'sage commit', and on pre-push before
+sage pushes a branch. Each time they do, sage keeps a report of which plugins ran, how long each
+took and its verdict (allow, warn or block) with its message, or why it failed to run.
+
+'sage hooks last' shows the latest report, to find out why a commit or push was blocked. Give
+a hook name to see the latest run of that hook instead. The last 20 reports are kept.
+
+EXAMPLES:
+  sage hooks last
+  sage hooks last pre-push"
+    )]
+    Hooks(hooks::HooksArgs),
+
+    /// Install a plugin, checking its publisher's signature
+    #[clap(
+        long_about = "Installs the plugin in a directory, e.g. one copied onto an air-gapped machine, into sage's
+plugin directory, replacing any copy already installed.
+
+A signed plugin ships a SHA256SUMS file listing every other file in it, signed with minisign as
+SHA256SUMS.minisig. The signature must come from one of the publisher keys in
+plugins.trusted_keys, and every file must match its checksum, or the plugin isn't installed.
+Unsigned plugins are installed unless plugins.require_signed is on, which an org policy can set
+for everyone. 'sage policy sync' checks the plugins it installs the same way.
+
+EXAMPLES:
+  sage config set plugins.trusted_keys RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+  sage plugin install ./secret-scan"
+    )]
+    Plugin(plugin::PluginArgs),
+
+    /// Install your organisation's policy: settings, plugins and branch and commit rules
+    #[clap(
+        long_about = "An organisation can publish a policy bundle: a git repository or .tar.gz with a policy.json
+listing the plugins everyone needs, branches nobody commits or pushes to directly, rules for
+commit messages and settings, with the plugins themselves under plugins/<name>/.
+
+Point policy.source at it and run 'sage policy sync' to fetch it and install its plugins; run
+it again to pick up changes. Tarballs are checked against policy.sha256 when it's set. With
+policy.trusted_keys set, the bundle must be signed like a plugin, with a SHA256SUMS of every file
+and SHA256SUMS.minisig, by one of those keys, or nothing is installed.
+
+Once synced, sage refuses commits and pushes to protected branches and commit messages that
+break the rules, and the policy's settings apply unless global or repository config overrides
+them. 'sage doctor' checks nothing has drifted.
+
+EXAMPLES:
+  sage config set policy.source https://github.com/acme/sage-policy.git
+  sage policy sync
+  sage doctor"
+    )]
+    Policy(policy::PolicyArgs),
+
+    /// Check everything follows your organisation's policy
+    #[clap(
+        long_about = "Checks the org policy bundle installed is the one policy.source points at, that the plugins
+it requires are installed at the versions it ships, still match their signatures, and that
+none of its settings are overridden by global or repository config. Each check is listed with what's wrong, and doctor
+fails when any is.
+
+'sage policy sync' fixes an outdated bundle or plugins; overridden settings need 'sage config
+unset'.
+
+EXAMPLES:
+  sage doctor
+  sage policy sync && sage doctor"
+    )]
+    Doctor(doctor::DoctorArgs),
+
+    /// Run a command and show where its time went
+    #[clap(
+        long_about = "Runs a sage command with profiling on, the same as passing it --profile. sage records how
+long the command spends in each phase: git (every git process, with its arguments), network
+requests to GitHub, AI requests, and rendering output.
+
+Afterwards it prints a breakdown by phase and the slowest calls to stderr, so the command's own
+output is left as it is. The whole run is saved as a trace in the Trace Event Format under
+.git/sage/profiles (or the temp directory outside a repository); open it in chrome://tracing or
+ui.perfetto.dev to see when each call ran.
+
+Phases can overlap, e.g. requests made in parallel, so their times can add up to more than the
+command took.
+
+EXAMPLES:
+  sage profile status
+  sage profile list --prs
+  sage sync --profile"
+    )]
+    Profile(profile::ProfileArgs),
+
+    /// Save where you are in a stack and pick it up on another machine
+    #[clap(
+        long_about = "'sage session save' records the branch you're on, every branch of its stack with its
+parent and upstream, the stashes sage made and your uncommitted changes to tracked files.
+
+With --push, or once you confirm it, the session is pushed to origin as
+refs/sage/session/<your user.email>, where anyone who can read the repository can see it.
+A session saved there from another machine since is never overwritten; restore it first.
+Otherwise it's written to --file, or to .git/sage/session.json, to carry over yourself.
+
+'sage session restore' on the other machine fetches the branches, creates the ones that are
+missing, fast-forwards those that are behind, restores their stack parents, checks out the
+saved branch and puts the stashes and uncommitted changes back. Branches that have changed on
+both machines are left alone. Commits that were never pushed can't be restored, so push the
+stack before saving.
+
+EXAMPLES:
+  sage session save --push
+  sage session restore
+  sage session save --file ~/Dropbox/sage-session.json"
+    )]
+    Session(session::SessionArgs),
+
+    /// Keep a short note on a branch, shown in sage list and sage stack status
+    #[clap(
+        long_about = "Keeps a free-form note on a branch, such as why it's stuck or what it's waiting for. The note
+is shown next to the branch in 'sage list' and 'sage stack status', and travels with the
+branch through 'sage undo' and 'sage session'.
+
+Without any text the branch's current note is shown. Notes are stored in the repository's git
+config, so they stay on this machine.
+
+'sage pr create --with-note' adds the note to the end of the pull request description; set
+pr.include_note=true to always do so.
+
+EXAMPLES:
+  sage note 'waiting on infra team'
+  sage note --branch feature/retries 'needs a second review'
+  sage note
+  sage note --clear"
+    )]
+    Note(note::NoteArgs),
+
+    /// List the TODOs and FIXMEs the current stack adds
+    #[clap(
+        long_about = "Looks through the changes each branch of the current stack makes on top of its parent and
+lists the lines that add a TODO or FIXME, grouped by branch and file with their line numbers.
+Markers that were already there before the stack are left out.
+
+--markdown prints them as a task list instead, ready to paste into a pull request.
+'sage pr create' warns when the branch adds TODOs, and --with-todos adds the task list to the
+end of the description.
+
+EXAMPLES:
+  sage todos
+  sage todos --markdown
+  sage pr create --with-todos"
+    )]
+    Todos(todos::TodosArgs),
+
+    /// Update sage to the latest release
+    #[clap(
+        long_about = "Downloads the latest release of sage built for this platform (for example
+x86_64-unknown-linux-musl, aarch64-apple-darwin or x86_64-pc-windows-msvc), checks it against
+the SHA-256 published with the release and replaces the running binary. Nothing is installed if
+the checksum doesn't match or the release has none. Platforms without a pre-built release are
+pointed at cargo install instead.
+
+--channel picks between stable releases and nightly builds, and is remembered in update.channel
+for later updates and the new version notice. --check only says whether there is a newer
+version.
+
+EXAMPLES:
+  sage self-update
+  sage self-update --check
+  sage self-update --channel nightly"
+    )]
+    SelfUpdate(self_update::SelfUpdateArgs),
+
+    /// List experimental features and turn them on or off
+    #[clap(
+        long_about = "Experimental parts of sage stay off until you turn them on. 'sage features' lists each one,
+whether it's on and where that comes from.
+
+'sage features enable <name>' turns one on for every repository (it sets features.<name> in the
+global config). To try one for a single run instead, list it in SAGE_UNSTABLE, comma-separated,
+or use SAGE_UNSTABLE=all. SAGE_UNSTABLE wins over the config.
+
+Experimental features can change or go away between releases.
+
+EXAMPLES:
+  sage features
+  sage features enable ai-review
+  SAGE_UNSTABLE=ai-review sage review"
+    )]
+    Features(features::FeaturesArgs),
+
+    /// Get AI feedback on the current branch (experimental)
+    #[clap(
+        long_about = "Sends the changes the current branch makes on top of its stack parent (or the default
+branch) to the AI and prints its review: likely bugs, missed edge cases, missing tests and
+anything else worth fixing before a pull request is opened. Needs OPENAI_API_KEY.
+
+This is experimental: turn it on with 'sage features enable ai-review' first.
+
+EXAMPLES:
+  sage features enable ai-review
+  sage review"
+    )]
+    Review(review::ReviewArgs),
+
+    /// Explain a commit, a range of commits or something sage did, in plain English
+    #[clap(
+        long_about = "Shows what's known about a change, then asks the AI what it does and why it might matter.
+Needs OPENAI_API_KEY; without it, or with --offline, only the facts are shown.
+
+For a commit or a range of commits the facts are the commits and the files they touch, and
+the AI sees their diff. For an operation from 'sage snapshot list', written op:<id>, they're
+what the sync, restack or clean did to each branch: the branches it created, deleted and moved,
+and the commits each moved branch gained and lost.
+
+EXAMPLES:
+  sage explain HEAD
+  sage explain main..feature
+  sage explain op:12
+  sage explain a1b2c3d --offline"
+    )]
+    Explain(explain::ExplainArgs),
+
+    /// Ask for something in plain English and run the sage commands that do it
+    #[clap(
+        name = "do",
+        long_about = "Sends your request and the list of sage commands to the AI, which proposes the sage commands
+that do what you asked. The plan is shown and nothing runs until you confirm it; the commands
+then run one after another, stopping at the first that fails.
+
+Only sage commands are ever run. Each line of the plan is parsed exactly like a sage command
+line, without a shell, and a plan with anything else in it (git, other programs, pipes, sage do
+itself) is refused as a whole. Needs OPENAI_API_KEY.
+
+This is experimental: turn it on with 'sage features enable ai-do' first.
+
+EXAMPLES:
+  sage features enable ai-do
+  sage do \"get my branch up to date and open a draft PR\"
+  sage do clean up my merged branches"
+    )]
+    Do(intent::IntentArgs),
+
+    /// Show the AI prompts sage uses, and manage the index that gives it context
+    #[clap(
+        long_about = "sage has a built-in prompt for each thing it asks the AI: commit (commit messages and pull
+request titles), pr (pull request descriptions), review and explain. A repository can replace
+any of them with .sage/prompts/<name>.md, a template in the same {{...}} language as
+commit.template. Besides {{ticket}}, {{branch}} and the other template variables, prompts can use:
+
+  {{diff}}         the changes, cut short to fit what the model accepts
+  {{commits}}      the commits involved, one per line
+  {{conventions}}  the org policy's rules for commit messages, empty without any
+  {{title}}        the pull request's title (pr)
+  {{facts}}        what's known about the change (explain)
+  {{context}}      summaries of the files related to the diff, from the index
+
+'sage ai prompts show <name>' prints the prompt in effect, with where it comes from.
+
+'sage ai index' summarizes the files in the repository into .git/sage_index, locally, so the AI
+can be given the few files related to a change instead of none or all of them. Once it exists,
+sage commit keeps it up to date; 'sage ai index --clear' removes it.
+
+EXAMPLES:
+  sage ai prompts show commit
+  sage ai prompts show review > .sage/prompts/review.md
+  sage ai index"
+    )]
+    Ai(ai::AiArgs),
+
+    /// Bundle crash reports and environment details to attach to an issue
+    #[clap(
+        long_about = "When sage panics it saves a crash report with the backtrace, the command, versions and the
+last few operations from this repository, and the last command that failed is kept the same
+way. Reports live in the sage config directory under crash-reports.
+
+'sage bug-report' shows what it would include and, once you confirm, writes the latest crash
+report, the last error and a description of your environment (versions, platform, shell and
+which settings are set, but not their values) into a single .tar.gz to attach to an issue.
+
+Tokens, passwords in URLs and your home directory are removed from everything first.
+
+EXAMPLES:
+  sage bug-report
+  sage bug-report --output ~/Desktop/sage-bug.tar.gz --yes"
+    )]
+    BugReport(bug_report::BugReportArgs),
+
+    /// Report who contributed what over a time window
+    #[clap(
+        long_about = "'sage stats team' counts the commits on the default branch and the pull requests merged on
+GitHub by each author over a time window, two weeks back by default, and prints them as a
+Markdown table to paste into a sprint retrospective, or as CSV for a spreadsheet.
+
+Commits are counted from local history, so fetch first. A pull request is matched to its
+author's commits through their GitHub noreply email or a name that matches their login. When
+GitHub can't be reached, only commits are counted.
+
+EXAMPLES:
+  sage stats team
+  sage stats team --since 2026-10-01 --until 2026-10-15
+  sage stats team --since 30d --format csv --exclude-bots --exclude ci@acme.com"
+    )]
+    Stats(stats::StatsArgs),
+
+    /// Check a branch is ready to merge, for CI
+    #[clap(
+        long_about = "Runs the checks a pull request should pass on the commits between the base and HEAD, and
+fails if any finds a problem, listing everything to fix rather than stopping at the first:
+
+  commits  Messages follow Conventional Commits, with the org policy's types when it has them
+  merges   There are no merge commits
+  stack    The recorded stack parents don't loop, and all still exist
+  dco      Every commit is signed off by its author (only when commit.signoff is on)
+  secrets  No added line looks like a token, private key or password
+
+verify.checks picks which to run, e.g. 'sage config set --local verify.checks commits,secrets'.
+The base is the pull request's base branch in GitHub Actions (GITHUB_BASE_REF), otherwise the
+branch's stack parent or the default branch. There, problems are also shown as annotations on
+the pull request and the results are added to the job summary.
+
+With --report json only the results are printed, as JSON, for other tools to read.
+
+EXAMPLES:
+  sage verify
+  sage verify --base origin/release/2.0
+  sage verify --report json > verify.json"
+    )]
+    Verify(verify::VerifyArgs),
+
+    /// Save everything as a WIP commit
+    #[clap(
+        long_about = "Stages everything, untracked files included, and commits it as 'wip: <message>', e.g. to
+switch branches or leave for the day without stashing. Commit hooks are skipped, since the work
+isn't finished; set wip.skip_hooks to false to run them anyway.
+
+WIP commits aren't pushed by sage push, sage commit --push or sage sync until you take them off
+again with 'sage unwip' (or push with --allow-wip).
+
+EXAMPLES:
+  sage wip
+  sage wip \"halfway through the parser\""
+    )]
+    Wip(wip::WipArgs),
+
+    /// Put WIP commits back into the working tree
+    #[clap(
+        long_about = "Takes the WIP commits at the tip of the branch, those starting with 'wip:', off again and leaves
+their changes in the working tree, unstaged, to carry on where you left off.
+
+EXAMPLES:
+  sage unwip"
+    )]
+    Unwip(unwip::UnwipArgs),
+
+    /// Get set up to contribute to someone else's repository
+    #[clap(
+        long_about = "Does everything needed to start on a change to a GitHub repository you don't maintain:
+
+1. Forks it into your account, unless you can push to it (an existing fork is reused)
+2. Clones your fork into a directory named after it, as origin
+3. Adds the original repository as the upstream remote and catches the default branch up with it
+4. Starts a branch (contribution unless you name one) and records it on the stack
+
+After that, 'sage sync --upstream' keeps your fork and branch up to date with the original.
+--ssh clones over SSH instead of HTTPS. It needs a GitHub token, see 'sage auth login'.
+
+EXAMPLES:
+  sage contribute octocat/Hello-World
+  sage contribute rust-lang/rust fix-docs-typo --ssh"
+    )]
+    Contribute(contribute::ContributeArgs),
+
+    /// Rename the current branch, on origin and in the stack too
+    #[clap(
+        long_about = "Renames the current branch and everything that refers to it:
+
+1. The local branch, with its upstream, stack parent and note
+2. The branch on origin. On GitHub it's renamed in place, which moves its open pull requests
+   along; elsewhere the new name is pushed and the old one deleted (kept while a pull request
+   still uses it)
+3. Branches stacked on it, which get the new name as their parent
+
+EXAMPLES:
+  sage rename feature/search-v2"
+    )]
+    Rename(rename::RenameArgs),
+
+    /// Rebase the branches in the current stack that have fallen behind their parents
+    #[clap(
+        long_about = "Brings the whole stack the current branch is in back into shape after a parent has changed,
+say after amending a branch in the middle of it or pulling in review fixes:
+
+1. Works out which branches no longer sit on top of their parent, and the branches stacked on
+   those, which have to move with them
+2. Rebases each of them onto its parent, parents first, skipping branches already up to date
+3. Switches back to the branch you started on
+
+A checkpoint is taken first, so 'sage rollback' puts every branch back. When a rebase stops on
+conflicts, sage shows the commit it stopped at and what's left; resolve them and run
+'sage continue' to restack the rest. --dry-run lists the branches without touching them.
+
+EXAMPLES:
+  sage restack
+  sage restack --dry-run"
+    )]
+    Restack(restack::RestackArgs),
+}
+
+impl Cmd {
+    /// locks returns the name to hold the repository lock under. Every command that changes
+    /// something takes it, so none can run while another sage is rewriting the repository.
+    pub fn locks(&self) -> Option<String> {
+        (!self.read_only()).then(|| self.name())
+    }
+
+    /// name is the command as it's typed, e.g. `fixup-ci`
+    fn name(&self) -> String {
+        let debug = format!("{:?}", self);
+        let variant = debug.split(['(', ' ', '{']).next().unwrap_or_default();
+        let mut name = String::new();
+        for (i, c) in variant.chars().enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                name.push('-');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+
+    /// read_only says whether a command only looks at things, so it still runs with
+    /// SAGE_READONLY set. Anything not listed here is taken to change something.
+    pub fn read_only(&self) -> bool {
+        match self {
+            Cmd::Status(_) | Cmd::List(_) | Cmd::History(_) | Cmd::Diff(_) | Cmd::Grep(_) | Cmd::Completion(_) => true,
+            Cmd::Inbox(_) | Cmd::Watch(_) | Cmd::Open(_) | Cmd::Doctor(_) | Cmd::Todos(_) | Cmd::Stats(_) => true,
+            Cmd::Verify(_) => true,
+            Cmd::Snapshot(_) | Cmd::Hooks(_) | Cmd::Review(_) | Cmd::Explain(_) => true,
+            // These run other commands, which are checked on their own
+            Cmd::Profile(_) | Cmd::Do(_) => true,
+            Cmd::Pr(pr::PrArgs { command }) => matches!(
+                command,
+                None | Some(pr::PrCommands::Status(_)) | Some(pr::PrCommands::Size(pr::PrSizeArgs { plan: false, .. }))
+            ),
+            Cmd::Stack(stack::StackArgs { command, .. }) => matches!(
+                command,
+                None | Some(stack::StackCommands::Status(_) | stack::StackCommands::Export(_) | stack::StackCommands::Visualize(_))
+            ),
+            Cmd::Config(config::ConfigArgs { command }) => matches!(command, config::ConfigCommands::Get(_) | config::ConfigCommands::List),
+            Cmd::Identity(identity::IdentityArgs { command }) => {
+                matches!(command, identity::IdentityCommands::List | identity::IdentityCommands::Check)
+            }
+            Cmd::Remote(remote::RemoteArgs { command }) => matches!(command, remote::RemoteCommands::List),
+            Cmd::Ignore(ignore::IgnoreArgs { command }) => matches!(command, ignore::IgnoreCommands::Check(_)),
+            Cmd::Ci(ci::CiArgs { command }) => matches!(command, ci::CiCommands::Logs(_)),
+            Cmd::Auth(auth::AuthArgs { command }) => matches!(command, auth::AuthCommands::Status),
+            Cmd::Keys(_) => true,
+            Cmd::Features(features::FeaturesArgs { command }) => matches!(command, None | Some(features::FeaturesCommands::List)),
+            Cmd::Ai(ai::AiArgs { command }) => matches!(command, ai::AiCommands::Prompts(_)),
+            Cmd::Checkpoint(checkpoint::CheckpointArgs { list, .. }) => *list,
+            Cmd::Gc(gc::GcArgs { dry_run, .. }) => *dry_run,
+            Cmd::Lfs(lfs::LfsArgs { command: lfs::LfsCommands::Prune(lfs::LfsPruneArgs { dry_run, .. }) }) => *dry_run,
+            Cmd::Note(note::NoteArgs { text, clear, .. }) => text.is_none() && !clear,
+            _ => false,
+        }
+    }
 }
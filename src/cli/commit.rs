@@ -12,10 +12,17 @@ pub struct Commit {
     #[clap(short, long)]
     /// Create an empty commit
     #[clap(
-        long_help = "Creates a commit even when there are no changes. This is useful for triggering CI/CD pipelines or marking specific points in your repository's history without modifying any files."
+        long_help = "Creates a commit even when there are no changes. This is useful for triggering CI/CD pipelines or marking specific points in your repository's history without modifying any files. Only changes that are already staged are included; if you have unstaged changes and nothing staged, the commit is stopped (see commit.empty_guard)."
     )]
     empty: bool,
 
+    #[clap(long, conflicts_with = "ai")]
+    /// Create an empty commit with the configured message, e.g. to rerun CI
+    #[clap(
+        long_help = "Creates an empty commit using the commit.empty_message template (default 'chore: trigger ci [skip changelog]', where {{branch}}, {{ticket}} and the other template variables are filled in), so no message is needed. Combine with --push to retrigger CI. Like --empty, this is stopped when you have unstaged changes unless commit.empty_guard is set to warn or off."
+    )]
+    retry_empty: bool,
+
     #[clap(short, long)]
     /// Push changes to remote after committing
     #[clap(
@@ -30,22 +37,46 @@ pub struct Commit {
     )]
     ai: bool,
 
+    #[clap(long, conflicts_with_all = ["message", "ai", "retry_empty"])]
+    /// Pick a recent commit message to reuse
+    #[clap(
+        long_help = "Lists the commit messages sage commit used recently in this repository, typed or written by the AI, newest first. Pick one and edit it before committing, e.g. after a commit stopped by a hook, or for a maintenance commit made every week. Messages are kept in .git/sage/messages.json, even when the commit didn't go through."
+    )]
+    reuse: bool,
+
     #[clap(short = 'y', long = "yes")]
     /// Skip confirmation when using AI-generated commit message
     auto_confirm: bool,
+
+    #[clap(long, value_name = "RULE", value_delimiter = ',')]
+    /// Commit even if these guard rules match (binary, lockfile, generated or all)
+    #[clap(
+        long_help = "Lets the commit through even when the given commit guard rules match. Rules are configured with the guard.* settings (see 'sage config list'): 'binary' for large binary files, 'lockfile' for lockfile changes without their manifest, 'generated' for files matching guard.generated_paths, or 'all'."
+    )]
+    allow: Vec<String>,
+
+    #[clap(long, value_name = "DATE")]
+    /// Date the commit instead of using now, e.g. when importing older work
+    #[clap(
+        long_help = "Sets both the author and committer date of the commit, e.g. when importing work done elsewhere. Takes a local date and time like '2024-05-01 14:30', a date like '2024-05-01', RFC 3339, or a relative time like '2h ago'. Dates in the future or before the commit being built on are warned about (see commit.clock_guard), since changelogs and stats sort by date."
+    )]
+    date: Option<String>,
 }
 
 impl Run for Commit {
     async fn run(&self) -> Result<()> {
         let mut opts = app::commit::CommitOptions::default();
         opts.empty = self.empty;
-        opts.message = self.message.clone().unwrap_or_default();
+        opts.retry_empty = self.retry_empty;
+        opts.message = if self.reuse { app::messages::pick()? } else { self.message.clone().unwrap_or_default() };
         opts.push = self.push;
         opts.ai = self.ai;
         opts.auto_confirm = self.auto_confirm;
+        opts.allow = self.allow.clone();
+        opts.date = self.date.clone();
         
         // Validate that we either have a message or are using AI
-        if !opts.ai && opts.message.is_empty() {
+        if !opts.ai && !opts.retry_empty && opts.message.is_empty() {
             return Err(anyhow::anyhow!("Commit message is required when not using AI"));
         }
         
@@ -58,7 +58,7 @@ impl fmt::Display for Shell {
 
 impl Run for CompletionArgs {
     async fn run(&self) -> Result<()> {
-        let mut cmd = crate::cli::Cmd::command();
+        let mut cmd = crate::cli::Cli::command();
         let mut stdout = io::stdout();
 
         // Print a helpful comment at the top of the generated script
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::{app, config::Scope};
+
+/// Read and write sage settings
+#[derive(Parser, Debug)]
+#[clap(after_help = "Global settings are stored in $SAGE_CONFIG (or your config directory under sage/config.json).
+Settings written with --local are stored in .git/sage/config.json and only apply to the current repository.")]
+pub struct ConfigArgs {
+    #[clap(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print the effective value of a setting
+    Get(ConfigGetArgs),
+    /// Change a setting
+    Set(ConfigSetArgs),
+    /// Remove a setting
+    Unset(ConfigKeyArgs),
+    /// List configured values and available settings
+    List,
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigGetArgs {
+    /// The setting name, e.g. commit.signoff
+    pub key: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigKeyArgs {
+    /// The setting name, e.g. commit.signoff
+    pub key: String,
+
+    /// Remove the value from the repository config instead of the global one
+    #[clap(long)]
+    pub local: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigSetArgs {
+    /// The setting name, e.g. commit.signoff
+    pub key: String,
+
+    /// The value to store
+    pub value: String,
+
+    /// Store the value for the current repository only
+    #[clap(long)]
+    pub local: bool,
+}
+
+fn scope(local: bool) -> Scope {
+    if local { Scope::Local } else { Scope::Global }
+}
+
+impl Run for ConfigArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            ConfigCommands::Get(args) => app::config::get(&args.key),
+            ConfigCommands::Set(args) => app::config::set(&args.key, &args.value, scope(args.local)),
+            ConfigCommands::Unset(args) => app::config::unset(&args.key, scope(args.local)),
+            ConfigCommands::List => app::config::list(),
+        }
+    }
+}
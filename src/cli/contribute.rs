@@ -0,0 +1,26 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct ContributeArgs {
+    /// The repository to contribute to, as owner/repo or a GitHub URL
+    pub repo: String,
+
+    /// The branch to start
+    #[clap(default_value = "contribution")]
+    pub branch: String,
+
+    /// Clone over SSH instead of HTTPS
+    #[clap(long, short)]
+    pub ssh: bool,
+}
+
+impl Run for ContributeArgs {
+    async fn run(&self) -> Result<()> {
+        app::contribute::contribute(&self.repo, &self.branch, self.ssh).await
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app::{self, diff::DiffTarget};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+#[clap(group = clap::ArgGroup::new("target").multiple(false))]
+pub struct DiffArgs {
+    /// Diff against the stack parent of the current branch
+    #[clap(long, group = "target", long_help = "Compares the current branch against its stack parent (the branch it was started from with
+'sage start --parent'). Falls back to the default branch when no parent is recorded.")]
+    pub vs_parent: bool,
+
+    /// Diff against the merge-base with the default branch
+    #[clap(long, group = "target", long_help = "Compares the current branch against its merge-base with the default branch,
+showing only the changes introduced on this branch.")]
+    pub vs_default: bool,
+
+    /// Diff against the base branch of the current branch's pull request
+    #[clap(long, group = "target", long_help = "Looks up the pull request for the current branch on GitHub and compares against
+the latest state of its base branch. This matches what reviewers see on the PR.")]
+    pub pr: bool,
+
+    /// Print a plain patch suitable for piping
+    #[clap(long)]
+    pub patch: bool,
+
+    /// Only show a diffstat summary
+    #[clap(long)]
+    pub stat: bool,
+}
+
+impl Run for DiffArgs {
+    async fn run(&self) -> Result<()> {
+        let target = if self.vs_parent {
+            DiffTarget::Parent
+        } else if self.vs_default {
+            DiffTarget::Default
+        } else if self.pr {
+            DiffTarget::PullRequest
+        } else {
+            DiffTarget::WorkingTree
+        };
+
+        app::diff::diff(&app::diff::DiffOptions {
+            target,
+            patch: self.patch,
+            stat: self.stat,
+        })
+        .await
+    }
+}
@@ -0,0 +1,15 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct DoctorArgs {}
+
+impl Run for DoctorArgs {
+    async fn run(&self) -> Result<()> {
+        app::doctor::doctor()
+    }
+}
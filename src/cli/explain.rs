@@ -0,0 +1,22 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct ExplainArgs {
+    /// A commit, a range like main..feature, or op:<id> for an operation from sage snapshot list
+    pub target: String,
+
+    /// Only show the facts, without asking the AI
+    #[clap(long)]
+    pub offline: bool,
+}
+
+impl Run for ExplainArgs {
+    async fn run(&self) -> Result<()> {
+        app::explain::explain(&self.target, self.offline).await
+    }
+}
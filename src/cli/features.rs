@@ -0,0 +1,38 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct FeaturesArgs {
+    #[clap(subcommand)]
+    pub command: Option<FeaturesCommands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FeaturesCommands {
+    /// List every experimental feature and whether it's on (the default)
+    List,
+    /// Turn an experimental feature on
+    Enable(FeatureNameArgs),
+    /// Turn an experimental feature off
+    Disable(FeatureNameArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct FeatureNameArgs {
+    /// The feature, as shown by sage features list
+    pub name: String,
+}
+
+impl Run for FeaturesArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            None | Some(FeaturesCommands::List) => app::features::list(),
+            Some(FeaturesCommands::Enable(args)) => app::features::set(&args.name, true),
+            Some(FeaturesCommands::Disable(args)) => app::features::set(&args.name, false),
+        }
+    }
+}
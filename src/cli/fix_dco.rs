@@ -0,0 +1,18 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+#[clap(after_help = "Enable DCO enforcement with:
+  sage config set commit.signoff true          # For every repository
+  sage config set --local commit.signoff true  # For the current repository only")]
+pub struct FixDcoArgs;
+
+impl Run for FixDcoArgs {
+    async fn run(&self) -> Result<()> {
+        app::dco::fix_dco()
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app::{self, fixup_ci::FixupCiOptions};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct FixupCiArgs {
+    /// Commit message to use instead of "style: fix ci"
+    #[clap(short, long, conflicts_with = "fixup")]
+    pub message: Option<String>,
+
+    /// Create a fixup! commit for the last commit instead
+    #[clap(long)]
+    pub fixup: bool,
+
+    /// Commit even if these guard rules match (binary, lockfile, generated or all)
+    #[clap(long, value_name = "RULE", value_delimiter = ',')]
+    pub allow: Vec<String>,
+}
+
+impl Run for FixupCiArgs {
+    async fn run(&self) -> Result<()> {
+        app::fixup_ci::fixup_ci(&FixupCiOptions {
+            message: self.message.clone(),
+            fixup: self.fixup,
+            allow: self.allow.clone(),
+        })
+        .await
+    }
+}
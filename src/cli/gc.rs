@@ -0,0 +1,27 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct GcArgs {
+    /// Only report, don't delete or pack anything
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Pack with git gc --aggressive, which is slow but smallest
+    #[clap(long)]
+    pub aggressive: bool,
+
+    /// How many of the largest files in history to list
+    #[clap(long, default_value_t = 10)]
+    pub top: usize,
+}
+
+impl Run for GcArgs {
+    async fn run(&self) -> Result<()> {
+        app::gc::gc(self.dry_run, self.aggressive, self.top)
+    }
+}
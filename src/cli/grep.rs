@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app::{self, grep::GrepOptions};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct GrepArgs {
+    /// The pattern to search for (a git grep regular expression)
+    pub pattern: String,
+
+    /// Limit the search to these paths
+    pub paths: Vec<String>,
+
+    /// Match case-insensitively
+    #[clap(short, long)]
+    pub ignore_case: bool,
+}
+
+impl Run for GrepArgs {
+    async fn run(&self) -> Result<()> {
+        app::grep::grep(&GrepOptions {
+            pattern: self.pattern.clone(),
+            ignore_case: self.ignore_case,
+            paths: self.paths.clone(),
+        })
+    }
+}
@@ -1,15 +1,22 @@
 use anyhow::{Result};
 use clap::Parser;
 
-use crate::{app};
+use crate::{app, ui::template::Template};
 
 use super::Run;
 
 #[derive(Parser, Debug)]
-pub struct History;
+#[clap(after_help = "FORMAT FIELDS:
+  hash, author, date, message and branch, e.g. --format '{{hash}} {{message}} ({{author}})'")]
+pub struct History {
+    /// Render each commit through a template, or @file to read the template from a file
+    #[clap(long, value_name = "TEMPLATE")]
+    pub format: Option<String>,
+}
 
 impl Run for History {
     async fn run(&self) -> Result<()> {
-        app::history::history()
+        let format = self.format.as_deref().map(Template::load).transpose()?;
+        app::history::history(format.as_ref())
     }
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app;
+
+/// See what plugins did on hooks
+#[derive(Parser, Debug)]
+pub struct HooksArgs {
+    #[clap(subcommand)]
+    pub command: HooksCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HooksCommands {
+    /// Show the latest hook report
+    Last(HooksLastArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct HooksLastArgs {
+    /// Only look at this hook, e.g. pre-push
+    pub event: Option<String>,
+}
+
+impl Run for HooksArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            HooksCommands::Last(args) => app::hooks::last(args.event.as_deref()),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app::{self, identity::Profile};
+
+/// Manage commit identity profiles
+#[derive(Parser, Debug)]
+#[clap(after_help = "Profiles are stored in the global sage config as identity.<profile>.* settings.
+The profile selected with 'sage identity use' is remembered in the repository config.")]
+pub struct IdentityArgs {
+    #[clap(subcommand)]
+    pub command: IdentityCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IdentityCommands {
+    /// Add or replace an identity profile
+    Add(IdentityAddArgs),
+    /// Remove an identity profile
+    Remove(IdentityNameArgs),
+    /// List identity profiles
+    List,
+    /// Commit with a profile in the current repository
+    Use(IdentityNameArgs),
+    /// Check the current repository is using the expected identity
+    Check,
+}
+
+#[derive(Parser, Debug)]
+pub struct IdentityAddArgs {
+    /// Name of the profile, e.g. work
+    pub profile: String,
+
+    /// Author name to commit with
+    #[clap(long)]
+    pub name: String,
+
+    /// Author email to commit with
+    #[clap(long)]
+    pub email: String,
+
+    /// GPG/SSH key used to sign commits
+    #[clap(long)]
+    pub signing_key: Option<String>,
+
+    /// Remote owners (users or orgs) this profile is used for
+    #[clap(long, value_delimiter = ',')]
+    pub orgs: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct IdentityNameArgs {
+    /// Name of the profile
+    pub profile: String,
+}
+
+impl Run for IdentityArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            IdentityCommands::Add(args) => app::identity::add(&Profile {
+                name: args.profile.clone(),
+                user_name: args.name.clone(),
+                email: args.email.clone(),
+                signing_key: args.signing_key.clone(),
+                orgs: args.orgs.clone(),
+            }),
+            IdentityCommands::Remove(args) => app::identity::remove(&args.profile),
+            IdentityCommands::List => app::identity::list(),
+            IdentityCommands::Use(args) => app::identity::use_profile(&args.profile),
+            IdentityCommands::Check => app::identity::check(),
+        }
+    }
+}
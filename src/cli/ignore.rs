@@ -0,0 +1,73 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app::{self, ignore::IgnoreAddOptions};
+
+/// Manage ignored files
+#[derive(Parser, Debug)]
+pub struct IgnoreArgs {
+    #[clap(subcommand)]
+    pub command: IgnoreCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IgnoreCommands {
+    /// Add patterns to .gitignore
+    #[clap(long_about = "Appends patterns to the .gitignore at the root of the repository, skipping any that
+are already present. With --section the patterns are grouped under a '# <section>' comment,
+extending the section if it already exists.
+
+When files that are already committed match the new patterns, sage lists them and offers
+to stop tracking them (they are kept on disk).
+
+EXAMPLES:
+  sage ignore add '*.log'
+  sage ignore add .idea/ .vscode/ --section Editors
+  sage ignore add .env --untrack")]
+    Add(IgnoreAddArgs),
+
+    /// Explain why paths are ignored
+    #[clap(long_about = "Shows which pattern, and in which file, causes each path to be ignored. Also points
+out committed files that match a pattern and are therefore still tracked.
+
+EXAMPLES:
+  sage ignore check build/output.txt
+  sage ignore check .env debug.log")]
+    Check(IgnoreCheckArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct IgnoreAddArgs {
+    /// Patterns to ignore
+    #[clap(required = true)]
+    pub patterns: Vec<String>,
+
+    /// Group the patterns under a section comment
+    #[clap(short, long)]
+    pub section: Option<String>,
+
+    /// Stop tracking committed files that match the new patterns without asking
+    #[clap(long)]
+    pub untrack: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct IgnoreCheckArgs {
+    /// Paths to check
+    #[clap(required = true)]
+    pub paths: Vec<String>,
+}
+
+impl Run for IgnoreArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            IgnoreCommands::Add(args) => app::ignore::add(&IgnoreAddOptions {
+                patterns: args.patterns.clone(),
+                section: args.section.clone(),
+                untrack: args.untrack,
+            }),
+            IgnoreCommands::Check(args) => app::ignore::check(&args.paths),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app::{self, inbox::InboxOptions};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct InboxArgs {
+    /// Only include this repository (owner/repo), can be repeated
+    #[clap(long = "repo")]
+    pub repos: Vec<String>,
+
+    /// Only include this org or user, can be repeated
+    #[clap(long = "org")]
+    pub orgs: Vec<String>,
+
+    /// Just print the list
+    #[clap(long)]
+    pub no_interactive: bool,
+}
+
+impl Run for InboxArgs {
+    async fn run(&self) -> Result<()> {
+        app::inbox::inbox(&InboxOptions {
+            repos: self.repos.clone(),
+            orgs: self.orgs.clone(),
+            no_interactive: self.no_interactive,
+        })
+        .await
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use clap::{CommandFactory, Parser};
+use std::future::Future;
+use std::pin::Pin;
+
+use super::{cmd::Cmd, Cli, Run};
+use crate::{app::intent, config::features::{self, Feature}};
+
+/// Ask for something in plain English and run the sage commands that do it, once you agree
+#[derive(Parser, Debug)]
+pub struct IntentArgs {
+    /// What you want done, e.g. "get my branch up to date and open a draft PR"
+    #[clap(required = true, num_args = 1..)]
+    pub intent: Vec<String>,
+}
+
+impl Run for IntentArgs {
+    async fn run(&self) -> Result<()> {
+        features::require(Feature::AiDo)?;
+
+        let steps = intent::plan(&self.intent.join(" "), &catalog()).await?;
+        // Every step has to be a command sage itself would take, or none of them run
+        let commands = steps.iter().map(vet).collect::<Result<Vec<_>>>()?;
+
+        if !intent::confirm(&steps)? {
+            println!("Nothing was run.");
+            return Ok(());
+        }
+
+        for (number, (step, cli)) in steps.iter().zip(commands).enumerate() {
+            intent::announce(number + 1, steps.len(), step);
+            // A step is a whole sage command line, so it has to be boxed
+            let command: Pin<Box<dyn Future<Output = Result<()>>>> = Box::pin(cli.run_command());
+            if let Err(e) = command.await {
+                return Err(anyhow!("Stopped at '{}', step {} of {}: {}", intent::describe(step), number + 1, steps.len(), e));
+            }
+        }
+        println!("\n✨ Done");
+        Ok(())
+    }
+}
+
+/// Parse a step as a sage command line, refusing the ones that would run other commands
+fn vet(step: &intent::Step) -> Result<Cli> {
+    let cli = Cli::try_parse_from(std::iter::once("sage").chain(step.iter().map(String::as_str)))
+        .map_err(|e| anyhow!("The plan included '{}', which sage doesn't understand: {}", intent::describe(step), e.kind()))?;
+    if matches!(cli.cmd, Cmd::Do(_) | Cmd::Profile(_)) {
+        return Err(anyhow!("The plan included '{}', which sage do won't run", intent::describe(step)));
+    }
+    Ok(cli)
+}
+
+/// Every sage command with its flags, for the AI to pick from
+fn catalog() -> String {
+    let mut lines = Vec::new();
+    let root = Cli::command();
+    for command in root.get_subcommands().filter(|command| !matches!(command.get_name(), "do" | "profile" | "help")) {
+        let leaves = if command.has_subcommands() {
+            command.get_subcommands().map(|sub| (format!("{} {}", command.get_name(), sub.get_name()), sub)).collect::<Vec<_>>()
+        } else {
+            vec![(command.get_name().to_string(), command)]
+        };
+        for (name, leaf) in leaves {
+            let mut line = format!("sage {}", name);
+            for arg in leaf.get_arguments().filter(|arg| !arg.is_global_set() && !arg.is_hide_set()) {
+                let value = arg.get_value_names().and_then(|names| names.first()).map(|name| format!(" <{}>", name));
+                match arg.get_long() {
+                    Some(long) if arg.get_action().takes_values() => {
+                        line.push_str(&format!(" [--{}{}]", long, value.unwrap_or_else(|| " <VALUE>".to_string())))
+                    }
+                    Some(long) => line.push_str(&format!(" [--{}]", long)),
+                    None => line.push_str(&format!(" <{}>", arg.get_id())),
+                }
+            }
+            if let Some(about) = leaf.get_about() {
+                line.push_str(&format!(": {}", about));
+            }
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vet() {
+        let step = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(vet(&step(&["sync"])).is_ok());
+        assert!(vet(&step(&["pr", "create", "--title", "WIP"])).is_ok());
+        assert!(vet(&step(&["do", "anything"])).is_err());
+        assert!(vet(&step(&["profile", "sync"])).is_err());
+        assert!(vet(&step(&["frobnicate"])).is_err());
+    }
+
+    #[test]
+    fn test_catalog() {
+        let catalog = catalog();
+        assert!(catalog.lines().any(|line| line.starts_with("sage sync")));
+        assert!(catalog.lines().any(|line| line.starts_with("sage pr create") && line.contains("[--title <")));
+        assert!(!catalog.lines().any(|line| line.split([' ', ':']).nth(1) == Some("do")));
+    }
+}
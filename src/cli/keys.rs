@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct KeysArgs {
+    #[clap(subcommand)]
+    pub command: Option<KeysCommands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCommands {
+    /// List every action and the keys bound to it (the default)
+    List,
+    /// Press keys to see which action each is bound to
+    Test,
+}
+
+impl Run for KeysArgs {
+    async fn run(&self) -> Result<()> {
+        match self.command {
+            None | Some(KeysCommands::List) => app::keys::list(),
+            Some(KeysCommands::Test) => app::keys::test(),
+        }
+    }
+}
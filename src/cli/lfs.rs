@@ -0,0 +1,44 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app;
+
+/// Git LFS helpers
+#[derive(Parser, Debug)]
+pub struct LfsArgs {
+    #[clap(subcommand)]
+    pub command: LfsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LfsCommands {
+    /// Delete old local LFS objects
+    #[clap(long_about = "Runs 'git lfs prune' with safety checks: objects that are not on the remote yet are
+always kept, the current branch must not have unpushed commits, and the objects to delete
+are listed before asking for confirmation.
+
+EXAMPLES:
+  sage lfs prune --dry-run
+  sage lfs prune --yes")]
+    Prune(LfsPruneArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct LfsPruneArgs {
+    /// Only show what would be deleted
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Skip the confirmation prompt
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+impl Run for LfsArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            LfsCommands::Prune(args) => app::lfs::prune(args.dry_run, args.yes),
+        }
+    }
+}
@@ -1,12 +1,12 @@
-use crate::{app, cli::Run};
+use crate::{app::{self, list::Page}, cli::Run, config, ui::template::Template};
 use clap::Parser;
 
 use anyhow::Result;
 
 /// Arguments for the list command
 ///
-/// This command doesn't take any arguments but provides a comprehensive
-/// view of all branches in the repository with their status information.
+/// Provides a comprehensive view of all branches in the repository with their
+/// status information, and with --prs the pull request opened from each branch.
 #[derive(Parser, Debug)]
 #[clap(after_help = "COLOR CODING:
   Green: Current branch
@@ -19,12 +19,68 @@ SYMBOLS:
   * : Indicates the current branch
   -> : Shows tracking relationship with remote branch
   ↑n : n commits ahead of remote branch
-  ↓n : n commits behind remote branch")]
-pub struct ListArgs;
+  ↓n : n commits behind remote branch
+  #n : Pull request for the branch with --prs, with its state and checks (✓ passing, ✗ failing, ● pending)
+  — text : The branch's note, set with sage note
+  3d old, forked 2w ago : Time since the last commit and since the branch forked from the default
+  branch, green for the last week, yellow after that and red once stale (clean.stale_days, 30 by
+  default)
+
+  With ui.accessible set, colors and symbols are replaced by words: (current), 2 ahead, passing.
+
+FORMAT FIELDS:
+  name, current, upstream, ahead, behind, age_days, forked_days, stale, note, and with --prs pr (number, state, draft, url,
+  base, checks, conflicting), e.g. --format '{{name}}{{#if pr}} #{{pr.number}} {{pr.state}}{{/if}}'")]
+pub struct ListArgs {
+    /// Look up each branch's pull request on GitHub, always done when list.prs is true
+    #[clap(long, overrides_with = "no_prs")]
+    pub prs: bool,
+
+    /// Skip looking up pull requests, even when list.prs is true
+    #[clap(long, overrides_with = "prs")]
+    pub no_prs: bool,
+
+    /// Render each branch through a template, or @file to read the template from a file
+    #[clap(long, value_name = "TEMPLATE")]
+    pub format: Option<String>,
+
+    /// Show at most this many branches
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: Option<u64>,
+
+    /// Which page of --limit branches to show, starting at 1 (50 a page without --limit)
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub page: Option<u64>,
+
+    /// Only show branches without commits in DAYS days, clean.stale_days (or 30) if not given
+    #[clap(long, value_name = "DAYS", num_args = 0..=1)]
+    pub stale: Option<Option<u32>>,
+}
+
+/// Branches on a page when --page is given without --limit
+const DEFAULT_PAGE_SIZE: usize = 50;
 
 impl Run for ListArgs {
     async fn run(&self) -> Result<()> {
-        app::list::list()?;
+        let format = self.format.as_deref().map(Template::load).transpose()?;
+        let page = match (self.limit, self.page) {
+            (None, None) => None,
+            (limit, number) => Some(Page {
+                limit: limit.map(|limit| limit as usize).unwrap_or(DEFAULT_PAGE_SIZE),
+                number: number.unwrap_or(1) as usize,
+            }),
+        };
+        let stale = match self.stale {
+            Some(Some(days)) => Some(i64::from(days)),
+            Some(None) => Some(app::list::stale_days()?),
+            None => None,
+        };
+        let show_prs = match (self.prs, self.no_prs) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => config::get_bool("list.prs", false),
+        };
+        app::list::list(show_prs, format.as_ref(), page, stale).await?;
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
 pub use crate::cli::cmd::*;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
+use crate::events::{self, Event};
 use crate::update;
 pub mod clone;
 mod cmd;
@@ -16,18 +17,120 @@ pub mod pr;
 pub mod sync;
 pub mod clean;
 pub mod history;
+pub mod diff;
+pub mod stack;
+pub mod send_email;
+pub mod config;
+pub mod fix_dco;
+pub mod identity;
+pub mod ignore;
+pub mod mv;
+pub mod rm;
+pub mod grep;
+pub mod lfs;
+pub mod fixup_ci;
+pub mod ci;
+pub mod inbox;
+pub mod watch;
+pub mod open;
+pub mod auth;
+pub mod remote;
+pub mod resume;
+pub mod gc;
+pub mod purge;
+pub mod keys;
+pub mod undo;
+pub mod redo;
+pub mod checkpoint;
+pub mod snapshot;
+pub mod hooks;
+pub mod plugin;
+pub mod policy;
+pub mod profile;
+pub mod doctor;
+pub mod session;
+pub mod note;
+pub mod todos;
+pub mod self_update;
+pub mod features;
+pub mod review;
+pub mod explain;
+pub mod intent;
+pub mod ai;
+pub mod bug_report;
+pub mod stats;
+pub mod verify;
+pub mod wip;
+pub mod unwip;
+pub mod contribute;
+pub mod rename;
+pub mod restack;
 
 pub trait Run {
     async fn run(&self) -> Result<()>;
 }
 
-impl Run for Cmd {
+impl Run for Cli {
     async fn run(&self) -> Result<()> {
-        // Check for updates before running any command
-        if let Err(e) = update::check_for_updates().await {
-            eprintln!("Warning: Failed to check for updates: {}", e);
+        if self.no_pager {
+            crate::ui::pager::disable();
+        }
+        if self.events.is_none() {
+            return self.run_profiled().await;
+        }
+
+        events::start(self.events_fd)?;
+        let command = events::command_line(std::env::args().skip(1));
+        events::emit(Event::CommandStarted { command: &command });
+        let result = self.run_profiled().await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        events::emit(Event::CommandFinished { command: &command, ok: result.is_ok(), error });
+        result
+    }
+}
+
+impl Cli {
+    /// run_profiled runs the command, timing it with --profile
+    async fn run_profiled(&self) -> Result<()> {
+        if self.profile {
+            let name = std::env::args().skip(1).filter(|arg| arg != "--profile").collect::<Vec<_>>().join(" ");
+            return crate::app::profile::measure(&name, self.run_command()).await;
+        }
+        self.run_command().await
+    }
+
+    /// run_command runs the command under the repository lock when it needs one
+    pub(crate) async fn run_command(&self) -> Result<()> {
+        if read_only() && !self.cmd.read_only() {
+            return Err(anyhow!(
+                "SAGE_READONLY is set, so sage won't run commands that change anything. \
+                 Status, list, diff and the like still work"
+            ));
         }
 
+        // Outside a repository there's nothing to lock, and the command says so itself
+        let _lock = match self.cmd.locks() {
+            Some(name) if crate::git::repo::is_repo().unwrap_or(false) => {
+                Some(crate::app::lock::acquire(&name, self.wait).await?)
+            }
+            _ => None,
+        };
+        self.cmd.run().await
+    }
+}
+
+impl Run for Cmd {
+    async fn run(&self) -> Result<()> {
+        // Look for updates while the command runs rather than before it
+        let update_check = update::start_update_check();
+        let result = self.run_command().await;
+        update::finish_update_check(update_check).await;
+        result
+    }
+}
+
+impl Cmd {
+    async fn run_command(&self) -> Result<()> {
         match self {
             Cmd::Commit(cmd) => cmd.run().await,
             Cmd::Clone(cmd) => cmd.run().await,
@@ -41,6 +144,60 @@ impl Run for Cmd {
             Cmd::Sync(cmd) => cmd.run().await,
             Cmd::Clean(cmd) => cmd.run().await,
             Cmd::History(cmd) => cmd.run().await,
+            Cmd::Diff(cmd) => cmd.run().await,
+            Cmd::Stack(cmd) => cmd.run().await,
+            Cmd::SendEmail(cmd) => cmd.run().await,
+            Cmd::Config(cmd) => cmd.run().await,
+            Cmd::FixDco(cmd) => cmd.run().await,
+            Cmd::Identity(cmd) => cmd.run().await,
+            Cmd::Ignore(cmd) => cmd.run().await,
+            Cmd::Mv(cmd) => cmd.run().await,
+            Cmd::Rm(cmd) => cmd.run().await,
+            Cmd::Grep(cmd) => cmd.run().await,
+            Cmd::Lfs(cmd) => cmd.run().await,
+            Cmd::FixupCi(cmd) => cmd.run().await,
+            Cmd::Ci(cmd) => cmd.run().await,
+            Cmd::Inbox(cmd) => cmd.run().await,
+            Cmd::Watch(cmd) => cmd.run().await,
+            Cmd::Open(cmd) => cmd.run().await,
+            Cmd::Auth(cmd) => cmd.run().await,
+            Cmd::Remote(cmd) => cmd.run().await,
+            Cmd::Continue(cmd) => cmd.run().await,
+            Cmd::Gc(cmd) => cmd.run().await,
+            Cmd::PurgeFile(cmd) => cmd.run().await,
+            Cmd::Keys(cmd) => cmd.run().await,
+            Cmd::Undo(cmd) => cmd.run().await,
+            Cmd::Redo(cmd) => cmd.run().await,
+            Cmd::Checkpoint(cmd) => cmd.run().await,
+            Cmd::Rollback(cmd) => cmd.run().await,
+            Cmd::Snapshot(cmd) => cmd.run().await,
+            Cmd::Hooks(cmd) => cmd.run().await,
+            Cmd::Plugin(cmd) => cmd.run().await,
+            Cmd::Policy(cmd) => cmd.run().await,
+            Cmd::Profile(cmd) => cmd.run().await,
+            Cmd::Doctor(cmd) => cmd.run().await,
+            Cmd::Session(cmd) => cmd.run().await,
+            Cmd::Note(cmd) => cmd.run().await,
+            Cmd::Todos(cmd) => cmd.run().await,
+            Cmd::SelfUpdate(cmd) => cmd.run().await,
+            Cmd::Features(cmd) => cmd.run().await,
+            Cmd::Review(cmd) => cmd.run().await,
+            Cmd::Explain(cmd) => cmd.run().await,
+            Cmd::Do(cmd) => cmd.run().await,
+            Cmd::Ai(cmd) => cmd.run().await,
+            Cmd::BugReport(cmd) => cmd.run().await,
+            Cmd::Stats(cmd) => cmd.run().await,
+            Cmd::Verify(cmd) => cmd.run().await,
+            Cmd::Wip(cmd) => cmd.run().await,
+            Cmd::Unwip(cmd) => cmd.run().await,
+            Cmd::Contribute(cmd) => cmd.run().await,
+            Cmd::Rename(cmd) => cmd.run().await,
+            Cmd::Restack(cmd) => cmd.run().await,
         }
     }
 }
+
+/// read_only says whether SAGE_READONLY asks for commands that only look, e.g. in CI
+pub(crate) fn read_only() -> bool {
+    std::env::var("SAGE_READONLY").is_ok_and(|value| crate::config::parse_bool(&value).unwrap_or(true))
+}
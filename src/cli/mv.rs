@@ -0,0 +1,21 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct MvArgs {
+    /// The file or directory to move
+    pub source: String,
+
+    /// Where to move it to
+    pub destination: String,
+}
+
+impl Run for MvArgs {
+    async fn run(&self) -> Result<()> {
+        app::files::mv(&self.source, &self.destination)
+    }
+}
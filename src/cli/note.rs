@@ -0,0 +1,26 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct NoteArgs {
+    /// The note to keep on the branch; shows the current note when left out
+    pub text: Option<String>,
+
+    /// The branch to note (defaults to the current branch)
+    #[clap(short, long)]
+    pub branch: Option<String>,
+
+    /// Remove the note from the branch
+    #[clap(long, conflicts_with = "text")]
+    pub clear: bool,
+}
+
+impl Run for NoteArgs {
+    async fn run(&self) -> Result<()> {
+        app::note::note(self.branch.as_deref(), self.text.as_deref(), self.clear)
+    }
+}
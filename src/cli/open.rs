@@ -0,0 +1,22 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct OpenArgs {
+    /// What to open: repo, pr, ci, or a file as path[:line[-end]]
+    pub target: Option<String>,
+
+    /// Print the URL instead of opening it
+    #[clap(short, long)]
+    pub print: bool,
+}
+
+impl Run for OpenArgs {
+    async fn run(&self) -> Result<()> {
+        app::open::open(self.target.as_deref(), self.print).await
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use super::Run;
+use crate::app;
+
+/// Install plugins
+#[derive(Parser, Debug)]
+pub struct PluginArgs {
+    #[clap(subcommand)]
+    pub command: PluginCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PluginCommands {
+    /// Check a plugin's signature and install it from a directory
+    Install(PluginInstallArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct PluginInstallArgs {
+    /// Directory holding the plugin's plugin.json
+    pub path: PathBuf,
+}
+
+impl Run for PluginArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            PluginCommands::Install(args) => app::plugin::install(&args.path),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app;
+
+/// Work with your organisation's policy bundle
+#[derive(Parser, Debug)]
+pub struct PolicyArgs {
+    #[clap(subcommand)]
+    pub command: PolicyCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PolicyCommands {
+    /// Install or update the policy bundle from policy.source
+    Sync,
+}
+
+impl Run for PolicyArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            PolicyCommands::Sync => app::policy::sync(),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 
 use super::Run;
-use crate::app;
+use crate::{app, config};
 
 /// GitHub Pull Request commands
 #[derive(Parser, Debug)]
@@ -31,6 +31,13 @@ This command performs several operations automatically:
 If the branch already exists locally, it will switch to it and update it with the latest changes
 from the remote if the branch is clean.
 
+Pull requests from forks are fetched from the fork itself: sage adds a remote named after the
+fork's owner, reached the same way as origin, and the branch tracks it so you can pull updates
+(and push, when the author allows edits from maintainers). The remote is removed again once the
+last branch tracking it is deleted. If the fork's branch name is taken locally, the branch is
+called <owner>-<branch> instead. A pull request whose fork was deleted, or can't be fetched, is
+checked out from its pull/<n>/head ref, without an upstream.
+
 EXAMPLES:
   sage pr checkout 123                  # Checkout PR #123 using the PR's branch name
   sage pr checkout 123 feature/test     # Checkout PR #123 to a branch named 'feature/test'")]
@@ -55,6 +62,48 @@ EXAMPLES:
     Status(PrStatusArgs),
     /// Create a new PR
     Create(PrCreateArgs),
+
+    /// Comment on a pull request
+    #[clap(long_about = "Posts a comment on the pull request for the current branch, or the one given with --number.
+
+The message is taken from the argument. Pass - to read it from stdin, which is also used when
+stdin is piped. With neither, $EDITOR opens to write it.
+
+EXAMPLES:
+  sage pr comment \"Addressed the review, ready for another look\"
+  git log -1 --format=%B | sage pr comment -    # Comment with the last commit message
+  sage pr comment --number 42                   # Write a comment on #42 in $EDITOR")]
+    Comment(PrCommentArgs),
+
+    /// Edit the title, body or labels of a pull request
+    #[clap(long_about = "Changes the pull request for the current branch, or the one given with --number.
+
+Pass --title and --body to replace them (--body - reads the body from stdin), and --add-label or
+--remove-label, as often as needed, to change its labels. With none of these, the title and body
+open in $EDITOR: the first line is the title and everything after the blank line the body.
+
+EXAMPLES:
+  sage pr edit --title \"Add rate limiting\"
+  sage pr edit --add-label bug --remove-label wontfix
+  sage pr edit --number 42 --body - < notes.md
+  sage pr edit                                  # Edit the title and body in $EDITOR")]
+    Edit(PrEditArgs),
+
+    /// Show how big a pull request would be and how to split it
+    #[clap(long_about = "Measures the current branch's changes against its parent: files, lines added and removed,
+and how many areas (top-level directories, or packages under crates/, packages/ and the like)
+they're spread over. sage pr create runs the same check and warns when a limit is passed.
+
+Over pr.max_lines (default 400), pr.max_files (default 25) or pr.max_areas (default 3), it
+suggests split points: one branch per area, or groups of commits when everything is in one area.
+With --plan the split is scaffolded as a stack of new branches named <branch>-1, <branch>-2 and
+so on, each holding its files as they are on the branch. The branch itself is left unchanged.
+
+EXAMPLES:
+  sage pr size                          # Measure the current branch
+  sage pr size --base main              # Measure against main instead of the parent
+  sage pr size --plan                   # Create the suggested split as a stack")]
+    Size(PrSizeArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -86,11 +135,11 @@ pub struct PrCreateArgs {
     pub body: Option<String>,
 
     /// The base branch for the PR
-    #[clap(short, long)]
+    #[clap(long)]
     pub base_branch: Option<String>,
 
     /// The head branch for the PR
-    #[clap(short, long)]
+    #[clap(long)]
     pub head_branch: Option<String>,
 
     /// Toggle the PR as draft
@@ -100,6 +149,70 @@ pub struct PrCreateArgs {
     /// Use AI to generate title and body
     #[clap(short = 'a', long, default_value = "false")]
     pub ai: bool,
+
+    /// Append the branch's note from sage note to the body (default from pr.include_note)
+    #[clap(long)]
+    pub with_note: bool,
+
+    /// Append the TODOs this branch adds to the body as a task list
+    #[clap(long)]
+    pub with_todos: bool,
+
+    /// Suggest reviewers from CODEOWNERS and the history of the changed files, and request the ones picked
+    #[clap(long)]
+    pub suggest_reviewers: bool,
+
+    /// With --suggest-reviewers, request the top suggestions without asking, e.g. from a script
+    #[clap(long, requires = "suggest_reviewers")]
+    pub yes: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct PrCommentArgs {
+    /// The comment, or - to read it from stdin
+    pub message: Option<String>,
+
+    /// The PR to comment on, instead of the current branch's
+    #[clap(short, long)]
+    pub number: Option<u64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct PrEditArgs {
+    /// The PR to edit, instead of the current branch's
+    #[clap(short, long)]
+    pub number: Option<u64>,
+
+    /// The new title
+    #[clap(short, long)]
+    pub title: Option<String>,
+
+    /// The new body, or - to read it from stdin
+    #[clap(short, long)]
+    pub body: Option<String>,
+
+    /// Add a label
+    #[clap(long = "add-label", value_name = "LABEL")]
+    pub add_labels: Vec<String>,
+
+    /// Remove a label
+    #[clap(long = "remove-label", value_name = "LABEL")]
+    pub remove_labels: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct PrSizeArgs {
+    /// The branch to measure against, instead of the branch's parent
+    #[clap(long)]
+    pub base: Option<String>,
+
+    /// The branch to measure, instead of the current branch
+    #[clap(long)]
+    pub head: Option<String>,
+
+    /// Create the suggested split as a stack of new branches
+    #[clap(long)]
+    pub plan: bool,
 }
 
 impl Run for PrArgs {
@@ -108,6 +221,17 @@ impl Run for PrArgs {
             Some(PrCommands::Checkout(args)) => pr_checkout(args).await,
             Some(PrCommands::Status(args)) => pr_status(args).await,
             Some(PrCommands::Create(args)) => pr_create(args).await,
+            Some(PrCommands::Comment(args)) => app::pull_comment::pull_comment(args.number, args.message.clone()).await,
+            Some(PrCommands::Edit(args)) => {
+                let opts = app::pull_edit::EditOptions {
+                    title: args.title.clone(),
+                    body: args.body.clone(),
+                    add_labels: args.add_labels.clone(),
+                    remove_labels: args.remove_labels.clone(),
+                };
+                app::pull_edit::pull_edit(args.number, opts).await
+            }
+            Some(PrCommands::Size(args)) => app::pull_size::pull_size(args.base.clone(), args.head.clone(), args.plan),
             None => pr_status(&PrStatusArgs { pr_number: None }).await,
         }
     }
@@ -134,17 +258,19 @@ async fn pr_status(args: &PrStatusArgs) -> Result<()> {
 }
 
 async fn pr_create(args: &PrCreateArgs) -> Result<()> {
-    // Use interactive mode if any required fields are missing and AI is not enabled
-    let interactive = (args.title.is_none() || args.body.is_none()) && !args.ai;
-    
     app::pull_create::pull_create(
         args.title.clone(),
         args.body.clone(),
         args.base_branch.clone(),
         args.head_branch.clone(),
         args.draft.unwrap_or(false),
-        interactive,
         args.ai,
+        app::pull_create::Additions {
+            note: args.with_note || config::get_bool("pr.include_note", false),
+            todos: args.with_todos,
+            reviewers: args.suggest_reviewers,
+            reviewers_unasked: args.yes,
+        },
     )
     .await?;
     Ok(())
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use clap::Parser;
+use std::future::Future;
+use std::pin::Pin;
+
+use super::{Cli, Run};
+use crate::app;
+
+/// Run a sage command and show where its time went
+#[derive(Parser, Debug)]
+pub struct ProfileArgs {
+    /// The command to profile with its arguments, e.g. sync --continue
+    #[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+impl Run for ProfileArgs {
+    async fn run(&self) -> Result<()> {
+        let cli = Cli::parse_from(std::iter::once("sage").chain(self.command.iter().map(String::as_str)));
+        // The command can be any command, this one included, so it has to be boxed
+        let command: Pin<Box<dyn Future<Output = Result<()>>>> = Box::pin(cli.run_command());
+        app::profile::measure(&self.command.join(" "), command).await
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app::{self, purge::PurgeOptions};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct PurgeFileArgs {
+    /// File to remove from history
+    pub path: String,
+
+    /// Also rewrite history shared with the default branch
+    #[clap(long)]
+    pub shared: bool,
+
+    /// Force push rewritten branches without asking
+    #[clap(long)]
+    pub push: bool,
+
+    /// Close open pull requests for rewritten branches and open new ones
+    #[clap(long, requires = "push")]
+    pub recreate_prs: bool,
+
+    /// Skip confirmation prompts
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+impl Run for PurgeFileArgs {
+    async fn run(&self) -> Result<()> {
+        app::purge::purge_file(
+            &self.path,
+            PurgeOptions {
+                shared: self.shared,
+                push: self.push,
+                recreate_prs: self.recreate_prs,
+                yes: self.yes,
+            },
+        )
+        .await
+    }
+}
@@ -11,11 +11,26 @@ pub struct PushArgs {
     or amended commits and need to update the remote. Use with caution as it can overwrite
     changes others may have pushed.")]
     force: bool,
+
+    /// Switch origin between SSH and HTTPS when its credentials don't work
+    #[clap(long)]
+    switch_protocol: bool,
+
+    /// Push every branch in the current stack at once
+    #[clap(long)]
+    stack: bool,
+
+    /// Push even when there are WIP commits (see sage wip)
+    #[clap(long)]
+    allow_wip: bool,
 }
 
 impl Run for PushArgs {
     async fn run(&self) -> Result<()> {
-        app::push::push(self.force)?;
+        if self.stack {
+            return app::push::push_stack(self.force, self.switch_protocol, self.allow_wip).await;
+        }
+        app::push::push(self.force, self.switch_protocol, self.allow_wip)?;
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct RedoArgs {
+    /// Replay even if the branches have moved since they were restored
+    #[clap(long)]
+    pub force: bool,
+}
+
+impl Run for RedoArgs {
+    async fn run(&self) -> Result<()> {
+        app::undo::redo(self.force)
+    }
+}
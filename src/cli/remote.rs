@@ -0,0 +1,84 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use super::Run;
+use crate::{app, git::remote::Protocol};
+
+/// Manage the repository's remotes
+#[derive(Parser, Debug)]
+#[clap(after_help = "The primary remote (marked with *) is the one sage uses to find the repository on
+GitHub or GitLab. It defaults to origin, then upstream, then the first remote.")]
+pub struct RemoteArgs {
+    #[clap(subcommand)]
+    pub command: RemoteCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RemoteCommands {
+    /// List remotes with their URLs and protocols
+    List,
+    /// Add a remote after checking its URL
+    Add(RemoteAddArgs),
+    /// Point a remote at a new URL after checking it
+    SetUrl(RemoteSetUrlArgs),
+    /// Remove remote-tracking branches deleted on the remote
+    Prune(RemotePruneArgs),
+    /// Switch every remote to SSH or HTTPS URLs
+    Rewrite(RemoteRewriteArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct RemoteAddArgs {
+    /// Name of the remote, e.g. upstream
+    pub name: String,
+
+    /// URL of the remote
+    pub url: String,
+
+    /// Use this remote to find the repository on GitHub or GitLab
+    #[clap(long)]
+    pub primary: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct RemoteSetUrlArgs {
+    /// Name of the remote
+    pub name: String,
+
+    /// New URL for the remote
+    pub url: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct RemotePruneArgs {
+    /// Only prune this remote
+    pub name: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct RemoteRewriteArgs {
+    /// Protocol to switch remotes to
+    #[clap(value_enum)]
+    pub protocol: RewriteProtocol,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum RewriteProtocol {
+    Ssh,
+    Https,
+}
+
+impl Run for RemoteArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            RemoteCommands::List => app::remote::list(),
+            RemoteCommands::Add(args) => app::remote::add(&args.name, &args.url, args.primary),
+            RemoteCommands::SetUrl(args) => app::remote::set_url(&args.name, &args.url),
+            RemoteCommands::Prune(args) => app::remote::prune(args.name.as_deref()),
+            RemoteCommands::Rewrite(args) => app::remote::rewrite(match args.protocol {
+                RewriteProtocol::Ssh => Protocol::Ssh,
+                RewriteProtocol::Https => Protocol::Https,
+            }),
+        }
+    }
+}
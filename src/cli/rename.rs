@@ -0,0 +1,18 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct RenameArgs {
+    /// The branch's new name
+    pub name: String,
+}
+
+impl Run for RenameArgs {
+    async fn run(&self) -> Result<()> {
+        app::rename::rename(&self.name).await
+    }
+}
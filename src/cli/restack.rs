@@ -0,0 +1,19 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct RestackArgs {
+    /// List the branches that would be rebased, without rebasing them
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl Run for RestackArgs {
+    async fn run(&self) -> Result<()> {
+        app::restack::restack(self.dry_run)
+    }
+}
@@ -0,0 +1,15 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct ContinueArgs;
+
+impl Run for ContinueArgs {
+    async fn run(&self) -> Result<()> {
+        app::resume::resume()
+    }
+}
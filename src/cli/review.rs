@@ -0,0 +1,15 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct ReviewArgs;
+
+impl Run for ReviewArgs {
+    async fn run(&self) -> Result<()> {
+        app::review::review().await
+    }
+}
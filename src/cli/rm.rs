@@ -0,0 +1,23 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct RmArgs {
+    /// Files or directories to remove
+    #[clap(required = true)]
+    pub paths: Vec<String>,
+
+    /// Stop tracking the files but keep them on disk
+    #[clap(long)]
+    pub cached: bool,
+}
+
+impl Run for RmArgs {
+    async fn run(&self) -> Result<()> {
+        app::files::rm(&self.paths, self.cached)
+    }
+}
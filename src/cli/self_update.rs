@@ -0,0 +1,33 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use crate::update::{self, release::Channel};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct SelfUpdateArgs {
+    /// Release channel to follow from now on
+    #[clap(long, value_enum)]
+    pub channel: Option<UpdateChannel>,
+
+    /// Only check whether a newer version is available
+    #[clap(long)]
+    pub check: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum UpdateChannel {
+    Stable,
+    Nightly,
+}
+
+impl Run for SelfUpdateArgs {
+    async fn run(&self) -> Result<()> {
+        let channel = self.channel.map(|channel| match channel {
+            UpdateChannel::Stable => Channel::Stable,
+            UpdateChannel::Nightly => Channel::Nightly,
+        });
+        update::install::self_update(channel, self.check).await
+    }
+}
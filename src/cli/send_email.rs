@@ -0,0 +1,53 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+#[clap(after_help = "Sending is delegated to 'git send-email', so SMTP settings come from your git configuration:
+
+  git config --global sendemail.smtpServer smtp.example.com
+  git config --global sendemail.smtpUser you@example.com
+  git config --global sendemail.smtpEncryption tls
+  git config --global sendemail.smtpServerPort 587")]
+pub struct SendEmailArgs {
+    /// Recipient addresses (can be repeated)
+    #[clap(long)]
+    pub to: Vec<String>,
+
+    /// Carbon-copy addresses (can be repeated)
+    #[clap(long)]
+    pub cc: Vec<String>,
+
+    /// Send every commit in the stack, from the stack base up to the current branch
+    #[clap(long)]
+    pub stack: bool,
+
+    /// Use AI to write the cover letter
+    #[clap(short, long)]
+    pub ai: bool,
+
+    /// Don't generate a cover letter
+    #[clap(long)]
+    pub no_cover_letter: bool,
+
+    /// Show what would be sent without sending anything
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl Run for SendEmailArgs {
+    async fn run(&self) -> Result<()> {
+        app::send_email::send_email(&app::send_email::SendEmailOptions {
+            to: self.to.clone(),
+            cc: self.cc.clone(),
+            stack: self.stack,
+            ai: self.ai,
+            no_cover_letter: self.no_cover_letter,
+            dry_run: self.dry_run,
+        })
+        .await
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct SessionArgs {
+    #[clap(subcommand)]
+    pub command: SessionCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SessionCommands {
+    /// Save the current branch, its stack, sage's stashes and uncommitted changes
+    Save(SessionSaveArgs),
+    /// Pick up a saved session
+    Restore(SessionFileArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct SessionSaveArgs {
+    /// Write the session to a file
+    #[clap(long)]
+    pub file: Option<PathBuf>,
+
+    /// Push the session to origin without asking
+    #[clap(long, conflicts_with = "file")]
+    pub push: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct SessionFileArgs {
+    /// Use a file instead of the session saved on origin
+    #[clap(long)]
+    pub file: Option<PathBuf>,
+}
+
+impl Run for SessionArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            SessionCommands::Save(args) => app::session::save(args.file.as_deref(), args.push),
+            SessionCommands::Restore(args) => app::session::restore(args.file.as_deref()),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::Run;
+use crate::app;
+
+/// Look at what sage operations did to your branches
+#[derive(Parser, Debug)]
+pub struct SnapshotArgs {
+    #[clap(subcommand)]
+    pub command: SnapshotCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommands {
+    /// List the recorded operations and their ids
+    List,
+
+    /// Show how branches changed during one operation, or between two
+    #[clap(long_about = "Compares where every local branch pointed when operation FROM started with where they
+pointed when operation TO (or FROM itself) stopped, and lists the branches created, deleted and
+moved, with the commits each moved branch gained (+) and lost (-).
+
+EXAMPLES:
+  sage snapshot diff 12
+  sage snapshot diff 12 15")]
+    Diff(SnapshotDiffArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct SnapshotDiffArgs {
+    /// Id of the operation to start from, from sage snapshot list
+    pub from: u64,
+
+    /// Id of the operation to end with (defaults to FROM)
+    pub to: Option<u64>,
+}
+
+impl Run for SnapshotArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            SnapshotCommands::List => app::snapshot::list(),
+            SnapshotCommands::Diff(args) => app::snapshot::diff(args.from, args.to),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use super::Run;
+use crate::{app, ui::template::Template};
+
+/// Commands for working with stacked branches
+#[derive(Parser, Debug)]
+#[clap(after_help = "Stacks are built by starting branches on top of each other with 'sage start --parent'.
+Sage remembers each branch's parent so the whole stack can be handled as one unit.
+Without a subcommand, the stack is shown as it is by 'sage stack status'.", args_conflicts_with_subcommands = true)]
+pub struct StackArgs {
+    #[clap(subcommand)]
+    pub command: Option<StackCommands>,
+
+    #[clap(flatten)]
+    pub status: StackStatusArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StackCommands {
+    /// Show the branches in the current stack with their pull requests
+    #[clap(long_about = "Shows every branch in the current stack as a tree, along with how many commits it's ahead of and
+behind its parent, the state of its pull request, the combined result of its checks, whether it
+needs restacking onto its parent and its note from 'sage note'. 'sage stack' on its own does the
+same.
+
+Pull requests for the whole stack are fetched from GitHub in a single request.
+
+With --format each branch is rendered through a template instead. Branches have the fields
+name, parent, base, depth, current, ahead, behind, needs_restack, note and pr (number, state,
+draft, url, base, checks, conflicting). --json prints the stack as one JSON document for
+scripts: its base, the current branch and the branches, each with those same fields.
+
+EXAMPLES:
+  sage stack
+  sage stack status
+  sage stack --json | jq '.branches[] | select(.needs_restack) | .name'
+  sage stack status --format '{{name}}{{#if needs_restack}} (restack){{/if}}'")]
+    Status(StackStatusArgs),
+
+    /// Export the current stack as an ordered patch series
+    #[clap(long_about = "Writes every branch in the current stack as a numbered series of patches, one directory
+per branch, along with a stack.json manifest that records the branch boundaries and parents.
+
+The export can be reviewed offline or moved to another clone without pushing, and
+re-created there with 'sage stack apply'.
+
+EXAMPLES:
+  sage stack export                    # Writes to ./sage-stack-<branch>
+  sage stack export -o /tmp/my-stack")]
+    Export(StackExportArgs),
+
+    /// Re-create a stack from an exported patch series
+    #[clap(long_about = "Reads a directory written by 'sage stack export' and re-creates every branch in order,
+applying its patches with 'git am' on top of its parent and recording the stack relationship.
+
+Parents that are not part of the export (usually the default branch) must already exist locally.
+None of the exported branches may exist yet.
+
+EXAMPLES:
+  sage stack apply ./sage-stack-feature-login")]
+    Apply(StackApplyArgs),
+
+    /// Draw the current stack, or export it as a web page with --web
+    #[clap(long_about = "Draws the current stack as a tree in SVG, each branch with its pull request and the result of
+its checks. Dashed lines mark branches that need restacking onto their parent.
+
+With --web the drawing goes into a standalone HTML page along with a table of the branches,
+their pull requests and checks. It has no scripts and loads nothing else, so it can be attached
+to a design doc or shared with reviewers who don't use sage. It's written to
+sage-stack-<branch>.html unless you give -o.
+
+EXAMPLES:
+  sage stack visualize > stack.svg
+  sage stack visualize --web
+  sage stack visualize --web -o docs/login-stack.html")]
+    Visualize(StackVisualizeArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct StackExportArgs {
+    /// Directory to write the patch series to
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct StackApplyArgs {
+    /// Directory containing an exported stack
+    pub dir: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct StackVisualizeArgs {
+    /// Write a standalone HTML page instead of SVG
+    #[clap(long)]
+    pub web: bool,
+
+    /// File to write to, instead of stdout (or sage-stack-<branch>.html with --web)
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct StackStatusArgs {
+    /// Render each branch through a template, or @file to read the template from a file
+    #[clap(long, value_name = "TEMPLATE")]
+    pub format: Option<String>,
+
+    /// Print the stack as JSON, for scripts
+    #[clap(long, conflicts_with = "format")]
+    pub json: bool,
+}
+
+impl Run for StackArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            None => self.status.run().await,
+            Some(StackCommands::Status(args)) => args.run().await,
+            Some(StackCommands::Export(args)) => app::stack::export(args.output.clone()),
+            Some(StackCommands::Apply(args)) => app::stack::apply(&args.dir),
+            Some(StackCommands::Visualize(args)) => app::visualize::visualize(args.web, args.output.clone()).await,
+        }
+    }
+}
+
+impl Run for StackStatusArgs {
+    async fn run(&self) -> Result<()> {
+        let format = self.format.as_deref().map(Template::load).transpose()?;
+        app::stack::status(format.as_ref(), self.json).await
+    }
+}
@@ -28,12 +28,29 @@ pub struct StartArgs {
 If specified, the new branch will be created from this branch instead of the default branch."
     )]
     pub parent: Option<String>,
+
+    /// Start a branch with no history, e.g. for gh-pages or docs
+    #[clap(
+        long,
+        conflicts_with = "parent",
+        long_help = "Start a branch with no history and an empty tree, e.g. for gh-pages or docs. It gets an empty
+first commit and is recorded as a stack root, so other branches can be stacked on it."
+    )]
+    pub orphan: bool,
+
+    /// With --orphan, start it with a README instead of an empty commit, unless one is already there
+    #[clap(long, requires = "orphan")]
+    pub readme: bool,
 }
 
 impl Run for StartArgs {
     async fn run(&self) -> Result<()> {
-        app::start::start(&self.name)?;
-        println!("Successfully created branch: {}", self.name.sage());
+        let name = if self.orphan {
+            app::start::start_orphan(&self.name, self.readme)?
+        } else {
+            app::start::start(&self.name, self.parent.as_deref())?
+        };
+        println!("Successfully created branch: {}", name.sage());
         Ok(())
     }
 }
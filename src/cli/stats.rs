@@ -0,0 +1,61 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use super::Run;
+use crate::app::{self, stats::Format};
+
+/// Reports on the repository's history
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    #[clap(subcommand)]
+    pub command: StatsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StatsCommands {
+    /// Commits and merged pull requests by author over a time window
+    Team(StatsTeamArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct StatsTeamArgs {
+    /// Start of the window, e.g. 2w, 30d or 2026-10-01
+    #[clap(long, default_value = "2w")]
+    pub since: String,
+
+    /// End of the window, now by default
+    #[clap(long)]
+    pub until: Option<String>,
+
+    /// How to write the table
+    #[clap(long, value_enum, default_value = "markdown")]
+    pub format: ReportFormat,
+
+    /// Leave out bot accounts, e.g. dependabot[bot]
+    #[clap(long)]
+    pub exclude_bots: bool,
+
+    /// Leave out an author by name, email or GitHub login (repeatable)
+    #[clap(long, value_name = "AUTHOR")]
+    pub exclude: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Markdown,
+    Csv,
+}
+
+impl Run for StatsArgs {
+    async fn run(&self) -> Result<()> {
+        match &self.command {
+            StatsCommands::Team(args) => {
+                let format = match args.format {
+                    ReportFormat::Markdown => Format::Markdown,
+                    ReportFormat::Csv => Format::Csv,
+                };
+                app::stats::team(&args.since, args.until.as_deref(), format, args.exclude_bots, &args.exclude).await
+            }
+        }
+    }
+}
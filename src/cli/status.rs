@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crate::{app, cli::Run};
+use crate::{app, cli::Run, ui::template::Template};
 use clap::Parser;
 
 /// Command to display the current git repository status
@@ -25,12 +25,31 @@ COMBINED STATUS SYMBOLS:
 BRANCH INDICATORS:
   ↑n - n commits ahead of remote
   ↓n - n commits behind remote
-  $ - Stashed changes exist")]
-pub struct StatusArgs;
+  $ - Stashed changes exist
+
+Run sage status --legend for these and the check marks in your ui.theme.
+
+FORMAT FIELDS:
+  branch, upstream, ahead, behind, stash, clean, and the file lists staged, unstaged and
+  untracked, e.g. --format '{{branch}} +{{ahead}}{{#each staged}}\n  {{this}}{{/each}}'")]
+pub struct StatusArgs {
+    /// Render the status through a template, or @file to read the template from a file
+    #[clap(long, value_name = "TEMPLATE")]
+    pub format: Option<String>,
+
+    /// Explain the symbols sage uses, as the current ui.theme shows them
+    #[clap(long, conflicts_with = "format")]
+    pub legend: bool,
+}
 
 impl Run for StatusArgs {
     async fn run(&self) -> Result<()> {
-        app::status::status()?;
+        if self.legend {
+            app::status::legend();
+            return Ok(());
+        }
+        let format = self.format.as_deref().map(Template::load).transpose()?;
+        app::status::status(format.as_ref())?;
         Ok(())
     }
 }
\ No newline at end of file
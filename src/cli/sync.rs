@@ -1,6 +1,6 @@
 use anyhow::Result;
 use clap::Parser;
-use crate::{app, git};
+use crate::{app::{self, interrupt}, git};
 
 /// Arguments for the sync command
 #[derive(Parser, Debug)]
@@ -15,12 +15,28 @@ This command is particularly useful in these scenarios:
 
 The command automatically detects if your branch has diverged from the default branch
 (both ahead and behind) and uses rebase in that case to maintain a cleaner history.")]
-pub struct SyncArgs;
+pub struct SyncArgs {
+    /// Only sync when the branch is behind, or the remote has moved since it was last looked at
+    /// over sync.stale_minutes ago, e.g. from a shell startup script
+    #[clap(long)]
+    pub if_stale: bool,
+
+    /// For a fork: update the default branch from the upstream remote, push it to origin and
+    /// bring the current branch up to date with it
+    #[clap(long, conflicts_with = "if_stale")]
+    pub upstream: bool,
+}
 
 impl SyncArgs {
     pub async fn run(&self) -> Result<()> {
-        match app::sync::sync() {
+        if self.upstream {
+            return app::sync::sync_upstream();
+        }
+        let result = if self.if_stale { app::sync::sync_if_stale() } else { app::sync::sync() };
+        match result {
             Ok(_) => Ok(()),
+            // Interrupted syncs are recorded for sage continue, so leave everything where it stopped
+            Err(e) if interrupt::interrupted() => Err(e),
             Err(_) => {
                 // if there was an error doing this, we will try and give the user their changes back
                 // so as not to break their work.
@@ -0,0 +1,19 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct TodosArgs {
+    /// Print them as a markdown task list, ready to paste into a PR
+    #[clap(long)]
+    pub markdown: bool,
+}
+
+impl Run for TodosArgs {
+    async fn run(&self) -> Result<()> {
+        app::todos::todos(self.markdown)
+    }
+}
@@ -0,0 +1,15 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct UndoArgs;
+
+impl Run for UndoArgs {
+    async fn run(&self) -> Result<()> {
+        app::undo::undo()
+    }
+}
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct VerifyArgs {
+    /// Check the commits since this branch or commit (default the pull request's base in
+    /// GitHub Actions, otherwise the stack parent or default branch)
+    #[clap(long)]
+    pub base: Option<String>,
+
+    /// Print a report instead of the usual output
+    #[clap(long, value_enum)]
+    pub report: Option<ReportFormat>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Json,
+}
+
+impl Run for VerifyArgs {
+    async fn run(&self) -> Result<()> {
+        let json = matches!(self.report, Some(ReportFormat::Json));
+        app::verify::verify(self.base.clone(), json)
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app::{self, watch::WatchOptions};
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct WatchArgs {
+    /// Seconds between checks (at least 10)
+    #[clap(short, long)]
+    pub interval: Option<u64>,
+
+    /// Also show desktop notifications
+    #[clap(long)]
+    pub desktop: bool,
+}
+
+impl Run for WatchArgs {
+    async fn run(&self) -> Result<()> {
+        app::watch::watch(&WatchOptions {
+            interval: self.interval,
+            desktop: self.desktop,
+        })
+        .await
+    }
+}
@@ -0,0 +1,18 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::app;
+
+use super::Run;
+
+#[derive(Parser, Debug)]
+pub struct WipArgs {
+    /// What you were in the middle of, added after `wip: `
+    pub message: Option<String>,
+}
+
+impl Run for WipArgs {
+    async fn run(&self) -> Result<()> {
+        app::wip::wip(self.message.as_deref())
+    }
+}
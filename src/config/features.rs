@@ -0,0 +1,116 @@
+//! Experimental features, off until turned on
+//!
+//! A feature is turned on with `features.<name> = true` in the config, or for a single run by
+//! listing it in `SAGE_UNSTABLE` (comma-separated, or `all`). Commands behind a feature call
+//! [`require`] before doing anything, so they all fail the same way while it's off.
+
+use anyhow::{anyhow, Result};
+use std::env;
+
+use crate::config;
+
+/// Environment variable listing features to turn on for a single run
+pub const UNSTABLE_ENV: &str = "SAGE_UNSTABLE";
+
+/// An experimental subsystem that stays off unless asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// `sage review`: AI feedback on the current branch before opening a PR
+    AiReview,
+    /// `sage do`: turn a request in plain English into sage commands
+    AiDo,
+}
+
+impl Feature {
+    pub const ALL: &'static [Feature] = &[Feature::AiReview, Feature::AiDo];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::AiReview => "ai-review",
+            Feature::AiDo => "ai-do",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Feature::AiReview => "sage review: AI feedback on your branch before you open a PR",
+            Feature::AiDo => "sage do: ask for something in plain English and get sage commands to run",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Feature::ALL.iter().copied().find(|feature| feature.name() == name.trim())
+    }
+}
+
+/// Where a feature's state comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Listed in SAGE_UNSTABLE
+    Env,
+    /// Set with features.<name>
+    Config,
+    /// Not set anywhere, so off
+    Default,
+}
+
+/// state returns whether a feature is on, and what decided it. SAGE_UNSTABLE wins over the config.
+pub fn state(feature: Feature) -> (bool, Source) {
+    if unstable(&env::var(UNSTABLE_ENV).unwrap_or_default()).iter().any(|name| name == "all" || name == feature.name()) {
+        return (true, Source::Env);
+    }
+
+    match config::get(&format!("features.{}", feature.name())).and_then(|value| config::parse_bool(&value)) {
+        Some(enabled) => (enabled, Source::Config),
+        None => (false, Source::Default),
+    }
+}
+
+/// enabled returns whether a feature is turned on
+pub fn enabled(feature: Feature) -> bool {
+    state(feature).0
+}
+
+/// require stops a command behind a feature that isn't turned on
+pub fn require(feature: Feature) -> Result<()> {
+    if enabled(feature) {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "{} is experimental and turned off. Turn it on with 'sage features enable {}', or set {}={} for a single run",
+        feature.name(),
+        feature.name(),
+        UNSTABLE_ENV,
+        feature.name()
+    ))
+}
+
+/// unknown_unstable returns the names in SAGE_UNSTABLE that aren't features
+pub fn unknown_unstable() -> Vec<String> {
+    unstable(&env::var(UNSTABLE_ENV).unwrap_or_default())
+        .into_iter()
+        .filter(|name| name != "all" && Feature::from_name(name).is_none())
+        .collect()
+}
+
+/// Split a SAGE_UNSTABLE value into feature names
+fn unstable(value: &str) -> Vec<String> {
+    value
+        .split([',', ' '])
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unstable() {
+        assert_eq!(unstable("ai-review, Other"), vec!["ai-review", "other"]);
+        assert!(unstable(" ").is_empty());
+        assert_eq!(Feature::from_name("ai-review"), Some(Feature::AiReview));
+    }
+}
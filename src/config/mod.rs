@@ -0,0 +1,199 @@
+//! Sage configuration
+//!
+//! Settings are stored as flat `section.key` pairs in JSON files. The global file lives at
+//! `$SAGE_CONFIG` (or `<config dir>/sage/config.json`), and each repository can override it
+//! with `.git/sage/config.json`. Repository values always win over global ones. Underneath both
+//! are the settings of the org policy, when one has been synced (see [`crate::policy`]).
+
+pub mod features;
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::git;
+
+/// Settings sage understands, along with a short description
+pub const KNOWN_KEYS: &[(&str, &str)] = &[
+    ("ai.enabled", "Let sage send anything to the AI, usually turned off per repository with --local (true/false, default true)"),
+    ("ai.exclude_paths", "Comma-separated globs of files whose changes are never sent to the AI (default **/secrets/**,.env,.env.*,*.pem,*.key)"),
+    ("auth.sources", "Comma-separated order to look for a GitHub token in: env, keychain, gh (default env,keychain,gh)"),
+    ("auth.client_id", "Client ID of the GitHub OAuth app used by sage auth login --web"),
+    ("branch.template", "Template for branch names in sage start, with {{name}} the name given, e.g. feature/{{name}}; stacked branches see their parent's {{ticket}}"),
+    ("checkpoint.auto", "Take a checkpoint before sync, restack and clean (true/false, default true)"),
+    ("clean.merged_days", "Days since the last commit after which sage clean --policy removes a merged branch (default 30)"),
+    ("clean.stale_days", "Days without commits after which sage clean --policy removes a branch with no open pull request, merged or not (default never); sage list marks branches stale after it too (default 30 there)"),
+    ("clean.archive", "Have sage clean --policy archive branches under refs/sage/archive/ instead of deleting them (true/false, default false)"),
+    ("clean.protect", "Comma-separated globs of branches sage clean never removes, e.g. release/*,wip/*"),
+    ("clean.min_age_days", "Days a branch is left alone by sage clean after its last commit, whatever else it finds (default 0)"),
+    ("commit.empty_message", "Message for sage commit --retry-empty, a template with {{branch}}, {{ticket}} and the like (default chore: trigger ci [skip changelog])"),
+    ("commit.template", "Template commit messages are put through, with {{message}} the message given, e.g. {{#if ticket}}{{ticket}}: {{/if}}{{message}}"),
+    ("commit.empty_guard", "Empty commits while changes are unstaged: off, warn or block (default block)"),
+    ("commit.clock_guard", "Commit dates in the future, before their parent, or a clock behind the last commit: off, warn or block (default warn)"),
+    ("commit.signoff", "Add Signed-off-by to sage commits and require it on pushed commits (true/false)"),
+    ("features.*", "Turn an experimental feature on or off (true/false, default false), see sage features"),
+    ("gc.trash_days", "Days sage gc keeps deleted branch tips before removing them (default 30)"),
+    ("guard.binary", "Binary files over guard.binary_max_kb in a commit: off, warn or block"),
+    ("guard.binary_max_kb", "Size in KB above which binary files trip guard.binary (default 512)"),
+    ("guard.lockfile", "Lockfile changes without their manifest: off, warn or block"),
+    ("guard.generated", "Files matching guard.generated_paths in a commit: off, warn or block"),
+    ("guard.generated_paths", "Comma-separated globs of generated files, e.g. dist/**,*.pb.go"),
+    ("hooks.pre_commit", "Run pre-commit framework hooks before sage commit writes the message (true/false, default true)"),
+    ("hooks.husky", "Run husky's pre-commit hook before sage commit writes the message (true/false, default true)"),
+    ("inbox.repos", "Comma-separated owner/repo list sage inbox is limited to"),
+    ("inbox.orgs", "Comma-separated orgs or users sage inbox is limited to"),
+    ("keys.*", "Comma-separated keys for an action in sage's interactive screens, e.g. keys.quit = x,esc (see sage keys)"),
+    ("list.prs", "Have sage list look up each branch's pull request on GitHub without --prs (true/false, default false)"),
+    ("plugins.require_signed", "Refuse to install plugins without a valid minisign signature (true/false, default false)"),
+    ("plugins.trusted_keys", "Comma-separated minisign public keys of the plugin publishers to trust, e.g. RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"),
+    ("policy.source", "Org policy bundle sage policy sync installs: a git repository or a .tar.gz URL"),
+    ("policy.sha256", "SHA-256 a policy.source tarball must match before it's installed"),
+    ("policy.trusted_keys", "Comma-separated minisign public keys the policy bundle must be signed with; unsigned bundles are refused once it's set"),
+    ("pr.title_template", "Template for pull request titles in sage pr create, with {{title}} the title given, e.g. [{{ticket}}] {{title}}"),
+    ("pr.body_template", "Template for pull request descriptions in sage pr create, with {{body}} the description given"),
+    ("pr.include_note", "Append the branch's sage note to the description in sage pr create (true/false, default false)"),
+    ("pr.max_lines", "Lines changed above which sage pr create suggests splitting a pull request (default 400)"),
+    ("pr.max_files", "Files changed above which sage pr create suggests splitting a pull request (default 25)"),
+    ("pr.max_owners", "CODEOWNERS owners a change can need before sage status and sage pr create call it slow to review (default 3)"),
+    ("pr.max_areas", "Top-level directories or packages touched above which sage pr create suggests splitting (default 3)"),
+    ("push.probe", "Check credentials for the remote before pushing (true/false, default true)"),
+    ("sync.push", "Whether sage sync pushes a branch that's ahead of its upstream: auto, never or ask (default auto)"),
+    ("sync.stale_minutes", "Minutes after a fetch, or a look that found nothing new, before sage sync --if-stale looks at the remote again (default 60)"),
+    ("sync.strategy", "How sage sync brings in the default branch: auto, rebase, merge or ff-only (default git's pull.rebase and pull.ff, otherwise auto)"),
+    ("ui.accessible", "Screen-reader-friendly output: no colors or glyphs, numbered prompts instead of menus (true/false, default from SAGE_ACCESSIBLE)"),
+    ("ui.theme", "Glyphs and colors for status marks: default, or colorblind for shapes in blue and orange instead of green and red"),
+    ("ui.locale", "Language for sage's messages: en or de (default from SAGE_LANG, LC_ALL or LANG)"),
+    ("update.channel", "Releases sage self-update and the new version notice follow: stable or nightly (default stable)"),
+    ("verify.checks", "Comma-separated checks sage verify runs: commits, merges, stack, dco and secrets (default all, dco only when commit.signoff is on)"),
+    ("wip.skip_hooks", "Skip the commit hooks for sage wip commits (true/false, default true)"),
+    ("watch.interval", "Seconds between sage watch polls (default 60)"),
+    ("watch.desktop", "Show desktop notifications from sage watch (true/false)"),
+    ("identity.profile", "Identity profile to use, usually set per repository with --local"),
+    ("identity.*.name", "Author name for an identity profile"),
+    ("identity.*.email", "Author email for an identity profile"),
+    ("identity.*.signingkey", "Signing key for an identity profile"),
+    ("identity.*.orgs", "Comma-separated remote owners an identity profile is used for"),
+];
+
+/// A single layer of configuration values
+pub type Values = BTreeMap<String, String>;
+
+/// Which configuration file to read from or write to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Global,
+    Local,
+}
+
+/// global_path returns the location of the global config file
+pub fn global_path() -> Result<PathBuf> {
+    if let Ok(path) = env::var("SAGE_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let mut path = dirs::config_dir().ok_or_else(|| anyhow!("Could not find config directory"))?;
+    path.push("sage");
+    path.push("config.json");
+    Ok(path)
+}
+
+/// local_path returns the location of the config file for the current repository
+pub fn local_path() -> Result<PathBuf> {
+    let mut path = git::repo::git_dir()?;
+    path.push("sage");
+    path.push("config.json");
+    Ok(path)
+}
+
+fn path_for(scope: Scope) -> Result<PathBuf> {
+    match scope {
+        Scope::Global => global_path(),
+        Scope::Local => local_path(),
+    }
+}
+
+/// read loads a single configuration layer, treating a missing file as empty
+pub fn read(scope: Scope) -> Result<Values> {
+    let path = path_for(scope)?;
+    if !path.exists() {
+        return Ok(Values::new());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse config file {}", path.display()))
+}
+
+fn write(scope: Scope, values: &Values) -> Result<()> {
+    let path = path_for(scope)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(values)?)?;
+    Ok(())
+}
+
+/// load returns the effective configuration, with repository values overriding global ones and
+/// both overriding the org policy
+pub fn load() -> Result<Values> {
+    let mut values = crate::policy::config_defaults();
+    values.extend(read(Scope::Global)?);
+
+    // Outside of a repository there is simply no local layer
+    if git::repo::is_repo().unwrap_or(false) {
+        values.extend(read(Scope::Local)?);
+    }
+
+    Ok(values)
+}
+
+/// get returns the effective value for a key
+pub fn get(key: &str) -> Option<String> {
+    load().ok()?.remove(key)
+}
+
+/// get_bool returns the effective value for a key as a boolean
+pub fn get_bool(key: &str, default: bool) -> bool {
+    match get(key) {
+        Some(value) => parse_bool(&value).unwrap_or(default),
+        None => default,
+    }
+}
+
+/// set stores a value in the given scope
+pub fn set(key: &str, value: &str, scope: Scope) -> Result<()> {
+    let mut values = read(scope)?;
+    values.insert(key.to_string(), value.to_string());
+    write(scope, &values)
+}
+
+/// unset removes a value from the given scope, returning whether it was present
+pub fn unset(key: &str, scope: Scope) -> Result<bool> {
+    let mut values = read(scope)?;
+    let removed = values.remove(key).is_some();
+    if removed {
+        write(scope, &values)?;
+    }
+    Ok(removed)
+}
+
+/// is_known returns if sage understands a key, where `*` in a known key matches any single segment
+pub fn is_known(key: &str) -> bool {
+    KNOWN_KEYS.iter().any(|(known, _)| {
+        let known: Vec<&str> = known.split('.').collect();
+        let key: Vec<&str> = key.split('.').collect();
+        known.len() == key.len()
+            && known.iter().zip(&key).all(|(pattern, part)| *pattern == "*" || pattern == part)
+    })
+}
+
+/// Parse the boolean spellings git itself accepts
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
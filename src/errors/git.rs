@@ -28,7 +28,7 @@ pub enum GitError {
 /// Error type for GitHub API operations
 #[derive(Debug, Error)]
 pub enum GitHubError {
-    #[error("GitHub authentication failed: Please set GITHUB_TOKEN or SAGE_GITHUB_TOKEN environment variable")]
+    #[error("GitHub authentication failed: Run 'sage auth login' or set the SAGE_GITHUB_TOKEN environment variable")]
     AuthenticationError,
 
     #[error("GitHub API request failed: {0}")]
@@ -39,4 +39,7 @@ pub enum GitHubError {
 
     #[error("GitHub rate limit exceeded. Please wait or use an authenticated token")]
     RateLimitExceeded,
+
+    #[error("GitHub rate limit exceeded until {0}. Try again then, or use an authenticated token")]
+    RateLimitedUntil(String),
 }
@@ -0,0 +1,207 @@
+//! Progress events for tools that wrap sage
+//!
+//! With `--events ndjson`, sage writes a JSON object per line as a command runs: when it starts
+//! and finishes, when each phase of the work starts and finishes, every change it makes to a
+//! branch and any conflicts it stops on. Events go to `--events-fd` (stdout unless set), and the
+//! output meant for people moves to stderr so the two never mix. Like [`crate::profile`], emitting
+//! costs nothing unless events are turned on.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::ledger;
+
+/// Where events go, None unless they're turned on
+static SINK: Mutex<Option<File>> = Mutex::new(None);
+
+/// Something that happened while a command ran
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    CommandStarted {
+        command: &'a str,
+    },
+    CommandFinished {
+        command: &'a str,
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    PhaseStarted {
+        phase: &'a str,
+    },
+    PhaseFinished {
+        phase: &'a str,
+        ok: bool,
+        duration_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+    /// A branch was changed, or turned out not to need changing
+    ActionApplied {
+        operation: &'a str,
+        #[serde(flatten)]
+        step: &'a ledger::Step,
+    },
+    /// A rebase or merge stopped on conflicts, waiting for them to be resolved
+    Conflict {
+        operation: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        branch: Option<&'a str>,
+        onto: &'a str,
+        files: &'a [String],
+    },
+}
+
+/// start sends events to the file descriptor `fd`, and everything printed for people to stderr
+pub fn start(fd: i32) -> Result<()> {
+    let sink = open(fd)?;
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn open(fd: i32) -> Result<File> {
+    use std::io::{stderr, stdout};
+
+    let sink = match fd {
+        // Keep a copy of stdout for the events before pointing it at stderr
+        1 => File::from(rustix::io::dup(stdout())?),
+        2 => return Err(anyhow!("Events can't go to stderr, that's where the rest of the output goes")),
+        fd => std::fs::OpenOptions::new()
+            .append(true)
+            .open(format!("/dev/fd/{}", fd))
+            .map_err(|e| anyhow!("Can't write events to file descriptor {} ({}). Open it for sage, e.g. {}>events.ndjson", fd, e, fd))?,
+    };
+    rustix::stdio::dup2_stdout(stderr())?;
+    Ok(sink)
+}
+
+#[cfg(not(unix))]
+fn open(_fd: i32) -> Result<File> {
+    Err(anyhow!("--events is only supported on Unix-like systems"))
+}
+
+/// command_line is the command as it was typed, without the options for events
+pub fn command_line(args: impl IntoIterator<Item = String>) -> String {
+    let mut words = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--events" | "--events-fd" => {
+                args.next();
+            }
+            _ if arg.starts_with("--events=") || arg.starts_with("--events-fd=") => {}
+            _ => words.push(arg),
+        }
+    }
+    words.join(" ")
+}
+
+/// is_enabled returns if events are being written
+pub fn is_enabled() -> bool {
+    SINK.lock().map(|sink| sink.is_some()).unwrap_or(false)
+}
+
+/// emit writes an event, when they're turned on. A wrapper that stops reading doesn't stop sage.
+pub fn emit(event: Event) {
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = sink.as_mut() else {
+        return;
+    };
+    let _ = writeln!(file, "{}", line(&event, &chrono::Utc::now().to_rfc3339()));
+}
+
+/// The event as a line of JSON, with the time it happened
+fn line(event: &Event, time: &str) -> String {
+    let mut value = serde_json::to_value(event).unwrap_or(Value::Null);
+    if let Value::Object(fields) = &mut value {
+        fields.insert("time".to_string(), Value::String(time.to_string()));
+    }
+    value.to_string()
+}
+
+/// A phase of a command, reported finished when [`Phase::done`] is called and failed when it's
+/// dropped without that, e.g. when an error returns early
+#[must_use]
+pub struct Phase {
+    name: String,
+    started: Option<Instant>,
+}
+
+/// phase reports `name` as started, doing nothing unless events are on
+pub fn phase(name: impl Into<String>) -> Phase {
+    let started = is_enabled().then(Instant::now);
+    let name = if started.is_some() { name.into() } else { String::new() };
+    if started.is_some() {
+        emit(Event::PhaseStarted { phase: &name });
+    }
+    Phase { name, started }
+}
+
+impl Phase {
+    /// done reports the phase as finished successfully
+    pub fn done(self) {
+        self.finish(None);
+    }
+
+    /// fail reports the phase as failed with `reason`
+    pub fn fail(self, reason: &str) {
+        self.finish(Some(reason));
+    }
+
+    fn finish(mut self, error: Option<&str>) {
+        if let Some(started) = self.started.take() {
+            finished(&self.name, started, error);
+        }
+    }
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        if let Some(started) = self.started.take() {
+            finished(&self.name, started, Some("stopped before finishing"));
+        }
+    }
+}
+
+fn finished(phase: &str, started: Instant, error: Option<&str>) {
+    emit(Event::PhaseFinished { phase, ok: error.is_none(), duration_ms: started.elapsed().as_millis() as u64, error });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let time = "2026-10-16T09:30:00+00:00";
+        let event = Event::CommandFinished { command: "sync", ok: false, error: Some("conflicts".to_string()) };
+        let value: Value = serde_json::from_str(&line(&event, time)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "event": "command_finished", "command": "sync", "ok": false, "error": "conflicts", "time": time })
+        );
+
+        let step = ledger::Step {
+            branch: "ui".to_string(),
+            outcome: ledger::Outcome::Done,
+            before: Some("1a2b3c4".to_string()),
+            after: Some("5d6e7f8".to_string()),
+            error: None,
+        };
+        let value: Value = serde_json::from_str(&line(&Event::ActionApplied { operation: "restack", step: &step }, time)).unwrap();
+        assert_eq!(value["event"], "action_applied");
+        assert_eq!(value["branch"], "ui");
+        assert_eq!(value["outcome"], "done");
+        assert_eq!(value["after"], "5d6e7f8");
+
+        let args = ["sync", "--events", "ndjson", "--upstream", "--events-fd=3"].map(String::from);
+        assert_eq!(command_line(args), "sync --upstream");
+    }
+}
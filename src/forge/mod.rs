@@ -0,0 +1,118 @@
+//! Forge URLs
+//!
+//! Works out web URLs for the hosting service behind a remote, so commands can link to
+//! repositories, files, branches and CI on both GitHub and GitLab (including self-hosted ones).
+
+use anyhow::{anyhow, Result};
+
+use crate::git;
+
+/// The kind of service hosting a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    GitHub,
+    GitLab,
+}
+
+/// A repository on a forge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    pub kind: Kind,
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl Remote {
+    /// from_url builds a remote from an SSH or HTTPS clone URL
+    pub fn from_url(url: &str) -> Option<Remote> {
+        let (host, owner, repo) = git::repo::parse_remote_url(url)?;
+
+        // Self-hosted GitLab instances nearly always have gitlab in their hostname
+        let kind = if host.contains("gitlab") { Kind::GitLab } else { Kind::GitHub };
+        Some(Remote { kind, host, owner, repo })
+    }
+
+    /// origin returns the forge behind the primary remote (usually `origin`)
+    pub fn origin() -> Result<Remote> {
+        let url = git::remote::primary_url()?;
+        Remote::from_url(&url).ok_or_else(|| anyhow!("Could not work out the repository from {}", url))
+    }
+
+    /// repo_url links to the repository's home page
+    pub fn repo_url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.owner, self.repo)
+    }
+
+    /// branch_url links to a branch's tree
+    pub fn branch_url(&self, branch: &str) -> String {
+        match self.kind {
+            Kind::GitHub => format!("{}/tree/{}", self.repo_url(), branch),
+            Kind::GitLab => format!("{}/-/tree/{}", self.repo_url(), branch),
+        }
+    }
+
+    /// file_url links to a file at a commit, optionally highlighting a line range
+    pub fn file_url(&self, commit: &str, path: &str, lines: Option<(usize, Option<usize>)>) -> String {
+        let base = match self.kind {
+            Kind::GitHub => format!("{}/blob/{}/{}", self.repo_url(), commit, path),
+            Kind::GitLab => format!("{}/-/blob/{}/{}", self.repo_url(), commit, path),
+        };
+
+        match (self.kind, lines) {
+            (_, None) => base,
+            (_, Some((start, None))) => format!("{}#L{}", base, start),
+            (Kind::GitHub, Some((start, Some(end)))) => format!("{}#L{}-L{}", base, start, end),
+            (Kind::GitLab, Some((start, Some(end)))) => format!("{}#L{}-{}", base, start, end),
+        }
+    }
+
+    /// pull_request_url links to a pull (merge) request by number
+    pub fn pull_request_url(&self, number: u64) -> String {
+        match self.kind {
+            Kind::GitHub => format!("{}/pull/{}", self.repo_url(), number),
+            Kind::GitLab => format!("{}/-/merge_requests/{}", self.repo_url(), number),
+        }
+    }
+
+    /// ci_url links to the CI runs for a branch
+    pub fn ci_url(&self, branch: &str) -> String {
+        match self.kind {
+            Kind::GitHub => format!("{}/actions?query=branch%3A{}", self.repo_url(), branch),
+            Kind::GitLab => format!("{}/-/pipelines?ref={}", self.repo_url(), branch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_urls() {
+        let remote = Remote::from_url("git@github.com:acme/api.git").unwrap();
+        assert_eq!(remote.kind, Kind::GitHub);
+        assert_eq!(remote.repo_url(), "https://github.com/acme/api");
+        assert_eq!(
+            remote.file_url("abc123", "src/lib.rs", Some((42, None))),
+            "https://github.com/acme/api/blob/abc123/src/lib.rs#L42"
+        );
+        assert_eq!(
+            remote.file_url("abc123", "src/lib.rs", Some((10, Some(20)))),
+            "https://github.com/acme/api/blob/abc123/src/lib.rs#L10-L20"
+        );
+        assert_eq!(remote.pull_request_url(7), "https://github.com/acme/api/pull/7");
+    }
+
+    #[test]
+    fn test_gitlab_urls() {
+        let remote = Remote::from_url("https://gitlab.example.com/group/sub/app.git").unwrap();
+        assert_eq!(remote.kind, Kind::GitLab);
+        assert_eq!(remote.owner, "group/sub");
+        assert_eq!(
+            remote.file_url("abc123", "README.md", Some((1, Some(3)))),
+            "https://gitlab.example.com/group/sub/app/-/blob/abc123/README.md#L1-3"
+        );
+        assert_eq!(remote.ci_url("main"), "https://gitlab.example.com/group/sub/app/-/pipelines?ref=main");
+    }
+}
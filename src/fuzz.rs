@@ -0,0 +1,31 @@
+//! Entry points for the fuzz targets in fuzz/
+//!
+//! The parsers they exercise read text from git, the network or plugins and aren't public, so
+//! they're reached through here. Only built with `--cfg fuzzing`, which `cargo fuzz` sets.
+
+use crate::{app, git, plugin, policy};
+
+/// Records from `git log`, as read when checking for missing sign-offs
+pub fn commit_log(log: &str) {
+    let _ = git::commit::parse_missing_signoff(log);
+}
+
+/// A unified diff, as scanned for added TODOs
+pub fn diff(diff: &str) {
+    let _ = app::todos::parse_diff(diff);
+}
+
+/// A plugin's plugin.json
+pub fn plugin_manifest(contents: &str) {
+    let _ = plugin::parse_manifest(contents);
+}
+
+/// What a plugin prints in reply to a hook
+pub fn plugin_reply(output: &str) {
+    let _ = plugin::parse_reply(output);
+}
+
+/// An org policy bundle's policy.json
+pub fn policy(contents: &str) {
+    let _ = policy::parse(contents);
+}
@@ -0,0 +1,266 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{pulls::map_github_error, rate_limit, search::encode_query};
+use crate::{gh, profile::{self, Phase}};
+
+/// A single execution of a GitHub Actions workflow
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub name: Option<String>,
+    pub workflow_id: u64,
+    pub head_sha: String,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    pub html_url: String,
+}
+
+impl WorkflowRun {
+    /// is_failed returns if the run finished unsuccessfully
+    pub fn is_failed(&self) -> bool {
+        matches!(self.conclusion.as_deref(), Some("failure") | Some("timed_out") | Some("cancelled"))
+    }
+
+    /// is_completed returns if the run has finished, successfully or not
+    pub fn is_completed(&self) -> bool {
+        self.status.as_deref() == Some("completed")
+    }
+}
+
+/// An input declared under `on.workflow_dispatch.inputs` in a workflow file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkflowInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+    pub default: Option<String>,
+    /// string, boolean, choice, number or environment
+    pub kind: String,
+    /// Allowed values for `choice` inputs
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRuns {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+#[derive(Debug, Serialize)]
+struct DispatchRequest<'a> {
+    #[serde(rename = "ref")]
+    git_ref: &'a str,
+    inputs: &'a BTreeMap<String, String>,
+}
+
+/// runs_for_commit lists the latest run of every workflow for a commit on a branch
+pub async fn runs_for_commit(owner: &str, repo: &str, branch: &str, sha: &str) -> Result<Vec<WorkflowRun>> {
+    let route = format!(
+        "/repos/{}/{}/actions/runs?branch={}&head_sha={}&per_page=100",
+        owner,
+        repo,
+        encode_query(branch),
+        sha
+    );
+    let response = rate_limit::get_json::<WorkflowRuns>(&route).await?;
+
+    // Runs come back newest first, only keep the latest attempt per workflow
+    let mut latest: Vec<WorkflowRun> = Vec::new();
+    for run in response.workflow_runs {
+        if !latest.iter().any(|existing| existing.workflow_id == run.workflow_id) {
+            latest.push(run);
+        }
+    }
+
+    Ok(latest)
+}
+
+/// rerun starts a workflow run again, optionally only its failed jobs
+pub async fn rerun(owner: &str, repo: &str, run_id: u64, failed_only: bool) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "rerun workflow");
+    let action = if failed_only { "rerun-failed-jobs" } else { "rerun" };
+    let route = format!("/repos/{}/{}/actions/runs/{}/{}", owner, repo, run_id, action);
+    let response = gh::get_instance()
+        ._post(route, None::<&()>)
+        .await
+        .map_err(map_github_error)?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to re-run workflow run {}: {}", run_id, response.status()));
+    }
+
+    Ok(())
+}
+
+/// dispatch triggers a workflow with a `workflow_dispatch` trigger on a ref
+pub async fn dispatch(owner: &str, repo: &str, workflow: &str, git_ref: &str, inputs: &BTreeMap<String, String>) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "dispatch workflow");
+    let route = format!("/repos/{}/{}/actions/workflows/{}/dispatches", owner, repo, workflow);
+    let response = gh::get_instance()
+        ._post(route, Some(&DispatchRequest { git_ref, inputs }))
+        .await
+        .map_err(map_github_error)?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Failed to dispatch {}: {}. Does it have a workflow_dispatch trigger?",
+            workflow,
+            response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+/// parse_dispatch_inputs reads the `workflow_dispatch` inputs out of a workflow file.
+///
+/// This only understands the block-style YAML that workflow files are written in, which is
+/// all that's needed to build prompts.
+pub fn parse_dispatch_inputs(workflow: &str) -> Vec<WorkflowInput> {
+    let lines: Vec<(usize, &str)> = workflow
+        .lines()
+        .map(strip_comment)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| (line.len() - line.trim_start().len(), line.trim()))
+        .collect();
+
+    // Find `workflow_dispatch:` and then its `inputs:` block
+    let Some(dispatch) = lines.iter().position(|(_, line)| *line == "workflow_dispatch:") else {
+        return Vec::new();
+    };
+    let dispatch_indent = lines[dispatch].0;
+    let block_end = |from: usize, indent: usize| {
+        lines[from..]
+            .iter()
+            .position(|(line_indent, _)| *line_indent <= indent)
+            .map(|offset| from + offset)
+            .unwrap_or(lines.len())
+    };
+
+    let dispatch_end = block_end(dispatch + 1, dispatch_indent);
+    let Some(inputs) = (dispatch + 1..dispatch_end).find(|&i| lines[i].1 == "inputs:") else {
+        return Vec::new();
+    };
+    let inputs_end = block_end(inputs + 1, lines[inputs].0);
+
+    let mut parsed: Vec<WorkflowInput> = Vec::new();
+    let Some(&(input_indent, _)) = lines.get(inputs + 1).filter(|_| inputs + 1 < inputs_end) else {
+        return parsed;
+    };
+
+    let mut in_options = false;
+    for &(indent, line) in &lines[inputs + 1..inputs_end] {
+        if indent == input_indent {
+            parsed.push(WorkflowInput {
+                name: unquote(line.trim_end_matches(':')),
+                kind: "string".to_string(),
+                ..Default::default()
+            });
+            in_options = false;
+            continue;
+        }
+
+        let Some(input) = parsed.last_mut() else { continue };
+        if let Some(option) = line.strip_prefix("- ") {
+            if in_options {
+                input.options.push(unquote(option));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = unquote(value);
+        in_options = key == "options";
+        match key {
+            "description" => input.description = Some(value),
+            "required" => input.required = value == "true",
+            "default" => input.default = Some(value),
+            "type" => input.kind = value,
+            // Flow-style lists: `options: [a, b]`
+            "options" if value.starts_with('[') => {
+                input.options = value
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .map(unquote)
+                    .filter(|option| !option.is_empty())
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    parsed
+}
+
+fn strip_comment(line: &str) -> &str {
+    // A '#' only starts a comment at the beginning or after whitespace
+    match line.find(" #") {
+        Some(index) => &line[..index],
+        None if line.trim_start().starts_with('#') => "",
+        None => line,
+    }
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = r#"
+name: Deploy
+on:
+  push:
+    branches: [main]
+  workflow_dispatch:
+    inputs:
+      environment:
+        description: "Where to deploy"
+        required: true
+        type: choice
+        options:
+          - staging
+          - production
+      dry_run:
+        type: boolean
+        default: false # safety first
+      version:
+        description: Version to deploy
+jobs:
+  deploy:
+    runs-on: ubuntu-latest
+"#;
+
+    #[test]
+    fn test_parse_dispatch_inputs() {
+        let inputs = parse_dispatch_inputs(WORKFLOW);
+        assert_eq!(inputs.len(), 3);
+
+        assert_eq!(inputs[0].name, "environment");
+        assert_eq!(inputs[0].description.as_deref(), Some("Where to deploy"));
+        assert!(inputs[0].required);
+        assert_eq!(inputs[0].kind, "choice");
+        assert_eq!(inputs[0].options, vec!["staging", "production"]);
+
+        assert_eq!(inputs[1].kind, "boolean");
+        assert_eq!(inputs[1].default.as_deref(), Some("false"));
+
+        assert_eq!(inputs[2].kind, "string");
+        assert!(!inputs[2].required);
+    }
+
+    #[test]
+    fn test_parse_dispatch_inputs_without_inputs() {
+        assert!(parse_dispatch_inputs("on:\n  workflow_dispatch:\njobs:\n  a:\n").is_empty());
+        assert!(parse_dispatch_inputs("on: [push]\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_dispatch_inputs_flow_options() {
+        let inputs = parse_dispatch_inputs("on:\n  workflow_dispatch:\n    inputs:\n      level:\n        type: choice\n        options: [low, 'high']\n");
+        assert_eq!(inputs[0].options, vec!["low", "high"]);
+    }
+}
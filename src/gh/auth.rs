@@ -0,0 +1,402 @@
+//! GitHub token discovery and storage
+//!
+//! Tokens are looked up from the sources in `auth.sources` (by default the `SAGE_GITHUB_TOKEN` and
+//! `GITHUB_TOKEN` environment variables, then the OS keychain, then the GitHub CLI). The keychain
+//! entry is written by `sage auth login`. Tokens are never printed in full; use `redact` or
+//! `scrub` before showing anything that might contain one.
+
+use anyhow::{anyhow, Result};
+use octocrab::Octocrab;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::config;
+
+/// Keychain service and account the token is stored under
+const KEYCHAIN_SERVICE: &str = "sage";
+const KEYCHAIN_ACCOUNT: &str = "github.com";
+
+/// Lookup order used when `auth.sources` isn't set
+pub const DEFAULT_SOURCES: &str = "env,keychain,gh";
+
+/// Environment variables checked for a token, in order
+const ENV_VARS: &[&str] = &["SAGE_GITHUB_TOKEN", "GITHUB_TOKEN"];
+
+/// Prefixes GitHub puts on its token types, which are safe to show
+const TOKEN_PREFIXES: &[&str] = &["github_pat_", "ghp_", "gho_", "ghu_", "ghs_", "ghr_"];
+
+// Token resolved for this process, so every request (and every redaction) agrees on it
+static TOKEN: OnceLock<Option<(String, Source)>> = OnceLock::new();
+
+/// A place a GitHub token can come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Env,
+    Keychain,
+    GhCli,
+}
+
+impl Source {
+    fn parse(name: &str) -> Option<Source> {
+        match name.trim().to_lowercase().as_str() {
+            "env" => Some(Source::Env),
+            "keychain" => Some(Source::Keychain),
+            "gh" => Some(Source::GhCli),
+            _ => None,
+        }
+    }
+
+    /// lookup returns the token this source currently provides, if any
+    pub fn lookup(&self) -> Option<String> {
+        match self {
+            Source::Env => ENV_VARS
+                .iter()
+                .filter_map(|var| env::var(var).ok())
+                .find(|token| !token.trim().is_empty()),
+            Source::Keychain => keychain_token().ok().flatten(),
+            Source::GhCli => gh_cli_token(),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Env => write!(f, "environment"),
+            Source::Keychain => write!(f, "keychain"),
+            Source::GhCli => write!(f, "gh CLI"),
+        }
+    }
+}
+
+/// sources returns the configured lookup order, ignoring names sage doesn't know
+pub fn sources() -> Vec<Source> {
+    let configured = parse_sources(&config::get("auth.sources").unwrap_or_default());
+    if configured.is_empty() {
+        parse_sources(DEFAULT_SOURCES)
+    } else {
+        configured
+    }
+}
+
+fn parse_sources(value: &str) -> Vec<Source> {
+    let mut sources = Vec::new();
+    for source in value.split(',').filter_map(Source::parse) {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+/// token returns the token sage authenticates with and where it came from
+pub fn token() -> Option<(String, Source)> {
+    TOKEN
+        .get_or_init(|| {
+            sources()
+                .into_iter()
+                .find_map(|source| source.lookup().map(|token| (token.trim().to_string(), source)))
+        })
+        .clone()
+}
+
+/// Ask the GitHub CLI for its token, if it's installed and logged in
+fn gh_cli_token() -> Option<String> {
+    let output = Command::new("gh").args(["auth", "token"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() { None } else { Some(token) }
+}
+
+fn keychain_entry() -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| anyhow!("Failed to open the keychain: {}", e))
+}
+
+/// keychain_token returns the token saved by `sage auth login`, if any
+pub fn keychain_token() -> Result<Option<String>> {
+    match keychain_entry()?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!("Failed to read the GitHub token from the keychain: {}", e)),
+    }
+}
+
+/// store saves a token in the keychain
+pub fn store(token: &str) -> Result<()> {
+    keychain_entry()?
+        .set_password(token)
+        .map_err(|e| anyhow!("Failed to save the GitHub token to the keychain: {}", e))
+}
+
+/// forget removes the token from the keychain, returning whether there was one
+pub fn forget() -> Result<bool> {
+    match keychain_entry()?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(anyhow!("Failed to remove the GitHub token from the keychain: {}", e)),
+    }
+}
+
+/// The account a token belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub login: String,
+    /// OAuth scopes granted to the token (empty for fine-grained tokens)
+    pub scopes: Vec<String>,
+}
+
+/// verify checks a token against the API and returns who it belongs to
+pub async fn verify(token: &str) -> Result<Account> {
+    let client = Octocrab::builder()
+        .personal_token(token.to_string())
+        .build()
+        .map_err(|e| anyhow!("Failed to create GitHub client: {}", scrub(&e.to_string())))?;
+
+    let response = client
+        ._get("/user")
+        .await
+        .map_err(|e| anyhow!("Failed to reach GitHub: {}", scrub(&e.to_string())))?;
+    let status = response.status();
+    let scopes = response
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .map(|scope| scope.trim().to_string())
+                .filter(|scope| !scope.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let body = client
+        .body_to_string(response)
+        .await
+        .map_err(|e| anyhow!("Failed to read GitHub response: {}", e))?;
+
+    if status.as_u16() == 401 {
+        return Err(anyhow!("GitHub rejected the token. It may have expired or been revoked"));
+    }
+    if !status.is_success() {
+        return Err(anyhow!("GitHub returned {} when checking the token", status));
+    }
+
+    let user: serde_json::Value = serde_json::from_str(&body)?;
+    let login = user["login"]
+        .as_str()
+        .ok_or_else(|| anyhow!("GitHub did not say who the token belongs to"))?
+        .to_string();
+
+    Ok(Account { login, scopes })
+}
+
+/// Scopes requested by `sage auth login --web`: pull requests and checks on private repos,
+/// plus org membership for org-owned repositories
+pub const DEVICE_SCOPES: &str = "repo read:org";
+
+/// client_id returns the OAuth app used for device login: the `auth.client_id` setting, or
+/// the one baked in at build time with `SAGE_GITHUB_CLIENT_ID`
+pub fn client_id() -> Result<String> {
+    config::get("auth.client_id")
+        .or_else(|| option_env!("SAGE_GITHUB_CLIENT_ID").map(str::to_string))
+        .filter(|id| !id.trim().is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "No GitHub OAuth app is configured for browser login. Set auth.client_id to the client ID \
+                 of an OAuth app with device flow enabled, or use 'sage auth login' with a personal access token"
+            )
+        })
+}
+
+/// The codes GitHub hands out at the start of the device flow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceCode {
+    pub device_code: String,
+    /// Code the user types in at `verification_uri`
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until the codes expire
+    pub expires_in: u64,
+    /// Minimum seconds between polls
+    pub interval: u64,
+}
+
+/// Where a device login stands after a poll
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DevicePoll {
+    Token(String),
+    /// The user hasn't finished in the browser yet
+    Pending,
+    /// We're polling too quickly and should wait this many seconds between polls
+    SlowDown(u64),
+}
+
+/// request_device_code starts the OAuth device flow
+pub async fn request_device_code(client_id: &str) -> Result<DeviceCode> {
+    let fields = post_login(
+        "https://github.com/login/device/code",
+        &serde_json::json!({ "client_id": client_id, "scope": DEVICE_SCOPES }),
+    )
+    .await?;
+
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("GitHub's device code response is missing {}", name))
+    };
+    Ok(DeviceCode {
+        device_code: field("device_code")?,
+        user_code: field("user_code")?,
+        verification_uri: field("verification_uri")?,
+        expires_in: field("expires_in")?.parse().unwrap_or(900),
+        interval: field("interval")?.parse().unwrap_or(5),
+    })
+}
+
+/// poll_device_token asks whether the user has approved the device login yet
+pub async fn poll_device_token(client_id: &str, device_code: &str) -> Result<DevicePoll> {
+    let fields = post_login(
+        "https://github.com/login/oauth/access_token",
+        &serde_json::json!({
+            "client_id": client_id,
+            "device_code": device_code,
+            "grant_type": "urn:ietf:params:oauth:grant-type:device_code",
+        }),
+    )
+    .await?;
+
+    if let Some(token) = fields.get("access_token") {
+        return Ok(DevicePoll::Token(token.clone()));
+    }
+
+    match fields.get("error").map(String::as_str) {
+        Some("authorization_pending") => Ok(DevicePoll::Pending),
+        Some("slow_down") => Ok(DevicePoll::SlowDown(
+            fields.get("interval").and_then(|interval| interval.parse().ok()).unwrap_or(10),
+        )),
+        Some("expired_token") => Err(anyhow!("The login code expired. Run 'sage auth login --web' again")),
+        Some("access_denied") => Err(anyhow!("Login was cancelled in the browser")),
+        Some(error) => Err(anyhow!(
+            "GitHub login failed: {}",
+            fields.get("error_description").map(String::as_str).unwrap_or(error)
+        )),
+        None => Err(anyhow!("Unexpected response from GitHub while waiting for login")),
+    }
+}
+
+/// POST to one of github.com's OAuth endpoints, which answer with JSON or a form-encoded body
+/// depending on the Accept header
+async fn post_login(url: &str, body: &serde_json::Value) -> Result<HashMap<String, String>> {
+    let client = Octocrab::default();
+    let response = client
+        ._post(url, Some(body))
+        .await
+        .map_err(|e| anyhow!("Failed to reach GitHub: {}", e))?;
+    let body = client
+        .body_to_string(response)
+        .await
+        .map_err(|e| anyhow!("Failed to read GitHub response: {}", e))?;
+
+    Ok(parse_fields(&body))
+}
+
+/// Parse a flat JSON object or an `application/x-www-form-urlencoded` body into fields
+fn parse_fields(body: &str) -> HashMap<String, String> {
+    if let Ok(serde_json::Value::Object(object)) = serde_json::from_str(body) {
+        return object
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(value) => (key, value),
+                value => (key, value.to_string()),
+            })
+            .collect();
+    }
+
+    body.trim()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode_component(key), decode_component(value)))
+        .collect()
+}
+
+/// Undo percent-encoding (and `+` for spaces) in a form value
+fn decode_component(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    index += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// redact hides all but the type prefix and last four characters of a token
+pub fn redact(token: &str) -> String {
+    if token.len() < 12 {
+        return "****".to_string();
+    }
+
+    let prefix = TOKEN_PREFIXES
+        .iter()
+        .find(|prefix| token.starts_with(*prefix))
+        .unwrap_or(&"");
+    format!("{}…{}", prefix, token.get(token.len() - 4..).unwrap_or_default())
+}
+
+/// scrub replaces the token sage is using with its redacted form anywhere in `text`
+pub fn scrub(text: &str) -> String {
+    match TOKEN.get() {
+        Some(Some((token, _))) if !token.is_empty() => text.replace(token.as_str(), &redact(token)),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        assert_eq!(parse_sources("keychain, gh,env"), vec![Source::Keychain, Source::GhCli, Source::Env]);
+        assert_eq!(parse_sources("gh,bogus,gh"), vec![Source::GhCli]);
+        assert!(parse_sources("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let form = parse_fields("device_code=abc&user_code=WDJB-MJHT&verification_uri=https%3A%2F%2Fgithub.com%2Flogin%2Fdevice&interval=5");
+        assert_eq!(form["user_code"], "WDJB-MJHT");
+        assert_eq!(form["verification_uri"], "https://github.com/login/device");
+
+        let json = parse_fields(r#"{"error":"slow_down","interval":10}"#);
+        assert_eq!(json["error"], "slow_down");
+        assert_eq!(json["interval"], "10");
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(redact("ghp_abcdefghijklmnop1234"), "ghp_…1234");
+        assert_eq!(redact("github_pat_11ABCDEFG0123456789_wxyz"), "github_pat_…wxyz");
+        assert_eq!(redact("0123456789abcdef9876"), "…9876");
+        assert_eq!(redact("short"), "****");
+    }
+}
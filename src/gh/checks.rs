@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use super::{pulls::map_github_error, rate_limit};
+use crate::{gh, profile::{self, Phase}};
+
+/// The GitHub App behind a check suite or run
+#[derive(Debug, Clone, Deserialize)]
+pub struct App {
+    pub name: String,
+    pub slug: Option<String>,
+}
+
+/// A group of check runs created by one app for one commit
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckSuite {
+    pub id: u64,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub latest_check_runs_count: u64,
+    pub app: Option<App>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckSuiteRef {
+    pub id: u64,
+}
+
+/// A single check (e.g. one CI job) reported against a commit
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckRun {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub html_url: Option<String>,
+    pub details_url: Option<String>,
+    pub check_suite: Option<CheckSuiteRef>,
+    pub app: Option<App>,
+}
+
+impl CheckRun {
+    /// is_failed returns if the run finished unsuccessfully
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self.conclusion.as_deref(),
+            Some("failure") | Some("timed_out") | Some("cancelled") | Some("action_required")
+        )
+    }
+
+    /// is_actions_job returns if the run is a GitHub Actions job, whose logs can be downloaded
+    pub fn is_actions_job(&self) -> bool {
+        self.app.as_ref().and_then(|app| app.slug.as_deref()) == Some("github-actions")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckSuites {
+    check_suites: Vec<CheckSuite>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRuns {
+    check_runs: Vec<CheckRun>,
+}
+
+/// check_suites lists the check suites for a commit
+pub async fn check_suites(owner: &str, repo: &str, sha: &str) -> Result<Vec<CheckSuite>> {
+    let route = format!("/repos/{}/{}/commits/{}/check-suites?per_page=100", owner, repo, sha);
+    let response = rate_limit::get_json::<CheckSuites>(&route).await?;
+
+    Ok(response.check_suites)
+}
+
+/// check_runs lists the check runs for a commit
+pub async fn check_runs(owner: &str, repo: &str, sha: &str) -> Result<Vec<CheckRun>> {
+    let route = format!("/repos/{}/{}/commits/{}/check-runs?per_page=100", owner, repo, sha);
+    let response = rate_limit::get_json::<CheckRuns>(&route).await?;
+
+    Ok(response.check_runs)
+}
+
+/// rerequest_check_suite asks the app behind a check suite to run it again
+pub async fn rerequest_check_suite(owner: &str, repo: &str, suite_id: u64) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "rerequest check suite");
+    let route = format!("/repos/{}/{}/check-suites/{}/rerequest", owner, repo, suite_id);
+    let response = gh::get_instance()
+        ._post(route, None::<&()>)
+        .await
+        .map_err(map_github_error)?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to re-request check suite {}: {}", suite_id, response.status()));
+    }
+
+    Ok(())
+}
+
+/// check_run fetches a single check run
+pub async fn check_run(owner: &str, repo: &str, id: u64) -> Result<CheckRun> {
+    let route = format!("/repos/{}/{}/check-runs/{}", owner, repo, id);
+    rate_limit::get_json::<CheckRun>(&route).await
+}
+
+/// job_logs downloads the plain text log of a GitHub Actions job (the id of its check run)
+pub async fn job_logs(owner: &str, repo: &str, job_id: u64) -> Result<String> {
+    let _timing = profile::span(Phase::Network, "job logs");
+    let route = format!("/repos/{}/{}/actions/jobs/{}/logs", owner, repo, job_id);
+
+    // The API redirects to a short-lived download URL, which the client follows for us
+    let response = gh::get_instance()._get(route).await.map_err(map_github_error)?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Logs for job {} are not available yet ({})", job_id, response.status()));
+    }
+
+    gh::get_instance()
+        .body_to_string(response)
+        .await
+        .map_err(map_github_error)
+}
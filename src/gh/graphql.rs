@@ -0,0 +1,228 @@
+//! Batched pull request lookups over the GraphQL API
+//!
+//! Looking up pull requests one branch at a time over REST costs a round trip per branch. These
+//! helpers fetch the state, merge status and check rollup for up to 100 branches per request.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::rate_limit;
+use crate::{gh, git, profile::{self, Phase}};
+
+/// Most head refs we ask about in a single query
+pub const MAX_BRANCHES: usize = 100;
+
+/// Lifecycle state of a pull request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PrState {
+    Open,
+    Closed,
+    Merged,
+}
+
+/// The parts of a pull request sage shows next to a branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrSummary {
+    pub number: u64,
+    pub state: PrState,
+    pub is_draft: bool,
+    pub url: String,
+    pub base: String,
+    /// `MERGEABLE`, `CONFLICTING`, or `UNKNOWN` while GitHub is still working it out
+    pub mergeable: String,
+    /// Combined check state of the head commit (`SUCCESS`, `FAILURE`, `PENDING`, ...), if any ran
+    pub checks: Option<String>,
+}
+
+impl PrSummary {
+    pub fn is_conflicting(&self) -> bool {
+        self.mergeable == "CONFLICTING"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection {
+    nodes: Vec<PrNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrNode {
+    number: u64,
+    state: PrState,
+    is_draft: bool,
+    url: String,
+    base_ref_name: String,
+    mergeable: String,
+    head_repository_owner: Option<Login>,
+    commits: CommitConnection,
+}
+
+#[derive(Debug, Deserialize)]
+struct Login {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitConnection {
+    nodes: Vec<CommitNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitNode {
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Commit {
+    status_check_rollup: Option<Rollup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rollup {
+    state: String,
+}
+
+/// pull_requests_by_branch returns the most recent pull request for each branch that has one,
+/// keyed by branch name
+pub async fn pull_requests_by_branch(branches: &[String]) -> Result<HashMap<String, PrSummary>> {
+    let _timing = profile::span(Phase::Network, "pull requests by branch");
+    let (owner, repo) = git::repo::owner_repo()?;
+
+    let mut found = HashMap::new();
+    for chunk in branches.chunks(MAX_BRANCHES) {
+        let mut variables = json!({ "owner": owner, "repo": repo });
+        for (index, branch) in chunk.iter().enumerate() {
+            variables[format!("b{}", index)] = json!(branch);
+        }
+        let payload = json!({ "query": build_query(chunk.len()), "variables": variables });
+
+        let payload = &payload;
+        let response: Value =
+            rate_limit::with_retry(|| async move { gh::get_instance().graphql(payload).await }).await?;
+
+        for (branch, pr) in chunk.iter().zip(parse_response(&response, &owner, chunk.len())?) {
+            if let Some(pr) = pr {
+                found.insert(branch.clone(), pr);
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Build a query with one aliased `pullRequests` lookup per branch (`b0`, `b1`, ...)
+fn build_query(count: usize) -> String {
+    let variables = (0..count)
+        .map(|index| format!(", $b{}: String!", index))
+        .collect::<String>();
+    let lookups = (0..count)
+        .map(|index| {
+            format!(
+                "    b{0}: pullRequests(headRefName: $b{0}, first: 5, orderBy: {{field: CREATED_AT, direction: DESC}}) {{ nodes {{ ...pr }} }}\n",
+                index
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "query($owner: String!, $repo: String!{}) {{\n  repository(owner: $owner, name: $repo) {{\n{}  }}\n}}\n\
+         fragment pr on PullRequest {{\n  number state isDraft url baseRefName mergeable\n  \
+         headRepositoryOwner {{ login }}\n  commits(last: 1) {{ nodes {{ commit {{ statusCheckRollup {{ state }} }} }} }}\n}}",
+        variables, lookups
+    )
+}
+
+/// Pull the pull request for each aliased lookup out of a response, in branch order.
+///
+/// Pull requests opened from forks share branch names with ours, so only ones whose head lives
+/// under `owner` count.
+fn parse_response(response: &Value, owner: &str, count: usize) -> Result<Vec<Option<PrSummary>>> {
+    let repository = &response["data"]["repository"];
+    if repository.is_null() {
+        let messages = response["errors"]
+            .as_array()
+            .map(|errors| {
+                errors
+                    .iter()
+                    .filter_map(|error| error["message"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+            .unwrap_or_default();
+        return Err(anyhow!("GitHub GraphQL query failed: {}", messages));
+    }
+
+    (0..count)
+        .map(|index| {
+            let connection: Connection = serde_json::from_value(repository[format!("b{}", index)].clone())?;
+            Ok(connection
+                .nodes
+                .into_iter()
+                .find(|node| {
+                    node.head_repository_owner
+                        .as_ref()
+                        .is_some_and(|head| head.login.eq_ignore_ascii_case(owner))
+                })
+                .map(|node| PrSummary {
+                    number: node.number,
+                    state: node.state,
+                    is_draft: node.is_draft,
+                    url: node.url,
+                    base: node.base_ref_name,
+                    mergeable: node.mergeable,
+                    checks: node
+                        .commits
+                        .nodes
+                        .into_iter()
+                        .next()
+                        .and_then(|node| node.commit.status_check_rollup)
+                        .map(|rollup| rollup.state),
+                }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_query_declares_a_variable_per_branch() {
+        let query = build_query(2);
+        assert!(query.starts_with("query($owner: String!, $repo: String!, $b0: String!, $b1: String!)"));
+        assert!(query.contains("b1: pullRequests(headRefName: $b1, first: 5"));
+        assert!(!query.contains("$b2"));
+    }
+
+    #[test]
+    fn test_parse_response_skips_forks_and_missing_prs() {
+        let response = json!({
+            "data": { "repository": {
+                "b0": { "nodes": [
+                    { "number": 9, "state": "OPEN", "isDraft": false, "url": "u9", "baseRefName": "main",
+                      "mergeable": "MERGEABLE", "headRepositoryOwner": { "login": "someone-else" },
+                      "commits": { "nodes": [] } },
+                    { "number": 7, "state": "MERGED", "isDraft": false, "url": "u7", "baseRefName": "main",
+                      "mergeable": "UNKNOWN", "headRepositoryOwner": { "login": "Octo" },
+                      "commits": { "nodes": [ { "commit": { "statusCheckRollup": { "state": "SUCCESS" } } } ] } }
+                ] },
+                "b1": { "nodes": [] }
+            } }
+        });
+
+        let prs = parse_response(&response, "octo", 2).unwrap();
+        let first = prs[0].as_ref().unwrap();
+        assert_eq!(first.number, 7);
+        assert_eq!(first.state, PrState::Merged);
+        assert_eq!(first.checks.as_deref(), Some("SUCCESS"));
+        assert!(prs[1].is_none());
+
+        let failed = json!({ "data": { "repository": null }, "errors": [ { "message": "Not found" } ] });
+        assert!(parse_response(&failed, "octo", 1).unwrap_err().to_string().contains("Not found"));
+    }
+}
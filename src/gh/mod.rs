@@ -4,81 +4,55 @@
  * This module provides functionality for interacting with the GitHub API using
  * the octocrab crate. Authentication is handled in the following order:
  * 
- * 1. Check for SAGE_GITHUB_TOKEN environment variable
- * 2. Check for GITHUB_TOKEN environment variable
- * 3. Try to get token from GitHub CLI (gh auth token)
- * 4. Fall back to git credential helper via octocrab's default builder
+ * 1. Check the token sources in `auth.sources` (see the auth module), by default:
+ *    a. SAGE_GITHUB_TOKEN or GITHUB_TOKEN environment variables
+ *    b. The OS keychain, where `sage auth login` saves tokens
+ *    c. The GitHub CLI (gh auth token)
+ * 2. Fall back to git credential helper via octocrab's default builder
  * 
  * If all authentication methods fail, a warning is printed, and limited
  * functionality will be available (only public repositories/endpoints).
+ *
+ * In builds with the `vcr` feature, which the tests turn on, requests go to a local
+ * server that replays (or records) them instead when SAGE_GITHUB_CASSETTE is set, see
+ * the vcr module.
  */
 
+pub mod actions;
+pub mod auth;
+pub mod checks;
+pub mod graphql;
 pub mod pulls;
+pub mod rate_limit;
+pub mod repos;
+pub mod search;
+#[cfg(feature = "vcr")]
+pub mod vcr;
 
 use anyhow::{anyhow, Result};
 use octocrab::Octocrab;
-use std::env;
-use std::process::Command;
 use std::sync::OnceLock;
 
 // Global instance of authenticated Octocrab client
 static OCTOCRAB_INSTANCE: OnceLock<Octocrab> = OnceLock::new();
 
-/// Try to get GitHub token from environment variables
-fn get_token_from_env() -> Option<String> {
-    // Check for SAGE_GITHUB_TOKEN first (our custom env var)
-    if let Ok(token) = env::var("SAGE_GITHUB_TOKEN") {
-        return Some(token);
-    }
-    
-    // Then check for standard GITHUB_TOKEN
-    if let Ok(token) = env::var("GITHUB_TOKEN") {
-        return Some(token);
-    }
-    
-    None
-}
-
-/// Try to get GitHub token from gh CLI
-fn get_token_from_gh_cli() -> Option<String> {
-    // Check if gh CLI is installed and authenticated
-    let result = Command::new("gh")
-        .arg("auth")
-        .arg("token")
-        .output();
-    
-    match result {
-        Ok(output) => {
-            if output.status.success() {
-                // Convert the output to a string and trim whitespace
-                let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if !token.is_empty() {
-                    return Some(token);
-                }
-            }
-        }
-        Err(_) => {} // gh CLI not installed or other error
-    }
-    
-    None
-}
-
 /// Build Octocrab instance with available authentication
 fn build_octocrab() -> Result<Octocrab> {
-    // First try to get token from environment
-    if let Some(token) = get_token_from_env() {
-        return Octocrab::builder()
-            .personal_token(token)
-            .build()
-            .map_err(|e| anyhow!("Failed to authenticate with GitHub token: {}", e));
+    // Replayed requests need no token, and recorded ones are sent on with it by the vcr server
+    #[cfg(feature = "vcr")]
+    if let Some(uri) = vcr::base_uri()? {
+        let builder = Octocrab::builder()
+            .base_uri(uri)
+            .map_err(|e| anyhow!("Failed to set up {}: {}", vcr::CASSETTE_ENV, e))?;
+        return builder.build().map_err(|e| anyhow!("Failed to set up {}: {}", vcr::CASSETTE_ENV, e));
     }
-    
-    // Then try to get token from gh CLI
-    if let Some(token) = get_token_from_gh_cli() {
+
+    // Use the first token found in the configured sources
+    if let Some((token, source)) = auth::token() {
         return Octocrab::builder()
             .personal_token(token)
             .build()
-            .map_err(|e| anyhow!("Failed to authenticate with GitHub token from gh CLI: {}", e));
+            .map_err(|e| anyhow!("Failed to authenticate with GitHub token from {}: {}", source, e));
     }
     
     // Finally try to use git config credentials
@@ -92,8 +66,8 @@ pub fn get_instance() -> &'static Octocrab {
         match build_octocrab() {
             Ok(client) => client,
             Err(e) => {
-                eprintln!("Warning: GitHub authentication failed - {}", e);
-                eprintln!("Set GITHUB_TOKEN or SAGE_GITHUB_TOKEN environment variable for full functionality");
+                eprintln!("Warning: GitHub authentication failed - {}", auth::scrub(&e.to_string()));
+                eprintln!("Run 'sage auth login' or set SAGE_GITHUB_TOKEN for full functionality");
                 Octocrab::default()
             }
         }
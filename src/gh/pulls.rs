@@ -1,10 +1,10 @@
 use crate::errors::GitHubError;
-use crate::{gh, git};
+use crate::{gh, gh::{auth, rate_limit}, git, profile::{self, Phase}};
 use anyhow::Result;
 use octocrab::models::pulls::PullRequest;
 
 /// Maps octocrab errors to our custom GitHubError types
-fn map_github_error(err: octocrab::Error) -> anyhow::Error {
+pub(crate) fn map_github_error(err: octocrab::Error) -> anyhow::Error {
     // Convert the error to a string to check for specific error conditions
     let err_string = err.to_string();
 
@@ -15,30 +15,24 @@ fn map_github_error(err: octocrab::Error) -> anyhow::Error {
     } else if err_string.contains("403") || err_string.contains("rate limit") {
         GitHubError::RateLimitExceeded.into()
     } else {
-        GitHubError::RequestError(format!("GitHub API error: {}", err)).into()
+        GitHubError::RequestError(format!("GitHub API error: {}", auth::scrub(&err_string))).into()
     }
 }
 
 /// Gets a single pull request for a given repository
 pub async fn get_pull_request(owner: &str, repo: &str, pr_number: u64) -> Result<PullRequest> {
-    gh::get_instance()
-        .pulls(owner, repo)
-        .get(pr_number)
-        .await
-        .map_err(map_github_error)
+    let _timing = profile::span(Phase::Network, format!("pull request #{}", pr_number));
+    rate_limit::with_retry(|| async move { gh::get_instance().pulls(owner, repo).get(pr_number).await }).await
 }
 
 /// Lists all pull requests for a given repository
 pub async fn list_pull_requests(owner: &str, repo: &str) -> Result<Vec<PullRequest>> {
-    gh::get_instance()
-        .pulls(owner, repo)
-        .list()
-        .per_page(100)
-        .page(1u32)
-        .send()
-        .await
-        .map_err(map_github_error)
-        .map(|mut page| page.take_items())
+    let _timing = profile::span(Phase::Network, "list pull requests");
+    rate_limit::with_retry(|| async move {
+        gh::get_instance().pulls(owner, repo).list().per_page(100).page(1u32).send().await
+    })
+    .await
+    .map(|mut page| page.take_items())
 }
 
 /// Creates a new pull request for a given repository
@@ -51,6 +45,7 @@ pub async fn create_pull_request(
     body: &str,
     draft: bool,
 ) -> Result<PullRequest> {
+    let _timing = profile::span(Phase::Network, "create pull request");
     gh::get_instance()
         .pulls(owner, repo)
         .create(title, head, base)
@@ -61,18 +56,110 @@ pub async fn create_pull_request(
         .map_err(map_github_error)
 }
 
-/// Gets the PR number associated with a given branch
-pub async fn get_pr_number(owner: &str, repo: &str, branch: &str) -> Result<Option<u64>> {
-    // Use octocrab's head parameter to filter PRs by branch name directly
-    let pull_requests = gh::get_instance()
+/// Closes a pull request without merging it
+pub async fn close_pull_request(owner: &str, repo: &str, pr_number: u64) -> Result<PullRequest> {
+    let _timing = profile::span(Phase::Network, format!("close pull request #{}", pr_number));
+    gh::get_instance()
+        .pulls(owner, repo)
+        .update(pr_number)
+        .state(octocrab::params::pulls::State::Closed)
+        .send()
+        .await
+        .map_err(map_github_error)
+}
+
+/// Adds a comment to the conversation on a pull request, returning a link to it
+pub async fn comment(owner: &str, repo: &str, pr_number: u64, body: &str) -> Result<String> {
+    let _timing = profile::span(Phase::Network, format!("comment on #{}", pr_number));
+    // Only the link is needed, so skip octocrab's comment model and its many required fields
+    let route = format!("/repos/{}/{}/issues/{}/comments", owner, repo, pr_number);
+    let comment: serde_json::Value = gh::get_instance()
+        .post(route, Some(&serde_json::json!({ "body": body })))
+        .await
+        .map_err(map_github_error)?;
+    Ok(comment["html_url"].as_str().unwrap_or_default().to_string())
+}
+
+/// Asks people (by login) and teams (by slug, without the org) to review a pull request
+pub async fn request_reviewers(owner: &str, repo: &str, pr_number: u64, reviewers: &[String], teams: &[String]) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "request reviewers");
+    // GitHub answers with the whole pull request, which octocrab would try to read as a review
+    let route = format!("/repos/{}/{}/pulls/{}/requested_reviewers", owner, repo, pr_number);
+    let _: serde_json::Value = gh::get_instance()
+        .post(route, Some(&serde_json::json!({ "reviewers": reviewers, "team_reviewers": teams })))
+        .await
+        .map_err(map_github_error)?;
+    Ok(())
+}
+
+/// Gets the GitHub login of whoever authored a commit, or None when the commit's email isn't
+/// linked to an account
+pub async fn commit_author(owner: &str, repo: &str, sha: &str) -> Result<Option<String>> {
+    let _timing = profile::span(Phase::Network, "commit author");
+    let route = format!("/repos/{}/{}/commits/{}", owner, repo, sha);
+    let commit: serde_json::Value = gh::get_instance()
+        .get(route, None::<&()>)
+        .await
+        .map_err(map_github_error)?;
+    Ok(commit["author"]["login"].as_str().map(str::to_string))
+}
+
+/// Changes the title and/or body of a pull request, leaving whichever is None alone
+pub async fn update_pull_request(
+    owner: &str,
+    repo: &str,
+    pr_number: u64,
+    title: Option<&str>,
+    body: Option<&str>,
+) -> Result<PullRequest> {
+    let _timing = profile::span(Phase::Network, "update pull request");
+    gh::get_instance()
         .pulls(owner, repo)
-        .list()
-        .head(format!("{}:{}", owner, branch)) // Filter by head branch name
-        .per_page(10) // We likely only need a few results
+        .update(pr_number)
+        .title::<String>(title.map(str::to_string))
+        .body::<String>(body.map(str::to_string))
         .send()
         .await
-        .map_err(map_github_error)?
-        .take_items();
+        .map_err(map_github_error)
+}
+
+/// Adds labels to a pull request, creating any the repository doesn't have yet
+pub async fn add_labels(owner: &str, repo: &str, pr_number: u64, labels: &[String]) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "add labels");
+    gh::get_instance()
+        .issues(owner, repo)
+        .add_labels(pr_number, labels)
+        .await
+        .map_err(map_github_error)?;
+    Ok(())
+}
+
+/// Takes a label off a pull request
+pub async fn remove_label(owner: &str, repo: &str, pr_number: u64, label: &str) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "remove label");
+    gh::get_instance()
+        .issues(owner, repo)
+        .remove_label(pr_number, label)
+        .await
+        .map_err(map_github_error)?;
+    Ok(())
+}
+
+/// Gets the PR number associated with a given branch
+pub async fn get_pr_number(owner: &str, repo: &str, branch: &str) -> Result<Option<u64>> {
+    let _timing = profile::span(Phase::Network, format!("pull request for {}", branch));
+    // Use octocrab's head parameter to filter PRs by branch name directly
+    let pull_requests = rate_limit::with_retry(|| async move {
+        gh::get_instance()
+            .pulls(owner, repo)
+            .list()
+            .head(format!("{}:{}", owner, branch)) // Filter by head branch name
+            .per_page(10) // We likely only need a few results
+            .send()
+            .await
+    })
+    .await?
+    .take_items();
 
     // If we find a PR with the given branch, return its number
     if let Some(pr) = pull_requests.first() {
@@ -88,15 +175,18 @@ pub async fn get_timeline(
     repo: &str,
     pr_number: u64,
 ) -> Result<Vec<octocrab::models::repos::RepoCommit>> {
+    let _timing = profile::span(Phase::Network, "pull request timeline");
     // Get commits for the PR using the correct endpoint
-    let commits = gh::get_instance()
-        .pulls(owner, repo)
-        .pr_commits(pr_number)
-        .per_page(10) // Limit to 10 most recent commits
-        .send()
-        .await
-        .map_err(map_github_error)?
-        .take_items();
+    let commits = rate_limit::with_retry(|| async move {
+        gh::get_instance()
+            .pulls(owner, repo)
+            .pr_commits(pr_number)
+            .per_page(10) // Limit to 10 most recent commits
+            .send()
+            .await
+    })
+    .await?
+    .take_items();
 
     Ok(commits)
 }
@@ -104,24 +194,29 @@ pub async fn get_timeline(
 /// Gets the checks for a pull request
 pub async fn get_checks(owner: &str, repo: &str, pr_number: u64) -> Result<serde_json::Value> {
     // First, get the pull request to get the head SHA
-    let pr = gh::get_instance()
-        .pulls(owner, repo)
-        .get(pr_number)
-        .await
-        .map_err(map_github_error)?;
+    let pr = get_pull_request(owner, repo, pr_number).await?;
 
     let head_sha = pr.head.sha;
 
     // Use the http client directly to call the check-runs endpoint
     let route = format!("/repos/{}/{}/commits/{}/check-runs", owner, repo, head_sha);
-    let response = gh::get_instance()
-        .get::<serde_json::Value, _, ()>(&route, None)
-        .await
-        .map_err(map_github_error)?;
+    let response = rate_limit::get_json::<serde_json::Value>(&route).await?;
 
     Ok(response)
 }
 
+/// resolve_number returns `pr_number` when given, otherwise the number of the current branch's
+/// pull request
+pub async fn resolve_number(owner: &str, repo: &str, pr_number: Option<u64>) -> Result<u64> {
+    if let Some(number) = pr_number {
+        return Ok(number);
+    }
+    let branch = git::branch::current()?;
+    get_pr_number(owner, repo, &branch)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No pull request associated with the current branch '{}'", branch))
+}
+
 /// Gets a pull request by branch name
 pub async fn get_by_branch(branch: &str) -> Result<Option<PullRequest>> {
     // Get the owner and repo name from the remote URL
@@ -134,4 +229,23 @@ pub async fn get_by_branch(branch: &str) -> Result<Option<PullRequest>> {
         }
         None => Ok(None)
     }
-}
\ No newline at end of file
+}
+
+/// A submitted review on a pull request
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Review {
+    pub id: u64,
+    pub state: String,
+    pub user: Option<ReviewUser>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ReviewUser {
+    pub login: String,
+}
+
+/// Lists the reviews submitted on a pull request
+pub async fn list_reviews(owner: &str, repo: &str, pr_number: u64) -> Result<Vec<Review>> {
+    let route = format!("/repos/{}/{}/pulls/{}/reviews?per_page=100", owner, repo, pr_number);
+    rate_limit::get_json::<Vec<Review>>(&route).await
+}
@@ -0,0 +1,259 @@
+//! Rate limit handling for GitHub requests
+//!
+//! Tracks the remaining quota GitHub reports in response headers, waits out short rate limits
+//! (including secondary limits, which come with `retry-after`), and retries idempotent requests.
+//! When the wait would be too long, requests fail with a clear "rate limited until" error instead.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::pulls::map_github_error;
+use crate::errors::GitHubError;
+use crate::gh;
+use crate::profile::{self, Phase};
+
+/// How many times a request is retried after being rate limited
+const MAX_RETRIES: u32 = 3;
+
+/// Longest we're willing to sleep before a retry; longer limits are reported instead
+const MAX_WAIT: Duration = Duration::from_secs(90);
+
+/// The quota GitHub counts most requests against
+const CORE: &str = "core";
+
+/// An API quota as last reported by GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub remaining: u64,
+    pub reset: DateTime<Utc>,
+}
+
+/// The last quota reported for each resource (`x-ratelimit-resource`), since search, GraphQL and
+/// the rest of the API are limited separately
+static QUOTA: Mutex<BTreeMap<String, Quota>> = Mutex::new(BTreeMap::new());
+
+/// quota returns the last known quota of a resource like `core` or `search`, if any request has
+/// reported one
+pub fn quota(resource: &str) -> Option<Quota> {
+    QUOTA.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(resource).copied()
+}
+
+fn record(resource: &str, quota: Quota) {
+    QUOTA.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(resource.to_string(), quota);
+}
+
+/// The resource a route is counted against, before GitHub says so in its response
+fn resource_for(route: &str) -> &'static str {
+    if route.starts_with("/search/") {
+        "search"
+    } else if route.starts_with("/graphql") {
+        "graphql"
+    } else {
+        CORE
+    }
+}
+
+/// get_json performs a GET request against the API and deserializes the response, waiting out
+/// and retrying rate limits
+pub async fn get_json<T: DeserializeOwned>(route: &str) -> Result<T> {
+    let _timing = profile::span(Phase::Network, format!("GET {}", route));
+    let mut attempt = 0;
+    loop {
+        wait_for_quota(resource_for(route)).await?;
+
+        let response = gh::get_instance()._get(route).await.map_err(map_github_error)?;
+        let status = response.status();
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+        };
+
+        let remaining: Option<u64> = header("x-ratelimit-remaining").and_then(|value| value.parse().ok());
+        let reset: Option<i64> = header("x-ratelimit-reset").and_then(|value| value.parse().ok());
+        let retry_after: Option<u64> = header("retry-after").and_then(|value| value.parse().ok());
+        let reset_at = reset.and_then(|reset| Utc.timestamp_opt(reset, 0).single());
+        if let (Some(remaining), Some(reset)) = (remaining, reset_at) {
+            let resource = header("x-ratelimit-resource").unwrap_or_else(|| resource_for(route).to_string());
+            record(&resource, Quota { remaining, reset });
+        }
+
+        let body = gh::get_instance().body_to_string(response).await.map_err(map_github_error)?;
+        if status.is_success() {
+            return Ok(serde_json::from_str(&body)?);
+        }
+
+        let rate_limited = status.as_u16() == 429
+            || (status.as_u16() == 403 && (remaining == Some(0) || body.to_lowercase().contains("rate limit")));
+        if !rate_limited {
+            return Err(status_error(status.as_u16(), &body));
+        }
+
+        let wait = backoff(attempt, retry_after, remaining, reset, Utc::now().timestamp());
+        if attempt >= MAX_RETRIES || wait > MAX_WAIT {
+            return Err(limited_until(Utc::now() + chrono::Duration::from_std(wait)?));
+        }
+
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// with_retry runs an idempotent octocrab call, retrying it when GitHub rate limits it
+pub async fn with_retry<T, F, Fut>(mut call: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, octocrab::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        wait_for_quota(CORE).await?;
+
+        let err = match call().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        let message = err.to_string().to_lowercase();
+        let rate_limited = message.contains("rate limit") || message.contains("429");
+        if !rate_limited {
+            return Err(map_github_error(err));
+        }
+
+        // Typed calls don't expose headers, so ask GitHub when the quota resets
+        let wait = match current_limit().await {
+            Ok(quota) => {
+                record(CORE, quota);
+                backoff(attempt, None, Some(quota.remaining), Some(quota.reset.timestamp()), Utc::now().timestamp())
+            }
+            Err(_) => backoff(attempt, None, None, None, 0),
+        };
+
+        if attempt >= MAX_RETRIES || wait > MAX_WAIT {
+            return Err(limited_until(Utc::now() + chrono::Duration::from_std(wait)?));
+        }
+
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// Don't even send a request when we already know its resource's quota is used up
+async fn wait_for_quota(resource: &str) -> Result<()> {
+    let Some(quota) = quota(resource) else { return Ok(()) };
+    if quota.remaining > 0 {
+        return Ok(());
+    }
+
+    let wait = (quota.reset - Utc::now()).to_std().unwrap_or_default();
+    if wait > MAX_WAIT {
+        return Err(limited_until(quota.reset));
+    }
+
+    tokio::time::sleep(wait).await;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitResponse {
+    resources: RateLimitResources,
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitResources {
+    core: RateLimitResource,
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitResource {
+    remaining: u64,
+    reset: i64,
+}
+
+/// The /rate_limit endpoint doesn't count against the quota itself
+async fn current_limit() -> Result<Quota> {
+    let response = gh::get_instance()
+        .get::<RateLimitResponse, _, ()>("/rate_limit", None)
+        .await
+        .map_err(map_github_error)?;
+
+    let core = response.resources.core;
+    Ok(Quota {
+        remaining: core.remaining,
+        reset: Utc.timestamp_opt(core.reset, 0).single().unwrap_or_else(Utc::now),
+    })
+}
+
+/// Work out how long to wait before retrying a rate limited request
+fn backoff(attempt: u32, retry_after: Option<u64>, remaining: Option<u64>, reset: Option<i64>, now: i64) -> Duration {
+    // Secondary rate limits tell us exactly how long to wait
+    if let Some(seconds) = retry_after {
+        return Duration::from_secs(seconds);
+    }
+
+    // The primary quota is used up until it resets
+    if let (Some(0), Some(reset)) = (remaining, reset) {
+        return Duration::from_secs((reset - now).max(0) as u64 + 1);
+    }
+
+    // Otherwise back off exponentially, as GitHub recommends for secondary limits
+    Duration::from_secs(5 * 2u64.pow(attempt))
+}
+
+fn limited_until(until: DateTime<Utc>) -> anyhow::Error {
+    GitHubError::RateLimitedUntil(until.with_timezone(&Local).format("%H:%M:%S").to_string()).into()
+}
+
+fn status_error(status: u16, body: &str) -> anyhow::Error {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value.get("message")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string());
+
+    match status {
+        401 => GitHubError::AuthenticationError.into(),
+        404 => GitHubError::NotFound(message).into(),
+        _ => GitHubError::RequestError(format!("GitHub API error ({}): {}", status, message)).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_is_kept_per_resource() {
+        let reset = Utc::now();
+        record("search", Quota { remaining: 0, reset });
+        record(CORE, Quota { remaining: 4_000, reset });
+        assert_eq!(quota("search").map(|quota| quota.remaining), Some(0));
+        assert_eq!(quota(CORE).map(|quota| quota.remaining), Some(4_000));
+        assert_eq!(resource_for("/search/issues?q=is:pr"), "search");
+        assert_eq!(resource_for("/repos/o/r/pulls"), CORE);
+    }
+
+    #[test]
+    fn test_backoff_prefers_retry_after() {
+        assert_eq!(backoff(0, Some(30), Some(0), Some(1_000), 0), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_backoff_waits_for_reset() {
+        assert_eq!(backoff(0, None, Some(0), Some(1_060), 1_000), Duration::from_secs(61));
+        assert_eq!(backoff(0, None, Some(0), Some(900), 1_000), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff_is_exponential() {
+        assert_eq!(backoff(0, None, Some(10), Some(1_060), 1_000), Duration::from_secs(5));
+        assert_eq!(backoff(2, None, None, None, 0), Duration::from_secs(20));
+    }
+}
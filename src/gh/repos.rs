@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use super::{pulls::map_github_error, rate_limit};
+use crate::{gh, profile::{self, Phase}};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Owner {
+    pub login: String,
+}
+
+/// What the signed-in user may do with a repository
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Permissions {
+    #[serde(default)]
+    pub push: bool,
+}
+
+/// A repository as the API describes it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Repository {
+    pub name: String,
+    pub full_name: String,
+    pub owner: Owner,
+    pub clone_url: String,
+    pub ssh_url: String,
+    pub default_branch: String,
+    #[serde(default)]
+    pub fork: bool,
+    /// Only there when signed in
+    pub permissions: Option<Permissions>,
+}
+
+/// get fetches a repository
+pub async fn get(owner: &str, repo: &str) -> Result<Repository> {
+    rate_limit::get_json(&format!("/repos/{}/{}", owner, repo)).await
+}
+
+/// viewer returns the login of the signed-in user
+pub async fn viewer() -> Result<String> {
+    let user: serde_json::Value = rate_limit::get_json("/user").await?;
+    user["login"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("GitHub did not say who you're signed in as"))
+}
+
+/// fork forks a repository into the signed-in user's account, or returns the fork they already
+/// have. GitHub makes a new fork in the background, so it may be a little while before its
+/// contents can be cloned.
+pub async fn fork(owner: &str, repo: &str) -> Result<Repository> {
+    let _timing = profile::span(Phase::Network, "fork repository");
+    let route = format!("/repos/{}/{}/forks", owner, repo);
+    let fork: Repository = gh::get_instance()
+        .post(route, None::<&()>)
+        .await
+        .map_err(map_github_error)?;
+    Ok(fork)
+}
+
+/// rename_branch renames a branch on GitHub, which also moves the open pull requests that use it
+pub async fn rename_branch(owner: &str, repo: &str, branch: &str, new_name: &str) -> Result<()> {
+    let _timing = profile::span(Phase::Network, "rename branch");
+    let route = format!("/repos/{}/{}/branches/{}/rename", owner, repo, branch);
+    let _: serde_json::Value = gh::get_instance()
+        .post(route, Some(&serde_json::json!({ "new_name": new_name })))
+        .await
+        .map_err(map_github_error)?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use super::rate_limit;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchUser {
+    pub login: String,
+}
+
+/// An issue or pull request returned by the search API
+#[derive(Debug, Clone, Deserialize)]
+pub struct IssueItem {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    /// API URL of the repository, e.g. https://api.github.com/repos/owner/repo
+    pub repository_url: String,
+    pub created_at: DateTime<Utc>,
+    pub user: SearchUser,
+}
+
+impl IssueItem {
+    /// owner_repo returns the owner and name of the repository the item belongs to
+    pub fn owner_repo(&self) -> Option<(String, String)> {
+        let mut parts = self.repository_url.rsplit('/');
+        let repo = parts.next()?;
+        let owner = parts.next()?;
+        Some((owner.to_string(), repo.to_string()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    items: Vec<IssueItem>,
+}
+
+/// search_issues runs an issue/PR search query, e.g. `is:pr is:open review-requested:@me`
+pub async fn search_issues(query: &str) -> Result<Vec<IssueItem>> {
+    let route = format!("/search/issues?q={}&per_page=50&sort=created&order=asc", encode_query(query));
+    let response = rate_limit::get_json::<SearchResults>(&route).await?;
+
+    Ok(response.items)
+}
+
+/// search_all_issues runs a search like `search_issues`, following the pages to the end of the
+/// results (GitHub stops at 1000)
+pub async fn search_all_issues(query: &str) -> Result<Vec<IssueItem>> {
+    const PER_PAGE: usize = 100;
+    let mut items = Vec::new();
+    for page in 1..=10 {
+        let route = format!("/search/issues?q={}&per_page={}&page={}", encode_query(query), PER_PAGE, page);
+        let response = rate_limit::get_json::<SearchResults>(&route).await?;
+        let done = response.items.len() < PER_PAGE;
+        items.extend(response.items);
+        if done {
+            break;
+        }
+    }
+
+    Ok(items)
+}
+
+/// Percent-encode a search query, or any other value, for use in a URL's query
+pub(crate) fn encode_query(query: &str) -> String {
+    query
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_query() {
+        assert_eq!(encode_query("is:pr review-requested:@me"), "is%3Apr+review-requested%3A%40me");
+        assert_eq!(encode_query("fix/a&b#c+d%"), "fix%2Fa%26b%23c%2Bd%25");
+    }
+}
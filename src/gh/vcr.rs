@@ -0,0 +1,281 @@
+//! Record and replay GitHub API traffic
+//!
+//! When SAGE_GITHUB_CASSETTE names a JSON file, sage sends its API requests to a server on
+//! localhost instead of GitHub. By default that server answers from the file, so commands that
+//! talk to GitHub can be tested without a network or a token. With SAGE_GITHUB_RECORD=1 it passes
+//! each request on to GitHub and writes what came back into the file, scrubbed of the token.
+//!
+//! It's only built with the `vcr` feature. The tests turn that on, release builds leave it out.
+
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::gh::auth;
+
+/// Environment variable naming the cassette to replay or record
+pub const CASSETTE_ENV: &str = "SAGE_GITHUB_CASSETTE";
+/// Environment variable that switches from replaying to recording
+pub const RECORD_ENV: &str = "SAGE_GITHUB_RECORD";
+/// Where recorded requests are really sent
+const GITHUB_API: &str = "https://api.github.com";
+
+/// The requests a session made and what GitHub answered, in order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+/// A single request and its response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// Path and query, e.g. `/repos/acme/api/pulls/7`
+    pub path: String,
+    /// The JSON sent, for writes and GraphQL queries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    pub status: u16,
+    /// The JSON returned, or null for an empty response
+    #[serde(default)]
+    pub response: Value,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Cassette> {
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read cassette {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Failed to parse cassette {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, format!("{}\n", serde_json::to_string_pretty(self)?))?;
+        Ok(())
+    }
+}
+
+/// Hands out a cassette's interactions in order, each one once, except that the last match is
+/// repeated for requests made more often than when recording (e.g. polling)
+struct Player {
+    cassette: Cassette,
+    played: Vec<bool>,
+}
+
+impl Player {
+    fn new(cassette: Cassette) -> Player {
+        let played = vec![false; cassette.interactions.len()];
+        Player { cassette, played }
+    }
+
+    fn play(&mut self, method: &str, path: &str, body: Option<&Value>) -> Option<&Interaction> {
+        let matches = self
+            .cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                interaction.method.eq_ignore_ascii_case(method) && interaction.path == path && interaction.body.as_ref() == body
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        let index = matches.iter().copied().find(|index| !self.played[*index]).or(matches.last().copied())?;
+        self.played[index] = true;
+        Some(&self.cassette.interactions[index])
+    }
+}
+
+/// A request read off the connection
+struct Request {
+    method: String,
+    path: String,
+    body: Option<Value>,
+}
+
+/// base_uri starts the local server when SAGE_GITHUB_CASSETTE is set, returning the address to
+/// send API requests to in place of GitHub
+pub fn base_uri() -> Result<Option<String>> {
+    let Some(path) = std::env::var_os(CASSETTE_ENV).map(PathBuf::from) else {
+        return Ok(None);
+    };
+    let record = std::env::var(RECORD_ENV).is_ok_and(|value| value == "1" || value == "true");
+    let cassette = if record { Cassette::default() } else { Cassette::load(&path)? };
+    let player = Arc::new(Mutex::new(Player::new(cassette)));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    listener.set_nonblocking(true)?;
+    let address = listener.local_addr()?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let player = player.clone();
+            let path = path.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve(stream, &player, &path, record).await {
+                    eprintln!("{} {} {}: {}", "WARNING:".yellow(), CASSETTE_ENV, path.display(), e);
+                }
+            });
+        }
+    });
+
+    Ok(Some(format!("http://{}", address)))
+}
+
+async fn serve(mut stream: TcpStream, player: &Mutex<Player>, path: &Path, record: bool) -> Result<()> {
+    let request = read_request(&mut stream).await?;
+
+    let (status, response) = if record {
+        let (status, response) = forward(&request).await?;
+        let mut player = player.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        player.cassette.interactions.push(Interaction {
+            method: request.method.clone(),
+            path: request.path.clone(),
+            body: request.body.clone(),
+            status,
+            response: response.clone(),
+        });
+        player.cassette.save(path)?;
+        (status, response)
+    } else {
+        let mut player = player.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match player.play(&request.method, &request.path, request.body.as_ref()) {
+            Some(interaction) => (interaction.status, interaction.response.clone()),
+            // Not found rather than a server error, which clients would retry
+            None => (
+                404,
+                serde_json::json!({
+                    "message": format!(
+                        "{} has no response for {} {}{}",
+                        path.display(),
+                        request.method,
+                        request.path,
+                        request.body.as_ref().map(|body| format!(" with {}", body)).unwrap_or_default()
+                    )
+                }),
+            ),
+        }
+    };
+
+    let body = if response.is_null() { String::new() } else { response.to_string() };
+    let reply = format!(
+        "HTTP/1.1 {} VCR\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(reply.as_bytes()).await?;
+    Ok(())
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(anyhow!("Connection closed before the request was complete"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    // Some clients send the full URL rather than just the path
+    let path = match target.find("://") {
+        Some(scheme_end) => target[scheme_end + 3..].find('/').map_or("/", |start| &target[scheme_end + 3 + start..]),
+        None => target,
+    }
+    .to_string();
+
+    let length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    let body = if body.is_empty() { None } else { Some(serde_json::from_slice(&body)?) };
+    Ok(Request { method, path, body })
+}
+
+/// Send a request on to GitHub with the user's token, returning its status and scrubbed response
+async fn forward(request: &Request) -> Result<(u16, Value)> {
+    let mut command = tokio::process::Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--request", &request.method])
+        .args(["--header", "Accept: application/vnd.github+json", "--header", "User-Agent: sage"])
+        .args(["--write-out", "\n%{http_code}", "--data-binary", "@-"])
+        .arg(format!("{}{}", GITHUB_API, request.path))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some((token, _)) = auth::token() {
+        command.arg("--header").arg(format!("Authorization: Bearer {}", token));
+    }
+
+    let mut child = command.spawn().context("Failed to run curl, which recording needs")?;
+    let body = request.body.as_ref().map(Value::to_string).unwrap_or_default();
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to reach GitHub: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let output = auth::scrub(&String::from_utf8_lossy(&output.stdout));
+    let (response, status) = output.rsplit_once('\n').unwrap_or(("", output.as_str()));
+    let status = status.trim().parse().context("curl didn't report a status")?;
+    let response = if response.trim().is_empty() { Value::Null } else { serde_json::from_str(response)? };
+    Ok((status, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interaction(method: &str, path: &str, body: Option<Value>, status: u16) -> Interaction {
+        Interaction { method: method.to_string(), path: path.to_string(), body, status, response: Value::Null }
+    }
+
+    #[test]
+    fn test_player_replays_in_order() {
+        let query = serde_json::json!({ "query": "{ viewer { login } }" });
+        let mut player = Player::new(Cassette {
+            interactions: vec![
+                interaction("GET", "/repos/acme/api/pulls/7", None, 200),
+                interaction("GET", "/repos/acme/api/pulls/7", None, 201),
+                interaction("POST", "/graphql", Some(query.clone()), 202),
+            ],
+        });
+
+        assert_eq!(player.play("get", "/repos/acme/api/pulls/7", None).map(|i| i.status), Some(200));
+        assert_eq!(player.play("GET", "/repos/acme/api/pulls/7", None).map(|i| i.status), Some(201));
+        // Asked for more often than recorded, so the last answer is repeated
+        assert_eq!(player.play("GET", "/repos/acme/api/pulls/7", None).map(|i| i.status), Some(201));
+        assert_eq!(player.play("POST", "/graphql", Some(&query)).map(|i| i.status), Some(202));
+        assert!(player.play("POST", "/graphql", Some(&serde_json::json!({ "query": "{}" }))).is_none());
+        assert!(player.play("GET", "/repos/acme/api/pulls/8", None).is_none());
+    }
+}
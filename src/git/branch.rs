@@ -1,12 +1,11 @@
 use anyhow::{anyhow, Context, Result};
 use auth_git2::GitAuthenticator;
 use git2::{BranchType, Repository};
-use std::process::Command;
 use crate::git;
 
 /// current_branch returns the current branch name
 pub fn current() -> Result<String> {
-    let result = Command::new("git")
+    let result = git::command()
         .arg("rev-parse")
         .arg("--abbrev-ref")
         .arg("HEAD")
@@ -58,7 +57,7 @@ pub fn switch_new(branch_name: &str, create: bool) -> Result<()> {
 /// switch switches a branch, and will create it if required -- Returns current branch name
 pub fn switch(branch_name: &str, create: bool) -> Result<String> {
     let current_branch = current()?;
-    let mut cmd = Command::new("git");
+    let mut cmd = git::command();
     cmd.arg("switch");
     if create {
         cmd.arg("-c");
@@ -126,29 +125,19 @@ pub struct BranchInfo {
     pub is_current: bool,
 }
 
-/// list_with_info -- returns a list of branches with additional information
+/// list_with_info -- returns a list of branches with additional information, most recently
+/// committed to first. Ahead and behind counts come from the branch index where they can.
 pub fn list_with_info() -> Result<Vec<BranchInfo>> {
-    // Get the current branch first
     let current_branch = current()?;
-
-    // Get all branches
-    let branches = list()?;
-
-    // Create a result vector
-    let mut result = Vec::with_capacity(branches.len());
-
-    for branch in branches {
-        let (upstream, ahead, behind) = get_branch_tracking_info(&branch)?;
-
-        result.push(BranchInfo {
-            name: branch.clone(),
-            upstream,
-            ahead_count: ahead,
-            behind_count: behind,
-            is_current: branch == current_branch,
-        });
-    }
-
+    let branches = git::index::refs()?;
+
+    let mut index = git::index::Index::load();
+    let result = branches
+        .iter()
+        .map(|branch| index.info(branch, &current_branch))
+        .collect::<Result<Vec<_>>>()?;
+    // The index only saves time, so listing works without it
+    let _ = index.save(Some(&branches));
     Ok(result)
 }
 
@@ -156,7 +145,7 @@ pub fn list_with_info() -> Result<Vec<BranchInfo>> {
 /// Returns a tuple of (upstream_branch, ahead_count, behind_count)
 fn get_branch_tracking_info(branch: &str) -> Result<(Option<String>, usize, usize)> {
     // Get the upstream branch
-    let upstream_output = Command::new("git")
+    let upstream_output = git::command()
         .args([
             "for-each-ref",
             "--format=%(upstream:short)",
@@ -176,7 +165,7 @@ fn get_branch_tracking_info(branch: &str) -> Result<(Option<String>, usize, usiz
 
     // Now get ahead/behind counts
     let rev_list_args = format!("{}...{}", upstream_str, branch);
-    let count_output = Command::new("git")
+    let count_output = git::command()
         .args(["rev-list", "--left-right", "--count", &rev_list_args])
         .output()
         .context("Failed to get ahead/behind counts")?;
@@ -203,10 +192,16 @@ fn get_branch_tracking_info(branch: &str) -> Result<(Option<String>, usize, usiz
     Ok((Some(upstream_str), ahead, behind))
 }
 
+/// upstream returns the upstream tracking branch for a branch, if one is configured
+pub fn upstream(branch: &str) -> Result<Option<String>> {
+    let (upstream, _, _) = get_branch_tracking_info(branch)?;
+    Ok(upstream)
+}
+
 /// push will push the current branch to remote
 pub fn push(branch_name: &str, force: bool) -> Result<()> {
     // Create a git push command
-    let mut cmd = Command::new("git");
+    let mut cmd = git::command();
     cmd.arg("push")
        .arg("--set-upstream")
        .arg("origin")
@@ -232,6 +227,22 @@ pub fn push(branch_name: &str, force: bool) -> Result<()> {
     }
 }
 
+/// push_untracked pushes a branch to origin without recording an upstream. It leaves
+/// .git/config alone, so several can run at once; set the upstream with [`track`] afterwards.
+pub fn push_untracked(branch_name: &str, force: bool) -> Result<()> {
+    let lease = if force { "--force" } else { "--force-with-lease" };
+    let result = git::command().args(["push", lease, "origin", branch_name]).output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!(
+            "Failed to push branch: {}",
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
 /// exists returns if a branch exists
 pub fn exists(branch_name: &str) -> bool {
     let branches = list().unwrap_or(vec![]);
@@ -240,7 +251,7 @@ pub fn exists(branch_name: &str) -> bool {
 
 /// set_upstream with a specific refspec
 pub fn set_upstream(refspec: &str) -> Result<()> {
-    let result = Command::new("git")
+    let result = git::command()
         .arg("branch")
         .arg("--set-upstream-to")
         .arg(format!("origin/{}", refspec))
@@ -254,7 +265,7 @@ pub fn set_upstream(refspec: &str) -> Result<()> {
 
 /// merge will merge a specific branch into the current branch
 pub fn merge(branch_name: &str) -> Result<()> {
-    let result = Command::new("git").arg("merge").arg(branch_name).output()?;
+    let result = git::command().arg("merge").arg(branch_name).output()?;
 
     if result.status.success() {
         return Ok(());
@@ -266,9 +277,91 @@ pub fn merge(branch_name: &str) -> Result<()> {
     ))
 }
 
+/// fast_forward moves the current branch up to another, failing when it would need a merge commit
+pub fn fast_forward(branch_name: &str) -> Result<()> {
+    let result = git::command().args(["merge", "--ff-only", branch_name]).output()?;
+
+    if result.status.success() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Can't fast-forward to {}: {}",
+        branch_name,
+        String::from_utf8_lossy(&result.stderr).trim()
+    ))
+}
+
+/// fast_forward_branch moves a branch that isn't checked out up to `target`, refusing when it
+/// has commits `target` doesn't
+pub fn fast_forward_branch(branch_name: &str, target: &str) -> Result<()> {
+    let refspec = format!("{}:refs/heads/{}", target, branch_name);
+    let result = git::command().args(["fetch", "--quiet", ".", &refspec]).output()?;
+
+    if result.status.success() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Can't fast-forward {} to {}: {}",
+        branch_name,
+        target,
+        String::from_utf8_lossy(&result.stderr).trim()
+    ))
+}
+
+/// switch_orphan switches to a new branch with no history and an empty index and working tree
+/// (untracked files are left alone)
+pub fn switch_orphan(branch_name: &str) -> Result<()> {
+    let result = git::command().args(["switch", "--orphan", branch_name]).output()?;
+
+    if result.status.success() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Failed to create orphan branch {}: {}",
+        branch_name,
+        String::from_utf8_lossy(&result.stderr).trim()
+    ))
+}
+
+/// has_own_commits returns if anything was ever committed to `branch` locally, going by its
+/// reflog. A branch that was only created, or renamed, hasn't, whatever commit it points at. Without
+/// a reflog, e.g. once it has expired, there's no telling, so it's taken to have some.
+pub fn has_own_commits(branch: &str) -> bool {
+    let reflog = format!("refs/heads/{}", branch);
+    let Ok(result) = git::command().args(["reflog", "show", "--format=%gs", &reflog, "--"]).output() else {
+        return true;
+    };
+    let entries = String::from_utf8_lossy(&result.stdout).to_string();
+    if !result.status.success() || entries.trim().is_empty() {
+        return true;
+    }
+    entries
+        .lines()
+        .any(|entry| !entry.starts_with("branch: Created from") && !entry.starts_with("Branch: renamed"))
+}
+
+/// leave_orphan goes back to `rev` from an orphan branch that couldn't be started, dropping
+/// anything staged for it, and deletes the orphan branch if its first commit was made
+pub fn leave_orphan(orphan: &str, rev: &str) -> Result<()> {
+    let result = git::command().args(["checkout", "--quiet", "--force", rev]).output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!("Failed to switch back to {}: {}", rev, String::from_utf8_lossy(&result.stderr).trim()));
+    }
+
+    // The branch only exists once something has been committed to it
+    if exists(orphan) {
+        delete_local(orphan)?;
+    }
+    Ok(())
+}
+
 /// rebase will rebase a specific branch onto the current branch
 pub fn rebase(branch_name: &str) -> Result<()> {
-    let result = Command::new("git")
+    let result = git::command()
         .arg("rebase")
         .arg(branch_name)
         .arg("--autostash")
@@ -284,9 +377,26 @@ pub fn rebase(branch_name: &str) -> Result<()> {
     ))
 }
 
+/// continue_rebase carries on with a rebase after conflicts were resolved, keeping commit messages as they are
+pub fn continue_rebase() -> Result<()> {
+    let output = git::command()
+        .args(["rebase", "--continue"])
+        .env("GIT_EDITOR", "true")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to continue rebase: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
 /// List conflicting files within the branch
 pub fn conflicting_files() -> Result<Vec<String>> {
-    let output = Command::new("git")
+    let output = git::command()
         .arg("diff")
         .arg("--name-only")
         .arg("--diff-filter=U")
@@ -304,13 +414,15 @@ pub fn conflicting_files() -> Result<Vec<String>> {
 
 /// Delete a local branch
 pub fn delete_local(branch_name: &str) -> Result<()> {
-    let result = Command::new("git")
+    let result = git::command()
         .arg("branch")
         .arg("-D")  // Force delete
         .arg(branch_name)
         .output()?;
 
     if result.status.success() {
+        // A fork's remote goes with the last branch checked out from it
+        let _ = git::remote::remove_unused_forks();
         Ok(())
     } else {
         Err(anyhow!(
@@ -320,9 +432,25 @@ pub fn delete_local(branch_name: &str) -> Result<()> {
     }
 }
 
+/// rename renames a local branch, taking its config (upstream, stack parent, note) with it
+pub fn rename(branch_name: &str, new_name: &str) -> Result<()> {
+    let result = git::command().args(["branch", "-m", branch_name, new_name]).output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!(
+            "Failed to rename {} to {}: {}",
+            branch_name,
+            new_name,
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
 /// Delete a remote branch
 pub fn delete_remote(branch_name: &str) -> Result<()> {
-    let result = Command::new("git")
+    let result = git::command()
         .arg("push")
         .arg("origin")
         .arg("--delete")
@@ -339,13 +467,47 @@ pub fn delete_remote(branch_name: &str) -> Result<()> {
     }
 }
 
+/// create_at creates a branch pointing at a commit, without switching to it
+pub fn create_at(branch_name: &str, rev: &str) -> Result<()> {
+    let result = git::command()
+        .args(["branch", branch_name, rev])
+        .output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!(
+            "Failed to create branch {}: {}",
+            branch_name,
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// track sets the upstream of a branch, e.g. `origin/feature`
+pub fn track(branch_name: &str, upstream: &str) -> Result<()> {
+    let result = git::command()
+        .args(["branch", "--set-upstream-to", upstream, branch_name])
+        .output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!(
+            "Failed to set the upstream of {}: {}",
+            branch_name,
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn needs_push() -> Result<bool> {
     let status = git::status::status()?;
     Ok(status.needs_push())
 }
 
 pub fn abort_rebase() -> Result<()> {
-    let output = Command::new("git")
+    let output = git::command()
         .args(["rebase", "--abort"])
         .output()?;
 
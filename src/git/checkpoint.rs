@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::process::Stdio;
+
+/// Namespace for the refs that keep checkpointed commits from being garbage collected.
+/// Each checkpoint gets `refs/sage/checkpoints/<id>/heads/<branch>` and `.../worktree`.
+pub const CHECKPOINT_PREFIX: &str = "refs/sage/checkpoints/";
+
+/// branch_tips returns every local branch with the commit it points at
+pub fn branch_tips() -> Result<Vec<(String, String)>> {
+    let output = super::command()
+        .args(["for-each-ref", "--format=%(refname:short) %(objectname)", "refs/heads/"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list branches: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.rsplit_once(' '))
+        .map(|(name, oid)| (name.to_string(), oid.to_string()))
+        .collect())
+}
+
+/// stash_create records staged and unstaged changes to tracked files as a stash commit without
+/// touching the working tree, returning None when there are none
+pub fn stash_create() -> Result<Option<String>> {
+    let output = super::command().args(["stash", "create"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to save the working tree: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let oid = String::from_utf8(output.stdout)?.trim().to_string();
+    Ok((!oid.is_empty()).then_some(oid))
+}
+
+/// stash_apply puts the changes of a stash commit back into the working tree
+pub fn stash_apply(oid: &str) -> Result<()> {
+    let output = super::command().args(["stash", "apply", oid]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to restore the working tree: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// update_ref points a ref at a commit, creating it if needed
+pub fn update_ref(name: &str, oid: &str) -> Result<()> {
+    let output = super::command().args(["update-ref", name, oid]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to update {}: {}", name, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// delete_refs removes every ref under a prefix
+pub fn delete_refs(prefix: &str) -> Result<()> {
+    let output = super::command()
+        .args(["for-each-ref", "--format=delete %(refname)", prefix])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list {}: {}", prefix, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let mut child = super::command()
+        .args(["update-ref", "--stdin"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&output.stdout)?;
+    }
+    let result = child.wait_with_output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!("Failed to delete {}: {}", prefix, String::from_utf8_lossy(&result.stderr)));
+    }
+
+    Ok(())
+}
+
+/// reset_hard discards changes to tracked files. Untracked files are left alone.
+pub fn reset_hard() -> Result<()> {
+    let output = super::command().args(["reset", "--hard", "--quiet"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to reset the working tree: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// detach checks out the current commit without a branch, so every branch can be moved
+pub fn detach() -> Result<()> {
+    let output = super::command().args(["checkout", "--quiet", "--detach"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to detach HEAD: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
@@ -1,9 +1,13 @@
 use anyhow::{anyhow, Result};
-use std::process::Command;
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+
+/// Message of the temporary commit sync uses to carry uncommitted changes
+pub const WIP_MESSAGE: &str = "[SAGE WIP] Temporary commit for sync";
 
 /// list_branches returns a list of all local branches
 pub fn list_branches() -> Result<Vec<String>> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("branch")
         .arg("--sort=-committerdate")
         .arg("--format=%(refname:short)")
@@ -21,7 +25,7 @@ pub fn list_branches() -> Result<Vec<String>> {
 
 /// is_repo returns true or false if the current dir is a git repo
 pub fn is_repo() -> Result<bool> {
-    let output = Command::new("git")
+    let output = super::command()
         .arg("rev-parse")
         .arg("--is-inside-work-tree")
         .output()
@@ -34,7 +38,7 @@ pub fn is_repo() -> Result<bool> {
 
 /// current_branch returns the name of the current branch
 pub fn current_branch() -> Result<String> {
-    let output = Command::new("git")
+    let output = super::command()
         .arg("rev-parse")
         .arg("--abbrev-ref")
         .arg("HEAD")
@@ -47,7 +51,7 @@ pub fn current_branch() -> Result<String> {
 
 /// is_clean will return a bool based on if the current repo state is clean
 pub fn is_clean() -> Result<bool> {
-    let output = Command::new("git")
+    let output = super::command()
         .arg("status")
         .arg("--porcelain")
         .output()
@@ -58,8 +62,20 @@ pub fn is_clean() -> Result<bool> {
 }
 
 /// commit creates a new commit with message
-pub fn commit(message: &str, empty: bool) -> Result<()> {
-    let mut cmd = Command::new("git");
+pub fn commit(message: &str, empty: bool, signoff: bool) -> Result<()> {
+    commit_at(message, empty, signoff, None, false)
+}
+
+/// commit_at creates a new commit with message, dated `date` (anything git understands, e.g.
+/// RFC 3339) for both author and committer instead of now, skipping the pre-commit and
+/// commit-msg hooks with `no_verify`
+pub fn commit_at(message: &str, empty: bool, signoff: bool, date: Option<&str>, no_verify: bool) -> Result<()> {
+    let mut cmd = super::command();
+
+    if let Some(date) = date {
+        cmd.env("GIT_AUTHOR_DATE", date);
+        cmd.env("GIT_COMMITTER_DATE", date);
+    }
 
     cmd.arg("commit");
     cmd.arg("-m");
@@ -69,6 +85,14 @@ pub fn commit(message: &str, empty: bool) -> Result<()> {
         cmd.arg("--allow-empty");
     }
 
+    if signoff {
+        cmd.arg("--signoff");
+    }
+
+    if no_verify {
+        cmd.arg("--no-verify");
+    }
+
     let res = cmd.output()?;
 
     if res.status.success() {
@@ -77,10 +101,44 @@ pub fn commit(message: &str, empty: bool) -> Result<()> {
     Err(anyhow!("failed to create commit message"))
 }
 
+/// fixup creates a `fixup!` commit for `target`, to be squashed in by `git rebase --autosquash`
+pub fn fixup(target: &str, signoff: bool) -> Result<()> {
+    let mut cmd = super::command();
+    cmd.arg("commit").arg(format!("--fixup={}", target));
+
+    if signoff {
+        cmd.arg("--signoff");
+    }
+
+    let res = cmd.output()?;
+
+    if res.status.success() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "failed to create fixup commit: {}",
+        String::from_utf8_lossy(&res.stderr)
+    ))
+}
+
+/// head_date returns the committer date of HEAD as a unix timestamp, or None before the first commit
+pub fn head_date() -> Result<Option<i64>> {
+    let output = super::command()
+        .args(["log", "-1", "--format=%ct"])
+        .output()?;
+
+    // Fails on a branch with no commits yet
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().parse().ok())
+}
+
 /// Create a temporary WIP commit with all current changes
 pub fn create_wip_commit() -> Result<()> {
     // First add all changes
-    let add = Command::new("git")
+    let add = super::command()
         .args(["add", "."])
         .output()?;
 
@@ -89,8 +147,8 @@ pub fn create_wip_commit() -> Result<()> {
     }
 
     // Create the WIP commit
-    let commit = Command::new("git")
-        .args(["commit", "-m", "[SAGE WIP] Temporary commit for sync"])
+    let commit = super::command()
+        .args(["commit", "-m", WIP_MESSAGE])
         .output()?;
 
     if !commit.status.success() {
@@ -100,10 +158,106 @@ pub fn create_wip_commit() -> Result<()> {
     Ok(())
 }
 
+/// is_wip returns if a commit subject marks work in progress: `wip: ...`, or sync's temporary
+/// commit
+pub fn is_wip(subject: &str) -> bool {
+    subject == WIP_MESSAGE || subject.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("wip:"))
+}
+
+/// commit_all stages everything, untracked files included, and commits it, skipping the commit
+/// hooks with `no_verify`
+pub fn commit_all(message: &str, no_verify: bool) -> Result<()> {
+    let add = super::command().args(["add", "--all"]).output()?;
+    if !add.status.success() {
+        return Err(anyhow!("Failed to stage changes: {}", String::from_utf8_lossy(&add.stderr)));
+    }
+
+    let mut command = super::command();
+    command.args(["commit", "--quiet", "-m", message]);
+    if no_verify {
+        command.arg("--no-verify");
+    }
+    let commit = command.output()?;
+    if !commit.status.success() {
+        return Err(anyhow!("Failed to commit: {}", String::from_utf8_lossy(&commit.stderr)));
+    }
+
+    Ok(())
+}
+
+/// subjects returns the hash and subject of every commit in `range`, newest first
+pub fn subjects(range: &str) -> Result<Vec<(String, String)>> {
+    let output = super::command()
+        .args(["log", "--format=%h%x00%s", range])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to read commits in {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once('\0'))
+        .map(|(hash, subject)| (hash.to_string(), subject.to_string()))
+        .collect())
+}
+
+/// wip_at_tip counts the WIP commits at the tip of `rev`, and returns if any commit comes before
+/// them. History is only read as far as the first commit that isn't WIP.
+pub fn wip_at_tip(rev: &str) -> Result<(usize, bool)> {
+    let mut child = super::command()
+        .args(["log", "--format=%s", rev])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child.stdout.take().ok_or_else(|| anyhow!("Failed to read the commits in {}", rev))?;
+
+    let mut count = 0;
+    let mut has_parent = false;
+    for subject in BufReader::new(stdout).lines() {
+        if !is_wip(&subject?) {
+            has_parent = true;
+            break;
+        }
+        count += 1;
+    }
+    // Stop git walking the rest of the history
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok((count, has_parent))
+}
+
+/// uncommit takes the last `count` commits off the branch, leaving their changes in the working
+/// tree unstaged
+pub fn uncommit(count: usize) -> Result<()> {
+    let output = super::command()
+        .args(["reset", "--quiet", &format!("HEAD~{}", count)])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to undo the commits: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// head_is_wip returns if the commit at HEAD is a temporary sync commit
+pub fn head_is_wip() -> Result<bool> {
+    let output = super::command()
+        .args(["log", "-1", "--format=%s"])
+        .output()?;
+
+    Ok(output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == WIP_MESSAGE)
+}
+
 /// Pop the most recent WIP commit but keep the changes
 pub fn pop_wip_commit() -> Result<()> {
     // Reset the WIP commit but keep changes
-    let reset = Command::new("git")
+    let reset = super::command()
         .args(["reset", "--soft", "HEAD~1"])
         .output()?;
 
@@ -113,3 +267,161 @@ pub fn pop_wip_commit() -> Result<()> {
 
     Ok(())
 }
+
+/// A commit that is missing a Signed-off-by trailer from its author
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedCommit {
+    pub hash: String,
+    pub subject: String,
+    pub author: String,
+}
+
+/// missing_signoff lists the commits in `range` whose author has not signed them off
+pub fn missing_signoff(range: &str) -> Result<Vec<UnsignedCommit>> {
+    let output = super::command()
+        .arg("log")
+        .arg("--format=%h%x00%s%x00%an <%ae>%x00%B%x1e")
+        .arg(range)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to read commits in {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_missing_signoff(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse `git log` records and keep the ones without a matching Signed-off-by line
+pub(crate) fn parse_missing_signoff(log: &str) -> Vec<UnsignedCommit> {
+    log.split('\x1e')
+        .filter_map(|record| {
+            let parts: Vec<&str> = record.trim_start_matches('\n').split('\x00').collect();
+            if parts.len() < 4 {
+                return None;
+            }
+
+            let author = parts[2];
+            let trailer = format!("Signed-off-by: {}", author);
+            if parts[3].lines().any(|line| line.trim().eq_ignore_ascii_case(&trailer)) {
+                return None;
+            }
+
+            Some(UnsignedCommit {
+                hash: parts[0].to_string(),
+                subject: parts[1].to_string(),
+                author: author.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A commit in a range, as `sage verify` looks at it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeCommit {
+    pub hash: String,
+    pub subject: String,
+    pub message: String,
+    pub parents: usize,
+}
+
+/// range_commits lists the commits in `range`, oldest first
+pub fn range_commits(range: &str) -> Result<Vec<RangeCommit>> {
+    let output = super::command()
+        .args(["log", "--reverse", "--format=%h%x00%p%x00%s%x00%B%x1e", range])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to read commits in {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_range_commits(&String::from_utf8(output.stdout)?))
+}
+
+fn parse_range_commits(log: &str) -> Vec<RangeCommit> {
+    log.split('\x1e')
+        .filter_map(|record| {
+            let parts: Vec<&str> = record.trim_start_matches('\n').split('\x00').collect();
+            if parts.len() < 4 {
+                return None;
+            }
+            Some(RangeCommit {
+                hash: parts[0].to_string(),
+                parents: parts[1].split_whitespace().count(),
+                subject: parts[2].to_string(),
+                message: parts[3].trim_end().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// signoff_since rewrites every commit after `base` to carry a Signed-off-by trailer
+pub fn signoff_since(base: &str) -> Result<()> {
+    // --signoff forces the rebase, so commits are rewritten even when already on top of base
+    let output = super::command()
+        .args(["rebase", "--signoff", base])
+        .output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Failed to add Signed-off-by trailers: {}",
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_commit_is_not_reported() {
+        let log = "abc1234\x00feat: add thing\x00Jane <jane@example.com>\x00feat: add thing\n\nSigned-off-by: Jane <jane@example.com>\n\x1e\n";
+        assert!(parse_missing_signoff(log).is_empty());
+    }
+
+    #[test]
+    fn test_unsigned_commit_is_reported() {
+        let log = "abc1234\x00feat: add thing\x00Jane <jane@example.com>\x00feat: add thing\n\x1e\n\
+                   def5678\x00fix: other\x00Jane <jane@example.com>\x00fix: other\n\nSigned-off-by: Jane <jane@example.com>\n\x1e\n";
+        let missing = parse_missing_signoff(log);
+
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].hash, "abc1234");
+    }
+
+    #[test]
+    fn test_signoff_from_someone_else_does_not_count() {
+        let log = "abc1234\x00feat: add thing\x00Jane <jane@example.com>\x00feat: add thing\n\nSigned-off-by: Bob <bob@example.com>\n\x1e\n";
+        assert_eq!(parse_missing_signoff(log).len(), 1);
+    }
+
+    #[test]
+    fn test_is_wip() {
+        assert!(is_wip("wip: halfway through the parser"));
+        assert!(is_wip("WIP: lunch"));
+        assert!(is_wip(WIP_MESSAGE));
+        assert!(!is_wip("wipe the cache on logout"));
+        assert!(!is_wip("feat: wip"));
+    }
+
+    #[test]
+    fn test_parse_range_commits() {
+        let log = "abc1234\x00f00d123\x00feat: add thing\x00feat: add thing\n\nMore\n\x1e\n\
+                   def5678\x00abc1234 9876543\x00Merge branch 'main'\x00Merge branch 'main'\n\x1e\n";
+        let commits = parse_range_commits(log);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].message, "feat: add thing\n\nMore");
+        assert_eq!((commits[0].parents, commits[1].parents), (1, 2));
+        assert_eq!(commits[1].subject, "Merge branch 'main'");
+    }
+}
@@ -0,0 +1,505 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::process::Stdio;
+
+/// move_path renames a tracked file or directory and stages the rename
+pub fn move_path(source: &str, destination: &str) -> Result<()> {
+    let output = super::command()
+        .args(["mv", "--", source, destination])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to move {} to {}: {}",
+            source,
+            destination,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// remove_paths deletes tracked files and stages the deletion, keeping them on disk when `cached`
+pub fn remove_paths(paths: &[String], cached: bool) -> Result<()> {
+    let mut cmd = super::command();
+    cmd.args(["rm", "-r", "--quiet"]);
+    if cached {
+        cmd.arg("--cached");
+    }
+
+    let output = cmd.arg("--").args(paths).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to remove {}: {}",
+            paths.join(", "),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// stage_paths adds the current contents of paths, relative to the top of the working tree, to
+/// the index
+pub fn stage_paths(paths: &[String]) -> Result<()> {
+    let output = super::command()
+        .args(["add", "--"])
+        .args(paths)
+        .current_dir(super::repo::toplevel()?)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to stage {}: {}",
+            paths.join(", "),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// write_tree saves the index as a tree, returning its id, to put it back later with read_tree
+pub fn write_tree() -> Result<String> {
+    let output = super::command().args(["write-tree"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to save the index: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// read_tree replaces the index with a tree saved by write_tree, leaving the working tree alone
+pub fn read_tree(tree: &str) -> Result<()> {
+    let output = super::command().args(["read-tree", tree]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to restore the index: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// unstaged_files lists which of `paths`, relative to the top of the working tree, have changes
+/// in the working tree that aren't staged
+pub fn unstaged_files(paths: &[String]) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = super::command()
+        .args(["diff", "--name-only", "--"])
+        .args(paths)
+        .current_dir(super::repo::toplevel()?)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list unstaged changes: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// changed_files lists the files under `paths` that differ across a revision range
+pub fn changed_files(range: &str, paths: &[String]) -> Result<Vec<String>> {
+    let output = super::command()
+        .args(["diff", "--name-only", "--no-renames", range, "--"])
+        .args(paths)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list changed files for {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// staged_except lists the files staged for the next commit, deleted ones included, other than
+/// `paths` and anything under them
+pub fn staged_except(paths: &[String]) -> Result<Vec<String>> {
+    let mut cmd = super::command();
+    cmd.args(["diff", "--cached", "--name-only", "--", ":/"]);
+    for path in paths {
+        cmd.arg(format!(":(exclude){}", path));
+    }
+    let output = cmd.output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list staged files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// staged_files lists the files staged for the next commit
+pub fn staged_files() -> Result<Vec<String>> {
+    let output = super::command()
+        .args(["diff", "--cached", "--name-only", "--diff-filter=d"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list staged files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// untracked_paths lists the files git doesn't track, relative to the top of the working tree
+/// and with untracked directories as a whole, e.g. `build/`. With `ignored` it lists the ignored
+/// ones instead.
+pub fn untracked_paths(ignored: bool) -> Result<Vec<String>> {
+    let mut command = super::command();
+    command
+        .current_dir(super::repo::toplevel()?)
+        .args(["ls-files", "--others", "--exclude-standard", "--directory", "-z"]);
+    if ignored {
+        command.arg("--ignored");
+    }
+    let output = command.output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list untracked files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| path.to_string())
+        .collect())
+}
+
+/// staged_binaries lists staged files git considers binary
+pub fn staged_binaries() -> Result<Vec<String>> {
+    let output = super::command()
+        .args(["diff", "--cached", "--numstat", "--no-renames", "--diff-filter=d"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list staged files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // Binary files are reported with "-" instead of line counts
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.strip_prefix("-\t-\t"))
+        .map(|path| path.to_string())
+        .collect())
+}
+
+/// staged_size returns the size in bytes of a file as staged in the index
+pub fn staged_size(path: &str) -> Result<u64> {
+    let output = super::command()
+        .args(["cat-file", "-s", &format!(":{}", path)])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to read size of {}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().parse()?)
+}
+
+/// How much a single file changed across a revision range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+    /// Binary files have no line counts
+    pub binary: bool,
+}
+
+/// numstat lists how many lines each file gained and lost across a revision range
+pub fn numstat(range: &str) -> Result<Vec<FileChange>> {
+    let output = super::command()
+        .args(["diff", "--numstat", "--no-renames", range])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to get diff stats for {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_numstat(&String::from_utf8(output.stdout)?))
+}
+
+/// parse_numstat reads `git diff --numstat` output
+pub fn parse_numstat(output: &str) -> Vec<FileChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (added, removed, path) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(FileChange {
+                path: path.to_string(),
+                added: added.parse().unwrap_or(0),
+                removed: removed.parse().unwrap_or(0),
+                binary: added == "-",
+            })
+        })
+        .collect()
+}
+
+/// deleted_files lists the files a revision range deletes
+pub fn deleted_files(range: &str) -> Result<Vec<String>> {
+    let output = super::command()
+        .args(["diff", "--name-only", "--no-renames", "--diff-filter=D", range])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list deleted files for {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// commit_paths lists the commits in a revision range, oldest first, with the subject and files
+/// of each
+pub fn commit_paths(range: &str) -> Result<Vec<(String, Vec<String>)>> {
+    let output = super::command()
+        .args(["log", "--reverse", "--no-renames", "--name-only", "--format=%x00%s", range])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list commits for {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .split('\0')
+        .skip(1)
+        .map(|commit| {
+            let mut lines = commit.lines();
+            let subject = lines.next().unwrap_or_default().to_string();
+            let paths = lines.filter(|line| !line.is_empty()).map(|line| line.to_string()).collect();
+            (subject, paths)
+        })
+        .collect())
+}
+
+/// checkout_paths writes files as they are at `rev` into the working tree and index
+pub fn checkout_paths(rev: &str, paths: &[String]) -> Result<()> {
+    let output = super::command()
+        .args(["checkout", rev, "--"])
+        .args(paths)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to check out {} from {}: {}",
+            paths.join(", "),
+            rev,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// blame_authors counts how many lines of `path` at `rev` each author last touched, returning
+/// (email, a commit of theirs, lines)
+pub fn blame_authors(rev: &str, path: &str) -> Result<Vec<(String, String, usize)>> {
+    let output = super::command()
+        .args(["blame", "--line-porcelain", rev, "--", path])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to blame {}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_blame(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// parse_blame adds up `git blame --line-porcelain` output by author
+pub fn parse_blame(output: &str) -> Vec<(String, String, usize)> {
+    let mut authors: Vec<(String, String, usize)> = Vec::new();
+    let mut commit = "";
+    for line in output.lines() {
+        if let Some(email) = line.strip_prefix("author-mail ") {
+            let email = email.trim_start_matches('<').trim_end_matches('>');
+            match authors.iter_mut().find(|(known, _, _)| known == email) {
+                Some((_, _, lines)) => *lines += 1,
+                None => authors.push((email.to_string(), commit.to_string(), 1)),
+            }
+        } else if !line.starts_with('\t') {
+            // Each line's header starts with the commit it comes from
+            let first = line.split(' ').next().unwrap_or_default();
+            if first.len() == 40 && first.chars().all(|c| c.is_ascii_hexdigit()) {
+                commit = first;
+            }
+        }
+    }
+    authors
+}
+
+/// recent_authors lists who made the last `limit` commits at `rev` touching `paths`, newest
+/// first, as (commit, email)
+pub fn recent_authors(rev: &str, paths: &[String], limit: usize) -> Result<Vec<(String, String)>> {
+    let output = super::command()
+        .args(["log", "--no-merges", &format!("--max-count={}", limit), "--format=%H %ae", rev, "--"])
+        .args(paths)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list recent commits: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(commit, email)| (commit.to_string(), email.to_string()))
+        .collect())
+}
+
+/// A file in a commit's tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeFile {
+    pub path: String,
+    /// The blob's object id
+    pub id: String,
+    pub size: u64,
+}
+
+/// tree_files lists every file in `rev`'s tree, leaving out submodules and symlinks
+pub fn tree_files(rev: &str) -> Result<Vec<TreeFile>> {
+    let output = super::command()
+        .args(["ls-tree", "-r", "-l", "-z", rev])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list the files in {}: {}", rev, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(parse_tree(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Read `git ls-tree -r -l -z` output: `<mode> <type> <id> <size>\t<path>`, NUL separated
+fn parse_tree(output: &str) -> Vec<TreeFile> {
+    output
+        .split('\0')
+        .filter_map(|entry| {
+            let (meta, path) = entry.split_once('\t')?;
+            let mut parts = meta.split_whitespace();
+            let mode = parts.next()?;
+            if parts.next()? != "blob" || mode == "120000" {
+                return None;
+            }
+            let id = parts.next()?.to_string();
+            let size = parts.next()?.parse().ok()?;
+            Some(TreeFile { path: path.to_string(), id, size })
+        })
+        .collect()
+}
+
+/// read_blobs returns the contents of blobs by their object ids, in the same order
+pub fn read_blobs(ids: &[String]) -> Result<Vec<Vec<u8>>> {
+    let mut child = super::command()
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Write from another thread so a full stdout pipe can't deadlock us
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Failed to open git cat-file"))?;
+    let input = ids.iter().map(|id| format!("{}\n", id)).collect::<String>();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().map_err(|_| anyhow!("Failed to feed git cat-file"))??;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to read blobs: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    parse_batch(&output.stdout, ids.len())
+}
+
+/// Split `git cat-file --batch` output into the contents of each object: a `<id> <type> <size>`
+/// line, then that many bytes and a newline
+fn parse_batch(mut output: &[u8], count: usize) -> Result<Vec<Vec<u8>>> {
+    let mut blobs = Vec::with_capacity(count);
+    while blobs.len() < count {
+        let header_end = output.iter().position(|&b| b == b'\n').ok_or_else(|| anyhow!("git cat-file stopped early"))?;
+        let header = String::from_utf8_lossy(&output[..header_end]);
+        let size: usize = match header.rsplit(' ').next().and_then(|size| size.parse().ok()) {
+            Some(size) if !header.ends_with(" missing") => size,
+            _ => return Err(anyhow!("git cat-file could not read {}", header)),
+        };
+        let body = output.get(header_end + 1..header_end + 1 + size).ok_or_else(|| anyhow!("git cat-file stopped early"))?;
+        blobs.push(body.to_vec());
+        output = output.get(header_end + 2 + size..).unwrap_or_default();
+    }
+    Ok(blobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tree_and_batch() {
+        let tree = "100644 blob aaa      12\tsrc/main.rs\x00120000 blob bbb 7\tlink\x00160000 commit ccc       -\tvendor/lib\x00";
+        assert_eq!(parse_tree(tree), vec![TreeFile { path: "src/main.rs".to_string(), id: "aaa".to_string(), size: 12 }]);
+
+        let batch = b"aaa blob 5\nhello\nbbb blob 0\n\n";
+        assert_eq!(parse_batch(batch, 2).unwrap(), vec![b"hello".to_vec(), Vec::new()]);
+        assert!(parse_batch(b"ccc missing\n", 1).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::process::Stdio;
+
+/// Namespace for branch tips sage keeps after deleting or rewriting a branch.
+/// Refs are named `refs/sage/trash/<unix timestamp>/<branch>` so their age is known.
+pub const TRASH_PREFIX: &str = "refs/sage/trash/";
+/// Namespace for branches `sage clean --policy` archived rather than deleted, kept until removed
+/// by hand. `git branch <name> refs/sage/archive/<name>` brings one back.
+pub const ARCHIVE_PREFIX: &str = "refs/sage/archive/";
+
+/// Object database numbers from `git count-objects -v`, sizes in KiB
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectStats {
+    pub loose_count: u64,
+    pub loose_size: u64,
+    pub in_pack: u64,
+    pub packs: u64,
+    pub pack_size: u64,
+    /// Loose objects that are also in a pack and can be deleted
+    pub prune_packable: u64,
+    pub garbage_size: u64,
+}
+
+impl ObjectStats {
+    pub fn total_size(&self) -> u64 {
+        self.loose_size + self.pack_size + self.garbage_size
+    }
+}
+
+/// object_stats counts the repository's loose and packed objects
+pub fn object_stats() -> Result<ObjectStats> {
+    let output = super::command().args(["count-objects", "-v"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to count objects: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(parse_count_objects(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse the `key: value` lines of `git count-objects -v`
+fn parse_count_objects(output: &str) -> ObjectStats {
+    let mut stats = ObjectStats::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().parse().unwrap_or(0);
+        match key.trim() {
+            "count" => stats.loose_count = value,
+            "size" => stats.loose_size = value,
+            "in-pack" => stats.in_pack = value,
+            "packs" => stats.packs = value,
+            "size-pack" => stats.pack_size = value,
+            "prune-packable" => stats.prune_packable = value,
+            "size-garbage" => stats.garbage_size = value,
+            _ => {}
+        }
+    }
+    stats
+}
+
+/// stale_tracking_refs returns remote-tracking branches whose branch is gone from `remote`
+pub fn stale_tracking_refs(remote: &str) -> Result<Vec<String>> {
+    let output = super::command()
+        .args(["remote", "prune", "--dry-run", remote])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to check {} for stale branches: {}",
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("* [would prune] "))
+        .map(str::to_string)
+        .collect())
+}
+
+/// A blob reachable from some ref, with the first path it was seen at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    pub id: String,
+    pub size: u64,
+    pub path: String,
+}
+
+/// largest_blobs returns the `limit` biggest blobs reachable from any ref, largest first
+pub fn largest_blobs(limit: usize) -> Result<Vec<Blob>> {
+    let objects = super::command().args(["rev-list", "--objects", "--all"]).output()?;
+    if !objects.status.success() {
+        return Err(anyhow!("Failed to list objects: {}", String::from_utf8_lossy(&objects.stderr)));
+    }
+
+    let mut child = super::command()
+        .args(["cat-file", "--batch-check=%(objecttype) %(objectname) %(objectsize) %(rest)"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Write from another thread so a full stdout pipe can't deadlock us
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Failed to open git cat-file"))?;
+    let writer = std::thread::spawn(move || stdin.write_all(&objects.stdout));
+    let output = child.wait_with_output()?;
+    writer.join().map_err(|_| anyhow!("Failed to feed git cat-file"))??;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to inspect objects: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let mut blobs = parse_batch_check(&String::from_utf8_lossy(&output.stdout));
+    blobs.sort_by_key(|blob| std::cmp::Reverse(blob.size));
+    blobs.truncate(limit);
+    Ok(blobs)
+}
+
+/// Pick the blobs out of `git cat-file --batch-check` output, keeping the first path for each
+fn parse_batch_check(output: &str) -> Vec<Blob> {
+    let mut seen = std::collections::HashSet::new();
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ' ');
+            if parts.next()? != "blob" {
+                return None;
+            }
+            let id = parts.next()?.to_string();
+            let size = parts.next()?.parse().ok()?;
+            let path = parts.next().unwrap_or_default().to_string();
+            seen.insert(id.clone()).then_some(Blob { id, size, path })
+        })
+        .collect()
+}
+
+/// A ref under TRASH_PREFIX
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashRef {
+    pub name: String,
+    /// When the branch was trashed, as a unix timestamp
+    pub trashed_at: i64,
+}
+
+/// trash_refs lists the branch tips sage has kept around
+pub fn trash_refs() -> Result<Vec<TrashRef>> {
+    let output = super::command()
+        .args(["for-each-ref", "--format=%(refname)", TRASH_PREFIX])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list trash refs: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|name| {
+            let trashed_at = name.strip_prefix(TRASH_PREFIX)?.split('/').next()?.parse().ok()?;
+            Some(TrashRef { name: name.to_string(), trashed_at })
+        })
+        .collect())
+}
+
+/// trash keeps the current tip of a branch under TRASH_PREFIX, returning the ref it was saved as
+pub fn trash(branch: &str) -> Result<String> {
+    let name = format!("{}{}/{}", TRASH_PREFIX, chrono::Utc::now().timestamp(), branch);
+    let output = super::command()
+        .args(["update-ref", &name, &format!("refs/heads/{}", branch)])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to keep the tip of {}: {}", branch, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(name)
+}
+
+/// archive keeps a branch's tip under ARCHIVE_PREFIX, replacing any older archive of the same
+/// name, and returns the ref it was saved as
+pub fn archive(branch: &str) -> Result<String> {
+    let name = format!("{}{}", ARCHIVE_PREFIX, branch);
+    let output = super::command()
+        .args(["update-ref", &name, &format!("refs/heads/{}", branch)])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to archive {}: {}", branch, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(name)
+}
+
+/// delete_ref removes a ref
+pub fn delete_ref(name: &str) -> Result<()> {
+    let output = super::command().args(["update-ref", "-d", name]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to delete {}: {}", name, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// gc packs loose objects and removes unreachable ones older than `prune`
+/// (any `--prune` date git accepts, e.g. `2.weeks.ago`)
+pub fn gc(aggressive: bool, prune: &str) -> Result<()> {
+    let mut cmd = super::command();
+    cmd.args(["gc", "--quiet", &format!("--prune={}", prune)]);
+    if aggressive {
+        cmd.arg("--aggressive");
+    }
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to run git gc: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// repack merges all packs into one and writes a reachability bitmap to speed up fetches and clones
+pub fn repack() -> Result<()> {
+    let output = super::command()
+        .args(["repack", "-a", "-d", "--quiet", "--write-bitmap-index"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to repack: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_count_objects() {
+        let output = "count: 12\nsize: 48\nin-pack: 3050\npacks: 2\nsize-pack: 5120\nprune-packable: 4\ngarbage: 0\nsize-garbage: 0\n";
+        let stats = parse_count_objects(output);
+        assert_eq!(stats.loose_count, 12);
+        assert_eq!(stats.packs, 2);
+        assert_eq!(stats.prune_packable, 4);
+        assert_eq!(stats.total_size(), 5168);
+    }
+
+    #[test]
+    fn test_parse_batch_check_keeps_blobs_once() {
+        let output = "commit aaa 250 \ntree bbb 90 \nblob ccc 1048576 assets/video.mp4\nblob ddd 20 src/main.rs\nblob ccc 1048576 old/video.mp4\n";
+        let blobs = parse_batch_check(output);
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0], Blob { id: "ccc".to_string(), size: 1048576, path: "assets/video.mp4".to_string() });
+    }
+}
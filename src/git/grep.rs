@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+
+/// A single line matched by `git grep`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line: usize,
+    pub text: String,
+}
+
+/// grep searches tracked files in the working tree, or a revision when `rev` is given
+pub fn grep(pattern: &str, rev: Option<&str>, ignore_case: bool, paths: &[String]) -> Result<Vec<GrepMatch>> {
+    let mut cmd = super::command();
+    cmd.args(["grep", "--line-number", "--null", "--no-color"]);
+    if ignore_case {
+        cmd.arg("--ignore-case");
+    }
+    cmd.arg("-e").arg(pattern);
+    if let Some(rev) = rev {
+        cmd.arg(rev);
+    }
+
+    let output = cmd.arg("--").args(paths).output()?;
+
+    // git grep exits with 1 when nothing matched
+    match output.status.code() {
+        Some(0) => {}
+        Some(1) if output.stderr.is_empty() => return Ok(Vec::new()),
+        _ => {
+            return Err(anyhow!(
+                "Failed to search{}: {}",
+                rev.map(|rev| format!(" {}", rev)).unwrap_or_default(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+
+    Ok(parse_grep(&String::from_utf8_lossy(&output.stdout), rev))
+}
+
+/// Parse `[<rev>:]<path>\0<line>\0<text>` lines
+fn parse_grep(output: &str, rev: Option<&str>) -> Vec<GrepMatch> {
+    let prefix = rev.map(|rev| format!("{}:", rev)).unwrap_or_default();
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\0');
+            let path = parts.next()?;
+            let line_number = parts.next()?.parse().ok()?;
+            let text = parts.next()?;
+
+            Some(GrepMatch {
+                path: path.strip_prefix(&prefix).unwrap_or(path).to_string(),
+                line: line_number,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grep_working_tree() {
+        let matches = parse_grep("src/main.rs\x0012\x00fn main() {\n", None);
+        assert_eq!(
+            matches,
+            vec![GrepMatch { path: "src/main.rs".to_string(), line: 12, text: "fn main() {".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_parse_grep_revision_keeps_colons() {
+        let matches = parse_grep("feature/a:src/a.rs\x003\x00let x: u8 = 1;\n", Some("feature/a"));
+        assert_eq!(matches[0].path, "src/a.rs");
+        assert_eq!(matches[0].text, "let x: u8 = 1;");
+    }
+}
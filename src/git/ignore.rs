@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+
+/// The ignore rule responsible for a path, as reported by `git check-ignore -v`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreMatch {
+    /// The file the pattern comes from (.gitignore, .git/info/exclude, a global excludes file...)
+    pub source: String,
+    pub line: usize,
+    pub pattern: String,
+    pub path: String,
+}
+
+impl IgnoreMatch {
+    /// A `!pattern` match means the path is explicitly re-included
+    pub fn is_negated(&self) -> bool {
+        self.pattern.starts_with('!')
+    }
+}
+
+/// check_ignore explains why each path is ignored, ignoring whether the path is tracked
+pub fn check_ignore(paths: &[String]) -> Result<Vec<IgnoreMatch>> {
+    let output = super::command()
+        .args(["check-ignore", "--verbose", "--no-index", "--"])
+        .args(paths)
+        .output()?;
+
+    // check-ignore exits with 1 when none of the paths are ignored
+    match output.status.code() {
+        Some(0) | Some(1) => {}
+        _ => {
+            return Err(anyhow!(
+                "Failed to check ignore rules: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(parse_check_ignore_line)
+        .collect())
+}
+
+/// Parse `<source>:<line>:<pattern>\t<path>`
+fn parse_check_ignore_line(line: &str) -> Option<IgnoreMatch> {
+    let (rule, path) = line.split_once('\t')?;
+
+    // Patterns may themselves contain ':', so only split off the source and line number
+    let mut parts = rule.splitn(3, ':');
+    let source = parts.next()?;
+    let line_number = parts.next()?.parse().ok()?;
+    let pattern = parts.next()?;
+
+    Some(IgnoreMatch {
+        source: source.to_string(),
+        line: line_number,
+        pattern: pattern.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// tracked_ignored lists files that are committed but match an ignore pattern
+pub fn tracked_ignored() -> Result<Vec<String>> {
+    let output = super::command()
+        .args(["ls-files", "--cached", "--ignored", "--exclude-standard"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list ignored files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// untrack removes files from the index while keeping them on disk
+pub fn untrack(files: &[String]) -> Result<()> {
+    let output = super::command()
+        .args(["rm", "--cached", "--quiet", "--"])
+        .args(files)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to untrack files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_check_ignore_line() {
+        let parsed = parse_check_ignore_line(".gitignore:3:*.log\tdebug.log").unwrap();
+        assert_eq!(parsed.source, ".gitignore");
+        assert_eq!(parsed.line, 3);
+        assert_eq!(parsed.pattern, "*.log");
+        assert_eq!(parsed.path, "debug.log");
+        assert!(!parsed.is_negated());
+    }
+
+    #[test]
+    fn test_parse_check_ignore_line_negated_with_colon() {
+        let parsed = parse_check_ignore_line("sub/.gitignore:10:!keep:me\tsub/keep:me").unwrap();
+        assert_eq!(parsed.source, "sub/.gitignore");
+        assert_eq!(parsed.pattern, "!keep:me");
+        assert!(parsed.is_negated());
+    }
+
+    #[test]
+    fn test_parse_check_ignore_line_invalid() {
+        assert!(parse_check_ignore_line("").is_none());
+        assert!(parse_check_ignore_line("no tab here").is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! A cache of how far each branch is from its upstream and the default branch
+//!
+//! Counting the commits a branch is ahead of and behind its upstream walks history, which adds
+//! up over thousands of branches. The counts only change when the branch or its upstream moves,
+//! so they're kept in `.git/sage/branch-index.json` with both tips, and a listing only counts
+//! again for the branches that moved since the last one. When a branch forked from the default
+//! branch is kept the same way.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use super::branch::BranchInfo;
+use crate::git;
+
+/// A local branch as git lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ref {
+    pub name: String,
+    pub tip: String,
+    /// The upstream's short name, e.g. origin/feature
+    pub upstream: Option<String>,
+    /// None when there's no upstream, or it's configured but gone
+    pub upstream_tip: Option<String>,
+    /// When the tip was committed, as a unix timestamp
+    pub committed_at: i64,
+}
+
+/// Counts for a branch, valid while neither tip moves
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    tip: String,
+    /// Empty when only the fork point is known
+    upstream_tip: String,
+    ahead: usize,
+    behind: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fork: Option<Fork>,
+}
+
+/// When a branch forked from the default branch, valid while neither tip moves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Fork {
+    tip: String,
+    default_tip: String,
+    /// Commit time of the merge base, None when they share no history
+    at: Option<i64>,
+}
+
+/// The cached counts of every branch
+#[derive(Debug, Default)]
+pub struct Index {
+    entries: BTreeMap<String, Entry>,
+    changed: bool,
+}
+
+/// refs lists the local branches in two git calls however many there are, in the same order as
+/// [`git::branch::list`]: most recently committed to first, and by name when that's a tie
+pub fn refs() -> Result<Vec<Ref>> {
+    let heads = for_each_ref(&[
+        "--sort=refname",
+        "--format=%(refname:short)%00%(objectname)%00%(upstream)%00%(upstream:short)%00%(committerdate:unix)",
+        "refs/heads",
+    ])?;
+    let tips = for_each_ref(&["--format=%(refname)%00%(objectname)", "refs/heads", "refs/remotes"])?;
+    Ok(parse_refs(&heads, &tips))
+}
+
+fn for_each_ref(args: &[&str]) -> Result<String> {
+    let output = git::command().arg("for-each-ref").args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list branches: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+impl Ref {
+    /// age_days returns how many whole days ago the branch was last committed to
+    pub fn age_days(&self, now: i64) -> i64 {
+        (now - self.committed_at).max(0) / (24 * 60 * 60)
+    }
+}
+
+pub(crate) fn parse_refs(heads: &str, tips: &str) -> Vec<Ref> {
+    let tips = tips
+        .lines()
+        .filter_map(|line| line.split_once('\0'))
+        .collect::<HashMap<_, _>>();
+
+    let mut refs = heads
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split('\0').collect::<Vec<_>>();
+            let [name, tip, upstream, upstream_short, committed_at] = fields.as_slice() else {
+                return None;
+            };
+            Some(Ref {
+                name: name.to_string(),
+                tip: tip.to_string(),
+                upstream: Some(upstream_short.to_string()).filter(|upstream| !upstream.is_empty()),
+                upstream_tip: tips.get(upstream).map(|tip| tip.to_string()),
+                committed_at: committed_at.parse().unwrap_or_default(),
+            })
+        })
+        .collect::<Vec<_>>();
+    // Stable, so branches committed to at the same time stay in name order
+    refs.sort_by_key(|r| std::cmp::Reverse(r.committed_at));
+    refs
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(git::repo::git_dir()?.join("sage").join("branch-index.json"))
+}
+
+impl Index {
+    /// load reads the index, starting afresh when there's none or it can't be read
+    pub fn load() -> Index {
+        let entries = path()
+            .and_then(|path| Ok(fs::read_to_string(path)?))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Index { entries, changed: false }
+    }
+
+    /// counts returns how many commits a branch is ahead of and behind its upstream, from the
+    /// index while the branch and its upstream haven't moved
+    pub fn counts(&mut self, branch: &Ref) -> Result<(usize, usize)> {
+        let Some(upstream_tip) = &branch.upstream_tip else {
+            return Ok((0, 0));
+        };
+        if let Some(counts) = self.cached(branch) {
+            return Ok(counts);
+        }
+
+        let output = git::command()
+            .args(["rev-list", "--left-right", "--count", &format!("{}...{}", upstream_tip, branch.tip)])
+            .output()?;
+        if !output.status.success() {
+            return Ok((0, 0));
+        }
+        let counts = String::from_utf8(output.stdout)?;
+        let mut counts = counts.split_whitespace().map(|count| count.parse().unwrap_or(0));
+        let (behind, ahead) = (counts.next().unwrap_or(0), counts.next().unwrap_or(0));
+
+        let entry = self.entries.entry(branch.name.clone()).or_default();
+        entry.tip = branch.tip.clone();
+        entry.upstream_tip = upstream_tip.clone();
+        (entry.ahead, entry.behind) = (ahead, behind);
+        self.changed = true;
+        Ok((ahead, behind))
+    }
+
+    /// forked_at returns when a branch forked from the default branch, the commit time of their
+    /// merge base, from the index while neither branch has moved
+    pub fn forked_at(&mut self, branch: &Ref, default_tip: &str) -> Result<Option<i64>> {
+        let fork = self.entries.get(&branch.name).and_then(|entry| entry.fork.as_ref());
+        if let Some(fork) = fork.filter(|fork| fork.tip == branch.tip && fork.default_tip == default_tip) {
+            return Ok(fork.at);
+        }
+
+        let output = git::command().args(["merge-base", &branch.tip, default_tip]).output()?;
+        let at = if output.status.success() {
+            let base = String::from_utf8(output.stdout)?;
+            let output = git::command().args(["show", "-s", "--format=%ct", base.trim()]).output()?;
+            String::from_utf8(output.stdout)?.trim().parse().ok()
+        } else {
+            None
+        };
+
+        let fork = Fork { tip: branch.tip.clone(), default_tip: default_tip.to_string(), at };
+        self.entries.entry(branch.name.clone()).or_default().fork = Some(fork);
+        self.changed = true;
+        Ok(at)
+    }
+
+    fn cached(&self, branch: &Ref) -> Option<(usize, usize)> {
+        let entry = self.entries.get(&branch.name)?;
+        (Some(&entry.upstream_tip) == branch.upstream_tip.as_ref() && entry.tip == branch.tip)
+            .then_some((entry.ahead, entry.behind))
+    }
+
+    /// info describes a branch the way `list_with_info` does
+    pub fn info(&mut self, branch: &Ref, current: &str) -> Result<BranchInfo> {
+        let (ahead_count, behind_count) = self.counts(branch)?;
+        Ok(BranchInfo {
+            name: branch.name.clone(),
+            upstream: branch.upstream.clone(),
+            ahead_count,
+            behind_count,
+            is_current: branch.name == current,
+        })
+    }
+
+    /// save writes the index back when anything was counted, forgetting branches that no
+    /// longer exist when `all` is every branch there is
+    pub fn save(&mut self, all: Option<&[Ref]>) -> Result<()> {
+        if let Some(all) = all {
+            let before = self.entries.len();
+            self.entries.retain(|name, _| all.iter().any(|branch| &branch.name == name));
+            self.changed |= self.entries.len() != before;
+        }
+        if !self.changed {
+            return Ok(());
+        }
+
+        let path = path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(&self.entries)?)?;
+        self.changed = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_refs() {
+        let heads = "feature\0aaa\0refs/remotes/origin/feature\0origin/feature\x001760000000\n\
+                     gone\0ccc\0refs/remotes/origin/gone\0origin/gone\x001740000000\n\
+                     local\0bbb\0\0\x001750000000\n";
+        let tips = "refs/heads/feature\0aaa\nrefs/remotes/origin/feature\0ddd\n";
+
+        let refs = parse_refs(heads, tips);
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs.iter().map(|branch| branch.name.as_str()).collect::<Vec<_>>(), ["feature", "local", "gone"]);
+        assert_eq!(refs[0].upstream.as_deref(), Some("origin/feature"));
+        assert_eq!(refs[0].upstream_tip.as_deref(), Some("ddd"));
+        assert_eq!(refs[0].committed_at, 1760000000);
+        assert_eq!(refs[1].upstream, None);
+        assert_eq!(refs[2].upstream.as_deref(), Some("origin/gone"));
+        assert_eq!(refs[2].upstream_tip, None);
+
+        // Ties stay in the name order git lists them in
+        let heads = "a\0aaa\0\0\x001750000000\nb\0bbb\0\0\x001760000000\nc\0ccc\0\0\x001750000000\n";
+        let names = parse_refs(heads, "").into_iter().map(|branch| branch.name).collect::<Vec<_>>();
+        assert_eq!(names, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn test_cached_until_either_tip_moves() {
+        let mut index = Index::default();
+        index.entries.insert(
+            "feature".to_string(),
+            Entry { tip: "aaa".to_string(), upstream_tip: "ddd".to_string(), ahead: 2, behind: 1, fork: None },
+        );
+        let mut branch = Ref {
+            name: "feature".to_string(),
+            tip: "aaa".to_string(),
+            upstream: Some("origin/feature".to_string()),
+            upstream_tip: Some("ddd".to_string()),
+            committed_at: 0,
+        };
+
+        assert_eq!(index.cached(&branch), Some((2, 1)));
+        branch.upstream_tip = Some("eee".to_string());
+        assert_eq!(index.cached(&branch), None);
+        branch.upstream_tip = Some("ddd".to_string());
+        branch.tip = "fff".to_string();
+        assert_eq!(index.cached(&branch), None);
+    }
+}
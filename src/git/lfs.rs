@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+
+use super::repo::toplevel;
+
+/// is_installed returns if the git-lfs extension is available
+pub fn is_installed() -> bool {
+    super::command()
+        .args(["lfs", "version"])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// patterns returns the paths the repository's root .gitattributes stores in LFS
+pub fn patterns() -> Result<Vec<String>> {
+    let path = toplevel()?.join(".gitattributes");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(parse_patterns(&fs::read_to_string(path)?))
+}
+
+/// uses_lfs returns if the repository tracks any files with LFS
+pub fn uses_lfs() -> Result<bool> {
+    Ok(!patterns()?.is_empty())
+}
+
+/// tracked_by_lfs returns which of `paths` are configured to be stored in LFS
+pub fn tracked_by_lfs(paths: &[String]) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = super::command()
+        .args(["check-attr", "filter", "--"])
+        .args(paths)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to check attributes: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_check_attr(&String::from_utf8(output.stdout)?))
+}
+
+/// object_count returns the number of files in HEAD stored as LFS objects
+pub fn object_count() -> Result<usize> {
+    let output = super::command().args(["lfs", "ls-files"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list LFS files: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.lines().count())
+}
+
+/// prune deletes local LFS objects that are no longer needed, keeping any not yet on the remote
+pub fn prune(dry_run: bool) -> Result<String> {
+    let mut cmd = super::command();
+    cmd.args(["lfs", "prune", "--verify-remote", "--verbose"]);
+    if dry_run {
+        cmd.arg("--dry-run");
+    }
+
+    let output = cmd.output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to prune LFS objects: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // git lfs reports progress on stderr, the summary is more useful than nothing
+    let mut report = String::from_utf8(output.stdout)?;
+    report.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(report)
+}
+
+/// Pull the patterns using the lfs filter out of a .gitattributes file
+fn parse_patterns(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?;
+            parts
+                .any(|attribute| attribute == "filter=lfs")
+                .then(|| pattern.to_string())
+        })
+        .collect()
+}
+
+/// Parse `<path>: filter: <value>` lines, keeping the paths filtered by lfs
+fn parse_check_attr(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_suffix(": filter: lfs"))
+        .map(|path| path.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_patterns() {
+        let contents = "# Assets\n*.psd filter=lfs diff=lfs merge=lfs -text\n*.txt text eol=lf\n\nassets/** filter=lfs diff=lfs merge=lfs -text\n";
+        assert_eq!(parse_patterns(contents), vec!["*.psd", "assets/**"]);
+    }
+
+    #[test]
+    fn test_parse_check_attr() {
+        let output = "art.psd: filter: lfs\nREADME.md: filter: unspecified\n";
+        assert_eq!(parse_check_attr(output), vec!["art.psd"]);
+    }
+}
@@ -1,12 +1,11 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use std::process::Command;
 
 use super::repo::default_branch;
 
 /// local returns a list of local branches
 pub fn local() -> Result<Vec<String>> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("branch")
         .arg("--list")
         .arg("--sort=-committerdate")
@@ -23,7 +22,7 @@ pub fn local() -> Result<Vec<String>> {
 
 /// merged returns a list of merged branches
 pub fn merged() -> Result<Vec<String>> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("branch")
         .arg("--merged")
         .arg(default_branch()?)
@@ -40,9 +39,54 @@ pub fn merged() -> Result<Vec<String>> {
     Ok(branches.iter().map(|b| b.to_string()).collect())
 }
 
+/// squash_merged returns if everything on `branch` has landed on `target` as different commits,
+/// rebased or squashed into one as GitHub's merge buttons do, which `merged()` can't see. It's
+/// landed when merging the branch into the target wouldn't change anything, so a squash that was
+/// changed while merging isn't found.
+pub fn squash_merged(branch: &str, target: &str) -> Result<bool> {
+    let base = super::repo::merge_base(target, branch)?;
+    // Nothing of its own: either merged the ordinary way or never committed to
+    if super::repo::rev_parse(branch)? == base {
+        return Ok(false);
+    }
+    if cherry_applied(target, branch)? {
+        return Ok(true);
+    }
+
+    // Merge without touching the work tree or making a commit, and compare that with the target
+    let result = super::command().args(["merge-tree", "--write-tree", target, branch]).output()?;
+    match result.status.code() {
+        Some(0) => {}
+        // Conflicts, so merging would change something
+        Some(1) => return Ok(false),
+        _ => return Err(anyhow!("Failed to merge {} into {}: {}", branch, target, String::from_utf8_lossy(&result.stderr))),
+    }
+    let output = String::from_utf8(result.stdout)?;
+    let tree = output.lines().next().unwrap_or_default();
+
+    let result = super::command().args(["diff", "--quiet", target, tree]).output()?;
+    match result.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => Err(anyhow!("Failed to compare {} with {}: {}", branch, target, String::from_utf8_lossy(&result.stderr))),
+    }
+}
+
+/// cherry_applied returns if every commit on `head` has an equivalent on `upstream`
+fn cherry_applied(upstream: &str, head: &str) -> Result<bool> {
+    let result = super::command().args(["cherry", upstream, head]).output()?;
+    if !result.status.success() {
+        return Err(anyhow!("Failed to compare {} with {}: {}", head, upstream, String::from_utf8_lossy(&result.stderr)));
+    }
+
+    let output = String::from_utf8(result.stdout)?;
+    // `- <hash>` for a commit upstream has, `+ <hash>` for one it doesn't
+    Ok(!output.trim().is_empty() && output.lines().all(|line| line.starts_with('-')))
+}
+
 /// remote returns a list of remote branches
 pub fn remote() -> Result<Vec<String>> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("branch")
         .arg("--list")
         .arg("--remotes")
@@ -72,7 +116,7 @@ pub struct Commit {
 }
 
 pub fn log(branch: &str, limit: usize, stats: bool, all: bool) -> Result<Vec<String>> {
-    let mut cmd = Command::new("git");
+    let mut cmd = super::command();
     cmd.arg("log");
     cmd.arg("--pretty=format:%H%x00%an%x00%at%x00%s");
 
@@ -144,3 +188,36 @@ pub fn commits() -> Result<Vec<Commit>> {
 
     Ok(commits)
 }
+
+/// An author of a commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author {
+    pub name: String,
+    pub email: String,
+}
+
+/// authors returns the author of every commit on `rev` made in a time window, leaving out
+/// merge commits
+pub fn authors(rev: &str, since: DateTime<Utc>, until: Option<DateTime<Utc>>) -> Result<Vec<Author>> {
+    let mut command = super::command();
+    command
+        .arg("log")
+        .arg("--no-merges")
+        .arg("--format=%aN%x00%aE")
+        .arg(format!("--since={}", since.to_rfc3339()));
+    if let Some(until) = until {
+        command.arg(format!("--until={}", until.to_rfc3339()));
+    }
+    let result = command.arg(rev).arg("--").output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!("Failed to read the history of {}: {}", rev, String::from_utf8_lossy(&result.stderr)));
+    }
+
+    let output = String::from_utf8(result.stdout)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once('\0'))
+        .map(|(name, email)| Author { name: name.to_string(), email: email.to_string() })
+        .collect())
+}
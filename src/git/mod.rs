@@ -3,4 +3,34 @@ pub mod commit;
 pub mod repo;
 pub mod status;
 pub mod stash;
-pub mod list;
\ No newline at end of file
+pub mod list;
+pub mod index;
+pub mod patch;
+pub mod stack;
+
+pub mod ignore;
+pub mod files;
+pub mod grep;
+pub mod lfs;
+pub mod remote;
+pub mod gc;
+pub mod purge;
+pub mod checkpoint;
+pub mod session;
+pub mod rebase;
+use std::process::Command;
+
+/// command returns a git command to run; every call to git goes through here so `--profile` can
+/// time it
+pub fn command() -> Command {
+    let mut command = Command::new("git");
+    if let Some(trace) = crate::profile::git_trace() {
+        command.env("GIT_TRACE2_EVENT", trace);
+    }
+    command
+}
+
+/// short_oid abbreviates a commit id to the 7 characters git shows
+pub fn short_oid(oid: &str) -> &str {
+    &oid[..oid.len().min(7)]
+}
@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+
+/// format_patch writes one patch file per commit in `range` into `out_dir`, returning their paths
+pub fn format_patch(range: &str, out_dir: &Path, cover_letter: bool) -> Result<Vec<String>> {
+    let mut cmd = super::command();
+    cmd.arg("format-patch")
+        .arg("--output-directory")
+        .arg(out_dir);
+
+    if cover_letter {
+        cmd.arg("--cover-letter");
+    }
+
+    let output = cmd.arg(range).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to format patches for {}: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // format-patch prints the path of every file it writes
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(stdout
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// apply_mailbox applies patch files on top of the current branch with `git am`
+pub fn apply_mailbox(files: &[String]) -> Result<()> {
+    let output = super::command()
+        .arg("am")
+        .arg("--3way")
+        .args(files)
+        .output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Failed to apply patches: {}",
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+/// abort_mailbox aborts an in-progress `git am`
+pub fn abort_mailbox() -> Result<()> {
+    let output = super::command().args(["am", "--abort"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to abort patch application"));
+    }
+
+    Ok(())
+}
+
+/// send_email hands patch files to `git send-email`, which uses the user's sendemail.* config
+pub fn send_email(files: &[String], to: &[String], cc: &[String], dry_run: bool) -> Result<()> {
+    let mut cmd = super::command();
+    cmd.arg("send-email");
+
+    for address in to {
+        cmd.arg("--to").arg(address);
+    }
+
+    for address in cc {
+        cmd.arg("--cc").arg(address);
+    }
+
+    if dry_run {
+        cmd.arg("--dry-run");
+    }
+
+    // send-email may prompt for confirmation or credentials, so it needs the terminal
+    let status = cmd.args(files).status()?;
+
+    if status.success() {
+        return Ok(());
+    }
+
+    Err(anyhow!("git send-email failed. Check your sendemail.* git configuration"))
+}
+
+/// working_tree_diff returns uncommitted changes to tracked files, staged or not, as a
+/// binary-safe patch
+pub fn working_tree_diff() -> Result<String> {
+    let output = super::command().args(["diff", "HEAD", "--binary"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to diff the working tree: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// apply applies a patch to the working tree
+pub fn apply(patch: &str) -> Result<()> {
+    let mut child = super::command()
+        .args(["apply", "--binary", "-"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to apply patch: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
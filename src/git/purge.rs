@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Result};
+
+/// touches returns if any commit in `revs` (a revision or range, e.g. `main..feature`) adds,
+/// changes or deletes `path`
+pub fn touches(revs: &str, path: &str) -> Result<bool> {
+    let output = super::command()
+        .args(["log", "-1", "--format=%H", revs, "--", path])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to search history for {}: {}", path, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(!output.stdout.trim_ascii().is_empty())
+}
+
+/// commit_count returns how many commits `revs` selects
+pub fn commit_count(revs: &str) -> Result<usize> {
+    let output = super::command().args(["rev-list", "--count", revs]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to count commits: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().parse()?)
+}
+
+/// remove_path rewrites the commits reachable from `branches` (but not from `exclude`, when given)
+/// so `path` never existed in them, dropping commits left empty. The branches are updated in
+/// place; commits they share are rewritten once, so stacks stay stacked.
+pub fn remove_path(branches: &[String], exclude: Option<&str>, path: &str) -> Result<()> {
+    let filter = format!("git rm -r --cached --ignore-unmatch --quiet -- {}", shell_quote(path));
+
+    let mut cmd = super::command();
+    cmd.args(["filter-branch", "--force", "--index-filter", &filter, "--prune-empty", "--"])
+        .args(branches.iter().map(|branch| format!("refs/heads/{}", branch)))
+        .env("FILTER_BRANCH_SQUELCH_WARNING", "1");
+    if let Some(exclude) = exclude {
+        cmd.arg(format!("^{}", exclude));
+    }
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to rewrite history: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    // filter-branch keeps its own backups under refs/original, which would keep the file reachable
+    for branch in branches {
+        super::command()
+            .args(["update-ref", "-d", &format!("refs/original/refs/heads/{}", branch)])
+            .output()?;
+    }
+
+    Ok(())
+}
+
+/// Quote a value for the shell filter-branch runs its filters in
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("config/.env"), "'config/.env'");
+        assert_eq!(shell_quote("it's here"), r"'it'\''s here'");
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::Path;
+
+/// A commit a rebase has applied or has still to apply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoCommit {
+    pub hash: String,
+    pub subject: String,
+    pub author: String,
+}
+
+/// Where a stopped rebase has got to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// The branch being rebased, when it isn't a detached HEAD
+    pub branch: Option<String>,
+    /// The step it stopped at, counting from 1
+    pub current: usize,
+    pub total: usize,
+    /// The commit it stopped at
+    pub commit: Option<TodoCommit>,
+    /// The commits still to apply after it, in order
+    pub remaining: Vec<TodoCommit>,
+}
+
+/// progress reads where the rebase git is in the middle of has got to, from `.git/rebase-merge`
+/// (or `.git/rebase-apply`, which only has the counts). None when no rebase is in progress.
+pub fn progress() -> Result<Option<Progress>> {
+    let dir = super::repo::git_dir()?;
+    let read = |path: &Path| fs::read_to_string(path).map(|contents| contents.trim().to_string()).ok();
+    let count = |path: &Path| read(path).and_then(|value| value.parse().ok()).unwrap_or(0);
+
+    let merge = dir.join("rebase-merge");
+    if merge.is_dir() {
+        let done = read(&merge.join("done")).unwrap_or_default();
+        let todo = read(&merge.join("git-rebase-todo")).unwrap_or_default();
+        let stopped = todo_hashes(&done).pop();
+        let remaining = todo_hashes(&todo);
+
+        let mut details = describe(stopped.iter().chain(&remaining))?;
+        let commit = stopped.and_then(|_| (!details.is_empty()).then(|| details.remove(0)));
+        return Ok(Some(Progress {
+            branch: head_name(read(&merge.join("head-name"))),
+            current: count(&merge.join("msgnum")),
+            total: count(&merge.join("end")),
+            commit,
+            remaining: details,
+        }));
+    }
+
+    let apply = dir.join("rebase-apply");
+    if apply.is_dir() {
+        return Ok(Some(Progress {
+            branch: head_name(read(&apply.join("head-name"))),
+            current: count(&apply.join("next")),
+            total: count(&apply.join("last")),
+            commit: None,
+            remaining: Vec::new(),
+        }));
+    }
+
+    Ok(None)
+}
+
+/// The branch in a head-name file, e.g. refs/heads/feature, which is `detached HEAD` without one
+fn head_name(contents: Option<String>) -> Option<String> {
+    contents?.strip_prefix("refs/heads/").map(str::to_string)
+}
+
+/// The commits a rebase todo list picks, leaving out comments, exec, label and the like
+fn todo_hashes(todo: &str) -> Vec<String> {
+    todo.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let command = words.next()?;
+            if !matches!(command, "pick" | "p" | "reword" | "r" | "edit" | "e" | "squash" | "s" | "fixup" | "f") {
+                return None;
+            }
+            // fixup -C <commit> and fixup -c <commit> take the message from that commit
+            let hash = words.find(|word| !word.starts_with('-'))?;
+            Some(hash.to_string())
+        })
+        .collect()
+}
+
+/// Look up the subject and author of commits, keeping their order
+fn describe<'a>(hashes: impl Iterator<Item = &'a String>) -> Result<Vec<TodoCommit>> {
+    let hashes = hashes.collect::<Vec<_>>();
+    if hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = super::command()
+        .args(["log", "--no-walk=unsorted", "--format=%h%x00%s%x00%an"])
+        .args(&hashes)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to look up the commits being rebased: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            Some(TodoCommit {
+                hash: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_todo_hashes() {
+        let todo = "pick 1a2b3c4 feat: add parser\n\
+                    exec cargo test\n\
+                    fixup -C 5d6e7f8 fix: better message\n\
+                    label onto\n\
+                    \n\
+                    # Rebase 0a0a0a0..5d6e7f8 onto 0a0a0a0 (3 commands)\n\
+                    r 9f9f9f9 docs: explain\n";
+        assert_eq!(todo_hashes(todo), ["1a2b3c4", "5d6e7f8", "9f9f9f9"]);
+        assert_eq!(head_name(Some("refs/heads/feature/parser".to_string())).as_deref(), Some("feature/parser"));
+        assert_eq!(head_name(Some("detached HEAD".to_string())), None);
+    }
+}
@@ -0,0 +1,417 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::process::Command;
+
+use super::repo::{get_config, parse_remote_url, remote_url, set_config};
+
+/// Git config key caching which remote identifies the repository on its forge
+const PRIMARY_KEY: &str = "sage.primaryRemote";
+
+/// How a remote is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Https,
+    Ssh,
+    /// Local paths, file://, git:// and anything else we don't manage credentials for
+    Other,
+}
+
+/// protocol works out how a remote URL is reached
+pub fn protocol(url: &str) -> Protocol {
+    let url = url.trim();
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Protocol::Https
+    } else if url.starts_with("ssh://") || url.starts_with("git+ssh://") {
+        Protocol::Ssh
+    } else if !url.contains("://") && url.contains('@') && url.contains(':') {
+        // scp-like syntax, e.g. git@github.com:owner/repo.git
+        Protocol::Ssh
+    } else {
+        Protocol::Other
+    }
+}
+
+/// to_ssh_url rewrites a remote URL to the scp-like SSH form
+pub fn to_ssh_url(url: &str) -> Option<String> {
+    let (host, owner, repo) = parse_remote_url(url)?;
+    Some(format!("git@{}:{}/{}.git", host, owner, repo))
+}
+
+/// to_https_url rewrites a remote URL to the HTTPS form
+pub fn to_https_url(url: &str) -> Option<String> {
+    let (host, owner, repo) = parse_remote_url(url)?;
+    Some(format!("https://{}/{}/{}.git", host, owner, repo))
+}
+
+/// Why talking to a remote failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessProblem {
+    /// The SSH server didn't accept any of our keys
+    SshKey,
+    /// The SSH host key is unknown or changed
+    HostKey,
+    /// No (or wrong) HTTPS credentials
+    HttpsCredentials,
+    /// The repository doesn't exist, or we aren't allowed to see it
+    NotFound,
+    /// The host couldn't be reached at all
+    Network,
+    Unknown,
+}
+
+/// probe checks we can authenticate against a remote without pushing anything, returning the
+/// problem and git's error output when we can't. Git and ssh are told not to prompt, so a missing
+/// credential fails instead of hanging.
+pub fn probe(remote: &str) -> Result<Option<(AccessProblem, String)>> {
+    let mut cmd = super::command();
+    cmd.args(["ls-remote", "--quiet", remote, "HEAD"])
+        .env("GIT_TERMINAL_PROMPT", "0");
+    // Leave any ssh command the user configured alone
+    if env::var_os("GIT_SSH_COMMAND").is_none() && get_config("core.sshCommand")?.is_none() {
+        cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+
+    let output = cmd.output()?;
+    if output.status.success() {
+        return Ok(None);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Ok(Some((classify(&stderr), stderr)))
+}
+
+/// tip returns the commit `branch` points at on a remote, without fetching it, or None when the
+/// remote doesn't have the branch. Like probe, it never stops to prompt for credentials.
+pub fn tip(remote: &str, branch: &str) -> Result<Option<String>> {
+    let mut cmd = super::command();
+    cmd.args(["ls-remote", "--quiet", remote, &format!("refs/heads/{}", branch)])
+        .env("GIT_TERMINAL_PROMPT", "0");
+    if env::var_os("GIT_SSH_COMMAND").is_none() && get_config("core.sshCommand")?.is_none() {
+        cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to reach {}: {}", remote, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8(output.stdout)?.split_whitespace().next().map(str::to_string))
+}
+
+/// Work out what went wrong from git's error output
+fn classify(stderr: &str) -> AccessProblem {
+    let stderr = stderr.to_lowercase();
+    if stderr.contains("permission denied (publickey") || stderr.contains("no supported authentication methods") {
+        AccessProblem::SshKey
+    } else if stderr.contains("host key verification failed") {
+        AccessProblem::HostKey
+    } else if stderr.contains("could not read username")
+        || stderr.contains("could not read password")
+        || stderr.contains("authentication failed")
+        || stderr.contains("terminal prompts disabled")
+        || stderr.contains("the requested url returned error: 403")
+    {
+        AccessProblem::HttpsCredentials
+    } else if stderr.contains("repository not found") || stderr.contains("does not appear to be a git repository") {
+        AccessProblem::NotFound
+    } else if stderr.contains("could not resolve host")
+        || stderr.contains("connection timed out")
+        || stderr.contains("connection refused")
+        || stderr.contains("network is unreachable")
+    {
+        AccessProblem::Network
+    } else {
+        AccessProblem::Unknown
+    }
+}
+
+/// Whether an ssh-agent is reachable and holding keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentStatus {
+    /// SSH_AUTH_SOCK isn't set, so there's no agent (or it wasn't forwarded)
+    Missing,
+    /// SSH_AUTH_SOCK is set but the agent can't be reached
+    Unreachable,
+    /// The agent is running but has no keys loaded
+    Empty,
+    Ready,
+}
+
+/// ssh_agent checks the ssh-agent sage's git commands would use
+pub fn ssh_agent() -> AgentStatus {
+    if env::var_os("SSH_AUTH_SOCK").is_none() {
+        return AgentStatus::Missing;
+    }
+
+    // ssh-add -l exits with 1 when the agent has no identities and 2 when it can't be reached
+    match Command::new("ssh-add").arg("-l").output().map(|output| output.status.code()) {
+        Ok(Some(0)) => AgentStatus::Ready,
+        Ok(Some(1)) => AgentStatus::Empty,
+        _ => AgentStatus::Unreachable,
+    }
+}
+
+/// A configured remote and its fetch URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+}
+
+/// list returns every configured remote with its fetch URL
+pub fn list() -> Result<Vec<RemoteInfo>> {
+    let output = super::command().args(["remote", "-v"]).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list remotes: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(parse_remotes(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse `git remote -v` output, keeping the fetch URL of each remote
+fn parse_remotes(output: &str) -> Vec<RemoteInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            let url = parts.next()?;
+            (parts.next() == Some("(fetch)")).then(|| RemoteInfo { name: name.to_string(), url: url.to_string() })
+        })
+        .collect()
+}
+
+/// primary returns the remote that identifies the repository on its forge: the one picked with
+/// [`set_primary`] while it still exists, otherwise `origin`, `upstream` or the first remote
+pub fn primary() -> Result<String> {
+    let remotes = list()?;
+    let exists = |name: &str| remotes.iter().any(|remote| remote.name == name);
+
+    if let Some(chosen) = get_config(PRIMARY_KEY)?.filter(|chosen| exists(chosen)) {
+        return Ok(chosen);
+    }
+
+    ["origin", "upstream"]
+        .into_iter()
+        .find(|name| exists(name))
+        .map(str::to_string)
+        .or_else(|| remotes.first().map(|remote| remote.name.clone()))
+        .ok_or_else(|| anyhow!("No remotes configured"))
+}
+
+/// set_primary records which remote identifies the repository, for `sage remote add --primary`
+pub fn set_primary(remote: &str) -> Result<()> {
+    set_config(PRIMARY_KEY, remote)
+}
+
+/// primary_url returns the URL of the primary remote
+pub fn primary_url() -> Result<String> {
+    let remote = primary()?;
+    remote_url(&remote)?.ok_or_else(|| anyhow!("No URL configured for remote {}", remote))
+}
+
+/// validate_url checks a URL is something git can clone from, and that forge URLs name an
+/// owner and a repository
+pub fn validate_url(url: &str) -> Result<()> {
+    if url.trim().is_empty() || url.chars().any(char::is_whitespace) {
+        return Err(anyhow!("'{}' is not a valid remote URL", url));
+    }
+
+    match protocol(url) {
+        Protocol::Https | Protocol::Ssh => {
+            if parse_remote_url(url).is_none() {
+                return Err(anyhow!("'{}' doesn't look like <host>/<owner>/<repo>", url));
+            }
+        }
+        Protocol::Other => {
+            let known_scheme = ["file://", "git://"].iter().any(|scheme| url.starts_with(scheme));
+            if url.contains("://") && !known_scheme {
+                return Err(anyhow!("Unsupported protocol in '{}'", url));
+            }
+            if !url.contains("://") && !std::path::Path::new(url).exists() {
+                return Err(anyhow!("'{}' is not a URL and no such path exists", url));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// add creates a new remote
+pub fn add(name: &str, url: &str) -> Result<()> {
+    let output = super::command().args(["remote", "add", name, url]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to add remote {}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// remove deletes a remote, along with its remote-tracking branches
+pub fn remove(name: &str) -> Result<()> {
+    let output = super::command().args(["remote", "remove", name]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to remove remote {}: {}", name, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// prune deletes remote-tracking branches that no longer exist on a remote, returning git's report
+pub fn prune(remote: &str) -> Result<String> {
+    let output = super::command().args(["remote", "prune", remote]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to prune remote {}: {}",
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// set_url points a remote at a new URL
+pub fn set_url(remote: &str, url: &str) -> Result<()> {
+    let output = super::command()
+        .args(["remote", "set-url", remote, url])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to set URL for remote {}: {}",
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Git config variable marking a remote sage added for a pull request from a fork, e.g.
+/// `remote.octocat.sagefork`
+const FORK_MARKER: &str = "sagefork";
+
+/// add_fork adds a remote for a contributor's fork, marked so it's removed along with the last
+/// branch that tracks it (see [`remove_unused_forks`])
+pub fn add_fork(name: &str, url: &str) -> Result<()> {
+    add(name, url)?;
+    set_config(&format!("remote.{}.{}", name, FORK_MARKER), "true")
+}
+
+/// fetch_branch fetches a single branch from a remote into its remote-tracking branch
+pub fn fetch_branch(remote: &str, branch: &str) -> Result<()> {
+    let refspec = format!("+refs/heads/{0}:refs/remotes/{1}/{0}", branch, remote);
+    let output = super::command().args(["fetch", "--quiet", remote, &refspec]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to fetch {} from {}: {}",
+            branch,
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// push_branch pushes a branch to a remote without forcing, so it fails rather than drop commits
+/// the remote has
+pub fn push_branch(remote: &str, branch: &str) -> Result<()> {
+    let output = super::command().args(["push", "--quiet", remote, branch]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to push {} to {}: {}",
+            branch,
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// remove_unused_forks removes the fork remotes sage added that no branch tracks anymore,
+/// returning their names
+pub fn remove_unused_forks() -> Result<Vec<String>> {
+    let forks = config_matching(&format!(r"^remote\..*\.{}$", FORK_MARKER))?;
+    let tracked = config_matching(r"^branch\..*\.remote$")?;
+    let unused = forks
+        .iter()
+        .filter_map(|(key, _)| key.strip_prefix("remote.")?.strip_suffix(&format!(".{}", FORK_MARKER)))
+        .filter(|remote| !tracked.iter().any(|(_, value)| value == remote))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    for remote in &unused {
+        remove(remote)?;
+    }
+    Ok(unused)
+}
+
+/// The git config keys matching a regular expression, with their values
+fn config_matching(pattern: &str) -> Result<Vec<(String, String)>> {
+    let output = super::command().args(["config", "--get-regexp", pattern]).output()?;
+    // git config exits with 1 when nothing matches
+    if !output.status.success() && output.status.code() != Some(1) {
+        return Err(anyhow!("Failed to read git config: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_and_rewrites() {
+        assert_eq!(protocol("git@github.com:octo/sage.git"), Protocol::Ssh);
+        assert_eq!(protocol("ssh://git@github.com/octo/sage"), Protocol::Ssh);
+        assert_eq!(protocol("https://github.com/octo/sage.git"), Protocol::Https);
+        assert_eq!(protocol("/srv/git/sage.git"), Protocol::Other);
+
+        assert_eq!(to_ssh_url("https://github.com/octo/sage").unwrap(), "git@github.com:octo/sage.git");
+        assert_eq!(to_https_url("git@gitlab.com:group/sub/sage.git").unwrap(), "https://gitlab.com/group/sub/sage.git");
+    }
+
+    #[test]
+    fn test_parse_remotes() {
+        let output = "origin\tgit@github.com:octo/sage.git (fetch)\norigin\tgit@github.com:octo/sage.git (push)\n\
+                      mirror\thttps://example.com/sage.git (fetch)\nmirror\tno_push (push)\n";
+        let remotes = parse_remotes(output);
+        assert_eq!(remotes.len(), 2);
+        assert_eq!(remotes[1], RemoteInfo { name: "mirror".to_string(), url: "https://example.com/sage.git".to_string() });
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("git@github.com:octo/sage.git").is_ok());
+        assert!(validate_url("https://gitlab.com/group/sub/sage").is_ok());
+        assert!(validate_url("https://github.com/octo").is_err());
+        assert!(validate_url("ftp://example.com/sage.git").is_err());
+        assert!(validate_url("https://github.com/octo/sage extra").is_err());
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("git@github.com: Permission denied (publickey).\nfatal: Could not read from remote repository."), AccessProblem::SshKey);
+        assert_eq!(classify("fatal: could not read Username for 'https://github.com': terminal prompts disabled"), AccessProblem::HttpsCredentials);
+        assert_eq!(classify("remote: Repository not found.\nfatal: repository 'https://github.com/o/r/' not found"), AccessProblem::NotFound);
+        assert_eq!(classify("ssh: Could not resolve hostname github.com: Name or service not known"), AccessProblem::Network);
+        assert_eq!(classify("fatal: the remote end hung up unexpectedly"), AccessProblem::Unknown);
+        assert_eq!(classify("fatal: unable to access 'https://github.com/o/r/': Could not resolve host: github.com"), AccessProblem::Network);
+    }
+}
@@ -1,12 +1,12 @@
 use anyhow::{anyhow, Result};
 use git2::Repository;
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 
 /// is_repo returns if user is in an active repo
 pub fn is_repo() -> Result<bool> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("rev-parse")
         .arg("--is-inside-work-tree")
         .output()?;
@@ -37,9 +37,21 @@ pub fn clone(repo: &str, use_ssh: bool) -> Result<()> {
     Ok(())
 }
 
+/// clone_into clones a URL into a directory with git itself, so its credential helpers and SSH
+/// setup are used
+pub fn clone_into(url: &str, dir: &Path) -> Result<()> {
+    let output = super::command().arg("clone").arg("--quiet").arg(url).arg(dir).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Git clone failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(())
+}
+
 /// stage_all is used to stage all Changes
 pub fn stage_all() -> Result<()> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("add")
         .arg("-A")
         .arg(".")
@@ -52,20 +64,72 @@ pub fn stage_all() -> Result<()> {
     }
 }
 
-/// default_branch returns the default branch
+/// Git config key caching the default branch when origin/HEAD isn't set
+const DEFAULT_BRANCH_KEY: &str = "sage.defaultBranch";
+
+/// default_branch returns the default branch of the repository.
+///
+/// origin/HEAD is used when it is set. Otherwise the branch is looked up once (asking the remote,
+/// then looking for a main or master branch) and cached in the repository config.
 pub fn default_branch() -> Result<String> {
-    let result: std::process::Output = Command::new("git")
-        .arg("symbolic-ref")
-        .arg("refs/remotes/origin/HEAD")
+    let result = super::command()
+        .args(["symbolic-ref", "--quiet", "refs/remotes/origin/HEAD"])
         .output()?;
 
-    let stdout = String::from_utf8(result.stdout)?;
-    Ok(stdout.trim().replace("refs/remotes/origin/", "").to_string())
+    if result.status.success() {
+        let head = String::from_utf8(result.stdout)?;
+        let branch = head.trim().trim_start_matches("refs/remotes/origin/");
+        if !branch.is_empty() {
+            return Ok(branch.to_string());
+        }
+    }
+
+    if let Some(cached) = get_config(DEFAULT_BRANCH_KEY)?.filter(|branch| branch_ref_exists(branch)) {
+        return Ok(cached);
+    }
+
+    let detected = remote_head("origin")
+        .or_else(|| ["main", "master"].into_iter().find(|branch| branch_ref_exists(branch)).map(str::to_string))
+        .or_else(|| get_config("init.defaultBranch").ok().flatten())
+        .ok_or_else(|| anyhow!("Could not work out the default branch. Run 'git remote set-head origin --auto' to set it"))?;
+
+    // Caching is best effort, the branch is still right if it fails
+    let _ = set_config(DEFAULT_BRANCH_KEY, &detected);
+    Ok(detected)
+}
+
+/// Ask the remote which branch its HEAD points at
+fn remote_head(remote: &str) -> Option<String> {
+    let output = super::command()
+        .args(["remote", "show", remote])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_remote_head(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Find the `HEAD branch: <name>` line in `git remote show` output
+fn parse_remote_head(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("HEAD branch:"))
+        .map(|branch| branch.trim().to_string())
+        .filter(|branch| !branch.is_empty() && branch != "(unknown)")
+}
+
+/// Whether a branch exists locally or on origin
+fn branch_ref_exists(branch: &str) -> bool {
+    rev_exists(&format!("refs/heads/{}", branch)) || rev_exists(&format!("refs/remotes/origin/{}", branch))
 }
 
 /// fetch_remote will fetch the remote
 pub fn fetch_remote() -> Result<()> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("fetch")
         .arg("--all")
         .arg("--prune")
@@ -77,10 +141,24 @@ pub fn fetch_remote() -> Result<()> {
     return Err(anyhow!("Failed to fetch remote"));
 }
 
+/// last_fetch returns when the repository last fetched, from FETCH_HEAD, None when it never has
+pub fn last_fetch() -> Result<Option<SystemTime>> {
+    let output = super::command()
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "FETCH_HEAD"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to find FETCH_HEAD: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let path = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+    Ok(std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+}
+
 /// pull will pull the latest changes from the remote
 pub fn pull(branch: &str, fast_forward: bool) -> Result<()> {
     // First ensure we have the latest objects from remote
-    let fetch_result = Command::new("git")
+    let fetch_result = super::command()
         .arg("fetch")
         .arg("--all")
         .arg("--prune")
@@ -92,7 +170,7 @@ pub fn pull(branch: &str, fast_forward: bool) -> Result<()> {
     }
     
     // Now pull the changes
-    let mut cmd = Command::new("git");
+    let mut cmd = super::command();
     cmd.arg("pull");
     cmd.arg("origin");
     cmd.arg(branch);
@@ -114,47 +192,20 @@ pub fn pull(branch: &str, fast_forward: bool) -> Result<()> {
         String::from_utf8_lossy(&result.stderr)));
 }
 
-/// get the owner and repo name from the remote URL
+/// get the owner and repo name from the primary remote's URL
 pub fn owner_repo() -> Result<(String, String)> {
-    let result = Command::new("git")
-        .arg("remote")
-        .arg("get-url")
-        .arg("origin")
-        .output()?;
+    let remote_url = super::remote::primary_url()?;
 
-    
-    // The repo url could be SSH or it could be HTTPS
-    // We are going to handle both cases here.
-
-    let remote_url = String::from_utf8(result.stdout)?.trim().to_string();
-    if remote_url.starts_with("git@github.com:") {
-        let parts = remote_url.trim_start_matches("git@github.com:")
-            .trim_end_matches(".git")
-            .split('/')
-            .collect::<Vec<_>>();
-
-        if parts.len() >= 2 {
-            return Ok((parts[0].to_string(), parts[1].to_string()));
-        }
-    }
-
-    // If we are here... we have an HTTPS URL
-    let parts = remote_url.trim_start_matches("https://github.com/")
-        .trim_end_matches(".git")
-        .split("/")
-        .collect::<Vec<_>>();
-
-    if parts.len() >= 2 {
-        return Ok((parts[0].to_string(), parts[1].to_string()));
-    }
-
-    unreachable!("Invalid remote URL");
+    // The repo url could be SSH or it could be HTTPS, parse_remote_url handles both
+    parse_remote_url(&remote_url)
+        .map(|(_, owner, repo)| (owner, repo))
+        .ok_or_else(|| anyhow!("Could not work out the repository from {}", remote_url))
 }
 
 
 /// fetch with a specific refspec
 pub fn fetch(refspec: &str) -> Result<()> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("fetch")
         .arg("origin")
         .arg(refspec)
@@ -172,7 +223,7 @@ pub fn fetch(refspec: &str) -> Result<()> {
 
 /// get the diff of the repo
 pub fn diff() -> Result<String> {
-    let mut binding = Command::new("git");
+    let mut binding = super::command();
     let staged_results = binding
         .arg("diff")
         .arg("--cached");
@@ -198,7 +249,7 @@ pub fn diff() -> Result<String> {
 /// get the commit log history for the current branch
 pub fn commit_log() -> Result<String> {
     // Get the most recent commits (limited to 20)
-    let output = Command::new("git")
+    let output = super::command()
         .arg("log")
         .arg("--pretty=format:%h %s (%an)")
         .arg("-n")
@@ -215,7 +266,7 @@ pub fn commit_log() -> Result<String> {
 }
 
 pub fn fetch_branch(branch_name: &str) -> Result<()> {
-    let output = Command::new("git")
+    let output = super::command()
         .args(["fetch", "origin", branch_name])
         .output()?;
 
@@ -224,4 +275,366 @@ pub fn fetch_branch(branch_name: &str) -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// merge_base returns the best common ancestor between two commits
+pub fn merge_base(first: &str, second: &str) -> Result<String> {
+    let output = super::command()
+        .args(["merge-base", first, second])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to find merge base of {} and {}: {}",
+            first,
+            second,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// get the diff for a revision range (e.g. `main...HEAD`), or the working tree when `range` is empty
+pub fn diff_range(range: &str, stat: bool) -> Result<String> {
+    let mut cmd = super::command();
+    cmd.arg("diff");
+
+    if stat {
+        cmd.arg("--stat");
+    }
+
+    if range.is_empty() {
+        cmd.arg("HEAD");
+    } else {
+        cmd.arg(range);
+    }
+
+    let output = cmd.output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to get diff: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// rev_exists returns if a revision (branch, tag, commit...) resolves to a commit
+pub fn rev_exists(rev: &str) -> bool {
+    super::command()
+        .args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// remote_branch_exists returns if `origin/<branch>` exists locally
+pub fn remote_branch_exists(branch: &str) -> bool {
+    rev_exists(&format!("refs/remotes/origin/{}", branch))
+}
+
+/// get the one-line commit log for a revision range (e.g. `main..HEAD`)
+pub fn log_range(range: &str) -> Result<String> {
+    let output = super::command()
+        .arg("log")
+        .arg("--reverse")
+        .arg("--pretty=format:%h %s (%an)")
+        .arg(range)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to get commit log: {}",
+            String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// git_dir returns the path to the repository's .git directory
+pub fn git_dir() -> Result<PathBuf> {
+    let output = super::command()
+        .args(["rev-parse", "--absolute-git-dir"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to locate .git directory"));
+    }
+
+    Ok(PathBuf::from(String::from_utf8(output.stdout)?.trim()))
+}
+
+/// An operation git itself has stopped in the middle of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InProgress {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+}
+
+impl std::fmt::Display for InProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InProgress::Rebase => write!(f, "rebase"),
+            InProgress::Merge => write!(f, "merge"),
+            InProgress::CherryPick => write!(f, "cherry-pick"),
+            InProgress::Revert => write!(f, "revert"),
+        }
+    }
+}
+
+/// in_progress returns the rebase, merge, cherry-pick or revert git is in the middle of, if any
+pub fn in_progress() -> Result<Option<InProgress>> {
+    let dir = git_dir()?;
+    let state = if dir.join("rebase-merge").exists() || dir.join("rebase-apply").exists() {
+        Some(InProgress::Rebase)
+    } else if dir.join("MERGE_HEAD").exists() {
+        Some(InProgress::Merge)
+    } else if dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(InProgress::CherryPick)
+    } else if dir.join("REVERT_HEAD").exists() {
+        Some(InProgress::Revert)
+    } else {
+        None
+    };
+    Ok(state)
+}
+
+/// remote_url returns the URL configured for a remote, if the remote exists
+pub fn remote_url(remote: &str) -> Result<Option<String>> {
+    let output = super::command()
+        .args(["remote", "get-url", remote])
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+}
+
+/// parse_remote_url splits an SSH or HTTPS remote URL into (host, owner, repo)
+pub fn parse_remote_url(url: &str) -> Option<(String, String, String)> {
+    let url = url.trim().trim_end_matches('/').trim_end_matches(".git");
+
+    // ssh://git@host/owner/repo and https://host/owner/repo
+    let path = if let Some((_, rest)) = url.split_once("://") {
+        rest.rsplit_once('@').map(|(_, host_path)| host_path).unwrap_or(rest).to_string()
+    } else {
+        // git@host:owner/repo (scp-like syntax)
+        let (user_host, path) = url.split_once(':')?;
+        let host = user_host.rsplit_once('@').map(|(_, host)| host).unwrap_or(user_host);
+        format!("{}/{}", host, path)
+    };
+
+    let mut parts = path.split('/').filter(|part| !part.is_empty());
+    let host = parts.next()?;
+    let rest: Vec<&str> = parts.collect();
+    if rest.len() < 2 {
+        return None;
+    }
+
+    // Hosts like GitLab allow nested groups, the repo is always the last segment
+    let repo = rest[rest.len() - 1];
+    let owner = rest[..rest.len() - 1].join("/");
+    Some((host.to_string(), owner, repo.to_string()))
+}
+
+/// get_config reads a value from the repository's git config
+pub fn get_config(key: &str) -> Result<Option<String>> {
+    let output = super::command().args(["config", "--get", key]).output()?;
+
+    // git config exits with 1 when the key is not set
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+}
+
+/// set_config writes a value to the repository's local git config
+pub fn set_config(key: &str, value: &str) -> Result<()> {
+    let output = super::command()
+        .args(["config", "--local", key, value])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to set {}: {}",
+            key,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// unset_config removes a value from the repository's local git config. Removing one that isn't
+/// set is not an error.
+pub fn unset_config(key: &str) -> Result<()> {
+    let output = super::command()
+        .args(["config", "--local", "--unset", key])
+        .output()?;
+
+    // Exit code 5 means it wasn't set
+    if !output.status.success() && output.status.code() != Some(5) {
+        return Err(anyhow!(
+            "Failed to unset {}: {}",
+            key,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// toplevel returns the root directory of the working tree
+pub fn toplevel() -> Result<PathBuf> {
+    let output = super::command()
+        .args(["rev-parse", "--show-toplevel"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to locate the repository root"));
+    }
+
+    Ok(PathBuf::from(String::from_utf8(output.stdout)?.trim()))
+}
+
+/// rev_parse resolves a revision to its full commit hash
+pub fn rev_parse(rev: &str) -> Result<String> {
+    let output = super::command()
+        .args(["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to resolve {}: {}", rev, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// show_prefix returns the current directory relative to the root of the working tree
+pub fn show_prefix() -> Result<String> {
+    let output = super::command()
+        .args(["rev-parse", "--show-prefix"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to locate the current directory in the repository"));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// is_pushed returns if a commit is contained in any remote-tracking branch
+pub fn is_pushed(rev: &str) -> Result<bool> {
+    let output = super::command()
+        .args(["branch", "--remotes", "--contains", rev])
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(false);
+    }
+
+    Ok(!String::from_utf8(output.stdout)?.trim().is_empty())
+}
+
+/// has_tracked_changes returns if any tracked file differs from HEAD, staged or not. Untracked
+/// files don't count, since rebasing and switching branches carry them along.
+pub fn has_tracked_changes() -> Result<bool> {
+    let output = super::command()
+        .args(["diff-index", "--quiet", "HEAD", "--"])
+        .output()?;
+
+    match output.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(anyhow!("Failed to check for changes: {}", String::from_utf8_lossy(&output.stderr))),
+    }
+}
+
+/// is_ancestor returns if `ancestor` is in the history of `rev`
+pub fn is_ancestor(ancestor: &str, rev: &str) -> bool {
+    super::command()
+        .args(["merge-base", "--is-ancestor", ancestor, rev])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// ahead_behind counts the commits `rev` has that `base` doesn't, and the ones `base` has that
+/// `rev` doesn't
+pub fn ahead_behind(base: &str, rev: &str) -> Result<(usize, usize)> {
+    let output = super::command()
+        .args(["rev-list", "--left-right", "--count", &format!("{}...{}", base, rev)])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to compare {} with {}: {}", rev, base, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let counts = String::from_utf8(output.stdout)?;
+    let mut counts = counts.split_whitespace().map(|count| count.parse().unwrap_or(0));
+    let (behind, ahead) = (counts.next().unwrap_or(0), counts.next().unwrap_or(0));
+    Ok((ahead, behind))
+}
+
+/// version returns the installed git's version, e.g. `git version 2.44.0`
+pub fn version() -> Result<String> {
+    let output = super::command().arg("--version").output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to get git version: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_remote_url() {
+        assert_eq!(
+            parse_remote_url("git@github.com:crazywolf132/sage-rs.git"),
+            Some(("github.com".to_string(), "crazywolf132".to_string(), "sage-rs".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_https_remote_url() {
+        assert_eq!(
+            parse_remote_url("https://github.com/crazywolf132/sage-rs"),
+            Some(("github.com".to_string(), "crazywolf132".to_string(), "sage-rs".to_string()))
+        );
+        assert_eq!(
+            parse_remote_url("https://user@gitlab.com/group/sub/project.git"),
+            Some(("gitlab.com".to_string(), "group/sub".to_string(), "project".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_ssh_scheme_remote_url() {
+        assert_eq!(
+            parse_remote_url("ssh://git@github.com/owner/repo.git"),
+            Some(("github.com".to_string(), "owner".to_string(), "repo".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_remote_head() {
+        let output = "* remote origin\n  Fetch URL: git@github.com:o/r.git\n  HEAD branch: trunk\n  Remote branches:\n";
+        assert_eq!(parse_remote_head(output), Some("trunk".to_string()));
+        assert_eq!(parse_remote_head("  HEAD branch: (unknown)\n"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_remote_url() {
+        assert_eq!(parse_remote_url("/tmp/some/path"), None);
+        assert_eq!(parse_remote_url("not a url"), None);
+    }
+}
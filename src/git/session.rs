@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::process::{Output, Stdio};
+
+/// Where saved sessions are kept, locally and on the remote, a ref per user under it
+pub const SESSION_REFS: &str = "refs/sage/session";
+/// Name of the session file inside the commit a session ref points at
+const SESSION_FILE: &str = "session.json";
+
+/// Run git with `input` on stdin
+fn run_with_input(args: &[&str], input: &[u8]) -> Result<Output> {
+    let mut child = super::command()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    Ok(child.wait_with_output()?)
+}
+
+fn stdout(output: Output, action: &str) -> Result<String> {
+    if !output.status.success() {
+        return Err(anyhow!("Failed to {}: {}", action, String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// session_ref returns the ref the current user's session is kept under, named after their
+/// user.email so people sharing a remote don't overwrite each other's sessions
+pub fn session_ref() -> Result<String> {
+    let email = super::repo::get_config("user.email")?.unwrap_or_default();
+    ref_for(&email).ok_or_else(|| anyhow!("Set user.email so your saved session can be told apart from everyone else's"))
+}
+
+fn ref_for(email: &str) -> Option<String> {
+    let name = email
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@') { c } else { '-' })
+        .collect::<String>();
+    // Characters git doesn't allow in a ref name are already gone, which leaves its rules about dots
+    let name = name.trim_matches('.').replace("..", "-");
+    (!name.is_empty()).then(|| format!("{}/{}", SESSION_REFS, name))
+}
+
+/// store commits a session file on top of the session `reference` points at, and points it at
+/// the new commit, returning it
+pub fn store(reference: &str, contents: &str, message: &str) -> Result<String> {
+    let blob = stdout(run_with_input(&["hash-object", "-w", "--stdin"], contents.as_bytes())?, "store the session")?;
+    let entry = format!("100644 blob {}\t{}\n", blob, SESSION_FILE);
+    let tree = stdout(run_with_input(&["mktree"], entry.as_bytes())?, "store the session")?;
+
+    // Built on the previous session, so pushing it is a fast-forward
+    let mut args = vec!["commit-tree".to_string(), tree, "-m".to_string(), message.to_string()];
+    if let Ok(previous) = super::repo::rev_parse(reference) {
+        args.extend(["-p".to_string(), previous]);
+    }
+    let commit = stdout(super::command().args(&args).output()?, "store the session")?;
+    stdout(super::command().args(["update-ref", reference, &commit]).output()?, "store the session")?;
+    Ok(commit)
+}
+
+/// read returns the session file `reference` points at
+pub fn read(reference: &str) -> Result<String> {
+    stdout(
+        super::command().args(["show", &format!("{}:{}", reference, SESSION_FILE)]).output()?,
+        "read the saved session",
+    )
+}
+
+/// push sends the local session to the remote. It's never forced, so a session saved there from
+/// another machine since the last fetch isn't lost.
+pub fn push(remote: &str, reference: &str) -> Result<()> {
+    stdout(
+        super::command()
+            .args(["push", remote, &format!("{}:{}", reference, reference)])
+            .output()?,
+        "push the session",
+    )?;
+    Ok(())
+}
+
+/// fetch replaces the local session with the one on the remote
+pub fn fetch(remote: &str, reference: &str) -> Result<()> {
+    stdout(
+        super::command()
+            .args(["fetch", remote, &format!("+{}:{}", reference, reference)])
+            .output()?,
+        "fetch the session",
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ref_for() {
+        assert_eq!(ref_for("jo.doe@example.com").as_deref(), Some("refs/sage/session/jo.doe@example.com"));
+        assert_eq!(ref_for(" a b:c~^d@{1}..x ").as_deref(), Some("refs/sage/session/a-b-c--d@-1--x"));
+        assert_eq!(ref_for(""), None);
+    }
+}
@@ -0,0 +1,599 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+
+use super::repo::default_branch;
+
+/// Git config key used to record the stack parent of a branch
+fn parent_key(branch: &str) -> String {
+    format!("branch.{}.sage-parent", branch)
+}
+
+/// Git config key recording the commit of the parent a branch was last stacked on, so a parent
+/// that is amended or rebased later can still be told apart from the branch's own commits
+fn base_key(branch: &str) -> String {
+    format!("branch.{}.sage-base", branch)
+}
+
+/// Git config key used to keep a free-form note on a branch
+fn note_key(branch: &str) -> String {
+    format!("branch.{}.sage-note", branch)
+}
+
+/// Git config key marking a branch sage clean must leave alone
+fn keep_key(branch: &str) -> String {
+    format!("branch.{}.sage-keep", branch)
+}
+
+/// Git config key marking an orphan branch as the root of stacks of its own
+fn root_key(branch: &str) -> String {
+    format!("branch.{}.sage-root", branch)
+}
+
+/// A stack of branches, each built on top of the previous one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    /// The trunk the bottom of the stack is based on (usually the default branch)
+    pub base: String,
+    /// Branches in the stack, ordered so every parent comes before its children
+    pub branches: Vec<String>,
+}
+
+/// parent returns the stack parent recorded for a branch, if any
+pub fn parent(branch: &str) -> Result<Option<String>> {
+    let output = super::command()
+        .args(["config", "--get", &parent_key(branch)])
+        .output()?;
+
+    // git config exits with 1 when the key is not set
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let parent = String::from_utf8(output.stdout)?.trim().to_string();
+    if parent.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(parent))
+}
+
+/// set_parent records the stack parent for a branch
+pub fn set_parent(branch: &str, parent: &str) -> Result<()> {
+    let output = super::command()
+        .args(["config", &parent_key(branch), parent])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to record parent for branch {}: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // Where the branch currently forks from its parent, for restacking it later
+    if let Ok(commit) = super::repo::merge_base(parent, branch) {
+        set_base(branch, &commit)?;
+    }
+
+    Ok(())
+}
+
+/// base returns the commit of its parent the branch was last stacked on, if recorded
+pub fn base(branch: &str) -> Result<Option<String>> {
+    let output = super::command()
+        .args(["config", "--get", &base_key(branch)])
+        .output()?;
+
+    // git config exits with 1 when the key is not set
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let base = String::from_utf8(output.stdout)?.trim().to_string();
+    Ok((!base.is_empty()).then_some(base))
+}
+
+/// set_base records the commit of its parent a branch is stacked on
+pub fn set_base(branch: &str, commit: &str) -> Result<()> {
+    let output = super::command()
+        .args(["config", &base_key(branch), commit])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to record base for branch {}: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// note returns the note kept on a branch, if any
+pub fn note(branch: &str) -> Result<Option<String>> {
+    let output = super::command()
+        .args(["config", "--get", &note_key(branch)])
+        .output()?;
+
+    // git config exits with 1 when the key is not set
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let note = String::from_utf8(output.stdout)?.trim().to_string();
+    Ok(Some(note).filter(|note| !note.is_empty()))
+}
+
+/// set_note keeps a note on a branch, replacing any earlier one
+pub fn set_note(branch: &str, note: &str) -> Result<()> {
+    let output = super::command()
+        .args(["config", &note_key(branch), note])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to save the note for branch {}: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// clear_note removes the note from a branch. Clearing a branch without one is not an error.
+pub fn clear_note(branch: &str) -> Result<()> {
+    let output = super::command()
+        .args(["config", "--unset", &note_key(branch)])
+        .output()?;
+
+    // Exit code 5 means there was no note to remove
+    if !output.status.success() && output.status.code() != Some(5) {
+        return Err(anyhow!(
+            "Failed to clear the note for branch {}: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// set_keep marks a branch for sage clean to leave alone, or takes the mark off again
+pub fn set_keep(branch: &str, keep: bool) -> Result<()> {
+    let key = keep_key(branch);
+    let args = if keep { vec!["config", &key, "true"] } else { vec!["config", "--unset", &key] };
+    let output = super::command().args(args).output()?;
+
+    // Exit code 5 means there was no mark to remove
+    if !output.status.success() && output.status.code() != Some(5) {
+        return Err(anyhow!(
+            "Failed to mark branch {}: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// kept returns the branches marked for sage clean to leave alone
+pub fn kept() -> Result<HashSet<String>> {
+    let output = super::command()
+        .args(["config", "--get-regexp", r"^branch\..*\.sage-keep$"])
+        .output()?;
+
+    // No marked branches at all is not an error
+    if !output.status.success() {
+        return Ok(HashSet::new());
+    }
+
+    Ok(parse_kept(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse `git config --get-regexp` output into the branches marked to keep
+fn parse_kept(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            let branch = key.strip_prefix("branch.")?.strip_suffix(".sage-keep")?;
+            (value.trim() == "true").then(|| branch.to_string())
+        })
+        .collect()
+}
+
+/// set_root records that a branch has no history in common with the default branch, so stacks
+/// built on it are based on it rather than the default branch
+pub fn set_root(branch: &str) -> Result<()> {
+    let output = super::command().args(["config", &root_key(branch), "true"]).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to mark {} as a stack root: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// is_root says whether a branch was started as an orphan stack root
+pub fn is_root(branch: &str) -> bool {
+    super::command()
+        .args(["config", "--get", "--type=bool", &root_key(branch)])
+        .output()
+        .is_ok_and(|output| output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "true")
+}
+
+/// relations returns every (branch, parent) pair recorded in the repository
+pub fn relations() -> Result<Vec<(String, String)>> {
+    let output = super::command()
+        .args(["config", "--get-regexp", r"^branch\..*\.sage-parent$"])
+        .output()?;
+
+    // No recorded parents at all is not an error
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    Ok(parse_relations(&String::from_utf8(output.stdout)?))
+}
+
+/// children returns the branches directly stacked on top of `branch`
+pub fn children(branch: &str) -> Result<Vec<String>> {
+    Ok(relations()?
+        .into_iter()
+        .filter(|(_, parent)| parent == branch)
+        .map(|(child, _)| child)
+        .collect())
+}
+
+/// descendants returns every branch stacked (directly or indirectly) on top of `branch`,
+/// ordered so every parent comes before its children
+pub fn descendants(branch: &str) -> Result<Vec<String>> {
+    Ok(collect_descendants(branch, &relations()?))
+}
+
+/// rebase replays the commits `branch` has of its own on top of `onto`, leaving `branch` checked
+/// out. Only the commits after the one of `onto` it was last stacked on are replayed, so the old
+/// commits of a parent that has since been amended or rebased aren't replayed with them.
+pub fn rebase(branch: &str, onto: &str) -> Result<()> {
+    let upstream = old_base(branch, onto);
+    // Recorded up front, so it's right once a rebase stopped on conflicts is continued
+    set_base(branch, &super::repo::rev_parse(onto)?)?;
+    let output = super::command()
+        .args(["rebase", "--onto", onto, &upstream, branch])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to rebase {} onto {}: {}",
+            branch,
+            onto,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Where `branch`'s own commits start: the recorded base while the branch still has it, otherwise
+/// where git's reflog says it forked from `onto`, or failing that wherever the two meet
+fn old_base(branch: &str, onto: &str) -> String {
+    if let Ok(Some(base)) = base(branch)
+        && super::repo::is_ancestor(&base, branch)
+    {
+        return base;
+    }
+
+    let fork_point = super::command()
+        .args(["merge-base", "--fork-point", onto, branch])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|commit| !commit.is_empty());
+    fork_point.unwrap_or_else(|| onto.to_string())
+}
+
+/// stack returns the full stack `branch` belongs to
+pub fn stack(branch: &str) -> Result<Stack> {
+    let relations = relations()?;
+    let trunk = default_branch().unwrap_or_else(|_| "main".to_string());
+    let mut stack = build_stack(branch, &relations, &trunk);
+
+    // A stack root shares no history with the trunk, so it's its own base
+    if stack.base == trunk && is_root(&stack.branches[0]) {
+        stack.base = stack.branches[0].clone();
+    }
+    Ok(stack)
+}
+
+/// Parse `git config --get-regexp` output into (branch, parent) pairs
+pub fn parse_relations(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, parent) = line.split_once(' ')?;
+            let branch = key
+                .strip_prefix("branch.")?
+                .strip_suffix(".sage-parent")?;
+            Some((branch.to_string(), parent.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Work out the stack for `branch` from the recorded relations.
+///
+/// The bottom of the stack is the furthest ancestor that still has a parent; that parent
+/// becomes the base. Branches without any recorded parent form a stack of their own on
+/// top of `trunk`.
+pub fn build_stack(branch: &str, relations: &[(String, String)], trunk: &str) -> Stack {
+    let parent_of = |name: &str| {
+        relations
+            .iter()
+            .find(|(child, _)| child == name)
+            .map(|(_, parent)| parent.clone())
+    };
+
+    // Walk down to the bottom of the stack, guarding against cycles
+    let mut bottom = branch.to_string();
+    let mut base = trunk.to_string();
+    let mut seen = HashSet::new();
+    seen.insert(bottom.clone());
+    while let Some(parent) = parent_of(&bottom) {
+        base = parent.clone();
+        if parent_of(&parent).is_none() || !seen.insert(parent.clone()) {
+            break;
+        }
+        bottom = parent;
+    }
+
+    // Then collect everything above the bottom, parents before children
+    let mut branches = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![bottom];
+    while let Some(current) = pending.pop() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+
+        // Push children in reverse so they're visited in recorded order
+        let children: Vec<String> = relations
+            .iter()
+            .filter(|(_, parent)| *parent == current)
+            .map(|(child, _)| child.clone())
+            .collect();
+        pending.extend(children.into_iter().rev());
+
+        branches.push(current);
+    }
+
+    debug_assert!(is_ordered(&branches[0], &branches[1..], relations), "stack of {} out of order: {:?}", branch, branches);
+    Stack { base, branches }
+}
+
+/// Collect the descendants of `branch` depth-first, in recorded order, guarding against cycles
+pub fn collect_descendants(branch: &str, relations: &[(String, String)]) -> Vec<String> {
+    let mut descendants = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(branch.to_string());
+
+    let mut pending: Vec<String> = Vec::new();
+    let children_of = |name: &str| {
+        relations
+            .iter()
+            .filter(|(_, parent)| parent == name)
+            .map(|(child, _)| child.clone())
+            .collect::<Vec<_>>()
+    };
+
+    pending.extend(children_of(branch).into_iter().rev());
+    while let Some(current) = pending.pop() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        pending.extend(children_of(&current).into_iter().rev());
+        descendants.push(current);
+    }
+
+    debug_assert!(is_ordered(branch, &descendants, relations), "descendants of {} out of order: {:?}", branch, descendants);
+    descendants
+}
+
+/// Whether `branches` can be restacked in order on top of `root`: none repeats or is `root`, and
+/// each is recorded as the child of `root` or of a branch before it
+fn is_ordered(root: &str, branches: &[String], relations: &[(String, String)]) -> bool {
+    let mut placed = HashSet::from([root]);
+    for branch in branches {
+        let after_parent = relations
+            .iter()
+            .any(|(child, parent)| child == branch && placed.contains(parent.as_str()));
+        if !after_parent || !placed.insert(branch) {
+            return false;
+        }
+    }
+    true
+}
+
+/// cycles returns every loop in the recorded parents, child first and starting from the
+/// alphabetically first branch. sage never records one, but parents are plain git config and
+/// can be edited by hand.
+pub fn cycles(relations: &[(String, String)]) -> Vec<Vec<String>> {
+    let parent_of = |name: &str| {
+        relations
+            .iter()
+            .find(|(child, _)| child == name)
+            .map(|(_, parent)| parent.as_str())
+    };
+
+    let mut cycles: Vec<Vec<String>> = Vec::new();
+    for (start, _) in relations {
+        let mut path = vec![start.as_str()];
+        while let Some(parent) = parent_of(path[path.len() - 1]) {
+            if let Some(position) = path.iter().position(|name| *name == parent) {
+                let mut cycle = path[position..].iter().map(|name| name.to_string()).collect::<Vec<_>>();
+                let first = (0..cycle.len()).min_by_key(|index| &cycle[*index]).unwrap_or(0);
+                cycle.rotate_left(first);
+                if !cycles.contains(&cycle) {
+                    cycles.push(cycle);
+                }
+                break;
+            }
+            path.push(parent);
+        }
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn relations(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(child, parent)| (child.to_string(), parent.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_relations() {
+        let output = "branch.feature/a.sage-parent main\nbranch.b.c.sage-parent feature/a\n";
+        assert_eq!(
+            parse_relations(output),
+            relations(&[("feature/a", "main"), ("b.c", "feature/a")])
+        );
+    }
+
+    #[test]
+    fn test_parse_kept() {
+        let output = "branch.release/1.x.sage-keep true\nbranch.old.sage-keep false\n";
+        assert_eq!(parse_kept(output), HashSet::from(["release/1.x".to_string()]));
+    }
+
+    #[test]
+    fn test_stack_from_middle_branch() {
+        let rels = relations(&[("a", "main"), ("b", "a"), ("c", "b")]);
+        let stack = build_stack("b", &rels, "main");
+
+        assert_eq!(stack.base, "main");
+        assert_eq!(stack.branches, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_stack_with_branching_children() {
+        let rels = relations(&[("a", "develop"), ("b", "a"), ("c", "a"), ("d", "b")]);
+        let stack = build_stack("d", &rels, "main");
+
+        assert_eq!(stack.base, "develop");
+        assert_eq!(stack.branches, vec!["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn test_stack_for_untracked_branch() {
+        let stack = build_stack("lonely", &[], "main");
+
+        assert_eq!(stack.base, "main");
+        assert_eq!(stack.branches, vec!["lonely"]);
+    }
+
+    #[test]
+    fn test_stack_survives_cycles() {
+        let rels = relations(&[("a", "b"), ("b", "a")]);
+        let stack = build_stack("a", &rels, "main");
+
+        assert_eq!(stack.branches.len(), 2);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let rels = relations(&[("a", "main"), ("b", "a"), ("c", "a"), ("d", "b"), ("main", "d")]);
+
+        assert_eq!(collect_descendants("a", &rels), vec!["b", "d", "main", "c"]);
+        assert_eq!(collect_descendants("c", &rels), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let rels = relations(&[("a", "main"), ("c", "b"), ("b", "d"), ("d", "c"), ("e", "e")]);
+
+        assert_eq!(cycles(&rels), vec![vec!["b", "d", "c"], vec!["e"]]);
+        assert!(cycles(&relations(&[("a", "main"), ("b", "a")])).is_empty());
+    }
+
+    /// Relations between a handful of branches and main, loops and repeats included
+    fn any_relations() -> impl Strategy<Value = Vec<(String, String)>> {
+        prop::collection::vec((0..6usize, 0..7usize), 0..12).prop_map(|pairs| {
+            pairs
+                .into_iter()
+                .map(|(child, parent)| (format!("b{}", child), if parent == 6 { "main".to_string() } else { format!("b{}", parent) }))
+                .collect()
+        })
+    }
+
+    /// Relations where every branch has one parent that comes before it, as sage records them
+    fn forest() -> impl Strategy<Value = Vec<(String, String)>> {
+        prop::collection::vec(any::<prop::sample::Index>(), 1..12).prop_map(|parents| {
+            parents
+                .iter()
+                .enumerate()
+                .map(|(child, parent)| {
+                    // Index into main plus the branches before this one
+                    let parent = parent.index(child + 1);
+                    (format!("b{}", child), if parent == 0 { "main".to_string() } else { format!("b{}", parent - 1) })
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        // build_stack and collect_descendants check their own order in debug builds, so these
+        // also fail if either plans a branch before its parent
+        #[test]
+        fn prop_stack_holds_branch_once(rels in any_relations(), branch in 0..6usize) {
+            let branch = format!("b{}", branch);
+            let stack = build_stack(&branch, &rels, "main");
+
+            prop_assert!(stack.branches.contains(&branch));
+            prop_assert_eq!(stack.branches.iter().collect::<HashSet<_>>().len(), stack.branches.len());
+        }
+
+        #[test]
+        fn prop_descendants_exclude_branch(rels in any_relations(), branch in 0..6usize) {
+            let branch = format!("b{}", branch);
+            let descendants = collect_descendants(&branch, &rels);
+
+            prop_assert!(!descendants.contains(&branch));
+            prop_assert!(descendants.len() <= rels.len());
+        }
+
+        #[test]
+        fn prop_forest_restacks_every_branch(rels in forest()) {
+            prop_assert!(cycles(&rels).is_empty());
+
+            // Restacking from main reaches every branch, so none is left orphaned
+            let mut descendants = collect_descendants("main", &rels);
+            descendants.sort();
+            let mut branches = rels.iter().map(|(child, _)| child.clone()).collect::<Vec<_>>();
+            branches.sort();
+            prop_assert_eq!(descendants, branches);
+        }
+
+        #[test]
+        fn prop_cycles_are_real(rels in any_relations()) {
+            for cycle in cycles(&rels) {
+                for (index, branch) in cycle.iter().enumerate() {
+                    let next = &cycle[(index + 1) % cycle.len()];
+                    prop_assert_eq!(rels.iter().find(|(child, _)| child == branch).map(|(_, parent)| parent), Some(next));
+                }
+            }
+        }
+    }
+}
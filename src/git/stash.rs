@@ -1,13 +1,15 @@
 use anyhow::{anyhow, Result};
-use std::process::Command;
+
+/// Message of the stashes sage makes, so it can tell them apart from the user's own
+pub const SAGE_STASH_MESSAGE: &str = "Auto-stashed by sage";
 
 /// Stashes current changes
 pub fn stash_changes() -> Result<()> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("stash")
         .arg("push")
         .arg("-m")
-        .arg("Auto-stashed by sage")
+        .arg(SAGE_STASH_MESSAGE)
         .output()?;
     
     if result.status.success() {
@@ -19,7 +21,7 @@ pub fn stash_changes() -> Result<()> {
 
 /// Determines if there are any stashes
 pub fn has_stash() -> Result<bool> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("stash")
         .arg("list")
         .output()?;
@@ -39,7 +41,7 @@ pub fn has_stash() -> Result<bool> {
 
 /// Applies and drops the most recent stash
 pub fn apply_stash() -> Result<()> {
-    let result = Command::new("git")
+    let result = super::command()
         .arg("stash")
         .arg("pop")
         .output()?;
@@ -58,4 +60,61 @@ pub fn apply_stash() -> Result<()> {
     }
     
     return Err(anyhow!("Failed to apply stashed changes. {}", String::from_utf8(result.stderr)?));
-}
\ No newline at end of file
+}
+
+/// A stash entry, e.g. `stash@{0}` with the message `On main: Auto-stashed by sage`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stash {
+    pub name: String,
+    pub message: String,
+}
+
+impl Stash {
+    /// is_sage returns if sage made this stash
+    pub fn is_sage(&self) -> bool {
+        self.message.ends_with(SAGE_STASH_MESSAGE)
+    }
+}
+
+/// list returns every stash entry, newest first
+pub fn list() -> Result<Vec<Stash>> {
+    let result = super::command()
+        .args(["stash", "list", "--format=%gd%x00%gs"])
+        .output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!("Failed to list stashes. {}", String::from_utf8_lossy(&result.stderr)));
+    }
+
+    Ok(String::from_utf8(result.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once('\0'))
+        .map(|(name, message)| Stash { name: name.to_string(), message: message.to_string() })
+        .collect())
+}
+
+/// patch returns the changes in a stash as a binary-safe patch
+pub fn patch(name: &str) -> Result<String> {
+    let result = super::command()
+        .args(["stash", "show", "--patch", "--binary", name])
+        .output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!("Failed to read {}. {}", name, String::from_utf8_lossy(&result.stderr)));
+    }
+
+    Ok(String::from_utf8(result.stdout)?)
+}
+
+/// push_message stashes the current changes with a message
+pub fn push_message(message: &str) -> Result<()> {
+    let result = super::command()
+        .args(["stash", "push", "-m", message])
+        .output()?;
+
+    if !result.status.success() {
+        return Err(anyhow!("Failed to stash changes. {}", String::from_utf8_lossy(&result.stderr)));
+    }
+
+    Ok(())
+}
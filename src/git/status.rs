@@ -1,6 +1,7 @@
 use std::fmt::Display;
 use git2::{Repository, StatusOptions, StatusShow, BranchType};
 use anyhow::{anyhow, Result, Context};
+use crate::{t, tn};
 
 /// Represents the current state of the git repository
 #[derive(Default, Debug, Clone)]
@@ -109,26 +110,26 @@ impl GitStatus {
 
         // Branch information
         if options.show_branch_info {
-            lines.push(format!("On branch {}", self.current_branch));
+            lines.push(t!("status.on_branch", branch = self.current_branch));
             
             if let Some(upstream) = &self.upstream_branch {
                 let relation = if self.ahead_count > 0 && self.behind_count > 0 {
-                    format!("ahead {}, behind {}", self.ahead_count, self.behind_count)
+                    t!("status.diverged", upstream = upstream, ahead = self.ahead_count, behind = self.behind_count)
                 } else if self.ahead_count > 0 {
-                    format!("ahead {}", self.ahead_count)
+                    tn!("status.ahead", self.ahead_count, upstream = upstream)
                 } else if self.behind_count > 0 {
-                    format!("behind {}", self.behind_count)
+                    tn!("status.behind", self.behind_count, upstream = upstream)
                 } else {
-                    "up to date".to_string()
+                    t!("status.up_to_date", upstream = upstream)
                 };
                 
-                lines.push(format!("Your branch is {} with '{}'", relation, upstream));
+                lines.push(relation);
             } else if !self.current_branch.is_empty() {
-                lines.push("Your branch is not tracking a remote branch".to_string());
+                lines.push(t!("status.no_upstream"));
             }
             
             if self.has_stash {
-                lines.push("You have stashed changes".to_string());
+                lines.push(t!("status.stash"));
             }
             
             lines.push(String::new()); // Empty line after branch info
@@ -158,12 +159,12 @@ impl GitStatus {
             
         // Show summary if nothing to display
         if !has_staged && !has_unstaged && self.untracked.is_empty() && self.ignored.is_empty() {
-            lines.push("Nothing to commit, working tree clean".to_string());
+            lines.push(t!("status.clean"));
         }
         
         // Staged changes
         if options.show_staged && has_staged {
-            lines.push("Changes to be committed:".to_string());
+            lines.push(t!("status.staged_header"));
             
             if options.group_by_status {
                 // Add staged added files
@@ -241,7 +242,7 @@ impl GitStatus {
         
         // Unstaged changes
         if options.show_unstaged && has_unstaged {
-            lines.push("Changes not staged for commit:".to_string());
+            lines.push(t!("status.unstaged_header"));
             
             // Add unstaged modified files
             for item in &self.unstaged_modified {
@@ -266,7 +267,7 @@ impl GitStatus {
         
         // Untracked files
         if options.show_untracked && !self.untracked.is_empty() {
-            lines.push("Untracked files:".to_string());
+            lines.push(t!("status.untracked_header"));
             for item in &self.untracked {
                 let path = self.maybe_truncate_path(item, options.max_path_length);
                 lines.push(format!("  {:<2} {}", symbols.untracked, path));
@@ -276,7 +277,7 @@ impl GitStatus {
         
         // Ignored files
         if options.show_ignored && !self.ignored.is_empty() {
-            lines.push("Ignored files:".to_string());
+            lines.push(t!("status.ignored_header"));
             for item in &self.ignored {
                 let path = self.maybe_truncate_path(item, options.max_path_length);
                 lines.push(format!("  {:<2} {}", symbols.ignored, path));
@@ -296,19 +297,19 @@ impl GitStatus {
         let untracked_count = self.untracked.len();
         
         if staged_count > 0 {
-            parts.push(format!("{} staged", staged_count));
+            parts.push(tn!("status.summary.staged", staged_count));
         }
         
         if unstaged_count > 0 {
-            parts.push(format!("{} not staged", unstaged_count));
+            parts.push(tn!("status.summary.unstaged", unstaged_count));
         }
         
         if untracked_count > 0 {
-            parts.push(format!("{} untracked", untracked_count));
+            parts.push(tn!("status.summary.untracked", untracked_count));
         }
         
         if parts.is_empty() {
-            "clean".to_string()
+            t!("status.summary.clean")
         } else {
             parts.join(", ")
         }
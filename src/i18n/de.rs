@@ -0,0 +1,74 @@
+//! German messages
+
+pub const MESSAGES: &[(&str, &str)] = &[
+    // sage status
+    ("status.on_branch", "Auf Branch {branch}"),
+    ("status.ahead.one", "Dein Branch ist {count} Commit vor '{upstream}'"),
+    ("status.ahead.other", "Dein Branch ist {count} Commits vor '{upstream}'"),
+    ("status.behind.one", "Dein Branch ist {count} Commit hinter '{upstream}'"),
+    ("status.behind.other", "Dein Branch ist {count} Commits hinter '{upstream}'"),
+    ("status.diverged", "Dein Branch und '{upstream}' sind auseinandergelaufen, mit {ahead} und {behind} unterschiedlichen Commits"),
+    ("status.up_to_date", "Dein Branch ist auf demselben Stand wie '{upstream}'"),
+    ("status.no_upstream", "Dein Branch folgt keinem Remote-Branch"),
+    ("status.stash", "Du hast gestashte Änderungen"),
+    ("status.clean", "Nichts zu committen, Arbeitsverzeichnis sauber"),
+    ("status.staged_header", "Zum Commit vorgemerkte Änderungen:"),
+    ("status.unstaged_header", "Nicht zum Commit vorgemerkte Änderungen:"),
+    ("status.untracked_header", "Unversionierte Dateien:"),
+    ("status.ignored_header", "Ignorierte Dateien:"),
+    ("status.summary.staged.one", "{count} Datei vorgemerkt"),
+    ("status.summary.staged.other", "{count} Dateien vorgemerkt"),
+    ("status.summary.unstaged.one", "{count} Datei nicht vorgemerkt"),
+    ("status.summary.unstaged.other", "{count} Dateien nicht vorgemerkt"),
+    ("status.summary.untracked.one", "{count} unversionierte Datei"),
+    ("status.summary.untracked.other", "{count} unversionierte Dateien"),
+    ("status.summary.clean", "sauber"),
+    // sage list and history
+    ("list.header", "Branches:"),
+    ("history.header", "Branch-Verlauf:"),
+    ("history.empty", "Keine Commits gefunden"),
+    ("history.date", "Datum:"),
+    ("history.by", "von"),
+    // sage sync
+    ("sync.fetching", "Hole Änderungen vom Remote..."),
+    ("sync.pulling_default", "Auf dem Standard-Branch, hole die neuesten Änderungen..."),
+    ("sync.default_updated", "✨ Standard-Branch erfolgreich aktualisiert!"),
+    ("sync.analyzing", "Analysiere den Branch..."),
+    ("sync.wip", "Erstelle einen temporären Commit für lokale Änderungen..."),
+    ("sync.diverged", "Der Branch ist von {branch} abgewichen..."),
+    ("sync.fallback_merge", "Rebase hatte Konflikte, versuche stattdessen einen Merge..."),
+    ("sync.failed", "\n⚠️  Der Branch konnte nicht automatisch synchronisiert werden:"),
+    ("sync.failed_diverged", "1. Dein Branch ist stark von {branch} abgewichen"),
+    ("sync.failed_conflicts", "2. Sowohl Rebase als auch Merge führten zu Konflikten"),
+    ("sync.recommended", "\nEmpfohlene Schritte:"),
+    ("sync.recommended_merge", "1. Merge {branch} von Hand in deinen Branch"),
+    ("sync.recommended_resolve", "2. Löse die Konflikte"),
+    ("sync.recommended_rerun", "3. Führe sage sync erneut aus"),
+    ("sync.behind", "Der Branch ist hinter {branch}, aktualisiere..."),
+    ("sync.rebasing", "Rebase auf {branch} ({setting})..."),
+    ("sync.merging", "Merge von {branch} in den Branch ({setting})..."),
+    ("sync.fast_forwarding", "Fast-Forward auf {branch} ({setting})..."),
+    ("sync.pushing", "Pushe Commits zum Remote..."),
+    ("sync.not_pushing_wip", "Kein Push, der Branch hat WIP-Commits. Mit {command} geht es daran weiter"),
+    ("sync.not_pushing", "Nicht gepusht. Führe {command} aus, wenn es so weit ist"),
+    ("sync.skip_in_progress", "Kein Sync, git ist mitten in einem {operation}"),
+    ("sync.skip_unreachable", "Kein Sync, origin ist nicht erreichbar"),
+    ("sync.skip_up_to_date", "Nichts zu syncen, auf dem Stand von {branch} (vor {minutes} Min. geprüft)"),
+    ("sync.skip_up_to_date_unfetched", "Nichts zu syncen, auf dem Stand von {branch}"),
+    ("sync.fetching_upstream", "Hole {branch}..."),
+    ("sync.updating_fork", "Fast-Forward von {branch} auf upstream und Push nach origin..."),
+    ("sync.push_rewritten", "Der Branch wurde per Rebase aktualisiert, führe {command} aus, um seinen Pull Request zu aktualisieren"),
+    ("sync.restoring", "Stelle nicht committete Änderungen wieder her..."),
+    ("sync.done", "✨ Branch {branch} erfolgreich synchronisiert!"),
+    // Interrupted operations and sage continue
+    ("interrupt.stopping", "Halte am nächsten sicheren Punkt an. Drücke Strg-C erneut, um sofort zu beenden"),
+    ("interrupt.stopped", "Vor dem Abschluss angehalten."),
+    ("interrupt.resume", "Führe {command} aus, um dort weiterzumachen."),
+    ("interrupt.manual", "Oder stelle den vorherigen Zustand von Hand wieder her mit:"),
+    ("resume.continuing", "Setze {operation} fort, gestartet auf {branch} um {time}"),
+    ("resume.resolve_first", "Löse zuerst diese Konflikte:"),
+    ("resume.restacked", "✨ Restack abgeschlossen"),
+    // Shared
+    ("warning", "WARNUNG:"),
+    ("error", "Fehler: {error}"),
+];
@@ -0,0 +1,74 @@
+//! English messages, which every other catalog falls back to
+
+pub const MESSAGES: &[(&str, &str)] = &[
+    // sage status
+    ("status.on_branch", "On branch {branch}"),
+    ("status.ahead.one", "Your branch is ahead of '{upstream}' by {count} commit"),
+    ("status.ahead.other", "Your branch is ahead of '{upstream}' by {count} commits"),
+    ("status.behind.one", "Your branch is behind '{upstream}' by {count} commit"),
+    ("status.behind.other", "Your branch is behind '{upstream}' by {count} commits"),
+    ("status.diverged", "Your branch and '{upstream}' have diverged, with {ahead} and {behind} different commits"),
+    ("status.up_to_date", "Your branch is up to date with '{upstream}'"),
+    ("status.no_upstream", "Your branch is not tracking a remote branch"),
+    ("status.stash", "You have stashed changes"),
+    ("status.clean", "Nothing to commit, working tree clean"),
+    ("status.staged_header", "Changes to be committed:"),
+    ("status.unstaged_header", "Changes not staged for commit:"),
+    ("status.untracked_header", "Untracked files:"),
+    ("status.ignored_header", "Ignored files:"),
+    ("status.summary.staged.one", "{count} file staged"),
+    ("status.summary.staged.other", "{count} files staged"),
+    ("status.summary.unstaged.one", "{count} file not staged"),
+    ("status.summary.unstaged.other", "{count} files not staged"),
+    ("status.summary.untracked.one", "{count} untracked file"),
+    ("status.summary.untracked.other", "{count} untracked files"),
+    ("status.summary.clean", "clean"),
+    // sage list and history
+    ("list.header", "Branches:"),
+    ("history.header", "Branch History:"),
+    ("history.empty", "No commits found"),
+    ("history.date", "Date:"),
+    ("history.by", "by"),
+    // sage sync
+    ("sync.fetching", "Fetching remote changes..."),
+    ("sync.pulling_default", "On default branch, pulling latest changes..."),
+    ("sync.default_updated", "✨ Successfully updated default branch!"),
+    ("sync.analyzing", "Analyzing branch state..."),
+    ("sync.wip", "Creating temporary commit for local changes..."),
+    ("sync.diverged", "Branch has diverged from {branch}..."),
+    ("sync.fallback_merge", "Rebase encountered conflicts, falling back to merge..."),
+    ("sync.failed", "\n⚠️  Could not automatically sync branch:"),
+    ("sync.failed_diverged", "1. Your branch has diverged significantly from {branch}"),
+    ("sync.failed_conflicts", "2. Both rebase and merge resulted in conflicts"),
+    ("sync.recommended", "\nRecommended actions:"),
+    ("sync.recommended_merge", "1. Manually merge {branch} into your branch"),
+    ("sync.recommended_resolve", "2. Resolve the conflicts"),
+    ("sync.recommended_rerun", "3. Run sage sync again"),
+    ("sync.behind", "Branch is behind {branch}, updating..."),
+    ("sync.rebasing", "Rebasing onto {branch} ({setting})..."),
+    ("sync.merging", "Merging {branch} into the branch ({setting})..."),
+    ("sync.fast_forwarding", "Fast-forwarding to {branch} ({setting})..."),
+    ("sync.pushing", "Pushing commits to remote..."),
+    ("sync.not_pushing_wip", "Not pushing, the branch has WIP commits. Run {command} to carry on with them"),
+    ("sync.not_pushing", "Not pushing. Run {command} when it's ready"),
+    ("sync.skip_in_progress", "Not syncing, git is in the middle of a {operation}"),
+    ("sync.skip_unreachable", "Not syncing, origin can't be reached"),
+    ("sync.skip_up_to_date", "Nothing to sync, up to date with {branch} (checked {minutes} min ago)"),
+    ("sync.skip_up_to_date_unfetched", "Nothing to sync, up to date with {branch}"),
+    ("sync.fetching_upstream", "Fetching {branch}..."),
+    ("sync.updating_fork", "Fast-forwarding {branch} to upstream and pushing it to origin..."),
+    ("sync.push_rewritten", "The branch was rebased, run {command} to update its pull request"),
+    ("sync.restoring", "Restoring uncommitted changes..."),
+    ("sync.done", "✨ Successfully synced branch {branch}!"),
+    // Interrupted operations and sage continue
+    ("interrupt.stopping", "Stopping at the next safe point. Press Ctrl-C again to quit immediately"),
+    ("interrupt.stopped", "Stopped before finishing."),
+    ("interrupt.resume", "Run {command} to pick up where it left off."),
+    ("interrupt.manual", "Or put things back by hand with:"),
+    ("resume.continuing", "Continuing the {operation} started on {branch} at {time}"),
+    ("resume.resolve_first", "Resolve these conflicts first:"),
+    ("resume.restacked", "✨ Finished restacking"),
+    // Shared
+    ("warning", "WARNING:"),
+    ("error", "Error: {error}"),
+];
@@ -0,0 +1,148 @@
+//! Translated user-facing messages
+//!
+//! Messages are looked up by key in the catalog for the active locale, falling back to English
+//! when a translation is missing. `{name}` placeholders are filled in from the arguments given to
+//! [`t!`](crate::t) and [`tn!`](crate::tn). Messages that depend on a count have `.one` and
+//! `.other` forms, picked by [`tn!`](crate::tn), which also provides the count as `{count}`.
+//!
+//! The locale comes from the `ui.locale` setting, then the `SAGE_LANG`, `LC_ALL`,
+//! `LC_MESSAGES` and `LANG` environment variables.
+
+use std::env;
+use std::sync::OnceLock;
+
+use crate::config;
+
+mod de;
+mod en;
+
+/// A language sage has messages for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::De];
+
+    /// parse reads a locale name such as `de`, `de_DE.UTF-8` or `en-GB`
+    pub fn parse(value: &str) -> Option<Locale> {
+        let language = value.split(['_', '-', '.', '@']).next()?.to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+
+    fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => en::MESSAGES,
+            Locale::De => de::MESSAGES,
+        }
+    }
+
+    /// Which plural form a count takes
+    fn plural_form(&self, count: u64) -> &'static str {
+        match self {
+            Locale::En | Locale::De if count == 1 => "one",
+            Locale::En | Locale::De => "other",
+        }
+    }
+}
+
+/// locale returns the locale messages are shown in
+pub fn locale() -> Locale {
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    *LOCALE.get_or_init(|| {
+        config::get("ui.locale")
+            .into_iter()
+            .chain(["SAGE_LANG", "LC_ALL", "LC_MESSAGES", "LANG"].iter().filter_map(|name| env::var(name).ok()))
+            .filter(|value| !value.trim().is_empty())
+            .find_map(|value| Locale::parse(&value))
+            .unwrap_or(Locale::En)
+    })
+}
+
+/// translate looks up a message in the active locale and fills in its placeholders
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    message(locale(), key, args)
+}
+
+/// translate_plural picks the form of a message for `count` and fills in its placeholders
+pub fn translate_plural(key: &str, count: u64, args: &[(&str, String)]) -> String {
+    plural_message(locale(), key, count, args)
+}
+
+fn message(locale: Locale, key: &str, args: &[(&str, String)]) -> String {
+    let template = lookup(locale, key).unwrap_or(key);
+    args.iter()
+        .fold(template.to_string(), |message, (name, value)| message.replace(&format!("{{{}}}", name), value))
+}
+
+fn plural_message(locale: Locale, key: &str, count: u64, args: &[(&str, String)]) -> String {
+    let key = format!("{}.{}", key, locale.plural_form(count));
+    let mut args = args.to_vec();
+    args.push(("count", count.to_string()));
+    message(locale, &key, &args)
+}
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    let find = |catalog: &'static [(&'static str, &'static str)]| {
+        catalog.iter().find(|(candidate, _)| *candidate == key).map(|(_, message)| *message)
+    };
+    find(locale.catalog()).or_else(|| find(Locale::En.catalog()))
+}
+
+/// Look up a translated message, e.g. `t!("sync.done", branch = name)`
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+/// Look up a translated message with a count, e.g. `tn!("status.staged", 3)`
+#[macro_export]
+macro_rules! tn {
+    ($key:expr, $count:expr) => {
+        $crate::i18n::translate_plural($key, $count as u64, &[])
+    };
+    ($key:expr, $count:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate_plural($key, $count as u64, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(Locale::parse("de_DE.UTF-8"), Some(Locale::De));
+        assert_eq!(Locale::parse("en-GB"), Some(Locale::En));
+        assert_eq!(Locale::parse("C"), Some(Locale::En));
+        assert_eq!(Locale::parse("fr_FR"), None);
+    }
+
+    #[test]
+    fn test_placeholders_plurals_and_fallback() {
+        let args = [("upstream", "origin/main".to_string())];
+        assert_eq!(plural_message(Locale::En, "status.ahead", 1, &args), "Your branch is ahead of 'origin/main' by 1 commit");
+        assert_eq!(plural_message(Locale::De, "status.summary.staged", 2, &[]), "2 Dateien vorgemerkt");
+        assert_eq!(message(Locale::De, "no.such.key", &[]), "no.such.key");
+    }
+
+    #[test]
+    fn test_every_translation_has_an_english_original() {
+        for locale in Locale::ALL {
+            for (key, _) in locale.catalog() {
+                assert!(lookup(Locale::En, key).is_some(), "{:?} has {} but English doesn't", locale, key);
+            }
+        }
+    }
+}
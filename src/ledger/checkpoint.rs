@@ -0,0 +1,126 @@
+//! Whole-repository snapshots that `sage rollback` can return to
+//!
+//! A checkpoint records where every local branch pointed, their stack parents, the branch that
+//! was checked out and any uncommitted changes to tracked files. It's listed in
+//! `.git/sage/checkpoints.json`, and refs under `refs/sage/checkpoints/<id>/` keep its commits
+//! from being garbage collected.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::git::{self, checkpoint::CHECKPOINT_PREFIX};
+
+/// Most checkpoints kept; the oldest are dropped when a new one is made
+const MAX_CHECKPOINTS: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Milliseconds since the epoch when it was taken
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    /// Why it was taken, e.g. "before sync" or the user's own message
+    pub label: String,
+    /// The branch checked out, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+    /// Every local branch and the commit it pointed at
+    pub branches: BTreeMap<String, String>,
+    /// Stack parents, by branch
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parents: BTreeMap<String, String>,
+    /// Stash commit holding uncommitted changes to tracked files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<String>,
+}
+
+impl Checkpoint {
+    /// ref_prefix is where the refs keeping this checkpoint's commits live
+    pub fn ref_prefix(&self) -> String {
+        format!("{}{}/", CHECKPOINT_PREFIX, self.id)
+    }
+}
+
+/// path returns the location of the checkpoint list for the current repository
+pub fn path() -> Result<PathBuf> {
+    let mut path = git::repo::git_dir()?;
+    path.push("sage");
+    path.push("checkpoints.json");
+    Ok(path)
+}
+
+/// load returns every checkpoint, oldest first
+pub fn load() -> Result<Vec<Checkpoint>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse checkpoints {}", path.display()))
+}
+
+fn save(checkpoints: &[Checkpoint]) -> Result<()> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(checkpoints)?)?;
+    Ok(())
+}
+
+/// add stores a checkpoint and pins its commits, dropping the oldest past MAX_CHECKPOINTS
+pub fn add(checkpoint: Checkpoint) -> Result<()> {
+    let prefix = checkpoint.ref_prefix();
+    for (branch, oid) in &checkpoint.branches {
+        git::checkpoint::update_ref(&format!("{}heads/{}", prefix, branch), oid)?;
+    }
+    if let Some(worktree) = &checkpoint.worktree {
+        git::checkpoint::update_ref(&format!("{}worktree", prefix), worktree)?;
+    }
+
+    let mut checkpoints = load()?;
+    checkpoints.push(checkpoint);
+
+    let excess = checkpoints.len().saturating_sub(MAX_CHECKPOINTS);
+    for dropped in checkpoints.drain(..excess) {
+        git::checkpoint::delete_refs(&dropped.ref_prefix())?;
+    }
+    save(&checkpoints)
+}
+
+/// at_or_before returns the latest checkpoint taken no later than `time`
+pub fn at_or_before(checkpoints: &[Checkpoint], time: DateTime<Utc>) -> Option<&Checkpoint> {
+    checkpoints.iter().rev().find(|checkpoint| checkpoint.created_at <= time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn checkpoint(id: i64) -> Checkpoint {
+        Checkpoint {
+            id,
+            created_at: Utc.timestamp_millis_opt(id).unwrap(),
+            label: "before sync".to_string(),
+            head: Some("main".to_string()),
+            branches: BTreeMap::new(),
+            parents: BTreeMap::new(),
+            worktree: None,
+        }
+    }
+
+    #[test]
+    fn test_at_or_before_picks_latest_not_after() {
+        let checkpoints = vec![checkpoint(1_000), checkpoint(5_000), checkpoint(9_000)];
+        let at = |millis| at_or_before(&checkpoints, Utc.timestamp_millis_opt(millis).unwrap()).map(|c| c.id);
+        assert_eq!(at(500), None);
+        assert_eq!(at(5_000), Some(5_000));
+        assert_eq!(at(8_999), Some(5_000));
+        assert_eq!(at(20_000), Some(9_000));
+    }
+}
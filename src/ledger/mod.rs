@@ -0,0 +1,345 @@
+//! A record of the long-running operations sage has started in a repository
+//!
+//! Sync and restack rewrite branches in several steps. Each run is written to
+//! `.git/sage/ledger.json` before it starts and updated as it goes, so an operation that was
+//! interrupted or stopped on conflicts can be resumed with `sage continue`. Commands that delete
+//! branches record the tips they removed, so `sage undo` can bring them back.
+//!
+//! Plugin hooks leave a report of which plugins ran, how long they took and what they said, for
+//! `sage hooks last`.
+//!
+//! Every entry also keeps where each local branch pointed when the operation started and when it
+//! stopped, which `sage snapshot diff` compares.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{events::{self, Event}, git, plugin::Verdict};
+
+pub mod checkpoint;
+
+/// Most entries kept; older ones are dropped when a new operation starts
+const MAX_ENTRIES: usize = 100;
+/// Most hook reports kept, so committing often doesn't push out the operations
+const MAX_HOOK_REPORTS: usize = 20;
+
+/// How far an operation got
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Running,
+    /// Stopped by Ctrl-C or conflicts, and can be resumed
+    Interrupted,
+    Done,
+    Failed,
+    /// Reverted by `sage undo`
+    Undone,
+}
+
+/// What an operation was doing, with enough detail to pick it back up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Operation {
+    Sync {
+        /// Uncommitted changes are parked in a temporary WIP commit
+        wip: bool,
+    },
+    Restack {
+        /// The branch whose descendants are being restacked
+        root: String,
+        /// Branches still to be rebased, in order
+        remaining: Vec<String>,
+    },
+    /// Branches deleted by a command such as `sage clean`
+    #[serde(rename = "delete_branches")]
+    DeleteBranches {
+        /// The sage command that deleted them
+        command: String,
+        branches: Vec<DeletedBranch>,
+    },
+    /// Plugins run on a hook event such as `pre-push`
+    Hooks {
+        event: String,
+        plugins: Vec<PluginRun>,
+    },
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Sync { .. } => "sync",
+            Operation::Restack { .. } => "restack",
+            Operation::DeleteBranches { .. } => "branch deletion",
+            Operation::Hooks { .. } => "hooks",
+        }
+    }
+}
+
+/// A deleted branch, with what's needed to put it back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletedBranch {
+    pub name: String,
+    /// The commit the branch pointed at
+    pub tip: String,
+    /// Its stack parent, if it had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// Its `sage note`, if it had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// How one plugin went on a hook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginRun {
+    pub plugin: String,
+    pub duration_ms: u64,
+    /// What it replied, None when it failed to run or replied with something unreadable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<Verdict>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Why it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How one branch of an operation went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Done,
+    /// Nothing needed doing
+    Skipped,
+    Failed,
+}
+
+/// What an operation did to one branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub branch: String,
+    pub outcome: Outcome,
+    /// The commit the branch pointed at before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// The commit it points at now, when it moved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// What git said when it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    pub started_at: DateTime<Utc>,
+    /// The branch checked out when the operation started
+    pub branch: String,
+    pub operation: Operation,
+    pub status: Status,
+    /// Git commands that undo or finish the operation by hand
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recovery: Vec<String>,
+    /// When `sage undo` reverted the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undone_at: Option<DateTime<Utc>>,
+    /// What happened to each branch so far, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<Step>,
+    /// Every local branch and the commit it pointed at when the operation started
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub refs_before: BTreeMap<String, String>,
+    /// The same when the operation last stopped, whether it finished, failed or was interrupted
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub refs_after: BTreeMap<String, String>,
+}
+
+/// path returns the location of the ledger for the current repository
+pub fn path() -> Result<PathBuf> {
+    let mut path = git::repo::git_dir()?;
+    path.push("sage");
+    path.push("ledger.json");
+    Ok(path)
+}
+
+/// load returns every recorded entry, oldest first
+pub fn load() -> Result<Vec<Entry>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse ledger {}", path.display()))
+}
+
+fn save(entries: &[Entry]) -> Result<()> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(entries)?)?;
+    Ok(())
+}
+
+/// begin records a new running operation and returns its id
+pub fn begin(branch: &str, operation: Operation) -> Result<u64> {
+    append(branch, operation, Status::Running, branch_tips())
+}
+
+/// record_hooks keeps the report of the plugins run on a hook `event`, as failed when any of
+/// them failed or blocked it
+pub fn record_hooks(branch: &str, event: &str, plugins: Vec<PluginRun>) -> Result<u64> {
+    let failed = plugins.iter().any(|run| run.verdict.is_none_or(|verdict| verdict == Verdict::Block));
+    let status = if failed { Status::Failed } else { Status::Done };
+    // Hooks don't move branches, so there's nothing to snapshot
+    append(branch, Operation::Hooks { event: event.to_string(), plugins }, status, BTreeMap::new())
+}
+
+fn append(branch: &str, operation: Operation, status: Status, refs_before: BTreeMap<String, String>) -> Result<u64> {
+    let mut entries = load()?;
+    let id = entries.last().map_or(1, |entry| entry.id + 1);
+    let is_hooks = matches!(operation, Operation::Hooks { .. });
+    entries.push(Entry {
+        id,
+        started_at: Utc::now(),
+        branch: branch.to_string(),
+        operation,
+        status,
+        recovery: Vec::new(),
+        undone_at: None,
+        steps: Vec::new(),
+        refs_before,
+        refs_after: BTreeMap::new(),
+    });
+
+    if is_hooks {
+        // Oldest reports go first
+        let is_report = |entry: &Entry| matches!(entry.operation, Operation::Hooks { .. });
+        let mut reports = entries.iter().filter(|entry| is_report(entry)).count();
+        entries.retain(|entry| {
+            if reports > MAX_HOOK_REPORTS && is_report(entry) {
+                reports -= 1;
+                return false;
+            }
+            true
+        });
+    }
+    let excess = entries.len().saturating_sub(MAX_ENTRIES);
+    entries.drain(..excess);
+    save(&entries)?;
+    Ok(id)
+}
+
+/// record adds a step to a recorded entry, and reports it to anything reading `--events`
+pub fn record(id: u64, step: Step) -> Result<()> {
+    let mut operation = "";
+    update(id, |entry| {
+        operation = entry.operation.name();
+        entry.steps.push(step.clone());
+    })?;
+    events::emit(Event::ActionApplied { operation, step: &step });
+    Ok(())
+}
+
+/// update changes a recorded entry in place. When the change stops the operation, the branch
+/// tips are recorded as where it left them.
+pub fn update(id: u64, change: impl FnOnce(&mut Entry)) -> Result<()> {
+    let mut entries = load()?;
+    let entry = entries
+        .iter_mut()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| anyhow!("No ledger entry {}", id))?;
+    let status = entry.status;
+    change(entry);
+    if entry.status != status && matches!(entry.status, Status::Done | Status::Failed | Status::Interrupted) {
+        entry.refs_after = branch_tips();
+    }
+    save(&entries)
+}
+
+/// Snapshots are a nicety, so an operation is still recorded when the branches can't be listed
+fn branch_tips() -> BTreeMap<String, String> {
+    git::checkpoint::branch_tips().unwrap_or_default().into_iter().collect()
+}
+
+/// set_status changes the status of a recorded entry
+pub fn set_status(id: u64, status: Status) -> Result<()> {
+    update(id, |entry| entry.status = status)
+}
+
+/// last_interrupted returns the most recent operation that can be resumed, if it is also the most
+/// recent operation; anything started since then has moved the repository on. Hooks that ran in
+/// the meantime don't count.
+pub fn last_interrupted() -> Result<Option<Entry>> {
+    Ok(load()?
+        .into_iter()
+        .rev()
+        .find(|entry| !matches!(entry.operation, Operation::Hooks { .. }))
+        .filter(|entry| entry.status == Status::Interrupted))
+}
+
+/// last_hooks returns the most recent hook report, for `event` when given
+pub fn last_hooks(event: Option<&str>) -> Result<Option<Entry>> {
+    Ok(load()?.into_iter().rev().find(|entry| match &entry.operation {
+        Operation::Hooks { event: ran, .. } => event.is_none_or(|event| event == ran),
+        _ => false,
+    }))
+}
+
+/// last_undone returns the operation `sage undo` reverted most recently, which `sage redo` can
+/// replay
+pub fn last_undone() -> Result<Option<Entry>> {
+    Ok(load()?
+        .into_iter()
+        .filter(|entry| entry.status == Status::Undone)
+        .max_by_key(|entry| entry.undone_at))
+}
+
+/// last_undoable returns the most recent finished operation `sage undo` can revert
+pub fn last_undoable() -> Result<Option<Entry>> {
+    Ok(load()?
+        .into_iter()
+        .rev()
+        .find(|entry| entry.status == Status::Done && matches!(entry.operation, Operation::DeleteBranches { .. })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_round_trips_through_json() {
+        let entry = Entry {
+            id: 3,
+            started_at: Utc::now(),
+            branch: "feature".to_string(),
+            operation: Operation::Restack { root: "main".to_string(), remaining: vec!["child".to_string()] },
+            status: Status::Interrupted,
+            recovery: vec!["git rebase --abort".to_string()],
+            undone_at: None,
+            steps: vec![Step {
+                branch: "feature".to_string(),
+                outcome: Outcome::Done,
+                before: Some("a1".to_string()),
+                after: Some("b2".to_string()),
+                error: None,
+            }],
+            refs_before: BTreeMap::from([("feature".to_string(), "a1".to_string())]),
+            refs_after: BTreeMap::from([("feature".to_string(), "b2".to_string())]),
+        };
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""kind":"restack""#));
+        assert!(json.contains(r#""status":"interrupted""#));
+        assert!(json.contains(r#""outcome":"done""#));
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), entry);
+    }
+}
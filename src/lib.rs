@@ -1,13 +1,24 @@
-pub mod ai;
 pub mod app;
 pub mod cli;
 pub mod config;
 pub mod errors;
+pub mod events;
+pub mod forge;
+#[cfg(fuzzing)]
+pub mod fuzz;
 pub mod gh;
 pub mod git;
+pub mod i18n;
+pub mod ledger;
+pub mod plugin;
+pub mod policy;
+pub mod profile;
 pub mod tui;
 pub mod ui;
 pub mod update;
 
+// AI lives in its own crate so other tools can use it too
+pub use sage_ai as ai;
+
 // Re-export common types for easier access
 pub use errors::{AppError, GitError}; 
\ No newline at end of file
@@ -1,16 +1,21 @@
-use sage::{cli::Run, update::check_for_updates};
+use sage::cli::Run;
 use clap::Parser;
 use std::process::ExitCode;
 
 #[tokio::main]
 async fn main() -> ExitCode {
-    let _ = check_for_updates().await;
+    sage::app::interrupt::install();
+    sage::app::crash::install();
+    sage::ui::accessible::apply();
 
     // Runs the main CLI
-    match sage::cli::Cmd::parse().run().await {
+    match sage::cli::Cli::parse().run().await {
         Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
-            eprintln!("Error: {}", err);
+            sage::app::crash::record_error(&err);
+            // In GitHub Actions the failure also shows up on the pull request
+            sage::ui::actions::Annotation { title: "sage", message: &err.to_string(), ..Default::default() }.emit();
+            eprintln!("{}", sage::t!("error", error = err));
             ExitCode::FAILURE
         }
     }
@@ -0,0 +1,305 @@
+//! Sage plugins
+//!
+//! A plugin is a directory under `<config dir>/sage/plugins` (or `$SAGE_PLUGIN_DIR`) containing a
+//! `plugin.json` manifest. Plugins subscribe to hook events; when one fires, sage runs the
+//! plugin's command with the event as JSON on stdin and reads a JSON reply from stdout.
+//!
+//! Events fired today are `pre-commit` and `post-commit` around `sage commit`, and `pre-push`
+//! before sage pushes a branch. Every run is reported in the ledger for `sage hooks last`.
+//!
+//! `sage plugin install` and `sage policy sync` check a plugin's minisign signature before
+//! installing it, see [`signature`].
+
+pub mod signature;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::{config, git, ledger};
+
+/// Name of the manifest file every plugin directory must contain
+pub const MANIFEST_FILE: &str = "plugin.json";
+
+/// Plugins found when the first hook fired, so a command firing hooks for every branch it pushes
+/// only looks through the plugin directory once, and commands firing none never do
+static LOADED: Mutex<Option<Vec<Plugin>>> = Mutex::new(None);
+
+/// Describes a plugin and the hooks it wants to run on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Hook events the plugin subscribes to, e.g. `pre-commit`
+    #[serde(default)]
+    pub hooks: Vec<String>,
+    /// Command to run, relative to the plugin directory
+    pub command: String,
+}
+
+/// An installed plugin
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub manifest: Manifest,
+    pub dir: PathBuf,
+}
+
+/// The payload sent to a plugin on stdin
+#[derive(Debug, Serialize)]
+pub struct Event<'a, T: Serialize> {
+    pub event: &'a str,
+    pub data: &'a T,
+}
+
+/// What a plugin thinks about an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    #[default]
+    Allow,
+    Warn,
+    Block,
+}
+
+/// A plugin's response to an event
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reply {
+    #[serde(default)]
+    pub verdict: Verdict,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// plugin_dir returns where plugins are installed
+pub fn plugin_dir() -> Result<PathBuf> {
+    if let Ok(path) = env::var("SAGE_PLUGIN_DIR") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let mut path = dirs::config_dir().ok_or_else(|| anyhow!("Could not find config directory"))?;
+    path.push("sage");
+    path.push("plugins");
+    Ok(path)
+}
+
+/// discover returns every installed plugin with a readable manifest
+pub fn discover() -> Result<Vec<Plugin>> {
+    let dir = plugin_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut plugins = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let manifest_path = path.join(MANIFEST_FILE);
+        if !manifest_path.exists() {
+            continue;
+        }
+
+        let manifest = parse_manifest(&fs::read_to_string(&manifest_path)?)
+            .with_context(|| format!("Invalid plugin manifest {}", manifest_path.display()))?;
+        plugins.push(Plugin { manifest, dir: path });
+    }
+
+    plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    Ok(plugins)
+}
+
+/// verify checks the plugin in `dir` against its publisher's signature, returning the id of the
+/// key that signed it. Unsigned plugins are None, or refused when plugins.require_signed is on.
+pub fn verify(dir: &Path) -> Result<Option<String>> {
+    if !signature::is_signed(dir) {
+        if config::get_bool("plugins.require_signed", false) {
+            return Err(anyhow!("The plugin isn't signed, and plugins.require_signed only allows signed plugins"));
+        }
+        return Ok(None);
+    }
+
+    let keys = signature::trusted_keys(&config::get("plugins.trusted_keys").unwrap_or_default())?;
+    if keys.is_empty() {
+        return Err(anyhow!(
+            "The plugin is signed, but no publisher keys are trusted. Add its minisign public key to plugins.trusted_keys"
+        ));
+    }
+    let key = signature::verify_dir(dir, &keys)?;
+    Ok(Some(key.id_hex()))
+}
+
+/// install checks the plugin in `from` and copies it into the plugin directory, replacing any
+/// copy already installed. Returns its manifest and the key that signed it, if any.
+pub fn install(from: &Path) -> Result<(Manifest, Option<String>)> {
+    let manifest_path = from.join(MANIFEST_FILE);
+    let contents = fs::read_to_string(&manifest_path).with_context(|| format!("{} has no {}", from.display(), MANIFEST_FILE))?;
+    let manifest = parse_manifest(&contents).with_context(|| format!("Invalid plugin manifest {}", manifest_path.display()))?;
+    let signed_by = verify(from).map_err(|e| anyhow!("Refusing to install plugin {}: {}", manifest.name, e))?;
+
+    let target = plugin_dir()?.join(&manifest.name);
+    let _ = fs::remove_dir_all(&target);
+    copy_dir(from, &target)?;
+    *LOADED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    Ok((manifest, signed_by))
+}
+
+/// Copy a directory and everything in it, keeping file permissions so plugin commands stay
+/// executable
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Plugins for hooks to run, found on first use
+fn loaded() -> Result<Vec<Plugin>> {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if loaded.is_none() {
+        *loaded = Some(discover()?);
+    }
+    Ok(loaded.clone().unwrap_or_default())
+}
+
+/// run_hook sends an event to every plugin subscribed to it, returning each plugin's reply. A
+/// report of the run is kept in the ledger, including the plugin that failed if one did.
+pub fn run_hook<T: Serialize>(event: &str, data: &T) -> Result<Vec<(String, Reply)>> {
+    let payload = serde_json::to_string(&Event { event, data })?;
+
+    let mut replies = Vec::new();
+    let mut runs = Vec::new();
+    let mut failure = None;
+    for plugin in loaded()? {
+        if !plugin.manifest.hooks.iter().any(|hook| hook == event) {
+            continue;
+        }
+
+        let started = Instant::now();
+        let result = run_plugin(&plugin, &payload);
+        let mut run = ledger::PluginRun {
+            plugin: plugin.manifest.name.clone(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            verdict: None,
+            message: None,
+            error: None,
+        };
+        match result {
+            Ok(reply) => {
+                run.verdict = Some(reply.verdict);
+                run.message = reply.message.clone();
+                runs.push(run);
+                replies.push((plugin.manifest.name.clone(), reply));
+            }
+            Err(e) => {
+                run.error = Some(e.to_string());
+                runs.push(run);
+                failure = Some(e.context(format!("Plugin {} failed on {}", plugin.manifest.name, event)));
+                break;
+            }
+        }
+    }
+
+    if !runs.is_empty() {
+        // A report that can't be written mustn't get in the way of the hook itself
+        let _ = ledger::record_hooks(&git::branch::current().unwrap_or_default(), event, runs);
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(replies),
+    }
+}
+
+fn run_plugin(plugin: &Plugin, payload: &str) -> Result<Reply> {
+    let mut child = Command::new(resolve_command(&plugin.dir, &plugin.manifest.command))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("exited with {}", output.status));
+    }
+
+    parse_reply(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Commands starting with `./` are relative to the plugin directory
+fn resolve_command(dir: &Path, command: &str) -> PathBuf {
+    match command.strip_prefix("./") {
+        Some(relative) => dir.join(relative),
+        None => PathBuf::from(command),
+    }
+}
+
+pub(crate) fn parse_manifest(contents: &str) -> Result<Manifest> {
+    let manifest: Manifest = serde_json::from_str(contents)?;
+    check_name(&manifest.name)?;
+    Ok(manifest)
+}
+
+/// check_name makes sure a plugin name is a single directory name, since the plugin is installed
+/// under it and anything already there is removed
+pub(crate) fn check_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("plugin name must not be empty"));
+    }
+    if name == "." || name == ".." || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')) {
+        return Err(anyhow!("plugin name {:?} may only use letters, digits, '.', '_' and '-'", name));
+    }
+    Ok(())
+}
+
+/// Plugins that have nothing to say may print nothing at all
+pub(crate) fn parse_reply(output: &str) -> Result<Reply> {
+    if output.trim().is_empty() {
+        return Ok(Reply::default());
+    }
+    serde_json::from_str(output.trim()).context("plugin replied with invalid JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = parse_manifest(r#"{"name": "policy", "hooks": ["pre-commit"], "command": "./check.sh"}"#).unwrap();
+        assert_eq!(manifest.name, "policy");
+        assert_eq!(manifest.hooks, vec!["pre-commit"]);
+        assert!(parse_manifest(r#"{"name": " ", "command": "x"}"#).is_err());
+        for name in ["..", ".", "../../.ssh", "a/b", "/tmp", "a\\b", "ünicode"] {
+            assert!(check_name(name).is_err(), "{}", name);
+        }
+        assert!(check_name("policy-v1.2_beta").is_ok());
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("").unwrap().verdict, Verdict::Allow);
+
+        let reply = parse_reply(r#"{"verdict": "block", "message": "no binaries"}"#).unwrap();
+        assert_eq!(reply.verdict, Verdict::Block);
+        assert_eq!(reply.message.as_deref(), Some("no binaries"));
+
+        assert!(parse_reply("not json").is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Checking plugins against their publisher's minisign signature
+//!
+//! A signed plugin ships a `SHA256SUMS` file listing every other file in its directory, in the
+//! format `sha256sum` writes, and `SHA256SUMS.minisig` made with `minisign -Sm SHA256SUMS`. The
+//! signature is checked against the publisher keys in `plugins.trusted_keys` (the `RW...` line of
+//! a minisign public key), and each file against its checksum, so nothing can be added to or
+//! changed in the plugin after it was signed. Everything is checked locally, so this works
+//! without network access. Org policy bundles are signed the same way, against
+//! `policy.trusted_keys`.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use blake2::Blake2b512;
+use ed25519_dalek::{Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// The checksums a signed plugin ships
+pub const SUMS_FILE: &str = "SHA256SUMS";
+/// The minisign signature of the checksums
+pub const SIGNATURE_FILE: &str = "SHA256SUMS.minisig";
+
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+/// A minisign public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// parse reads the base64 line of a minisign public key
+    pub fn parse(text: &str) -> Result<PublicKey> {
+        let bytes = decode(text.trim())?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return Err(anyhow!("{} isn't a minisign public key", text.trim()));
+        }
+        Ok(PublicKey { id: bytes[2..10].try_into()?, key: bytes[10..].try_into()? })
+    }
+
+    /// id_hex is the key id as minisign shows it
+    pub fn id_hex(&self) -> String {
+        self.id.iter().rev().map(|byte| format!("{:02X}", byte)).collect()
+    }
+}
+
+/// A minisign signature file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Signed over the BLAKE2b-512 hash of the file rather than the file itself, minisign's
+    /// default since 0.10
+    prehashed: bool,
+    key_id: [u8; 8],
+    signature: [u8; 64],
+    pub trusted_comment: String,
+    global_signature: [u8; 64],
+}
+
+impl Signature {
+    pub fn parse(text: &str) -> Result<Signature> {
+        let lines = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+        let [untrusted, signature, trusted, global] = lines.as_slice() else {
+            return Err(anyhow!("A minisign signature has four lines"));
+        };
+        if !untrusted.starts_with("untrusted comment:") {
+            return Err(anyhow!("A minisign signature starts with an untrusted comment"));
+        }
+        let trusted_comment = trusted
+            .strip_prefix(TRUSTED_PREFIX)
+            .ok_or_else(|| anyhow!("A minisign signature's third line is its trusted comment"))?;
+
+        let bytes = decode(signature)?;
+        let prehashed = match bytes.get(..2) {
+            Some(b"ED") => true,
+            Some(b"Ed") => false,
+            _ => return Err(anyhow!("Unsupported minisign signature algorithm")),
+        };
+        if bytes.len() != 74 {
+            return Err(anyhow!("The minisign signature is the wrong length"));
+        }
+
+        Ok(Signature {
+            prehashed,
+            key_id: bytes[2..10].try_into()?,
+            signature: bytes[10..].try_into()?,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature: decode(global)?.try_into().map_err(|_| anyhow!("The minisign global signature is the wrong length"))?,
+        })
+    }
+
+    /// verify checks `message` was signed by one of `keys`, returning the key that signed it
+    pub fn verify<'a>(&self, message: &[u8], keys: &'a [PublicKey]) -> Result<&'a PublicKey> {
+        let key = keys.iter().find(|key| key.id == self.key_id).ok_or_else(|| {
+            let id = PublicKey { id: self.key_id, key: [0; 32] }.id_hex();
+            anyhow!("Signed with key {}, which isn't one of the trusted keys", id)
+        })?;
+        let verifier = VerifyingKey::from_bytes(&key.key).map_err(|_| anyhow!("Key {} isn't a valid Ed25519 key", key.id_hex()))?;
+        let signature = ed25519_dalek::Signature::from_bytes(&self.signature);
+        let global_signature = ed25519_dalek::Signature::from_bytes(&self.global_signature);
+
+        let hashed;
+        let signed = if self.prehashed {
+            hashed = Blake2b512::digest(message);
+            &hashed[..]
+        } else {
+            message
+        };
+        verifier.verify(signed, &signature).map_err(|_| anyhow!("The signature doesn't match"))?;
+
+        // The trusted comment is signed too, so it can't be swapped for another
+        let mut global = self.signature.to_vec();
+        global.extend_from_slice(self.trusted_comment.as_bytes());
+        verifier.verify(&global, &global_signature).map_err(|_| anyhow!("The signature's trusted comment doesn't match"))?;
+        Ok(key)
+    }
+}
+
+fn decode(text: &str) -> Result<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD.decode(text).context("Invalid base64 in minisign key or signature")
+}
+
+/// trusted_keys reads the publisher keys plugins.trusted_keys pins, comma separated
+pub fn trusted_keys(setting: &str) -> Result<Vec<PublicKey>> {
+    setting.split(',').map(str::trim).filter(|key| !key.is_empty()).map(PublicKey::parse).collect()
+}
+
+/// is_signed returns if a plugin directory ships a signature
+pub fn is_signed(dir: &Path) -> bool {
+    dir.join(SIGNATURE_FILE).exists()
+}
+
+/// verify_dir checks the plugin or policy bundle in `dir` was signed by one of `keys` and hasn't
+/// changed since, returning the key that signed it
+pub fn verify_dir<'a>(dir: &Path, keys: &'a [PublicKey]) -> Result<&'a PublicKey> {
+    let sums = fs::read(dir.join(SUMS_FILE)).with_context(|| format!("{} has no {}", dir.display(), SUMS_FILE))?;
+    let signature = Signature::parse(&fs::read_to_string(dir.join(SIGNATURE_FILE))?)?;
+    let key = signature.verify(&sums, keys)?;
+
+    let expected = parse_sums(&String::from_utf8_lossy(&sums))?;
+    let mut actual = BTreeMap::new();
+    hash_files(dir, dir, &mut actual)?;
+    for (path, hash) in &actual {
+        match expected.get(path) {
+            None => return Err(anyhow!("{} isn't covered by the signature", path)),
+            Some(expected) if !expected.eq_ignore_ascii_case(hash) => {
+                return Err(anyhow!("{} has changed since it was signed", path));
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(missing) = expected.keys().find(|path| !actual.contains_key(*path)) {
+        return Err(anyhow!("{} is signed but missing", missing));
+    }
+    Ok(key)
+}
+
+/// Read `sha256sum` output into path and hash, dropping the `./` and binary-mode `*` it may add
+fn parse_sums(text: &str) -> Result<BTreeMap<String, String>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (hash, path) = line.split_once(' ').ok_or_else(|| anyhow!("Invalid line in {}: {}", SUMS_FILE, line))?;
+            let path = path.trim_start_matches([' ', '*']).trim_start_matches("./");
+            Ok((path.to_string(), hash.to_string()))
+        })
+        .collect()
+}
+
+/// Hash every file under `dir` apart from the signature itself, by path relative to `root`
+fn hash_files(root: &Path, dir: &Path, hashes: &mut BTreeMap<String, String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            hash_files(root, &path, hashes)?;
+            continue;
+        }
+        let relative = path.strip_prefix(root)?.to_string_lossy().replace('\\', "/");
+        if relative == SUMS_FILE || relative == SIGNATURE_FILE {
+            continue;
+        }
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut fs::File::open(&path)?, &mut hasher)?;
+        hashes.insert(relative, format!("{:x}", hasher.finalize()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Sign `message` the way minisign does, returning the public key line and signature file
+    fn sign(message: &[u8], prehashed: bool) -> (String, String) {
+        let pair = SigningKey::from_bytes(&[7; 32]);
+        let id = [1, 2, 3, 4, 5, 6, 7, 8];
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let mut public = b"Ed".to_vec();
+        public.extend_from_slice(&id);
+        public.extend_from_slice(pair.verifying_key().as_bytes());
+
+        let signed = if prehashed { Blake2b512::digest(message).to_vec() } else { message.to_vec() };
+        let signature = pair.sign(&signed);
+        let mut line = if prehashed { b"ED".to_vec() } else { b"Ed".to_vec() };
+        line.extend_from_slice(&id);
+        line.extend_from_slice(&signature.to_bytes());
+
+        let trusted = "timestamp:1760000000\tfile:SHA256SUMS";
+        let mut global = signature.to_bytes().to_vec();
+        global.extend_from_slice(trusted.as_bytes());
+        let file = format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+            engine.encode(&line),
+            trusted,
+            engine.encode(pair.sign(&global).to_bytes())
+        );
+        (engine.encode(&public), file)
+    }
+
+    #[test]
+    fn test_verify() {
+        for prehashed in [true, false] {
+            let (public, file) = sign(b"checksums", prehashed);
+            let keys = trusted_keys(&format!(" {} ", public)).unwrap();
+            let signature = Signature::parse(&file).unwrap();
+
+            assert_eq!(signature.verify(b"checksums", &keys).unwrap().id_hex(), "0807060504030201");
+            assert!(signature.verify(b"checksum5", &keys).is_err());
+            assert!(signature.verify(b"checksums", &[]).is_err());
+
+            let tampered = file.replace("file:SHA256SUMS", "file:other");
+            assert!(Signature::parse(&tampered).unwrap().verify(b"checksums", &keys).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_dir() {
+        let dir = std::env::temp_dir().join(format!("sage-signature-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(dir.join("plugin.json"), "{}").unwrap();
+        fs::write(dir.join("bin/check.sh"), "echo ok").unwrap();
+
+        let sum = |contents: &[u8]| hex(&Sha256::digest(contents));
+        let sums = format!("{}  ./plugin.json\n{} *bin/check.sh\n", sum(b"{}"), sum(b"echo ok"));
+        fs::write(dir.join(SUMS_FILE), &sums).unwrap();
+        let (public, file) = sign(sums.as_bytes(), true);
+        fs::write(dir.join(SIGNATURE_FILE), file).unwrap();
+        let keys = trusted_keys(&public).unwrap();
+
+        assert!(verify_dir(&dir, &keys).is_ok());
+        fs::write(dir.join("bin/check.sh"), "echo changed").unwrap();
+        assert!(verify_dir(&dir, &keys).is_err());
+        fs::write(dir.join("bin/check.sh"), "echo ok").unwrap();
+        fs::write(dir.join("extra.sh"), "rm -rf /").unwrap();
+        assert!(verify_dir(&dir, &keys).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
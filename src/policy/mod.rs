@@ -0,0 +1,377 @@
+//! Org policy bundles
+//!
+//! An organisation can publish the settings, plugins and rules everyone should work with as a
+//! bundle. `policy.source` points at it: a git repository, or a `.tar.gz` URL that is checked
+//! against `policy.sha256` when that is set. `sage policy sync` fetches it into
+//! `<config dir>/sage/policy` (or `$SAGE_POLICY_DIR`), installs its plugins and records where it
+//! came from, and `sage doctor` checks everything still follows it.
+//!
+//! With `policy.trusted_keys` set, the bundle must be signed by one of those keys the way plugins
+//! are (see [`crate::plugin::signature`]): a `SHA256SUMS` at its root covering every file, and
+//! `SHA256SUMS.minisig`. Anything unsigned, or changed since it was signed, is refused.
+//!
+//! The bundle has a `policy.json` at its root:
+//!
+//! ```json
+//! {
+//!   "name": "acme",
+//!   "version": "2026.10",
+//!   "plugins": ["secret-scan"],
+//!   "protected_branches": ["main", "release/*"],
+//!   "commits": { "conventional": true, "types": ["feat", "fix", "chore"] },
+//!   "config": { "commit.signoff": "true" }
+//! }
+//! ```
+//!
+//! Required plugins ship in the bundle under `plugins/<name>/`, and are installed like any other,
+//! so a policy setting `plugins.require_signed` has its own plugins checked too. Settings in `config` are
+//! defaults underneath the global and repository config; `sage doctor` flags any overridden.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::{app::guard::glob_match, git, plugin::{self, signature::{self, PublicKey}}, update::install};
+
+/// Name of the file describing a bundle
+pub const POLICY_FILE: &str = "policy.json";
+/// Commit types allowed by a conventional commit policy that doesn't list its own
+const DEFAULT_TYPES: &[&str] = &["build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test"];
+
+/// The installed policy's settings, read on the first config lookup and replaced by a sync
+static CONFIG_DEFAULTS: Mutex<Option<BTreeMap<String, String>>> = Mutex::new(None);
+
+/// What an organisation's policy asks for
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Policy {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    /// Plugins everyone must have, shipped in the bundle under `plugins/<name>/`
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Branches nobody commits or pushes to directly, as globs like `release/*`
+    #[serde(default)]
+    pub protected_branches: Vec<String>,
+    #[serde(default)]
+    pub commits: Commits,
+    /// Settings the organisation expects, as `section.key` pairs
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+}
+
+/// Rules for commit messages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commits {
+    /// Subjects must look like `type(scope): summary`
+    #[serde(default)]
+    pub conventional: bool,
+    /// The types allowed, a common set when empty
+    #[serde(default)]
+    pub types: Vec<String>,
+}
+
+/// Where the installed bundle came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Installed {
+    pub source: String,
+    pub synced_at: DateTime<Utc>,
+    /// Id of the key the bundle was signed with, when policy.trusted_keys asked for a signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_by: Option<String>,
+}
+
+impl Policy {
+    /// is_protected returns if `branch` is one of the protected branches. Patterns without a `/`
+    /// only match branches without one, so `main` doesn't protect `feature/main`.
+    pub fn is_protected(&self, branch: &str) -> bool {
+        self.protected_branches.iter().any(|pattern| {
+            if pattern.contains('/') { glob_match(pattern, branch) } else { !branch.contains('/') && glob_match(pattern, branch) }
+        })
+    }
+
+    /// check_message explains what's wrong with a commit message under the commit rules, None
+    /// when it follows them
+    pub fn check_message(&self, message: &str) -> Option<String> {
+        if !self.commits.conventional {
+            return None;
+        }
+
+        let subject = message.lines().next().unwrap_or_default();
+        let Some((prefix, summary)) = subject.split_once(": ") else {
+            return Some(format!("'{}' isn't a conventional commit, e.g. 'feat: add login'", subject));
+        };
+        let kind = prefix.trim_end_matches('!');
+        let kind = match kind.split_once('(') {
+            Some((kind, scope)) if scope.ends_with(')') && scope.len() > 1 => kind,
+            Some(_) => return Some(format!("'{}' has a malformed scope, e.g. 'feat(api): add login'", prefix)),
+            None => kind,
+        };
+
+        let types = self.commits.types();
+        if !types.contains(&kind) {
+            return Some(format!("'{}' isn't an allowed commit type (use one of {})", kind, types.join(", ")));
+        }
+        if summary.trim().is_empty() {
+            return Some("The commit message has no summary after the type".to_string());
+        }
+        None
+    }
+}
+
+impl Commits {
+    /// types returns the commit types allowed
+    pub fn types(&self) -> Vec<&str> {
+        if self.types.is_empty() { DEFAULT_TYPES.to_vec() } else { self.types.iter().map(String::as_str).collect() }
+    }
+
+    /// describe puts the rules into words, e.g. for an AI writing commit messages, None when
+    /// there are none
+    pub fn describe(&self) -> Option<String> {
+        self.conventional.then(|| {
+            format!(
+                "Commit subjects must follow Conventional Commits, `type(scope): summary`, with type one of {}",
+                self.types().join(", ")
+            )
+        })
+    }
+}
+
+/// dir returns where the policy bundle is installed
+pub fn dir() -> Result<PathBuf> {
+    if let Ok(path) = env::var("SAGE_POLICY_DIR") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let mut path = dirs::config_dir().ok_or_else(|| anyhow!("Could not find config directory"))?;
+    path.push("sage");
+    path.push("policy");
+    Ok(path)
+}
+
+fn bundle_dir() -> Result<PathBuf> {
+    Ok(dir()?.join("bundle"))
+}
+
+fn installed_path() -> Result<PathBuf> {
+    Ok(dir()?.join("installed.json"))
+}
+
+pub(crate) fn parse(contents: &str) -> Result<Policy> {
+    let policy: Policy = serde_json::from_str(contents)?;
+    if policy.name.trim().is_empty() {
+        return Err(anyhow!("policy name must not be empty"));
+    }
+    // The names become directories under plugins/ in the bundle and the plugin directory
+    for name in &policy.plugins {
+        plugin::check_name(name)?;
+    }
+    Ok(policy)
+}
+
+fn read(bundle: &Path) -> Result<Policy> {
+    let path = bundle.join(POLICY_FILE);
+    let contents = fs::read_to_string(&path).with_context(|| format!("The policy bundle has no {}", POLICY_FILE))?;
+    parse(&contents).with_context(|| format!("Invalid policy {}", path.display()))
+}
+
+/// load returns the installed policy, None when none has been synced
+pub fn load() -> Result<Option<Policy>> {
+    let bundle = bundle_dir()?;
+    if !bundle.join(POLICY_FILE).exists() {
+        return Ok(None);
+    }
+    read(&bundle).map(Some)
+}
+
+/// installed returns where the installed policy was synced from and when
+pub fn installed() -> Result<Option<Installed>> {
+    let path = installed_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(&path)?)?))
+}
+
+/// config_defaults returns the settings the installed policy expects, nothing when there's no
+/// policy or it can't be read. The bundle is only read once, as every config lookup asks.
+pub fn config_defaults() -> BTreeMap<String, String> {
+    let mut defaults = CONFIG_DEFAULTS.lock().unwrap_or_else(|e| e.into_inner());
+    defaults
+        .get_or_insert_with(|| load().ok().flatten().map(|policy| policy.config).unwrap_or_default())
+        .clone()
+}
+
+/// bundled_plugin returns the manifest of a plugin as the installed bundle ships it
+pub fn bundled_plugin(name: &str) -> Result<plugin::Manifest> {
+    let path = bundle_dir()?.join("plugins").join(name).join(plugin::MANIFEST_FILE);
+    let contents = fs::read_to_string(&path).with_context(|| format!("The policy bundle doesn't ship plugin {}", name))?;
+    plugin::parse_manifest(&contents)
+}
+
+/// sync fetches the bundle at `source` and installs it with its plugins, replacing whatever was
+/// installed before. Tarballs are checked against `sha256` when given, and the bundle must be
+/// signed by one of `keys` unless there are none.
+pub fn sync(source: &str, sha256: Option<&str>, keys: &[PublicKey]) -> Result<(Policy, Installed)> {
+    let dir = dir()?;
+    fs::create_dir_all(&dir)?;
+    let staging = dir.join(format!("staging-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging)?;
+
+    let result = fetch(source, sha256, &staging).and_then(|bundle| {
+        let signed_by = check_signature(&bundle, keys)?;
+        let policy = read(&bundle)?;
+        for name in &policy.plugins {
+            if !bundle.join("plugins").join(name).join(plugin::MANIFEST_FILE).exists() {
+                return Err(anyhow!("The policy requires plugin {} but the bundle doesn't ship it", name));
+            }
+        }
+        Ok((bundle, policy, signed_by))
+    });
+    let (bundle, policy, signed_by) = match result {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    // Only swap the new bundle in once it's known to be good
+    let target = bundle_dir()?;
+    let _ = fs::remove_dir_all(&target);
+    fs::rename(&bundle, &target)?;
+    let _ = fs::remove_dir_all(&staging);
+    *CONFIG_DEFAULTS.lock().unwrap_or_else(|e| e.into_inner()) = Some(policy.config.clone());
+
+    // The new bundle's settings are in effect by now, so plugins.require_signed applies to its
+    // own plugins
+    for name in &policy.plugins {
+        plugin::install(&target.join("plugins").join(name))?;
+    }
+
+    let record = Installed { source: source.to_string(), synced_at: Utc::now(), signed_by };
+    fs::write(installed_path()?, serde_json::to_string_pretty(&record)?)?;
+    Ok((policy, record))
+}
+
+/// Check the bundle in `dir` was signed by one of `keys`, returning the id of the key that signed
+/// it. Without keys nothing is checked.
+fn check_signature(dir: &Path, keys: &[PublicKey]) -> Result<Option<String>> {
+    if keys.is_empty() {
+        return Ok(None);
+    }
+    if !signature::is_signed(dir) {
+        return Err(anyhow!("The policy bundle isn't signed, and policy.trusted_keys only allows signed bundles. Nothing was installed"));
+    }
+    let key = signature::verify_dir(dir, keys)
+        .map_err(|e| anyhow!("The policy bundle's signature doesn't check out: {}. Nothing was installed", e))?;
+    Ok(Some(key.id_hex()))
+}
+
+/// is_tarball returns if a source is a tarball rather than a git repository
+pub fn is_tarball(source: &str) -> bool {
+    source.ends_with(".tar.gz") || source.ends_with(".tgz")
+}
+
+/// Fetch a bundle into `staging`, returning the directory holding its policy.json
+fn fetch(source: &str, sha256: Option<&str>, staging: &Path) -> Result<PathBuf> {
+    if !is_tarball(source) {
+        let bundle = staging.join("bundle");
+        let output = git::command().args(["clone", "--quiet", "--depth", "1", source]).arg(&bundle).output()?;
+        if !output.status.success() {
+            return Err(anyhow!("Failed to clone the policy bundle {}: {}", source, String::from_utf8_lossy(&output.stderr)));
+        }
+        let _ = fs::remove_dir_all(bundle.join(".git"));
+        return Ok(bundle);
+    }
+
+    let archive = staging.join("bundle.tar.gz");
+    install::download(source, &archive)?;
+    if let Some(expected) = sha256 {
+        let actual = install::sha256(&archive)?;
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(anyhow!(
+                "The policy bundle doesn't match policy.sha256 (expected {}, got {}). Nothing was installed",
+                expected.trim(),
+                actual
+            ));
+        }
+    }
+
+    let unpacked = staging.join("unpacked");
+    fs::create_dir_all(&unpacked)?;
+    let output = Command::new("tar").arg("-xzf").arg(&archive).arg("-C").arg(&unpacked).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to unpack the policy bundle: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    // Tarballs often wrap everything in a single directory
+    if unpacked.join(POLICY_FILE).exists() {
+        return Ok(unpacked);
+    }
+    let entries = fs::read_dir(&unpacked)?.collect::<std::io::Result<Vec<_>>>()?;
+    match entries.as_slice() {
+        [entry] if entry.path().join(POLICY_FILE).exists() => Ok(entry.path()),
+        _ => Err(anyhow!("The policy bundle has no {}", POLICY_FILE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let policy = parse(r#"{"name": "acme", "protected_branches": ["main"], "config": {"commit.signoff": "true"}}"#).unwrap();
+        assert_eq!(policy.name, "acme");
+        assert_eq!(policy.config.get("commit.signoff").map(String::as_str), Some("true"));
+        assert!(!policy.commits.conventional);
+        assert!(parse(r#"{"name": ""}"#).is_err());
+        assert!(parse(r#"{"name": "acme", "plugins": ["../../bin"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_check_signature() {
+        let dir = std::env::temp_dir().join(format!("sage-policy-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(POLICY_FILE), r#"{"name": "acme"}"#).unwrap();
+        assert_eq!(check_signature(&dir, &[]).unwrap(), None);
+
+        let keys = signature::trusted_keys("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3").unwrap();
+        let err = check_signature(&dir, &keys).unwrap_err();
+        assert!(err.to_string().contains("isn't signed"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_is_protected() {
+        let policy = Policy { protected_branches: vec!["main".to_string(), "release/*".to_string()], ..Policy::default() };
+        assert!(policy.is_protected("main"));
+        assert!(policy.is_protected("release/1.2"));
+        assert!(!policy.is_protected("feature/main"));
+        assert!(!policy.is_protected("feature"));
+    }
+
+    #[test]
+    fn test_check_message() {
+        let mut policy = Policy::default();
+        assert_eq!(policy.check_message("whatever"), None);
+
+        policy.commits.conventional = true;
+        assert_eq!(policy.check_message("feat: add login\n\nDetails"), None);
+        assert_eq!(policy.check_message("fix(api)!: drop v1"), None);
+        assert!(policy.check_message("add login").is_some());
+        assert!(policy.check_message("feature: add login").is_some());
+        assert!(policy.check_message("feat(: add login").is_some());
+
+        policy.commits.types = vec!["feat".to_string()];
+        assert!(policy.check_message("fix: typo").is_some());
+    }
+}
@@ -0,0 +1,265 @@
+//! Timing where a command spends its time
+//!
+//! With `--profile` (or `sage profile <command>`), sage records how long each phase of the
+//! command took: git calls, GitHub and other network requests, AI requests and rendering output.
+//! Code marks a phase with [`span`], which costs nothing unless profiling is on. Git calls are
+//! timed by git itself through its trace2 event log (see [`git_trace`]), so every git process
+//! shows up with its arguments.
+//!
+//! At the end, a breakdown is printed to stderr and the whole run is saved as a trace for
+//! chrome://tracing or https://ui.perfetto.dev.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The profile of the running command, None unless profiling
+static PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+
+/// What a piece of time was spent on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    Git,
+    Network,
+    Ai,
+    Render,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Git, Phase::Network, Phase::Ai, Phase::Render];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Git => "git",
+            Phase::Network => "network",
+            Phase::Ai => "ai",
+            Phase::Render => "rendering",
+        }
+    }
+}
+
+/// One timed piece of work, offsets from the start of the command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub phase: Phase,
+    pub name: String,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+struct Profile {
+    started: Instant,
+    started_at: DateTime<Utc>,
+    timings: Vec<Timing>,
+    git_trace: PathBuf,
+}
+
+/// Marks a phase from when it's created until it's dropped
+#[must_use]
+pub struct Span {
+    phase: Phase,
+    name: String,
+    started: Option<Instant>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(started) = self.started else {
+            return;
+        };
+        if let Some(profile) = PROFILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            profile.timings.push(Timing {
+                phase: self.phase,
+                name: std::mem::take(&mut self.name),
+                start: started.saturating_duration_since(profile.started),
+                duration: started.elapsed(),
+            });
+        }
+    }
+}
+
+/// span times `phase` until the returned guard is dropped, doing nothing unless profiling
+pub fn span(phase: Phase, name: impl Into<String>) -> Span {
+    let started = is_enabled().then(Instant::now);
+    Span { phase, name: if started.is_some() { name.into() } else { String::new() }, started }
+}
+
+/// time times `phase` while awaiting `future`
+pub async fn time<F: Future>(phase: Phase, name: impl Into<String>, future: F) -> F::Output {
+    let _span = span(phase, name);
+    future.await
+}
+
+/// is_enabled returns if the command is being profiled
+pub fn is_enabled() -> bool {
+    PROFILE.lock().map(|profile| profile.is_some()).unwrap_or(false)
+}
+
+/// start begins profiling the command
+pub fn start() {
+    let git_trace = std::env::temp_dir().join(format!("sage-profile-{}.trace2.json", std::process::id()));
+    let _ = fs::remove_file(&git_trace);
+    *PROFILE.lock().unwrap_or_else(|e| e.into_inner()) =
+        Some(Profile { started: Instant::now(), started_at: Utc::now(), timings: Vec::new(), git_trace });
+}
+
+/// git_trace returns where git should write its trace2 events while profiling, for
+/// `GIT_TRACE2_EVENT`
+pub fn git_trace() -> Option<PathBuf> {
+    PROFILE.lock().ok()?.as_ref().map(|profile| profile.git_trace.clone())
+}
+
+/// finish stops profiling, returning how long the command took and everything timed in it
+pub fn finish() -> Option<(Duration, Vec<Timing>)> {
+    let profile = PROFILE.lock().unwrap_or_else(|e| e.into_inner()).take()?;
+    let total = profile.started.elapsed();
+
+    let mut timings = profile.timings;
+    if let Ok(events) = fs::read_to_string(&profile.git_trace) {
+        timings.extend(parse_git_trace(&events, profile.started_at));
+    }
+    let _ = fs::remove_file(&profile.git_trace);
+    timings.sort_by_key(|timing| timing.start);
+    Some((total, timings))
+}
+
+/// Read the git processes out of a trace2 event log: each has a start event with its arguments
+/// and an exit event with how long it ran. Child processes git runs itself are left out, they're
+/// part of their parent's time.
+pub(crate) fn parse_git_trace(events: &str, started_at: DateTime<Utc>) -> Vec<Timing> {
+    let mut starts = HashMap::new();
+    let mut timings = Vec::new();
+    for event in events.lines().filter_map(|line| serde_json::from_str::<Value>(line).ok()) {
+        let Some(sid) = event["sid"].as_str().filter(|sid| !sid.contains('/')) else {
+            continue;
+        };
+        match event["event"].as_str() {
+            Some("start") => {
+                let argv = event["argv"].as_array().map(|argv| argv.iter().filter_map(Value::as_str).collect::<Vec<_>>());
+                let Some(time) = event["time"].as_str().and_then(|time| time.parse::<DateTime<Utc>>().ok()) else {
+                    continue;
+                };
+                // git writes its own path as the first argument
+                let name = argv.map(|argv| argv.iter().skip(1).fold("git".to_string(), |name, arg| name + " " + arg));
+                starts.insert(sid.to_string(), (time, name.unwrap_or_else(|| "git".to_string())));
+            }
+            Some("exit") => {
+                let (Some((time, name)), Some(seconds)) = (starts.remove(sid), event["t_abs"].as_f64()) else {
+                    continue;
+                };
+                timings.push(Timing {
+                    phase: Phase::Git,
+                    name,
+                    start: (time - started_at).to_std().unwrap_or_default(),
+                    duration: Duration::from_secs_f64(seconds.max(0.0)),
+                });
+            }
+            _ => {}
+        }
+    }
+    timings
+}
+
+/// Total time and number of timings in each phase. Phases can overlap, e.g. requests made in
+/// parallel, so the totals can add up to more than the command took.
+pub fn breakdown(timings: &[Timing]) -> Vec<(Phase, Duration, usize)> {
+    Phase::ALL
+        .into_iter()
+        .map(|phase| {
+            let timings = timings.iter().filter(|timing| timing.phase == phase);
+            let (total, count) = timings.fold((Duration::ZERO, 0), |(total, count), timing| (total + timing.duration, count + 1));
+            (phase, total, count)
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    cat: &'a str,
+    ph: &'a str,
+    ts: u128,
+    dur: u128,
+    pid: u32,
+    tid: usize,
+}
+
+/// chrome_trace turns a run into the Trace Event Format chrome://tracing reads, with a row per
+/// phase under the command itself
+pub fn chrome_trace(command: &str, total: Duration, timings: &[Timing]) -> Result<String> {
+    let pid = std::process::id();
+    let mut events = vec![TraceEvent { name: command, cat: "sage", ph: "X", ts: 0, dur: total.as_micros(), pid, tid: 0 }];
+    for timing in timings {
+        let tid = Phase::ALL.iter().position(|phase| *phase == timing.phase).unwrap_or_default() + 1;
+        events.push(TraceEvent {
+            name: &timing.name,
+            cat: timing.phase.name(),
+            ph: "X",
+            ts: timing.start.as_micros(),
+            dur: timing.duration.as_micros(),
+            pid,
+            tid,
+        });
+    }
+    Ok(serde_json::to_string_pretty(&serde_json::json!({ "traceEvents": events, "displayTimeUnit": "ms" }))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_trace() {
+        let started_at = "2026-10-16T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let events = r#"{"event":"version","sid":"a","time":"2026-10-16T10:00:00.100000Z","evt":"3"}
+{"event":"start","sid":"a","time":"2026-10-16T10:00:00.100000Z","t_abs":0.001,"argv":["/usr/bin/git","status","--porcelain"]}
+{"event":"start","sid":"a/b","time":"2026-10-16T10:00:00.110000Z","t_abs":0.001,"argv":["git","gc"]}
+{"event":"exit","sid":"a/b","time":"2026-10-16T10:00:00.120000Z","t_abs":0.010,"code":0}
+{"event":"exit","sid":"a","time":"2026-10-16T10:00:00.150000Z","t_abs":0.050,"code":0}
+{"event":"start","sid":"c","time":"2026-10-16T10:00:00.200000Z","t_abs":0.001,"argv":["git","fetch"]}
+not json"#;
+
+        assert_eq!(
+            parse_git_trace(events, started_at),
+            vec![Timing {
+                phase: Phase::Git,
+                name: "git status --porcelain".to_string(),
+                start: Duration::from_millis(100),
+                duration: Duration::from_millis(50),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_breakdown_and_trace() {
+        let timing = |phase, name: &str, start, duration| Timing {
+            phase,
+            name: name.to_string(),
+            start: Duration::from_millis(start),
+            duration: Duration::from_millis(duration),
+        };
+        let timings =
+            vec![timing(Phase::Git, "git status", 0, 20), timing(Phase::Network, "pulls", 10, 100), timing(Phase::Git, "git log", 30, 5)];
+
+        let breakdown = breakdown(&timings);
+        assert_eq!(breakdown[0], (Phase::Git, Duration::from_millis(25), 2));
+        assert_eq!(breakdown[1], (Phase::Network, Duration::from_millis(100), 1));
+        assert_eq!(breakdown[2], (Phase::Ai, Duration::ZERO, 0));
+
+        let trace: Value = serde_json::from_str(&chrome_trace("status", Duration::from_millis(150), &timings).unwrap()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["name"], "status");
+        assert_eq!(events[2]["cat"], "network");
+        assert_eq!(events[2]["ts"], 10_000);
+        assert_eq!(events[2]["tid"], 2);
+    }
+}
@@ -1,7 +1,5 @@
 use anyhow::Result;
-use inquire::Select;
-
-use crate::git;
+use crate::{git, tui::select::Select, ui::accessible};
 
 /// Displays an interactive branch selector and returns the selected branch name
 pub fn select_branch() -> Result<String> {
@@ -12,16 +10,20 @@ pub fn select_branch() -> Result<String> {
     let branch_displays: Vec<String> = branches
         .iter()
         .map(|b| {
-            let current_marker = if b.is_current { "* " } else { "  " };
+            let current_marker = match (b.is_current, accessible::enabled()) {
+                (true, true) => "(current) ",
+                (true, false) => "* ",
+                (false, true) => "",
+                (false, false) => "  ",
+            };
             let tracking_info = match &b.upstream {
                 Some(upstream) => {
                     let ahead_behind = match (b.ahead_count, b.behind_count) {
                         (0, 0) => String::new(),
-                        (ahead, 0) => format!(" ↑{}", ahead),
-                        (0, behind) => format!(" ↓{}", behind),
-                        (ahead, behind) => format!(" ↑{}↓{}", ahead, behind),
+                        (ahead, behind) => format!(" {}", accessible::ahead_behind(ahead, behind)),
                     };
-                    format!(" → {}{}", upstream, ahead_behind)
+                    let arrow = if accessible::enabled() { "tracking" } else { "→" };
+                    format!(" {} {}{}", arrow, upstream, ahead_behind)
                 }
                 None => String::new(),
             };
@@ -29,24 +31,13 @@ pub fn select_branch() -> Result<String> {
         })
         .collect();
 
-    // Create a mapping of display strings to branch names
-    let branch_map: Vec<(String, String)> = branches
-        .iter()
-        .zip(branch_displays.iter())
-        .map(|(branch, display)| (display.clone(), branch.name.clone()))
-        .collect();
+    // Screen readers can't follow the interactive selector, so number the branches instead
+    if accessible::enabled() {
+        let index = accessible::select("Select a branch to switch to:", &branch_displays, None)?;
+        return Ok(branches[index].name.clone());
+    }
 
     // Show the selector
-    let selection = Select::new("Select a branch to switch to:", branch_displays)
-        .with_help_message("↑↓ to move, enter to select, esc to cancel")
-        .prompt()?;
-
-    // Find the corresponding branch name for the selected display string
-    let selected_branch = branch_map
-        .into_iter()
-        .find(|(display, _)| display == &selection)
-        .map(|(_, name)| name)
-        .ok_or_else(|| anyhow::anyhow!("Failed to map selection to branch name"))?;
-
-    Ok(selected_branch)
-} 
\ No newline at end of file
+    let index = Select::new("Select a branch to switch to:", &branch_displays).prompt()?;
+    Ok(branches[index].name.clone())
+}
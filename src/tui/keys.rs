@@ -0,0 +1,272 @@
+//! Configurable keybindings for sage's interactive screens
+//!
+//! Each action has default keys, which `keys.<action>` in the config replaces with a
+//! comma-separated list, e.g. `sage config set keys.quit "x,esc"`. Keys are written as a single
+//! character (`q`, `+`, `ö`), a name (`enter`, `esc`, `up`, `pagedown`, `f1`, `space`), with
+//! optional `ctrl-` or `alt-` in front.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+
+use crate::config;
+
+/// Named keys, as they are written in the config
+const NAMED_KEYS: &[&str] = &[
+    "enter", "esc", "tab", "backtab", "backspace", "delete", "insert", "space", "up", "down", "left", "right",
+    "home", "end", "pageup", "pagedown",
+];
+
+/// Something a key can do in an interactive screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Up,
+    Down,
+    Select,
+    Expand,
+    Collapse,
+    Refresh,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::Up,
+        Action::Down,
+        Action::Select,
+        Action::Expand,
+        Action::Collapse,
+        Action::Refresh,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// name is how the action is written in `keys.<action>`
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Select => "select",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::Refresh => "refresh",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Up => "Move to the previous item",
+            Action::Down => "Move to the next item",
+            Action::Select => "Choose the highlighted item",
+            Action::Expand => "Show more detail",
+            Action::Collapse => "Show less detail",
+            Action::Refresh => "Reload",
+            Action::Help => "Show the keybindings",
+            Action::Quit => "Leave the screen",
+        }
+    }
+
+    pub fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["up", "ctrl-p"],
+            Action::Down => &["down", "ctrl-n"],
+            Action::Select => &["enter"],
+            Action::Expand => &["+"],
+            Action::Collapse => &["-"],
+            Action::Refresh => &["r"],
+            Action::Help => &["?"],
+            Action::Quit => &["q", "esc"],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// The keys bound to each action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<String>>,
+    /// Actions whose keys come from the config rather than the defaults
+    configured: Vec<Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| (action, action.defaults().iter().map(|key| key.to_string()).collect()))
+            .collect();
+        Keymap { bindings, configured: Vec::new() }
+    }
+}
+
+impl Keymap {
+    /// load reads `keys.*` from the config on top of the defaults
+    pub fn load() -> Result<Keymap> {
+        let values = config::load()?;
+        Keymap::from_config(values.iter().filter_map(|(key, value)| Some((key.strip_prefix("keys.")?, value.as_str()))))
+    }
+
+    /// from_config applies `(action, keys)` settings on top of the defaults
+    pub fn from_config<'a>(settings: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Keymap> {
+        let mut keymap = Keymap::default();
+        for (name, value) in settings {
+            let action = Action::from_name(name).ok_or_else(|| {
+                let names = Action::ALL.map(Action::name).join(", ");
+                anyhow!("Unknown key action keys.{}, expected one of {}", name, names)
+            })?;
+            let keys = value
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(parse_key)
+                .collect::<Result<Vec<_>>>()?;
+            if keys.is_empty() {
+                return Err(anyhow!("keys.{} needs at least one key", name));
+            }
+            keymap.bindings.insert(action, keys);
+            keymap.configured.push(action);
+        }
+        Ok(keymap)
+    }
+
+    /// keys returns the keys bound to an action
+    pub fn keys(&self, action: Action) -> &[String] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// is_configured returns if an action's keys were set in the config
+    pub fn is_configured(&self, action: Action) -> bool {
+        self.configured.contains(&action)
+    }
+
+    /// action returns what a key press does, if anything
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = key_name(event)?;
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// conflicts returns keys bound to more than one action, with the actions they're bound to
+    pub fn conflicts(&self) -> Vec<(String, Vec<Action>)> {
+        let mut actions_by_key: BTreeMap<&str, Vec<Action>> = BTreeMap::new();
+        for (action, keys) in &self.bindings {
+            for key in keys {
+                actions_by_key.entry(key).or_default().push(*action);
+            }
+        }
+        actions_by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(key, actions)| (key.to_string(), actions))
+            .collect()
+    }
+}
+
+/// parse_key checks a key as written in the config and returns it in its canonical form
+pub fn parse_key(spec: &str) -> Result<String> {
+    let mut rest = spec;
+    let (mut ctrl, mut alt) = (false, false);
+    // A lone "-" is the minus key, not an empty modifier
+    while rest.len() > 1 {
+        let lower = rest.to_lowercase();
+        if lower.starts_with("ctrl-") {
+            ctrl = true;
+        } else if lower.starts_with("alt-") {
+            alt = true;
+        } else {
+            break;
+        }
+        rest = &rest[rest.find('-').unwrap_or_default() + 1..];
+    }
+
+    let mut chars = rest.chars();
+    let key = match (chars.next(), chars.next()) {
+        // Terminals report ctrl with a letter the same whether shift is held or not
+        (Some(c), None) if ctrl => c.to_ascii_lowercase().to_string(),
+        (Some(c), None) => c.to_string(),
+        _ => {
+            let name = rest.to_lowercase();
+            let function_key = name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()).is_some_and(|n| (1..=12).contains(&n));
+            if !NAMED_KEYS.contains(&name.as_str()) && !function_key {
+                return Err(anyhow!("Unknown key '{}'", spec));
+            }
+            name
+        }
+    };
+
+    Ok(format!("{}{}{}", if ctrl { "ctrl-" } else { "" }, if alt { "alt-" } else { "" }, key))
+}
+
+/// key_name writes a key press the way it's written in the config
+pub fn key_name(event: &KeyEvent) -> Option<String> {
+    let key = match event.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::CONTROL) => c.to_ascii_lowercase().to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        _ => return None,
+    };
+
+    // Shift is already part of the character typed, and of backtab
+    let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = event.modifiers.contains(KeyModifiers::ALT);
+    Some(format!("{}{}{}", if ctrl { "ctrl-" } else { "" }, if alt { "alt-" } else { "" }, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("q").unwrap(), "q");
+        assert_eq!(parse_key("-").unwrap(), "-");
+        assert_eq!(parse_key("Ctrl-C").unwrap(), "ctrl-c");
+        assert_eq!(parse_key("ctrl--").unwrap(), "ctrl--");
+        assert_eq!(parse_key("alt-ctrl-x").unwrap(), "ctrl-alt-x");
+        assert_eq!(parse_key("PageDown").unwrap(), "pagedown");
+        assert_eq!(parse_key("f5").unwrap(), "f5");
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("ctrl-escape").is_err());
+    }
+
+    #[test]
+    fn test_config_overrides_and_conflicts() {
+        let keymap = Keymap::from_config([("quit", "x, esc"), ("expand", "ü")]).unwrap();
+        let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(keymap.action(&press(KeyCode::Char('x'))), Some(Action::Quit));
+        assert_eq!(keymap.action(&press(KeyCode::Char('q'))), None);
+        assert_eq!(keymap.action(&press(KeyCode::Char('ü'))), Some(Action::Expand));
+        assert!(keymap.is_configured(Action::Quit) && !keymap.is_configured(Action::Up));
+        assert!(keymap.conflicts().is_empty());
+
+        let keymap = Keymap::from_config([("refresh", "x"), ("quit", "x")]).unwrap();
+        assert_eq!(keymap.conflicts(), vec![("x".to_string(), vec![Action::Refresh, Action::Quit])]);
+
+        assert!(Keymap::from_config([("jump", "j")]).is_err());
+        assert!(Keymap::from_config([("quit", " , ")]).is_err());
+    }
+}
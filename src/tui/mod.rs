@@ -1,5 +1,8 @@
 pub mod branch;
+pub mod keys;
 pub mod pull;
+pub mod select;
+pub mod workflow;
 
 pub use branch::*;
 
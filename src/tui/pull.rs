@@ -1,5 +1,7 @@
 use anyhow::Result;
 
+use crate::ui::accessible;
+
 pub struct PullRequestDetails {
     pub title: String,
     pub body: String,
@@ -8,7 +10,12 @@ pub struct PullRequestDetails {
 
 pub fn create_pull_request() -> Result<PullRequestDetails> {
     let title = inquire::Text::new("Title: ").prompt()?;
-    let body = inquire::Editor::new("Body: ").prompt()?;
+    // The editor prompt redraws the screen, so ask for the body on one line in accessible mode
+    let body = if accessible::enabled() {
+        inquire::Text::new("Body: ").prompt()?
+    } else {
+        inquire::Editor::new("Body: ").prompt()?
+    };
     let draft = inquire::Confirm::new("Draft: ").prompt()?;
 
     Ok(PullRequestDetails { title, body, draft })
@@ -0,0 +1,210 @@
+//! A list to pick one item from, driven by the keymap
+//!
+//! inquire's prompts only know their own keys, so the screens that ask for one of a list use this
+//! instead, and whatever `keys.up`, `keys.down`, `keys.select`, `keys.help` and `keys.quit` are set
+//! to is what moves, picks and leaves. It draws on stderr, so it works with stdout piped.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    terminal::{self, ClearType},
+};
+use std::io::{self, Write};
+
+use super::keys::{Action, Keymap};
+use crate::ui::ColorizeExt;
+
+/// How many options show at once by default
+const PAGE_SIZE: usize = 7;
+
+/// What a key press did to the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Moved,
+    Chosen(usize),
+    Cancelled,
+    Ignored,
+}
+
+/// Where the highlight is in a list of `len` options, and which of them are on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    index: usize,
+    first: usize,
+    len: usize,
+    page: usize,
+    help: bool,
+}
+
+impl Cursor {
+    fn new(len: usize, page: usize) -> Cursor {
+        Cursor { index: 0, first: 0, len, page: page.max(1), help: false }
+    }
+
+    fn apply(&mut self, action: Action) -> Outcome {
+        match action {
+            // Moving past either end wraps around to the other
+            Action::Up => self.index = (self.index + self.len - 1) % self.len,
+            Action::Down => self.index = (self.index + 1) % self.len,
+            Action::Select => return Outcome::Chosen(self.index),
+            Action::Quit => return Outcome::Cancelled,
+            Action::Help => self.help = !self.help,
+            Action::Expand | Action::Collapse | Action::Refresh => return Outcome::Ignored,
+        }
+
+        // Scroll just far enough to keep the highlight on screen
+        if self.index < self.first {
+            self.first = self.index;
+        } else if self.index >= self.first + self.page {
+            self.first = self.index + 1 - self.page;
+        }
+        Outcome::Moved
+    }
+}
+
+/// A list prompt, shown with [`Select::prompt`]
+pub struct Select<'a> {
+    message: &'a str,
+    options: &'a [String],
+    page: usize,
+}
+
+impl<'a> Select<'a> {
+    pub fn new(message: &'a str, options: &'a [String]) -> Select<'a> {
+        Select { message, options, page: PAGE_SIZE }
+    }
+
+    /// with_page_size sets how many options show at once
+    pub fn with_page_size(mut self, page: usize) -> Select<'a> {
+        self.page = page;
+        self
+    }
+
+    /// prompt shows the list and returns the index of the option picked
+    pub fn prompt(self) -> Result<usize> {
+        if self.options.is_empty() {
+            return Err(anyhow!("Nothing to choose from"));
+        }
+        let keymap = Keymap::load()?;
+        let mut cursor = Cursor::new(self.options.len(), self.page);
+        let mut out = io::stderr();
+
+        terminal::enable_raw_mode()?;
+        // Long options are cut at the edge rather than wrapped, so every option is one line
+        let _ = queue!(out, terminal::DisableLineWrap, cursor::Hide);
+        let picked = self.run(&mut out, &keymap, &mut cursor);
+        let _ = queue!(out, terminal::EnableLineWrap, cursor::Show);
+        let _ = out.flush();
+        terminal::disable_raw_mode()?;
+        picked
+    }
+
+    fn run(&self, out: &mut impl Write, keymap: &Keymap, cursor: &mut Cursor) -> Result<usize> {
+        let mut drawn = 0;
+        loop {
+            drawn = self.draw(out, keymap, cursor, drawn)?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            // Ctrl-C always gets out, whatever it's bound to
+            let outcome = if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                Outcome::Cancelled
+            } else {
+                keymap.action(&key).map_or(Outcome::Ignored, |action| cursor.apply(action))
+            };
+
+            match outcome {
+                Outcome::Chosen(index) => {
+                    clear(out, drawn)?;
+                    write!(out, "{} {}\r\n", self.message.bold(), self.options[index].sage())?;
+                    out.flush()?;
+                    return Ok(index);
+                }
+                Outcome::Cancelled => {
+                    clear(out, drawn)?;
+                    return Err(anyhow!("Cancelled"));
+                }
+                Outcome::Moved | Outcome::Ignored => {}
+            }
+        }
+    }
+
+    /// draw replaces the `drawn` lines from last time with the list as it is now, returning how
+    /// many lines it drew
+    fn draw(&self, out: &mut impl Write, keymap: &Keymap, cursor: &Cursor, drawn: usize) -> Result<usize> {
+        clear(out, drawn)?;
+
+        let mut lines = vec![self.message.bold().to_string()];
+        for (index, option) in self.options.iter().enumerate().skip(cursor.first).take(cursor.page) {
+            lines.push(match index == cursor.index {
+                true => format!("{} {}", ">".sage(), option),
+                false => format!("  {}", option),
+            });
+        }
+        lines.push(help(keymap, cursor.help).gray().to_string());
+
+        for line in &lines {
+            write!(out, "{}\r\n", line)?;
+        }
+        out.flush()?;
+        Ok(lines.len())
+    }
+}
+
+/// The line under the list: the keys to move, pick and leave, or every binding with `all`
+fn help(keymap: &Keymap, all: bool) -> String {
+    let keys = |action: Action| keymap.keys(action).join("/");
+    if all {
+        return [Action::Up, Action::Down, Action::Select, Action::Quit, Action::Help]
+            .map(|action| format!("{} {}", keys(action), action.name()))
+            .join(", ");
+    }
+    format!(
+        "{} {} to move, {} to select, {} to cancel, {} for help",
+        keys(Action::Up),
+        keys(Action::Down),
+        keys(Action::Select),
+        keys(Action::Quit),
+        keys(Action::Help)
+    )
+}
+
+/// clear moves back up over `lines` lines and erases them
+fn clear(out: &mut impl Write, lines: usize) -> Result<()> {
+    if lines > 0 {
+        queue!(out, cursor::MoveUp(lines as u16))?;
+    }
+    queue!(out, cursor::MoveToColumn(0), terminal::Clear(ClearType::FromCursorDown))?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let mut cursor = Cursor::new(4, 2);
+        assert_eq!(cursor.apply(Action::Up), Outcome::Moved);
+        assert_eq!((cursor.index, cursor.first), (3, 2));
+        cursor.apply(Action::Down);
+        assert_eq!((cursor.index, cursor.first), (0, 0));
+        cursor.apply(Action::Down);
+        cursor.apply(Action::Down);
+        assert_eq!((cursor.index, cursor.first), (2, 1));
+        assert_eq!(cursor.apply(Action::Refresh), Outcome::Ignored);
+        assert_eq!(cursor.apply(Action::Select), Outcome::Chosen(2));
+        assert_eq!(cursor.apply(Action::Quit), Outcome::Cancelled);
+
+        let keymap = Keymap::from_config([("up", "k"), ("down", "j")]).unwrap();
+        assert_eq!(help(&keymap, false), "k j to move, enter to select, q/esc to cancel, ? for help");
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use inquire::{Confirm, Select, Text};
+use std::collections::BTreeMap;
+
+use crate::{gh::actions::WorkflowInput, ui::accessible};
+
+/// Prompts for every workflow input that wasn't already provided
+pub fn prompt_inputs(inputs: &[WorkflowInput], provided: &BTreeMap<String, String>) -> Result<BTreeMap<String, String>> {
+    let mut values = provided.clone();
+
+    for input in inputs {
+        if values.contains_key(&input.name) {
+            continue;
+        }
+
+        let label = match &input.description {
+            Some(description) => format!("{} ({}):", input.name, description),
+            None => format!("{}:", input.name),
+        };
+
+        let value = match input.kind.as_str() {
+            "boolean" => {
+                let default = input.default.as_deref() == Some("true");
+                Confirm::new(&label).with_default(default).prompt()?.to_string()
+            }
+            "choice" if !input.options.is_empty() => {
+                let start = input
+                    .default
+                    .as_ref()
+                    .and_then(|default| input.options.iter().position(|option| option == default))
+                    .unwrap_or(0);
+                if accessible::enabled() {
+                    input.options[accessible::select(&label, &input.options, Some(start))?].clone()
+                } else {
+                    Select::new(&label, input.options.clone()).with_starting_cursor(start).prompt()?
+                }
+            }
+            _ => {
+                let mut prompt = Text::new(&label);
+                if let Some(default) = &input.default {
+                    prompt = prompt.with_default(default);
+                }
+                prompt.prompt()?
+            }
+        };
+
+        // Leave optional inputs out entirely so the workflow's own default applies
+        if value.is_empty() && !input.required {
+            continue;
+        }
+        values.insert(input.name.clone(), value);
+    }
+
+    Ok(values)
+}
@@ -0,0 +1,148 @@
+//! Screen-reader-friendly output
+//!
+//! With `ui.accessible` set (or `SAGE_ACCESSIBLE=1`), sage prints plain linear text: no colors,
+//! words instead of glyphs like ✓ and ●, and numbered prompts instead of interactive menus that
+//! redraw the screen.
+
+use anyhow::{anyhow, Result};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::sync::OnceLock;
+
+use crate::config;
+
+/// enabled returns if accessible output is turned on
+pub fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| match env::var("SAGE_ACCESSIBLE") {
+        Ok(value) => config::parse_bool(&value).unwrap_or(true),
+        Err(_) => config::get_bool("ui.accessible", false),
+    })
+}
+
+/// apply turns colors and styled prompts off for the rest of the process when accessible output
+/// is enabled
+pub fn apply() {
+    if enabled() {
+        colored::control::set_override(false);
+        inquire::set_global_render_config(inquire::ui::RenderConfig::empty());
+    }
+}
+
+/// A status glyph and the words read out in its place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// Starts an item in a list
+    Bullet,
+    Passing,
+    Failing,
+    Pending,
+    Skipped,
+    Cancelled,
+    Pushed,
+}
+
+impl Mark {
+    /// The glyph in the default theme
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Mark::Bullet => "●",
+            Mark::Passing => "✓",
+            Mark::Failing => "✗",
+            Mark::Pending => "●",
+            Mark::Skipped => "-",
+            Mark::Cancelled => "○",
+            Mark::Pushed => "↑",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Mark::Bullet => "-",
+            Mark::Passing => "passing",
+            Mark::Failing => "failing",
+            Mark::Pending => "pending",
+            Mark::Skipped => "skipped",
+            Mark::Cancelled => "cancelled",
+            Mark::Pushed => "pushed",
+        }
+    }
+}
+
+/// mark returns the theme's glyph for a status, or its label in accessible mode
+pub fn mark(mark: Mark) -> &'static str {
+    if enabled() { mark.label() } else { super::theme::current().glyph(mark) }
+}
+
+/// ahead_behind describes how far a branch is from its upstream, e.g. `↑2, ↓1` or `2 ahead, 1 behind`
+pub fn ahead_behind(ahead: usize, behind: usize) -> String {
+    describe_ahead_behind(ahead, behind, enabled())
+}
+
+fn describe_ahead_behind(ahead: usize, behind: usize, accessible: bool) -> String {
+    let mut parts = Vec::new();
+    if ahead > 0 {
+        parts.push(if accessible { format!("{} ahead", ahead) } else { format!("↑{}", ahead) });
+    }
+    if behind > 0 {
+        parts.push(if accessible { format!("{} behind", behind) } else { format!("↓{}", behind) });
+    }
+    parts.join(", ")
+}
+
+/// select asks the user to pick one of `options` from a numbered list, returning its index.
+/// It reads a plain line from stdin, so works where inquire's menus can't be followed.
+pub fn select(prompt: &str, options: &[String], default: Option<usize>) -> Result<usize> {
+    if options.is_empty() {
+        return Err(anyhow!("Nothing to choose from"));
+    }
+
+    println!("{}", prompt);
+    for (index, option) in options.iter().enumerate() {
+        println!("  {}. {}", index + 1, option);
+    }
+
+    let stdin = io::stdin();
+    loop {
+        match default {
+            Some(default) => print!("Enter a number from 1 to {} (default {}): ", options.len(), default + 1),
+            None => print!("Enter a number from 1 to {}: ", options.len()),
+        }
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Err(anyhow!("No selection made"));
+        }
+        match parse_choice(&line, options.len(), default) {
+            Some(index) => return Ok(index),
+            None => println!("{} is not one of the choices", line.trim()),
+        }
+    }
+}
+
+/// Turn a typed answer into an index, using the default for an empty answer
+fn parse_choice(answer: &str, count: usize, default: Option<usize>) -> Option<usize> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return default;
+    }
+    answer.parse::<usize>().ok().filter(|number| (1..=count).contains(number)).map(|number| number - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ahead_behind_and_choices() {
+        assert_eq!(describe_ahead_behind(2, 1, false), "↑2, ↓1");
+        assert_eq!(describe_ahead_behind(2, 1, true), "2 ahead, 1 behind");
+        assert_eq!(describe_ahead_behind(0, 3, true), "3 behind");
+
+        assert_eq!(parse_choice("2\n", 3, None), Some(1));
+        assert_eq!(parse_choice("\n", 3, Some(0)), Some(0));
+        assert_eq!(parse_choice("4", 3, None), None);
+        assert_eq!(parse_choice("0", 3, None), None);
+    }
+}
@@ -0,0 +1,113 @@
+//! GitHub Actions output, so what sage finds in CI shows up on the pull request
+//!
+//! Inside a workflow run (`GITHUB_ACTIONS=true`), checks report problems as `::error` and
+//! `::warning` workflow commands, which GitHub turns into annotations on the pull request's files,
+//! and add Markdown to the job summary at `$GITHUB_STEP_SUMMARY`. Outside Actions nothing extra
+//! is printed. Workflow commands go to stderr, which the runner reads too, so they never end up
+//! in a JSON report on stdout.
+
+use anyhow::Result;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// How serious an annotation is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Level {
+    #[default]
+    Error,
+    Warning,
+    Notice,
+}
+
+impl Level {
+    fn command(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Notice => "notice",
+        }
+    }
+}
+
+/// A problem to show on the pull request, on a file or line when it has one
+#[derive(Debug, Clone, Default)]
+pub struct Annotation<'a> {
+    pub level: Level,
+    pub title: &'a str,
+    pub message: &'a str,
+    pub file: Option<&'a str>,
+    pub line: Option<usize>,
+}
+
+impl Annotation<'_> {
+    /// emit prints the annotation to stderr when running in GitHub Actions
+    pub fn emit(&self) {
+        if enabled() {
+            eprintln!("{}", self.command());
+        }
+    }
+
+    /// The workflow command, e.g. `::error file=src/main.rs,line=3,title=Secret::A token`
+    fn command(&self) -> String {
+        let mut properties = Vec::new();
+        if let Some(file) = self.file {
+            properties.push(format!("file={}", escape_property(file)));
+        }
+        if let Some(line) = self.line {
+            properties.push(format!("line={}", line));
+        }
+        if !self.title.is_empty() {
+            properties.push(format!("title={}", escape_property(self.title)));
+        }
+
+        let properties = if properties.is_empty() { String::new() } else { format!(" {}", properties.join(",")) };
+        format!("::{}{}::{}", self.level.command(), properties, escape_data(self.message))
+    }
+}
+
+/// enabled says whether sage is running in a GitHub Actions workflow
+pub fn enabled() -> bool {
+    env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+/// summary adds Markdown to the job summary, when running in GitHub Actions
+pub fn summary(markdown: &str) -> Result<()> {
+    let Some(path) = env::var_os("GITHUB_STEP_SUMMARY").filter(|_| enabled()) else {
+        return Ok(());
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", markdown.trim_end())?;
+    Ok(())
+}
+
+/// Escape a message, which runs to the end of the line
+fn escape_data(text: &str) -> String {
+    text.trim_end().replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a property value, which also ends at `,` and `:`
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let annotation = Annotation {
+            level: Level::Warning,
+            title: "Generated file",
+            message: "is generated (dist/**)\nrun the build instead: 100%",
+            file: Some("dist/app,v2.js"),
+            line: Some(3),
+        };
+        assert_eq!(
+            annotation.command(),
+            "::warning file=dist/app%2Cv2.js,line=3,title=Generated file::is generated (dist/**)%0Arun the build instead: 100%25"
+        );
+        assert_eq!(Annotation { message: "failed", ..Annotation::default() }.command(), "::error::failed");
+    }
+}
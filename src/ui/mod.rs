@@ -1,3 +1,11 @@
+pub mod accessible;
+pub mod actions;
+pub mod pager;
+pub mod progress;
+pub mod template;
+pub mod text;
+pub mod theme;
+
 use anyhow::{anyhow, Result};
 use colored::ColoredString;
 use colored::Colorize;
@@ -73,3 +81,42 @@ impl ColorizeExt for str {
         <str as ColorizeExt>::blue(self).underline()
     }
 }
+
+/// Opens a URL in the user's default browser
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+
+    let status = cmd.arg(url).status()?;
+    if !status.success() {
+        return Err(anyhow!("Failed to open {} in a browser", url));
+    }
+
+    Ok(())
+}
+
+/// Shows a desktop notification, returning whether one could be shown
+pub fn notify(title: &str, body: &str) -> bool {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = std::process::Command::new("osascript");
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        );
+        cmd.args(["-e", &script]);
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("notify-send");
+        cmd.args([title, body]);
+        cmd
+    };
+
+    cmd.output().map(|output| output.status.success()).unwrap_or(false)
+}
@@ -0,0 +1,129 @@
+//! Paging long output
+//!
+//! Commands with long output, like history, diff and CI logs, collect it in a [`Pager`] and
+//! show it through the user's pager when it goes to a terminal and doesn't fit on the screen.
+//! The pager is picked the way git picks it: `GIT_PAGER`, `core.pager`, `PAGER`, then `less`.
+//! `--no-pager`, or a pager of `cat` or nothing at all, prints straight to the terminal.
+
+use anyhow::Result;
+use std::env;
+use std::fmt;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::git;
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// disable turns paging off for the rest of the run, for `--no-pager`
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Output collected to be shown in one go, through the pager when it's long. Write to it with
+/// `writeln!`, then call [`Pager::finish`].
+#[derive(Debug, Default)]
+pub struct Pager {
+    text: String,
+}
+
+impl Pager {
+    pub fn new() -> Pager {
+        Pager::default()
+    }
+
+    /// finish shows everything written
+    pub fn finish(self) -> Result<()> {
+        page(&self.text)
+    }
+}
+
+impl fmt::Write for Pager {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.text.push_str(text);
+        Ok(())
+    }
+}
+
+/// page shows text through the pager, or prints it when it fits or paging is off
+pub fn page(text: &str) -> Result<()> {
+    let Some(pager) = pager().filter(|_| wanted(text)) else {
+        print!("{}", text);
+        return Ok(());
+    };
+
+    let mut command = if cfg!(windows) {
+        let mut parts = pager.split_whitespace();
+        let mut command = Command::new(parts.next().unwrap_or_default());
+        command.args(parts);
+        command
+    } else {
+        // Like git, run it through the shell so settings like `less -R +G` work
+        let mut command = Command::new("sh");
+        command.args(["-c", &pager]);
+        command
+    };
+    // git's defaults: quit when it fits, keep colors, don't clear the screen
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    if env::var_os("LV").is_none() {
+        command.env("LV", "-c");
+    }
+
+    match command.stdin(Stdio::piped()).spawn() {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The user quitting the pager early closes the pipe, which is fine
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+        }
+        Err(_) => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// Paging is for a person at a terminal, and only when the output won't fit on the screen
+fn wanted(text: &str) -> bool {
+    if DISABLED.load(Ordering::Relaxed) || !std::io::stdout().is_terminal() {
+        return false;
+    }
+    match crossterm::terminal::size() {
+        Ok((_, rows)) if rows > 0 => text.lines().count() >= rows as usize,
+        _ => true,
+    }
+}
+
+/// The pager command from the environment and git config
+fn pager() -> Option<String> {
+    choose(
+        env::var("GIT_PAGER").ok(),
+        git::repo::get_config("core.pager").ok().flatten(),
+        env::var("PAGER").ok(),
+    )
+}
+
+/// The first pager set, or less. None when it's turned off with an empty value or cat.
+fn choose(git_pager: Option<String>, core_pager: Option<String>, pager: Option<String>) -> Option<String> {
+    let pager = git_pager.or(core_pager).or(pager).unwrap_or_else(|| "less".to_string());
+    let pager = pager.trim();
+    (!pager.is_empty() && pager != "cat").then(|| pager.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose() {
+        let some = |pager: &str| Some(pager.to_string());
+        assert_eq!(choose(None, None, None), some("less"));
+        assert_eq!(choose(some("delta"), some("less -S"), some("more")), some("delta"));
+        assert_eq!(choose(None, some("less -S"), some("more")), some("less -S"));
+        assert_eq!(choose(None, None, some("more")), some("more"));
+        assert_eq!(choose(some(""), some("less -S"), None), None);
+        assert_eq!(choose(None, some("cat"), None), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! One status line per task for work that runs side by side
+//!
+//! Add a [`Task`] for each piece of work, hand the tasks to whatever runs them (usually tokio
+//! tasks) and [`MultiProgress::wait`] until they're all done. On a terminal every line spins
+//! while its task runs and turns into ✓ or ✗ with how long it took. Piped, or in accessible mode,
+//! each line is printed once as its task finishes.
+
+use colored::Colorize;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{events, ui::{accessible::{self, Mark}, text, theme, ColorizeExt}};
+
+/// Frames a running task's spinner cycles through
+const FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+/// How often running lines are redrawn
+const TICK: Duration = Duration::from_millis(80);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Running,
+    Done(Duration),
+    Failed(Duration, String),
+}
+
+#[derive(Debug)]
+struct Line {
+    label: String,
+    started: Instant,
+    state: State,
+    /// Whether the finished line has been printed, when lines aren't redrawn
+    printed: bool,
+}
+
+/// A set of tasks shown one line each
+#[derive(Default)]
+pub struct MultiProgress {
+    lines: Arc<Mutex<Vec<Line>>>,
+}
+
+/// Reports how one task went, from wherever it runs
+pub struct Task {
+    lines: Arc<Mutex<Vec<Line>>>,
+    index: usize,
+    /// The task as a phase for `--events`
+    phase: Option<events::Phase>,
+}
+
+impl MultiProgress {
+    pub fn new() -> MultiProgress {
+        MultiProgress::default()
+    }
+
+    /// add starts a line for a task labelled `label`
+    pub fn add(&self, label: impl Into<String>) -> Task {
+        let label = label.into();
+        let phase = events::phase(label.as_str());
+        let mut lines = lock(&self.lines);
+        lines.push(Line { label, started: Instant::now(), state: State::Running, printed: false });
+        Task { lines: self.lines.clone(), index: lines.len() - 1, phase: Some(phase) }
+    }
+
+    /// wait shows the tasks until every one of them has finished, returning whether they all
+    /// succeeded
+    pub async fn wait(self) -> bool {
+        let live = io::stdout().is_terminal() && !accessible::enabled();
+        let mut drawn = 0;
+        let mut frame = 0;
+
+        loop {
+            let finished = {
+                let mut lines = lock(&self.lines);
+                if live {
+                    drawn = redraw(&lines, drawn, FRAMES[frame % FRAMES.len()]);
+                } else {
+                    for line in lines.iter_mut().filter(|line| line.state != State::Running && !line.printed) {
+                        println!("{}", describe(line, ""));
+                        line.printed = true;
+                    }
+                }
+                lines.iter().all(|line| line.state != State::Running)
+            };
+            if finished {
+                break;
+            }
+            frame += 1;
+            tokio::time::sleep(TICK).await;
+        }
+
+        lock(&self.lines).iter().all(|line| matches!(line.state, State::Done(_)))
+    }
+}
+
+impl Task {
+    /// succeed marks the task as done
+    pub fn succeed(mut self) {
+        if let Some(phase) = self.phase.take() {
+            phase.done();
+        }
+        self.set(State::Done);
+    }
+
+    /// fail marks the task as failed, showing `reason` after it
+    pub fn fail(mut self, reason: impl std::fmt::Display) {
+        let reason = reason.to_string();
+        if let Some(phase) = self.phase.take() {
+            phase.fail(&reason);
+        }
+        self.set(|elapsed| State::Failed(elapsed, reason));
+    }
+
+    /// finish marks the task as done or failed depending on `result`
+    pub fn finish<T, E: std::fmt::Display>(self, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.succeed(),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn set(&self, state: impl FnOnce(Duration) -> State) {
+        let mut lines = lock(&self.lines);
+        let line = &mut lines[self.index];
+        if line.state == State::Running {
+            line.state = state(line.started.elapsed());
+        }
+    }
+}
+
+impl Drop for Task {
+    /// A task dropped without finishing (e.g. it panicked) counts as failed, so waiting on it
+    /// doesn't hang
+    fn drop(&mut self) {
+        self.set(|elapsed| State::Failed(elapsed, "stopped before finishing".to_string()));
+    }
+}
+
+fn lock(lines: &Mutex<Vec<Line>>) -> std::sync::MutexGuard<'_, Vec<Line>> {
+    lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Draw every line over the `drawn` lines from last time, returning how many were drawn now
+fn redraw(lines: &[Line], drawn: usize, spinner: &str) -> usize {
+    let columns = text::terminal_width().unwrap_or(80);
+    let mut stdout = io::stdout().lock();
+    if drawn > 0 {
+        let _ = crossterm::execute!(stdout, crossterm::cursor::MoveUp(drawn as u16));
+    }
+    for line in lines {
+        let _ = crossterm::execute!(stdout, crossterm::terminal::Clear(crossterm::terminal::ClearType::CurrentLine));
+        // Keep each line on one row, otherwise moving back up would land in the wrong place
+        let _ = writeln!(stdout, "{}", describe_to_fit(line, spinner, columns));
+    }
+    let _ = stdout.flush();
+    lines.len()
+}
+
+fn describe_to_fit(line: &Line, spinner: &str, columns: usize) -> String {
+    let described = describe(line, spinner);
+    if text::width(&plain(line, spinner)) <= columns.saturating_sub(1) {
+        return described;
+    }
+    text::ellipsize(&plain(line, spinner), columns.saturating_sub(1))
+}
+
+/// describe renders a line, with `spinner` in front while its task runs
+fn describe(line: &Line, spinner: &str) -> String {
+    match &line.state {
+        State::Running => format!("{} {} {}", spinner.sage(), line.label, format_elapsed(line.started.elapsed()).gray()),
+        State::Done(elapsed) => {
+            format!("{} {} {}", theme::styled(Mark::Passing), line.label, format_elapsed(*elapsed).gray())
+        }
+        State::Failed(elapsed, reason) => format!(
+            "{} {} {} {}",
+            theme::styled(Mark::Failing),
+            line.label,
+            format_elapsed(*elapsed).gray(),
+            summary(reason).red()
+        ),
+    }
+}
+
+/// plain is [`describe`] without colors, for measuring and cutting
+fn plain(line: &Line, spinner: &str) -> String {
+    match &line.state {
+        State::Running => format!("{} {} {}", spinner, line.label, format_elapsed(line.started.elapsed())),
+        State::Done(elapsed) => format!("{} {} {}", accessible::mark(Mark::Passing), line.label, format_elapsed(*elapsed)),
+        State::Failed(elapsed, reason) => format!(
+            "{} {} {} {}",
+            accessible::mark(Mark::Failing),
+            line.label,
+            format_elapsed(*elapsed),
+            summary(reason)
+        ),
+    }
+}
+
+/// summary keeps the first line of why a task failed, so every task stays on one line
+fn summary(reason: &str) -> &str {
+    reason.trim().lines().next().unwrap_or_default()
+}
+
+/// format_elapsed shows a duration the way cargo does, e.g. `0.4s` or `12.0s`
+fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.1}s", elapsed.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_tasks() {
+        let progress = MultiProgress::new();
+        let tasks = ["first", "second", "third"].map(|label| progress.add(label));
+
+        let handles = tasks
+            .into_iter()
+            .enumerate()
+            .map(|(index, task)| {
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(10 * index as u64)).await;
+                    task.finish(&if index == 1 { Err("rejected") } else { Ok(()) });
+                })
+            })
+            .collect::<Vec<_>>();
+
+        assert!(!progress.wait().await);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_task_fails() {
+        let progress = MultiProgress::new();
+        progress.add("done").succeed();
+        drop(progress.add("abandoned"));
+        assert!(!progress.wait().await);
+
+        let progress = MultiProgress::new();
+        progress.add("done").succeed();
+        assert!(progress.wait().await);
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(400)), "0.4s");
+        assert_eq!(format_elapsed(Duration::from_secs(12)), "12.0s");
+    }
+}
@@ -0,0 +1,239 @@
+//! A small handlebars-style template language for `--format`
+//!
+//! Templates are rendered once per record (a branch, a commit, ...), with the record's fields
+//! available by name:
+//!
+//! - `{{name}}` or `{{pr.number}}` inserts a field
+//! - `{{#if field}}...{{else}}...{{/if}}` renders a section when the field is set, non-empty and
+//!   not false or zero
+//! - `{{#each list}}...{{/each}}` renders a section per item, with the item as `{{this}}` (or its
+//!   fields by name) and its position as `{{@index}}`
+//!
+//! Inline templates understand `\n` and `\t`; `@path` reads the template from a file instead.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::fs;
+
+use crate::profile::{self, Phase};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Field(String),
+    If { field: String, then: Vec<Node>, otherwise: Vec<Node> },
+    Each { field: String, body: Vec<Node> },
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// load parses a `--format` argument: a template, or `@path` to read one from a file
+    pub fn load(arg: &str) -> Result<Template> {
+        match arg.strip_prefix('@') {
+            Some(path) => {
+                let source = fs::read_to_string(path).with_context(|| format!("Failed to read template {}", path))?;
+                Template::parse(&source)
+            }
+            None => Template::parse(&arg.replace("\\n", "\n").replace("\\t", "\t")),
+        }
+    }
+
+    /// parse compiles template source
+    pub fn parse(source: &str) -> Result<Template> {
+        let mut tags = Tags { rest: source };
+        let (nodes, end) = parse_nodes(&mut tags)?;
+        match end {
+            None => Ok(Template { nodes }),
+            Some(tag) => Err(anyhow!("Unexpected {{{{{}}}}} in template", tag)),
+        }
+    }
+
+    /// render fills the template in with a record's fields
+    pub fn render(&self, record: &Value) -> String {
+        let _timing = profile::span(Phase::Render, "template");
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![record.clone()], &mut out);
+        out
+    }
+}
+
+/// Splits template source into text and `{{tags}}`
+struct Tags<'a> {
+    rest: &'a str,
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+impl<'a> Tags<'a> {
+    fn next(&mut self) -> Result<Option<Token<'a>>> {
+        if self.rest.is_empty() {
+            return Ok(None);
+        }
+
+        match self.rest.find("{{") {
+            Some(0) => {
+                let end = self.rest.find("}}").ok_or_else(|| anyhow!("Unclosed {{{{ in template"))?;
+                let tag = self.rest[2..end].trim();
+                self.rest = &self.rest[end + 2..];
+                Ok(Some(Token::Tag(tag)))
+            }
+            Some(start) => {
+                let text = &self.rest[..start];
+                self.rest = &self.rest[start..];
+                Ok(Some(Token::Text(text)))
+            }
+            None => {
+                let text = self.rest;
+                self.rest = "";
+                Ok(Some(Token::Text(text)))
+            }
+        }
+    }
+}
+
+/// Parse nodes up to the end of the source or a closing tag (`else`, `/if`, `/each`), which is
+/// returned so the caller can check it matches
+fn parse_nodes<'a>(tags: &mut Tags<'a>) -> Result<(Vec<Node>, Option<&'a str>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tags.next()? {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text.to_string()));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+
+        if tag == "else" || tag.starts_with('/') {
+            return Ok((nodes, Some(tag)));
+        }
+
+        if let Some(field) = tag.strip_prefix("#if ") {
+            let (then, end) = parse_nodes(tags)?;
+            let otherwise = match end {
+                Some("/if") => Vec::new(),
+                Some("else") => match parse_nodes(tags)? {
+                    (otherwise, Some("/if")) => otherwise,
+                    _ => return Err(anyhow!("Missing {{{{/if}}}} in template")),
+                },
+                _ => return Err(anyhow!("Missing {{{{/if}}}} in template")),
+            };
+            nodes.push(Node::If { field: field.trim().to_string(), then, otherwise });
+        } else if let Some(field) = tag.strip_prefix("#each ") {
+            let body = match parse_nodes(tags)? {
+                (body, Some("/each")) => body,
+                _ => return Err(anyhow!("Missing {{{{/each}}}} in template")),
+            };
+            nodes.push(Node::Each { field: field.trim().to_string(), body });
+        } else if tag.starts_with('#') {
+            return Err(anyhow!("Unknown block {{{{{}}}}} in template", tag));
+        } else {
+            nodes.push(Node::Field(tag.to_string()));
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn render_nodes(nodes: &[Node], scopes: &mut Vec<Value>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Field(field) => out.push_str(&display(&lookup(scopes, field))),
+            Node::If { field, then, otherwise } => {
+                let branch = if truthy(&lookup(scopes, field)) { then } else { otherwise };
+                render_nodes(branch, scopes, out);
+            }
+            Node::Each { field, body } => {
+                if let Value::Array(items) = lookup(scopes, field) {
+                    for (index, item) in items.into_iter().enumerate() {
+                        // The index gets a scope of its own above the item's
+                        scopes.push(item);
+                        scopes.push(serde_json::json!({ "@index": index }));
+                        render_nodes(body, scopes, out);
+                        scopes.truncate(scopes.len() - 2);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resolve a dotted field name against the innermost scope that has it
+fn lookup(scopes: &[Value], field: &str) -> Value {
+    if field == "this" {
+        // Inside #each the item scope sits under the @index scope; outside it's the record
+        return scopes.len().checked_sub(2).map_or(&scopes[0], |index| &scopes[index]).clone();
+    }
+
+    let mut parts = field.split('.');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut value) = scopes.iter().rev().find_map(|scope| scope.get(first)) else {
+        return Value::Null;
+    };
+    for part in parts {
+        match value.get(part) {
+            Some(next) => value = next,
+            None => return Value::Null,
+        }
+    }
+    value.clone()
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(display).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fields_and_conditionals() {
+        let template = Template::load(r"{{name}}{{#if pr}} #{{pr.number}}{{else}} -{{/if}}\t{{ahead}}").unwrap();
+        assert_eq!(template.render(&json!({ "name": "feat", "pr": { "number": 7 }, "ahead": 2 })), "feat #7\t2");
+        assert_eq!(template.render(&json!({ "name": "fix", "pr": null, "ahead": 0 })), "fix -\t0");
+    }
+
+    #[test]
+    fn test_each_exposes_items_and_outer_fields() {
+        let template = Template::parse("{{#each files}}{{@index}}:{{this}}@{{branch}} {{/each}}").unwrap();
+        assert_eq!(template.render(&json!({ "branch": "main", "files": ["a.rs", "b.rs"] })), "0:a.rs@main 1:b.rs@main ");
+
+        let template = Template::parse("{{#each prs}}{{number}}{{/each}}").unwrap();
+        assert_eq!(template.render(&json!({ "prs": [{ "number": 1 }, { "number": 2 }] })), "12");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{#if x}}open").is_err());
+        assert!(Template::parse("{{name").is_err());
+        assert!(Template::parse("{{/each}}").is_err());
+        assert!(Template::parse("{{#with x}}{{/with}}").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Laying out text by how wide it shows up in a terminal
+//!
+//! Counting bytes or chars lines up ASCII, but CJK characters and most emoji take two columns
+//! and combining marks none, so columns measured that way drift apart. Everything here counts
+//! display columns instead.
+
+use std::io::IsTerminal;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Marks text that was cut short to fit
+const ELLIPSIS: char = '…';
+
+/// width returns how many columns `text` takes up in a terminal
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// terminal_width returns how many columns stdout has, or None when it isn't a terminal, in which
+/// case output shouldn't be cut to fit
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size().ok().map(|(columns, _)| columns as usize).filter(|columns| *columns > 0)
+}
+
+/// pad fills `text` out with spaces to `columns` wide, leaving text that's already wider alone
+pub fn pad(text: &str, columns: usize) -> String {
+    format!("{}{}", text, " ".repeat(columns.saturating_sub(width(text))))
+}
+
+/// ellipsize cuts `text` down to at most `columns` wide, ending it with an ellipsis when it had
+/// to be cut
+pub fn ellipsize(text: &str, columns: usize) -> String {
+    if width(text) <= columns {
+        return text.to_string();
+    }
+    if columns == 0 {
+        return String::new();
+    }
+
+    let mut cut = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width > columns - 1 {
+            break;
+        }
+        cut.push(c);
+        used += c_width;
+    }
+    cut.push(ELLIPSIS);
+    cut
+}
+
+/// wrap breaks `text` into lines at most `columns` wide, between words where it can. Words wider
+/// than a whole line are split wherever they have to be.
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut used = 0;
+
+    for word in text.split_whitespace() {
+        let word_width = width(word);
+        if used > 0 && used + 1 + word_width <= columns {
+            line.push(' ');
+            line.push_str(word);
+            used += 1 + word_width;
+            continue;
+        }
+        if used > 0 {
+            lines.push(std::mem::take(&mut line));
+            used = 0;
+        }
+        if word_width <= columns {
+            line.push_str(word);
+            used = word_width;
+            continue;
+        }
+
+        for c in word.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if used + c_width > columns && used > 0 {
+                lines.push(std::mem::take(&mut line));
+                used = 0;
+            }
+            line.push(c);
+            used += c_width;
+        }
+    }
+
+    if used > 0 || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width_and_pad() {
+        assert_eq!(width("main"), 4);
+        assert_eq!(width("日本"), 4);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(pad("日本", 6), "日本  ");
+        assert_eq!(pad("feature", 3), "feature");
+    }
+
+    #[test]
+    fn test_ellipsize() {
+        assert_eq!(ellipsize("short", 10), "short");
+        assert_eq!(ellipsize("a long title", 6), "a lon…");
+        // A wide character that would straddle the edge is dropped rather than split
+        assert_eq!(ellipsize("修复登录问题", 6), "修复…");
+        assert_eq!(width(&ellipsize("修复登录问题", 6)), 5);
+        assert_eq!(ellipsize("anything", 0), "");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("the quick brown fox", 10), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("日本語テキスト", 5), vec!["日本", "語テ", "キス", "ト"]);
+        assert_eq!(wrap("", 10), vec![""]);
+        for line in wrap("mixed 日本語 and emoji 🎉🎉🎉 text that keeps going", 7) {
+            assert!(width(&line) <= 7, "{:?} is too wide", line);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Status glyphs and their colors
+//!
+//! The default theme tells passing from failing by green and red. `ui.theme colorblind` swaps in
+//! glyphs that differ in shape, and blue and orange, which stay apart for red-green color blindness.
+
+use colored::{ColoredString, Colorize};
+use std::sync::OnceLock;
+
+use super::{accessible::{self, Mark}, ColorizeExt};
+use crate::config;
+
+/// Every mark, in the order the legend lists them
+pub const MARKS: [Mark; 7] = [
+    Mark::Passing,
+    Mark::Failing,
+    Mark::Pending,
+    Mark::Skipped,
+    Mark::Cancelled,
+    Mark::Pushed,
+    Mark::Bullet,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Default,
+    ColorBlind,
+}
+
+impl Theme {
+    pub fn parse(value: &str) -> Option<Theme> {
+        match value.trim().to_lowercase().as_str() {
+            "default" => Some(Theme::Default),
+            "colorblind" | "colourblind" | "color-blind" => Some(Theme::ColorBlind),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::ColorBlind => "colorblind",
+        }
+    }
+
+    /// The glyph the theme shows for a mark
+    pub fn glyph(self, mark: Mark) -> &'static str {
+        match self {
+            Theme::Default => mark.glyph(),
+            Theme::ColorBlind => match mark {
+                Mark::Bullet => "•",
+                Mark::Passing => "✔",
+                Mark::Failing => "✘",
+                Mark::Pending => "◔",
+                Mark::Skipped => "⊘",
+                Mark::Cancelled => "⊗",
+                Mark::Pushed => "⇡",
+            },
+        }
+    }
+
+    /// Color text the way the theme colors a mark
+    pub fn paint(self, mark: Mark, text: &str) -> ColoredString {
+        match (self, mark) {
+            (_, Mark::Bullet) | (_, Mark::Pushed) => text.sage(),
+            (_, Mark::Skipped) => text.gray(),
+            (Theme::Default, Mark::Passing) => text.green(),
+            (Theme::Default, Mark::Failing) => text.red(),
+            (Theme::Default, Mark::Pending) | (Theme::Default, Mark::Cancelled) => text.yellow(),
+            (Theme::ColorBlind, Mark::Passing) => super::blue(text),
+            (Theme::ColorBlind, Mark::Failing) => super::hex(text, "#E69F00").bold(),
+            (Theme::ColorBlind, Mark::Pending) => super::hex(text, "#F0E442"),
+            (Theme::ColorBlind, Mark::Cancelled) => text.gray(),
+        }
+    }
+}
+
+/// current returns the theme from `ui.theme`, the default when it's unset or unknown
+pub fn current() -> Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    *THEME.get_or_init(|| config::get("ui.theme").as_deref().and_then(Theme::parse).unwrap_or(Theme::Default))
+}
+
+/// styled returns a mark as it's shown: the theme's glyph in its color, or the plain label in
+/// accessible mode
+pub fn styled(mark: Mark) -> ColoredString {
+    let theme = current();
+    theme.paint(mark, accessible::mark(mark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_colorblind_glyphs_are_distinct() {
+        assert_eq!(Theme::parse("Colorblind"), Some(Theme::ColorBlind));
+        assert_eq!(Theme::parse("default"), Some(Theme::Default));
+        assert_eq!(Theme::parse("neon"), None);
+
+        // Nothing but shape tells the marks apart without color
+        let glyphs = MARKS.iter().map(|mark| Theme::ColorBlind.glyph(*mark)).collect::<HashSet<_>>();
+        assert_eq!(glyphs.len(), MARKS.len());
+    }
+}
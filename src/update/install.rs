@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use semver::Version;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{releases, release::{self, Channel}, CURRENT_VERSION};
+use crate::{config::{self, Scope}, ui::ColorizeExt};
+
+/// self_update replaces the running sage with the newest build for this platform on `channel`
+/// (update.channel when None), after checking it against the release's SHA-256. A given channel
+/// is remembered for later updates. With `check` it only says whether there is one.
+pub async fn self_update(channel: Option<Channel>, check: bool) -> Result<()> {
+    if let Some(channel) = channel {
+        config::set("update.channel", channel.as_str(), Scope::Global)?;
+    }
+    let channel = channel.unwrap_or_else(Channel::configured);
+
+    let releases = releases().await?;
+    let release = release::latest(&releases, channel)
+        .ok_or_else(|| anyhow!("No {} release of sage has been published yet", channel.as_str()))?;
+    let latest = release::version(release).ok_or_else(|| anyhow!("Can't read the version of {}", release.tag_name))?;
+    let current = Version::parse(CURRENT_VERSION)?;
+
+    if latest <= current {
+        println!("✨ sage {} is up to date on the {} channel", current, channel.as_str());
+        return Ok(());
+    }
+
+    let target = release::target();
+    let artifact = release::artifact(release, &target).ok_or_else(|| {
+        anyhow!(
+            "sage {} has no build for {}. Update with cargo install sage-rs --force instead",
+            latest,
+            target
+        )
+    })?;
+
+    if check {
+        println!("sage {} is available on the {} channel (you have {})", latest.to_string().green(), channel.as_str(), current);
+        println!("To update, run: {}", "sage self-update".sage());
+        return Ok(());
+    }
+
+    let checksum_asset = release::checksum_asset(release, artifact)?;
+    let dir = std::env::temp_dir().join(format!("sage-update-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let result = install(&dir, artifact.browser_download_url.as_str(), &artifact.name, checksum_asset.browser_download_url.as_str());
+    let _ = fs::remove_dir_all(&dir);
+    result?;
+
+    println!("✨ Updated sage from {} to {}", current, latest.to_string().green());
+    Ok(())
+}
+
+/// Download, verify and unpack a build into `dir`, then swap it in for the running binary
+fn install(dir: &Path, url: &str, name: &str, checksum_url: &str) -> Result<()> {
+    let archive = dir.join(name);
+    println!("Downloading {}", name.sage());
+    download(url, &archive)?;
+
+    let checksum_file = dir.join("checksum");
+    download(checksum_url, &checksum_file)?;
+    let expected = release::expected_sha256(&fs::read_to_string(&checksum_file)?, name)
+        .ok_or_else(|| anyhow!("The release's checksum file has no SHA-256 for {}", name))?;
+    let actual = sha256(&archive)?;
+    if actual != expected {
+        return Err(anyhow!(
+            "{} doesn't match its published checksum (expected {}, got {}). Nothing was installed",
+            name,
+            expected,
+            actual
+        ));
+    }
+    println!("  {} {}", "checksum verified".gray(), actual.gray());
+
+    let output = Command::new("tar")
+        .arg(if name.ends_with(".zip") { "-xf" } else { "-xzf" })
+        .arg(&archive)
+        .arg("-C")
+        .arg(dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to unpack {}: {}", name, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let binary = dir.join(if cfg!(windows) { "sage.exe" } else { "sage" });
+    if !binary.exists() {
+        return Err(anyhow!("{} doesn't contain a sage binary", name));
+    }
+    replace_current_exe(&binary)
+}
+
+/// download fetches `url` into `to` with curl
+pub(crate) fn download(url: &str, to: &Path) -> Result<()> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+        .arg(to)
+        .arg(url)
+        .output()
+        .context("Failed to run curl, which sage needs to download releases and policy bundles")?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to download {}: {}", url, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+/// sha256 returns the hex SHA-256 of a file
+pub(crate) fn sha256(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Move `binary` into the place of the running executable. Windows won't replace a running
+/// executable, but will rename it, so the old one is moved aside first.
+fn replace_current_exe(binary: &Path) -> Result<()> {
+    let current = std::env::current_exe()?;
+    let staged = with_suffix(&current, "new");
+    fs::copy(binary, &staged).with_context(|| format!("Failed to write next to {}", current.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+
+    if cfg!(windows) {
+        let old = with_suffix(&current, "old");
+        let _ = fs::remove_file(&old);
+        fs::rename(&current, &old)?;
+    }
+    fs::rename(&staged, &current).with_context(|| format!("Failed to replace {}", current.display()))?;
+
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", suffix));
+    path.with_file_name(name)
+}
@@ -1,19 +1,33 @@
+pub mod install;
+pub mod release;
+
 use std::{fs, io::{Error, ErrorKind}, time::Duration};
 use std::path::PathBuf;
 use anyhow::{Result, Context};
 use serde::{Serialize, Deserialize};
 use semver::Version;
 use colored::*;
-use crate::{gh, ui::ColorizeExt};
+use crate::{gh, profile::{self, Phase}, ui::ColorizeExt};
 use chrono::Utc;
+use octocrab::models::repos::Release;
+use release::Channel;
+use tokio::task::JoinHandle;
 
 const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60); // 24 hours
 const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// How long a finished command waits for a background update check to come back
+const UPDATE_CHECK_GRACE: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Serialize, Deserialize)]
 struct UpdateCheck {
     last_check: i64,
     latest_version: Option<String>,
+    /// The channel the last check was for, so switching channels checks again
+    #[serde(default)]
+    channel: Option<String>,
+    /// Whether the latest release has a build for this platform
+    #[serde(default)]
+    has_build: bool,
 }
 
 impl Default for UpdateCheck {
@@ -21,6 +35,8 @@ impl Default for UpdateCheck {
         Self {
             last_check: 0,
             latest_version: None,
+            channel: None,
+            has_build: false,
         }
     }
 }
@@ -52,62 +68,92 @@ fn save_update_check(check: &UpdateCheck) -> Result<()> {
     Ok(())
 }
 
-fn should_check_for_updates() -> Result<bool> {
+fn should_check_for_updates(channel: Channel) -> Result<bool> {
     let check = load_update_check()?;
     let now = Utc::now().timestamp();
-    Ok(now - check.last_check >= CHECK_INTERVAL.as_secs() as i64)
+    Ok(now - check.last_check >= CHECK_INTERVAL.as_secs() as i64
+        || check.channel.as_deref() != Some(channel.as_str()))
 }
 
-async fn get_latest_version() -> Result<Option<String>> {
+/// releases returns the most recent releases of sage, nightly builds included
+async fn releases() -> Result<Vec<Release>> {
+    let _timing = profile::span(Phase::Network, "releases");
     let octocrab = gh::get_instance();
     let releases = octocrab
         .repos("crazywolf132", "sage-rs")
         .releases()
         .list()
-        .per_page(1)
+        .per_page(30)
         .send()
         .await
         .context("Failed to fetch releases")?;
 
-    if let Some(release) = releases.items.first() {
-        // Remove 'v' prefix if present
-        let version = release.tag_name.trim_start_matches('v').to_string();
-        Ok(Some(version))
-    } else {
-        Ok(None)
-    }
+    Ok(releases.items)
 }
 
-fn show_update_notification(current: &str, latest: &str) {
-    println!("\n{}", "✨ A new version of Sage is available!".sage().bold());
-    println!("Current version: {}", current.yellow());
-    println!("Latest version: {}", latest.green());
-    println!("To update, run: {}", "cargo install sage-rs --force".cyan());
-    println!();
+/// show_update_notification tells the user about a newer release, on stderr so it never ends up in
+/// output that's piped or parsed
+fn show_update_notification(current: &str, latest: &str, has_build: bool) {
+    eprintln!("\n{}", "✨ A new version of Sage is available!".sage().bold());
+    eprintln!("Current version: {}", current.yellow());
+    eprintln!("Latest version: {}", latest.green());
+    // Not every platform gets a pre-built release
+    let command = if has_build { "sage self-update" } else { "cargo install sage-rs --force" };
+    eprintln!("To update, run: {}", command.cyan());
+    eprintln!();
 }
 
-pub async fn check_for_updates() -> Result<()> {
-    if !should_check_for_updates()? {
-        return Ok(());
+/// start_update_check looks for a new release in the background when the last look was long
+/// enough ago, so no command waits on the network before it runs
+pub fn start_update_check() -> Option<JoinHandle<()>> {
+    let channel = Channel::configured();
+    if !should_check_for_updates(channel).unwrap_or(false) {
+        return None;
     }
+    // Being offline or rate limited mustn't get in the way of the command either
+    Some(tokio::spawn(async move {
+        let _ = refresh_update_check(channel).await;
+    }))
+}
 
-    let latest_version = get_latest_version().await?;
-    let current_version = CURRENT_VERSION;
-
-    if let Some(latest) = latest_version {
-        let current = Version::parse(current_version)?;
-        let latest = Version::parse(&latest)?;
-
-        if latest > current {
-            show_update_notification(current_version, &latest.to_string());
-        }
+/// finish_update_check gives a background check a moment to finish once the command is done,
+/// then tells the user about a newer release if there is one. Like the check itself, that's at
+/// most once a day.
+pub async fn finish_update_check(check: Option<JoinHandle<()>>) {
+    let Some(check) = check else {
+        return;
+    };
+    // A check that takes longer leaves the last release it found to go by
+    let _ = tokio::time::timeout(UPDATE_CHECK_GRACE, check).await;
+
+    let Ok(check) = load_update_check() else {
+        return;
+    };
+    if check.channel.as_deref() != Some(Channel::configured().as_str()) {
+        return;
+    }
+    let Some(latest) = check.latest_version.as_deref().and_then(|latest| Version::parse(latest).ok()) else {
+        return;
+    };
+    if Version::parse(CURRENT_VERSION).is_ok_and(|current| latest > current) {
+        show_update_notification(CURRENT_VERSION, &latest.to_string(), check.has_build);
+    }
+}
 
-        // Update the check file
-        let mut check = load_update_check()?;
-        check.last_check = Utc::now().timestamp();
+/// Look up the latest release and remember it for the notice
+async fn refresh_update_check(channel: Channel) -> Result<()> {
+    // Record the attempt first, so being offline doesn't mean trying again on every command
+    let mut check = load_update_check()?;
+    check.last_check = Utc::now().timestamp();
+    check.channel = Some(channel.as_str().to_string());
+    save_update_check(&check)?;
+
+    let releases = releases().await?;
+    if let Some(release) = release::latest(&releases, channel) {
+        let latest = release::version(release).context("Failed to read the latest version")?;
         check.latest_version = Some(latest.to_string());
+        check.has_build = release::artifact(release, &release::target()).is_some();
         save_update_check(&check)?;
     }
-
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,134 @@
+//! Picking the release, and the build within it, that sage updates to
+//!
+//! Release assets are named `sage-<target>-<version>.tar.gz` (`.zip` on Windows), with the
+//! SHA-256 of each in `<asset>.sha256` or in a `SHA256SUMS` file covering all of them.
+
+use anyhow::{anyhow, Result};
+use octocrab::models::repos::{Asset, Release};
+use semver::Version;
+
+use crate::config;
+
+/// Name of a checksum file covering every asset of a release
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// Which releases to update to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Regular releases only
+    Stable,
+    /// Nightly builds too, whichever is newest
+    Nightly,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Nightly => "nightly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Channel> {
+        match value.trim().to_lowercase().as_str() {
+            "stable" => Some(Channel::Stable),
+            "nightly" => Some(Channel::Nightly),
+            _ => None,
+        }
+    }
+
+    /// configured returns the channel set with update.channel, stable by default
+    pub fn configured() -> Channel {
+        config::get("update.channel")
+            .and_then(|value| Channel::parse(&value))
+            .unwrap_or(Channel::Stable)
+    }
+
+    fn includes(&self, release: &Release) -> bool {
+        !release.draft && (*self == Channel::Nightly || !release.prerelease)
+    }
+}
+
+/// target returns the target triple release builds for this platform are named after
+pub fn target() -> String {
+    let arch = std::env::consts::ARCH;
+    match std::env::consts::OS {
+        "macos" => format!("{}-apple-darwin", arch),
+        "windows" => format!("{}-pc-windows-msvc", arch),
+        os => format!("{}-unknown-{}-{}", arch, os, if cfg!(target_env = "musl") { "musl" } else { "gnu" }),
+    }
+}
+
+/// version reads the version from a release tag like `v1.2.3`
+pub fn version(release: &Release) -> Option<Version> {
+    Version::parse(release.tag_name.trim_start_matches('v')).ok()
+}
+
+/// latest returns the newest release on a channel
+pub fn latest(releases: &[Release], channel: Channel) -> Option<&Release> {
+    releases
+        .iter()
+        .filter(|release| channel.includes(release))
+        .filter_map(|release| version(release).map(|version| (version, release)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release)
+}
+
+/// artifact returns the build of a release for `target`, if one was published
+pub fn artifact<'a>(release: &'a Release, target: &str) -> Option<&'a Asset> {
+    let prefix = format!("sage-{}-", target);
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name.starts_with(&prefix) && (asset.name.ends_with(".tar.gz") || asset.name.ends_with(".zip")))
+}
+
+/// checksum_asset returns the asset holding the SHA-256 of `artifact`
+pub fn checksum_asset<'a>(release: &'a Release, artifact: &Asset) -> Result<&'a Asset> {
+    let own = format!("{}.sha256", artifact.name);
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == own)
+        .or_else(|| release.assets.iter().find(|asset| asset.name == CHECKSUMS_ASSET))
+        .ok_or_else(|| anyhow!("Release {} has no checksum for {}, so it can't be verified", release.tag_name, artifact.name))
+}
+
+/// expected_sha256 finds the hash for `name` in a checksum file: either a bare hash, or
+/// `sha256sum` output with one `<hash>  <name>` line per file
+pub fn expected_sha256(checksums: &str, name: &str) -> Option<String> {
+    let lines = checksums.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+    let hash = match lines.as_slice() {
+        [line] if !line.contains(char::is_whitespace) => Some(*line),
+        _ => lines.iter().find_map(|line| {
+            let (hash, file) = line.split_once(char::is_whitespace)?;
+            (file.trim().trim_start_matches('*') == name).then_some(hash)
+        }),
+    }?;
+
+    let hash = hash.to_lowercase();
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())).then_some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_sha256() {
+        let hash = "a".repeat(64);
+        assert_eq!(expected_sha256(&format!("{}\n", hash), "sage.tar.gz"), Some(hash.clone()));
+
+        let sums = format!("{}  sage-x86_64-apple-darwin-1.0.0.tar.gz\n{} *sage.tar.gz\n", "b".repeat(64), hash.to_uppercase());
+        assert_eq!(expected_sha256(&sums, "sage.tar.gz"), Some(hash));
+        assert_eq!(expected_sha256(&sums, "other.tar.gz"), None);
+        assert_eq!(expected_sha256("not-a-hash", "sage.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_channel_parse() {
+        assert_eq!(Channel::parse("Nightly"), Some(Channel::Nightly));
+        assert_eq!(Channel::parse("stable"), Some(Channel::Stable));
+        assert_eq!(Channel::parse("beta"), None);
+    }
+}
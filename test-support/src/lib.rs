@@ -0,0 +1,275 @@
+//! Throwaway git repositories for driving the sage binary end to end
+//!
+//! A [`TestRepo`] is a clone with one commit on `main`, whose `origin` is a bare repository next
+//! to it, so pushes, fetches and pulls work without a network. sage runs with its own home
+//! directory, so neither the user's git config nor their sage config leak in.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tells repositories created by the same test run apart
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A repository with a bare `origin`, removed again when dropped
+pub struct TestRepo {
+    root: PathBuf,
+    work: PathBuf,
+    remote: PathBuf,
+    home: PathBuf,
+    sage: PathBuf,
+    /// Extra environment for sage runs
+    env: Vec<(String, String)>,
+}
+
+/// What a sage run printed, and whether it succeeded
+#[derive(Debug)]
+pub struct Run {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Run {
+    fn from_output(output: Output) -> Run {
+        Run {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }
+    }
+
+    /// assert_success panics with everything sage printed when the run failed
+    pub fn assert_success(&self) -> &Run {
+        assert!(self.success, "sage failed\nstdout:\n{}\nstderr:\n{}", self.stdout, self.stderr);
+        self
+    }
+}
+
+impl TestRepo {
+    /// new creates a repository to run the sage binary at `sage` in, usually
+    /// `env!("CARGO_BIN_EXE_sage")`
+    pub fn new(sage: impl Into<PathBuf>) -> TestRepo {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let root = std::env::temp_dir().join(format!("sage-e2e-{}-{}", std::process::id(), id));
+        let _ = fs::remove_dir_all(&root);
+
+        let repo = TestRepo {
+            work: root.join("work"),
+            remote: root.join("origin.git"),
+            home: root.join("home"),
+            sage: sage.into(),
+            env: Vec::new(),
+            root,
+        };
+        fs::create_dir_all(&repo.home).expect("Failed to create the test home");
+        fs::write(
+            repo.home.join(".gitconfig"),
+            "[user]\n\tname = Sage Test\n\temail = test@example.com\n[init]\n\tdefaultBranch = main\n",
+        )
+        .expect("Failed to write the test git config");
+        repo.skip_update_check();
+
+        repo.run_git(&repo.root, &["init", "--quiet", "--bare", "origin.git"]);
+        repo.run_git(&repo.root, &["clone", "--quiet", "origin.git", "work"]);
+        repo.commit_file("README.md", "# test\n", "initial commit");
+        repo.git(&["push", "--quiet", "-u", "origin", "main"]);
+        // Clones of an empty remote don't know its default branch yet
+        repo.git(&["remote", "set-head", "origin", "main"]);
+
+        repo
+    }
+
+    /// path returns the working tree sage runs in
+    pub fn path(&self) -> &Path {
+        &self.work
+    }
+
+    /// set_env sets an environment variable for every later sage run
+    pub fn set_env(&mut self, key: &str, value: impl Into<String>) {
+        self.env.push((key.to_string(), value.into()));
+    }
+
+    /// sage runs sage with `args` in the working tree
+    pub fn sage(&self, args: &[&str]) -> Run {
+        self.sage_with_input(args, "")
+    }
+
+    /// sage_with_input runs sage with `input` as its stdin, for commands that ask before acting
+    pub fn sage_with_input(&self, args: &[&str], input: &str) -> Run {
+        let mut child = self
+            .command(&self.sage)
+            .envs(self.env.iter().map(|(key, value)| (key, value)))
+            .args(args)
+            .current_dir(&self.work)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to run sage");
+        child
+            .stdin
+            .take()
+            .expect("sage has no stdin")
+            .write_all(input.as_bytes())
+            .expect("Failed to write to sage");
+
+        Run::from_output(child.wait_with_output().expect("Failed to wait for sage"))
+    }
+
+    /// git runs git in the working tree and returns its trimmed output, panicking if it fails
+    pub fn git(&self, args: &[&str]) -> String {
+        self.run_git(&self.work, args)
+    }
+
+    /// remote_git runs git in the bare origin
+    pub fn remote_git(&self, args: &[&str]) -> String {
+        self.run_git(&self.remote, args)
+    }
+
+    /// write creates or replaces a file in the working tree
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.work.join(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("Failed to create a directory");
+        }
+        fs::write(path, contents).expect("Failed to write a file");
+    }
+
+    /// read returns a file from the working tree, or None when it doesn't exist
+    pub fn read(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.work.join(path)).ok()
+    }
+
+    /// commit_file writes a file and commits it on the current branch with git itself, returning
+    /// the new commit
+    pub fn commit_file(&self, path: &str, contents: &str, message: &str) -> String {
+        self.write(path, contents);
+        self.git(&["add", path]);
+        self.git(&["commit", "--quiet", "-m", message]);
+        self.rev("HEAD")
+    }
+
+    /// push_from_elsewhere commits a file to `branch` of origin from another clone, as a teammate
+    /// would
+    pub fn push_from_elsewhere(&self, branch: &str, path: &str, contents: &str, message: &str) -> String {
+        let other = self.root.join("elsewhere");
+        if !other.exists() {
+            self.run_git(&self.root, &["clone", "--quiet", "origin.git", "elsewhere"]);
+        }
+        self.run_git(&other, &["checkout", "--quiet", branch]);
+        self.run_git(&other, &["pull", "--quiet"]);
+        fs::write(other.join(path), contents).expect("Failed to write a file");
+        self.run_git(&other, &["add", path]);
+        self.run_git(&other, &["commit", "--quiet", "-m", message]);
+        self.run_git(&other, &["push", "--quiet", "origin", branch]);
+        self.run_git(&other, &["rev-parse", "HEAD"])
+    }
+
+    /// current_branch returns the branch checked out in the working tree
+    pub fn current_branch(&self) -> String {
+        self.git(&["branch", "--show-current"])
+    }
+
+    /// branches returns the local branches, sorted
+    pub fn branches(&self) -> Vec<String> {
+        let output = self.git(&["for-each-ref", "--format=%(refname:short)", "refs/heads"]);
+        let mut branches = output.lines().map(str::to_string).collect::<Vec<_>>();
+        branches.sort();
+        branches
+    }
+
+    /// rev returns the commit `rev` points at in the working tree
+    pub fn rev(&self, rev: &str) -> String {
+        self.git(&["rev-parse", rev])
+    }
+
+    /// remote_rev returns the commit `branch` points at in origin, or None when origin doesn't have it
+    pub fn remote_rev(&self, branch: &str) -> Option<String> {
+        let output = self
+            .command("git")
+            .args(["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)])
+            .current_dir(&self.remote)
+            .output()
+            .expect("Failed to run git");
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// is_ancestor returns whether `ancestor` is in the history of `rev`
+    pub fn is_ancestor(&self, ancestor: &str, rev: &str) -> bool {
+        self.command("git")
+            .args(["merge-base", "--is-ancestor", ancestor, rev])
+            .current_dir(&self.work)
+            .status()
+            .expect("Failed to run git")
+            .success()
+    }
+
+    /// config returns a git config value of the working tree
+    pub fn config(&self, key: &str) -> Option<String> {
+        let output = self
+            .command("git")
+            .args(["config", "--get", key])
+            .current_dir(&self.work)
+            .output()
+            .expect("Failed to run git");
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn run_git(&self, dir: &Path, args: &[&str]) -> String {
+        let output = self.command("git").args(args).current_dir(dir).output().expect("Failed to run git");
+        assert!(
+            output.status.success(),
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// A command isolated from the user's configuration, credentials and language
+    fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> Command {
+        let mut command = Command::new(program);
+        command
+            .env("HOME", &self.home)
+            .env("XDG_CONFIG_HOME", self.home.join(".config"))
+            .env("GIT_CONFIG_GLOBAL", self.home.join(".gitconfig"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("SAGE_LANG", "en")
+            .env("NO_COLOR", "1")
+            .env_remove("SAGE_GITHUB_TOKEN")
+            .env_remove("GITHUB_TOKEN")
+            .env_remove("GH_TOKEN")
+            .env_remove("SAGE_UNSTABLE")
+            .env_remove("GITHUB_ACTIONS")
+            .env_remove("GITHUB_STEP_SUMMARY");
+        command
+    }
+
+    /// Record an update check as just done, so sage doesn't go looking for releases
+    fn skip_update_check(&self) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let check = format!(r#"{{"last_check": {}, "latest_version": null, "channel": "stable"}}"#, now);
+        // Where dirs::config_dir points on Linux, and on macOS
+        for dir in [self.home.join(".config/sage"), self.home.join("Library/Application Support/sage")] {
+            fs::create_dir_all(&dir).expect("Failed to create the sage config directory");
+            fs::write(dir.join("update_check.json"), &check).expect("Failed to write the update check");
+        }
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,1313 @@
+//! End-to-end tests running the sage binary against throwaway repositories
+
+use sage_test_support::TestRepo;
+
+fn repo() -> TestRepo {
+    TestRepo::new(env!("CARGO_BIN_EXE_sage"))
+}
+
+#[test]
+fn start_creates_and_switches_to_branch() {
+    let repo = repo();
+
+    repo.sage(&["start", "feature"]).assert_success();
+
+    assert_eq!(repo.current_branch(), "feature");
+    assert_eq!(repo.branches(), vec!["feature", "main"]);
+    assert_eq!(repo.rev("feature"), repo.rev("main"));
+}
+
+#[test]
+fn start_with_parent_records_the_stack() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    let feature = repo.commit_file("feature.txt", "feature\n", "add feature");
+
+    repo.sage(&["start", "child", "--parent", "feature"]).assert_success();
+
+    assert_eq!(repo.current_branch(), "child");
+    assert_eq!(repo.rev("child"), feature);
+    assert_eq!(repo.config("branch.child.sage-parent").as_deref(), Some("feature"));
+}
+
+#[test]
+fn commit_records_all_changes() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("src/lib.rs", "pub fn answer() -> u8 { 42 }\n");
+    repo.write("README.md", "# test\n\nMore words.\n");
+
+    repo.sage(&["commit", "feat: add answer"]).assert_success();
+
+    assert_eq!(repo.git(&["log", "-1", "--format=%s"]), "feat: add answer");
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+    assert_eq!(repo.git(&["show", "--name-only", "--format=", "HEAD"]), "README.md\nsrc/lib.rs");
+}
+
+#[test]
+fn commit_push_updates_origin() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("feature.txt", "feature\n");
+
+    repo.sage(&["commit", "--push", "add feature"]).assert_success();
+
+    assert_eq!(repo.remote_rev("feature"), Some(repo.rev("HEAD")));
+}
+
+#[test]
+fn push_stack_pushes_every_branch() {
+    let repo = repo();
+    repo.sage(&["start", "feature", "--parent", "main"]).assert_success();
+    let feature = repo.commit_file("feature.txt", "feature\n", "add feature");
+    repo.sage(&["start", "child", "--parent", "feature"]).assert_success();
+    let child = repo.commit_file("child.txt", "child\n", "add child");
+
+    let run = repo.sage(&["push", "--stack"]);
+    run.assert_success();
+
+    assert_eq!(repo.remote_rev("feature"), Some(feature));
+    assert_eq!(repo.remote_rev("child"), Some(child));
+    assert_eq!(repo.config("branch.feature.merge").as_deref(), Some("refs/heads/feature"));
+    assert_eq!(repo.config("branch.child.merge").as_deref(), Some("refs/heads/child"));
+    assert!(run.stdout.contains("✓ feature"), "{}", run.stdout);
+}
+
+#[test]
+#[cfg(unix)]
+fn hooks_last_explains_a_blocked_push() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut repo = repo();
+    repo.write(".git/plugins/policy/plugin.json", r#"{"name": "policy", "hooks": ["pre-push"], "command": "./check.sh"}"#);
+    repo.write(".git/plugins/policy/check.sh", "#!/bin/sh\ncat >/dev/null\necho '{\"verdict\": \"block\", \"message\": \"no pushing on Fridays\"}'\n");
+    let script = repo.path().join(".git/plugins/policy/check.sh");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    repo.set_env("SAGE_PLUGIN_DIR", repo.path().join(".git/plugins").display().to_string());
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.commit_file("feature.txt", "feature\n", "add feature");
+
+    let push = repo.sage(&["push"]);
+    assert!(!push.success);
+    assert_eq!(repo.remote_rev("feature"), None);
+
+    let run = repo.sage(&["hooks", "last"]);
+    run.assert_success();
+    assert!(run.stdout.contains("pre-push on feature"), "{}", run.stdout);
+    assert!(run.stdout.contains("policy block"), "{}", run.stdout);
+    assert!(run.stdout.contains("no pushing on Fridays"), "{}", run.stdout);
+}
+
+#[test]
+fn policy_sync_installs_the_bundle_and_enforces_it() {
+    let repo = repo();
+    repo.write(
+        ".git/policy/policy.json",
+        r#"{"name": "acme", "plugins": ["lint"], "protected_branches": ["main"], "commits": {"conventional": true}, "config": {"commit.clock_guard": "warn"}}"#,
+    );
+    repo.write(".git/policy/plugins/lint/plugin.json", r#"{"name": "lint", "version": "1.0", "hooks": [], "command": "true"}"#);
+    repo.git(&["-C", ".git/policy", "init", "--quiet"]);
+    repo.git(&["-C", ".git/policy", "add", "."]);
+    repo.git(&["-C", ".git/policy", "commit", "--quiet", "-m", "policy"]);
+    let source = repo.path().join(".git/policy").display().to_string();
+    repo.write(".git/sage/config.json", &format!(r#"{{"policy.source": "{}"}}"#, source));
+
+    repo.sage(&["policy", "sync"]).assert_success();
+    assert!(repo.path().join("../home/.config/sage/plugins/lint/plugin.json").exists());
+    repo.sage(&["doctor"]).assert_success();
+
+    repo.write("notes.txt", "notes\n");
+    assert!(!repo.sage(&["commit", "feat: add notes"]).success);
+    repo.sage(&["start", "notes"]).assert_success();
+    assert!(!repo.sage(&["commit", "add notes"]).success);
+    repo.sage(&["commit", "feat: add notes"]).assert_success();
+
+    repo.write(".git/sage/config.json", &format!(r#"{{"policy.source": "{}", "commit.clock_guard": "off"}}"#, source));
+    let run = repo.sage(&["doctor"]);
+    assert!(!run.success);
+    assert!(run.stdout.contains("commit.clock_guard: off in the repository config"), "{}", run.stdout);
+}
+
+#[test]
+fn plugin_install_refuses_unsigned_plugins_when_required() {
+    let repo = repo();
+    repo.write(".git/lint/plugin.json", r#"{"name": "lint", "hooks": [], "command": "true"}"#);
+    let installed = repo.path().join("../home/.config/sage/plugins/lint/plugin.json");
+
+    repo.write(".git/sage/config.json", r#"{"plugins.require_signed": "true"}"#);
+    let run = repo.sage(&["plugin", "install", ".git/lint"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("isn't signed"), "{}", run.stderr);
+    assert!(!installed.exists());
+
+    repo.write(".git/sage/config.json", "{}");
+    repo.sage(&["plugin", "install", ".git/lint"]).assert_success();
+    assert!(installed.exists());
+}
+
+#[test]
+fn profile_times_the_git_calls_and_saves_a_trace() {
+    let repo = repo();
+
+    let run = repo.sage(&["profile", "status"]);
+    run.assert_success();
+
+    assert!(run.stderr.contains("Profile of sage status"), "{}", run.stderr);
+    assert!(run.stderr.contains("git rev-parse"), "{}", run.stderr);
+    let traces = std::fs::read_dir(repo.path().join(".git/sage/profiles")).unwrap().collect::<Vec<_>>();
+    assert_eq!(traces.len(), 1);
+    let trace = std::fs::read_to_string(traces[0].as_ref().unwrap().path()).unwrap();
+    assert!(trace.contains("traceEvents"), "{}", trace);
+}
+
+#[test]
+fn list_pages_through_branches_and_indexes_them() {
+    let repo = repo();
+    repo.sage(&["start", "older"]).assert_success();
+    repo.commit_file("older.txt", "older\n", "add older");
+    repo.sage(&["push"]).assert_success();
+    repo.commit_file("older2.txt", "older\n", "add more");
+    repo.sage(&["start", "newer"]).assert_success();
+    repo.commit_file("newer.txt", "newer\n", "add newer");
+
+    let run = repo.sage(&["list", "--limit", "2", "--page", "2"]);
+    run.assert_success();
+    assert_eq!(run.stdout.lines().filter(|line| line.starts_with("  ") || line.starts_with("* ")).count(), 1, "{}", run.stdout);
+    assert!(run.stdout.contains("Branches 3-3 of 3"), "{}", run.stdout);
+
+    let run = repo.sage(&["list", "--format", "{{name}} {{ahead}}"]);
+    run.assert_success();
+    let mut lines = run.stdout.lines().collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(lines, vec!["main 0", "newer 0", "older 1"]);
+    assert!(repo.path().join(".git/sage/branch-index.json").exists());
+}
+
+#[test]
+fn list_shows_branch_age_and_filters_stale_branches() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.commit_file("feature.txt", "feature\n", "add feature");
+
+    let run = repo.sage(&["list", "--format", "{{name}} {{age_days}} {{forked_days}} {{stale}}"]);
+    run.assert_success();
+    let mut lines = run.stdout.lines().collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(lines, vec!["feature 0 0 false", "main 0  false"]);
+
+    // Nothing has gone a day without commits yet
+    let run = repo.sage(&["list", "--stale", "1", "--format", "{{name}}"]);
+    run.assert_success();
+    assert_eq!(run.stdout, "");
+
+    let run = repo.sage(&["status"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Age: <1d old, forked <1d ago"), "{}", run.stdout);
+}
+
+#[test]
+fn status_shows_who_owns_the_changes() {
+    let repo = repo();
+    repo.write(".git/sage/config.json", r#"{"pr.max_owners": "1"}"#);
+    repo.commit_file(".github/CODEOWNERS", "/api/ @acme/backend\n/web/ @acme/frontend\n", "add owners");
+    repo.sage(&["start", "feature", "--parent", "main"]).assert_success();
+    repo.commit_file("api/users.go", "package api\n", "add users");
+    repo.write("web/app.ts", "export {}\n");
+    repo.git(&["add", "web"]);
+
+    let run = repo.sage(&["status"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("@acme/backend (1 file), @acme/frontend (1 file)"), "{}", run.stdout);
+    assert!(run.stdout.contains("need review from 2 owners"), "{}", run.stdout);
+}
+
+#[test]
+fn pr_size_plan_splits_the_branch_into_a_stack() {
+    let repo = repo();
+    repo.write(".git/sage/config.json", r#"{"pr.max_areas": "1"}"#);
+    repo.sage(&["start", "feature", "--parent", "main"]).assert_success();
+    repo.commit_file("docs/guide.md", "guide\n", "document it");
+    repo.commit_file("src/lib.rs", "code\n", "build it");
+    // main moving on since isn't part of the split
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.commit_file("news.md", "news\n", "share news");
+    repo.git(&["checkout", "--quiet", "feature"]);
+
+    let run = repo.sage(&["pr", "size", "--plan"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("spread over 2 areas"), "{}", run.stdout);
+    assert_eq!(repo.current_branch(), "feature");
+    assert_eq!(repo.config("branch.feature-1.sage-parent").as_deref(), Some("main"));
+    assert_eq!(repo.git(&["show", "feature-1:docs/guide.md"]), "guide");
+    assert_eq!(repo.git(&["diff", "feature-2", "feature"]), "");
+    assert!(repo.is_ancestor("feature-1", "feature-2"));
+    assert!(!repo.is_ancestor("main", "feature-1"));
+}
+
+#[test]
+fn templates_fill_in_branch_variables() {
+    let repo = repo();
+    repo.write(
+        ".git/sage/config.json",
+        r#"{"branch.template": "feature/{{name}}", "commit.template": "{{#if ticket}}{{ticket}}: {{/if}}{{message}}"}"#,
+    );
+
+    let run = repo.sage(&["start", "ABC-42-login"]);
+    run.assert_success();
+    assert_eq!(repo.current_branch(), "feature/ABC-42-login");
+
+    repo.write("login.txt", "login\n");
+    repo.sage(&["commit", "Add login"]).assert_success();
+    assert_eq!(repo.git(&["log", "-1", "--format=%s"]), "ABC-42: Add login");
+}
+
+#[test]
+fn sync_pulls_the_default_branch() {
+    let repo = repo();
+    let upstream = repo.push_from_elsewhere("main", "other.txt", "other\n", "teammate change");
+
+    repo.sage(&["sync"]).assert_success();
+
+    assert_eq!(repo.rev("main"), upstream);
+    assert_eq!(repo.read("other.txt").as_deref(), Some("other\n"));
+}
+
+#[test]
+fn snapshot_diff_shows_what_sync_changed() {
+    let repo = repo();
+    repo.push_from_elsewhere("main", "other.txt", "other\n", "teammate change");
+    repo.sage(&["sync"]).assert_success();
+
+    let run = repo.sage(&["snapshot", "diff", "1"]);
+    run.assert_success();
+    assert!(run.stdout.contains("#1 sync on main"), "{}", run.stdout);
+    assert!(run.stdout.contains("+ "), "{}", run.stdout);
+    assert!(run.stdout.contains("teammate change"), "{}", run.stdout);
+}
+
+#[test]
+fn sync_pushes_new_commits_on_a_feature_branch() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("feature.txt", "feature\n");
+    repo.sage(&["commit", "--push", "add feature"]).assert_success();
+    let local = repo.commit_file("more.txt", "more\n", "add more");
+
+    repo.sage(&["sync"]).assert_success();
+
+    assert_eq!(repo.remote_rev("feature"), Some(local));
+}
+
+#[test]
+fn sync_keeps_uncommitted_changes() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("feature.txt", "feature\n");
+    repo.sage(&["commit", "--push", "add feature"]).assert_success();
+    let pushed = repo.rev("HEAD");
+    repo.write("feature.txt", "still working\n");
+
+    repo.sage(&["sync"]).assert_success();
+
+    assert_eq!(repo.rev("HEAD"), pushed);
+    assert_eq!(repo.read("feature.txt").as_deref(), Some("still working\n"));
+}
+
+#[test]
+fn clean_deletes_merged_branches() {
+    let repo = repo();
+    repo.sage(&["start", "done"]).assert_success();
+    repo.commit_file("done.txt", "done\n", "finish work");
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.git(&["merge", "--quiet", "--ff-only", "done"]);
+    repo.sage(&["start", "ongoing"]).assert_success();
+    repo.commit_file("ongoing.txt", "ongoing\n", "start work");
+    repo.git(&["checkout", "--quiet", "main"]);
+
+    repo.sage_with_input(&["clean"], "y\n").assert_success();
+
+    assert_eq!(repo.branches(), vec!["main", "ongoing"]);
+}
+
+#[test]
+fn clean_deletes_squash_and_rebase_merged_branches() {
+    let repo = repo();
+    repo.sage(&["start", "squashed"]).assert_success();
+    repo.commit_file("a.txt", "a\n", "feat: add a");
+    repo.commit_file("a.txt", "a\nmore\n", "fix: more a");
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.sage(&["start", "rebased"]).assert_success();
+    repo.commit_file("b.txt", "b\n", "feat: add b");
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.sage(&["start", "ongoing"]).assert_success();
+    repo.commit_file("c.txt", "c\n", "feat: add c");
+    repo.git(&["checkout", "--quiet", "main"]);
+
+    // As GitHub's squash and rebase buttons would merge them
+    repo.git(&["merge", "--quiet", "--squash", "squashed"]);
+    repo.git(&["commit", "--quiet", "-m", "feat: add a (#1)"]);
+    repo.git(&["cherry-pick", "rebased"]);
+    repo.git(&["push", "--quiet", "origin", "main"]);
+
+    repo.sage_with_input(&["clean"], "y\n").assert_success();
+
+    assert_eq!(repo.branches(), vec!["main", "ongoing"]);
+    // Checking for the squash leaves no commits behind
+    let fsck = repo.git(&["fsck", "--unreachable", "--no-reflogs"]);
+    assert!(!fsck.contains("unreachable commit"), "{}", fsck);
+}
+
+#[test]
+fn clean_keeps_branches_when_declined() {
+    let repo = repo();
+    repo.sage(&["start", "done"]).assert_success();
+    repo.git(&["checkout", "--quiet", "main"]);
+
+    repo.sage_with_input(&["clean"], "n\n").assert_success();
+
+    assert_eq!(repo.branches(), vec!["done", "main"]);
+}
+
+#[test]
+fn clean_policy_archives_merged_branches_from_a_scheduled_job() {
+    let repo = repo();
+    repo.sage(&["start", "done"]).assert_success();
+    repo.commit_file("done.txt", "done\n", "finish work");
+    let done = repo.rev("done");
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.git(&["merge", "--quiet", "--ff-only", "done"]);
+    repo.sage(&["start", "ongoing"]).assert_success();
+    repo.commit_file("ongoing.txt", "ongoing\n", "start work");
+    repo.git(&["checkout", "--quiet", "main"]);
+    // Just started from an old commit on main, with nothing of its own yet
+    repo.git(&["branch", "fresh", "main~1"]);
+    repo.write(".git/sage/config.json", r#"{"clean.merged_days": "0"}"#);
+
+    // Without --yes it only reports what it would do
+    let run = repo.sage(&["clean", "--policy", "--archive", "--report", "json"]);
+    run.assert_success();
+    let report: serde_json::Value = serde_json::from_str(&run.stdout).unwrap();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["branches"][0]["name"], "done");
+    assert_eq!(report["branches"][0]["action"], "archive");
+    assert_eq!(repo.branches(), vec!["done", "fresh", "main", "ongoing"]);
+
+    let run = repo.sage(&["clean", "--policy", "--archive", "--yes", "--report", "json"]);
+    run.assert_success();
+    let report: serde_json::Value = serde_json::from_str(&run.stdout).unwrap();
+    assert_eq!(report["dry_run"], false);
+    assert_eq!(report["branches"].as_array().unwrap().len(), 1);
+    assert_eq!(report["branches"][0]["done"], true);
+    assert_eq!(report["branches"][0]["archived_as"], "refs/sage/archive/done");
+    assert_eq!(repo.branches(), vec!["fresh", "main", "ongoing"]);
+    assert_eq!(repo.rev("refs/sage/archive/done"), done);
+}
+
+/// Write a sage lock into the repository as if sage with `pid` held it
+fn hold_lock(repo: &TestRepo, pid: u32) {
+    std::fs::write(
+        repo.path().join(".git/sage.lock"),
+        format!(r#"{{"pid": {}, "command": "sync", "started_at": "2026-01-01T00:00:00Z"}}"#, pid),
+    )
+    .unwrap();
+}
+
+#[test]
+fn commands_fail_while_another_sage_holds_the_lock() {
+    let repo = repo();
+    // This test's own process is certainly running
+    hold_lock(&repo, std::process::id());
+
+    let run = repo.sage(&["start", "feature"]);
+
+    assert!(!run.success);
+    assert!(run.stderr.contains("sage sync (pid"), "{}", run.stderr);
+    assert_eq!(repo.branches(), vec!["main"]);
+    // Read-only commands don't need the lock
+    repo.sage(&["status"]).assert_success();
+}
+
+#[test]
+fn stale_locks_are_cleared() {
+    let repo = repo();
+    let mut finished = std::process::Command::new("true").spawn().unwrap();
+    let pid = finished.id();
+    finished.wait().unwrap();
+    hold_lock(&repo, pid);
+
+    let run = repo.sage(&["start", "feature"]);
+
+    run.assert_success();
+    assert!(run.stderr.contains("no longer running"), "{}", run.stderr);
+    assert_eq!(repo.current_branch(), "feature");
+    assert!(!repo.path().join(".git/sage.lock").exists());
+}
+
+#[test]
+fn wait_waits_for_the_lock() {
+    let repo = repo();
+    hold_lock(&repo, std::process::id());
+    let lock = repo.path().join(".git/sage.lock");
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        std::fs::remove_file(lock).unwrap();
+    });
+
+    let run = repo.sage(&["start", "feature", "--wait"]);
+    release.join().unwrap();
+
+    run.assert_success();
+    assert!(run.stderr.contains("Waiting for"), "{}", run.stderr);
+    assert_eq!(repo.current_branch(), "feature");
+}
+
+#[test]
+fn commands_outside_a_repository_fail() {
+    let repo = repo();
+    std::fs::remove_dir_all(repo.path().join(".git")).expect("Failed to remove .git");
+
+    let run = repo.sage(&["sync"]);
+
+    assert!(!run.success);
+}
+
+#[test]
+fn pr_status_shows_a_replayed_pull_request() {
+    let mut repo = repo();
+    repo.git(&["remote", "set-url", "origin", "https://github.com/acme/api.git"]);
+    repo.set_env("SAGE_GITHUB_CASSETTE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github/pr_status.json"));
+
+    let run = repo.sage(&["pr", "status", "7"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("#7: Add rate limiting"));
+    assert!(run.stdout.contains("https://github.com/acme/api/pull/7"));
+    assert!(run.stdout.contains("rate-limit → main"));
+    assert!(run.stdout.contains("build"));
+    assert!(run.stdout.contains("lint"));
+    assert!(run.stdout.contains("9fceb02: Limit requests per client"));
+}
+
+#[test]
+fn pr_create_opens_a_pull_request() {
+    let mut repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.commit_file("feature.txt", "feature\n", "add feature");
+    repo.git(&["remote", "set-url", "origin", "https://github.com/acme/api.git"]);
+    repo.set_env("SAGE_GITHUB_CASSETTE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github/pr_create.json"));
+
+    let run = repo.sage(&["pr", "create", "--title", "Add feature", "--body", "Adds the feature."]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("https://github.com/acme/api/pull/8"));
+}
+
+#[test]
+fn pr_create_requests_suggested_reviewers() {
+    let mut repo = repo();
+    repo.write(".github/CODEOWNERS", "/docs/ @acme/docs-team\n");
+    repo.write("src/lib.rs", "one\ntwo\nthree\n");
+    repo.git(&["add", "."]);
+    repo.git(&["-c", "user.email=1234+dana@users.noreply.github.com", "commit", "--quiet", "-m", "add lib"]);
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("docs/guide.md", "guide\n");
+    repo.git(&["add", "docs"]);
+    repo.commit_file("src/lib.rs", "one\n2\nthree\n", "add feature");
+    repo.git(&["remote", "set-url", "origin", "https://github.com/acme/api.git"]);
+    repo.set_env("SAGE_GITHUB_CASSETTE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github/pr_reviewers.json"));
+
+    let run = repo.sage(&["pr", "create", "--title", "Add feature", "--body", "Adds the feature.", "--suggest-reviewers", "--yes"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("@acme/docs-team (owns 1 file)"), "{}", run.stdout);
+    assert!(run.stdout.contains("Requested reviews from @acme/docs-team, @dana"), "{}", run.stdout);
+}
+
+#[test]
+fn pr_create_stops_when_a_pull_request_exists() {
+    let mut repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.git(&["remote", "set-url", "origin", "https://github.com/acme/api.git"]);
+    repo.set_env("SAGE_GITHUB_CASSETTE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github/pr_exists.json"));
+
+    let run = repo.sage(&["pr", "create", "--title", "Add feature", "--body", "Adds the feature."]);
+
+    assert!(!run.success);
+    assert!(run.stderr.contains("A pull request already exists for this branch"));
+    assert!(run.stdout.contains("/acme/api/pull/8"));
+}
+
+#[test]
+fn pr_comment_comments_on_the_current_branch() {
+    let mut repo = repo();
+    repo.git(&["remote", "set-url", "origin", "https://github.com/acme/api.git"]);
+    repo.git(&["checkout", "--quiet", "-b", "feature"]);
+    repo.set_env("SAGE_GITHUB_CASSETTE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github/pr_comment.json"));
+
+    let run = repo.sage(&["pr", "comment", "Ready for review"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("Commented on #8"));
+    assert!(run.stdout.contains("https://github.com/acme/api/pull/8#issuecomment-501"));
+}
+
+#[test]
+fn pr_edit_changes_the_title_and_labels() {
+    let mut repo = repo();
+    repo.git(&["remote", "set-url", "origin", "https://github.com/acme/api.git"]);
+    repo.git(&["checkout", "--quiet", "-b", "feature"]);
+    repo.set_env("SAGE_GITHUB_CASSETTE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github/pr_edit.json"));
+
+    let run = repo.sage(&["pr", "edit", "--title", "Add the feature", "--add-label", "bug", "--remove-label", "wontfix"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("Updated #8"));
+    assert!(run.stdout.contains("title: Add the feature"));
+    assert!(run.stdout.contains("+bug"));
+    assert!(run.stdout.contains("-wontfix"));
+}
+
+#[test]
+fn stats_team_counts_commits_by_author_without_bots() {
+    let repo = repo();
+    repo.commit_file("a.txt", "a\n", "add a");
+    repo.git(&["commit", "--quiet", "--allow-empty", "-m", "by ada", "--author", "Ada <ada@example.com>"]);
+    repo.git(&[
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "-m",
+        "bump deps",
+        "--author",
+        "dependabot[bot] <49699333+dependabot[bot]@users.noreply.github.com>",
+    ]);
+
+    let run = repo.sage(&["stats", "team", "--format", "csv", "--exclude-bots"]);
+    run.assert_success();
+
+    // There's no GitHub remote, so only commits are counted
+    assert_eq!(run.stdout, "author,commits\nSage Test,2\nAda,1\n");
+    assert!(run.stderr.contains("showing commits only"));
+}
+
+#[test]
+fn explain_offline_shows_the_facts_of_a_commit() {
+    let repo = repo();
+    repo.commit_file("api.txt", "one\ntwo\n", "add the api");
+
+    let run = repo.sage(&["explain", "HEAD", "--offline"]);
+    run.assert_success();
+    assert!(run.stdout.starts_with("Commit "), "{}", run.stdout);
+    assert!(run.stdout.contains("add the api (Sage Test)"), "{}", run.stdout);
+    assert!(run.stdout.contains("api.txt | 2 ++"), "{}", run.stdout);
+
+    let run = repo.sage(&["explain", "op:99", "--offline"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("No operation 99 in the ledger"), "{}", run.stderr);
+}
+
+#[test]
+fn ai_prompts_show_prefers_the_repository_override() {
+    let repo = repo();
+
+    let run = repo.sage(&["ai", "prompts", "show", "review"]);
+    run.assert_success();
+    assert!(run.stderr.contains("built in"), "{}", run.stderr);
+    assert!(run.stdout.contains("{{diff}}"), "{}", run.stdout);
+
+    repo.write(".sage/prompts/review.md", "Review {{branch}} for our style:\n{{diff}}\n");
+    let run = repo.sage(&["ai", "prompts", "show", "review"]);
+    run.assert_success();
+    assert!(run.stderr.contains(".sage/prompts/review.md"), "{}", run.stderr);
+    assert_eq!(run.stdout, "Review {{branch}} for our style:\n{{diff}}\n");
+
+    let run = repo.sage(&["ai", "prompts", "show", "changelog"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("The prompts are commit, pr, review, explain"), "{}", run.stderr);
+}
+
+#[test]
+fn ai_enabled_false_keeps_everything_local() {
+    let repo = repo();
+    repo.commit_file("api.txt", "one\n", "add the api");
+    repo.write(".git/sage/config.json", r#"{"ai.enabled": "false"}"#);
+
+    // explain still shows the facts without the AI
+    let run = repo.sage(&["explain", "HEAD"]);
+    run.assert_success();
+    assert!(run.stdout.contains("add the api"), "{}", run.stdout);
+    assert!(run.stdout.contains("AI is turned off here (ai.enabled is false)"), "{}", run.stdout);
+}
+
+#[test]
+fn ai_index_is_kept_up_to_date_by_commit() {
+    let repo = repo();
+    repo.commit_file("src/tree.rs", "//! Parsing trees\npub fn parse_tree() {}\n", "add trees");
+
+    let run = repo.sage(&["ai", "index"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Indexed"), "{}", run.stdout);
+    let index = repo.read(".git/sage_index/index.json").expect("index written");
+    assert!(index.contains("src/tree.rs") && index.contains("Parsing trees"), "{}", index);
+
+    repo.write("src/walk.rs", "pub fn walk_tree() {}\n");
+    repo.sage(&["commit", "feat: walk trees"]).assert_success();
+    let index = repo.read(".git/sage_index/index.json").expect("index kept");
+    assert!(index.contains("src/walk.rs"), "{}", index);
+
+    repo.sage(&["ai", "index", "--clear"]).assert_success();
+    assert!(repo.read(".git/sage_index/index.json").is_none());
+}
+
+#[test]
+fn readonly_mode_refuses_commands_that_change_anything() {
+    let mut repo = repo();
+    repo.set_env("SAGE_READONLY", "1");
+    repo.write("notes.txt", "hello\n");
+
+    let run = repo.sage(&["commit", "feat: add notes"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("SAGE_READONLY is set"), "{}", run.stderr);
+    assert_eq!(repo.git(&["log", "--oneline"]).lines().count(), 1);
+
+    repo.sage(&["status"]).assert_success();
+    assert!(!repo.sage(&["config", "get", "commit.signoff"]).stderr.contains("SAGE_READONLY"));
+    assert!(!repo.sage(&["config", "set", "commit.signoff", "true"]).success);
+
+    repo.set_env("SAGE_READONLY", "0");
+    repo.sage(&["commit", "feat: add notes"]).assert_success();
+}
+
+#[test]
+fn github_actions_errors_become_annotations() {
+    let mut repo = repo();
+    repo.set_env("SAGE_READONLY", "1");
+    repo.write("notes.txt", "hello\n");
+
+    let run = repo.sage(&["commit", "feat: add notes"]);
+    assert!(!run.stderr.contains("::error"), "{}", run.stderr);
+
+    repo.set_env("GITHUB_ACTIONS", "true");
+    let run = repo.sage(&["commit", "feat: add notes"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("::error title=sage::SAGE_READONLY is set"), "{}", run.stderr);
+}
+
+#[test]
+fn verify_reports_every_failing_check() {
+    let mut repo = repo();
+    repo.git(&["checkout", "--quiet", "-b", "feature"]);
+    repo.commit_file("a.txt", "a\n", "feat: add a");
+
+    let run = repo.sage(&["verify", "--base", "main"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Everything since main passes"), "{}", run.stdout);
+
+    repo.commit_file("db.env", "password = \"hunter2hunter2\"\n", "add the database settings");
+    // In GitHub Actions the annotations go to stderr, leaving the JSON alone
+    repo.set_env("GITHUB_ACTIONS", "true");
+    let run = repo.sage(&["verify", "--base", "main", "--report", "json"]);
+    assert!(!run.success);
+    let report: serde_json::Value = serde_json::from_str(&run.stdout).expect("verify --report json prints JSON");
+    let failed = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|check| check["passed"] == false)
+        .map(|check| check["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(failed, ["commits", "secrets"]);
+    assert_eq!(report["checks"][3]["problems"][0]["file"], "db.env");
+    assert_eq!(report["checks"][3]["problems"][0]["line"], 1);
+    assert!(run.stderr.contains("2 of 4 checks failed"), "{}", run.stderr);
+    assert!(run.stderr.contains("::error title=sage::"), "{}", run.stderr);
+}
+
+#[test]
+fn clean_leaves_protected_and_kept_branches_alone() {
+    let repo = repo();
+    for branch in ["release/1.x", "pinned", "done"] {
+        repo.git(&["branch", branch]);
+    }
+    repo.write(".git/sage/config.json", r#"{"clean.protect": "release/*"}"#);
+    repo.sage(&["clean", "--keep", "pinned"]).assert_success();
+
+    let run = repo.sage_with_input(&["clean"], "y\n");
+    run.assert_success();
+    assert!(run.stdout.contains("Keeping pinned (marked with sage clean --keep)"), "{}", run.stdout);
+    assert_eq!(repo.branches(), vec!["main", "pinned", "release/1.x"]);
+
+    repo.sage(&["clean", "--unkeep", "pinned"]).assert_success();
+    repo.sage_with_input(&["clean"], "y\n").assert_success();
+    assert_eq!(repo.branches(), vec!["main", "release/1.x"]);
+}
+
+#[test]
+fn clean_untracked_deletes_untracked_files_after_asking() {
+    let repo = repo();
+    repo.write("scratch.txt", "scratch\n");
+    repo.write("out/a.log", "log\n");
+    repo.commit_file(".gitignore", "*.tmp\n", "chore: ignore temporary files");
+    repo.write("cache.tmp", "cache\n");
+
+    let run = repo.sage_with_input(&["clean", "--untracked"], "n\n");
+    run.assert_success();
+    assert!(run.stdout.contains("out/ (4 B)"), "{}", run.stdout);
+    assert!(repo.read("scratch.txt").is_some());
+
+    repo.sage_with_input(&["clean", "--untracked"], "y\n").assert_success();
+    assert_eq!(repo.read("scratch.txt"), None);
+    assert_eq!(repo.read("out/a.log"), None);
+    assert!(repo.read("cache.tmp").is_some());
+
+    repo.sage(&["clean", "--untracked", "--ignored", "--yes"]).assert_success();
+    assert_eq!(repo.read("cache.tmp"), None);
+
+    // A repository inside the working tree is never deleted
+    repo.write("vendor/lib/README", "lib\n");
+    repo.git(&["init", "--quiet", "vendor/lib"]);
+    let run = repo.sage(&["clean", "--untracked", "--yes"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Leaving vendor/ alone"), "{}", run.stdout);
+    assert!(repo.read("vendor/lib/README").is_some());
+}
+
+#[test]
+fn commit_stages_hook_fixes_without_unstaged_changes() {
+    let repo = repo();
+    // A formatter that rewrites a.txt and fails, as pre-commit and husky hooks do
+    repo.commit_file(
+        ".husky/pre-commit",
+        "if [ \"$(git show :a.txt)\" = bad ]; then printf 'good\\n' > a.txt; exit 1; fi\n",
+        "chore: add hooks",
+    );
+    repo.write("a.txt", "bad\n");
+    repo.git(&["add", "a.txt"]);
+
+    repo.sage(&["commit", "feat: add a"]).assert_success();
+    assert_eq!(repo.git(&["show", "HEAD:a.txt"]).trim(), "good");
+
+    // A file with unstaged changes of its own isn't staged whole
+    repo.write("a.txt", "bad\n");
+    repo.git(&["add", "a.txt"]);
+    repo.write("a.txt", "bad\nnot ready\n");
+    let index = repo.git(&["write-tree"]);
+    let run = repo.sage(&["commit", "fix: a"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("also have unstaged changes"), "{}", run.stderr);
+    assert_eq!(repo.git(&["write-tree"]), index);
+}
+
+#[test]
+#[cfg(unix)]
+fn commit_runs_hooks_once_and_still_checks_the_message() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = repo();
+    repo.commit_file(".husky/pre-commit", "echo run >> .git/hook-runs\n", "chore: add hooks");
+    // Installed the way husky does, so git would run it again on commit
+    repo.write(".git/hooks/pre-commit", "#!/bin/sh\nsh -e .husky/pre-commit\n");
+    repo.write(".git/hooks/commit-msg", "#!/bin/sh\necho 'Checked-by: hook' >> \"$1\"\n");
+    for hook in ["pre-commit", "commit-msg"] {
+        let script = repo.path().join(".git/hooks").join(hook);
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    repo.write("a.txt", "a\n");
+    repo.git(&["add", "a.txt"]);
+    repo.sage(&["commit", "feat: add a"]).assert_success();
+    assert_eq!(repo.read(".git/hook-runs").as_deref(), Some("run\n"));
+    assert!(repo.git(&["log", "-1", "--format=%B"]).contains("Checked-by: hook"));
+}
+
+#[test]
+fn commit_keeps_recent_messages_for_reuse() {
+    let repo = repo();
+    repo.write("a.txt", "a\n");
+    repo.sage(&["commit", "feat: add a"]).assert_success();
+    repo.write("b.txt", "b\n");
+    repo.sage(&["commit", "feat: add b"]).assert_success();
+
+    let messages = repo.read(".git/sage/messages.json").expect("messages are kept");
+    let messages: serde_json::Value = serde_json::from_str(&messages).unwrap();
+    assert_eq!(messages[0]["message"], "feat: add b");
+    assert_eq!(messages[1]["message"], "feat: add a");
+
+    // Picking one takes a terminal
+    let run = repo.sage(&["commit", "--reuse"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("needs a terminal"), "{}", run.stderr);
+}
+
+#[test]
+fn wip_commits_everything_and_unwip_brings_it_back() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.commit_file("a.txt", "a\n", "feat: add a");
+    repo.sage(&["push"]).assert_success();
+    repo.write("a.txt", "a\nb\n");
+    repo.write("new.txt", "new\n");
+
+    repo.sage(&["wip", "halfway"]).assert_success();
+    assert_eq!(repo.git(&["log", "-1", "--format=%s"]), "wip: halfway");
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+
+    let run = repo.sage(&["push"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("1 WIP commit(s) to push"), "{}", run.stderr);
+
+    let run = repo.sage(&["unwip"]);
+    run.assert_success();
+    assert!(!run.stdout.contains("already pushed"), "{}", run.stdout);
+    assert_eq!(repo.git(&["log", "-1", "--format=%s"]), "feat: add a");
+    assert_eq!(repo.read("a.txt").as_deref(), Some("a\nb\n"));
+    assert_eq!(repo.git(&["status", "--porcelain"]), "M a.txt\n?? new.txt");
+
+    assert!(!repo.sage(&["unwip"]).success);
+
+    // Taking back a WIP commit that was pushed anyway warns it rewrites what's on the remote
+    repo.sage(&["wip"]).assert_success();
+    repo.sage(&["push", "--allow-wip"]).assert_success();
+    let run = repo.sage(&["unwip"]);
+    run.assert_success();
+    assert!(run.stdout.contains("already pushed"), "{}", run.stdout);
+}
+
+#[test]
+fn sync_if_stale_only_syncs_when_the_remote_moved() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.commit_file("a.txt", "a\n", "feat: add a");
+    repo.git(&["fetch", "--quiet"]);
+
+    let run = repo.sage(&["sync", "--if-stale"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Nothing to sync"), "{}", run.stdout);
+
+    // Long enough ago to look at the remote, which hasn't moved, so the look is noted
+    repo.write(".git/sage/config.json", r#"{"sync.stale_minutes": "0"}"#);
+    let run = repo.sage(&["sync", "--if-stale"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Nothing to sync"), "{}", run.stdout);
+    assert!(repo.read(".git/sage/last-check").is_some());
+
+    // Now it has moved on
+    let tip = repo.push_from_elsewhere("main", "b.txt", "b\n", "feat: add b");
+    let run = repo.sage(&["sync", "--if-stale"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Fetching remote changes"), "{}", run.stdout);
+    assert_eq!(repo.rev("origin/main"), tip);
+}
+
+#[test]
+fn sync_follows_the_configured_strategy_and_push_setting() {
+    let repo = repo();
+    repo.commit_file("local.txt", "local\n", "feat: local change");
+    let upstream = repo.push_from_elsewhere("main", "other.txt", "other\n", "teammate change");
+
+    // Fast-forwarding can't bring in the teammate's commit, git's pull.rebase can
+    assert!(!repo.sage(&["sync"]).success);
+    repo.git(&["config", "pull.rebase", "true"]);
+    let run = repo.sage(&["sync"]);
+    run.assert_success();
+    assert_eq!(repo.rev("HEAD~1"), upstream);
+    assert_eq!(repo.read("local.txt").as_deref(), Some("local\n"));
+
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("feature.txt", "feature\n");
+    repo.sage(&["commit", "--push", "add feature"]).assert_success();
+    let pushed = repo.rev("HEAD");
+    repo.commit_file("more.txt", "more\n", "add more");
+    repo.write(".git/sage/config.json", r#"{"sync.push": "never"}"#);
+
+    let run = repo.sage(&["sync"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Not pushing"), "{}", run.stdout);
+    assert_eq!(repo.remote_rev("feature"), Some(pushed));
+}
+
+#[test]
+fn sync_upstream_updates_the_fork_and_rebases_the_branch() {
+    let repo = repo();
+    let upstream = repo.path().parent().unwrap().join("upstream.git");
+    repo.git(&["clone", "--quiet", "--bare", "../origin.git", upstream.to_str().unwrap()]);
+    repo.git(&["remote", "add", "upstream", upstream.to_str().unwrap()]);
+
+    // The canonical repository moves on without the fork
+    repo.git(&["checkout", "--quiet", "-b", "elsewhere"]);
+    let tip = repo.commit_file("upstream.txt", "upstream\n", "feat: upstream change");
+    repo.git(&["push", "--quiet", "upstream", "elsewhere:main"]);
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.git(&["branch", "--quiet", "-D", "elsewhere"]);
+
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.commit_file("feature.txt", "feature\n", "feat: add feature");
+
+    repo.sage(&["sync", "--upstream"]).assert_success();
+
+    assert_eq!(repo.rev("main"), tip);
+    assert_eq!(repo.remote_rev("main"), Some(tip.clone()));
+    assert_eq!(repo.rev("feature~1"), tip);
+    assert_eq!(repo.read("upstream.txt").as_deref(), Some("upstream\n"));
+}
+
+#[test]
+fn rebase_conflicts_show_where_the_rebase_stopped() {
+    let repo = repo();
+    repo.git(&["config", "pull.rebase", "true"]);
+    repo.commit_file("shared.txt", "mine\n", "feat: my change");
+    repo.commit_file("later.txt", "later\n", "feat: later change");
+    repo.push_from_elsewhere("main", "shared.txt", "theirs\n", "feat: their change");
+
+    let run = repo.sage(&["sync"]);
+
+    assert!(run.stdout.contains("Stopped at commit 1 of 2"), "{}", run.stdout);
+    assert!(run.stdout.contains("feat: my change"), "{}", run.stdout);
+    assert!(run.stdout.contains("Still to apply:"), "{}", run.stdout);
+    assert!(run.stdout.contains("feat: later change"), "{}", run.stdout);
+}
+
+#[test]
+fn rename_moves_the_branch_on_origin_and_in_the_stack() {
+    let repo = repo();
+    repo.sage(&["start", "feature"]).assert_success();
+    repo.write("feature.txt", "feature\n");
+    repo.sage(&["commit", "--push", "feat: add feature"]).assert_success();
+    repo.sage(&["start", "child", "--parent", "feature"]).assert_success();
+    repo.git(&["checkout", "--quiet", "feature"]);
+
+    repo.sage(&["rename", "search"]).assert_success();
+
+    assert_eq!(repo.current_branch(), "search");
+    assert_eq!(repo.branches(), vec!["child", "main", "search"]);
+    assert_eq!(repo.config("branch.child.sage-parent").as_deref(), Some("search"));
+    assert_eq!(repo.remote_rev("search"), Some(repo.rev("search")));
+    assert_eq!(repo.remote_rev("feature"), None);
+    assert_eq!(repo.git(&["rev-parse", "--abbrev-ref", "search@{u}"]).trim(), "origin/search");
+}
+
+#[test]
+fn start_orphan_creates_a_stack_root_without_history() {
+    let repo = repo();
+
+    repo.sage(&["start", "gh-pages", "--orphan", "--readme"]).assert_success();
+
+    assert_eq!(repo.current_branch(), "gh-pages");
+    assert_eq!(repo.git(&["rev-list", "--count", "HEAD"]).trim(), "1");
+    assert_eq!(repo.git(&["ls-files"]).trim(), "README.md");
+    assert_eq!(repo.read("README.md").as_deref(), Some("# gh-pages\n"));
+    assert_eq!(repo.config("branch.gh-pages.sage-root").as_deref(), Some("true"));
+
+    repo.sage(&["start", "docs", "--parent", "gh-pages"]).assert_success();
+    let run = repo.sage(&["stack", "status"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Stack on gh-pages"), "{}", run.stdout);
+    assert!(!run.stdout.contains("needs restack"), "{}", run.stdout);
+}
+
+#[test]
+fn start_orphan_leaves_an_untracked_readme_alone() {
+    let repo = repo();
+    repo.git(&["rm", "--quiet", "README.md"]);
+    repo.git(&["commit", "--quiet", "-m", "chore: drop the readme"]);
+    repo.write("README.md", "mine\n");
+
+    let run = repo.sage(&["start", "gh-pages", "--orphan", "--readme"]);
+    assert!(!run.success);
+    assert!(run.stderr.contains("already a README.md"), "{}", run.stderr);
+    assert_eq!(repo.read("README.md").as_deref(), Some("mine\n"));
+    assert_eq!(repo.current_branch(), "main");
+    assert!(!repo.branches().contains(&"gh-pages".to_string()));
+}
+
+#[test]
+fn restack_rebases_the_branches_behind_their_parent() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: add api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("ui.txt", "ui\n", "feat: add ui");
+    repo.sage(&["start", "docs", "--parent", "ui"]).assert_success();
+    repo.commit_file("docs.txt", "docs\n", "docs: explain ui");
+    repo.git(&["checkout", "--quiet", "api"]);
+    let api = repo.commit_file("api.txt", "api v2\n", "fix: review comments");
+
+    let run = repo.sage(&["restack", "--dry-run"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Would restack 2 branch(es)"), "{}", run.stdout);
+    assert!(!repo.is_ancestor(&api, "ui"));
+
+    let run = repo.sage(&["restack"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Branch  Result  Before   After"), "{}", run.stdout);
+
+    assert_eq!(repo.current_branch(), "api");
+    assert!(repo.is_ancestor(&api, "ui"));
+    assert!(repo.is_ancestor("ui", "docs"));
+    let run = repo.sage(&["restack"]);
+    assert!(run.stdout.contains("already on top of its parent"), "{}", run.stdout);
+
+    // A conflict stops it part way, and sage continue restacks what's left
+    repo.git(&["checkout", "--quiet", "ui"]);
+    repo.commit_file("shared.txt", "ui\n", "feat: ui uses shared");
+    repo.git(&["checkout", "--quiet", "api"]);
+    let api = repo.commit_file("shared.txt", "api\n", "feat: api uses shared");
+
+    assert!(!repo.sage(&["restack"]).success);
+    repo.write("shared.txt", "both\n");
+    repo.git(&["add", "shared.txt"]);
+    repo.sage(&["continue"]).assert_success();
+
+    assert!(repo.is_ancestor(&api, "ui"));
+    assert!(repo.is_ancestor("ui", "docs"));
+}
+
+#[test]
+fn restack_after_amending_a_middle_branch_replays_only_the_childs_commits() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: add api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("ui.txt", "ui\n", "feat: add ui");
+    repo.sage(&["start", "docs", "--parent", "ui"]).assert_success();
+    repo.commit_file("docs.txt", "docs\n", "docs: explain ui");
+
+    // Rewriting ui's commit leaves docs on the old one, which must not be replayed again
+    repo.git(&["checkout", "--quiet", "ui"]);
+    repo.write("ui.txt", "ui, reviewed\n");
+    repo.git(&["commit", "--quiet", "--all", "--amend", "--no-edit"]);
+    let ui = repo.rev("ui");
+
+    repo.sage(&["restack"]).assert_success();
+
+    assert!(repo.is_ancestor(&ui, "docs"));
+    assert_eq!(repo.git(&["rev-list", "--count", "ui..docs"]).trim(), "1");
+    repo.git(&["checkout", "--quiet", "docs"]);
+    assert_eq!(repo.read("ui.txt").as_deref(), Some("ui, reviewed\n"));
+}
+
+#[test]
+fn stack_visualize_web_writes_a_standalone_page() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: add api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+
+    repo.sage(&["stack", "visualize", "--web", "-o", "stack.html"]).assert_success();
+
+    let page = repo.read("stack.html").expect("No page written");
+    assert!(page.starts_with("<!DOCTYPE html>"), "{}", page);
+    assert!(page.contains("<svg "), "{}", page);
+    assert!(page.contains("<title>Stack on api</title>"), "{}", page);
+    assert!(page.contains("<td>ui</td><td>api</td>"), "{}", page);
+    assert!(!page.contains("<script"), "{}", page);
+}
+
+#[test]
+fn stack_json_describes_every_branch() {
+    let mut repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: add api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("ui.txt", "ui\n", "feat: add ui");
+    repo.commit_file("ui.css", "body {}\n", "style: ui");
+    repo.git(&["checkout", "--quiet", "api"]);
+    repo.commit_file("api.txt", "api v2\n", "fix: review comments");
+    repo.git(&["checkout", "--quiet", "ui"]);
+    // Annotations for GitHub Actions mustn't end up in the JSON
+    repo.set_env("GITHUB_ACTIONS", "true");
+
+    let run = repo.sage(&["stack", "--json"]);
+    run.assert_success();
+
+    let stack: serde_json::Value = serde_json::from_str(&run.stdout).expect("Not JSON");
+    assert_eq!(stack["base"], "api");
+    assert_eq!(stack["current"], "ui");
+    let ui = &stack["branches"][0];
+    assert_eq!(ui["name"], "ui");
+    assert_eq!(ui["parent"], "api");
+    assert_eq!(ui["ahead"], 2);
+    assert_eq!(ui["behind"], 1);
+    assert_eq!(ui["needs_restack"], true);
+
+    let run = repo.sage(&["stack", "--format", "{{name}}"]);
+    run.assert_success();
+    assert_eq!(run.stdout.trim(), "ui");
+
+    let run = repo.sage(&["stack"]);
+    run.assert_success();
+    assert!(run.stdout.contains("ui ↑2, ↓1 (needs restack)"), "{}", run.stdout);
+}
+
+#[test]
+fn events_ndjson_reports_progress_on_stdout() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("shared.txt", "api\n", "feat: add api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("ui.txt", "ui\n", "feat: add ui");
+    repo.sage(&["start", "docs", "--parent", "ui"]).assert_success();
+    repo.commit_file("shared.txt", "docs\n", "docs: shared");
+    repo.git(&["checkout", "--quiet", "api"]);
+    repo.commit_file("shared.txt", "api v2\n", "fix: review comments");
+
+    let run = repo.sage(&["restack", "--events", "ndjson"]);
+
+    assert!(!run.success);
+    assert!(run.stderr.contains("Conflicts while restacking docs onto ui"), "{}", run.stderr);
+    let events = run
+        .stdout
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("Not an event"))
+        .collect::<Vec<_>>();
+    let kinds = events.iter().map(|event| event["event"].as_str().unwrap_or_default()).collect::<Vec<_>>();
+    assert_eq!(kinds, ["command_started", "action_applied", "action_applied", "conflict", "command_finished"]);
+    assert_eq!(events[0]["command"], "restack");
+    assert_eq!(events[1]["branch"], "ui");
+    assert_eq!(events[1]["outcome"], "done");
+    assert_eq!(events[3]["branch"], "docs");
+    assert_eq!(events[3]["files"], serde_json::json!(["shared.txt"]));
+    assert_eq!(events[4]["ok"], false);
+}
+
+#[test]
+fn identity_use_stops_signing_for_a_profile_without_a_key() {
+    let repo = repo();
+    repo.sage(&["identity", "add", "work", "--name", "Dana", "--email", "dana@acme.dev", "--signing-key", "ABC123"])
+        .assert_success();
+    repo.sage(&["identity", "add", "home", "--name", "Dana", "--email", "dana@home.dev"]).assert_success();
+
+    repo.sage(&["identity", "use", "work"]).assert_success();
+    assert_eq!(repo.config("user.signingkey").as_deref(), Some("ABC123"));
+    assert_eq!(repo.config("commit.gpgsign").as_deref(), Some("true"));
+
+    repo.sage(&["identity", "use", "home"]).assert_success();
+    assert_eq!(repo.config("user.email").as_deref(), Some("dana@home.dev"));
+    assert_eq!(repo.config("user.signingkey"), None);
+    assert_eq!(repo.config("commit.gpgsign"), None);
+}
+
+#[test]
+fn mv_leaves_committing_to_the_user_when_other_changes_are_staged() {
+    let repo = repo();
+    repo.sage(&["start", "api", "--parent", "main"]).assert_success();
+    repo.commit_file("shared.txt", "shared\n", "feat: shared");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("shared.txt", "shared by ui\n", "feat: ui");
+    repo.git(&["checkout", "--quiet", "api"]);
+    let head = repo.rev("HEAD");
+
+    repo.write("other.txt", "unrelated\n");
+    repo.git(&["add", "other.txt"]);
+    let run = repo.sage(&["mv", "shared.txt", "common.txt"]);
+    run.assert_success();
+
+    assert!(run.stdout.contains("Other changes are staged too (other.txt)"), "{}", run.stdout);
+    assert_eq!(repo.rev("HEAD"), head);
+    assert!(repo.git(&["diff", "--cached", "--name-only"]).contains("other.txt"));
+}
+
+#[test]
+fn session_save_keeps_the_session_local_unless_pushed() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: add api");
+    repo.git(&["push", "--quiet", "-u", "origin", "api"]);
+
+    let run = repo.sage(&["session", "save"]);
+    run.assert_success();
+    let saved = repo.read(".git/sage/session.json").expect("No session written");
+    assert!(saved.contains("\"head\": \"api\""), "{}", saved);
+    assert!(repo.git(&["ls-remote", "origin", "refs/sage/*"]).trim().is_empty());
+
+    repo.sage(&["session", "save", "--push"]).assert_success();
+    let email = repo.git(&["config", "user.email"]);
+    let pushed = repo.git(&["ls-remote", "origin", &format!("refs/sage/session/{}", email.trim())]);
+    assert!(!pushed.trim().is_empty());
+}
+
+#[test]
+fn session_restore_fast_forwards_the_checked_out_branch_with_its_files() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: add api");
+    repo.commit_file("api.txt", "api v2\n", "feat: api v2");
+    repo.git(&["push", "--quiet", "-u", "origin", "api"]);
+    repo.sage(&["session", "save", "--file", ".git/session.json"]).assert_success();
+    let tip = repo.rev("api");
+
+    // Still on api here, one commit behind the session
+    repo.git(&["reset", "--quiet", "--hard", "HEAD~1"]);
+    repo.sage(&["session", "restore", "--file", ".git/session.json"]).assert_success();
+
+    assert_eq!(repo.current_branch(), "api");
+    assert_eq!(repo.rev("api"), tip);
+    assert_eq!(repo.read("api.txt").as_deref(), Some("api v2\n"));
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+}
+
+#[test]
+fn sync_if_stale_syncs_when_the_stack_parent_or_upstream_moved() {
+    let repo = repo();
+    repo.sage(&["start", "api"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("ui.txt", "ui\n", "feat: ui");
+    repo.sage(&["push"]).assert_success();
+    repo.git(&["fetch", "--quiet"]);
+
+    let run = repo.sage(&["sync", "--if-stale"]);
+    run.assert_success();
+    assert!(run.stdout.contains("Nothing to sync"), "{}", run.stdout);
+
+    // The parent moved on locally
+    repo.git(&["checkout", "--quiet", "api"]);
+    repo.commit_file("api.txt", "api v2\n", "feat: api v2");
+    repo.git(&["checkout", "--quiet", "ui"]);
+    let run = repo.sage(&["sync", "--if-stale"]);
+    assert!(run.stdout.contains("Fetching remote changes"), "{}", run.stdout);
+    repo.git(&["reset", "--quiet", "--hard", "origin/ui"]);
+    repo.git(&["branch", "--quiet", "-f", "api", "ui~1"]);
+
+    // Someone pushed to the branch itself
+    repo.push_from_elsewhere("ui", "ui.txt", "ui v2\n", "feat: ui v2");
+    repo.write(".git/sage/config.json", r#"{"sync.stale_minutes": "0"}"#);
+    let run = repo.sage(&["sync", "--if-stale"]);
+    assert!(run.stdout.contains("Fetching remote changes"), "{}", run.stdout);
+}
+
+#[test]
+fn remote_list_only_records_a_primary_remote_when_asked() {
+    let repo = repo();
+    let run = repo.sage(&["remote", "list"]);
+    run.assert_success();
+    assert!(run.stdout.contains("* origin"), "{}", run.stdout);
+    assert_eq!(repo.config("sage.primaryRemote"), None);
+
+    repo.sage(&["remote", "add", "upstream", "https://github.com/acme/api.git", "--primary"]).assert_success();
+    assert_eq!(repo.config("sage.primaryRemote").as_deref(), Some("upstream"));
+}
+
+#[test]
+fn stack_apply_removes_the_branches_it_made_when_a_patch_fails() {
+    let repo = repo();
+    repo.sage(&["start", "api", "--parent", "main"]).assert_success();
+    repo.commit_file("api.txt", "api\n", "feat: api");
+    repo.sage(&["start", "ui", "--parent", "api"]).assert_success();
+    repo.commit_file("ui.txt", "ui\n", "feat: ui");
+    repo.sage(&["stack", "export", "--output", ".git/stack"]).assert_success();
+
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.git(&["branch", "--quiet", "-D", "api", "ui"]);
+    // ui's patch adds a file main now has
+    repo.commit_file("ui.txt", "other\n", "feat: other ui");
+
+    let run = repo.sage(&["stack", "apply", ".git/stack"]);
+    assert!(!run.success, "{}", run.stdout);
+    assert!(run.stderr.contains("Failed to apply patches for ui"), "{}", run.stderr);
+    assert_eq!(repo.current_branch(), "main");
+    assert!(!repo.branches().contains(&"api".to_string()), "{:?}", repo.branches());
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+}
@@ -0,0 +1,60 @@
+//! Development tasks for sage, run with `cargo xtask <task>`
+//!
+//! Tasks:
+//!   bench [--save <name>] [--compare <name>] [filter]
+//!       Run the criterion benchmarks in benches/. `--save` keeps the results as a named
+//!       baseline, `--compare` reports the change against one saved earlier, and a filter
+//!       only runs benchmarks whose name contains it (e.g. `restack_plan`).
+
+use std::env;
+use std::process::{exit, Command};
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(String::as_str) {
+        Some("bench") => bench(&args[1..]),
+        Some(task) => Err(format!("Unknown task '{}'\n\n{}", task, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+const USAGE: &str = "Usage: cargo xtask bench [--save <name>] [--compare <name>] [filter]";
+
+fn bench(args: &[String]) -> Result<(), String> {
+    let mut criterion_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--save" => {
+                let name = args.next().ok_or("--save needs a baseline name")?;
+                criterion_args.extend(["--save-baseline".to_string(), name.clone()]);
+            }
+            "--compare" => {
+                let name = args.next().ok_or("--compare needs a baseline name")?;
+                criterion_args.extend(["--baseline".to_string(), name.clone()]);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            filter => criterion_args.push(filter.to_string()),
+        }
+    }
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["bench", "--package", "sage", "--bench", "core", "--"])
+        .args(&criterion_args)
+        .status()
+        .map_err(|e| format!("Failed to run cargo bench: {}", e))?;
+
+    if !status.success() {
+        return Err("Benchmarks failed".to_string());
+    }
+    Ok(())
+}